@@ -0,0 +1,427 @@
+use crate::config::{lookup_json, merge_json, read_config_file, ConfigLayers, UserConfig};
+use crate::style::Colorize;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// How a setting's value is written on the command line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Bool,
+    Number,
+    Text,
+    /// Text that can be unset with an empty value
+    OptionalText,
+    /// Comma-separated, or a JSON array
+    List,
+}
+
+#[derive(Debug)]
+struct Setting {
+    key: &'static str,
+    kind: Kind,
+    /// Accepted values (or list items), when limited
+    choices: &'static [&'static str],
+}
+
+/// Every setting of [`UserConfig`], by dot path.
+const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "reports.auto_open_browser",
+        kind: Kind::Bool,
+        choices: &[],
+    },
+    Setting {
+        key: "reports.default_template",
+        kind: Kind::Text,
+        choices: &["detailed", "simple", "minimal", "chatty", "compact"],
+    },
+    Setting {
+        key: "reports.report_dir",
+        kind: Kind::OptionalText,
+        choices: &[],
+    },
+    Setting {
+        key: "reports.report_name",
+        kind: Kind::OptionalText,
+        choices: &[],
+    },
+    Setting {
+        key: "proxy",
+        kind: Kind::OptionalText,
+        choices: &[],
+    },
+    Setting {
+        key: "defaults.env",
+        kind: Kind::OptionalText,
+        choices: &[],
+    },
+    Setting {
+        key: "defaults.base_url",
+        kind: Kind::OptionalText,
+        choices: &[],
+    },
+    Setting {
+        key: "defaults.parallel",
+        kind: Kind::Number,
+        choices: &[],
+    },
+    Setting {
+        key: "defaults.report",
+        kind: Kind::List,
+        choices: &["json", "html", "junit", "markdown", "csv"],
+    },
+    Setting {
+        key: "defaults.secrets",
+        kind: Kind::List,
+        choices: &[],
+    },
+];
+
+/// Print one setting's effective value, or every setting under a section
+/// (or all of them) with where its value comes from when `key` isn't a
+/// single setting.
+pub fn handle_get(key: Option<&str>) -> Result<()> {
+    let layers = ConfigLayers::load()?;
+    print_warnings(&layers.warnings());
+    let config = serde_json::to_value(layers.merged())?;
+    let Some(key) = key else {
+        print_settings(&layers, &config, SETTINGS.iter());
+        return Ok(());
+    };
+
+    if let Some(setting) = SETTINGS.iter().find(|setting| setting.key == key) {
+        println!("{}", display_value(lookup_json(&config, setting.key)));
+        return Ok(());
+    }
+    let prefix = format!("{}.", key.trim_end_matches('.'));
+    let mut section = SETTINGS
+        .iter()
+        .filter(|setting| setting.key.starts_with(&prefix))
+        .peekable();
+    if section.peek().is_none() {
+        return Err(unknown_setting(key));
+    }
+    print_settings(&layers, &config, section);
+    Ok(())
+}
+
+/// Print every setting's effective value and where it comes from.
+pub fn handle_list() -> Result<()> {
+    handle_get(None)
+}
+
+/// Parse `value` for the setting `key` and write it to the home-dir config,
+/// or to the project config with `project`.
+pub fn handle_set(key: &str, value: &str, project: bool) -> Result<()> {
+    let setting = find_setting(key)?;
+    let value = parse_value(setting, value)?;
+
+    let path = if project {
+        project_file()?
+    } else {
+        UserConfig::path()?
+    };
+    // The project file only holds what it overrides; the home file is complete
+    let mut file = if path.exists() {
+        read_config_file(&path)?
+    } else if project {
+        Value::Object(Default::default())
+    } else {
+        serde_json::to_value(UserConfig::default())?
+    };
+    if project && value.is_null() {
+        // Unsetting a project setting falls back to the home-dir one
+        remove_path(&mut file, setting.key);
+    } else {
+        set_path(&mut file, setting.key, value.clone());
+    }
+
+    // Whatever is written must still load
+    let mut check = serde_json::to_value(UserConfig::default())?;
+    merge_json(&mut check, file.clone());
+    serde_json::from_value::<UserConfig>(check)
+        .with_context(|| format!("{} has settings rivet can't read", path.display()))?;
+
+    write_file(&path, &file)?;
+    println!(
+        "{} {} = {} {}",
+        "✔".green().bold(),
+        setting.key.bright_white(),
+        display_value(Some(&value)),
+        format!("({})", path.display()).dimmed()
+    );
+    Ok(())
+}
+
+/// Print where the config file lives.
+pub fn handle_path(project: bool) -> Result<()> {
+    let path = if project {
+        std::env::current_dir()?.join(project_file()?)
+    } else {
+        UserConfig::path()?
+    };
+    println!("{}", path.display());
+    Ok(())
+}
+
+/// The merged config for a command, warning about project files it
+/// skipped; the defaults when there's none to read.
+pub fn load_user_config() -> UserConfig {
+    match UserConfig::load_with_warnings() {
+        Ok((config, warnings)) => {
+            print_warnings(&warnings);
+            config
+        }
+        Err(_) => UserConfig::default(),
+    }
+}
+
+fn print_warnings(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("{} {}", "⚠".yellow(), warning);
+    }
+}
+
+/// The project config in use, or where to start one.
+fn project_file() -> Result<PathBuf> {
+    Ok(ConfigLayers::load()?
+        .project
+        .map(|(path, _)| path)
+        .unwrap_or_else(UserConfig::project_path))
+}
+
+fn print_settings<'a>(
+    layers: &ConfigLayers,
+    config: &Value,
+    settings: impl Iterator<Item = &'a Setting>,
+) {
+    for setting in settings {
+        println!(
+            "{} = {} {}",
+            setting.key.bright_white(),
+            display_value(lookup_json(config, setting.key)),
+            format!("({})", layers.source(setting.key)).dimmed()
+        );
+    }
+}
+
+fn find_setting(key: &str) -> Result<&'static Setting> {
+    SETTINGS
+        .iter()
+        .find(|setting| setting.key == key)
+        .ok_or_else(|| unknown_setting(key))
+}
+
+fn unknown_setting(key: &str) -> anyhow::Error {
+    let suggestion = SETTINGS
+        .iter()
+        .map(|setting| setting.key)
+        .filter(|candidate| strsim::levenshtein(key, candidate) <= 3)
+        .min_by_key(|candidate| strsim::levenshtein(key, candidate))
+        .map(|candidate| format!("; did you mean `{}`?", candidate))
+        .unwrap_or_else(|| "; run `rivet config list` to see them all".to_string());
+    anyhow::anyhow!("Unknown setting `{}`{}", key, suggestion)
+}
+
+/// Turn the command-line text for a setting into its JSON value.
+fn parse_value(setting: &Setting, text: &str) -> Result<Value> {
+    let text = text.trim();
+    let value = match setting.kind {
+        Kind::Bool => match text.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Value::Bool(true),
+            "false" | "no" | "off" | "0" => Value::Bool(false),
+            _ => anyhow::bail!("{} expects true or false, got `{}`", setting.key, text),
+        },
+        Kind::Number if text.is_empty() => Value::Null,
+        Kind::Number => match text.parse::<u64>() {
+            Ok(number) if number > 0 => Value::from(number),
+            _ => anyhow::bail!("{} expects a positive number, got `{}`", setting.key, text),
+        },
+        Kind::Text => {
+            if text.is_empty() {
+                anyhow::bail!("{} can't be empty", setting.key);
+            }
+            Value::String(text.to_string())
+        }
+        Kind::OptionalText if text.is_empty() => Value::Null,
+        Kind::OptionalText => Value::String(text.to_string()),
+        Kind::List => {
+            let items: Vec<String> = if text.starts_with('[') {
+                serde_json::from_str(text).with_context(|| {
+                    format!("{} expects a list of strings, got `{}`", setting.key, text)
+                })?
+            } else {
+                text.split(',')
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect()
+            };
+            Value::from(items)
+        }
+    };
+
+    if !setting.choices.is_empty() {
+        let values: Vec<&str> = match &value {
+            Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+            other => other.as_str().into_iter().collect(),
+        };
+        if let Some(bad) = values.iter().find(|v| !setting.choices.contains(v)) {
+            anyhow::bail!(
+                "`{}` is not a valid value for {} (expected one of: {})",
+                bad,
+                setting.key,
+                setting.choices.join(", ")
+            );
+        }
+    }
+    Ok(value)
+}
+
+/// Set the value at a dot path, creating the objects along it.
+fn set_path(target: &mut Value, key: &str, value: Value) {
+    let mut current = target;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if !current.is_object() {
+            *current = Value::Object(Default::default());
+        }
+        let object = current.as_object_mut().expect("just made an object");
+        if parts.peek().is_none() {
+            object.insert(part.to_string(), value);
+            return;
+        }
+        current = object
+            .entry(part)
+            .or_insert_with(|| Value::Object(Default::default()));
+    }
+}
+
+/// Remove the value at a dot path.
+fn remove_path(target: &mut Value, key: &str) {
+    let (parent, last) = match key.rsplit_once('.') {
+        Some((parent, last)) => (lookup_json_mut(target, parent), last),
+        None => (Some(target), key),
+    };
+    if let Some(Value::Object(object)) = parent {
+        object.remove(last);
+    }
+}
+
+fn lookup_json_mut<'a>(value: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    key.split('.')
+        .try_fold(value, |value, part| value.get_mut(part))
+}
+
+/// Strings unquoted and lists comma-separated, as `set` accepts them.
+fn display_value(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| display_value(Some(item)))
+            .collect::<Vec<_>>()
+            .join(","),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn write_file(path: &Path, value: &Value) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content = if path.extension().is_some_and(|ext| ext == "toml") {
+        toml::to_string_pretty(value)?
+    } else {
+        serde_json::to_string_pretty(value)?
+    };
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_cover_user_config() {
+        let config = serde_json::to_value(UserConfig::default()).unwrap();
+        fn leaves(value: &Value, prefix: &str, out: &mut Vec<String>) {
+            match value {
+                Value::Object(map) => {
+                    for (key, value) in map {
+                        let path = if prefix.is_empty() {
+                            key.clone()
+                        } else {
+                            format!("{}.{}", prefix, key)
+                        };
+                        leaves(value, &path, out);
+                    }
+                }
+                _ => out.push(prefix.to_string()),
+            }
+        }
+        let mut keys = Vec::new();
+        leaves(&config, "", &mut keys);
+        for key in keys {
+            assert!(
+                SETTINGS.iter().any(|setting| setting.key == key),
+                "{} is missing from SETTINGS",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_value_by_kind() {
+        let setting = |key| find_setting(key).unwrap();
+        assert_eq!(
+            parse_value(setting("reports.auto_open_browser"), "no").unwrap(),
+            Value::Bool(false)
+        );
+        assert!(parse_value(setting("reports.auto_open_browser"), "maybe").is_err());
+        assert_eq!(
+            parse_value(setting("defaults.report"), "html, junit").unwrap(),
+            serde_json::json!(["html", "junit"])
+        );
+        assert_eq!(
+            parse_value(setting("defaults.report"), r#"["json"]"#).unwrap(),
+            serde_json::json!(["json"])
+        );
+        assert!(parse_value(setting("defaults.report"), "html,pdf").is_err());
+        assert!(parse_value(setting("reports.default_template"), "fancy").is_err());
+        assert_eq!(parse_value(setting("proxy"), "").unwrap(), Value::Null);
+        assert_eq!(
+            parse_value(setting("defaults.parallel"), "8").unwrap(),
+            Value::from(8)
+        );
+        assert!(parse_value(setting("defaults.parallel"), "0").is_err());
+    }
+
+    #[test]
+    fn test_set_path_creates_sections() {
+        let mut value = serde_json::json!({});
+        set_path(&mut value, "reports.report_name", Value::from("latest"));
+        assert_eq!(
+            value,
+            serde_json::json!({"reports": {"report_name": "latest"}})
+        );
+        assert_eq!(
+            display_value(lookup_json(&value, "reports.report_name")),
+            "latest"
+        );
+        remove_path(&mut value, "reports.report_name");
+        assert_eq!(value, serde_json::json!({"reports": {}}));
+    }
+
+    #[test]
+    fn test_unknown_setting_suggests_the_closest() {
+        let error = find_setting("reports.default_templte").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("did you mean `reports.default_template`?"));
+        let error = find_setting("colour").unwrap_err();
+        assert!(error.to_string().contains("rivet config list"));
+    }
+}
@@ -1,20 +1,499 @@
-use anyhow::Result;
-use std::path::PathBuf;
+use crate::style::Colorize;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-pub async fn handle_coverage(
-    spec: PathBuf,
-    from: Vec<PathBuf>,
-    out: Option<PathBuf>,
-) -> Result<()> {
-    println!("Generating coverage report from spec: {}", spec.display());
-    println!("Analyzing {} report files", from.len());
+pub struct CoverageOptions {
+    pub spec: PathBuf,
+    pub from: Vec<PathBuf>,
+    pub out: Option<PathBuf>,
+    pub min: Option<f64>,
+    pub baseline: Option<PathBuf>,
+    pub fail_on_regression: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub spec: String,
+    pub total: usize,
+    pub covered: usize,
+    pub percentage: f64,
+    pub operations: Vec<OperationCoverage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<CoverageDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationCoverage {
+    pub method: String,
+    pub path: String,
+    pub operation_id: Option<String>,
+    pub tags: Vec<String>,
+    pub covered: bool,
+    pub covered_by: Vec<String>,
+}
+
+/// Per-operation changes relative to a previously saved coverage report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageDiff {
+    pub baseline_percentage: f64,
+    pub percentage_change: f64,
+    pub newly_covered: Vec<OperationRef>,
+    pub newly_uncovered: Vec<OperationRef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OperationRef {
+    pub method: String,
+    pub path: String,
+}
+
+/// A single request recorded in a run report.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub test_name: String,
+    pub method: String,
+    pub url: String,
+}
+
+// Minimal view of the JSON run report; only the fields coverage needs
+#[derive(Debug, Deserialize)]
+struct RunReport {
+    #[serde(default)]
+    suites: Vec<RunReportSuite>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RunReportSuite {
+    #[serde(default)]
+    tests: Vec<RunReportTest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RunReportTest {
+    name: String,
+    #[serde(default)]
+    request_method: Option<String>,
+    #[serde(default)]
+    request_url: Option<String>,
+}
+
+pub async fn handle_coverage(options: CoverageOptions) -> Result<()> {
+    println!(
+        "{} Generating coverage report from spec: {}",
+        "→".cyan(),
+        options.spec.display().to_string().bright_white()
+    );
+    println!(
+        "{} Analyzing {} report files",
+        "→".cyan(),
+        options.from.len()
+    );
+
+    let spec = crate::gen::load_spec(&options.spec)?;
+
+    let mut requests = Vec::new();
+    for report_path in &options.from {
+        requests.extend(load_recorded_requests(report_path)?);
+    }
+
+    if requests.is_empty() {
+        println!(
+            "{} No recorded requests found in the given reports",
+            "⚠".yellow()
+        );
+    }
+
+    let mut report = compute_coverage(&spec, &requests, &options.spec.display().to_string());
+
+    if let Some(baseline_path) = &options.baseline {
+        let content = fs::read_to_string(baseline_path)
+            .map_err(|e| anyhow!("Failed to read baseline {}: {}", baseline_path.display(), e))?;
+        let baseline: CoverageReport = serde_json::from_str(&content).map_err(|e| {
+            anyhow!(
+                "Failed to parse baseline {}: {}",
+                baseline_path.display(),
+                e
+            )
+        })?;
+        report.diff = Some(diff_coverage(&baseline, &report));
+    }
+
+    print_coverage_table(&report);
+
+    if let Some(diff) = &report.diff {
+        print_coverage_diff(diff);
+    }
+
+    if let Some(output) = &options.out {
+        write_coverage_report(&report, output)?;
+        println!(
+            "\n{} Coverage report written to {}",
+            "✓".green(),
+            output.display()
+        );
+    }
+
+    if options.fail_on_regression {
+        if let Some(diff) = report
+            .diff
+            .as_ref()
+            .filter(|d| !d.newly_uncovered.is_empty())
+        {
+            anyhow::bail!(
+                "Coverage regressed: {} operation(s) are no longer covered",
+                diff.newly_uncovered.len()
+            );
+        }
+    }
+
+    if let Some(min) = options.min {
+        if report.percentage < min {
+            anyhow::bail!(
+                "Coverage {:.1}% is below the required minimum of {:.1}%",
+                report.percentage,
+                min
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn load_recorded_requests(report_path: &Path) -> Result<Vec<RecordedRequest>> {
+    let content = fs::read_to_string(report_path)
+        .map_err(|e| anyhow!("Failed to read report {}: {}", report_path.display(), e))?;
+    let report: RunReport = serde_json::from_str(&content)
+        .map_err(|e| anyhow!("Failed to parse report {}: {}", report_path.display(), e))?;
+
+    let requests = report
+        .suites
+        .into_iter()
+        .flat_map(|suite| suite.tests)
+        .filter_map(|test| match (test.request_method, test.request_url) {
+            (Some(method), Some(url)) => Some(RecordedRequest {
+                test_name: test.name,
+                method,
+                url,
+            }),
+            _ => None,
+        })
+        .collect();
+
+    Ok(requests)
+}
+
+pub fn compute_coverage(
+    spec: &openapiv3::OpenAPI,
+    requests: &[RecordedRequest],
+    spec_name: &str,
+) -> CoverageReport {
+    // Server base paths (e.g. "/v1") are stripped from request paths before matching
+    let base_paths = server_base_paths(spec);
+
+    let mut operations = Vec::new();
+
+    for (path, path_item) in &spec.paths.paths {
+        if let openapiv3::ReferenceOr::Item(path_item) = path_item {
+            let methods = [
+                ("GET", &path_item.get),
+                ("POST", &path_item.post),
+                ("PUT", &path_item.put),
+                ("DELETE", &path_item.delete),
+                ("PATCH", &path_item.patch),
+                ("HEAD", &path_item.head),
+                ("OPTIONS", &path_item.options),
+                ("TRACE", &path_item.trace),
+            ];
+
+            for (method, operation) in methods {
+                if let Some(operation) = operation {
+                    let covered_by: Vec<String> = requests
+                        .iter()
+                        .filter(|req| req.method.eq_ignore_ascii_case(method))
+                        .filter(|req| {
+                            let request_path = request_path(&req.url);
+                            path_matches(path, &request_path)
+                                || base_paths.iter().any(|base| {
+                                    request_path
+                                        .strip_prefix(base.as_str())
+                                        .is_some_and(|rest| path_matches(path, rest))
+                                })
+                        })
+                        .map(|req| req.test_name.clone())
+                        .collect();
+
+                    operations.push(OperationCoverage {
+                        method: method.to_string(),
+                        path: path.clone(),
+                        operation_id: operation.operation_id.clone(),
+                        tags: operation.tags.clone(),
+                        covered: !covered_by.is_empty(),
+                        covered_by,
+                    });
+                }
+            }
+        }
+    }
+
+    let total = operations.len();
+    let covered = operations.iter().filter(|op| op.covered).count();
+
+    CoverageReport {
+        spec: spec_name.to_string(),
+        total,
+        covered,
+        percentage: if total > 0 {
+            (covered as f64 / total as f64) * 100.0
+        } else {
+            0.0
+        },
+        operations,
+        diff: None,
+    }
+}
+
+pub fn diff_coverage(baseline: &CoverageReport, current: &CoverageReport) -> CoverageDiff {
+    let was_covered = |op: &OperationCoverage| {
+        baseline
+            .operations
+            .iter()
+            .any(|b| b.covered && b.method == op.method && b.path == op.path)
+    };
+    let to_ref = |op: &OperationCoverage| OperationRef {
+        method: op.method.clone(),
+        path: op.path.clone(),
+    };
+
+    // Operations removed from the spec are not treated as regressions
+    let newly_covered = current
+        .operations
+        .iter()
+        .filter(|op| op.covered && !was_covered(op))
+        .map(to_ref)
+        .collect();
+    let newly_uncovered = current
+        .operations
+        .iter()
+        .filter(|op| !op.covered && was_covered(op))
+        .map(to_ref)
+        .collect();
+
+    CoverageDiff {
+        baseline_percentage: baseline.percentage,
+        percentage_change: current.percentage - baseline.percentage,
+        newly_covered,
+        newly_uncovered,
+    }
+}
+
+fn request_path(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(parsed) => parsed.path().to_string(),
+        Err(_) => {
+            // Relative or partially templated URLs: drop the query string and any scheme/host
+            let without_query = url.split(['?', '#']).next().unwrap_or(url);
+            match without_query.find("://") {
+                Some(idx) => {
+                    let rest = &without_query[idx + 3..];
+                    rest.find('/')
+                        .map(|slash| rest[slash..].to_string())
+                        .unwrap_or_else(|| "/".to_string())
+                }
+                None => without_query.to_string(),
+            }
+        }
+    }
+}
+
+/// Paths of the spec's server URLs, such as `/v1`; root paths are left out.
+pub fn server_base_paths(spec: &openapiv3::OpenAPI) -> Vec<String> {
+    spec.servers
+        .iter()
+        .filter_map(|server| {
+            let path = match url::Url::parse(&server.url) {
+                Ok(url) => url.path().to_string(),
+                Err(_) => server.url.clone(),
+            };
+            let path = path.trim_end_matches('/').to_string();
+            if path.is_empty() {
+                None
+            } else {
+                Some(path)
+            }
+        })
+        .collect()
+}
+
+/// Match a spec path template such as `/users/{id}` against a concrete request path.
+pub fn path_matches(template: &str, path: &str) -> bool {
+    let template_segments: Vec<&str> = template.trim_matches('/').split('/').collect();
+    let path_segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    if template_segments.len() != path_segments.len() {
+        return false;
+    }
+
+    template_segments
+        .iter()
+        .zip(path_segments.iter())
+        .all(|(expected, actual)| {
+            (expected.starts_with('{') && expected.ends_with('}') && !actual.is_empty())
+                || expected == actual
+        })
+}
+
+fn group_by_tag(operations: &[OperationCoverage]) -> BTreeMap<String, Vec<&OperationCoverage>> {
+    let mut groups: BTreeMap<String, Vec<&OperationCoverage>> = BTreeMap::new();
+    for op in operations {
+        if op.tags.is_empty() {
+            groups.entry("default".to_string()).or_default().push(op);
+        } else {
+            for tag in &op.tags {
+                groups.entry(tag.clone()).or_default().push(op);
+            }
+        }
+    }
+    groups
+}
+
+fn print_coverage_table(report: &CoverageReport) {
+    for (tag, operations) in group_by_tag(&report.operations) {
+        println!("\n{}", tag.bright_white().bold());
+        for op in operations {
+            if op.covered {
+                println!("  {} {:<7} {}", "✔".green(), op.method, op.path);
+            } else {
+                println!("  {} {:<7} {}", "✖".red(), op.method, op.path.dimmed());
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Coverage: {}/{} operations ({:.1}%)",
+        report.covered, report.total, report.percentage
+    );
+}
+
+fn print_coverage_diff(diff: &CoverageDiff) {
+    println!();
+    println!("{}", "Compared to baseline".bright_white().bold());
+    for op in &diff.newly_covered {
+        println!("  {} {:<7} {}", "+".green(), op.method, op.path);
+    }
+    for op in &diff.newly_uncovered {
+        println!("  {} {:<7} {}", "-".red(), op.method, op.path);
+    }
+    if diff.newly_covered.is_empty() && diff.newly_uncovered.is_empty() {
+        println!("  No per-operation changes");
+    }
+
+    let change = format!("{:+.1}%", diff.percentage_change);
+    let change = if diff.percentage_change < 0.0 {
+        change.red().to_string()
+    } else {
+        change.green().to_string()
+    };
+    println!(
+        "Net change: {} (baseline {:.1}%)",
+        change, diff.baseline_percentage
+    );
+}
+
+fn render_coverage_text(report: &CoverageReport) -> String {
+    let mut text = String::new();
+    for (tag, operations) in group_by_tag(&report.operations) {
+        text.push_str(&format!("{}\n", tag));
+        for op in operations {
+            let mark = if op.covered { "covered" } else { "uncovered" };
+            text.push_str(&format!("  {:<9} {:<7} {}\n", mark, op.method, op.path));
+        }
+        text.push('\n');
+    }
+    text.push_str(&format!(
+        "Coverage: {}/{} operations ({:.1}%)\n",
+        report.covered, report.total, report.percentage
+    ));
+    if let Some(diff) = &report.diff {
+        text.push_str("\nCompared to baseline\n");
+        for op in &diff.newly_covered {
+            text.push_str(&format!("  + {:<7} {}\n", op.method, op.path));
+        }
+        for op in &diff.newly_uncovered {
+            text.push_str(&format!("  - {:<7} {}\n", op.method, op.path));
+        }
+        text.push_str(&format!(
+            "Net change: {:+.1}% (baseline {:.1}%)\n",
+            diff.percentage_change, diff.baseline_percentage
+        ));
+    }
+    text
+}
+
+pub fn render_coverage_html(report: &CoverageReport) -> Result<String> {
+    use tera::{Context, Tera};
+
+    let mut tera = Tera::default();
+    tera.add_raw_template(
+        "coverage.html",
+        include_str!("../../templates/coverage.html"),
+    )
+    .map_err(|e| anyhow!("Failed to add template: {}", e))?;
+
+    let tags: Vec<serde_json::Value> = group_by_tag(&report.operations)
+        .into_iter()
+        .map(|(tag, operations)| {
+            serde_json::json!({
+                "name": tag,
+                "total": operations.len(),
+                "covered": operations.iter().filter(|op| op.covered).count(),
+                "operations": operations,
+            })
+        })
+        .collect();
+
+    // HTTP methods that appear in the spec but were not hit by any test
+    let mut methods: BTreeMap<&str, bool> = BTreeMap::new();
+    for op in &report.operations {
+        *methods.entry(op.method.as_str()).or_insert(false) |= op.covered;
+    }
+    let never_exercised: Vec<&str> = methods
+        .into_iter()
+        .filter(|(_, covered)| !covered)
+        .map(|(method, _)| method)
+        .collect();
+
+    let mut context = Context::new();
+    context.insert("report", report);
+    context.insert(
+        "timestamp",
+        &chrono::Utc::now()
+            .format("%Y-%m-%d %H:%M:%S UTC")
+            .to_string(),
+    );
+    context.insert("percentage_rounded", &format!("{:.1}", report.percentage));
+    context.insert("tags", &tags);
+    context.insert("never_exercised", &never_exercised);
+
+    Ok(tera.render("coverage.html", &context)?)
+}
 
-    if let Some(output) = out {
-        println!("Output file: {}", output.display());
+fn write_coverage_report(report: &CoverageReport, output: &Path) -> Result<()> {
+    if let Some(parent) = output.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
     }
 
-    // TODO: Implement coverage analysis
-    println!("✔ Coverage analyzer not yet implemented");
+    let content = match output.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::to_string_pretty(report)?,
+        Some("html") | Some("htm") => render_coverage_html(report)?,
+        _ => render_coverage_text(report),
+    };
+    fs::write(output, content)?;
 
     Ok(())
 }
@@ -0,0 +1,67 @@
+//! How `rivet run` and `rivet perf` end, and the exit code each outcome gets.
+
+use serde::Serialize;
+
+/// Why a run ended. `main` turns it into the process exit code, so handlers
+/// return normally and their callers can see how the run went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitReason {
+    /// Every test passed (or every threshold held)
+    Passed,
+    /// Some tests failed, or a threshold or regression check did
+    TestFailures,
+    /// Bad flags, settings or suite files
+    UsageError,
+    /// Suites couldn't be read, or no request got through to a server
+    InfrastructureError,
+    /// Stopped by Ctrl-C
+    Interrupted,
+}
+
+impl ExitReason {
+    pub fn code(self) -> i32 {
+        match self {
+            ExitReason::Passed => 0,
+            ExitReason::TestFailures => 1,
+            ExitReason::UsageError => 2,
+            ExitReason::InfrastructureError => 3,
+            ExitReason::Interrupted => 130,
+        }
+    }
+
+    /// The reason a handler failed with `error`: reading files or reaching
+    /// the network is infrastructure, anything else a usage error.
+    pub fn for_error(error: &anyhow::Error) -> Self {
+        if error
+            .chain()
+            .any(|cause| cause.is::<std::io::Error>() || cause.is::<reqwest::Error>())
+        {
+            ExitReason::InfrastructureError
+        } else {
+            ExitReason::UsageError
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_errors_are_classified_by_cause() {
+        let missing = std::fs::read_to_string("/nonexistent/suite.rivet.yaml")
+            .context("Failed to read file")
+            .unwrap_err();
+        assert_eq!(
+            ExitReason::for_error(&missing),
+            ExitReason::InfrastructureError
+        );
+        assert_eq!(ExitReason::InfrastructureError.code(), 3);
+
+        let invalid = anyhow::anyhow!("--max-failures must be at least 1");
+        assert_eq!(ExitReason::for_error(&invalid), ExitReason::UsageError);
+        assert_eq!(ExitReason::UsageError.code(), 2);
+    }
+}
@@ -1,74 +1,769 @@
-use anyhow::Result;
-use owo_colors::OwoColorize;
-use std::path::PathBuf;
+use crate::style::Colorize;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tonic::Code;
 
-use crate::grpc::GrpcClient;
-use crate::utils::parse_timeout;
+use crate::config::{Expectation, GrpcRequest, Request, RivetConfig, StatusExpectation, TestStep};
+use crate::grpc::{
+    code_name, parse_metadata, parse_status_code, CallKind, GrpcClient, GrpcTlsOptions,
+    MethodSummary, ProtoSchema, ServiceSummary, StatusError,
+};
+use crate::runner::events::OutputFormat;
+use crate::runner::executor::{resolve_grpc_data, RequestExecutor};
+use crate::runner::variables::VariableContext;
+use crate::utils::{parse_timeout, warn_insecure};
 
-pub async fn handle_grpc(
-    server: String,
-    proto: PathBuf,
-    call: String,
-    data: Option<String>,
-    expect_jsonpath: Vec<String>,
-    timeout: String,
-) -> Result<()> {
-    println!("{} Making gRPC call: {}", "→".cyan(), call.bright_white());
-    println!("{} gRPC server: {}", "→".cyan(), server.bright_blue());
-    println!("{} Proto directory: {}", "→".cyan(), proto.display());
-    println!("{} Timeout: {}", "→".cyan(), timeout);
+const DEFAULT_TIMEOUT: &str = "30s";
+
+/// Metadata saved as a variable filled from the environment, never as its value.
+const SECRET_METADATA: &str = "authorization";
+
+pub struct GrpcOptions {
+    pub server: Option<String>,
+    pub proto: Option<PathBuf>,
+    pub call: Option<String>,
+    pub data: Option<String>,
+    pub metadata: Vec<String>,
+    pub expect_jsonpath: Vec<String>,
+    /// Status the call should end with (name or number); OK when unset
+    pub expect_status: Option<String>,
+    /// 30s unless set here or in the `--from` file
+    pub timeout: Option<String>,
+    /// Call saved with `--save` to start from; the options above override it
+    pub from: Option<PathBuf>,
+    /// Write the call to this file as a suite `rivet run` can replay
+    pub save: Option<PathBuf>,
+    pub use_protoc: bool,
+    pub tls: GrpcTlsOptions,
+    /// human, or json to print only the response (or error) document
+    pub output: String,
+}
+
+/// Everything that makes up one call, whether from flags or a saved file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrpcCall {
+    pub server: String,
+    pub proto: PathBuf,
+    pub call: String,
+    pub data: Option<String>,
+    /// `key: value` entries
+    pub metadata: Vec<String>,
+    pub expect_jsonpath: Vec<String>,
+    pub expect_status: Option<Code>,
+    pub timeout: String,
+}
+
+impl GrpcCall {
+    /// The call `options` describe, on top of `saved` when given: single
+    /// values replace the saved ones, metadata is merged by key and
+    /// `--expect-jsonpath` replaces the saved paths.
+    pub fn from_options(options: &GrpcOptions, saved: Option<GrpcCall>) -> Result<Self> {
+        let required = |value: Option<String>, flag: &str| {
+            value.ok_or_else(|| anyhow!("{} is required unless --from is given", flag))
+        };
+        let saved = saved.as_ref();
+
+        let mut metadata: Vec<String> = saved
+            .map(|saved| saved.metadata.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| {
+                !options
+                    .metadata
+                    .iter()
+                    .any(|given| metadata_key(given).eq_ignore_ascii_case(metadata_key(entry)))
+            })
+            .collect();
+        metadata.extend(options.metadata.iter().cloned());
+
+        Ok(Self {
+            server: required(
+                options.server.clone().or(saved.map(|s| s.server.clone())),
+                "--server",
+            )?,
+            proto: options
+                .proto
+                .clone()
+                .or(saved.map(|s| s.proto.clone()))
+                .ok_or_else(|| anyhow!("--proto is required unless --from is given"))?,
+            call: required(
+                options.call.clone().or(saved.map(|s| s.call.clone())),
+                "--call",
+            )?,
+            data: options
+                .data
+                .clone()
+                .or_else(|| saved.and_then(|s| s.data.clone())),
+            metadata,
+            expect_jsonpath: if options.expect_jsonpath.is_empty() {
+                saved.map(|s| s.expect_jsonpath.clone()).unwrap_or_default()
+            } else {
+                options.expect_jsonpath.clone()
+            },
+            expect_status: match &options.expect_status {
+                Some(status) => Some(parse_status_code(status)?),
+                None => saved.and_then(|s| s.expect_status),
+            },
+            timeout: options
+                .timeout
+                .clone()
+                .or(saved.map(|s| s.timeout.clone()))
+                .unwrap_or_else(|| DEFAULT_TIMEOUT.to_string()),
+        })
+    }
+
+    /// Load the first gRPC step of a suite, e.g. one written by `--save`.
+    /// Variables are filled in from the suite's `vars` and the environment.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: RivetConfig = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        let step = config
+            .tests
+            .iter()
+            .find(|step| step.request.grpc.is_some())
+            .ok_or_else(|| anyhow!("{} has no gRPC step", path.display()))?;
+        let grpc = step.request.grpc.as_ref().expect("found a gRPC step");
+
+        let context = VariableContext::new()
+            .with_env_vars()
+            .with_config_vars(config.vars.as_ref())?;
+        let resolve = |text: &str| context.resolve(text);
+
+        let mut metadata = grpc
+            .metadata
+            .iter()
+            .flatten()
+            .map(|(key, value)| Ok(format!("{}: {}", resolve(key)?, resolve(value)?)))
+            .collect::<Result<Vec<_>>>()?;
+        metadata.sort();
+        let expect = step.expect.as_ref();
+        let mut expect_jsonpath: Vec<String> = expect
+            .and_then(|expect| expect.jsonpath.as_ref())
+            .map(|paths| paths.keys().cloned().collect())
+            .unwrap_or_default();
+        expect_jsonpath.sort();
+        let expect_status = match expect.and_then(|expect| expect.status.as_ref()) {
+            Some(StatusExpectation::Number(code)) => Some(Code::from_i32(i32::from(*code))),
+            Some(StatusExpectation::String(code)) => Some(parse_status_code(&resolve(code)?)?),
+            None => None,
+        };
+
+        Ok(Self {
+            server: resolve(&grpc.server)?,
+            proto: PathBuf::from(resolve(&grpc.proto)?),
+            call: resolve(&grpc.call)?,
+            data: grpc
+                .data
+                .as_ref()
+                .map(|data| resolve_grpc_data(data, &context))
+                .transpose()?,
+            metadata,
+            expect_jsonpath,
+            expect_status,
+            timeout: match &grpc.timeout {
+                Some(timeout) => resolve(timeout)?,
+                None => DEFAULT_TIMEOUT.to_string(),
+            },
+        })
+    }
+
+    /// A single-step suite making this call. `jsonpath` expects the values
+    /// `response` had and `status` the one the call ended with, unless OK;
+    /// `authorization` metadata becomes `{{authorization}}`, filled from
+    /// `${AUTHORIZATION}`.
+    pub fn to_suite(&self, response: &Value, status: Code) -> RivetConfig {
+        let mut vars = HashMap::new();
+        let metadata: HashMap<String, String> = self
+            .metadata
+            .iter()
+            .map(|entry| {
+                let key = metadata_key(entry).to_string();
+                let value = entry.split_once(':').map_or("", |(_, value)| value.trim());
+                if key.eq_ignore_ascii_case(SECRET_METADATA) {
+                    vars.insert(
+                        SECRET_METADATA.to_string(),
+                        format!("${{{}}}", SECRET_METADATA.to_uppercase()),
+                    );
+                    (key, format!("{{{{{}}}}}", SECRET_METADATA))
+                } else {
+                    (key, value.to_string())
+                }
+            })
+            .collect();
+        let jsonpath: HashMap<String, Value> = self
+            .expect_jsonpath
+            .iter()
+            .map(|path| {
+                let value =
+                    RequestExecutor::extract_jsonpath_value(response, path).unwrap_or(Value::Null);
+                (path.clone(), value)
+            })
+            .collect();
+        let status = self.expect_status.unwrap_or(status);
+
+        RivetConfig {
+            default_headers: None,
+            identify_requests: None,
+            base_path: None,
+            include: None,
+            templates: None,
+            auth: None,
+            max_capture_bytes: None,
+            follow_redirects: None,
+            name: self.call.clone(),
+            description: None,
+            env: None,
+            secrets: (!vars.is_empty()).then(|| vec![SECRET_METADATA.to_string()]),
+            vars: (!vars.is_empty()).then_some(vars),
+            strict_vars: None,
+            setup: None,
+            before_each: None,
+            tests: vec![TestStep {
+                capture: None,
+                name: self.call.clone(),
+                description: None,
+                vars: None,
+                request: Request {
+                    compression: None,
+                    follow_redirects: None,
+                    method: "POST".to_string(),
+                    url: String::new(),
+                    headers: None,
+                    params: None,
+                    body: None,
+                    grpc: Some(GrpcRequest {
+                        server: self.server.clone(),
+                        proto: self.proto.display().to_string(),
+                        call: self.call.clone(),
+                        data: self.data.as_deref().map(data_value),
+                        metadata: (!metadata.is_empty()).then_some(metadata),
+                        timeout: (self.timeout != DEFAULT_TIMEOUT).then(|| self.timeout.clone()),
+                    }),
+                },
+                expect: Some(Expectation {
+                    poll: None,
+                    redirects_to: None,
+                    content_type: None,
+                    status: (status != Code::Ok)
+                        .then_some(StatusExpectation::Number(status as u16)),
+                    schema: None,
+                    jsonpath: (!jsonpath.is_empty()).then_some(jsonpath),
+                    jsonpath_length: None,
+                    jsonpath_contains: None,
+                    jsonpath_every: None,
+                    headers: None,
+                }),
+            }],
+            after_each: None,
+            dataset: None,
+            teardown: None,
+            tls: None,
+            insecure: None,
+        }
+    }
+
+    /// Whether saving puts a placeholder in place of a metadata value.
+    fn has_secret_metadata(&self) -> bool {
+        self.metadata
+            .iter()
+            .any(|entry| metadata_key(entry).eq_ignore_ascii_case(SECRET_METADATA))
+    }
+}
+
+fn metadata_key(entry: &str) -> &str {
+    entry.split_once(':').map_or(entry, |(key, _)| key).trim()
+}
+
+/// `--data` as YAML-friendly JSON: a message, a list of messages for JSON
+/// lines, or the text itself when it isn't JSON.
+fn data_value(data: &str) -> Value {
+    if let Ok(value) = serde_json::from_str(data) {
+        return value;
+    }
+    let lines: Option<Vec<Value>> = data
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).ok())
+        .collect();
+    match lines {
+        Some(messages) if !messages.is_empty() => Value::Array(messages),
+        _ => Value::String(data.to_string()),
+    }
+}
+
+/// Write `suite` to `path`, adding `.rivet.yaml` when it has no extension.
+fn save_suite(path: &Path, suite: &RivetConfig) -> Result<PathBuf> {
+    let path = if path.extension().is_none() {
+        path.with_extension("rivet.yaml")
+    } else {
+        path.to_path_buf()
+    };
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_yaml::to_string(suite)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+pub async fn handle_grpc(options: GrpcOptions) -> Result<()> {
+    let saved = options.from.as_deref().map(GrpcCall::load).transpose()?;
+    let grpc_call = GrpcCall::from_options(&options, saved)?;
+    let GrpcOptions {
+        save,
+        use_protoc,
+        tls,
+        output,
+        ..
+    } = options;
+    let GrpcCall {
+        server,
+        proto,
+        call,
+        data,
+        metadata,
+        expect_jsonpath,
+        expect_status,
+        timeout,
+    } = grpc_call.clone();
+
+    let json = output.parse::<OutputFormat>()? == OutputFormat::Json;
+    // In JSON mode stdout carries only the result document
+    macro_rules! progress {
+        ($($arg:tt)*) => {
+            if !json {
+                println!($($arg)*);
+            }
+        };
+    }
+
+    let expected_status = expect_status.unwrap_or(Code::Ok);
+
+    progress!("{} Making gRPC call: {}", "→".cyan(), call.bright_white());
+    progress!("{} gRPC server: {}", "→".cyan(), server.bright_blue());
+    progress!("{} Proto directory: {}", "→".cyan(), proto.display());
+    progress!("{} Timeout: {}", "→".cyan(), timeout);
 
     if let Some(ref request_data) = data {
-        println!("{} Request data: {}", "→".cyan(), request_data);
+        progress!("{} Request data: {}", "→".cyan(), request_data);
+    }
+
+    let request_metadata = parse_metadata(&metadata)?;
+    if !metadata.is_empty() {
+        progress!("{} Metadata:", "→".cyan());
+        for entry in &metadata {
+            let (key, value) = entry.split_once(':').unwrap_or((entry, ""));
+            let value = if key.trim().eq_ignore_ascii_case("authorization") {
+                "****"
+            } else {
+                value.trim()
+            };
+            progress!("  {}: {}", key.trim(), value);
+        }
     }
 
     if !expect_jsonpath.is_empty() {
-        println!("{} JSONPath expectations:", "→".cyan());
+        progress!("{} JSONPath expectations:", "→".cyan());
         for expectation in &expect_jsonpath {
-            println!("  - {}", expectation.bright_yellow());
+            progress!("  - {}", expectation.bright_yellow());
         }
     }
+    if expect_status.is_some() {
+        progress!(
+            "{} Expected status: {}",
+            "→".cyan(),
+            code_name(expected_status).bright_yellow()
+        );
+    }
 
     // Parse timeout
     let timeout_duration = parse_timeout(&timeout)?;
 
+    if tls.insecure {
+        warn_insecure("--insecure");
+    }
+
     // Create gRPC client - this will now fail if server is unreachable
-    println!("{} Compiling proto files...", "→".cyan());
-    let mut client = GrpcClient::new(&proto, &server).await?;
+    progress!("{} Compiling proto files...", "→".cyan());
+    let mut client = GrpcClient::new(&proto, &server, use_protoc, &tls).await?;
 
     // Display available services and methods
     let services = client.list_services();
     if !services.is_empty() {
-        println!(
+        progress!(
             "{} Available services: {}",
             "→".cyan(),
             services.join(", ").bright_green()
         );
     }
 
+    let kind = client.call_kind(&call)?;
+    let data = match data {
+        Some(data) => Some(data),
+        // Client streams can be piped in as JSON lines
+        None if kind == CallKind::ClientStreaming && !atty::is(atty::Stream::Stdin) => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            Some(input)
+        }
+        None => None,
+    };
+
     // Make the gRPC call
-    println!("{} Calling {}...", "→".cyan(), call.bright_white());
-    let response = client
-        .call(&call, data.as_deref(), timeout_duration)
-        .await?;
+    progress!("{} Calling {}...", "→".cyan(), call.bright_white());
+    let streaming = kind == CallKind::ServerStreaming;
+    let outcome = if streaming {
+        progress!("\n{} Response stream:", "✓".green().bold());
+        client
+            .call_streaming(
+                &call,
+                data.as_deref(),
+                &request_metadata,
+                timeout_duration,
+                |message| progress!("{}", message),
+            )
+            .await
+    } else {
+        client
+            .call(&call, data.as_deref(), &request_metadata, timeout_duration)
+            .await
+    };
+
+    // A failed call is answered with its error document, so expectations
+    // such as `$.error.status` can check it
+    let (document, failure) = match outcome {
+        Ok(response) => (response, None),
+        Err(e) => {
+            let failure = e.downcast::<StatusError>()?;
+            (failure.to_json(), Some(failure))
+        }
+    };
+
+    match &failure {
+        Some(failure) => {
+            if !json {
+                print_status_error(failure);
+            }
+        }
+        None if streaming => {
+            let count = document.as_array().map_or(0, Vec::len);
+            progress!("{} {} message(s) received", "✓".green().bold(), count);
+        }
+        None => {
+            progress!("\n{} Response:", "✓".green().bold());
+            progress!(
+                "{}",
+                serde_json::to_string_pretty(&document)?.bright_white()
+            );
+        }
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&document)?);
+    }
+
+    // Validate expectations; in JSON mode they go to stderr
+    if !expect_jsonpath.is_empty() {
+        let report = |line: String| {
+            if json {
+                eprintln!("{}", line);
+            } else {
+                println!("{}", line);
+            }
+        };
+        report(format!("\n{} Validating expectations:", "→".cyan()));
+        match document.as_array() {
+            // Every streamed message, then the whole stream as an array
+            Some(messages) if streaming && failure.is_none() => {
+                for (index, message) in messages.iter().enumerate() {
+                    report(format!("  Message {}:", index + 1));
+                    for result in client.validate_expectations(message, &expect_jsonpath)? {
+                        report(format!("    {}", result));
+                    }
+                }
+                report("  All messages:".to_string());
+                for result in client.validate_expectations(&document, &expect_jsonpath)? {
+                    report(format!("    {}", result));
+                }
+            }
+            _ => {
+                for result in client.validate_expectations(&document, &expect_jsonpath)? {
+                    report(format!("  {}", result));
+                }
+            }
+        }
+    }
+
+    let actual_status = failure
+        .as_ref()
+        .map_or(Code::Ok, |failure| failure.status.code());
+
+    if let Some(save_path) = save {
+        let sent = GrpcCall { data, ..grpc_call };
+        let saved_path = save_suite(&save_path, &sent.to_suite(&document, actual_status))?;
+        let report = |line: String| {
+            if json {
+                eprintln!("{}", line);
+            } else {
+                println!("{}", line);
+            }
+        };
+        report(format!(
+            "\n{} Saved call to {}",
+            "✓".green(),
+            saved_path.display()
+        ));
+        if sent.has_secret_metadata() {
+            report(format!(
+                "  {} authorization was saved as a placeholder; set AUTHORIZATION before running it",
+                "note:".dimmed()
+            ));
+        }
+    }
+
+    if actual_status != expected_status {
+        return Err(match failure {
+            Some(failure) if expect_status.is_none() => failure.into(),
+            _ => anyhow!(
+                "Expected status {} but got {}",
+                code_name(expected_status),
+                code_name(actual_status)
+            ),
+        });
+    }
 
-    // Display response
-    println!("\n{} Response:", "✓".green().bold());
+    match failure {
+        Some(_) => progress!(
+            "\n{} gRPC call failed with the expected status {}",
+            "✓".green().bold(),
+            code_name(actual_status)
+        ),
+        None => progress!("\n{} gRPC call completed", "✓".green().bold()),
+    }
+
+    Ok(())
+}
+
+/// Print a failed call's status and decoded error details.
+fn print_status_error(failure: &StatusError) {
+    let code = failure.status.code();
+    println!("\n{} gRPC call failed", "✗".red().bold());
     println!(
-        "{}",
-        serde_json::to_string_pretty(&response)?.bright_white()
+        "  Status:  {} ({})",
+        code_name(code).red().bold(),
+        code as i32
     );
+    println!("  Message: {}", failure.status.message());
+    if failure.details.is_empty() {
+        return;
+    }
 
-    // Validate expectations
-    if !expect_jsonpath.is_empty() {
-        println!("\n{} Validating expectations:", "→".cyan());
-        let validation_results = client.validate_expectations(&response, &expect_jsonpath)?;
-        for result in validation_results {
-            println!("  {}", result);
+    println!("  Details:");
+    for detail in &failure.details {
+        let type_url = detail["@type"].as_str().unwrap_or_default();
+        let type_name = type_url.rsplit('/').next().unwrap_or(type_url);
+        println!("    {}", type_name.bright_white());
+        match type_name {
+            "google.rpc.BadRequest" => {
+                for violation in detail["fieldViolations"].as_array().into_iter().flatten() {
+                    println!(
+                        "      {}: {}",
+                        violation["field"].as_str().unwrap_or_default().yellow(),
+                        violation["description"].as_str().unwrap_or_default()
+                    );
+                }
+            }
+            "google.rpc.RetryInfo" => {
+                if let Some(delay) = detail["retryDelay"].as_str() {
+                    println!("      retry after {}", delay);
+                }
+            }
+            _ => {
+                let mut fields = detail.clone();
+                if let Some(fields) = fields.as_object_mut() {
+                    fields.remove("@type");
+                }
+                println!("      {}", fields);
+            }
+        }
+    }
+}
+
+/// `rivet grpc --list`: print every service and method in the protos,
+/// without connecting to a server.
+pub fn handle_grpc_list(proto: PathBuf, use_protoc: bool, output: &str) -> Result<()> {
+    let output: OutputFormat = output.parse()?;
+    let schema = ProtoSchema::load(&proto, use_protoc)?;
+    let services = schema.services();
+
+    if output == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct ServiceList<'a> {
+            services: &'a [ServiceSummary],
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&ServiceList {
+                services: &services
+            })?
+        );
+        return Ok(());
+    }
+
+    if services.is_empty() {
+        println!("No services found in {}", proto.display());
+        return Ok(());
+    }
+
+    for service in &services {
+        println!("{}", service.name.bright_green());
+        for method in &service.methods {
+            println!("  {}", method_signature(method));
+        }
+    }
+
+    Ok(())
+}
+
+/// `rivet grpc --describe Service/Method`: print the request and response
+/// messages of one method, without connecting to a server.
+pub fn handle_grpc_describe(
+    proto: PathBuf,
+    service_method: &str,
+    use_protoc: bool,
+    output: &str,
+) -> Result<()> {
+    let output: OutputFormat = output.parse()?;
+    let schema = ProtoSchema::load(&proto, use_protoc)?;
+    let description = schema.describe(service_method)?;
+
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&description)?);
+        return Ok(());
+    }
+
+    println!("{}", description.service.bright_green());
+    println!("  {}", method_signature(&description.method));
+
+    for message in &description.messages {
+        println!("\nmessage {} {{", message.name.bright_white());
+        for field in &message.fields {
+            let label = if field.repeated { "repeated " } else { "" };
+            println!(
+                "  {}{} {} = {};",
+                label,
+                field.type_name.cyan(),
+                field.name,
+                field.number
+            );
         }
+        println!("}}");
     }
 
-    println!("\n{} gRPC call completed", "✓".green().bold());
+    for enumeration in &description.enums {
+        println!("\nenum {} {{", enumeration.name.bright_white());
+        for value in &enumeration.values {
+            println!("  {} = {};", value.name, value.number);
+        }
+        println!("}}");
+    }
 
     Ok(())
 }
+
+fn method_signature(method: &MethodSummary) -> String {
+    let stream = |streaming: bool| if streaming { "stream " } else { "" };
+    format!(
+        "rpc {}({}{}) returns ({}{})",
+        method.name,
+        stream(method.client_streaming),
+        method.input_type,
+        stream(method.server_streaming),
+        method.output_type
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call() -> GrpcCall {
+        GrpcCall {
+            server: "http://localhost:50051".to_string(),
+            proto: PathBuf::from("protos"),
+            call: "svc.Users/GetUser".to_string(),
+            data: Some(r#"{"id":"42"}"#.to_string()),
+            metadata: vec!["x-tenant: acme".to_string()],
+            expect_jsonpath: vec!["$.name".to_string()],
+            expect_status: Some(Code::NotFound),
+            timeout: "5s".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_saved_call_loads_back_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = save_suite(
+            &dir.path().join("get-user"),
+            &call().to_suite(&json!({"name": "Ada"}), Code::NotFound),
+        )
+        .unwrap();
+
+        assert!(path.ends_with("get-user.rivet.yaml"));
+        assert_eq!(GrpcCall::load(&path).unwrap(), call());
+        let saved: RivetConfig = serde_yaml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let expect = saved.tests[0].expect.as_ref().unwrap();
+        assert_eq!(expect.jsonpath.as_ref().unwrap()["$.name"], json!("Ada"));
+    }
+
+    #[test]
+    fn test_options_override_saved_call() {
+        let options = GrpcOptions {
+            server: None,
+            proto: None,
+            call: None,
+            data: Some(r#"{"id":"7"}"#.to_string()),
+            metadata: vec!["X-Tenant: globex".to_string()],
+            expect_jsonpath: Vec::new(),
+            expect_status: Some("OK".to_string()),
+            timeout: None,
+            from: None,
+            save: None,
+            use_protoc: false,
+            tls: GrpcTlsOptions::default(),
+            output: "human".to_string(),
+        };
+
+        let merged = GrpcCall::from_options(&options, Some(call())).unwrap();
+
+        assert_eq!(merged.data.as_deref(), Some(r#"{"id":"7"}"#));
+        assert_eq!(merged.metadata, vec!["X-Tenant: globex"]);
+        assert_eq!(merged.expect_status, Some(Code::Ok));
+        assert_eq!(merged.expect_jsonpath, call().expect_jsonpath);
+        assert_eq!(merged.timeout, "5s");
+        assert!(GrpcCall::from_options(&options, None).is_err());
+    }
+
+    #[test]
+    fn test_authorization_is_saved_as_placeholder() {
+        let call = GrpcCall {
+            metadata: vec!["authorization: Bearer s3cr3t".to_string()],
+            ..call()
+        };
+        let yaml = serde_yaml::to_string(&call.to_suite(&json!({}), Code::Ok)).unwrap();
+
+        assert!(!yaml.contains("s3cr3t"), "{}", yaml);
+        assert!(yaml.contains("${AUTHORIZATION}"), "{}", yaml);
+        assert!(call.has_secret_metadata());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::runner::parser::load_each_suite;
+use crate::runner::test_runner::TestFilter;
+use crate::style::Colorize;
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Default)]
+pub struct ListOptions {
+    /// List only tests whose `suite name :: test name` matches this regex
+    pub grep: Option<String>,
+    /// List the tests `grep` does *not* match instead
+    pub grep_invert: bool,
+    pub json: bool,
+}
+
+/// The suites and tests a run of the same target would find.
+#[derive(Debug, Serialize)]
+pub struct Listing {
+    /// By path
+    pub suites: Vec<ListedSuite>,
+    /// Files that didn't load, by path
+    pub failed: Vec<FailedSuite>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListedSuite {
+    /// Path relative to the listed directory, e.g. `users/smoke.rivet.yaml`
+    pub file: String,
+    pub path: PathBuf,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The suite's own `env`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    /// The `dataset.file` the tests run once per row of
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<String>,
+    /// Main tests, without setup, teardown and the steps around each test
+    pub tests: Vec<String>,
+    /// Problems that didn't stop the suite loading, e.g. a renamed step
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailedSuite {
+    pub file: String,
+    pub path: PathBuf,
+    pub error: String,
+}
+
+impl Listing {
+    pub fn test_count(&self) -> usize {
+        self.suites.iter().map(|suite| suite.tests.len()).sum()
+    }
+}
+
+/// Load the suites at `target` and list them with the tests that `--grep`
+/// selects; suites it leaves no test in are left out. A file that doesn't
+/// load is listed as failed rather than stopping the listing.
+pub async fn list_suites(target: &Path, options: &ListOptions) -> Result<Listing> {
+    let filter = options
+        .grep
+        .as_deref()
+        .map(TestFilter::new)
+        .transpose()?
+        .map(|filter| filter.inverted(options.grep_invert));
+
+    let mut listing = Listing {
+        suites: Vec::new(),
+        failed: Vec::new(),
+    };
+    for suite in load_each_suite(target, false).await? {
+        let suite = match suite {
+            Ok(suite) => suite,
+            Err(failure) => {
+                listing.failed.push(FailedSuite {
+                    file: failure.name,
+                    path: failure.path,
+                    error: format!("{:#}", failure.error),
+                });
+                continue;
+            }
+        };
+        let config = suite.config;
+        let tests: Vec<String> = config
+            .tests
+            .into_iter()
+            .map(|step| step.name)
+            .filter(|test| match &filter {
+                Some(filter) => filter.matches(&config.name, test),
+                None => true,
+            })
+            .collect();
+        if tests.is_empty() && filter.is_some() {
+            continue;
+        }
+        listing.suites.push(ListedSuite {
+            file: suite.name,
+            path: suite.path,
+            name: config.name,
+            description: config.description,
+            env: config.env,
+            dataset: config.dataset.map(|dataset| dataset.file),
+            tests,
+            warnings: suite.warnings,
+        });
+    }
+    listing.suites.sort_by(|a, b| a.file.cmp(&b.file));
+    listing.failed.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(listing)
+}
+
+/// `rivet list`: print the suites under `target` as a tree of files, or as
+/// JSON. Fails only when no suite file loads.
+pub async fn handle_list(target: PathBuf, options: ListOptions) -> Result<()> {
+    let listing = list_suites(&target, &options).await?;
+    for warning in listing.suites.iter().flat_map(|suite| &suite.warnings) {
+        eprintln!("{} {}", "⚠".yellow(), warning);
+    }
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&listing)?);
+    } else {
+        print_tree(&target, &listing);
+    }
+
+    if listing.suites.is_empty() && !listing.failed.is_empty() {
+        anyhow::bail!(
+            "None of the {} suite file(s) in {} loaded",
+            listing.failed.len(),
+            target.display()
+        );
+    }
+    Ok(())
+}
+
+fn print_tree(target: &Path, listing: &Listing) {
+    println!("{}", target.display().to_string().bright_white());
+
+    let mut dirs: Vec<&str> = Vec::new();
+    for suite in &listing.suites {
+        let mut parts: Vec<&str> = suite.file.split('/').collect();
+        let file = parts.pop().unwrap_or_default();
+        // Open the directories this file is in that the last one wasn't
+        let common = dirs.iter().zip(&parts).take_while(|(a, b)| a == b).count();
+        for (depth, dir) in parts.iter().enumerate().skip(common) {
+            println!("{}{}/", "  ".repeat(depth + 1), dir.bright_blue());
+        }
+        dirs = parts;
+
+        let indent = "  ".repeat(dirs.len() + 1);
+        let mut details = vec![match suite.tests.len() {
+            1 => "1 test".to_string(),
+            count => format!("{} tests", count),
+        }];
+        if let Some(env) = &suite.env {
+            details.push(format!("env {}", env));
+        }
+        if let Some(dataset) = &suite.dataset {
+            details.push(format!("dataset {}", dataset));
+        }
+        println!(
+            "{}{} {} {}",
+            indent,
+            file,
+            suite.name.bright_white(),
+            format!("({})", details.join(", ")).dimmed()
+        );
+        if let Some(description) = &suite.description {
+            println!("{}  {}", indent, description.dimmed());
+        }
+        for test in &suite.tests {
+            println!("{}  {} {}", indent, "-".cyan(), test);
+        }
+    }
+
+    if !listing.failed.is_empty() {
+        println!("\n{}", "Failed to load:".red().bold());
+        for failure in &listing.failed {
+            println!("  {}", failure.file.bright_white());
+            for line in failure.error.lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+
+    println!(
+        "\n{} suite(s), {} test(s){}",
+        listing.suites.len(),
+        listing.test_count(),
+        if listing.failed.is_empty() {
+            String::new()
+        } else {
+            format!("; {} file(s) failed to load", listing.failed.len())
+        }
+    );
+}
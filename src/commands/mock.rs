@@ -0,0 +1,105 @@
+use crate::mock::{MockApi, MockServer};
+use crate::runner::parser::load_single_file;
+use crate::style::Colorize;
+use crate::utils::parse_timeout;
+use anyhow::{anyhow, bail, Context, Result};
+use std::net::ToSocketAddrs;
+use std::path::PathBuf;
+
+pub struct MockOptions {
+    pub spec: Option<PathBuf>,
+    pub suite: Option<PathBuf>,
+    pub host: String,
+    pub port: u16,
+    pub latency: Option<String>,
+    pub chaos: Option<String>,
+    pub seed: Option<u64>,
+    pub verbose: bool,
+}
+
+/// Serve the spec's operations, or the suite's steps, until interrupted.
+pub async fn handle_mock(options: MockOptions) -> Result<()> {
+    let (api, source) = match (&options.spec, &options.suite) {
+        (Some(spec), _) => (
+            MockApi::from_spec(&crate::gen::load_spec(spec)?).await,
+            spec,
+        ),
+        (None, Some(suite)) => (
+            MockApi::from_suite(&load_single_file(suite, false).await?)?,
+            suite,
+        ),
+        (None, None) => bail!("Pass --spec or --suite to choose what to mock"),
+    };
+    if api.routes().is_empty() {
+        bail!("{} has no operations to mock", source.display());
+    }
+
+    let mut server = MockServer::new(api).with_verbose(options.verbose);
+    if let Some(latency) = &options.latency {
+        server = server.with_latency(
+            parse_timeout(latency).with_context(|| format!("Invalid --latency: {}", latency))?,
+        );
+    }
+    if let Some(chaos) = &options.chaos {
+        server = server.with_chaos(parse_chaos(chaos)?);
+    }
+    if let Some(seed) = options.seed {
+        server = server.with_seed(seed);
+    }
+
+    println!(
+        "{} Mocking {} route(s) from {}",
+        "→".cyan(),
+        server.routes().len(),
+        source.display().to_string().bright_white()
+    );
+    for route in server.routes() {
+        println!(
+            "  {:<7} {} {} {}",
+            route.method,
+            route.path,
+            "→".dimmed(),
+            route.status
+        );
+    }
+
+    let addr = (options.host.as_str(), options.port)
+        .to_socket_addrs()
+        .with_context(|| format!("Invalid --host: {}", options.host))?
+        .next()
+        .ok_or_else(|| anyhow!("No address for --host {}", options.host))?;
+    let (addr, handle) = server.serve(addr)?;
+    println!(
+        "{} Listening on {} (Ctrl-C to stop)",
+        "✓".green(),
+        format!("http://{}", addr).bright_white()
+    );
+    handle.await?;
+    Ok(())
+}
+
+/// A `--chaos` rate such as `5%` or `5`, as a percentage.
+fn parse_chaos(chaos: &str) -> Result<f64> {
+    let percent: f64 = chaos
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| anyhow!("Invalid --chaos: {} (expected a percentage like 5%)", chaos))?;
+    if !(0.0..=100.0).contains(&percent) {
+        bail!("Invalid --chaos: {} (must be between 0% and 100%)", chaos);
+    }
+    Ok(percent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chaos() {
+        assert_eq!(parse_chaos("5%").unwrap(), 5.0);
+        assert_eq!(parse_chaos("12.5").unwrap(), 12.5);
+        assert!(parse_chaos("150%").is_err());
+        assert!(parse_chaos("often").is_err());
+    }
+}
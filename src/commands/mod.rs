@@ -1,7 +1,14 @@
+pub mod config;
 pub mod coverage;
+pub mod exit;
 pub mod gen;
 pub mod grpc;
 pub mod import;
+pub mod list;
+pub mod mock;
 pub mod perf;
+pub mod record;
+pub mod report;
 pub mod run;
 pub mod send;
+pub mod validate;
@@ -1,10 +1,23 @@
-use anyhow::Result;
-use owo_colors::OwoColorize;
-use std::path::PathBuf;
+use crate::style::Colorize;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use crate::performance::{LoadPattern, PerformanceTestRunner};
-use crate::utils::parse_timeout;
+use crate::commands::config::load_user_config;
+use crate::commands::exit::ExitReason;
+use crate::config::{PerfConfig, TlsConfig};
+use crate::performance::baseline::{BaselineComparison, BaselineFigures};
+use crate::performance::connection::ConnectionOptions;
+use crate::performance::prometheus;
+use crate::performance::stress::StressLimits;
+use crate::performance::{LoadPattern, PerformanceTestRunner, Thresholds};
+use crate::utils::{
+    is_interactive, load_env_file, on_ctrl_c, parse_headers, parse_timeout, validate_base_url,
+    warn_insecure, ClientOptions,
+};
 
 pub struct PerfOptions {
     pub target: PathBuf,
@@ -13,12 +26,74 @@ pub struct PerfOptions {
     pub concurrent: u32,
     pub warmup: String,
     pub report_interval: String,
+    /// Report file, or a directory to write reports into
     pub output: Option<PathBuf>,
+    /// Comma-separated report formats; json when only `output` is set
+    pub report: Option<String>,
+    /// CSV or NDJSON file to append per-interval samples to
+    pub timeseries: Option<PathBuf>,
+    /// Dataset whose rows vary every suite's requests
+    pub data: Option<PathBuf>,
+    pub http1_only: bool,
+    pub http2_only: bool,
+    /// New connection per request
+    pub no_keepalive: bool,
+    pub pool_idle_timeout: Option<String>,
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Response body bytes kept per request; 0 discards bodies
+    pub max_capture_bytes: Option<usize>,
+    /// Live dashboard instead of progress reports
+    pub tui: bool,
+    /// Address to serve live metrics at `/metrics` on
+    pub prometheus_listen: Option<String>,
+    /// Pushgateway to push the final results to
+    pub pushgateway: Option<String>,
+    /// Labels exported metrics; generated when unset
+    pub run_id: Option<String>,
     pub pattern: String,
+    /// Step and stress patterns: RPS added per step
+    pub step_rps: u32,
+    pub step_duration: String,
+    /// Ramp pattern phases; unset ones are derived from the duration
+    pub ramp_up: Option<String>,
+    pub hold: Option<String>,
+    pub ramp_down: Option<String>,
+    /// Stress pattern: percent of a step's requests that may fail
+    pub stress_max_error_rate: f64,
+    pub stress_max_p99: Option<String>,
+    pub suite: Option<String>,
+    pub fail_p95: Option<String>,
+    pub fail_p99: Option<String>,
+    pub fail_avg: Option<String>,
+    pub min_rps: Option<f64>,
+    /// Percent; 95 unless set here or in the perf config
+    pub min_success_rate: Option<f64>,
+    pub count_assertion_failures: bool,
+    /// Saved JSON report to compare the run against
+    pub baseline: Option<PathBuf>,
+    /// Percent a latency percentile may grow over the baseline
+    pub fail_on_regression: Option<f64>,
+    /// Perf config file holding a `thresholds` block
+    pub config: Option<PathBuf>,
     pub env: Option<String>,
+    pub proxy: Option<String>,
+    pub no_proxy: bool,
+    pub env_file: Option<PathBuf>,
+    pub insecure: bool,
+    pub cacert: Option<PathBuf>,
+    pub base_url: Option<String>,
+    /// `--header "Name: value"`s, sent with every HTTP step
+    pub headers: Vec<String>,
 }
 
-pub async fn handle_perf(options: PerfOptions) -> Result<()> {
+/// Run the load test and say how it went; threshold and regression
+/// failures are [`ExitReason::TestFailures`], as failed tests are for `run`.
+pub async fn handle_perf(mut options: PerfOptions) -> Result<ExitReason> {
+    // Flags win over the config files
+    let user_config = load_user_config();
+    options.env = options.env.or(user_config.defaults.env);
+    options.base_url = options.base_url.or(user_config.defaults.base_url);
+
     println!("{} Starting performance test", "→".cyan());
     println!(
         "Target: {}",
@@ -35,25 +110,121 @@ pub async fn handle_perf(options: PerfOptions) -> Result<()> {
         options.concurrent.to_string().bright_white()
     );
     println!("Pattern: {}", options.pattern.bright_white());
+    if let Some(suite) = &options.suite {
+        println!("Suite: {}", suite.bright_white());
+    }
+    if let Some(timeseries) = &options.timeseries {
+        println!(
+            "Time series: {}",
+            timeseries.display().to_string().bright_white()
+        );
+    }
+    if let Some(data) = &options.data {
+        println!("Data: {}", data.display().to_string().bright_white());
+    }
+    let run_id = options
+        .run_id
+        .clone()
+        .unwrap_or_else(|| format!("perf-{}", Utc::now().format("%Y%m%d-%H%M%S")));
+    if options.prometheus_listen.is_some() || options.pushgateway.is_some() {
+        println!("Run ID: {}", run_id.bright_white());
+    }
+    if let Some(path) = load_env_file(options.env_file.as_deref())? {
+        println!("Env file: {}", path.display().to_string().bright_white());
+    }
+    if let Some(base_url) = &options.base_url {
+        validate_base_url(base_url)?;
+        println!("Base URL: {}", base_url.bright_white());
+    }
+    let headers = parse_headers(&options.headers)?;
 
     // Parse time strings to Durations
     let test_duration = parse_timeout(&options.duration)?;
     let warmup_duration = parse_timeout(&options.warmup)?;
     let report_interval = parse_timeout(&options.report_interval)?;
 
-    // Validate load pattern
-    let load_pattern = match options.pattern.as_str() {
-        "constant" => LoadPattern::Constant,
-        "ramp-up" => LoadPattern::RampUp,
-        "spike" => LoadPattern::Spike,
-        _ => {
-            anyhow::bail!(
-                "Invalid load pattern '{}'. Use: constant, ramp-up, spike",
-                options.pattern
-            );
-        }
+    // Resolve thresholds up front so a bad value fails before the run
+    let perf_config = options
+        .config
+        .as_deref()
+        .map(PerfConfig::load)
+        .transpose()?
+        .unwrap_or_default();
+    let duration_flag = |value: &Option<String>, flag: &str| {
+        value
+            .as_deref()
+            .map(|value| {
+                parse_timeout(value).with_context(|| format!("Invalid {}: {}", flag, value))
+            })
+            .transpose()
+    };
+    let thresholds = Thresholds {
+        max_p95: duration_flag(&options.fail_p95, "--fail-p95")?,
+        max_p99: duration_flag(&options.fail_p99, "--fail-p99")?,
+        max_avg: duration_flag(&options.fail_avg, "--fail-avg")?,
+        min_rps: options.min_rps,
+        min_success_rate: options.min_success_rate,
+        count_assertion_failures: options.count_assertion_failures,
+    }
+    .or_config(perf_config.thresholds.as_ref())?;
+
+    // Read the baseline before the run so a bad file fails fast
+    let baseline = options
+        .baseline
+        .as_deref()
+        .map(BaselineFigures::load)
+        .transpose()?;
+    if options.fail_on_regression.is_some_and(|pct| pct < 0.0) {
+        anyhow::bail!("--fail-on-regression must not be negative");
+    }
+
+    let prometheus_listen = options
+        .prometheus_listen
+        .as_deref()
+        .map(|addr| {
+            addr.parse::<SocketAddr>()
+                .with_context(|| format!("Invalid --prometheus-listen: {}", addr))
+        })
+        .transpose()?;
+
+    let load_pattern = load_pattern(&options, test_duration)?;
+    let reports = report_paths(options.output.as_deref(), options.report.as_deref())?;
+
+    let client_options = ClientOptions {
+        proxy: options.proxy.clone().or(user_config.proxy),
+        no_proxy: options.no_proxy,
+        tls: TlsConfig {
+            cacert: options.cacert.clone(),
+            ..Default::default()
+        },
+        insecure: options.insecure,
+    };
+    if options.insecure {
+        warn_insecure("--insecure");
+    }
+    let connection = ConnectionOptions {
+        http1_only: options.http1_only,
+        http2_only: options.http2_only,
+        no_keepalive: options.no_keepalive,
+        pool_idle_timeout: options
+            .pool_idle_timeout
+            .as_deref()
+            .map(|value| {
+                parse_timeout(value)
+                    .with_context(|| format!("Invalid --pool-idle-timeout: {}", value))
+            })
+            .transpose()?,
+        pool_max_idle_per_host: options.pool_max_idle_per_host,
     };
 
+    let tui = options.tui && is_interactive();
+    if options.tui && !tui {
+        println!(
+            "{} --tui needs a terminal, showing progress reports instead",
+            "⚠".yellow()
+        );
+    }
+
     // Create performance test runner
     let runner = PerformanceTestRunner::new(
         options.concurrent,
@@ -62,16 +233,39 @@ pub async fn handle_perf(options: PerfOptions) -> Result<()> {
         warmup_duration,
         report_interval,
         load_pattern,
-    )?;
+        &client_options,
+    )?
+    .with_connection(&connection)?
+    .with_max_capture_bytes(options.max_capture_bytes)
+    .with_base_url(options.base_url.clone())
+    .with_headers(headers)
+    .with_suite(options.suite.clone())
+    .with_timeseries(options.timeseries.clone())
+    .with_data(options.data.clone())
+    .with_tui(tui)
+    .with_prometheus_listen(prometheus_listen)
+    .with_run_id(run_id.clone());
+
+    // Ctrl-C stops the load and keeps the results gathered so far
+    let cancel = runner.cancellation_token();
+    on_ctrl_c(move || cancel.cancel());
 
     // Run performance test
-    let results = runner
+    let mut results = runner
         .run_performance_test(&options.target, options.env.as_deref())
         .await?;
 
     // Print final summary
     println!();
-    println!("{} Performance test completed", "✔".green().bold());
+    if results.interrupted {
+        println!(
+            "{} Performance test interrupted after {:.1}s; results are partial",
+            "⚠".yellow().bold(),
+            results.total_duration.as_secs_f64()
+        );
+    } else {
+        println!("{} Performance test completed", "✔".green().bold());
+    }
 
     let avg_response_time = results.average_response_time;
     let total_requests = results.total_requests;
@@ -91,6 +285,12 @@ pub async fn handle_perf(options: PerfOptions) -> Result<()> {
         avg_response_time.as_millis().to_string().bright_white()
     );
     println!("Actual RPS: {:.1}", actual_rps.to_string().bright_white());
+    if let Some(stress) = &results.stress {
+        println!(
+            "Max sustained RPS: {}",
+            format!("{:.0}", stress.max_rps).bright_white()
+        );
+    }
 
     if results.p99_response_time > Duration::from_millis(1000) {
         println!(
@@ -104,21 +304,236 @@ pub async fn handle_perf(options: PerfOptions) -> Result<()> {
         );
     }
 
-    // Save performance report if requested
-    if let Some(output_path) = options.output {
+    results.thresholds = thresholds.evaluate(&results);
+    println!();
+    println!("Thresholds:");
+    for threshold in &results.thresholds {
+        if threshold.passed {
+            println!("  {} {}", "✔".green(), threshold);
+        } else {
+            println!("  {} {}", "✘".red(), threshold);
+        }
+    }
+
+    if let (Some(path), Some(figures)) = (&options.baseline, &baseline) {
+        let comparison =
+            BaselineComparison::new(path, figures, &results, options.fail_on_regression);
+        print_comparison(&comparison);
+        results.comparison = Some(comparison);
+    }
+
+    // Save performance reports if requested
+    for (format, path) in &reports {
         println!();
-        println!("Saving performance report to: {}", output_path.display());
-        results.save_report(&output_path)?;
+        println!("Saving performance report to: {}", path.display());
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        match format.as_str() {
+            "html" => results.save_html_report(path)?,
+            _ => results.save_report(path)?,
+        }
         println!("{} Performance report saved", "✔".green());
     }
 
+    if let Some(gateway) = &options.pushgateway {
+        println!();
+        match prometheus::push_results(gateway, &run_id, &results).await {
+            Ok(()) => println!("{} Results pushed to {}", "✔".green(), gateway),
+            Err(e) => println!("{} Failed to push results: {:#}", "⚠".yellow(), e),
+        }
+    }
+
+    if results.interrupted {
+        eprintln!("Performance test interrupted");
+        return Ok(ExitReason::Interrupted);
+    }
+
     // Exit with error code if test failed performance criteria
-    if success_rate < 95.0 {
-        anyhow::bail!(
-            "Performance test failed: Success rate {:.1}% is below 95%",
-            success_rate
+    let violations: Vec<String> = results
+        .thresholds
+        .iter()
+        .filter(|threshold| !threshold.passed)
+        .map(|threshold| format!("  - {}", threshold))
+        .collect();
+    if !violations.is_empty() {
+        eprintln!(
+            "Performance test failed {} threshold(s):\n{}",
+            violations.len(),
+            violations.join("\n")
+        );
+        return Ok(ExitReason::TestFailures);
+    }
+
+    if let Some(comparison) = &results.comparison {
+        let regressions: Vec<String> = comparison
+            .regressions()
+            .map(|metric| format!("  - {}", metric))
+            .collect();
+        if !regressions.is_empty() {
+            eprintln!(
+                "Performance regressed more than {}% versus {}:\n{}",
+                comparison.max_regression.unwrap_or_default(),
+                comparison.baseline,
+                regressions.join("\n")
+            );
+            return Ok(ExitReason::TestFailures);
+        }
+    }
+
+    Ok(ExitReason::Passed)
+}
+
+/// Print the run next to the baseline, improvements in green and
+/// regressions in red.
+fn print_comparison(comparison: &BaselineComparison) {
+    println!();
+    println!("Compared with {}:", comparison.baseline.bright_white());
+    println!(
+        "  {:<12} {:>12} {:>12} {:>12}",
+        "Metric", "Baseline", "Current", "Change"
+    );
+    for metric in &comparison.metrics {
+        let change = format!("{:>12}", metric.change_text());
+        let change = if !metric.changed() {
+            change
+        } else if metric.worse {
+            change.red().to_string()
+        } else {
+            change.green().to_string()
+        };
+        let flag = if metric.regressed {
+            format!(" {}", "✘ regressed".red())
+        } else {
+            String::new()
+        };
+        println!(
+            "  {:<12} {:>12} {:>12} {}{}",
+            metric.name,
+            metric.value_text(metric.baseline),
+            metric.value_text(metric.current),
+            change,
+            flag
         );
     }
+}
+
+/// Parse `--pattern` and the options of the pattern it names.
+fn load_pattern(options: &PerfOptions, test_duration: Duration) -> Result<LoadPattern> {
+    let duration_flag = |value: &str, flag: &str| {
+        parse_timeout(value).with_context(|| format!("Invalid {}: {}", flag, value))
+    };
+    let steps = || -> Result<(u32, Duration)> {
+        if options.step_rps == 0 {
+            anyhow::bail!("--step-rps must be greater than 0");
+        }
+        let every = duration_flag(&options.step_duration, "--step-duration")?;
+        if every.is_zero() {
+            anyhow::bail!("--step-duration must be greater than 0");
+        }
+        Ok((options.step_rps, every))
+    };
+
+    let pattern = match options.pattern.as_str() {
+        "constant" => LoadPattern::Constant,
+        "ramp-up" => LoadPattern::RampUp,
+        "spike" => LoadPattern::Spike,
+        "step" => {
+            let (increment, every) = steps()?;
+            LoadPattern::Step { increment, every }
+        }
+        "ramp" => {
+            let phase = |value: &Option<String>, flag: &str| {
+                value
+                    .as_deref()
+                    .map(|value| duration_flag(value, flag))
+                    .transpose()
+            };
+            let up = phase(&options.ramp_up, "--ramp-up")?.unwrap_or(test_duration / 4);
+            let down = phase(&options.ramp_down, "--ramp-down")?.unwrap_or(test_duration / 4);
+            let hold = phase(&options.hold, "--hold")?
+                .unwrap_or_else(|| test_duration.saturating_sub(up + down));
+            if up + hold + down > test_duration {
+                anyhow::bail!(
+                    "Ramp profile ({:?} up, {:?} hold, {:?} down) is longer than --duration {:?}",
+                    up,
+                    hold,
+                    down,
+                    test_duration
+                );
+            }
+            LoadPattern::Ramp { up, hold, down }
+        }
+        "stress" => {
+            let (increment, every) = steps()?;
+            let max_p99 = options
+                .stress_max_p99
+                .as_deref()
+                .map(|value| duration_flag(value, "--stress-max-p99"))
+                .transpose()?;
+            LoadPattern::Stress {
+                increment,
+                every,
+                limits: StressLimits {
+                    max_error_rate: options.stress_max_error_rate,
+                    max_p99,
+                },
+            }
+        }
+        _ => {
+            anyhow::bail!(
+                "Invalid load pattern '{}'. Use: constant, ramp-up, spike, step, ramp, stress",
+                options.pattern
+            );
+        }
+    };
+    Ok(pattern)
+}
+
+/// Where each requested report goes. Reports are written into `output` when
+/// it is a directory (or ends with `/`), or the current directory when only
+/// formats are given, as `perf_report_<timestamp>.<ext>`. A file `output`
+/// takes a single format as is; with several, each gets its own extension.
+fn report_paths(output: Option<&Path>, formats: Option<&str>) -> Result<Vec<(String, PathBuf)>> {
+    let formats: Vec<String> = match formats {
+        Some(formats) => formats
+            .split(',')
+            .map(|format| format.trim().to_lowercase())
+            .filter(|format| !format.is_empty())
+            .collect(),
+        None if output.is_some() => vec!["json".to_string()],
+        None => return Ok(Vec::new()),
+    };
+    for format in &formats {
+        if format != "json" && format != "html" {
+            anyhow::bail!("Invalid report format '{}'. Use: json, html", format);
+        }
+    }
 
-    Ok(())
+    let is_dir = |path: &Path| path.is_dir() || path.to_string_lossy().ends_with('/');
+    let single = formats.len() == 1;
+    let paths = formats
+        .into_iter()
+        .map(|format| {
+            let path = match output {
+                Some(path) if !is_dir(path) => {
+                    if single {
+                        path.to_path_buf()
+                    } else {
+                        path.with_extension(&format)
+                    }
+                }
+                dir => dir.unwrap_or(Path::new(".")).join(format!(
+                    "perf_report_{}.{}",
+                    Utc::now().format("%Y%m%d_%H%M%S"),
+                    format
+                )),
+            };
+            (format, path)
+        })
+        .collect();
+    Ok(paths)
 }
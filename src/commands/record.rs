@@ -0,0 +1,56 @@
+use crate::record::{serve, Recorder};
+use crate::style::Colorize;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub struct RecordOptions {
+    pub listen: SocketAddr,
+    pub target: String,
+    pub out: PathBuf,
+    pub filter_path: Option<String>,
+}
+
+/// Proxy to the target, saving tests as requests come in, until Ctrl-C;
+/// then write the main `rivet.yaml`.
+pub async fn handle_record(options: RecordOptions) -> Result<()> {
+    let mut recorder = Recorder::new(&options.target, &options.out)?;
+    if let Some(filter) = &options.filter_path {
+        recorder = recorder.with_filter(
+            Regex::new(filter).with_context(|| format!("Invalid --filter-path: {}", filter))?,
+        );
+    }
+    let recorder = Arc::new(recorder);
+
+    let shutdown = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    let (addr, handle) = serve(recorder.clone(), options.listen, shutdown)?;
+    println!(
+        "{} Recording {} into {}",
+        "→".cyan(),
+        recorder.target().bright_white(),
+        options.out.display()
+    );
+    println!(
+        "{} Proxy listening on {} (Ctrl-C to stop and save)",
+        "✓".green(),
+        format!("http://{}", addr).bright_white()
+    );
+    handle.await?;
+
+    let (config_path, recorded, repeats) = recorder.finish()?;
+    println!("\n{} Recording stopped", "✓".green().bold());
+    println!(
+        "  {} test(s) recorded, {} repeat request(s) skipped",
+        recorded.to_string().bright_white(),
+        repeats.to_string().bright_white()
+    );
+    println!(
+        "  Main config: {}",
+        config_path.display().to_string().bright_blue()
+    );
+    Ok(())
+}
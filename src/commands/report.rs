@@ -0,0 +1,126 @@
+use crate::style::Colorize;
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::commands::config::load_user_config;
+use crate::report::{ReportGenerator, TestReport};
+
+pub struct MergeOptions {
+    pub files: Vec<PathBuf>,
+    pub format: String,
+    pub out: PathBuf,
+    pub template: Option<String>,
+    pub name: Option<String>,
+}
+
+pub async fn handle_merge(options: MergeOptions) -> Result<()> {
+    let mut reports = Vec::new();
+    let sources = source_labels(&options.files);
+    for (file, source) in options.files.iter().zip(sources) {
+        let content = fs::read_to_string(file)
+            .map_err(|e| anyhow!("Failed to read report {}: {}", file.display(), e))?;
+        let report: TestReport = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse report {}: {}", file.display(), e))?;
+        reports.push((source, report));
+    }
+
+    let merged = ReportGenerator::merge_reports(reports)?;
+
+    let user_config = load_user_config();
+    let template = options
+        .template
+        .as_deref()
+        .unwrap_or(&user_config.reports.default_template);
+
+    for format in ReportGenerator::unknown_formats(&options.format) {
+        eprintln!("{} Unknown report format '{}'", "⚠".yellow(), format);
+    }
+    let generated_files = ReportGenerator::write_reports(
+        &merged,
+        &options.format,
+        &options.out,
+        template,
+        options.name.as_deref(),
+        &[],
+    )?;
+
+    println!(
+        "Merged {} reports: {} suites, {} tests ({} passed, {} failed)",
+        options.files.len(),
+        merged.suites.len(),
+        merged.summary.total,
+        merged.summary.passed.to_string().green(),
+        merged.summary.failed.to_string().red()
+    );
+    println!("Reports generated:");
+    for file in &generated_files {
+        println!("  📊 {}", file.display());
+    }
+
+    Ok(())
+}
+
+/// Name each report after the shortest trailing part of its path that no
+/// other report's path ends with, e.g. `linux/report.json` and
+/// `macos/report.json`, so suites that share a name stay told apart.
+fn source_labels(files: &[PathBuf]) -> Vec<String> {
+    let components: Vec<Vec<String>> = files
+        .iter()
+        .map(|file| {
+            file.components()
+                .map(|part| part.as_os_str().to_string_lossy().to_string())
+                .collect()
+        })
+        .collect();
+    let suffix = |parts: &[String], len: usize| parts[parts.len().saturating_sub(len)..].to_vec();
+
+    files
+        .iter()
+        .zip(&components)
+        .map(|(file, parts)| {
+            (1..=parts.len())
+                .find(|&len| {
+                    let own = suffix(parts, len);
+                    components
+                        .iter()
+                        .filter(|other| suffix(other, len) == own)
+                        .count()
+                        == 1
+                })
+                .map(|len| label(&suffix(parts, len)))
+                .unwrap_or_else(|| file.display().to_string())
+        })
+        .collect()
+}
+
+fn label(parts: &[String]) -> String {
+    parts.iter().collect::<PathBuf>().display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(files: &[&str]) -> Vec<String> {
+        let files: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+        source_labels(&files)
+    }
+
+    #[test]
+    fn test_source_labels_use_shortest_unique_suffix() {
+        assert_eq!(
+            labels(&["ci/linux/report.json", "ci/macos/report.json", "smoke.json"]),
+            ["linux/report.json", "macos/report.json", "smoke.json"]
+        );
+        assert_eq!(
+            labels(&["a/x/report.json", "b/x/report.json"]),
+            ["a/x/report.json", "b/x/report.json"]
+        );
+        // The same file twice can't be told apart by any suffix
+        assert_eq!(
+            labels(&["out/report.json", "out/report.json"]),
+            ["out/report.json", "out/report.json"]
+        );
+    }
+}
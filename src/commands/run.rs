@@ -1,10 +1,18 @@
-use crate::config::UserConfig;
-use crate::report::ReportGenerator;
-use crate::runner::TestRunner;
-use anyhow::Result;
-use owo_colors::OwoColorize;
+use crate::commands::config::load_user_config;
+use crate::commands::exit::ExitReason;
+use crate::config::{FlagDefaults, TlsConfig};
+use crate::report::{check_report_name, BodyInclusion};
+use crate::run::{self, run_suites, ConsoleReporter, JsonReporter, ReportOptions, Reporter};
+use crate::runner::data::DataSelection;
+use crate::runner::events::{duration_ms, OutputFormat};
+use crate::runner::order::TestOrder;
+use crate::utils::{on_ctrl_c, parse_headers, warn_insecure, ClientOptions};
+use anyhow::{Context, Result};
+use serde::Serialize;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio_util::sync::CancellationToken;
 
 fn open_in_browser(file_path: &Path) -> Result<()> {
     let path = file_path.canonicalize()?;
@@ -28,25 +36,134 @@ pub struct RunOptions {
     pub target: PathBuf,
     pub env: Option<String>,
     pub _data: Option<PathBuf>,
-    pub parallel: usize,
+    pub parallel_suites: Option<usize>,
+    pub parallel_requests: Option<usize>,
     pub grep: Option<String>,
+    pub grep_invert: bool,
     pub bail: bool,
+    pub max_failures: Option<usize>,
     pub report: Option<String>,
     pub template: Option<String>,
+    pub report_dir: Option<PathBuf>,
+    pub report_name: Option<String>,
+    pub report_include_bodies: String,
+    pub history: usize,
     pub open: bool,
     pub no_open: bool,
     pub ci: bool,
+    pub proxy: Option<String>,
+    pub no_proxy: bool,
+    pub env_file: Option<PathBuf>,
+    pub tls: TlsConfig,
+    pub insecure: bool,
+    pub base_url: Option<String>,
+    /// `--header "Name: value"`s, sent with every HTTP step
+    pub headers: Vec<String>,
+    /// `--identify`: send `X-Rivet-Test` with every HTTP step
+    pub identify: bool,
+    pub data_filter: Option<String>,
+    pub data_sample: Option<String>,
+    pub seed: Option<u64>,
+    pub shuffle: bool,
+    /// `--order`: declared, file or name
+    pub order: String,
+    pub secrets: Vec<String>,
+    pub output: String,
+    /// `-v` count: print each step's exchange, whole bodies from 2
+    pub verbose: u8,
+    /// NDJSON file every step's exchange is written to
+    pub log_file: Option<PathBuf>,
+    /// Response body bytes kept per step, over any suite's setting
+    pub max_capture_bytes: Option<usize>,
+    /// Fail suites that repeat a step name instead of renaming the repeats
+    pub strict: bool,
+    /// Where to write the [`RunSummary`] as JSON; `-` is stdout
+    pub summary_json: Option<PathBuf>,
+    /// The command line, recorded in report metadata with secrets masked
+    pub args: Vec<String>,
 }
 
-pub async fn handle_run(options: RunOptions) -> Result<()> {
-    // Load user config
-    let user_config = UserConfig::load().unwrap_or_default();
+/// What `--summary-json` writes: how the run ended and its counts.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub exit_code: i32,
+    pub reason: ExitReason,
+    /// Why the run couldn't start, for usage and infrastructure errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub duration_ms: u64,
+    pub reports: Vec<String>,
+}
+
+impl RunSummary {
+    fn new(reason: ExitReason) -> Self {
+        Self {
+            exit_code: reason.code(),
+            reason,
+            error: None,
+            run_id: None,
+            total: 0,
+            passed: 0,
+            failed: 0,
+            skipped: 0,
+            duration_ms: 0,
+            reports: Vec::new(),
+        }
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        if path == Path::new("-") {
+            println!("{}", json);
+            return Ok(());
+        }
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write summary to {}", path.display()))
+    }
+}
 
-    // Determine final template to use (CLI overrides config)
-    let final_template = options
-        .template
-        .as_ref()
-        .unwrap_or(&user_config.reports.default_template);
+impl RunOptions {
+    /// Fill flags left unset from the config files.
+    fn with_config_defaults(mut self, defaults: &FlagDefaults) -> Self {
+        self.env = self.env.or_else(|| defaults.env.clone());
+        self.base_url = self.base_url.or_else(|| defaults.base_url.clone());
+        self.parallel_suites = self.parallel_suites.or(defaults.parallel);
+        self.report = self
+            .report
+            .or_else(|| defaults.report.as_ref().map(|formats| formats.join(",")));
+        self.secrets.extend(defaults.secrets.iter().cloned());
+        self
+    }
+}
+
+/// Run the suites and say how the run went; `main` turns that into the exit
+/// code. With `--summary-json` the summary is written whichever way it ends.
+pub async fn handle_run(options: RunOptions) -> Result<ExitReason> {
+    let summary_json = options.summary_json.clone();
+    let outcome = run(options).await;
+    if let Some(path) = summary_json {
+        let summary = match &outcome {
+            Ok(summary) => summary.clone(),
+            Err(e) => RunSummary {
+                error: Some(format!("{:#}", e)),
+                ..RunSummary::new(ExitReason::for_error(e))
+            },
+        };
+        summary.write(&path)?;
+    }
+    outcome.map(|summary| summary.reason)
+}
+
+async fn run(options: RunOptions) -> Result<RunSummary> {
+    // Load user config, with the project's merged over it
+    let user_config = load_user_config();
+    let options = options.with_config_defaults(&user_config.defaults);
 
     // Determine final auto-open behavior (CLI flags override config)
     let should_open = if options.open {
@@ -57,106 +174,136 @@ pub async fn handle_run(options: RunOptions) -> Result<()> {
         user_config.reports.auto_open_browser // Use config default
     };
 
-    println!("Running tests from: {}", options.target.display());
-    println!(
-        "Environment: {}",
-        options.env.as_deref().unwrap_or("default")
-    );
-    println!("Parallel workers: {}", options.parallel);
+    let include_bodies: BodyInclusion = options.report_include_bodies.parse()?;
+    let output: OutputFormat = options.output.parse()?;
+    let order: TestOrder = options.order.parse()?;
+    if options.max_failures == Some(0) {
+        anyhow::bail!("--max-failures must be at least 1");
+    }
+    let headers = parse_headers(&options.headers)?;
+
+    // Without --seed, pick one and print it with the sample or shuffle so the
+    // run can be repeated
+    let seed = options.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default()
+    });
+    let order = if options.shuffle {
+        TestOrder::Shuffle { seed }
+    } else {
+        order
+    };
 
-    if let Some(pattern) = &options.grep {
-        println!("Filter pattern: {}", pattern);
+    // CLI proxy overrides config; the client rejects bad URLs before any test runs
+    let client = ClientOptions {
+        proxy: options.proxy.clone().or_else(|| user_config.proxy.clone()),
+        no_proxy: options.no_proxy,
+        tls: options.tls.clone(),
+        insecure: options.insecure,
+    };
+    if options.insecure {
+        warn_insecure("--insecure");
     }
 
-    // Create test runner
-    let timeout = Duration::from_secs(30); // Default timeout
-    let runner = TestRunner::new(
-        timeout,
-        options.parallel,
-        options.bail,
-        options.grep,
-        options.ci,
-    )?;
-
-    // Run tests
-    let results = runner
-        .run_tests(&options.target, options.env.as_deref())
-        .await?;
-
-    // Generate reports if requested
-    if let Some(report_formats) = &options.report {
-        let reports_dir = PathBuf::from("./reports");
-        match ReportGenerator::generate_reports(
-            &results,
-            report_formats,
-            &reports_dir,
-            final_template,
-        ) {
-            Ok(generated_files) => {
-                println!();
-                println!("Reports generated:");
-                for file in &generated_files {
-                    println!("  📊 {}", file.display());
-
-                    // Auto-open HTML reports based on config/flags
-                    if should_open && file.extension().is_some_and(|ext| ext == "html") {
-                        if let Err(e) = open_in_browser(file) {
-                            eprintln!("Warning: Failed to open browser: {}", e);
-                        } else {
-                            println!("  🌐 Opened in browser");
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Warning: Failed to generate reports: {}", e);
-            }
-        }
+    // CLI flags override config, which overrides the ./reports default
+    let reports = options.report.clone().map(|formats| ReportOptions {
+        formats,
+        dir: options
+            .report_dir
+            .clone()
+            .or_else(|| user_config.reports.report_dir.clone())
+            .unwrap_or_else(|| PathBuf::from("./reports")),
+        template: options
+            .template
+            .clone()
+            .unwrap_or_else(|| user_config.reports.default_template.clone()),
+        name: options
+            .report_name
+            .clone()
+            .or_else(|| user_config.reports.report_name.clone()),
+        history: options.history,
+        include_bodies,
+        args: options.args.clone(),
+    });
+    if let Some(name) = options
+        .report_name
+        .as_deref()
+        .or(user_config.reports.report_name.as_deref())
+    {
+        check_report_name(name)?;
     }
 
-    // Print overall summary
-    let total_passed: usize = results.iter().map(|r| r.passed).sum();
-    let total_failed: usize = results.iter().map(|r| r.failed).sum();
-    let total_tests = total_passed + total_failed;
-    let total_duration: Duration = results.iter().map(|r| r.duration).sum();
-
-    println!();
-    if total_failed == 0 {
-        if options.ci {
-            println!("PASS {} tests in {:?}", total_tests, total_duration);
-        } else {
-            println!(
-                "{} {} tests passed in {:?}",
-                "✔".green().bold(),
-                total_tests,
-                total_duration
-            );
-
-            if total_tests > 0 {
-                // Add some celebration for successful runs (only in interactive mode)
-                println!("      .       .  *     .     *");
-                println!("   *    .   *   .  *      .        *");
+    let reporter: Arc<dyn Reporter> = match output {
+        OutputFormat::Human => Arc::new(ConsoleReporter::new(options.ci)),
+        OutputFormat::Json => Arc::new(JsonReporter),
+    };
+
+    // Ctrl-C skips what is left and still reports the tests that ran
+    let interrupt = CancellationToken::new();
+    let token = interrupt.clone();
+    on_ctrl_c(move || token.cancel());
+
+    let report = run_suites(run::RunOptions {
+        target: options.target,
+        env: options.env,
+        parallel_suites: options.parallel_suites.unwrap_or(1),
+        parallel_requests: options.parallel_requests,
+        grep: options.grep,
+        grep_invert: options.grep_invert,
+        max_failures: options.max_failures.or(options.bail.then_some(1)),
+        client,
+        base_url: options.base_url,
+        headers,
+        identify: options.identify,
+        data_selection: DataSelection {
+            filter: options.data_filter,
+            sample: options.data_sample,
+            seed,
+        },
+        order,
+        secrets: options.secrets,
+        verbose: options.verbose,
+        log_file: options.log_file,
+        // ./.env is picked up when there is one
+        env_file: options
+            .env_file
+            .or_else(|| Some(PathBuf::from(".env")).filter(|path| path.is_file())),
+        max_capture_bytes: options.max_capture_bytes,
+        strict: options.strict,
+        reports,
+        reporter,
+        interrupt,
+        ..run::RunOptions::default()
+    })
+    .await?;
+
+    // Auto-open HTML reports based on config/flags
+    if should_open && output == OutputFormat::Human {
+        for file in &report.reports {
+            if file.extension().is_some_and(|ext| ext == "html") {
+                if let Err(e) = open_in_browser(file) {
+                    eprintln!("Warning: Failed to open browser: {}", e);
+                } else {
+                    println!("  🌐 Opened {} in browser", file.display());
+                }
             }
         }
-    } else {
-        if options.ci {
-            println!(
-                "FAIL {} passed, {} failed in {:?}",
-                total_passed, total_failed, total_duration
-            );
-        } else {
-            println!(
-                "{} {} passed, {} failed in {:?}",
-                "✖".red().bold(),
-                total_passed,
-                total_failed,
-                total_duration
-            );
-        }
-
-        // Exit with error code if any tests failed
-        std::process::exit(1);
     }
 
-    Ok(())
+    Ok(RunSummary {
+        run_id: report.run_id.clone(),
+        total: report.total,
+        passed: report.passed,
+        failed: report.failed,
+        skipped: report.skipped,
+        duration_ms: duration_ms(report.duration),
+        reports: report
+            .reports
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect(),
+        ..RunSummary::new(report.exit_reason())
+    })
 }
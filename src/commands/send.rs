@@ -1,119 +1,897 @@
-use anyhow::Result;
-use owo_colors::OwoColorize;
+use crate::style::Colorize;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::StreamExt;
+use reqwest::multipart::{Form, Part};
+use reqwest::redirect::Policy;
 use reqwest::Client;
 use serde_json::Value;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
+use tokio::io::AsyncWriteExt;
+use url::Url;
 
+use crate::commands::config::load_user_config;
+use crate::config::{
+    Compression, Expectation, Request, RivetConfig, StatusExpectation, TestStep, TlsConfig,
+};
+use crate::runner::compression::BodyDecoder;
 use crate::ui::{request_box, response_box};
-use crate::utils::{parse_headers, parse_timeout};
-
-pub async fn handle_send(
-    method: String,
-    url: String,
-    headers: Vec<String>,
-    data: Option<String>,
-    save: Option<PathBuf>,
-    insecure: bool,
-    timeout: String,
-) -> Result<()> {
-    let start = Instant::now();
+use crate::utils::{
+    is_interactive, is_rivet_header, load_env_file, parse_headers, parse_timeout, warn_insecure,
+    ClientOptions, USER_AGENT,
+};
+
+pub struct SendOptions {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<String>,
+    pub query: Vec<String>,
+    pub data: Option<String>,
+    pub json: Option<String>,
+    pub form: Vec<String>,
+    pub files: Vec<String>,
+    pub bearer: Option<String>,
+    pub basic: Option<String>,
+    pub api_key: Option<String>,
+    pub save: Option<PathBuf>,
+    pub insecure: bool,
+    pub timeout: String,
+    pub output: Option<PathBuf>,
+    pub force: bool,
+    pub verbose: bool,
+    pub max_redirects: usize,
+    pub follow_redirects: bool,
+    pub retry: usize,
+    pub retry_delay: String,
+    pub fail: bool,
+    pub headers_only: bool,
+    pub silent: bool,
+    pub raw: bool,
+    /// `--compression`: gzip, br, identity or none
+    pub compression: Option<String>,
+    pub proxy: Option<String>,
+    pub no_proxy: bool,
+    pub env_file: Option<PathBuf>,
+    pub tls: TlsConfig,
+}
+
+pub async fn handle_send(options: SendOptions) -> Result<()> {
+    let SendOptions {
+        method,
+        url,
+        mut headers,
+        query,
+        mut data,
+        json,
+        form,
+        files,
+        bearer,
+        basic,
+        api_key,
+        save,
+        insecure,
+        timeout,
+        output,
+        force,
+        verbose,
+        max_redirects,
+        follow_redirects,
+        retry,
+        retry_delay,
+        fail,
+        headers_only,
+        silent,
+        raw,
+        compression,
+        proxy,
+        no_proxy,
+        env_file,
+        tls,
+    } = options;
+
+    // Piped output gets the body verbatim and nothing else, so `| jq .` just works
+    let interactive = is_interactive();
+    let silent = silent || !interactive;
+    let raw = raw || !interactive;
+    let retry_delay = parse_timeout(&retry_delay)?;
+    let compression = compression
+        .as_deref()
+        .map(str::parse::<Compression>)
+        .transpose()?;
+
+    if let Some(path) = load_env_file(env_file.as_deref())? {
+        if !silent {
+            eprintln!("Loaded environment from {}", path.display());
+        }
+    }
+
+    // "-o -" keeps the default behaviour of printing to stdout
+    let output = output.filter(|path| path.as_os_str() != "-");
+    if let Some(path) = &output {
+        confirm_overwrite(path, force)?;
+    }
+
+    if let Some(json) = json {
+        data = Some(read_json_body(&json)?);
+        add_default_header(&mut headers, "Content-Type", "application/json");
+        add_default_header(&mut headers, "Accept", "application/json");
+    }
+
+    let query_params = query
+        .iter()
+        .map(|param| parse_key_value(param, "query parameter"))
+        .collect::<Result<Vec<_>>>()?;
+    let request_url = build_url(&url, &query_params)?;
+
+    let form_fields = form
+        .iter()
+        .map(|field| parse_key_value(field, "form field"))
+        .collect::<Result<Vec<_>>>()?;
+    let file_fields = files
+        .iter()
+        .map(|field| parse_file_field(field))
+        .collect::<Result<Vec<_>>>()?;
+
+    let credentials = auth_headers(
+        &headers,
+        bearer.as_deref(),
+        basic.as_deref(),
+        api_key.as_deref(),
+    )?;
 
     // Build HTTP client
-    let mut client_builder = Client::builder().timeout(parse_timeout(&timeout)?);
+    let client_options = ClientOptions {
+        proxy: proxy.or_else(|| load_user_config().proxy),
+        no_proxy,
+        tls,
+        insecure,
+    };
+    let mut client_builder =
+        client_options.configure(Client::builder().timeout(parse_timeout(&timeout)?))?;
 
-    if insecure {
-        client_builder = client_builder.danger_accept_invalid_certs(true);
+    if insecure && !silent {
+        warn_insecure("--insecure");
     }
 
+    // Record every hop so verbose mode can show the redirect chain
+    let redirects: Arc<Mutex<Vec<RedirectHop>>> = Arc::default();
+    let policy = if follow_redirects {
+        let redirects = Arc::clone(&redirects);
+        Policy::custom(move |attempt| {
+            if attempt.previous().len() > max_redirects {
+                return attempt.error(format!("too many redirects (max {})", max_redirects));
+            }
+            if let Some(from) = attempt.previous().last() {
+                redirects.lock().unwrap().push(RedirectHop {
+                    status: attempt.status().as_u16(),
+                    from: from.to_string(),
+                    location: attempt.url().to_string(),
+                });
+            }
+            attempt.follow()
+        })
+    } else {
+        Policy::none()
+    };
+    client_builder = client_builder.redirect(policy);
+
     let client = client_builder.build()?;
 
     // Parse headers
     let parsed_headers = parse_headers(&headers)?;
 
     // Build request
-    let mut request = client.request(method.parse()?, &url);
+    let mut request = client.request(method.parse()?, &request_url);
 
+    // rivet's User-Agent unless one is given; an empty one sends none
+    let given = |name: &str| {
+        parsed_headers
+            .keys()
+            .any(|key| key.eq_ignore_ascii_case(name))
+    };
+    let own_user_agent = given("User-Agent");
+    let own_accept_encoding = given("Accept-Encoding");
     for (key, value) in parsed_headers {
+        if value.is_empty() && is_rivet_header(&key) {
+            continue;
+        }
         request = request.header(key, value);
     }
+    if !own_user_agent {
+        request = request.header("User-Agent", USER_AGENT);
+    }
+    if let Some(compression) = compression.filter(|_| !own_accept_encoding) {
+        request = request.header("Accept-Encoding", compression.accept_encoding());
+    }
+
+    for credential in &credentials {
+        request = request.header(&credential.name, &credential.value);
+    }
 
     if let Some(ref body) = data {
         request = request.body(body.clone());
     }
 
+    // Any file turns the whole form into multipart; otherwise fields are URL-encoded
+    if !file_fields.is_empty() {
+        let mut multipart = Form::new();
+        for (key, value) in &form_fields {
+            multipart = multipart.text(key.clone(), value.clone());
+        }
+        for (field, path) in &file_fields {
+            multipart = multipart.part(field.clone(), file_part(path).await?);
+        }
+        request = request.multipart(multipart);
+    } else if !form_fields.is_empty() {
+        request = request.form(&form_fields);
+    }
+
     // Show request box
-    request_box::print_request_box(&method, &url, &headers);
+    let mut box_fields = form_fields.clone();
+    box_fields.extend(
+        file_fields
+            .iter()
+            .map(|(field, path)| (field.clone(), format!("@{}", path.display()))),
+    );
+    let mut box_headers = headers.clone();
+    box_headers.extend(
+        credentials
+            .iter()
+            .map(|credential| format!("{}: ****", credential.name)),
+    );
+    if !silent {
+        request_box::print_request_box(&method, &request_url, &box_headers, &box_fields);
+    }
+
+    // Saved requests carry form fields as a URL-encoded body and
+    // reference credentials through environment placeholders
+    let mut saved_headers = headers.clone();
+    saved_headers.extend(
+        credentials
+            .iter()
+            .map(|credential| format!("{}: {}", credential.name, credential.placeholder)),
+    );
+    let saved_body = data.clone().or_else(|| {
+        (!form_fields.is_empty()).then(|| {
+            add_default_header(
+                &mut saved_headers,
+                "Content-Type",
+                "application/x-www-form-urlencoded",
+            );
+            url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&form_fields)
+                .finish()
+        })
+    });
+
+    // Send request, retrying connection errors and 5xx responses
+    let request = request.build()?;
+    if verbose {
+        print_verbose_request(&request, &credentials);
+    }
+
+    let total_attempts = retry + 1;
+    let mut attempt = 1;
+    let mut pending = request;
+    let (response, start, duration) = loop {
+        // Streaming (multipart) bodies can't be cloned, so they're sent once
+        let next = if attempt < total_attempts {
+            pending.try_clone()
+        } else {
+            None
+        };
+
+        let start = Instant::now();
+        let result = client.execute(pending).await;
+        let duration = start.elapsed();
+
+        let retryable = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(_) => true,
+        };
+        match next {
+            Some(next) if retryable => {
+                let reason = match &result {
+                    Ok(response) => response.status().to_string(),
+                    Err(e) => e.to_string(),
+                };
+                eprintln!(
+                    "{} attempt {}/{} failed ({}); retrying in {:?}",
+                    "⟳".yellow(),
+                    attempt,
+                    total_attempts,
+                    reason,
+                    retry_delay
+                );
+                tokio::time::sleep(retry_delay).await;
+                attempt += 1;
+                pending = next;
+            }
+            _ => break (result?, start, duration),
+        }
+    };
 
-    // Send request
-    let response = request.send().await?;
-    let duration = start.elapsed();
+    if verbose {
+        print_verbose_response(&response, &redirects.lock().unwrap());
+    }
 
     // Get response info
     let status = response.status();
-    let status_text = format!(
+    let mut status_text = format!(
         "{} {}",
         status.as_u16(),
         status.canonical_reason().unwrap_or("")
     );
+    if attempt > 1 {
+        status_text.push_str(&format!(" • attempt {}/{}", attempt, total_attempts));
+    }
     let response_headers = response.headers().clone();
-    let body_bytes = response.bytes().await?;
-    let body_size = body_bytes.len();
-
-    // Show response box
-    response_box::print_response_box(&status_text, duration, body_size, &response_headers);
+    // --compression decodes what the server compressed, unless it is `none`
+    let encoding = compression
+        .filter(|compression| compression.decodes())
+        .and_then(|_| response_headers.get(reqwest::header::CONTENT_ENCODING))
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    // With --fail an error response goes to stderr and never to --output
+    let failed = fail && status.as_u16() >= 400;
 
-    // Pretty print JSON if possible
-    if let Ok(json_value) = serde_json::from_slice::<Value>(&body_bytes) {
-        let pretty_json = serde_json::to_string_pretty(&json_value)?;
-        for (i, line) in pretty_json.lines().enumerate() {
-            println!("{:>3}  {}", (i + 1).to_string().dimmed(), line);
+    if headers_only {
+        if verbose {
+            print_timings(duration, start.elapsed());
+        }
+        println!("{:?} {}", response.version(), status_text);
+        for (name, value) in &response_headers {
+            println!("{}: {}", name, String::from_utf8_lossy(value.as_bytes()));
         }
     } else {
-        // Print as text
-        if let Ok(text) = String::from_utf8(body_bytes.to_vec()) {
-            println!("{}", text);
-        } else {
-            println!("{}", "[Binary data]".dimmed());
+        match &output {
+            Some(path) if !failed => {
+                // Stream straight to disk so binary bodies aren't mangled or held in memory
+                let (written, received) =
+                    stream_to_file(response, path, encoding.as_deref()).await?;
+                if verbose {
+                    print_timings(duration, start.elapsed());
+                    print_body_size(encoding.as_deref(), written, received);
+                }
+                if !silent {
+                    response_box::print_response_box(
+                        &status_text,
+                        duration,
+                        written,
+                        received,
+                        &response_headers,
+                    );
+                }
+                notice(
+                    silent,
+                    format!(
+                        "{} Saved {} bytes to {}",
+                        "✓".green(),
+                        written,
+                        path.display()
+                    ),
+                );
+            }
+            _ => {
+                let received = response.bytes().await?;
+                let body_bytes = decode_body(encoding.as_deref(), &received)?;
+                if verbose {
+                    print_timings(duration, start.elapsed());
+                    print_body_size(encoding.as_deref(), body_bytes.len(), received.len());
+                }
+
+                if !silent {
+                    response_box::print_response_box(
+                        &status_text,
+                        duration,
+                        body_bytes.len(),
+                        received.len(),
+                        &response_headers,
+                    );
+                }
+                write_body(&body_bytes, raw, failed)?;
+            }
         }
     }
 
     // Save request file if requested
     if let Some(save_path) = save {
-        save_request_file(&save_path, &method, &url, &headers, &data).await?;
-        println!("\n{} Saved request to {}", "✓".green(), save_path.display());
+        let request = saved_request(
+            &method,
+            &url,
+            &query_params,
+            &saved_headers,
+            &saved_body,
+            compression,
+        );
+        let saved_path = save_request_file(&save_path, request, status.as_u16()).await?;
+        notice(
+            silent,
+            format!(
+                "\n{} Saved request to {}",
+                "✓".green(),
+                saved_path.display()
+            ),
+        );
+        print_credential_note(&credentials, silent);
+    }
+
+    if failed {
+        anyhow::bail!("Request failed with HTTP {}", status.as_u16());
+    }
+
+    Ok(())
+}
+
+/// Status messages go to stderr in silent mode so stdout carries only the body.
+fn notice(silent: bool, message: String) {
+    if silent {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// Write the response body to stdout (stderr for `--fail` errors), verbatim when `raw`.
+fn write_body(body: &[u8], raw: bool, to_stderr: bool) -> Result<()> {
+    if raw {
+        if to_stderr {
+            std::io::stderr().write_all(body)?;
+        } else {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(body)?;
+            stdout.flush()?;
+        }
+        return Ok(());
     }
 
+    let body = render_body(body)?;
+    if to_stderr {
+        eprintln!("{}", body);
+    } else {
+        println!("{}", body);
+    }
     Ok(())
 }
 
-async fn save_request_file(
-    path: &PathBuf,
+/// Pretty-print JSON with line numbers, fall back to plain text, and never dump binary.
+fn render_body(body: &[u8]) -> Result<String> {
+    if let Ok(json_value) = serde_json::from_slice::<Value>(body) {
+        let pretty_json = serde_json::to_string_pretty(&json_value)?;
+        Ok(pretty_json
+            .lines()
+            .enumerate()
+            .map(|(i, line)| format!("{:>3}  {}", (i + 1).to_string().dimmed(), line))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    } else if let Ok(text) = std::str::from_utf8(body) {
+        Ok(text.to_string())
+    } else {
+        Ok("[Binary data]".dimmed().to_string())
+    }
+}
+
+/// Load a `--json` argument (inline or `@file`) and make sure it parses.
+fn read_json_body(arg: &str) -> Result<String> {
+    let (body, source) = match arg.strip_prefix('@') {
+        Some(path) => (
+            std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read JSON body from {}: {}", path, e))?,
+            path.to_string(),
+        ),
+        None => (arg.to_string(), "--json".to_string()),
+    };
+
+    if let Err(e) = serde_json::from_str::<Value>(&body) {
+        // Show the offending line with a caret under the column serde reports
+        let line = body.lines().nth(e.line().saturating_sub(1)).unwrap_or("");
+        let caret = " ".repeat(e.column().saturating_sub(1));
+        anyhow::bail!(
+            "Invalid JSON in {}: {}\n  {}\n  {}^",
+            source,
+            e,
+            line,
+            caret
+        );
+    }
+
+    Ok(body)
+}
+
+/// One followed redirect: the response status at `from` and where it pointed.
+struct RedirectHop {
+    status: u16,
+    from: String,
+    location: String,
+}
+
+/// Headers whose values are never printed in full.
+fn is_secret_header(name: &str, credentials: &[AuthHeader]) -> bool {
+    crate::utils::is_secret_header(name)
+        || credentials
+            .iter()
+            .any(|credential| credential.name.eq_ignore_ascii_case(name))
+}
+
+/// Curl-style dump of the outgoing request on stderr, keeping stdout for the response.
+fn print_verbose_request(request: &reqwest::Request, credentials: &[AuthHeader]) {
+    let url = request.url();
+    eprintln!("{} {} {}", ">".dimmed(), request.method(), url);
+    if let Some(host) = url.host_str() {
+        match url.port() {
+            Some(port) => eprintln!("{} host: {}:{}", ">".dimmed(), host, port),
+            None => eprintln!("{} host: {}", ">".dimmed(), host),
+        }
+    }
+
+    for (name, value) in request.headers() {
+        let value = if is_secret_header(name.as_str(), credentials) {
+            "****".to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).to_string()
+        };
+        eprintln!("{} {}: {}", ">".dimmed(), name, value);
+    }
+    // reqwest adds this when the request doesn't set one
+    if !request.headers().contains_key(reqwest::header::ACCEPT) {
+        eprintln!("{} accept: */*", ">".dimmed());
+    }
+
+    match request.body() {
+        Some(body) => match body.as_bytes() {
+            Some(bytes) => {
+                eprintln!("{} content-length: {}", ">".dimmed(), bytes.len());
+                eprintln!("{}", ">".dimmed());
+                eprintln!("{}", String::from_utf8_lossy(bytes));
+            }
+            None => eprintln!("{} [streamed body]", ">".dimmed()),
+        },
+        None => eprintln!("{}", ">".dimmed()),
+    }
+}
+
+fn print_verbose_response(response: &reqwest::Response, redirects: &[RedirectHop]) {
+    for hop in redirects {
+        eprintln!(
+            "{} {} redirect from {} to {}",
+            "*".dimmed(),
+            hop.status,
+            hop.from,
+            hop.location
+        );
+    }
+    if let Some(addr) = response.remote_addr() {
+        eprintln!("{} Connected to {}", "*".dimmed(), addr);
+    }
+    if response.url().scheme() == "https" {
+        eprintln!("{} TLS connection (rustls)", "*".dimmed());
+    }
+    eprintln!(
+        "{} {:?} {}",
+        "<".dimmed(),
+        response.version(),
+        response.status()
+    );
+}
+
+fn print_timings(ttfb: std::time::Duration, total: std::time::Duration) {
+    eprintln!(
+        "{} Timing: TTFB {:.1}ms, download {:.1}ms, total {:.1}ms",
+        "*".dimmed(),
+        ttfb.as_secs_f64() * 1000.0,
+        total.saturating_sub(ttfb).as_secs_f64() * 1000.0,
+        total.as_secs_f64() * 1000.0
+    );
+}
+
+/// A header produced by one of the auth flags.
+struct AuthHeader {
+    name: String,
+    value: String,
+    /// What `--save` writes instead of the secret, e.g. `Bearer ${RIVET_TOKEN}`.
+    placeholder: String,
+    env_var: &'static str,
+}
+
+fn auth_headers(
+    headers: &[String],
+    bearer: Option<&str>,
+    basic: Option<&str>,
+    api_key: Option<&str>,
+) -> Result<Vec<AuthHeader>> {
+    let has_header = |name: &str| {
+        headers.iter().any(|header| {
+            header
+                .split_once(':')
+                .is_some_and(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        })
+    };
+
+    if (bearer.is_some() || basic.is_some()) && has_header("Authorization") {
+        anyhow::bail!("--bearer/--basic cannot be combined with an explicit Authorization header");
+    }
+
+    let mut credentials = Vec::new();
+
+    if let Some(token) = bearer {
+        let token = if token == "-" {
+            std::env::var("RIVET_TOKEN")
+                .map_err(|_| anyhow::anyhow!("--bearer - requires the RIVET_TOKEN env var"))?
+        } else {
+            token.to_string()
+        };
+        credentials.push(AuthHeader {
+            name: "Authorization".to_string(),
+            value: format!("Bearer {}", token),
+            placeholder: "Bearer ${RIVET_TOKEN}".to_string(),
+            env_var: "RIVET_TOKEN",
+        });
+    }
+
+    if let Some(user_pass) = basic {
+        if !user_pass.contains(':') {
+            anyhow::bail!("Invalid --basic value: expected user:pass");
+        }
+        credentials.push(AuthHeader {
+            name: "Authorization".to_string(),
+            value: format!("Basic {}", BASE64.encode(user_pass)),
+            placeholder: "Basic ${RIVET_BASIC_AUTH}".to_string(),
+            env_var: "RIVET_BASIC_AUTH",
+        });
+    }
+
+    if let Some(api_key) = api_key {
+        let (name, value) = api_key
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid --api-key value: expected HEADER=VALUE"))?;
+        if has_header(name) {
+            anyhow::bail!("--api-key conflicts with the explicit {} header", name);
+        }
+        credentials.push(AuthHeader {
+            name: name.trim().to_string(),
+            value: value.to_string(),
+            placeholder: "${RIVET_API_KEY}".to_string(),
+            env_var: "RIVET_API_KEY",
+        });
+    }
+
+    Ok(credentials)
+}
+
+fn print_credential_note(credentials: &[AuthHeader], silent: bool) {
+    for credential in credentials {
+        notice(
+            silent,
+            format!(
+                "  {} {} was saved as a placeholder; set {} before running it",
+                "note:".dimmed(),
+                credential.name,
+                credential.env_var
+            ),
+        );
+    }
+}
+
+/// Parse a `key=value` argument; only the first `=` separates, so values may contain more.
+fn parse_key_value(arg: &str, kind: &str) -> Result<(String, String)> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Invalid {} '{}': expected key=value", kind, arg))?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Append encoded query pairs to `url`, keeping any it already has.
+fn build_url(url: &str, params: &[(String, String)]) -> Result<String> {
+    if params.is_empty() {
+        return Ok(url.to_string());
+    }
+
+    let mut parsed =
+        Url::parse(url).map_err(|e| anyhow::anyhow!("Invalid URL '{}': {}", url, e))?;
+    parsed.query_pairs_mut().extend_pairs(params);
+    Ok(parsed.to_string())
+}
+
+/// Parse a `--file field=@path` argument.
+fn parse_file_field(field: &str) -> Result<(String, PathBuf)> {
+    let (name, path) = field
+        .split_once("=@")
+        .ok_or_else(|| anyhow::anyhow!("Invalid file field '{}': expected field=@path", field))?;
+    Ok((name.to_string(), PathBuf::from(path)))
+}
+
+async fn file_part(path: &Path) -> Result<Part> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+
+    Ok(Part::bytes(bytes)
+        .file_name(file_name)
+        .mime_str(mime.as_ref())?)
+}
+
+fn add_default_header(headers: &mut Vec<String>, name: &str, value: &str) {
+    let present = headers.iter().any(|header| {
+        header
+            .split_once(':')
+            .is_some_and(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+    });
+    if !present {
+        headers.push(format!("{}: {}", name, value));
+    }
+}
+
+/// Refuse to clobber an existing file unless `--force` is given or the user agrees on a TTY.
+fn confirm_overwrite(path: &Path, force: bool) -> Result<()> {
+    if force || !path.exists() {
+        return Ok(());
+    }
+
+    if atty::is(atty::Stream::Stdin) {
+        print!("{} already exists. Overwrite? [y/N] ", path.display());
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            return Ok(());
+        }
+        anyhow::bail!("Not overwriting {}", path.display());
+    }
+
+    anyhow::bail!(
+        "{} already exists (use --force to overwrite)",
+        path.display()
+    )
+}
+
+/// Returns the bytes written and the bytes received, which differ when the
+/// body is decoded by its `encoding`.
+async fn stream_to_file(
+    response: reqwest::Response,
+    path: &Path,
+    encoding: Option<&str>,
+) -> Result<(usize, usize)> {
+    let mut file = tokio::fs::File::create(path).await?;
+    let mut stream = response.bytes_stream();
+    let mut body = BodyDecoder::new(encoding, Vec::new());
+    let mut written = 0;
+
+    while let Some(chunk) = stream.next().await {
+        body.write_all(&chunk?)
+            .with_context(|| invalid_body(encoding))?;
+        let decoded = std::mem::take(body.get_mut());
+        file.write_all(&decoded).await?;
+        written += decoded.len();
+    }
+    let received = body.received() as usize;
+    let decoded = body.finish().with_context(|| invalid_body(encoding))?;
+    file.write_all(&decoded).await?;
+    written += decoded.len();
+    file.flush().await?;
+
+    Ok((written, received))
+}
+
+/// `body` decoded by its `encoding`; bodies in other encodings stay as sent.
+fn decode_body(encoding: Option<&str>, body: &[u8]) -> Result<Vec<u8>> {
+    let mut decoder = BodyDecoder::new(encoding, Vec::new());
+    decoder
+        .write_all(body)
+        .and_then(|_| decoder.finish())
+        .with_context(|| invalid_body(encoding))
+}
+
+fn invalid_body(encoding: Option<&str>) -> String {
+    format!("Invalid {} response body", encoding.unwrap_or_default())
+}
+
+/// With `--verbose`, how big a decoded body was on the wire.
+fn print_body_size(encoding: Option<&str>, decoded: usize, received: usize) {
+    if let Some(encoding) = encoding.filter(|_| decoded != received) {
+        eprintln!(
+            "{} Body: {} bytes {}-encoded, {} bytes decoded",
+            "*".dimmed(),
+            received,
+            encoding,
+            decoded
+        );
+    }
+}
+
+/// The request as it is saved, with `--header`s as a map.
+fn saved_request(
     method: &str,
     url: &str,
+    params: &[(String, String)],
     headers: &[String],
     data: &Option<String>,
-) -> Result<()> {
-    let mut request_yaml = format!("request:\n  method: {}\n  url: \"{}\"\n", method, url);
-
-    if !headers.is_empty() {
-        request_yaml.push_str("  headers:\n");
-        for header in headers {
-            let parts: Vec<&str> = header.splitn(2, ':').collect();
-            if parts.len() == 2 {
-                request_yaml.push_str(&format!(
-                    "    {}: \"{}\"\n",
-                    parts[0].trim(),
-                    parts[1].trim()
-                ));
-            }
-        }
+    compression: Option<Compression>,
+) -> Request {
+    let headers: HashMap<String, String> = headers
+        .iter()
+        .filter_map(|header| header.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let params: HashMap<String, String> = params.iter().cloned().collect();
+    Request {
+        compression,
+        follow_redirects: None,
+        method: method.to_uppercase(),
+        url: url.to_string(),
+        headers: (!headers.is_empty()).then_some(headers),
+        params: (!params.is_empty()).then_some(params),
+        body: data.clone(),
+        grpc: None,
     }
+}
 
-    if let Some(body) = data {
-        request_yaml.push_str(&format!("  body: \"{}\"\n", body));
-    }
+/// Write the request as a runnable single-test suite and return the path written.
+///
+/// The expectation defaults to the status the server actually returned.
+async fn save_request_file(path: &Path, request: Request, status: u16) -> Result<PathBuf> {
+    let path = if path.extension().is_none() {
+        path.with_extension("rivet.yaml")
+    } else {
+        path.to_path_buf()
+    };
 
-    tokio::fs::write(path, request_yaml).await?;
-    Ok(())
+    let url_path = Url::parse(&request.url)
+        .map(|parsed| parsed.path().to_string())
+        .unwrap_or_else(|_| request.url.clone());
+    let name = format!("{} {}", request.method, url_path);
+
+    let config = RivetConfig {
+        default_headers: None,
+        identify_requests: None,
+        base_path: None,
+        include: None,
+        templates: None,
+        auth: None,
+        max_capture_bytes: None,
+        follow_redirects: None,
+        name: name.clone(),
+        description: None,
+        env: None,
+        vars: None,
+        setup: None,
+        tests: vec![TestStep {
+            capture: None,
+            name,
+            description: None,
+            vars: None,
+            request,
+            expect: Some(Expectation {
+                poll: None,
+                redirects_to: None,
+                content_type: None,
+                status: Some(StatusExpectation::Number(status)),
+                schema: None,
+                jsonpath: None,
+                jsonpath_length: None,
+                jsonpath_contains: None,
+                jsonpath_every: None,
+                headers: None,
+            }),
+        }],
+        dataset: None,
+        teardown: None,
+        tls: None,
+        insecure: None,
+        before_each: None,
+        after_each: None,
+        secrets: None,
+        strict_vars: None,
+    };
+
+    tokio::fs::write(&path, serde_yaml::to_string(&config)?).await?;
+    Ok(path)
 }
@@ -0,0 +1,50 @@
+use crate::runner::validate::{validate_target, Severity};
+use crate::style::Colorize;
+use crate::utils::load_env_file;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Check the suites under `target` without sending requests. Warnings are
+/// printed; only errors fail the command.
+pub async fn handle_validate(target: PathBuf, env_file: Option<&Path>) -> Result<()> {
+    // Variables from .env count as defined, as they do for `run`
+    load_env_file(env_file)?;
+
+    let validation = validate_target(&target).await?;
+    for finding in &validation.findings {
+        let severity = match finding.severity {
+            Severity::Error => "error".red().bold().to_string(),
+            Severity::Warning => "warning".yellow().bold().to_string(),
+        };
+        println!(
+            "{}: {}: {}",
+            finding.location().bright_white(),
+            severity,
+            finding.message
+        );
+    }
+
+    let (errors, warnings) = (validation.errors(), validation.warnings());
+    if errors > 0 {
+        anyhow::bail!(
+            "{} error(s), {} warning(s) in {} suite file(s)",
+            errors,
+            warnings,
+            validation.files
+        );
+    }
+    if warnings > 0 {
+        println!();
+    }
+    println!(
+        "{} {} suite file(s) valid{}",
+        "✔".green().bold(),
+        validation.files,
+        if warnings > 0 {
+            format!(", {} warning(s)", warnings)
+        } else {
+            String::new()
+        }
+    );
+    Ok(())
+}
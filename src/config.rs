@@ -1,18 +1,50 @@
-use anyhow::Result;
+use crate::utils::is_rivet_header;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct UserConfig {
     pub reports: ReportConfig,
+    /// Proxy for every request unless overridden by `--proxy` or `--no-proxy`
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub defaults: FlagDefaults,
 }
 
+/// Defaults for `run` and `perf` flags, usually set per project. A flag on
+/// the command line wins.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FlagDefaults {
+    /// `--env`
+    #[serde(default)]
+    pub env: Option<String>,
+    /// `--base-url`
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// `rivet run --parallel-suites`
+    #[serde(default)]
+    pub parallel: Option<usize>,
+    /// `rivet run --report` formats
+    #[serde(default)]
+    pub report: Option<Vec<String>>,
+    /// Variables masked as if passed with `--secret`
+    #[serde(default)]
+    pub secrets: Vec<String>,
+}
+
+/// Report settings. Report formats default from `defaults.report`; the old
+/// `default_formats` key was never read and is ignored in existing files.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ReportConfig {
     pub auto_open_browser: bool,
     pub default_template: String,
-    pub default_formats: Vec<String>,
+    #[serde(default)]
+    pub report_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub report_name: Option<String>,
 }
 
 impl Default for ReportConfig {
@@ -20,15 +52,28 @@ impl Default for ReportConfig {
         Self {
             auto_open_browser: true,                 // Default to true as requested
             default_template: "compact".to_string(), // Default to compact - interactive and modern
-            default_formats: vec!["html".to_string()],
+            report_dir: None,
+            report_name: None,
         }
     }
 }
 
 impl UserConfig {
+    /// The home-dir config with the project config, if any, merged over it.
+    /// A project file that can't be used is skipped; [`Self::load_with_warnings`]
+    /// says why.
     pub fn load() -> Result<Self> {
-        let config_dir = Self::get_config_dir()?;
-        let config_path = config_dir.join("config.json");
+        Ok(ConfigLayers::load()?.merged())
+    }
+
+    /// Like [`Self::load`], with a warning for each project file skipped.
+    pub fn load_with_warnings() -> Result<(Self, Vec<String>)> {
+        let layers = ConfigLayers::load()?;
+        Ok((layers.merged(), layers.warnings()))
+    }
+
+    fn load_user() -> Result<Self> {
+        let config_path = Self::path()?;
 
         if config_path.exists() {
             let content = std::fs::read_to_string(&config_path)?;
@@ -53,6 +98,16 @@ impl UserConfig {
         Ok(())
     }
 
+    /// The home-dir config file.
+    pub fn path() -> Result<PathBuf> {
+        Ok(Self::get_config_dir()?.join("config.json"))
+    }
+
+    /// Where a new project config is written when none is found.
+    pub fn project_path() -> PathBuf {
+        Path::new(".rivet").join("config.json")
+    }
+
     fn get_config_dir() -> Result<PathBuf> {
         let home = std::env::var("HOME")
             .or_else(|_| std::env::var("USERPROFILE"))
@@ -62,33 +117,548 @@ impl UserConfig {
     }
 }
 
+/// Where a setting's effective value comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigSource {
+    Default,
+    User(PathBuf),
+    Project(PathBuf),
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::User(path) => write!(f, "user: {}", path.display()),
+            ConfigSource::Project(path) => write!(f, "project: {}", path.display()),
+        }
+    }
+}
+
+/// The home-dir config and the project config, before merging.
+pub struct ConfigLayers {
+    user: UserConfig,
+    /// The home-dir file as written, to tell its settings from defaults
+    user_file: serde_json::Value,
+    pub user_path: PathBuf,
+    /// The project file, which holds only the settings it overrides
+    pub project: Option<(PathBuf, serde_json::Value)>,
+    /// Why a project file that was found couldn't be read
+    unreadable: Option<String>,
+}
+
+impl ConfigLayers {
+    /// Read the home-dir config (creating it when missing) and the nearest
+    /// project config above the working directory.
+    pub fn load() -> Result<Self> {
+        let user = UserConfig::load_user()?;
+        let user_path = UserConfig::path()?;
+        let user_file = std::fs::read_to_string(&user_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        let mut unreadable = None;
+        let project = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| find_project_config(&cwd, &user_path))
+            .and_then(|path| match read_config_file(&path) {
+                Ok(value) => Some((path, value)),
+                Err(e) => {
+                    unreadable = Some(format!("Ignoring {:#}", e));
+                    None
+                }
+            });
+
+        Ok(Self {
+            user,
+            user_file,
+            user_path,
+            project,
+            unreadable,
+        })
+    }
+
+    /// Project settings over the home-dir ones; a project file with a
+    /// setting of the wrong type is ignored, see [`Self::warnings`].
+    pub fn merged(&self) -> UserConfig {
+        self.try_merged().unwrap_or_else(|_| self.user.clone())
+    }
+
+    /// Project files that were found but ignored, and why.
+    pub fn warnings(&self) -> Vec<String> {
+        self.unreadable
+            .clone()
+            .into_iter()
+            .chain(self.try_merged().err().map(|e| format!("Ignoring {:#}", e)))
+            .collect()
+    }
+
+    fn try_merged(&self) -> Result<UserConfig> {
+        let Some((path, project)) = &self.project else {
+            return Ok(self.user.clone());
+        };
+        let mut merged = serde_json::to_value(&self.user)?;
+        merge_json(&mut merged, project.clone());
+        serde_json::from_value(merged).with_context(|| path.display().to_string())
+    }
+
+    /// The file that sets `key` (a dot path) in the merged config.
+    pub fn source(&self, key: &str) -> ConfigSource {
+        match &self.project {
+            Some((path, project)) if lookup_json(project, key).is_some() => {
+                ConfigSource::Project(path.clone())
+            }
+            // The home file is written whole, with unset settings as null
+            _ if lookup_json(&self.user_file, key).is_some_and(|value| !value.is_null()) => {
+                ConfigSource::User(self.user_path.clone())
+            }
+            _ => ConfigSource::Default,
+        }
+    }
+}
+
+/// The nearest `rivet.toml` or `.rivet/config.json` in `start` or a parent
+/// directory, other than the home-dir config itself.
+pub fn find_project_config(start: &Path, user_path: &Path) -> Option<PathBuf> {
+    let user_path = user_path
+        .canonicalize()
+        .unwrap_or_else(|_| user_path.to_path_buf());
+    start.ancestors().find_map(|dir| {
+        [
+            dir.join("rivet.toml"),
+            dir.join(".rivet").join("config.json"),
+        ]
+        .into_iter()
+        .find(|candidate| {
+            candidate.is_file()
+                && candidate.canonicalize().ok().as_deref() != Some(user_path.as_path())
+        })
+    })
+}
+
+/// A config file as JSON, read as TOML when it ends in `.toml`.
+pub fn read_config_file(path: &Path) -> Result<serde_json::Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if path.extension().is_some_and(|ext| ext == "toml") {
+        let value: toml::Value = toml::from_str(&content)
+            .with_context(|| format!("{} is not valid TOML", path.display()))?;
+        Ok(serde_json::to_value(value)?)
+    } else {
+        serde_json::from_str(&content)
+            .with_context(|| format!("{} is not valid JSON", path.display()))
+    }
+}
+
+/// The value at a dot path such as `reports.default_template`.
+pub fn lookup_json<'a>(value: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    key.split('.')
+        .try_fold(value, |value, part| value.get(part))
+}
+
+/// Merge `overrides` into `base`, recursing into objects so an override
+/// only replaces the keys it has.
+pub fn merge_json(base: &mut serde_json::Value, overrides: serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// A `rivet perf --config` file.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PerfConfig {
+    pub thresholds: Option<PerfThresholds>,
+}
+
+impl PerfConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read perf config: {}", path.display()))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse perf config: {}", path.display()))
+    }
+}
+
+/// Limits a perf run must meet, named like the `rivet perf` flags. Unknown
+/// keys are rejected so a typo can't silently disable a gate.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PerfThresholds {
+    /// Maximum P95 latency, e.g. `250ms`
+    pub fail_p95: Option<String>,
+    /// Maximum P99 latency
+    pub fail_p99: Option<String>,
+    /// Maximum average latency
+    pub fail_avg: Option<String>,
+    /// Minimum requests per second
+    pub min_rps: Option<f64>,
+    /// Minimum success rate in percent (default 95)
+    pub min_success_rate: Option<f64>,
+    /// Count assertion failures against the success rate (default false)
+    pub count_assertion_failures: Option<bool>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RivetConfig {
     pub name: String,
     pub description: Option<String>,
     pub env: Option<String>,
     pub vars: Option<HashMap<String, String>>,
+    /// Variables whose values are masked in console output and reports
+    pub secrets: Option<Vec<String>>,
+    /// Fail requests that still contain an unresolved `{{name}}`
+    pub strict_vars: Option<bool>,
     pub setup: Option<Vec<TestStep>>,
+    /// Steps run before every main test, sharing its variable context
+    pub before_each: Option<Vec<TestStep>>,
     pub tests: Vec<TestStep>,
+    /// Steps run after every main test, even when it failed
+    pub after_each: Option<Vec<TestStep>>,
     pub dataset: Option<Dataset>,
     pub teardown: Option<Vec<TestStep>>,
+    pub tls: Option<TlsConfig>,
+    /// Skip server certificate verification for this suite
+    pub insecure: Option<bool>,
+    /// Default for steps that don't set `follow_redirects`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_redirects: Option<bool>,
+    /// Response body bytes kept for assertions and reports; the rest is
+    /// read and counted but dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_capture_bytes: Option<usize>,
+    /// `Authorization` for every step that doesn't send its own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthConfig>,
+    /// Headers sent with every HTTP step; a step's own value wins, and an
+    /// empty one leaves the header out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_headers: Option<HashMap<String, String>>,
+    /// Send `X-Rivet-Test: <suite>/<step>` with every HTTP step, as
+    /// `rivet run --identify` does; `false` wins over the flag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identify_requests: Option<bool>,
+    /// Path under `baseUrl` that relative request URLs are joined to,
+    /// e.g. `/api/v2`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_path: Option<String>,
+    /// Files lending this suite their `vars`, `setup` and `templates`,
+    /// relative to it; expanded when the suite is loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+    /// Partial steps that steps start from with `use: <name>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub templates: Option<HashMap<String, serde_yaml::Value>>,
+}
+
+impl RivetConfig {
+    /// Give every step the suite's defaults for settings it leaves unset.
+    pub fn apply_step_defaults(&mut self) {
+        let follow_redirects = self.follow_redirects;
+        let default_headers = self.default_headers.clone().unwrap_or_default();
+        for step in self.steps_mut() {
+            if let Some(follow_redirects) = follow_redirects {
+                step.request
+                    .follow_redirects
+                    .get_or_insert(follow_redirects);
+            }
+            if default_headers.is_empty() || step.request.grpc.is_some() {
+                continue;
+            }
+            let own = step.request.headers.take().unwrap_or_default();
+            let mut headers = default_headers.clone();
+            for (name, value) in own {
+                remove_header(&mut headers, &name);
+                // rivet's own headers keep the empty value, which stops the
+                // executor sending them at all
+                if !value.is_empty() || is_rivet_header(&name) {
+                    headers.insert(name, value);
+                }
+            }
+            step.request.headers = Some(headers);
+        }
+    }
+
+    /// Set `headers` on every HTTP step, over its own and the suite's,
+    /// as `rivet run --header` does.
+    pub fn override_headers(&mut self, headers: &HashMap<String, String>) {
+        for step in self.steps_mut() {
+            if step.request.grpc.is_some() {
+                continue;
+            }
+            let own = step.request.headers.get_or_insert_with(HashMap::new);
+            for (name, value) in headers {
+                remove_header(own, name);
+                own.insert(name.clone(), value.clone());
+            }
+        }
+    }
+
+    /// Every step of every section, setup through teardown.
+    pub fn steps_mut(&mut self) -> impl Iterator<Item = &mut TestStep> {
+        [
+            self.setup.as_mut(),
+            self.before_each.as_mut(),
+            Some(&mut self.tests),
+            self.after_each.as_mut(),
+            self.teardown.as_mut(),
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+    }
+}
+
+/// Drop `name` from `headers`, in any case.
+fn remove_header(headers: &mut HashMap<String, String>, name: &str) {
+    headers.retain(|key, _| !key.eq_ignore_ascii_case(name));
+}
+
+/// Client TLS settings. Paths may use variables such as `${RIVET_ENV}` so
+/// each environment can present its own identity.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TlsConfig {
+    /// PEM client certificate (used with `key`)
+    pub cert: Option<PathBuf>,
+    /// PEM private key for `cert`
+    pub key: Option<PathBuf>,
+    /// PKCS#12 bundle holding both certificate and key
+    pub identity: Option<PathBuf>,
+    pub identity_password: Option<String>,
+    /// PEM bundle of extra CAs to trust
+    pub cacert: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    /// Settings from `self`, falling back to `other` for anything unset.
+    /// The client identity is taken as a whole from one side.
+    pub fn or(self, other: TlsConfig) -> TlsConfig {
+        let has_identity = self.cert.is_some() || self.key.is_some() || self.identity.is_some();
+        let cacert = self.cacert.or(other.cacert);
+        if has_identity {
+            TlsConfig { cacert, ..self }
+        } else {
+            TlsConfig { cacert, ..other }
+        }
+    }
+}
+
+/// How a suite authenticates. Exactly one scheme is set; values may use
+/// variables.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AuthConfig {
+    /// Token sent as `Bearer <token>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bearer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub basic: Option<BasicAuth>,
+    /// Token fetched from `token_url` and sent as a bearer token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth2_client_credentials: Option<OAuth2ClientCredentials>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BasicAuth {
+    pub user: String,
+    pub password: String,
+}
+
+/// The OAuth 2.0 client credentials grant (RFC 6749, section 4.4).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OAuth2ClientCredentials {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+}
+
+/// The scheme an [`AuthConfig`] sets.
+pub enum AuthScheme<'a> {
+    Bearer(&'a str),
+    Basic(&'a BasicAuth),
+    OAuth2ClientCredentials(&'a OAuth2ClientCredentials),
+}
+
+impl AuthConfig {
+    pub fn scheme(&self) -> anyhow::Result<AuthScheme<'_>> {
+        match (&self.bearer, &self.basic, &self.oauth2_client_credentials) {
+            (Some(token), None, None) => Ok(AuthScheme::Bearer(token)),
+            (None, Some(basic), None) => Ok(AuthScheme::Basic(basic)),
+            (None, None, Some(oauth2)) => Ok(AuthScheme::OAuth2ClientCredentials(oauth2)),
+            _ => anyhow::bail!(
+                "auth must set exactly one of bearer, basic and oauth2_client_credentials"
+            ),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TestStep {
     pub name: String,
     pub description: Option<String>,
+    /// Variables for this step only, over the suite's and the dataset row's;
+    /// values may reference other variables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vars: Option<HashMap<String, String>>,
     pub request: Request,
     pub expect: Option<Expectation>,
+    /// Variables set from the response for the steps that follow, by name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture: Option<HashMap<String, CaptureSource>>,
+}
+
+/// Where a captured variable's value comes from.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum CaptureSource {
+    /// A JSONPath into the response body, e.g. `$.id`
+    JsonPath(String),
+    /// A response header, or with `pattern`, the first group of the regex
+    /// matching its value (the whole match if it has no groups)
+    Header {
+        header: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pattern: Option<String>,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(try_from = "RequestFields")]
 pub struct Request {
+    /// Empty for gRPC steps
     pub method: String,
+    /// Empty for gRPC steps
     pub url: String,
     pub headers: Option<HashMap<String, String>>,
     pub params: Option<HashMap<String, String>>,
     pub body: Option<String>,
+    /// Make this step a gRPC call; `method`, `url` and the HTTP fields are ignored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc: Option<GrpcRequest>,
+    /// Follow 3xx responses to their `Location` (the default); `false`
+    /// leaves the redirect itself to be checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_redirects: Option<bool>,
+    /// The `Accept-Encoding` to send and whether to decode the response;
+    /// without it nothing is asked for and bodies are kept as sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Compression>,
+}
+
+/// A [`Request`] as written: only gRPC steps may leave out `method` and `url`.
+#[derive(Deserialize)]
+struct RequestFields {
+    method: Option<String>,
+    url: Option<String>,
+    headers: Option<HashMap<String, String>>,
+    params: Option<HashMap<String, String>>,
+    body: Option<String>,
+    #[serde(default)]
+    grpc: Option<GrpcRequest>,
+    #[serde(default)]
+    follow_redirects: Option<bool>,
+    #[serde(default)]
+    compression: Option<Compression>,
+}
+
+impl TryFrom<RequestFields> for Request {
+    type Error = String;
+
+    fn try_from(fields: RequestFields) -> Result<Self, Self::Error> {
+        let is_grpc = fields.grpc.is_some();
+        let required = |value: Option<String>, field: &str| match value {
+            Some(value) => Ok(value),
+            None if is_grpc => Ok(String::new()),
+            None => Err(format!("missing field `{}`", field)),
+        };
+        Ok(Self {
+            method: required(fields.method, "method")?,
+            url: required(fields.url, "url")?,
+            headers: fields.headers,
+            params: fields.params,
+            body: fields.body,
+            grpc: fields.grpc,
+            follow_redirects: fields.follow_redirects,
+            compression: fields.compression,
+        })
+    }
+}
+
+/// Response compression a step asks for, with `compression:` or
+/// `rivet send --compression`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// Ask for gzip and decode it
+    Gzip,
+    /// Ask for brotli and decode it
+    #[serde(rename = "br")]
+    Brotli,
+    /// Ask for an uncompressed body
+    Identity,
+    /// Ask for gzip or brotli but keep the body as sent, so its raw bytes
+    /// and `Content-Encoding` can be checked
+    None,
+}
+
+impl Compression {
+    pub fn accept_encoding(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Brotli => "br",
+            Compression::Identity => "identity",
+            Compression::None => "gzip, br",
+        }
+    }
+
+    /// Whether a compressed response is decoded before it is checked.
+    pub fn decodes(self) -> bool {
+        self != Compression::None
+    }
+}
+
+impl std::str::FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "gzip" => Ok(Compression::Gzip),
+            "br" => Ok(Compression::Brotli),
+            "identity" => Ok(Compression::Identity),
+            "none" => Ok(Compression::None),
+            _ => anyhow::bail!("Invalid compression '{}'. Use: gzip, br, identity, none", s),
+        }
+    }
+}
+
+/// A unary or streaming gRPC call. Server, proto, call and data may all use
+/// variables.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GrpcRequest {
+    /// Server address, e.g. `http://localhost:50051`
+    pub server: String,
+    /// Directory of `.proto` files describing the service
+    pub proto: String,
+    /// Method to call, as `Service/Method`
+    pub call: String,
+    /// Request message in the proto3 JSON mapping, as YAML or a JSON string.
+    /// Client-streaming calls take a list of messages.
+    pub data: Option<serde_json::Value>,
+    pub metadata: Option<HashMap<String, String>>,
+    /// Deadline for the call, e.g. `5s`; the run's request timeout otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -96,7 +666,68 @@ pub struct Expectation {
     pub status: Option<StatusExpectation>,
     pub schema: Option<String>,
     pub jsonpath: Option<HashMap<String, serde_json::Value>>,
+    /// Lengths of the arrays at JSONPaths, e.g. `10` or `">= 1"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jsonpath_length: Option<HashMap<String, LengthExpectation>>,
+    /// Values at least one element of the array at each JSONPath equals,
+    /// e.g. `"$.items[*].id": 42`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jsonpath_contains: Option<HashMap<String, serde_json::Value>>,
+    /// Values every element of the array at each JSONPath equals; an empty
+    /// array passes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jsonpath_every: Option<HashMap<String, serde_json::Value>>,
     pub headers: Option<HashMap<String, String>>,
+    /// URL the request ended at after redirects, or the `Location` of a
+    /// redirect that wasn't followed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirects_to: Option<String>,
+    /// Media type the `Content-Type` header must match, e.g.
+    /// `application/json` or `application/*+json`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Once the response passes, request another URL until it passes too,
+    /// e.g. for the outcome of a job the step started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll: Option<Box<Poll>>,
+}
+
+/// A request repeated until its response meets `until`, or `timeout` runs
+/// out. Values may use variables, including the step's captures.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Poll {
+    /// URL to poll; the response's `Location` header when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// `GET` when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// Sent over the step's own headers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<HashMap<String, String>>,
+    /// Wait between requests, e.g. `500ms`; `1s` when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+    /// How long to keep polling, e.g. `120s`; `30s` when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    pub until: Expectation,
+}
+
+impl Poll {
+    pub const DEFAULT_INTERVAL: &'static str = "1s";
+    pub const DEFAULT_TIMEOUT: &'static str = "30s";
+}
+
+impl Expectation {
+    /// Whether any assertion reads the response body as JSON.
+    pub fn checks_json(&self) -> bool {
+        self.jsonpath.is_some()
+            || self.jsonpath_length.is_some()
+            || self.jsonpath_contains.is_some()
+            || self.jsonpath_every.is_some()
+            || self.schema.is_some()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -106,10 +737,41 @@ pub enum StatusExpectation {
     String(String),
 }
 
+/// An expected array length: a number, or a bound such as `">= 1"` or
+/// `"<= 50"`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum LengthExpectation {
+    Number(usize),
+    String(String),
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Dataset {
     pub file: String,
     pub parallel: Option<usize>,
+    /// csv, json or jsonl; inferred from the file extension when omitted
+    pub format: Option<String>,
+    /// Only run rows matching this expression, e.g. `country == "US"`
+    pub filter: Option<String>,
+    /// Run a seeded random subset: a row count (`50`) or percentage (`"10%"`)
+    pub sample: Option<DatasetSample>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum DatasetSample {
+    Count(usize),
+    Text(String),
+}
+
+impl std::fmt::Display for DatasetSample {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatasetSample::Count(count) => write!(f, "{}", count),
+            DatasetSample::Text(text) => f.write_str(text),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -122,7 +784,26 @@ mod tests {
         let config = ReportConfig::default();
         assert!(config.auto_open_browser);
         assert_eq!(config.default_template, "compact");
-        assert_eq!(config.default_formats, vec!["html"]);
+        assert!(config.report_dir.is_none());
+        assert!(config.report_name.is_none());
+    }
+
+    #[test]
+    fn test_report_config_without_naming_fields() {
+        // Config files written before report_dir/report_name existed, with
+        // the retired default_formats, must still load
+        let json = r#"{
+            "auto_open_browser": false,
+            "default_template": "detailed",
+            "default_formats": ["json"]
+        }"#;
+        let config: ReportConfig = serde_json::from_str(json).unwrap();
+        assert!(!config.auto_open_browser);
+        assert!(!serde_json::to_string(&config)
+            .unwrap()
+            .contains("default_formats"));
+        assert!(config.report_dir.is_none());
+        assert!(config.report_name.is_none());
     }
 
     #[test]
@@ -130,6 +811,42 @@ mod tests {
         let config = UserConfig::default();
         assert!(config.reports.auto_open_browser);
         assert_eq!(config.reports.default_template, "compact");
+        assert!(config.proxy.is_none());
+    }
+
+    #[test]
+    fn test_tls_config_or_keeps_identity_together() {
+        let cli = TlsConfig {
+            identity: Some("cli.p12".into()),
+            ..Default::default()
+        };
+        let suite = TlsConfig {
+            cert: Some("suite.pem".into()),
+            key: Some("suite-key.pem".into()),
+            cacert: Some("ca.pem".into()),
+            ..Default::default()
+        };
+
+        let merged = cli.or(suite.clone());
+        assert_eq!(merged.identity, Some("cli.p12".into()));
+        assert!(merged.cert.is_none());
+        assert!(merged.key.is_none());
+        assert_eq!(merged.cacert, Some("ca.pem".into()));
+
+        let merged = TlsConfig::default().or(suite);
+        assert_eq!(merged.cert, Some("suite.pem".into()));
+        assert_eq!(merged.key, Some("suite-key.pem".into()));
+    }
+
+    #[test]
+    fn test_user_config_proxy_is_optional() {
+        let json = r#"{"reports": {"auto_open_browser": false, "default_template": "compact"}}"#;
+        let config: UserConfig = serde_json::from_str(json).unwrap();
+        assert!(config.proxy.is_none());
+
+        let json = r#"{"reports": {"auto_open_browser": false, "default_template": "compact"}, "proxy": "socks5://localhost:1080"}"#;
+        let config: UserConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.proxy.as_deref(), Some("socks5://localhost:1080"));
     }
 
     #[test]
@@ -161,30 +878,55 @@ mod tests {
         headers.insert("Content-Type".to_string(), "application/json".to_string());
 
         let config = RivetConfig {
+            default_headers: None,
+            identify_requests: None,
+            base_path: None,
+            include: None,
+            templates: None,
+            auth: None,
+            max_capture_bytes: None,
+            follow_redirects: None,
             name: "Test Suite".to_string(),
             description: Some("A test suite for API testing".to_string()),
             env: Some("staging".to_string()),
             vars: Some(vars),
             setup: None,
             tests: vec![TestStep {
+                capture: None,
                 name: "Test user creation".to_string(),
                 description: Some("Creates a new user".to_string()),
+                vars: None,
                 request: Request {
+                    compression: None,
+                    follow_redirects: None,
                     method: "POST".to_string(),
                     url: "{{baseUrl}}/users".to_string(),
                     headers: Some(headers),
                     params: None,
                     body: Some(r#"{"name": "John Doe"}"#.to_string()),
+                    grpc: None,
                 },
                 expect: Some(Expectation {
+                    poll: None,
+                    redirects_to: None,
+                    content_type: None,
                     status: Some(StatusExpectation::Number(201)),
                     schema: None,
                     jsonpath: None,
+                    jsonpath_length: None,
+                    jsonpath_contains: None,
+                    jsonpath_every: None,
                     headers: None,
                 }),
             }],
             dataset: None,
             teardown: None,
+            tls: None,
+            insecure: None,
+            before_each: None,
+            after_each: None,
+            secrets: None,
+            strict_vars: None,
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -197,17 +939,93 @@ mod tests {
         assert_eq!(deserialized.env, Some("staging".to_string()));
     }
 
+    #[test]
+    fn test_default_headers_merge_into_steps() {
+        let mut config: RivetConfig = serde_yaml::from_str(
+            r#"
+name: Headers
+default_headers:
+  Accept: application/json
+  X-Tenant-Id: acme
+tests:
+  - name: Own accept
+    request:
+      method: GET
+      url: /a
+      headers:
+        accept: text/plain
+  - name: No tenant
+    request:
+      method: GET
+      url: /b
+      headers:
+        X-Tenant-Id: ""
+  - name: gRPC
+    request:
+      grpc:
+        server: http://localhost:50051
+        proto: api.proto
+        call: Api/Get
+"#,
+        )
+        .unwrap();
+        config.apply_step_defaults();
+        let headers = |config: &RivetConfig, index: usize| {
+            config.tests[index].request.headers.clone().unwrap()
+        };
+        assert_eq!(
+            headers(&config, 0),
+            HashMap::from([
+                ("accept".to_string(), "text/plain".to_string()),
+                ("X-Tenant-Id".to_string(), "acme".to_string()),
+            ])
+        );
+        assert_eq!(
+            headers(&config, 1),
+            HashMap::from([("Accept".to_string(), "application/json".to_string())])
+        );
+        assert!(config.tests[2].request.headers.is_none());
+
+        config.override_headers(&HashMap::from([(
+            "ACCEPT".to_string(),
+            "application/xml".to_string(),
+        )]));
+        assert_eq!(headers(&config, 0)["ACCEPT"], "application/xml");
+        assert_eq!(headers(&config, 0).len(), 2);
+        assert_eq!(headers(&config, 1)["ACCEPT"], "application/xml");
+    }
+
+    #[test]
+    fn test_method_and_url_are_required_outside_grpc() {
+        let err = serde_yaml::from_str::<Request>("method: GET").unwrap_err();
+        assert!(err.to_string().contains("missing field `url`"), "{}", err);
+        let err = serde_yaml::from_str::<Request>("url: /a").unwrap_err();
+        assert!(
+            err.to_string().contains("missing field `method`"),
+            "{}",
+            err
+        );
+
+        let request: Request =
+            serde_yaml::from_str("grpc: {server: http://localhost:50051, proto: p, call: A/B}")
+                .unwrap();
+        assert_eq!((request.method.as_str(), request.url.as_str()), ("", ""));
+    }
+
     #[test]
     fn test_request_serialization() {
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), "Bearer token".to_string());
 
         let request = Request {
+            compression: None,
+            follow_redirects: None,
             method: "GET".to_string(),
             url: "/api/users".to_string(),
             headers: Some(headers),
             params: None,
             body: None,
+            grpc: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -235,9 +1053,15 @@ mod tests {
         );
 
         let expectation = Expectation {
+            poll: None,
+            redirects_to: None,
+            content_type: None,
             status: Some(StatusExpectation::Number(200)),
             schema: None,
             jsonpath: Some(jsonpath),
+            jsonpath_length: None,
+            jsonpath_contains: None,
+            jsonpath_every: None,
             headers: None,
         };
 
@@ -265,6 +1089,9 @@ mod tests {
         let dataset = Dataset {
             file: "test_data.csv".to_string(),
             parallel: Some(4),
+            format: None,
+            filter: None,
+            sample: None,
         };
 
         let json = serde_json::to_string(&dataset).unwrap();
@@ -274,6 +1101,48 @@ mod tests {
         assert_eq!(deserialized.parallel, Some(4));
     }
 
+    #[test]
+    fn test_dataset_sample_forms() {
+        let dataset: Dataset =
+            serde_yaml::from_str("file: users.csv\nsample: 50\nfilter: country == \"US\"").unwrap();
+        assert_eq!(dataset.sample.unwrap().to_string(), "50");
+        assert_eq!(dataset.filter.as_deref(), Some("country == \"US\""));
+
+        let dataset: Dataset = serde_yaml::from_str("file: users.csv\nsample: 10%").unwrap();
+        assert_eq!(dataset.sample.unwrap().to_string(), "10%");
+    }
+
+    #[test]
+    fn test_merge_json_keeps_unset_keys() {
+        let mut base = serde_json::to_value(UserConfig::default()).unwrap();
+        merge_json(
+            &mut base,
+            serde_json::json!({"reports": {"default_template": "detailed"}, "proxy": "http://proxy:3128"}),
+        );
+        let config: UserConfig = serde_json::from_value(base).unwrap();
+        assert_eq!(config.reports.default_template, "detailed");
+        assert!(config.reports.auto_open_browser);
+        assert_eq!(config.proxy.as_deref(), Some("http://proxy:3128"));
+    }
+
+    #[test]
+    fn test_config_layers_warn_instead_of_merging_a_bad_project_file() {
+        let layers = ConfigLayers {
+            user: UserConfig::default(),
+            user_file: serde_json::Value::Null,
+            user_path: PathBuf::from("config.json"),
+            project: Some((
+                PathBuf::from("rivet.toml"),
+                serde_json::json!({"defaults": {"parallel": "four"}}),
+            )),
+            unreadable: None,
+        };
+        assert_eq!(layers.merged().defaults.parallel, None);
+        let warnings = layers.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Ignoring rivet.toml: invalid type"));
+    }
+
     // Note: UserConfig::load() and save() tests would require mocking the filesystem
     // or using temporary directories, which would be integration tests
     #[test]
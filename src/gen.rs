@@ -0,0 +1,992 @@
+//! Test suites generated from an OpenAPI spec, as `rivet gen` and `rivet
+//! import openapi` write them.
+
+use crate::mock::success_response;
+use crate::runner::assertions::is_json_media_type;
+use crate::runner::base_url::BASE_URL_VAR;
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Dataset, Expectation, Request, RivetConfig, StatusExpectation, TestStep};
+
+mod bundle;
+
+#[derive(Debug, Clone, Default)]
+pub struct GenOptions {
+    /// Directory the suites are written to
+    pub out: PathBuf,
+    /// Write each success response's JSON Schema to `schemas/` and check
+    /// responses against it
+    pub with_schemas: bool,
+    /// Server to generate for: an index into the spec's `servers`, a URL, or
+    /// `all`; the first server when unset
+    pub server: Option<String>,
+    /// Turn operations with enum path or query parameters into data-driven
+    /// tests over `data/<operationId>.csv`
+    pub data_driven: bool,
+    /// Most rows a generated dataset gets; [`DEFAULT_DATA_LIMIT`] when unset
+    pub data_limit: Option<usize>,
+    /// Write request URLs as paths relative to `baseUrl`, which each test
+    /// then carries in its `vars`
+    pub relative_urls: bool,
+}
+
+pub const DEFAULT_DATA_LIMIT: usize = 100;
+
+/// What [`generate`] wrote.
+#[derive(Debug, Clone, Default)]
+pub struct GenSummary {
+    /// The spec's `info.title` and `info.version`
+    pub title: String,
+    pub version: String,
+    /// Paths in the spec
+    pub endpoints: usize,
+    /// A suite per operation, in the order they were written
+    pub suites: Vec<PathBuf>,
+    /// The main `rivet.yaml`, with the server's variables
+    pub config: PathBuf,
+    /// Notes on what was left out, e.g. dataset rows over the limit
+    pub warnings: Vec<String>,
+}
+
+/// Where generated tests send their requests.
+struct GenServer {
+    /// Start of every request URL: the server URL, or `{{baseUrl}}`
+    url_prefix: String,
+    /// `baseUrl` and the server's variables
+    vars: HashMap<String, String>,
+    /// Whether each test file carries `vars` itself
+    vars_in_tests: bool,
+    /// YAML comment lines written at the top of each file
+    comment: String,
+}
+
+impl GenServer {
+    fn select(servers: &[openapiv3::Server], choice: Option<&str>) -> Result<Self> {
+        let listing = || {
+            servers
+                .iter()
+                .enumerate()
+                .map(|(index, server)| format!("{}: {}", index, server.url))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let single = |server: &openapiv3::Server| {
+            let (template, mut vars) = server_template(server);
+            vars.insert(BASE_URL_VAR.to_string(), template);
+            GenServer {
+                url_prefix: expand_server_url(server),
+                vars,
+                vars_in_tests: false,
+                comment: String::new(),
+            }
+        };
+
+        match choice {
+            None if servers.is_empty() => Ok(single(&openapiv3::Server {
+                url: DEFAULT_SERVER_URL.to_string(),
+                ..Default::default()
+            })),
+            None => Ok(single(&servers[0])),
+            Some("all") => {
+                let Some(first) = servers.first() else {
+                    anyhow::bail!("--server all needs the spec to declare servers");
+                };
+                let (template, mut vars) = server_template(first);
+                vars.insert(BASE_URL_VAR.to_string(), template);
+                let mut comment =
+                    "# Servers in the spec; set baseUrl or pass --base-url to use another:\n"
+                        .to_string();
+                for server in servers {
+                    comment.push_str(&format!("#   {}", server.url));
+                    if let Some(description) = &server.description {
+                        comment.push_str(&format!(" ({})", description));
+                    }
+                    comment.push('\n');
+                }
+                Ok(GenServer {
+                    url_prefix: format!("{{{{{}}}}}", BASE_URL_VAR),
+                    vars,
+                    vars_in_tests: true,
+                    comment,
+                })
+            }
+            Some(choice) => match choice.parse::<usize>() {
+                Ok(index) => match servers.get(index) {
+                    Some(server) => Ok(single(server)),
+                    None if servers.is_empty() => {
+                        anyhow::bail!("The spec declares no servers; pass a URL to --server")
+                    }
+                    None => anyhow::bail!(
+                        "--server {} is out of range; the spec declares {}",
+                        index,
+                        listing()
+                    ),
+                },
+                // A declared server by its URL, or any other URL as is
+                Err(_) => Ok(servers
+                    .iter()
+                    .find(|server| server.url == choice || expand_server_url(server) == choice)
+                    .map(single)
+                    .unwrap_or_else(|| {
+                        single(&openapiv3::Server {
+                            url: choice.to_string(),
+                            ..Default::default()
+                        })
+                    })),
+            },
+        }
+    }
+}
+
+/// Base URL for specs that declare no servers.
+const DEFAULT_SERVER_URL: &str = "https://api.example.com";
+
+/// A server variable's value: its default, or its first allowed value when
+/// the default is empty.
+fn server_variable_value(variable: &openapiv3::ServerVariable) -> String {
+    if variable.default.is_empty() {
+        variable.enumeration.first().cloned().unwrap_or_default()
+    } else {
+        variable.default.clone()
+    }
+}
+
+/// `server`'s URL with each `{variable}` given its value.
+fn expand_server_url(server: &openapiv3::Server) -> String {
+    let mut url = server.url.clone();
+    for (name, variable) in server.variables.iter().flatten() {
+        url = url.replace(&format!("{{{}}}", name), &server_variable_value(variable));
+    }
+    url
+}
+
+/// `server`'s URL with each `{variable}` as a `{{variable}}` reference, and
+/// the variables' values.
+fn server_template(server: &openapiv3::Server) -> (String, HashMap<String, String>) {
+    let mut url = server.url.clone();
+    let mut vars = HashMap::new();
+    for (name, variable) in server.variables.iter().flatten() {
+        url = url.replace(&format!("{{{}}}", name), &format!("{{{{{}}}}}", name));
+        vars.insert(name.clone(), server_variable_value(variable));
+    }
+    (url, vars)
+}
+
+/// Load an OpenAPI spec, JSON by its extension and YAML otherwise. `$ref`s
+/// to other files are resolved relative to the file they are in.
+pub fn load_spec(spec_path: &Path) -> Result<openapiv3::OpenAPI> {
+    if !spec_path.exists() {
+        return Err(anyhow!(
+            "OpenAPI spec file does not exist: {}",
+            spec_path.display()
+        ));
+    }
+
+    let spec_content = fs::read_to_string(spec_path)?;
+    let json = spec_path.extension().and_then(|s| s.to_str()) == Some("json");
+    let format = if json { "JSON" } else { "YAML" };
+
+    if bundle::may_reference_files(&spec_content) {
+        let document: serde_yaml::Value = if json {
+            serde_json::from_str(&spec_content)
+                .map_err(|e| anyhow!("Failed to parse OpenAPI JSON: {}", e))?
+        } else {
+            serde_yaml::from_str(&spec_content)
+                .map_err(|e| anyhow!("Failed to parse OpenAPI YAML: {}", e))?
+        };
+        let document = bundle::bundle(spec_path, document)?;
+        return serde_yaml::from_value(document)
+            .map_err(|e| anyhow!("Failed to parse OpenAPI {}: {}", format, e));
+    }
+
+    // A single file is parsed from its text, so errors keep their lines
+    if json {
+        serde_json::from_str(&spec_content)
+            .map_err(|e| anyhow!("Failed to parse OpenAPI JSON: {}", e))
+    } else {
+        serde_yaml::from_str(&spec_content)
+            .map_err(|e| anyhow!("Failed to parse OpenAPI YAML: {}", e))
+    }
+}
+
+/// Write a suite for every operation in the OpenAPI spec at `spec` to
+/// `options.out`, plus a main `rivet.yaml`.
+///
+/// ```
+/// use rivet::gen::{generate, GenOptions};
+///
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let out = tempfile::tempdir()?;
+/// let summary = generate(
+///     "tests/fixtures/openapi/bundled.yaml".as_ref(),
+///     &GenOptions {
+///         out: out.path().to_path_buf(),
+///         with_schemas: true,
+///         ..Default::default()
+///     },
+/// )
+/// .await?;
+/// assert_eq!(summary.title, "Users API");
+/// assert_eq!(summary.suites.len(), 3);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn generate(spec: &Path, options: &GenOptions) -> Result<GenSummary> {
+    let out = &options.out;
+    let spec = load_spec(spec)?;
+
+    // Create output directory
+    fs::create_dir_all(out)?;
+
+    let mut summary = GenSummary {
+        title: spec.info.title.clone(),
+        version: spec.info.version.clone(),
+        ..Default::default()
+    };
+
+    let mut server = GenServer::select(&spec.servers, options.server.as_deref())?;
+    if options.relative_urls {
+        server.url_prefix = String::new();
+        server.vars_in_tests = true;
+    }
+
+    // Generate tests for each path and operation
+    for (path, path_item) in &spec.paths.paths {
+        if let openapiv3::ReferenceOr::Item(path_item) = path_item {
+            summary.endpoints += 1;
+
+            // Generate test for each HTTP method
+            let operations = [
+                ("GET", &path_item.get),
+                ("POST", &path_item.post),
+                ("PUT", &path_item.put),
+                ("DELETE", &path_item.delete),
+                ("PATCH", &path_item.patch),
+                ("HEAD", &path_item.head),
+                ("OPTIONS", &path_item.options),
+                ("TRACE", &path_item.trace),
+            ];
+
+            for (method, operation_ref) in operations {
+                if let Some(operation) = operation_ref {
+                    generate_test_for_operation(
+                        &spec,
+                        method,
+                        path,
+                        operation,
+                        &server,
+                        options,
+                        &mut summary,
+                    )
+                    .await?;
+                }
+            }
+        }
+    }
+
+    // Create main rivet config file
+    let main_config = RivetConfig {
+        default_headers: None,
+        identify_requests: None,
+        base_path: None,
+        include: None,
+        templates: None,
+        auth: None,
+        max_capture_bytes: None,
+        follow_redirects: None,
+        name: format!("{} Tests", spec.info.title),
+        description: spec.info.description.clone(),
+        env: None,
+        vars: Some(server.vars.clone()),
+        setup: None,
+        tests: vec![], // Individual test files will be loaded
+        dataset: None,
+        teardown: None,
+        tls: None,
+        insecure: None,
+        before_each: None,
+        after_each: None,
+        secrets: None,
+        strict_vars: None,
+    };
+
+    let config_path = out.join("rivet.yaml");
+    let config_yaml = serde_yaml::to_string(&main_config)?;
+    fs::write(&config_path, server.comment.clone() + &config_yaml)?;
+    summary.config = config_path;
+
+    Ok(summary)
+}
+
+async fn generate_test_for_operation(
+    spec: &openapiv3::OpenAPI,
+    method: &str,
+    path: &str,
+    operation: &openapiv3::Operation,
+    server: &GenServer,
+    options: &GenOptions,
+    summary: &mut GenSummary,
+) -> Result<()> {
+    let out_dir = options.out.as_path();
+    let operation_id = operation_id(method, path, operation);
+    let schema = if options.with_schemas {
+        write_response_schema(spec, method, path, operation, out_dir)?
+    } else {
+        None
+    };
+    let enums = if options.data_driven {
+        enum_parameters(spec, operation)
+    } else {
+        Vec::new()
+    };
+    let dataset = write_dataset(
+        &operation_id,
+        &enums,
+        out_dir,
+        options.data_limit.unwrap_or(DEFAULT_DATA_LIMIT),
+        &mut summary.warnings,
+    )?;
+
+    let name = operation
+        .summary
+        .clone()
+        .unwrap_or_else(|| format!("{} {}", method, path));
+
+    // Construct full URL, with enum path parameters taken from the dataset
+    let mut full_url = if path.starts_with('/') {
+        format!("{}{}", server.url_prefix, path)
+    } else {
+        format!("{}/{}", server.url_prefix, path)
+    };
+    for (name, _) in &enums {
+        full_url = full_url.replace(&format!("{{{}}}", name), &format!("{{{{{}}}}}", name));
+    }
+
+    // Extract headers from parameters
+    let mut headers = HashMap::new();
+    let mut query_params = HashMap::new();
+
+    for param_ref in &operation.parameters {
+        if let openapiv3::ReferenceOr::Item(param) = param_ref {
+            let param_name = &param.clone().parameter_data().name;
+            match param {
+                openapiv3::Parameter::Header { .. } => {
+                    headers.insert(param_name.clone(), "{{headerValue}}".to_string());
+                }
+                openapiv3::Parameter::Query { .. } => {
+                    let value = if enums.iter().any(|(name, _)| name == param_name) {
+                        format!("{{{{{}}}}}", param_name)
+                    } else {
+                        "{{queryValue}}".to_string()
+                    };
+                    query_params.insert(param_name.clone(), value);
+                }
+                openapiv3::Parameter::Path { .. } => {
+                    // Path parameters are handled in URL substitution
+                }
+                openapiv3::Parameter::Cookie { .. } => {
+                    // Handle cookies if needed
+                }
+            }
+        }
+    }
+
+    // Generate request body for POST/PUT/PATCH
+    let body = if matches!(method, "POST" | "PUT" | "PATCH") {
+        if let Some(openapiv3::ReferenceOr::Item(body)) = &operation.request_body {
+            // Convert IndexMap to HashMap
+            let content_map: HashMap<String, openapiv3::MediaType> = body
+                .content
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            generate_example_body(&content_map).await
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    // Create rivet request
+    let rivet_request = Request {
+        compression: None,
+        follow_redirects: None,
+        method: method.to_string(),
+        url: full_url,
+        headers: if headers.is_empty() {
+            None
+        } else {
+            Some(headers)
+        },
+        params: if query_params.is_empty() {
+            None
+        } else {
+            Some(query_params)
+        },
+        body,
+        grpc: None,
+    };
+
+    // Generate expectations based on responses
+    let expectation = generate_expectation_from_responses(&operation.responses)
+        .await
+        .map(|expectation| Expectation {
+            schema,
+            ..expectation
+        });
+
+    let test_step = TestStep {
+        capture: None,
+        name: name.clone(),
+        description: operation.description.clone(),
+        vars: None,
+        request: rivet_request,
+        expect: expectation,
+    };
+
+    let test_config = RivetConfig {
+        default_headers: None,
+        identify_requests: None,
+        base_path: None,
+        include: None,
+        templates: None,
+        auth: None,
+        max_capture_bytes: None,
+        follow_redirects: None,
+        name,
+        description: operation.description.clone(),
+        env: None,
+        vars: server.vars_in_tests.then(|| server.vars.clone()),
+        setup: None,
+        tests: vec![test_step],
+        dataset,
+        teardown: None,
+        tls: None,
+        insecure: None,
+        before_each: None,
+        after_each: None,
+        secrets: None,
+        strict_vars: None,
+    };
+
+    // Write test file
+    let filename = format!("{}.yaml", sanitize_filename(&operation_id));
+    let test_path = out_dir.join(filename);
+    let test_yaml = serde_yaml::to_string(&test_config)?;
+    fs::write(&test_path, server.comment.clone() + &test_yaml)?;
+    summary.suites.push(test_path);
+
+    Ok(())
+}
+
+/// The allowed values of each of the operation's path and query parameters
+/// that has an `enum`, in the order the parameters are declared.
+fn enum_parameters(
+    spec: &openapiv3::OpenAPI,
+    operation: &openapiv3::Operation,
+) -> Vec<(String, Vec<String>)> {
+    let mut enums = Vec::new();
+    for parameter in &operation.parameters {
+        let openapiv3::ReferenceOr::Item(parameter) = parameter else {
+            continue;
+        };
+        let (openapiv3::Parameter::Path { parameter_data, .. }
+        | openapiv3::Parameter::Query { parameter_data, .. }) = parameter
+        else {
+            continue;
+        };
+        let openapiv3::ParameterSchemaOrContent::Schema(schema) = &parameter_data.format else {
+            continue;
+        };
+        let schema = match schema {
+            openapiv3::ReferenceOr::Item(schema) => Some(schema),
+            openapiv3::ReferenceOr::Reference { reference } => reference
+                .strip_prefix("#/components/schemas/")
+                .and_then(|name| spec.components.as_ref()?.schemas.get(name))
+                .and_then(|schema| schema.as_item()),
+        };
+        let Some(Value::Array(values)) = schema
+            .and_then(|schema| serde_json::to_value(schema).ok())
+            .and_then(|schema| schema.get("enum").cloned())
+        else {
+            continue;
+        };
+        let values: Vec<String> = values
+            .iter()
+            .filter_map(|value| match value {
+                Value::Null => None,
+                Value::String(text) => Some(text.clone()),
+                other => Some(other.to_string()),
+            })
+            .collect();
+        if !values.is_empty() {
+            enums.push((parameter_data.name.clone(), values));
+        }
+    }
+    enums
+}
+
+/// Write every combination of the `enums` values, up to `limit` rows, to
+/// `data/<operationId>.csv` under `out_dir`, noting any rows left out in
+/// `warnings`. The dataset refers to it relative to the suite.
+fn write_dataset(
+    operation_id: &str,
+    enums: &[(String, Vec<String>)],
+    out_dir: &Path,
+    limit: usize,
+    warnings: &mut Vec<String>,
+) -> Result<Option<Dataset>> {
+    if enums.is_empty() {
+        return Ok(None);
+    }
+
+    let combinations = enums.iter().fold(1usize, |count, (_, values)| {
+        count.saturating_mul(values.len())
+    });
+    if combinations > limit {
+        warnings.push(format!(
+            "{}: {} parameter combinations, keeping the first {} (--data-limit)",
+            operation_id, combinations, limit
+        ));
+    }
+    // Combination `index` counts through the values with the last
+    // parameter changing fastest, so only the rows kept are built
+    let rows = (0..combinations)
+        .map(|index| {
+            let mut rest = index;
+            let mut row = vec![""; enums.len()];
+            for (value, (_, values)) in row.iter_mut().zip(enums).rev() {
+                *value = values[rest % values.len()].as_str();
+                rest /= values.len();
+            }
+            row
+        })
+        .take(limit);
+
+    let file = format!("data/{}.csv", sanitize_filename(operation_id));
+    let path = out_dir.join(&file);
+    fs::create_dir_all(out_dir.join("data"))?;
+    let mut writer = csv::Writer::from_path(&path)?;
+    writer.write_record(enums.iter().map(|(name, _)| name))?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    writer.flush()?;
+
+    Ok(Some(Dataset {
+        file,
+        parallel: None,
+        format: None,
+        filter: None,
+        sample: None,
+    }))
+}
+
+fn operation_id(method: &str, path: &str, operation: &openapiv3::Operation) -> String {
+    operation
+        .operation_id
+        .clone()
+        .unwrap_or_else(|| format!("{}_{}", method.to_lowercase(), sanitize_path(path)))
+}
+
+/// Write the JSON Schema of the operation's success response to
+/// `schemas/<operationId>.json` under `out_dir`, returning that path,
+/// relative to the suite, for `expect.schema`. Operations without a JSON
+/// success body get none.
+fn write_response_schema(
+    spec: &openapiv3::OpenAPI,
+    method: &str,
+    path: &str,
+    operation: &openapiv3::Operation,
+    out_dir: &Path,
+) -> Result<Option<String>> {
+    let Some(schema) = response_schema(spec, &operation.responses)? else {
+        return Ok(None);
+    };
+    fs::create_dir_all(out_dir.join("schemas"))?;
+    let file = format!(
+        "schemas/{}.json",
+        sanitize_filename(&operation_id(method, path, operation))
+    );
+    fs::write(
+        out_dir.join(&file),
+        serde_json::to_string_pretty(&schema)? + "\n",
+    )?;
+    Ok(Some(file))
+}
+
+/// The JSON Schema (draft 4, which OpenAPI 3.0 schemas are based on) of the
+/// JSON body of an operation's success response, with `$ref`s to
+/// `#/components/schemas` inlined. References back into a schema being
+/// inlined stay references, into the result's `definitions`.
+fn response_schema(
+    spec: &openapiv3::OpenAPI,
+    responses: &openapiv3::Responses,
+) -> Result<Option<Value>> {
+    let (_, response) = success_response(spec, responses);
+    let Some(schema) = response
+        .and_then(|response| {
+            response
+                .content
+                .iter()
+                .find(|(content_type, _)| is_json_media_type(content_type))
+        })
+        .and_then(|(_, media_type)| media_type.schema.as_ref())
+    else {
+        return Ok(None);
+    };
+
+    let mut components = Map::new();
+    for (name, schema) in spec
+        .components
+        .iter()
+        .flat_map(|components| &components.schemas)
+    {
+        components.insert(name.clone(), serde_json::to_value(schema)?);
+    }
+    let mut refs = SchemaRefs {
+        components: &components,
+        expanding: Vec::new(),
+        recursive: BTreeSet::new(),
+    };
+    let mut resolved = refs.resolve(&serde_json::to_value(schema)?);
+
+    let mut definitions = Map::new();
+    while let Some(name) = refs
+        .recursive
+        .iter()
+        .find(|name| !definitions.contains_key(*name))
+        .cloned()
+    {
+        refs.expanding = vec![name.clone()];
+        let definition = refs.resolve(&components[&name]);
+        definitions.insert(name, definition);
+    }
+
+    if let Value::Object(object) = &mut resolved {
+        object.insert(
+            "$schema".to_string(),
+            json!("http://json-schema.org/draft-04/schema#"),
+        );
+        if !definitions.is_empty() {
+            object.insert("definitions".to_string(), Value::Object(definitions));
+        }
+    }
+    Ok(Some(resolved))
+}
+
+/// Inlines `#/components/schemas` references and turns OpenAPI's
+/// `nullable` into a JSON Schema type.
+struct SchemaRefs<'a> {
+    components: &'a Map<String, Value>,
+    /// Components being inlined, outermost first
+    expanding: Vec<String>,
+    /// Components referred to from within themselves
+    recursive: BTreeSet<String>,
+}
+
+impl SchemaRefs<'_> {
+    fn resolve(&mut self, schema: &Value) -> Value {
+        match schema {
+            Value::Object(object) => {
+                if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+                    return self.resolve_ref(reference);
+                }
+                let mut resolved: Map<String, Value> = object
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.resolve(value)))
+                    .collect();
+                if resolved.remove("nullable") == Some(Value::Bool(true)) {
+                    if let Some(Value::String(kind)) = resolved.get("type") {
+                        let kind = kind.clone();
+                        resolved.insert("type".to_string(), json!([kind, "null"]));
+                    }
+                    if let Some(Value::Array(values)) = resolved.get_mut("enum") {
+                        values.push(Value::Null);
+                    }
+                }
+                Value::Object(resolved)
+            }
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|item| self.resolve(item)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    fn resolve_ref(&mut self, reference: &str) -> Value {
+        let Some((name, schema)) = reference
+            .strip_prefix("#/components/schemas/")
+            .and_then(|name| self.components.get_key_value(name))
+        else {
+            // Unknown references accept anything rather than fail to compile
+            return json!({});
+        };
+        if self.expanding.contains(name) {
+            self.recursive.insert(name.clone());
+            return json!({ "$ref": format!("#/definitions/{}", name) });
+        }
+        self.expanding.push(name.clone());
+        let resolved = self.resolve(schema);
+        self.expanding.pop();
+        resolved
+    }
+}
+
+/// An example for a request or response body: the JSON example, one built
+/// from the JSON schema, or any other content type's example.
+pub async fn generate_example_body(
+    content: &HashMap<String, openapiv3::MediaType>,
+) -> Option<String> {
+    // Look for JSON content type first
+    if let Some(json_content) = content.get("application/json") {
+        if let Some(example) = &json_content.example {
+            return Some(serde_json::to_string_pretty(example).unwrap_or_default());
+        }
+
+        // Generate example from schema if available
+        if let Some(openapiv3::ReferenceOr::Item(schema)) = &json_content.schema {
+            return generate_example_from_schema(schema).await;
+        }
+    }
+
+    // Fallback to any content type
+    for (content_type, media_type) in content {
+        if let Some(example) = &media_type.example {
+            if content_type.contains("json") {
+                return Some(serde_json::to_string_pretty(example).unwrap_or_default());
+            } else {
+                return Some(example.to_string());
+            }
+        }
+    }
+
+    None
+}
+
+async fn generate_example_from_schema(schema: &openapiv3::Schema) -> Option<String> {
+    match &schema.schema_kind {
+        openapiv3::SchemaKind::Type(openapiv3::Type::Object(obj)) => {
+            let mut example = serde_json::Map::new();
+
+            for (prop_name, prop_schema) in &obj.properties {
+                if let openapiv3::ReferenceOr::Item(prop_schema) = prop_schema {
+                    if let Some(prop_example) = generate_simple_example_value(prop_schema).await {
+                        example.insert(prop_name.clone(), prop_example);
+                    }
+                }
+            }
+
+            Some(
+                serde_json::to_string_pretty(&serde_json::Value::Object(example))
+                    .unwrap_or_default(),
+            )
+        }
+        _ => generate_simple_example_value(schema)
+            .await
+            .map(|v| serde_json::to_string_pretty(&v).unwrap_or_default()),
+    }
+}
+
+async fn generate_simple_example_value(schema: &openapiv3::Schema) -> Option<serde_json::Value> {
+    match &schema.schema_kind {
+        openapiv3::SchemaKind::Type(schema_type) => match schema_type {
+            openapiv3::Type::String(_) => Some(serde_json::Value::String("example".to_string())),
+            openapiv3::Type::Number(_) => {
+                Some(serde_json::Value::Number(serde_json::Number::from(42)))
+            }
+            openapiv3::Type::Integer(_) => {
+                Some(serde_json::Value::Number(serde_json::Number::from(42)))
+            }
+            openapiv3::Type::Boolean(_) => Some(serde_json::Value::Bool(true)),
+            openapiv3::Type::Array(_) => {
+                Some(serde_json::Value::Array(vec![serde_json::Value::String(
+                    "example".to_string(),
+                )]))
+            }
+            openapiv3::Type::Object(_) => Some(serde_json::Value::Object(serde_json::Map::new())),
+        },
+        _ => None,
+    }
+}
+
+async fn generate_expectation_from_responses(
+    responses: &openapiv3::Responses,
+) -> Option<Expectation> {
+    // Look for 2xx responses first
+    for (status_code, _response) in &responses.responses {
+        match status_code {
+            openapiv3::StatusCode::Code(code) => {
+                if *code >= 200 && *code < 300 {
+                    return Some(Expectation {
+                        poll: None,
+                        redirects_to: None,
+                        content_type: None,
+                        status: Some(StatusExpectation::Number(*code)),
+                        schema: None,
+                        jsonpath: None,
+                        jsonpath_length: None,
+                        jsonpath_contains: None,
+                        jsonpath_every: None,
+                        headers: None,
+                    });
+                }
+            }
+            openapiv3::StatusCode::Range(range) => {
+                // Handle range like "2XX"
+                if range == &2 {
+                    return Some(Expectation {
+                        poll: None,
+                        redirects_to: None,
+                        content_type: None,
+                        status: Some(StatusExpectation::Number(200)),
+                        schema: None,
+                        jsonpath: None,
+                        jsonpath_length: None,
+                        jsonpath_contains: None,
+                        jsonpath_every: None,
+                        headers: None,
+                    });
+                }
+            }
+        }
+    }
+
+    // Check for default response
+    if responses.default.is_some() {
+        return Some(Expectation {
+            poll: None,
+            redirects_to: None,
+            content_type: None,
+            status: Some(StatusExpectation::Number(200)),
+            schema: None,
+            jsonpath: None,
+            jsonpath_length: None,
+            jsonpath_contains: None,
+            jsonpath_every: None,
+            headers: None,
+        });
+    }
+
+    // Default to 200
+    Some(Expectation {
+        poll: None,
+        redirects_to: None,
+        content_type: None,
+        status: Some(StatusExpectation::Number(200)),
+        schema: None,
+        jsonpath: None,
+        jsonpath_length: None,
+        jsonpath_contains: None,
+        jsonpath_every: None,
+        headers: None,
+    })
+}
+
+fn sanitize_path(path: &str) -> String {
+    path.replace('/', "_")
+        .replace(['{', '}'], "")
+        .trim_matches('_')
+        .to_lowercase()
+}
+
+fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            ' ' => '_',
+            _ => '_',
+        })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_keeps_first_combinations_without_building_all() {
+        let enums: Vec<(String, Vec<String>)> = (0..16)
+            .map(|i| {
+                let values = (0..10).map(|value| value.to_string()).collect();
+                (format!("p{}", i), values)
+            })
+            .collect();
+        let out_dir = tempfile::tempdir().unwrap();
+        let mut warnings = Vec::new();
+
+        write_dataset("search", &enums, out_dir.path(), 3, &mut warnings).unwrap();
+        let csv = fs::read_to_string(out_dir.path().join("data/search.csv")).unwrap();
+        let rows: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(
+            rows,
+            [
+                "0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0",
+                "0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1",
+                "0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,2",
+            ]
+        );
+        assert_eq!(
+            warnings,
+            ["search: 10000000000000000 parameter combinations, keeping the first 3 (--data-limit)"]
+        );
+    }
+
+    #[test]
+    fn test_recursive_schemas_become_definitions() {
+        let spec: openapiv3::OpenAPI = serde_yaml::from_str(
+            r#"
+openapi: 3.0.0
+info: {title: Tree, version: 1.0.0}
+paths:
+  /tree:
+    get:
+      responses:
+        '200':
+          description: A tree
+          content:
+            application/json:
+              schema:
+                $ref: '#/components/schemas/Node'
+components:
+  schemas:
+    Node:
+      type: object
+      properties:
+        children:
+          type: array
+          items:
+            $ref: '#/components/schemas/Node'
+"#,
+        )
+        .unwrap();
+        let responses = &spec.paths.paths["/tree"]
+            .as_item()
+            .unwrap()
+            .get
+            .as_ref()
+            .unwrap()
+            .responses;
+
+        let schema = response_schema(&spec, responses).unwrap().unwrap();
+        assert_eq!(
+            schema["properties"]["children"]["items"],
+            json!({"$ref": "#/definitions/Node"})
+        );
+        assert_eq!(
+            schema["definitions"]["Node"]["properties"]["children"]["items"],
+            json!({"$ref": "#/definitions/Node"})
+        );
+        let compiled = jsonschema::JSONSchema::compile(&schema).unwrap();
+        assert!(compiled.is_valid(&json!({"children": [{"children": []}]})));
+        assert!(!compiled.is_valid(&json!({"children": [{"children": 3}]})));
+    }
+}
@@ -0,0 +1,376 @@
+//! Bringing the other files an OpenAPI spec's `$ref`s point to into the
+//! spec itself, so a spec split across files reads as one document.
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Whether `content`, a spec's text, may `$ref` another file. Cheap enough
+/// to decide whether a spec needs bundling at all.
+pub fn may_reference_files(content: &str) -> bool {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX
+        .get_or_init(|| Regex::new(r#"\$ref["']?\s*:\s*["']?[^"'#\s]"#).unwrap())
+        .is_match(content)
+}
+
+/// The spec at `path`, already parsed as `document`, with every `$ref` to
+/// another file resolved relative to the file it is in.
+///
+/// A reference to a component in another file, e.g.
+/// `schemas.yaml#/components/schemas/User`, becomes a component of the spec
+/// of the same name, so recursive schemas keep working. Anything else it
+/// points to is copied in place of the reference; a chain of those that
+/// leads back to itself is an error.
+pub fn bundle(path: &Path, document: Value) -> Result<Value> {
+    let root = canonical(path)?;
+    let mut bundler = Bundler {
+        root: root.clone(),
+        documents: HashMap::from([(root.clone(), document.clone())]),
+        components: HashMap::new(),
+        names: HashSet::new(),
+        hoisted: Vec::new(),
+        inlining: Vec::new(),
+    };
+    bundler.register_root_components(&document)?;
+
+    let mut document = document;
+    bundler.resolve(&mut document, &root, &mut Vec::new())?;
+    for (kind, name, value) in std::mem::take(&mut bundler.hoisted) {
+        let components = mapping_entry(&mut document, "components");
+        mapping_entry(components, &kind)
+            .as_mapping_mut()
+            .context("`components` entries must be mappings")?
+            .insert(Value::from(name), value);
+    }
+    Ok(document)
+}
+
+/// A `$ref` target: a file and a JSON pointer into it (`""` for all of it).
+type Target = (PathBuf, String);
+
+struct Bundler {
+    root: PathBuf,
+    /// Parsed files, by canonical path
+    documents: HashMap<PathBuf, Value>,
+    /// Targets that are components of the spec, and their `(kind, name)`
+    components: HashMap<Target, (String, String)>,
+    /// `kind/name` of every component of the spec, to keep new names apart
+    names: HashSet<String>,
+    /// Components brought in from other files, added once the walk is done
+    hoisted: Vec<(String, String, Value)>,
+    /// Targets being copied in, outermost first, to catch cycles
+    inlining: Vec<Target>,
+}
+
+impl Bundler {
+    /// Note the spec's own components, including those that are references
+    /// to other files: references to the same target use them.
+    fn register_root_components(&mut self, document: &Value) -> Result<()> {
+        let Some(components) = document.get("components").and_then(Value::as_mapping) else {
+            return Ok(());
+        };
+        for (kind, entries) in components {
+            let (Some(kind), Some(entries)) = (kind.as_str(), entries.as_mapping()) else {
+                continue;
+            };
+            for name in entries.keys().filter_map(Value::as_str) {
+                self.names.insert(format!("{}/{}", kind, name));
+                let target = (self.root.clone(), format!("/components/{}/{}", kind, name));
+                self.components
+                    .insert(target, (kind.to_string(), name.to_string()));
+            }
+            for (name, entry) in entries {
+                let (Some(name), Some(reference)) = (name.as_str(), reference_of(entry)) else {
+                    continue;
+                };
+                if !is_local(reference) && !is_remote(reference) {
+                    let target = self.target(reference, &self.root.clone())?;
+                    self.components
+                        .entry(target)
+                        .or_insert_with(|| (kind.to_string(), name.to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve the references in `value`, part of `file`, at `location` (the
+    /// keys leading to it from the top of the file).
+    fn resolve(
+        &mut self,
+        value: &mut Value,
+        file: &Path,
+        location: &mut Vec<String>,
+    ) -> Result<()> {
+        if let Some(reference) = reference_of(value).map(str::to_string) {
+            return self.resolve_reference(value, &reference, file, location);
+        }
+        match value {
+            Value::Mapping(mapping) => {
+                for (key, child) in mapping.iter_mut() {
+                    location.push(key_text(key));
+                    self.resolve(child, file, location)?;
+                    location.pop();
+                }
+            }
+            Value::Sequence(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    location.push(index.to_string());
+                    self.resolve(item, file, location)?;
+                    location.pop();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn resolve_reference(
+        &mut self,
+        value: &mut Value,
+        reference: &str,
+        file: &Path,
+        location: &[String],
+    ) -> Result<()> {
+        if is_remote(reference) || (is_local(reference) && file == self.root) {
+            return Ok(());
+        }
+        let target = self.target(reference, file)?;
+        let component = self.components.get(&target).cloned();
+
+        // One of the spec's own components that is a reference to another
+        // file takes what it points to
+        let defines_component = file == self.root
+            && location.len() == 3
+            && location[0] == "components"
+            && component.as_ref() == Some(&(location[1].clone(), location[2].clone()));
+        if !defines_component {
+            if target.0 == self.root {
+                *value = reference_to(&format!("#{}", target.1));
+                return Ok(());
+            }
+            if let Some((kind, name)) = component {
+                *value = reference_to(&format!("#/components/{}/{}", kind, name));
+                return Ok(());
+            }
+            if let Some((kind, name)) = component_pointer(&target.1) {
+                let name = self.hoist_name(&kind, &name);
+                self.components
+                    .insert(target.clone(), (kind.clone(), name.clone()));
+                let mut component = self.load(&target)?;
+                self.resolve(&mut component, &target.0, &mut pointer_location(&target.1))?;
+                self.hoisted.push((kind.clone(), name.clone(), component));
+                *value = reference_to(&format!("#/components/{}/{}", kind, name));
+                return Ok(());
+            }
+        }
+
+        if let Some(start) = self.inlining.iter().position(|t| *t == target) {
+            let chain: Vec<String> = self.inlining[start..]
+                .iter()
+                .chain([&target])
+                .map(|target| self.describe(target))
+                .collect();
+            anyhow::bail!("Circular $ref: {}", chain.join(" -> "));
+        }
+        self.inlining.push(target.clone());
+        let mut inlined = self.load(&target)?;
+        self.resolve(&mut inlined, &target.0, &mut pointer_location(&target.1))?;
+        self.inlining.pop();
+        *value = inlined;
+        Ok(())
+    }
+
+    /// The file and pointer `reference`, made in `file`, points to.
+    fn target(&mut self, reference: &str, file: &Path) -> Result<Target> {
+        let (path, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+        if path.is_empty() {
+            return Ok((file.to_path_buf(), pointer.to_string()));
+        }
+        let joined = file.parent().unwrap_or(Path::new("")).join(path);
+        let canonical = canonical(&joined).with_context(|| {
+            format!(
+                "Failed to resolve $ref `{}` in {}",
+                reference,
+                self.relative(file)
+            )
+        })?;
+        Ok((canonical, pointer.to_string()))
+    }
+
+    /// The value `target` points to.
+    fn load(&mut self, target: &Target) -> Result<Value> {
+        let (file, pointer) = target;
+        if !self.documents.contains_key(file) {
+            let content = fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", self.relative(file)))?;
+            let document = parse(file, &content)
+                .with_context(|| format!("Failed to parse {}", self.relative(file)))?;
+            self.documents.insert(file.clone(), document);
+        }
+        let document = &self.documents[file];
+        lookup(document, pointer)
+            .cloned()
+            .ok_or_else(|| anyhow!("No `{}` in {}", pointer, self.relative(file)))
+    }
+
+    /// A name for a component brought in as `name`, unlike the spec's own.
+    fn hoist_name(&mut self, kind: &str, name: &str) -> String {
+        let mut candidate = name.to_string();
+        let mut suffix = 1;
+        while !self.names.insert(format!("{}/{}", kind, candidate)) {
+            suffix += 1;
+            candidate = format!("{}{}", name, suffix);
+        }
+        candidate
+    }
+
+    fn describe(&self, (file, pointer): &Target) -> String {
+        if pointer.is_empty() {
+            self.relative(file)
+        } else {
+            format!("{}#{}", self.relative(file), pointer)
+        }
+    }
+
+    /// `file` relative to the spec's directory, for messages.
+    fn relative(&self, file: &Path) -> String {
+        let base = self.root.parent().unwrap_or(Path::new(""));
+        file.strip_prefix(base)
+            .unwrap_or(file)
+            .display()
+            .to_string()
+    }
+}
+
+fn canonical(path: &Path) -> Result<PathBuf> {
+    fs::canonicalize(path).with_context(|| format!("No such file: {}", path.display()))
+}
+
+/// Parse a spec file: JSON by its extension, YAML otherwise.
+fn parse(file: &Path, content: &str) -> Result<Value> {
+    if file.extension().and_then(|s| s.to_str()) == Some("json") {
+        Ok(serde_json::from_str(content)?)
+    } else {
+        Ok(serde_yaml::from_str(content)?)
+    }
+}
+
+fn reference_of(value: &Value) -> Option<&str> {
+    value.as_mapping()?.get("$ref")?.as_str()
+}
+
+fn reference_to(reference: &str) -> Value {
+    let mut mapping = Mapping::new();
+    mapping.insert(Value::from("$ref"), Value::from(reference));
+    Value::Mapping(mapping)
+}
+
+fn is_local(reference: &str) -> bool {
+    reference.starts_with('#')
+}
+
+fn is_remote(reference: &str) -> bool {
+    reference.starts_with("http://") || reference.starts_with("https://")
+}
+
+/// `kind` and `name` of a `/components/<kind>/<name>` pointer.
+fn component_pointer(pointer: &str) -> Option<(String, String)> {
+    match pointer_location(pointer).as_slice() {
+        [components, kind, name] if components == "components" => {
+            Some((kind.clone(), name.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// The unescaped keys of a JSON pointer.
+fn pointer_location(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|key| key.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// The value at `pointer` in `document`. Keys match numeric keys too, such
+/// as unquoted response codes.
+fn lookup<'a>(document: &'a Value, pointer: &str) -> Option<&'a Value> {
+    pointer_location(pointer)
+        .iter()
+        .try_fold(document, |value, key| match value {
+            Value::Mapping(mapping) => mapping
+                .iter()
+                .find(|(candidate, _)| key_text(candidate) == *key)
+                .map(|(_, value)| value),
+            Value::Sequence(items) => items.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+fn key_text(key: &Value) -> String {
+    match key {
+        Value::String(text) => text.clone(),
+        Value::Number(number) => number.to_string(),
+        Value::Bool(value) => value.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// The mapping under `key` in `value`, created when missing.
+fn mapping_entry<'a>(value: &'a mut Value, key: &str) -> &'a mut Value {
+    let mapping = value.as_mapping_mut().expect("spec is a mapping");
+    if !mapping.contains_key(key) {
+        mapping.insert(Value::from(key), Value::Mapping(Mapping::new()));
+    }
+    mapping.get_mut(key).expect("just inserted")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_may_reference_files() {
+        assert!(may_reference_files("$ref: ./schemas/user.yaml"));
+        assert!(may_reference_files(r#""$ref": "other.json#/x""#));
+        assert!(may_reference_files(
+            "$ref: 'user.yaml#/components/schemas/User'"
+        ));
+        assert!(!may_reference_files("$ref: '#/components/schemas/User'"));
+        assert!(!may_reference_files(
+            r##"{"$ref":"#/components/schemas/User"}"##
+        ));
+    }
+
+    #[test]
+    fn test_lookup_unescapes_pointers() {
+        let document: Value = serde_yaml::from_str(
+            "paths:\n  /users/{id}:\n    get:\n      responses:\n        200:\n          description: OK\n",
+        )
+        .unwrap();
+        let found = lookup(
+            &document,
+            "/paths/~1users~1{id}/get/responses/200/description",
+        );
+        assert_eq!(found.and_then(Value::as_str), Some("OK"));
+        assert!(lookup(&document, "/paths/~1orders").is_none());
+        assert_eq!(lookup(&document, ""), Some(&document));
+    }
+
+    #[test]
+    fn test_component_pointer() {
+        assert_eq!(
+            component_pointer("/components/schemas/User"),
+            Some(("schemas".to_string(), "User".to_string()))
+        );
+        assert_eq!(component_pointer("/components/schemas"), None);
+        assert_eq!(component_pointer("/definitions/User"), None);
+        assert_eq!(component_pointer(""), None);
+    }
+}
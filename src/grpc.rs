@@ -1,62 +1,367 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use jsonpath_rust::JsonPathFinder;
 use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, FieldDescriptor, Kind, MessageDescriptor,
+    MethodDescriptor,
+};
 use prost_types::FileDescriptorSet;
+use protox::file::{ChainFileResolver, File, FileResolver, GoogleFileResolver};
+use serde::Serialize;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
-use tonic::transport::{Channel, Endpoint};
-// tonic imports are available for potential future use
+use tokio_rustls::rustls;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::{PathAndQuery, Uri};
+use tonic::metadata::{AsciiMetadataValue, BinaryMetadataValue, MetadataKey, MetadataMap};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint};
+use tonic::{Code, Status};
 use walkdir::WalkDir;
 
+/// Cheap to clone: clones share the connection and the compiled protos.
+#[derive(Clone)]
 pub struct GrpcClient {
-    #[allow(dead_code)] // Reserved for future gRPC communication
     channel: Channel,
-    #[allow(dead_code)] // Reserved for future dynamic protobuf handling
-    descriptors: FileDescriptorSet,
+    schema: Arc<ProtoSchema>,
+}
+
+/// Trust and server name settings for `https://` endpoints.
+#[derive(Debug, Clone, Default)]
+pub struct GrpcTlsOptions {
+    /// PEM bundle of extra CAs to trust
+    pub cacert: Option<PathBuf>,
+    /// Skip server certificate verification
+    pub insecure: bool,
+    /// Name to send as SNI and verify the certificate against, instead of the URL host
+    pub domain_override: Option<String>,
+}
+
+/// Services and message types compiled from a directory of `.proto` files.
+pub struct ProtoSchema {
+    pool: DescriptorPool,
     services: HashMap<String, ServiceInfo>,
 }
 
 #[derive(Debug, Clone)]
 struct ServiceInfo {
-    #[allow(dead_code)] // Reserved for future service metadata
+    /// Package-qualified name, used in the request path
     service_name: String,
     methods: HashMap<String, MethodInfo>,
 }
 
 #[derive(Debug, Clone)]
 struct MethodInfo {
-    #[allow(dead_code)] // Reserved for future method metadata
     method_name: String,
-    #[allow(dead_code)] // Reserved for future dynamic protobuf handling
     input_type: String,
-    #[allow(dead_code)] // Reserved for future dynamic protobuf handling
     output_type: String,
+    client_streaming: bool,
+    server_streaming: bool,
+}
+
+/// Which sides of a call stream messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    Unary,
+    ServerStreaming,
+    ClientStreaming,
+    BidiStreaming,
+}
+
+impl MethodInfo {
+    fn kind(&self) -> CallKind {
+        match (self.client_streaming, self.server_streaming) {
+            (false, false) => CallKind::Unary,
+            (false, true) => CallKind::ServerStreaming,
+            (true, false) => CallKind::ClientStreaming,
+            (true, true) => CallKind::BidiStreaming,
+        }
+    }
+}
+
+/// A service and its methods, as printed by `rivet grpc --list`.
+#[derive(Debug, Serialize)]
+pub struct ServiceSummary {
+    pub name: String,
+    pub methods: Vec<MethodSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MethodSummary {
+    pub name: String,
+    pub input_type: String,
+    pub output_type: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+}
+
+/// A method with every message and enum its request and response use, as
+/// printed by `rivet grpc --describe`.
+#[derive(Debug, Serialize)]
+pub struct MethodDescription {
+    pub service: String,
+    pub method: MethodSummary,
+    pub messages: Vec<MessageSummary>,
+    pub enums: Vec<EnumSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessageSummary {
+    pub name: String,
+    pub fields: Vec<FieldSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldSummary {
+    pub name: String,
+    pub json_name: String,
+    pub number: u32,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub repeated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnumSummary {
+    pub name: String,
+    pub values: Vec<EnumValueSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnumValueSummary {
+    pub name: String,
+    pub number: i32,
 }
 
 impl GrpcClient {
-    pub async fn new(proto_path: &Path, endpoint: &str) -> Result<Self> {
+    /// Compile the protos under `proto_path` and connect to `endpoint`.
+    /// Protos are compiled in-process unless `use_protoc` asks for the
+    /// external `protoc` binary; `https://` endpoints use TLS.
+    pub async fn new(
+        proto_path: &Path,
+        endpoint: &str,
+        use_protoc: bool,
+        tls: &GrpcTlsOptions,
+    ) -> Result<Self> {
         // Load and compile protobuf descriptors
-        let descriptors = Self::compile_protos(proto_path)?;
-        let services = Self::parse_services(&descriptors)?;
+        let schema = ProtoSchema::load(proto_path, use_protoc)?;
 
-        // Create gRPC channel - fail if server is unreachable
-        let channel = Endpoint::from_shared(endpoint.to_string())?
-            .connect()
-            .await
-            .map_err(|e| anyhow!("Failed to connect to gRPC server at {}: {}", endpoint, e))?;
+        // Create gRPC channel - fail if server is unreachable. The cause stays
+        // in the chain so the failure is reported as a connection error
+        let channel = Self::connect(endpoint, tls).await.map_err(|e| {
+            let message = format!("Failed to connect to gRPC server at {}: {}", endpoint, e);
+            e.context(message)
+        })?;
 
         Ok(Self {
             channel,
-            descriptors,
-            services,
+            schema: Arc::new(schema),
         })
     }
 
-    fn compile_protos(proto_path: &Path) -> Result<FileDescriptorSet> {
+    async fn connect(endpoint: &str, tls: &GrpcTlsOptions) -> Result<Channel> {
+        let uri: Uri = endpoint
+            .parse()
+            .map_err(|e| anyhow!("Invalid gRPC server address '{}': {}", endpoint, e))?;
+        let host = uri.host().unwrap_or_default().to_string();
+
+        if uri.scheme_str() != Some("https") {
+            if tls.cacert.is_some() || tls.insecure || tls.domain_override.is_some() {
+                return Err(anyhow!(
+                    "--cacert, --insecure and --domain-override need an https:// server address"
+                ));
+            }
+            return Ok(Endpoint::from(uri).connect().await?);
+        }
+
+        if tls.insecure {
+            // tonic only does verified TLS, so dial plain TCP and do the
+            // handshake in our own connector; `origin` keeps :scheme https
+            let port = uri.port_u16().unwrap_or(443);
+            let dial: Uri = format!("http://{}:{}", host, port).parse()?;
+            let server_name = tls.domain_override.clone().unwrap_or(host);
+            return Ok(Endpoint::from(dial)
+                .origin(uri)
+                .connect_with_connector(InsecureConnector { server_name })
+                .await?);
+        }
+
+        let mut config = ClientTlsConfig::new();
+        if let Some(cacert) = &tls.cacert {
+            let pem = fs::read(cacert)
+                .with_context(|| format!("Failed to read CA bundle {}", cacert.display()))?;
+            config = config.ca_certificate(Certificate::from_pem(pem));
+        }
+        if let Some(domain) = &tls.domain_override {
+            config = config.domain_name(domain.clone());
+        }
+        Ok(Endpoint::from(uri).tls_config(config)?.connect().await?)
+    }
+
+    /// Whether `Service/Method` is unary or streams on either side.
+    pub fn call_kind(&self, service_method: &str) -> Result<CallKind> {
+        Ok(self.schema.find_method(service_method)?.1.kind())
+    }
+
+    /// Make a call and return the response; a server-streaming call returns
+    /// the array of every message received.
+    pub async fn call(
+        &mut self,
+        service_method: &str,
+        request_data: Option<&str>,
+        metadata: &MetadataMap,
+        timeout: Duration,
+    ) -> Result<Value> {
+        self.call_streaming(service_method, request_data, metadata, timeout, |_| {})
+            .await
+    }
+
+    /// Like [`GrpcClient::call`], handing each server-streamed message to
+    /// `on_message` as it arrives. Client-streaming calls send every message
+    /// in `request_data`: a JSON array, or one JSON value per line.
+    pub async fn call_streaming(
+        &mut self,
+        service_method: &str,
+        request_data: Option<&str>,
+        metadata: &MetadataMap,
+        timeout: Duration,
+        on_message: impl FnMut(&Value),
+    ) -> Result<Value> {
+        let (service, method) = self.schema.find_method(service_method)?;
+        let kind = method.kind();
+        if kind == CallKind::BidiStreaming {
+            return Err(anyhow!(
+                "'{}' is a bidirectional streaming method; bidirectional streaming is not supported yet",
+                service_method
+            ));
+        }
+
+        let path = format!("/{}/{}", service.service_name, method.method_name);
+        let input = self.schema.message_descriptor(&method.input_type)?;
+        let output = self.schema.message_descriptor(&method.output_type)?;
+        let requests = parse_request_messages(request_data, kind == CallKind::ClientStreaming)?
+            .iter()
+            .map(|json| json_to_message(input.clone(), json))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Make the actual gRPC call with timeout, covering the whole stream
+        let call = self.make_grpc_call(&path, kind, requests, metadata, output, on_message);
+        tokio::time::timeout(timeout, call)
+            .await
+            .map_err(|elapsed| {
+                anyhow::Error::new(elapsed)
+                    .context(format!("gRPC call timed out after {:?}", timeout))
+            })?
+    }
+
+    async fn make_grpc_call(
+        &mut self,
+        path: &str,
+        kind: CallKind,
+        mut requests: Vec<DynamicMessage>,
+        metadata: &MetadataMap,
+        output: MessageDescriptor,
+        mut on_message: impl FnMut(&Value),
+    ) -> Result<Value> {
+        let path = PathAndQuery::try_from(path.to_string())
+            .map_err(|e| anyhow!("Invalid gRPC method path '{}': {}", path, e))?;
+
+        let mut grpc = tonic::client::Grpc::new(self.channel.clone());
+        grpc.ready()
+            .await
+            .map_err(|e| anyhow!("gRPC channel not ready: {}", e))?;
+        let codec = DynamicCodec { output };
+        let pool = &self.schema.pool;
+
+        if kind == CallKind::ClientStreaming {
+            let mut request = tonic::Request::new(tokio_stream::iter(requests));
+            *request.metadata_mut() = metadata.clone();
+            let response = grpc
+                .client_streaming(request, path, codec)
+                .await
+                .map_err(|status| status_error(status, pool))?;
+            return Ok(serde_json::to_value(response.into_inner())?);
+        }
+
+        let mut request = tonic::Request::new(requests.remove(0));
+        *request.metadata_mut() = metadata.clone();
+
+        if kind == CallKind::ServerStreaming {
+            let mut stream = grpc
+                .server_streaming(request, path, codec)
+                .await
+                .map_err(|status| status_error(status, pool))?
+                .into_inner();
+            let mut messages = Vec::new();
+            while let Some(message) = stream
+                .message()
+                .await
+                .map_err(|status| status_error(status, pool))?
+            {
+                let message = serde_json::to_value(message)?;
+                on_message(&message);
+                messages.push(message);
+            }
+            return Ok(Value::Array(messages));
+        }
+
+        let response = grpc
+            .unary(request, path, codec)
+            .await
+            .map_err(|status| status_error(status, pool))?;
+
+        Ok(serde_json::to_value(response.into_inner())?)
+    }
+
+    pub fn list_services(&self) -> Vec<String> {
+        self.schema.list_services()
+    }
+
+    pub fn validate_expectations(
+        &self,
+        response: &Value,
+        expectations: &[String],
+    ) -> Result<Vec<String>> {
+        let mut results = Vec::new();
+
+        for expectation in expectations {
+            let response_str = serde_json::to_string(response)?;
+            let finder = JsonPathFinder::from_str(&response_str, expectation)
+                .map_err(|e| anyhow!("JSONPath error: {}", e))?;
+            let found = finder.find();
+
+            match found {
+                Value::Null => {
+                    results.push(format!("❌ JSONPath '{}' not found", expectation));
+                }
+                _ => {
+                    results.push(format!("✅ JSONPath '{}' found: {}", expectation, found));
+                }
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+impl ProtoSchema {
+    /// Compile the protos under `proto_path`, in-process unless `use_protoc`
+    /// asks for the external `protoc` binary.
+    pub fn load(proto_path: &Path, use_protoc: bool) -> Result<Self> {
+        let descriptors = Self::compile_protos(proto_path, use_protoc)?;
+        let services = Self::parse_services(&descriptors)?;
+        let pool = DescriptorPool::from_file_descriptor_set(descriptors)
+            .map_err(|e| anyhow!("Invalid proto descriptors: {}", e))?;
+
+        Ok(Self { pool, services })
+    }
+
+    fn compile_protos(proto_path: &Path, use_protoc: bool) -> Result<FileDescriptorSet> {
         if !proto_path.exists() {
             return Err(anyhow!(
                 "Proto directory does not exist: {}",
@@ -82,6 +387,32 @@ impl GrpcClient {
             ));
         }
 
+        if use_protoc {
+            Self::compile_with_protoc(proto_path, &proto_files)
+        } else {
+            Self::compile_with_protox(proto_path, &proto_files)
+        }
+    }
+
+    fn compile_with_protox(
+        proto_path: &Path,
+        proto_files: &[PathBuf],
+    ) -> Result<FileDescriptorSet> {
+        // Same as `protoc --proto_path=<dir> --include_imports --include_source_info`
+        let mut compiler = protox::Compiler::new([proto_path])
+            .map_err(|e| anyhow!("Failed to compile proto files: {}", e))?;
+        compiler.include_imports(true).include_source_info(true);
+        compiler
+            .open_files(proto_files)
+            .map_err(|e| anyhow!("Failed to compile proto files: {}", e))?;
+
+        Ok(compiler.file_descriptor_set())
+    }
+
+    fn compile_with_protoc(
+        proto_path: &Path,
+        proto_files: &[PathBuf],
+    ) -> Result<FileDescriptorSet> {
         // Create temporary directory for compilation
         let temp_dir = tempfile::tempdir()?;
         let descriptor_path = temp_dir.path().join("descriptors.pb");
@@ -97,11 +428,13 @@ impl GrpcClient {
         cmd.arg(format!("--proto_path={}", proto_path.display()));
 
         // Add proto files
-        for proto_file in &proto_files {
+        for proto_file in proto_files {
             cmd.arg(proto_file);
         }
 
-        let output = cmd.output()?;
+        let output = cmd
+            .output()
+            .map_err(|e| anyhow!("Failed to run protoc (is it installed?): {}", e))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -119,8 +452,14 @@ impl GrpcClient {
         let mut services = HashMap::new();
 
         for file_desc in &descriptors.file {
+            let package = file_desc.package.clone().unwrap_or_default();
             for service_desc in &file_desc.service {
                 let service_name = service_desc.name.clone().unwrap_or_default();
+                let full_name = if package.is_empty() {
+                    service_name.clone()
+                } else {
+                    format!("{}.{}", package, service_name)
+                };
                 let mut methods = HashMap::new();
 
                 for method_desc in &service_desc.method {
@@ -134,14 +473,16 @@ impl GrpcClient {
                             method_name,
                             input_type,
                             output_type,
+                            client_streaming: method_desc.client_streaming.unwrap_or(false),
+                            server_streaming: method_desc.server_streaming.unwrap_or(false),
                         },
                     );
                 }
 
                 services.insert(
-                    service_name.clone(),
+                    service_name,
                     ServiceInfo {
-                        service_name,
+                        service_name: full_name,
                         methods,
                     },
                 );
@@ -151,12 +492,105 @@ impl GrpcClient {
         Ok(services)
     }
 
-    pub async fn call(
-        &mut self,
-        service_method: &str,
-        request_data: Option<&str>,
-        timeout: Duration,
-    ) -> Result<Value> {
+    pub fn list_services(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.services.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Every service with its methods, sorted by package-qualified name.
+    pub fn services(&self) -> Vec<ServiceSummary> {
+        let mut services: Vec<ServiceSummary> = self
+            .pool
+            .services()
+            .map(|service| ServiceSummary {
+                name: service.full_name().to_string(),
+                methods: service.methods().map(|m| method_summary(&m)).collect(),
+            })
+            .collect();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        services
+    }
+
+    /// Describe `Service/Method` along with the messages and enums reachable
+    /// from its request and response types.
+    pub fn describe(&self, service_method: &str) -> Result<MethodDescription> {
+        let (service, method) = self.find_method(service_method)?;
+        let method_desc = self
+            .pool
+            .get_service_by_name(&service.service_name)
+            .and_then(|s| s.methods().find(|m| m.name() == method.method_name))
+            .ok_or_else(|| anyhow!("Method '{}' not found in proto files", service_method))?;
+
+        let mut messages = Vec::new();
+        let mut enums = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        let mut queue =
+            std::collections::VecDeque::from([method_desc.input(), method_desc.output()]);
+
+        while let Some(message) = queue.pop_front() {
+            if !seen.insert(message.full_name().to_string()) {
+                continue;
+            }
+
+            let mut fields = Vec::new();
+            for field in message.fields() {
+                let mut kinds = vec![field.kind()];
+                if let Kind::Message(entry) = field.kind() {
+                    if field.is_map() {
+                        kinds = vec![
+                            entry.map_entry_key_field().kind(),
+                            entry.map_entry_value_field().kind(),
+                        ];
+                    }
+                }
+                for kind in kinds {
+                    match kind {
+                        // Well-known types have their own JSON shapes, no need to expand them
+                        Kind::Message(m) if !m.full_name().starts_with("google.protobuf.") => {
+                            queue.push_back(m)
+                        }
+                        Kind::Enum(e) if seen.insert(e.full_name().to_string()) => {
+                            enums.push(EnumSummary {
+                                name: e.full_name().to_string(),
+                                values: e
+                                    .values()
+                                    .map(|v| EnumValueSummary {
+                                        name: v.name().to_string(),
+                                        number: v.number(),
+                                    })
+                                    .collect(),
+                            })
+                        }
+                        _ => {}
+                    }
+                }
+
+                fields.push(FieldSummary {
+                    name: field.name().to_string(),
+                    json_name: field.json_name().to_string(),
+                    number: field.number(),
+                    type_name: field_type_name(&field),
+                    repeated: field.is_list(),
+                });
+            }
+
+            messages.push(MessageSummary {
+                name: message.full_name().to_string(),
+                fields,
+            });
+        }
+
+        Ok(MethodDescription {
+            service: service.service_name.clone(),
+            method: method_summary(&method_desc),
+            messages,
+            enums,
+        })
+    }
+
+    /// Look up `Service/Method`, by short or package-qualified service name.
+    fn find_method(&self, service_method: &str) -> Result<(&ServiceInfo, &MethodInfo)> {
         // Validate service/method format
         let parts: Vec<&str> = service_method.split('/').collect();
         if parts.len() != 2 || parts[0].is_empty() || parts[1].is_empty() {
@@ -167,93 +601,488 @@ impl GrpcClient {
         let (service_name, method_name) = (parts[0], parts[1]);
 
         // Check if service exists
-        let service = self
-            .services
-            .get(service_name)
-            .ok_or_else(|| anyhow!("Service '{}' not found in proto files", service_name))?;
+        let service =
+            self.services
+                .get(service_name)
+                .or_else(|| {
+                    self.services
+                        .values()
+                        .find(|service| service.service_name == service_name)
+                })
+                .ok_or_else(|| {
+                    let candidates = self.services.iter().flat_map(|(short, service)| {
+                        [short.as_str(), service.service_name.as_str()]
+                    });
+                    anyhow!(
+                        "Service '{}' not found in proto files{}",
+                        service_name,
+                        suggestions(service_name, candidates)
+                    )
+                })?;
 
         // Check if method exists
-        let _method = service.methods.get(method_name).ok_or_else(|| {
+        let method = service.methods.get(method_name).ok_or_else(|| {
             anyhow!(
-                "Method '{}' not found in service '{}'",
+                "Method '{}' not found in service '{}'{}",
                 method_name,
-                service_name
+                service_name,
+                suggestions(method_name, service.methods.keys().map(String::as_str))
             )
         })?;
 
-        // Parse request data
-        let request_json: Value = match request_data {
-            Some(data) => serde_json::from_str(data)
-                .map_err(|e| anyhow!("Invalid JSON request data: {}", e))?,
-            None => Value::Object(serde_json::Map::new()),
-        };
+        Ok((service, method))
+    }
 
-        // Make the actual gRPC call with timeout
-        tokio::time::timeout(
-            timeout,
-            self.make_grpc_call(service_name, method_name, &request_json),
-        )
-        .await
-        .map_err(|_| anyhow!("gRPC call timed out after {:?}", timeout))?
+    fn message_descriptor(&self, type_name: &str) -> Result<MessageDescriptor> {
+        let name = type_name.trim_start_matches('.');
+        self.pool
+            .get_message_by_name(name)
+            .ok_or_else(|| anyhow!("Message type '{}' not found in proto files", name))
     }
+}
 
-    async fn make_grpc_call(
-        &mut self,
-        service_name: &str,
-        method_name: &str,
-        _request_data: &Value,
-    ) -> Result<Value> {
-        // For now, this is a placeholder that demonstrates the structure
-        // In a full implementation, you would:
-        // 1. Convert JSON to protobuf message using the type information
-        // 2. Make the actual gRPC call using tonic
-        // 3. Convert the protobuf response back to JSON
+fn method_summary(method: &MethodDescriptor) -> MethodSummary {
+    MethodSummary {
+        name: method.name().to_string(),
+        input_type: method.input().full_name().to_string(),
+        output_type: method.output().full_name().to_string(),
+        client_streaming: method.is_client_streaming(),
+        server_streaming: method.is_server_streaming(),
+    }
+}
 
-        // This requires dynamic protobuf handling which is complex
-        // For demonstration, return an error indicating the service is not mocked
-        Err(anyhow!(
-            "Real gRPC call to {}/{} not yet implemented. This requires dynamic protobuf message handling.",
-            service_name,
-            method_name
-        ))
+/// Proto-style type of a field: `string`, `pkg.Message`, `map<string, int32>`.
+fn field_type_name(field: &FieldDescriptor) -> String {
+    fn kind_name(kind: Kind) -> String {
+        match kind {
+            Kind::Double => "double".to_string(),
+            Kind::Float => "float".to_string(),
+            Kind::Int32 => "int32".to_string(),
+            Kind::Int64 => "int64".to_string(),
+            Kind::Uint32 => "uint32".to_string(),
+            Kind::Uint64 => "uint64".to_string(),
+            Kind::Sint32 => "sint32".to_string(),
+            Kind::Sint64 => "sint64".to_string(),
+            Kind::Fixed32 => "fixed32".to_string(),
+            Kind::Fixed64 => "fixed64".to_string(),
+            Kind::Sfixed32 => "sfixed32".to_string(),
+            Kind::Sfixed64 => "sfixed64".to_string(),
+            Kind::Bool => "bool".to_string(),
+            Kind::String => "string".to_string(),
+            Kind::Bytes => "bytes".to_string(),
+            Kind::Message(m) => m.full_name().to_string(),
+            Kind::Enum(e) => e.full_name().to_string(),
+        }
     }
 
-    pub fn list_services(&self) -> Vec<String> {
-        self.services.keys().cloned().collect()
+    match field.kind() {
+        Kind::Message(entry) if field.is_map() => format!(
+            "map<{}, {}>",
+            kind_name(entry.map_entry_key_field().kind()),
+            kind_name(entry.map_entry_value_field().kind())
+        ),
+        kind => kind_name(kind),
     }
+}
+
+/// " Did you mean: a, b?" for the candidates that look like `name`, or the
+/// full list when nothing is close.
+fn suggestions<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> String {
+    let mut candidates: Vec<&str> = candidates.collect();
+    candidates.sort();
+    candidates.dedup();
 
-    #[allow(dead_code)] // Reserved for future gRPC introspection functionality
-    pub fn list_methods(&self, service_name: &str) -> Vec<String> {
-        self.services
-            .get(service_name)
-            .map(|service| service.methods.keys().cloned().collect())
-            .unwrap_or_default()
+    let needle = name.to_lowercase();
+    let mut close: Vec<(f64, &str)> = candidates
+        .iter()
+        .map(|c| (strsim::jaro_winkler(&needle, &c.to_lowercase()), *c))
+        .filter(|(score, _)| *score >= 0.8)
+        .collect();
+    close.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    if !close.is_empty() {
+        let names: Vec<&str> = close.iter().take(3).map(|(_, c)| *c).collect();
+        format!(". Did you mean: {}?", names.join(", "))
+    } else if candidates.is_empty() {
+        String::new()
+    } else {
+        format!(". Available: {}", candidates.join(", "))
     }
+}
 
-    pub fn validate_expectations(
-        &self,
-        response: &Value,
-        expectations: &[String],
-    ) -> Result<Vec<String>> {
-        let mut results = Vec::new();
+/// A failed call's error, with any `google.rpc.Status` details decoded using
+/// the standard error types and then the types in `pool`.
+fn status_error(status: Status, pool: &DescriptorPool) -> anyhow::Error {
+    let details = decode_status_details(status.details(), pool);
+    StatusError { status, details }.into()
+}
 
-        for expectation in expectations {
-            let response_str = serde_json::to_string(response)?;
-            let finder = JsonPathFinder::from_str(&response_str, expectation)
-                .map_err(|e| anyhow!("JSONPath error: {}", e))?;
-            let found = finder.find();
+/// A call the server answered with a non-OK status. Kept as its own error
+/// type so callers can recover the status code and details.
+#[derive(Debug)]
+pub struct StatusError {
+    pub status: Status,
+    /// Decoded `grpc-status-details-bin` entries in the JSON mapping, each
+    /// with an `@type`
+    pub details: Vec<Value>,
+}
 
-            match found {
-                Value::Null => {
-                    results.push(format!("❌ JSONPath '{}' not found", expectation));
+impl StatusError {
+    /// The failure as JSON, in the shape of Google's error model, so
+    /// expectations such as `$.error.status` can inspect it.
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "error": {
+                "code": self.status.code() as i32,
+                "status": code_name(self.status.code()),
+                "message": self.status.message(),
+                "details": self.details,
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for StatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "gRPC call failed with status {}: {}",
+            code_name(self.status.code()),
+            self.status.message()
+        )
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+/// `google.rpc.Status`, the payload of `grpc-status-details-bin`.
+#[derive(Clone, PartialEq, Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<prost_types::Any>,
+}
+
+const ERROR_DETAILS_PROTO: &str = "google/rpc/error_details.proto";
+
+/// The standard `google.rpc` error detail messages.
+fn error_details_pool() -> &'static DescriptorPool {
+    static POOL: OnceLock<DescriptorPool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        struct ErrorDetailsResolver;
+
+        impl FileResolver for ErrorDetailsResolver {
+            fn open_file(&self, name: &str) -> std::result::Result<File, protox::Error> {
+                match name {
+                    ERROR_DETAILS_PROTO => File::from_source(
+                        name,
+                        include_str!("../proto/google/rpc/error_details.proto"),
+                    ),
+                    _ => Err(protox::Error::file_not_found(name)),
                 }
+            }
+        }
+
+        let mut resolver = ChainFileResolver::new();
+        resolver.add(ErrorDetailsResolver);
+        resolver.add(GoogleFileResolver::new());
+        protox::Compiler::with_file_resolver(resolver)
+            .open_file(ERROR_DETAILS_PROTO)
+            .expect("embedded error_details.proto compiles")
+            .descriptor_pool()
+    })
+}
+
+/// Decode `google.rpc.Status` details. Types found in neither the standard
+/// error details nor `pool` keep their raw bytes, base64 encoded.
+fn decode_status_details(bytes: &[u8], pool: &DescriptorPool) -> Vec<Value> {
+    let Ok(status) = RpcStatus::decode(bytes) else {
+        return Vec::new();
+    };
+
+    status
+        .details
+        .iter()
+        .map(|any| {
+            let type_name = any.type_url.rsplit('/').next().unwrap_or_default();
+            let decoded = [error_details_pool(), pool]
+                .into_iter()
+                .find_map(|pool| pool.get_message_by_name(type_name))
+                .and_then(|desc| DynamicMessage::decode(desc, any.value.as_slice()).ok())
+                .and_then(|message| serde_json::to_value(message).ok());
+            let mut detail = serde_json::Map::new();
+            detail.insert("@type".to_string(), Value::String(any.type_url.clone()));
+            match decoded {
+                Some(Value::Object(fields)) => detail.extend(fields),
                 _ => {
-                    results.push(format!("✅ JSONPath '{}' found: {}", expectation, found));
+                    detail.insert(
+                        "value".to_string(),
+                        Value::String(BASE64.encode(&any.value)),
+                    );
                 }
             }
+            Value::Object(detail)
+        })
+        .collect()
+}
+
+/// The canonical name of a status code, e.g. `NOT_FOUND`.
+pub fn code_name(code: Code) -> &'static str {
+    match code {
+        Code::Ok => "OK",
+        Code::Cancelled => "CANCELLED",
+        Code::Unknown => "UNKNOWN",
+        Code::InvalidArgument => "INVALID_ARGUMENT",
+        Code::DeadlineExceeded => "DEADLINE_EXCEEDED",
+        Code::NotFound => "NOT_FOUND",
+        Code::AlreadyExists => "ALREADY_EXISTS",
+        Code::PermissionDenied => "PERMISSION_DENIED",
+        Code::ResourceExhausted => "RESOURCE_EXHAUSTED",
+        Code::FailedPrecondition => "FAILED_PRECONDITION",
+        Code::Aborted => "ABORTED",
+        Code::OutOfRange => "OUT_OF_RANGE",
+        Code::Unimplemented => "UNIMPLEMENTED",
+        Code::Internal => "INTERNAL",
+        Code::Unavailable => "UNAVAILABLE",
+        Code::DataLoss => "DATA_LOSS",
+        Code::Unauthenticated => "UNAUTHENTICATED",
+    }
+}
+
+/// Parse a status code given by name (`NOT_FOUND`, `not_found`) or number (`5`).
+pub fn parse_status_code(text: &str) -> Result<Code> {
+    let text = text.trim();
+    if let Ok(number) = text.parse::<i32>() {
+        return match Code::from_i32(number) {
+            Code::Unknown if number != Code::Unknown as i32 => {
+                Err(anyhow!("Invalid gRPC status code: {}", number))
+            }
+            code => Ok(code),
+        };
+    }
+    (0..=16)
+        .map(Code::from_i32)
+        .find(|code| code_name(*code).eq_ignore_ascii_case(text))
+        .ok_or_else(|| {
+            anyhow!(
+                "Invalid gRPC status code '{}' (expected a name such as NOT_FOUND or a number)",
+                text
+            )
+        })
+}
+
+/// The request messages in `data`. Without data a call sends one empty
+/// message, or none when streaming from the client; a client stream takes
+/// a JSON array or JSON lines.
+fn parse_request_messages(data: Option<&str>, client_streaming: bool) -> Result<Vec<Value>> {
+    let Some(data) = data else {
+        return Ok(if client_streaming {
+            Vec::new()
+        } else {
+            vec![Value::Object(serde_json::Map::new())]
+        });
+    };
+
+    if !client_streaming {
+        let value =
+            serde_json::from_str(data).map_err(|e| anyhow!("Invalid JSON request data: {}", e))?;
+        return Ok(vec![value]);
+    }
+
+    if data.trim_start().starts_with('[') {
+        return serde_json::from_str::<Vec<Value>>(data)
+            .map_err(|e| anyhow!("Invalid JSON request data: {}", e));
+    }
+
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| anyhow!("Invalid JSON on line {} of request data: {}", index + 1, e))
+        })
+        .collect()
+}
+
+/// Parse `key: value` pairs into request metadata. Values of `-bin` keys
+/// are base64 and sent as binary metadata.
+pub fn parse_metadata(entries: &[String]) -> Result<MetadataMap> {
+    let mut metadata = MetadataMap::new();
+
+    for entry in entries {
+        let (key, value) = entry
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid metadata format: {} (expected 'key: value')", entry))?;
+        let (key, value) = (key.trim().to_lowercase(), value.trim());
+
+        if key.ends_with("-bin") {
+            let bytes = BASE64
+                .decode(value)
+                .map_err(|e| anyhow!("Metadata '{}' must be base64: {}", key, e))?;
+            let key = MetadataKey::from_bytes(key.as_bytes())
+                .map_err(|_| anyhow!("Invalid metadata key: {}", key))?;
+            metadata.append_bin(key, BinaryMetadataValue::from_bytes(&bytes));
+        } else {
+            let name = MetadataKey::from_bytes(key.as_bytes())
+                .map_err(|_| anyhow!("Invalid metadata key: {}", key))?;
+            let value = AsciiMetadataValue::try_from(value)
+                .map_err(|_| anyhow!("Invalid value for metadata '{}'", key))?;
+            metadata.append(name, value);
         }
+    }
 
-        Ok(results)
+    Ok(metadata)
+}
+
+/// Dials TCP and does a TLS handshake that accepts any server certificate,
+/// for `--insecure`.
+struct InsecureConnector {
+    server_name: String,
+}
+
+impl tonic::codegen::Service<Uri> for InsecureConnector {
+    type Response = tokio_rustls::client::TlsStream<tokio::net::TcpStream>;
+    type Error = std::io::Error;
+    type Future = tonic::codegen::BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let server_name = self.server_name.clone();
+        Box::pin(async move {
+            let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, e);
+            let host = uri.host().unwrap_or_default().to_string();
+            let port = uri.port_u16().unwrap_or(443);
+            let tcp = tokio::net::TcpStream::connect((host.as_str(), port)).await?;
+
+            let mut config = rustls::ClientConfig::builder()
+                .with_safe_defaults()
+                .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate))
+                .with_no_client_auth();
+            config.alpn_protocols = vec![b"h2".to_vec()];
+
+            let name = rustls::ServerName::try_from(server_name.as_str())
+                .map_err(|e| invalid(format!("Invalid server name '{}': {}", server_name, e)))?;
+            tokio_rustls::TlsConnector::from(Arc::new(config))
+                .connect(name, tcp)
+                .await
+        })
+    }
+}
+
+struct AcceptAnyCertificate;
+
+impl rustls::client::ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: std::time::SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+/// Build a request message from JSON, using the proto3 JSON mapping.
+fn json_to_message(desc: MessageDescriptor, json: &Value) -> Result<DynamicMessage> {
+    check_unknown_fields(&desc, json)?;
+    DynamicMessage::deserialize_with_options(desc.clone(), json, &DeserializeOptions::new())
+        .map_err(|e| anyhow!("Invalid request for message '{}': {}", desc.full_name(), e))
+}
+
+/// Reject JSON keys that are not fields of the message, naming the message
+/// type they were found in (serde only reports the field).
+fn check_unknown_fields(desc: &MessageDescriptor, json: &Value) -> Result<()> {
+    // Well-known types have their own JSON shapes (Struct, Any, Timestamp...)
+    if desc.full_name().starts_with("google.protobuf.") {
+        return Ok(());
+    }
+    let Value::Object(object) = json else {
+        return Ok(());
+    };
+
+    for (key, value) in object {
+        let field = desc
+            .get_field_by_json_name(key)
+            .or_else(|| desc.get_field_by_name(key))
+            .ok_or_else(|| anyhow!("Unknown field '{}' in message '{}'", key, desc.full_name()))?;
+
+        let Kind::Message(field_desc) = field.kind() else {
+            continue;
+        };
+        if field.is_map() {
+            if let Kind::Message(value_desc) = field_desc.map_entry_value_field().kind() {
+                if let Value::Object(entries) = value {
+                    for entry in entries.values() {
+                        check_unknown_fields(&value_desc, entry)?;
+                    }
+                }
+            }
+        } else if let Value::Array(items) = value {
+            for item in items {
+                check_unknown_fields(&field_desc, item)?;
+            }
+        } else {
+            check_unknown_fields(&field_desc, value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Encodes and decodes [`DynamicMessage`]s, so calls don't need generated code.
+struct DynamicCodec {
+    output: MessageDescriptor,
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        DynamicEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder(self.output.clone())
+    }
+}
+
+struct DynamicEncoder;
+
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, buf: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(buf)
+            .map_err(|e| Status::internal(format!("Failed to encode request: {}", e)))
+    }
+}
+
+struct DynamicDecoder(MessageDescriptor);
+
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, buf: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.0.clone(), buf)
+            .map(Some)
+            .map_err(|e| Status::internal(format!("Failed to decode response: {}", e)))
     }
 }
 
@@ -266,7 +1095,7 @@ mod tests {
     #[test]
     fn test_proto_compilation_no_directory() {
         let non_existent_path = std::path::Path::new("/non/existent/path");
-        let result = GrpcClient::compile_protos(non_existent_path);
+        let result = ProtoSchema::compile_protos(non_existent_path, false);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("does not exist"));
     }
@@ -278,7 +1107,7 @@ mod tests {
         // Create some non-proto files
         fs::write(temp_dir.path().join("test.txt"), "not a proto file").unwrap();
 
-        let result = GrpcClient::compile_protos(temp_dir.path());
+        let result = ProtoSchema::compile_protos(temp_dir.path(), false);
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -286,6 +1115,159 @@ mod tests {
             .contains("No .proto files found"));
     }
 
+    #[test]
+    fn test_proto_compilation_resolves_imports() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("common")).unwrap();
+        fs::write(
+            temp_dir.path().join("common/user.proto"),
+            "syntax = \"proto3\";\npackage common;\nmessage User { string id = 1; }\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("users.proto"),
+            r#"
+syntax = "proto3";
+package svc;
+import "common/user.proto";
+import "google/protobuf/empty.proto";
+service Users {
+  rpc GetUser(google.protobuf.Empty) returns (common.User);
+}
+"#,
+        )
+        .unwrap();
+
+        let descriptors = ProtoSchema::compile_protos(temp_dir.path(), false).unwrap();
+        let services = ProtoSchema::parse_services(&descriptors).unwrap();
+
+        let users = &services["Users"];
+        assert_eq!(users.service_name, "svc.Users");
+        assert_eq!(users.methods["GetUser"].output_type, ".common.User");
+        assert!(DescriptorPool::from_file_descriptor_set(descriptors).is_ok());
+    }
+
+    fn users_schema(dir: &Path) -> ProtoSchema {
+        fs::write(
+            dir.join("users.proto"),
+            r#"
+syntax = "proto3";
+package svc;
+service Users {
+  rpc GetUser(GetUserRequest) returns (User);
+  rpc WatchUsers(GetUserRequest) returns (stream User);
+}
+enum Role { ROLE_UNSPECIFIED = 0; ADMIN = 1; }
+message GetUserRequest { string user_id = 1; }
+message User {
+  string id = 1;
+  Role role = 2;
+  map<string, Tag> tags = 3;
+  repeated string emails = 4;
+}
+message Tag { string value = 1; }
+"#,
+        )
+        .unwrap();
+        ProtoSchema::load(dir, false).unwrap()
+    }
+
+    #[test]
+    fn test_schema_lists_services() {
+        let temp_dir = TempDir::new().unwrap();
+        let services = users_schema(temp_dir.path()).services();
+
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].name, "svc.Users");
+        let watch = &services[0].methods[1];
+        assert_eq!(watch.name, "WatchUsers");
+        assert_eq!(watch.output_type, "svc.User");
+        assert!(watch.server_streaming && !watch.client_streaming);
+    }
+
+    #[test]
+    fn test_schema_describe_method() {
+        let temp_dir = TempDir::new().unwrap();
+        let description = users_schema(temp_dir.path())
+            .describe("svc.Users/GetUser")
+            .unwrap();
+
+        let names: Vec<&str> = description
+            .messages
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["svc.GetUserRequest", "svc.User", "svc.Tag"]);
+
+        let user = &description.messages[1];
+        let types: Vec<&str> = user.fields.iter().map(|f| f.type_name.as_str()).collect();
+        assert_eq!(
+            types,
+            vec!["string", "svc.Role", "map<string, svc.Tag>", "string"]
+        );
+        assert!(user.fields[3].repeated);
+        assert_eq!(description.messages[0].fields[0].json_name, "userId");
+
+        assert_eq!(description.enums[0].name, "svc.Role");
+        assert_eq!(description.enums[0].values[1].name, "ADMIN");
+    }
+
+    #[test]
+    fn test_schema_suggests_close_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        let schema = users_schema(temp_dir.path());
+
+        let err = schema.describe("Usres/GetUser").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Service 'Usres' not found in proto files. Did you mean: Users?"
+        );
+
+        let err = schema.describe("Users/GetUsr").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Method 'GetUsr' not found in service 'Users'. Did you mean: GetUser?"
+        );
+
+        let err = schema.describe("Billing/Charge").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Service 'Billing' not found in proto files. Available: Users, svc.Users"
+        );
+    }
+
+    #[test]
+    fn test_proto_compilation_syntax_error() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("broken.proto"),
+            "syntax = \"proto3\";\nmessage Broken { string id = ; }\n",
+        )
+        .unwrap();
+
+        let err = ProtoSchema::compile_protos(temp_dir.path(), false).unwrap_err();
+        assert!(err.to_string().starts_with("Failed to compile proto files"));
+    }
+
+    #[test]
+    fn test_parse_request_messages() {
+        let single = parse_request_messages(Some(r#"{"id": 1}"#), false).unwrap();
+        assert_eq!(single, vec![serde_json::json!({"id": 1})]);
+        assert_eq!(
+            parse_request_messages(None, false).unwrap(),
+            vec![serde_json::json!({})]
+        );
+        assert!(parse_request_messages(None, true).unwrap().is_empty());
+
+        let array = parse_request_messages(Some(r#" [{"id": 1}, {"id": 2}]"#), true).unwrap();
+        assert_eq!(array.len(), 2);
+        let lines = parse_request_messages(Some("{\"id\": 1}\n\n{\"id\": 2}\n"), true).unwrap();
+        assert_eq!(lines, array);
+
+        let err = parse_request_messages(Some("{\"id\": 1}\n{oops"), true).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid JSON on line 2"));
+    }
+
     #[test]
     fn test_validate_service_method_format() {
         let invalid_formats = vec![
@@ -344,4 +1326,85 @@ mod tests {
         let found = finder.find();
         assert_eq!(found, serde_json::Value::Null);
     }
+
+    fn nested_pool() -> DescriptorPool {
+        use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto};
+
+        let field = |name: &str, number: i32, type_name: Option<&str>| FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(if type_name.is_some() { 3 } else { 1 }),
+            r#type: Some(if type_name.is_some() { 11 } else { 9 }),
+            type_name: type_name.map(str::to_string),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("orders.proto".to_string()),
+            package: Some("shop".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![
+                DescriptorProto {
+                    name: Some("Order".to_string()),
+                    field: vec![
+                        field("order_id", 1, None),
+                        field("items", 2, Some(".shop.Item")),
+                    ],
+                    ..Default::default()
+                },
+                DescriptorProto {
+                    name: Some("Item".to_string()),
+                    field: vec![field("sku", 1, None)],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: vec![file] }).unwrap()
+    }
+
+    #[test]
+    fn test_json_to_message_unknown_fields() {
+        let order = nested_pool().get_message_by_name("shop.Order").unwrap();
+
+        let message = json_to_message(
+            order.clone(),
+            &serde_json::json!({"orderId": "o-1", "items": [{"sku": "a"}]}),
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({"orderId": "o-1", "items": [{"sku": "a"}]})
+        );
+
+        let err = json_to_message(
+            order,
+            &serde_json::json!({"order_id": "o-1", "items": [{"sku": "a", "qty": 2}]}),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown field 'qty' in message 'shop.Item'"
+        );
+    }
+
+    #[test]
+    fn test_parse_status_code() {
+        assert_eq!(parse_status_code("NOT_FOUND").unwrap(), Code::NotFound);
+        assert_eq!(
+            parse_status_code("permission_denied").unwrap(),
+            Code::PermissionDenied
+        );
+        assert_eq!(parse_status_code("0").unwrap(), Code::Ok);
+        assert_eq!(parse_status_code("2").unwrap(), Code::Unknown);
+        assert!(parse_status_code("17").is_err());
+        assert!(parse_status_code("MISSING").is_err());
+    }
+
+    #[test]
+    fn test_decode_status_details_without_status_bytes() {
+        assert!(error_details_pool()
+            .get_message_by_name("google.rpc.BadRequest")
+            .is_some());
+        assert!(decode_status_details(&[], &DescriptorPool::new()).is_empty());
+    }
 }
@@ -0,0 +1,697 @@
+//! Suites converted from other tools' formats, as `rivet import` writes
+//! them: Postman collections, curl commands and OpenAPI specs.
+
+use crate::gen::{generate, GenOptions};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Expectation, Request, RivetConfig, StatusExpectation, TestStep};
+use crate::runner::base_url::BASE_URL_VAR;
+
+mod curl;
+mod scripts;
+
+pub use curl::curl_suite;
+
+/// A format [`import`] converts from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportTool {
+    /// A Postman Collection v2.1 export
+    Postman,
+    /// curl commands, e.g. from "Copy as cURL"
+    Curl,
+    /// An OpenAPI spec, converted as `rivet gen` does
+    OpenApi,
+}
+
+impl std::str::FromStr for ImportTool {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "postman" => Ok(Self::Postman),
+            "curl" => Ok(Self::Curl),
+            "openapi" => Ok(Self::OpenApi),
+            _ => anyhow::bail!("Unsupported import tool: {}", s),
+        }
+    }
+}
+
+/// What [`import`] reads.
+#[derive(Debug, Clone)]
+pub enum ImportInput {
+    File(PathBuf),
+    /// The contents of a file, e.g. curl commands read from stdin
+    Text(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Directory the suites are written to
+    pub out: PathBuf,
+    /// Write request URLs relative to `baseUrl`, for suites to set a
+    /// `base_path` on; Postman and OpenAPI only
+    pub relative_urls: bool,
+}
+
+impl From<PathBuf> for ImportOptions {
+    /// Import into `out` with the default options
+    fn from(out: PathBuf) -> Self {
+        Self {
+            out,
+            ..Default::default()
+        }
+    }
+}
+
+/// What [`import`] wrote.
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    /// The collection's or spec's name
+    pub name: String,
+    /// Suite files, in the order they were written
+    pub suites: Vec<PathBuf>,
+    /// Tests across the suites
+    pub tests: usize,
+    /// Folders created for a collection's folders
+    pub folders: usize,
+    /// The main `rivet.yaml`, for imports that write one
+    pub config: Option<PathBuf>,
+    /// Files heading with a pre-request script that couldn't be converted
+    pub needs_review: Vec<PathBuf>,
+    /// Env vars that credentials were saved as placeholders for, to set
+    /// before running the suites; curl only
+    pub env_vars: Vec<String>,
+}
+
+/// Convert `input` from `tool`'s format into suites under `options.out`.
+///
+/// ```
+/// use rivet::import::{import, ImportInput, ImportOptions, ImportTool};
+///
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let out = tempfile::tempdir()?;
+/// let commands = "curl https://api.example.com/users\n\
+///                 curl -X POST https://api.example.com/users -d '{\"name\": \"Ada\"}'";
+/// let summary = import(
+///     ImportTool::Curl,
+///     ImportInput::Text(commands.to_string()),
+///     &ImportOptions {
+///         out: out.path().to_path_buf(),
+///         ..Default::default()
+///     },
+/// )
+/// .await?;
+/// assert_eq!(summary.tests, 2);
+/// assert!(summary.suites[0].exists());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn import(
+    tool: ImportTool,
+    input: ImportInput,
+    options: &ImportOptions,
+) -> Result<ImportSummary> {
+    let out = &options.out;
+    if options.relative_urls && tool == ImportTool::Curl {
+        anyhow::bail!("relative_urls is only supported by the postman and openapi importers");
+    }
+    match (tool, input) {
+        (ImportTool::Postman, ImportInput::File(file)) => {
+            if !file.exists() {
+                return Err(anyhow!(
+                    "Postman collection file does not exist: {}",
+                    file.display()
+                ));
+            }
+            import_postman_collection(&fs::read_to_string(&file)?, options)
+        }
+        (ImportTool::Postman, ImportInput::Text(text)) => import_postman_collection(&text, options),
+        (ImportTool::Curl, ImportInput::File(file)) => {
+            if !file.exists() {
+                anyhow::bail!("curl command file does not exist: {}", file.display());
+            }
+            let name = file.file_stem().map(|stem| stem.to_string_lossy());
+            import_curl(&fs::read_to_string(&file)?, name.as_deref(), out)
+        }
+        (ImportTool::Curl, ImportInput::Text(text)) => import_curl(&text, None, out),
+        (ImportTool::OpenApi, ImportInput::File(spec)) => {
+            // The same suites `rivet gen` writes
+            let summary = generate(
+                &spec,
+                &GenOptions {
+                    out: out.clone(),
+                    relative_urls: options.relative_urls,
+                    ..Default::default()
+                },
+            )
+            .await?;
+            Ok(ImportSummary {
+                name: summary.title,
+                tests: summary.suites.len(),
+                suites: summary.suites,
+                folders: 0,
+                config: Some(summary.config),
+                ..Default::default()
+            })
+        }
+        (ImportTool::OpenApi, ImportInput::Text(_)) => {
+            anyhow::bail!("The openapi importer reads a file, so $refs to other files resolve")
+        }
+    }
+}
+
+/// Write the suite for the curl commands in `text` to `out`.
+fn import_curl(text: &str, name: Option<&str>, out: &Path) -> Result<ImportSummary> {
+    let (suite, env_vars) = curl_suite(text, name)?;
+    let yaml = serde_yaml::to_string(&suite)?;
+
+    fs::create_dir_all(out)?;
+    // `POST /users/{id}` becomes post_users_id.rivet.yaml
+    let stem = sanitize_filename(&suite.name)
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    let path = out.join(format!("{}.rivet.yaml", stem));
+    fs::write(&path, yaml)?;
+    Ok(ImportSummary {
+        tests: suite.tests.len(),
+        name: suite.name,
+        suites: vec![path],
+        env_vars,
+        ..Default::default()
+    })
+}
+
+// Postman Collection v2.1 data structures
+#[derive(Debug, Deserialize)]
+struct PostmanCollection {
+    info: PostmanInfo,
+    item: Vec<PostmanItem>,
+    #[serde(default)]
+    variables: Option<Vec<PostmanVariable>>, // Some use 'variables' instead of 'variable'
+    #[serde(default)]
+    variable: Option<Vec<PostmanVariable>>,
+    #[serde(default)]
+    event: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanInfo {
+    name: String,
+    description: Option<String>,
+    #[allow(dead_code)] // Used for validation but not processing
+    schema: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PostmanItem {
+    // Try folder first since it has the 'item' field that's distinctive
+    Folder(PostmanFolderItem),
+    Request(Box<PostmanRequestItem>),
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanRequestItem {
+    name: String,
+    request: PostmanRequest,
+    response: Option<Vec<serde_json::Value>>,
+    /// Pre-request and test scripts
+    #[serde(default)]
+    event: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanFolderItem {
+    name: String,
+    #[allow(dead_code)] // Reserved for future folder description processing
+    description: Option<String>,
+    item: Vec<PostmanItem>,
+    /// Scripts that run around each request in the folder
+    #[serde(default)]
+    event: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanRequest {
+    method: String,
+    #[serde(default)]
+    header: Option<Vec<PostmanHeader>>,
+    url: PostmanUrl,
+    #[serde(default)]
+    body: Option<PostmanBody>,
+    #[serde(default)]
+    #[allow(dead_code)] // Reserved for future request description processing
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanHeader {
+    key: String,
+    value: String,
+    #[serde(default)]
+    disabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PostmanUrl {
+    String(String),
+    Object(PostmanUrlObject),
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanUrlObject {
+    raw: Option<String>,
+    protocol: Option<String>,
+    host: Option<Vec<String>>,
+    path: Option<Vec<String>>,
+    query: Option<Vec<PostmanQuery>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanQuery {
+    key: String,
+    value: String,
+    disabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanBody {
+    #[serde(default)]
+    mode: Option<String>,
+    #[serde(default)]
+    raw: Option<String>,
+    #[serde(default)]
+    formdata: Option<Vec<PostmanFormData>>,
+    #[serde(default)]
+    urlencoded: Option<Vec<PostmanFormData>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanFormData {
+    key: String,
+    value: Option<String>, // Can be null
+    #[serde(default)]
+    disabled: Option<bool>,
+    #[serde(default)]
+    #[allow(dead_code)] // Reserved for future form data type processing
+    r#type: Option<String>, // "text", "file", etc.
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanVariable {
+    key: String,
+    value: String,
+}
+
+fn import_postman_collection(contents: &str, options: &ImportOptions) -> Result<ImportSummary> {
+    let out = &options.out;
+    let collection: PostmanCollection = serde_json::from_str(contents)
+        .map_err(|e| anyhow!("Failed to parse Postman collection: {}", e))?;
+
+    // Create output directory if it doesn't exist
+    fs::create_dir_all(out)?;
+
+    let mut summary = ImportSummary {
+        name: collection.info.name.clone(),
+        ..Default::default()
+    };
+
+    // Convert collection variables to rivet variables
+    let mut variables = HashMap::new();
+    // Check both 'variable' and 'variables' fields
+    let vars_to_process = collection
+        .variable
+        .as_ref()
+        .or(collection.variables.as_ref());
+    if let Some(vars) = vars_to_process {
+        for var in vars {
+            variables.insert(var.key.clone(), var.value.clone());
+        }
+    }
+
+    // Collection scripts run before every request; their variables go
+    // with the collection's own
+    let pre_request = scripts::convert_prerequest(collection.event.as_deref());
+    variables.extend(pre_request.vars.clone());
+
+    // Process all items (requests and folders)
+    process_postman_items(
+        &collection.item,
+        out,
+        "",
+        &[],
+        &mut summary,
+        &variables,
+        options.relative_urls,
+    )?;
+
+    // Create a main rivet config file
+    let main_config = RivetConfig {
+        default_headers: None,
+        identify_requests: None,
+        base_path: None,
+        include: None,
+        templates: None,
+        auth: None,
+        max_capture_bytes: None,
+        follow_redirects: None,
+        name: collection.info.name.clone(),
+        description: collection.info.description.clone(),
+        env: None,
+        vars: if variables.is_empty() {
+            None
+        } else {
+            Some(variables)
+        },
+        setup: None,
+        tests: vec![], // Individual test files will be referenced
+        dataset: None,
+        teardown: None,
+        tls: None,
+        insecure: None,
+        before_each: None,
+        after_each: None,
+        secrets: None,
+        strict_vars: None,
+    };
+
+    let config_path = out.join("rivet.yaml");
+    let config_yaml = serde_yaml::to_string(&main_config)?;
+    fs::write(
+        &config_path,
+        pre_request.comment().unwrap_or_default() + &config_yaml,
+    )?;
+    if pre_request.untranslated.is_some() {
+        summary.needs_review.push(config_path.clone());
+    }
+    summary.config = Some(config_path);
+
+    Ok(summary)
+}
+
+/// Write a suite per request under `base_path`. `events` are the scripts of
+/// the enclosing folders, which run before each request's own.
+fn process_postman_items(
+    items: &[PostmanItem],
+    base_path: &Path,
+    folder_prefix: &str,
+    events: &[serde_json::Value],
+    summary: &mut ImportSummary,
+    variables: &HashMap<String, String>,
+    relative_urls: bool,
+) -> Result<()> {
+    for item in items {
+        match item {
+            PostmanItem::Request(request_item) => {
+                // Convert postman request synchronously
+                let mut rivet_request =
+                    convert_postman_request_to_rivet(&request_item.request, variables)?;
+                if relative_urls {
+                    if let Some(path) = relative_to_base_url(&rivet_request.url) {
+                        rivet_request.url = path.to_string();
+                    }
+                }
+                let request_events: Vec<_> = events
+                    .iter()
+                    .chain(request_item.event.iter().flatten())
+                    .cloned()
+                    .collect();
+                let pre_request = scripts::convert_prerequest(Some(&request_events));
+                let expectation = create_expectation_from_responses(&request_item.response);
+
+                let request_name = sanitize_filename(&request_item.name);
+                let filename = if folder_prefix.is_empty() {
+                    format!("{}.yaml", request_name)
+                } else {
+                    format!("{}_{}.yaml", folder_prefix, request_name)
+                };
+
+                let test_step = TestStep {
+                    capture: None,
+                    name: request_item.name.clone(),
+                    description: None,
+                    vars: None,
+                    request: rivet_request,
+                    expect: expectation,
+                };
+
+                let test_config = RivetConfig {
+                    default_headers: None,
+                    identify_requests: None,
+                    base_path: None,
+                    include: None,
+                    templates: None,
+                    auth: None,
+                    max_capture_bytes: None,
+                    follow_redirects: None,
+                    name: request_item.name.clone(),
+                    description: None,
+                    env: None,
+                    vars: (!pre_request.vars.is_empty()).then(|| pre_request.vars.clone()),
+                    setup: None,
+                    tests: vec![test_step],
+                    dataset: None,
+                    teardown: None,
+                    tls: None,
+                    insecure: None,
+                    before_each: None,
+                    after_each: None,
+                    secrets: None,
+                    strict_vars: None,
+                };
+
+                let test_path = base_path.join(filename);
+                let test_yaml = serde_yaml::to_string(&test_config)?;
+                std::fs::write(
+                    &test_path,
+                    pre_request.comment().unwrap_or_default() + &test_yaml,
+                )?;
+
+                if pre_request.untranslated.is_some() {
+                    summary.needs_review.push(test_path.clone());
+                }
+                summary.suites.push(test_path);
+                summary.tests += 1;
+            }
+            PostmanItem::Folder(folder_item) => {
+                let folder_name = sanitize_filename(&folder_item.name);
+                let folder_path = base_path.join(&folder_name);
+                std::fs::create_dir_all(&folder_path)?;
+                summary.folders += 1;
+                let folder_events: Vec<_> = events
+                    .iter()
+                    .chain(folder_item.event.iter().flatten())
+                    .cloned()
+                    .collect();
+
+                let new_prefix = if folder_prefix.is_empty() {
+                    folder_name
+                } else {
+                    format!("{}_{}", folder_prefix, folder_name)
+                };
+
+                process_postman_items(
+                    &folder_item.item,
+                    &folder_path,
+                    &new_prefix,
+                    &folder_events,
+                    summary,
+                    variables,
+                    relative_urls,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn convert_postman_request_to_rivet(
+    postman_request: &PostmanRequest,
+    _variables: &HashMap<String, String>,
+) -> Result<Request> {
+    // Convert URL
+    let url = match &postman_request.url {
+        PostmanUrl::String(url_str) => url_str.clone(),
+        PostmanUrl::Object(url_obj) => {
+            if let Some(raw) = &url_obj.raw {
+                raw.clone()
+            } else {
+                // Reconstruct URL from parts
+                let protocol = url_obj.protocol.as_deref().unwrap_or("https");
+                let host = url_obj
+                    .host
+                    .as_ref()
+                    .map(|h| h.join("."))
+                    .unwrap_or_else(|| "localhost".to_string());
+                let path = url_obj
+                    .path
+                    .as_ref()
+                    .map(|p| "/".to_string() + &p.join("/"))
+                    .unwrap_or_else(|| "/".to_string());
+
+                let mut url = format!("{}://{}{}", protocol, host, path);
+
+                // Add query parameters
+                if let Some(query) = &url_obj.query {
+                    let params: Vec<String> = query
+                        .iter()
+                        .filter(|q| !q.disabled.unwrap_or(false))
+                        .map(|q| format!("{}={}", q.key, q.value))
+                        .collect();
+                    if !params.is_empty() {
+                        url.push('?');
+                        url.push_str(&params.join("&"));
+                    }
+                }
+                url
+            }
+        }
+    };
+
+    // Convert headers
+    let headers = if let Some(postman_headers) = &postman_request.header {
+        let mut header_map = HashMap::new();
+        for header in postman_headers {
+            if !header.disabled.unwrap_or(false) {
+                header_map.insert(header.key.clone(), header.value.clone());
+            }
+        }
+        if header_map.is_empty() {
+            None
+        } else {
+            Some(header_map)
+        }
+    } else {
+        None
+    };
+
+    // Convert body
+    let body = if let Some(postman_body) = &postman_request.body {
+        match postman_body.mode.as_deref() {
+            Some("raw") => postman_body.raw.clone(),
+            Some("formdata") => {
+                // Convert form data to form-encoded string
+                if let Some(form_data) = &postman_body.formdata {
+                    let params: Vec<String> = form_data
+                        .iter()
+                        .filter(|f| !f.disabled.unwrap_or(false))
+                        .map(|f| format!("{}={}", f.key, f.value.as_deref().unwrap_or("")))
+                        .collect();
+                    if params.is_empty() {
+                        None
+                    } else {
+                        Some(params.join("&"))
+                    }
+                } else {
+                    None
+                }
+            }
+            Some("urlencoded") => {
+                // Convert URL encoded data
+                if let Some(form_data) = &postman_body.urlencoded {
+                    let params: Vec<String> = form_data
+                        .iter()
+                        .filter(|f| !f.disabled.unwrap_or(false))
+                        .map(|f| format!("{}={}", f.key, f.value.as_deref().unwrap_or("")))
+                        .collect();
+                    if params.is_empty() {
+                        None
+                    } else {
+                        Some(params.join("&"))
+                    }
+                } else {
+                    None
+                }
+            }
+            _ => {
+                // If no mode or unknown mode, try to use raw if available
+                postman_body.raw.clone()
+            }
+        }
+    } else {
+        None
+    };
+
+    // Postman's `{{$guid}}` and the like, as rivet's dynamic variables
+    let rename = scripts::rename_dynamic_variables;
+    Ok(Request {
+        compression: None,
+        follow_redirects: None,
+        method: postman_request.method.to_uppercase(),
+        url: rename(&url),
+        headers: headers.map(|headers| {
+            headers
+                .into_iter()
+                .map(|(name, value)| (name, rename(&value)))
+                .collect()
+        }),
+        params: None, // Query params are included in URL
+        body: body.as_deref().map(rename),
+        grpc: None,
+    })
+}
+
+/// What follows `{{baseUrl}}` at the start of `url`, to join to it at run
+/// time instead.
+fn relative_to_base_url(url: &str) -> Option<&str> {
+    let rest = url.strip_prefix(&format!("{{{{{}}}}}", BASE_URL_VAR))?;
+    (rest.is_empty() || rest.starts_with(['/', '?', '#'])).then_some(rest)
+}
+
+fn create_expectation_from_responses(
+    responses: &Option<Vec<serde_json::Value>>,
+) -> Option<Expectation> {
+    if let Some(response_examples) = responses {
+        if let Some(first_response) = response_examples.first() {
+            // Try to extract status code from response example
+            if let Some(code) = first_response.get("code").and_then(|c| c.as_u64()) {
+                return Some(Expectation {
+                    poll: None,
+                    redirects_to: None,
+                    content_type: None,
+                    status: Some(StatusExpectation::Number(code as u16)),
+                    schema: None,
+                    jsonpath: None,
+                    jsonpath_length: None,
+                    jsonpath_contains: None,
+                    jsonpath_every: None,
+                    headers: None,
+                });
+            }
+        }
+    }
+
+    // Default expectation for successful requests
+    Some(Expectation {
+        poll: None,
+        redirects_to: None,
+        content_type: None,
+        status: Some(StatusExpectation::Number(200)),
+        schema: None,
+        jsonpath: None,
+        jsonpath_length: None,
+        jsonpath_contains: None,
+        jsonpath_every: None,
+        headers: None,
+    })
+}
+
+pub fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            ' ' => '_',
+            _ => '_',
+        })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_lowercase()
+}
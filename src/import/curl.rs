@@ -0,0 +1,602 @@
+//! `curl` command lines, e.g. from a browser's "Copy as cURL", turned into
+//! a suite with one test per command.
+
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use std::collections::HashMap;
+use url::Url;
+
+use crate::config::{Expectation, Request, RivetConfig, StatusExpectation, TestStep};
+use crate::utils::is_secret_header;
+
+/// Options that take a value but don't change the request, skipped with it.
+const IGNORED_WITH_VALUE: &[&str] = &[
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "--retry",
+    "-w",
+    "--write-out",
+    "--cacert",
+    "-E",
+    "--cert",
+    "--key",
+    "-x",
+    "--proxy",
+    "--resolve",
+    "-c",
+    "--cookie-jar",
+    "-r",
+    "--range",
+    "--max-redirs",
+];
+
+/// Short options that take a value, which may be attached, as in `-XPOST`.
+const SHORT_WITH_VALUE: &str = "XHdubAeoxmwFrcET";
+
+/// The suite for the curl commands in `text`: named `name`, or after its
+/// request when there is just one. Credentials are saved as `${VAR}`
+/// placeholders; the env vars they read come back alongside the suite.
+pub fn curl_suite(text: &str, name: Option<&str>) -> Result<(RivetConfig, Vec<String>)> {
+    let commands = split_commands(text)?;
+    if commands.is_empty() {
+        bail!("No curl command found");
+    }
+
+    let mut tests = Vec::new();
+    let mut insecure = false;
+    let mut env_vars: Vec<String> = Vec::new();
+    for words in &commands {
+        let command = parse_curl(words)?;
+        insecure |= command.insecure;
+        for env_var in command.env_vars {
+            if !env_vars.contains(&env_var) {
+                env_vars.push(env_var);
+            }
+        }
+        tests.push(TestStep {
+            capture: None,
+            name: test_name(&command.request),
+            description: None,
+            vars: None,
+            request: command.request,
+            expect: Some(Expectation {
+                poll: None,
+                redirects_to: None,
+                content_type: None,
+                status: Some(StatusExpectation::Number(200)),
+                schema: None,
+                jsonpath: None,
+                jsonpath_length: None,
+                jsonpath_contains: None,
+                jsonpath_every: None,
+                headers: None,
+            }),
+        });
+    }
+
+    let name = match (name, tests.as_slice()) {
+        (Some(name), _) => name.to_string(),
+        (None, [test]) => test.name.clone(),
+        (None, _) => "Imported curl commands".to_string(),
+    };
+    let suite = RivetConfig {
+        default_headers: None,
+        identify_requests: None,
+        base_path: None,
+        include: None,
+        templates: None,
+        auth: None,
+        max_capture_bytes: None,
+        follow_redirects: None,
+        name,
+        description: None,
+        env: None,
+        vars: None,
+        setup: None,
+        tests,
+        dataset: None,
+        teardown: None,
+        tls: None,
+        insecure: insecure.then_some(true),
+        before_each: None,
+        after_each: None,
+        secrets: None,
+        strict_vars: None,
+    };
+    Ok((suite, env_vars))
+}
+
+/// `METHOD /path`, as `rivet send --save` names its tests.
+fn test_name(request: &Request) -> String {
+    let path = Url::parse(&request.url)
+        .map(|url| url.path().to_string())
+        .unwrap_or_else(|_| request.url.clone());
+    format!("{} {}", request.method, path)
+}
+
+struct CurlCommand {
+    request: Request,
+    /// `-k`/`--insecure`
+    insecure: bool,
+    /// Env vars the credential placeholders in `request` read
+    env_vars: Vec<String>,
+}
+
+/// The request a curl command line (`words[0]` is `curl`) sends.
+fn parse_curl(words: &[String]) -> Result<CurlCommand> {
+    match words.first() {
+        Some(program) if program == "curl" || program.ends_with("/curl") => {}
+        Some(program) => bail!("Not a curl command: {}", program),
+        None => bail!("No curl command found"),
+    }
+
+    let mut method = None;
+    let mut url = None;
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut data: Vec<String> = Vec::new();
+    let mut get = false;
+    let mut head = false;
+    let mut json = false;
+    let mut insecure = false;
+
+    let mut args = expand_short_options(&words[1..]).into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| {
+            args.next()
+                .ok_or_else(|| anyhow!("Missing value for {}", option))
+        };
+        match arg.as_str() {
+            "-X" | "--request" => method = Some(value(&arg)?.to_uppercase()),
+            "-H" | "--header" => {
+                let header = value(&arg)?;
+                let (name, value) = header.split_once(':').ok_or_else(|| {
+                    anyhow!("Invalid header '{}': expected 'Name: value'", header)
+                })?;
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+            "-d" | "--data" | "--data-ascii" | "--data-binary" => {
+                let body = value(&arg)?;
+                if body.starts_with('@') {
+                    bail!(
+                        "{} {} reads the body from a file, which can't be imported; paste the body instead",
+                        arg,
+                        body
+                    );
+                }
+                data.push(body);
+            }
+            "--data-raw" => data.push(value(&arg)?),
+            "--data-urlencode" => data.push(urlencode_data(&value(&arg)?)),
+            "--json" => {
+                data.push(value(&arg)?);
+                json = true;
+            }
+            "-u" | "--user" => {
+                let credentials = value(&arg)?;
+                headers.push((
+                    "Authorization".to_string(),
+                    format!("Basic {}", BASE64.encode(credentials)),
+                ));
+            }
+            "-A" | "--user-agent" => headers.push(("User-Agent".to_string(), value(&arg)?)),
+            "-e" | "--referer" => headers.push(("Referer".to_string(), value(&arg)?)),
+            "-b" | "--cookie" => headers.push(("Cookie".to_string(), value(&arg)?)),
+            "--url" => url = Some(value(&arg)?),
+            "-G" | "--get" => get = true,
+            "-I" | "--head" => head = true,
+            "-k" | "--insecure" => insecure = true,
+            "-F" | "--form" => bail!("Multipart forms ({}) can't be imported", arg),
+            option if IGNORED_WITH_VALUE.contains(&option) => {
+                value(option)?;
+            }
+            option if option.starts_with('-') && option.len() > 1 => {}
+            _ => url = Some(arg.clone()),
+        }
+    }
+
+    let mut url = url.ok_or_else(|| anyhow!("The curl command has no URL"))?;
+    let mut body = None;
+    if !data.is_empty() {
+        let joined = data.join("&");
+        if get {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(&joined);
+        } else {
+            if json {
+                add_default_header(&mut headers, "Content-Type", "application/json");
+                add_default_header(&mut headers, "Accept", "application/json");
+            } else {
+                add_default_header(
+                    &mut headers,
+                    "Content-Type",
+                    "application/x-www-form-urlencoded",
+                );
+            }
+            body = Some(joined);
+        }
+    }
+
+    let method = method.unwrap_or_else(|| {
+        if head {
+            "HEAD".to_string()
+        } else if body.is_some() {
+            "POST".to_string()
+        } else {
+            "GET".to_string()
+        }
+    });
+    let mut env_vars = Vec::new();
+    for (name, value) in &mut headers {
+        if is_secret_header(name) {
+            let (placeholder, env_var) = credential_placeholder(name, value);
+            *value = placeholder;
+            env_vars.push(env_var);
+        }
+    }
+    let headers: HashMap<String, String> = headers.into_iter().collect();
+
+    Ok(CurlCommand {
+        request: Request {
+            compression: None,
+            follow_redirects: None,
+            method,
+            url,
+            headers: (!headers.is_empty()).then_some(headers),
+            params: None,
+            body,
+            grpc: None,
+        },
+        insecure,
+        env_vars,
+    })
+}
+
+/// What a suite saves instead of a credential header's value, and the env
+/// var it reads: the placeholders `rivet send --save` writes, e.g.
+/// `Bearer ${RIVET_TOKEN}`, or `${RIVET_<HEADER>}` for other headers.
+fn credential_placeholder(name: &str, value: &str) -> (String, String) {
+    let scheme = value.split_whitespace().next().unwrap_or_default();
+    let lower = name.to_ascii_lowercase();
+    let env_var = if lower == "authorization" && scheme.eq_ignore_ascii_case("bearer") {
+        "RIVET_TOKEN".to_string()
+    } else if lower == "authorization" && scheme.eq_ignore_ascii_case("basic") {
+        "RIVET_BASIC_AUTH".to_string()
+    } else if ["api-key", "apikey", "api_key"]
+        .iter()
+        .any(|key| lower.contains(key))
+    {
+        "RIVET_API_KEY".to_string()
+    } else {
+        let header: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("RIVET_{}", header)
+    };
+
+    let placeholder = match env_var.as_str() {
+        "RIVET_TOKEN" | "RIVET_BASIC_AUTH" => format!("{} ${{{}}}", scheme, env_var),
+        _ => format!("${{{}}}", env_var),
+    };
+    (placeholder, env_var)
+}
+
+/// Add the header curl would send unless the command sets its own.
+fn add_default_header(headers: &mut Vec<(String, String)>, name: &str, value: &str) {
+    if !headers
+        .iter()
+        .any(|(key, _)| key.eq_ignore_ascii_case(name))
+    {
+        headers.push((name.to_string(), value.to_string()));
+    }
+}
+
+/// Split bundled short options, `-sSL` into `-s -S -L`, and attached
+/// values, `-XPOST` into `-X POST`.
+fn expand_short_options(args: &[String]) -> Vec<String> {
+    let mut expanded = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let bundled = arg.starts_with('-') && !arg.starts_with("--") && arg.len() > 2;
+        if !bundled {
+            expanded.push(arg.clone());
+            // A value is never an option, even when it starts with `-`
+            if arg.starts_with('-') && takes_value(arg) {
+                expanded.extend(args.next().cloned());
+            }
+            continue;
+        }
+        for (i, flag) in arg.char_indices().skip(1) {
+            expanded.push(format!("-{}", flag));
+            if SHORT_WITH_VALUE.contains(flag) {
+                let rest = &arg[i + flag.len_utf8()..];
+                if rest.is_empty() {
+                    expanded.extend(args.next().cloned());
+                } else {
+                    expanded.push(rest.to_string());
+                }
+                break;
+            }
+        }
+    }
+    expanded
+}
+
+fn takes_value(option: &str) -> bool {
+    const LONG_WITH_VALUE: &[&str] = &[
+        "--request",
+        "--header",
+        "--data",
+        "--data-ascii",
+        "--data-binary",
+        "--data-raw",
+        "--data-urlencode",
+        "--json",
+        "--user",
+        "--user-agent",
+        "--referer",
+        "--cookie",
+        "--url",
+        "--form",
+    ];
+    match option.strip_prefix('-') {
+        Some(short) if short.len() == 1 => SHORT_WITH_VALUE.contains(short),
+        _ => LONG_WITH_VALUE.contains(&option) || IGNORED_WITH_VALUE.contains(&option),
+    }
+}
+
+/// `--data-urlencode`'s argument: `name=value` with the value encoded, or
+/// just an encoded value.
+fn urlencode_data(data: &str) -> String {
+    let encode = |value: &str| url::form_urlencoded::byte_serialize(value.as_bytes()).collect();
+    match data.split_once('=') {
+        Some(("", value)) => encode(value),
+        Some((name, value)) => format!("{}={}", name, encode(value)),
+        None => encode(data),
+    }
+}
+
+/// Split shell text into commands of words, quoting as POSIX shells do:
+/// `'...'`, `"..."`, `$'...'` and backslash escapes. A backslash before a
+/// newline continues the command; an unquoted newline or `;` ends it, and
+/// `#` starts a comment.
+fn split_commands(text: &str) -> Result<Vec<Vec<String>>> {
+    let mut commands = Vec::new();
+    let mut words = Vec::new();
+    // `Some` inside a word, so `''` is an empty word rather than none
+    let mut word: Option<String> = None;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\n') | None => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(escaped) => word.get_or_insert_with(String::new).push(escaped),
+            },
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated ' quote"),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => bail!("Unterminated \" quote"),
+                        },
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated \" quote"),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => word.push(ansi_c_escape(&mut chars)?),
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated $' quote"),
+                    }
+                }
+            }
+            '#' if word.is_none() => while chars.next_if(|&c| c != '\n').is_some() {},
+            '\n' | ';' => {
+                words.extend(word.take());
+                if !words.is_empty() {
+                    commands.push(std::mem::take(&mut words));
+                }
+            }
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    words.extend(word);
+    if !words.is_empty() {
+        commands.push(words);
+    }
+    Ok(commands)
+}
+
+/// The character after a backslash in a `$'...'` string.
+fn ansi_c_escape(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<char> {
+    Ok(match chars.next() {
+        Some('n') => '\n',
+        Some('t') => '\t',
+        Some('r') => '\r',
+        Some('0') => '\0',
+        Some('x') => hex_char(chars, 2)?,
+        Some('u') => hex_char(chars, 4)?,
+        Some('U') => hex_char(chars, 8)?,
+        Some(c) => c,
+        None => bail!("Unterminated $' quote"),
+    })
+}
+
+/// The character of the next `digits` (at most) hex digits.
+fn hex_char(chars: &mut std::iter::Peekable<std::str::Chars>, digits: usize) -> Result<char> {
+    let code: String = (0..digits)
+        .map_while(|_| chars.next_if(|c| c.is_ascii_hexdigit()))
+        .collect();
+    u32::from_str_radix(&code, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| anyhow!("Invalid escape in $' quote"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<Vec<String>> {
+        split_commands(text).unwrap()
+    }
+
+    fn curl_request(command: &str) -> Request {
+        let commands = words(command);
+        parse_curl(&commands[0]).unwrap().request
+    }
+
+    #[test]
+    fn test_split_commands_quoting() {
+        assert_eq!(
+            words(r#"curl 'a b' "c \"d\" \$e" f\ g $'h\ni\'' ''"#),
+            [["curl", "a b", "c \"d\" $e", "f g", "h\ni'", ""]]
+        );
+        assert_eq!(
+            words("# fetch\ncurl https://a.test \\\n  -H 'X: 1'\n\ncurl https://b.test; curl c"),
+            vec![
+                vec!["curl", "https://a.test", "-H", "X: 1"],
+                vec!["curl", "https://b.test"],
+                vec!["curl", "c"],
+            ]
+        );
+        assert_eq!(words(r#"curl $'\x41é'"#), [["curl", "Aé"]]);
+        assert!(split_commands("curl 'open").is_err());
+    }
+
+    #[test]
+    fn test_parse_devtools_command() {
+        let request = curl_request(
+            r#"curl 'https://api.example.com/users?page=2' \
+  -H 'accept: application/json' \
+  -H 'content-type: application/json' \
+  --data-raw '{"name":"Ada"}' \
+  --compressed"#,
+        );
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.url, "https://api.example.com/users?page=2");
+        let headers = request.headers.unwrap();
+        assert_eq!(headers["content-type"], "application/json");
+        assert_eq!(headers["accept"], "application/json");
+        assert_eq!(headers.len(), 2);
+        assert_eq!(request.body.as_deref(), Some(r#"{"name":"Ada"}"#));
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let request = curl_request("curl -sSL -XPUT -u ada:secret https://a.test/x -d a=1 -d b=2");
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.url, "https://a.test/x");
+        assert_eq!(request.body.as_deref(), Some("a=1&b=2"));
+        let headers = request.headers.unwrap();
+        assert_eq!(headers["Authorization"], "Basic ${RIVET_BASIC_AUTH}");
+        assert_eq!(headers["Content-Type"], "application/x-www-form-urlencoded");
+
+        let request =
+            curl_request("curl -G https://a.test/search --data-urlencode 'q=a b' -o out.txt");
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.url, "https://a.test/search?q=a+b");
+        assert!(request.body.is_none());
+
+        let request = curl_request(r#"curl --json '{"a":1}' https://a.test"#);
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.headers.unwrap()["Content-Type"], "application/json");
+
+        assert_eq!(curl_request("curl -I https://a.test").method, "HEAD");
+    }
+
+    #[test]
+    fn test_credentials_become_placeholders() {
+        let commands = words(
+            "curl https://a.test -H 'Authorization: Bearer abc.def' -b 'session=s3cr3t' \\
+  -H 'X-Api-Key: k3y' -H 'X-Auth-Token: t0k' -H 'Accept: */*'",
+        );
+        let command = parse_curl(&commands[0]).unwrap();
+        let headers = command.request.headers.unwrap();
+        assert_eq!(headers["Authorization"], "Bearer ${RIVET_TOKEN}");
+        assert_eq!(headers["Cookie"], "${RIVET_COOKIE}");
+        assert_eq!(headers["X-Api-Key"], "${RIVET_API_KEY}");
+        assert_eq!(headers["X-Auth-Token"], "${RIVET_X_AUTH_TOKEN}");
+        assert_eq!(headers["Accept"], "*/*");
+        assert_eq!(
+            command.env_vars,
+            [
+                "RIVET_TOKEN",
+                "RIVET_COOKIE",
+                "RIVET_API_KEY",
+                "RIVET_X_AUTH_TOKEN"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |command: &str| parse_curl(&words(command)[0]).map(|_| ());
+        assert!(parse("wget https://a.test")
+            .unwrap_err()
+            .to_string()
+            .contains("Not a curl command"));
+        assert!(parse("curl -H")
+            .unwrap_err()
+            .to_string()
+            .contains("Missing value"));
+        assert!(parse("curl -s").unwrap_err().to_string().contains("no URL"));
+        assert!(parse("curl -d @body.json https://a.test")
+            .unwrap_err()
+            .to_string()
+            .contains("reads the body from a file"));
+    }
+
+    #[test]
+    fn test_curl_suite_names() {
+        let (suite, env_vars) = curl_suite("curl -k https://a.test/users", None).unwrap();
+        assert_eq!(suite.name, "GET /users");
+        assert_eq!(suite.insecure, Some(true));
+        assert!(env_vars.is_empty());
+
+        let (suite, _) = curl_suite("curl https://a.test/a\ncurl https://a.test/b", None).unwrap();
+        assert_eq!(suite.name, "Imported curl commands");
+        assert_eq!(suite.tests.len(), 2);
+        assert_eq!(suite.tests[1].name, "GET /b");
+
+        assert!(curl_suite("# nothing\n", None).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//! Best-effort translation of Postman pre-request scripts into suite `vars`.
+
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Postman's dynamic variables, as the built-in variables that replace them.
+const POSTMAN_DYNAMIC_VARIABLES: &[(&str, &str)] =
+    &[("{{$guid}}", "{{$uuid}}"), ("{{$randomUUID}}", "{{$uuid}}")];
+
+/// Script expressions (without whitespace) and the variables they become.
+const EXPRESSIONS: &[(&str, &str)] = &[
+    ("newDate().toISOString()", "{{$isoTimestamp}}"),
+    ("Math.floor(Date.now()/1000)", "{{$timestamp}}"),
+    ("Math.round(Date.now()/1000)", "{{$timestamp}}"),
+    ("Math.floor(newDate().getTime()/1000)", "{{$timestamp}}"),
+    ("uuid.v4()", "{{$uuid}}"),
+    ("require('uuid').v4()", "{{$uuid}}"),
+    ("require(\"uuid\").v4()", "{{$uuid}}"),
+    ("crypto.randomUUID()", "{{$uuid}}"),
+];
+
+/// What a request's pre-request scripts amount to.
+#[derive(Debug, Default)]
+pub struct PreRequest {
+    /// Variables the script sets to values rivet can produce
+    pub vars: HashMap<String, String>,
+    /// The whole script, when any of it couldn't be translated
+    pub untranslated: Option<String>,
+}
+
+impl PreRequest {
+    /// The `# ` comment block that heads a suite whose script needs review.
+    pub fn comment(&self) -> Option<String> {
+        let script = self.untranslated.as_ref()?;
+        let mut comment =
+            "# Postman pre-request script, not fully converted; review by hand:\n".to_string();
+        for line in script.lines() {
+            comment.push_str(format!("#   {}", line).trim_end());
+            comment.push('\n');
+        }
+        Some(comment)
+    }
+}
+
+/// Translate the `prerequest` scripts among a Postman item's `event`s.
+pub fn convert_prerequest(events: Option<&[Value]>) -> PreRequest {
+    let script: Vec<String> = events
+        .unwrap_or_default()
+        .iter()
+        .filter(|event| event["listen"] == "prerequest")
+        .flat_map(|event| match &event["script"]["exec"] {
+            Value::Array(lines) => lines
+                .iter()
+                .filter_map(|line| line.as_str().map(str::to_string))
+                .collect(),
+            Value::String(text) => vec![text.clone()],
+            _ => Vec::new(),
+        })
+        .collect();
+    let script = script.join("\n");
+
+    let mut result = PreRequest::default();
+    let mut locals = HashMap::new();
+    let mut translated = true;
+    for statement in statements(&script) {
+        if !convert_statement(&statement, &mut locals, &mut result.vars) {
+            translated = false;
+        }
+    }
+    if !translated {
+        result.untranslated = Some(script.trim().to_string());
+    }
+    result
+}
+
+/// `text` with Postman's dynamic variables renamed to rivet's.
+pub fn rename_dynamic_variables(text: &str) -> String {
+    POSTMAN_DYNAMIC_VARIABLES
+        .iter()
+        .fold(text.to_string(), |text, (postman, rivet)| {
+            text.replace(postman, rivet)
+        })
+}
+
+/// Apply one statement; false when it can't be translated.
+fn convert_statement(
+    statement: &str,
+    locals: &mut HashMap<String, String>,
+    vars: &mut HashMap<String, String>,
+) -> bool {
+    if statement.starts_with("//") || statement.starts_with("console.") {
+        return true;
+    }
+    if let Some(caps) = set_regex().captures(statement) {
+        return match translate_expression(&caps[3], locals) {
+            Some(value) => {
+                vars.insert(caps[2].to_string(), value);
+                true
+            }
+            None => false,
+        };
+    }
+    if let Some(caps) = declaration_regex().captures(statement) {
+        let expression = caps[2].trim();
+        // Module imports, e.g. `const uuid = require('uuid')`
+        if expression.starts_with("require(") {
+            return true;
+        }
+        return match translate_expression(expression, locals) {
+            Some(value) => {
+                locals.insert(caps[1].to_string(), value);
+                true
+            }
+            None => false,
+        };
+    }
+    false
+}
+
+/// The value of a script expression, if rivet can produce it.
+fn translate_expression(expression: &str, locals: &HashMap<String, String>) -> Option<String> {
+    let expression = expression.trim();
+    if let Some(literal) = string_literal(expression) {
+        return Some(rename_dynamic_variables(literal));
+    }
+    if expression.parse::<f64>().is_ok() || expression == "true" || expression == "false" {
+        return Some(expression.to_string());
+    }
+    if let Some(value) = locals.get(expression) {
+        return Some(value.clone());
+    }
+
+    let compact: String = expression.split_whitespace().collect();
+    if let Some((_, variable)) = EXPRESSIONS.iter().find(|(js, _)| *js == compact) {
+        return Some(variable.to_string());
+    }
+    if random_int_regex().is_match(&compact) {
+        return Some("{{$randomInt}}".to_string());
+    }
+    let inner = expression
+        .strip_prefix("pm.variables.replaceIn(")
+        .and_then(|rest| rest.strip_suffix(')'))?;
+    string_literal(inner.trim()).map(rename_dynamic_variables)
+}
+
+/// The contents of a quoted string without escapes or `${...}` interpolation.
+fn string_literal(expression: &str) -> Option<&str> {
+    let quote = expression.chars().next()?;
+    if !matches!(quote, '"' | '\'' | '`') || expression.len() < 2 {
+        return None;
+    }
+    let inner = expression.strip_prefix(quote)?.strip_suffix(quote)?;
+    let plain = !inner.contains(quote) && !inner.contains('\\') && !inner.contains("${");
+    plain.then_some(inner)
+}
+
+/// The script's statements, split at `;` and line ends outside strings.
+fn statements(script: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    for line in script.lines() {
+        if quote.is_none() && line.trim_start().starts_with("//") {
+            continue;
+        }
+        for c in line.chars() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if matches!(c, '"' | '\'' | '`') => quote = Some(c),
+                None if c == ';' => {
+                    statements.push(std::mem::take(&mut current));
+                    continue;
+                }
+                None => {}
+            }
+            current.push(c);
+        }
+        // A line break ends a statement unless it is inside a string or
+        // an unclosed call, e.g. a multi-line argument list
+        if quote.is_none() && balanced(&current) {
+            statements.push(std::mem::take(&mut current));
+        } else {
+            current.push('\n');
+        }
+    }
+    statements.push(current);
+    statements
+        .into_iter()
+        .map(|statement| statement.trim().to_string())
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+fn balanced(text: &str) -> bool {
+    let open = text.matches(['(', '{', '[']).count();
+    let close = text.matches([')', '}', ']']).count();
+    open <= close
+}
+
+/// `pm.environment.set("name", value)` and the older `postman.set...Variable`.
+fn set_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r#"(?s)^(?:pm\.(?:environment|collectionVariables|variables|globals)\.set|postman\.set(?:Environment|Global)Variable)\(\s*(["'`])([\w.\-]+)["'`]\s*,\s*(.+?)\s*\)$"#,
+        )
+        .unwrap()
+    })
+}
+
+fn declaration_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?s)^(?:const|let|var)\s+(\w+)\s*=\s*(.+)$").unwrap())
+}
+
+/// `Math.floor(Math.random() * 1000)` and the like, without whitespace.
+fn random_int_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"^(?:Math\.(?:floor|round|ceil)\()?Math\.random\(\)\*\d+\)?$").unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn convert(lines: &[&str]) -> PreRequest {
+        let events = [json!({
+            "listen": "prerequest",
+            "script": { "type": "text/javascript", "exec": lines }
+        })];
+        convert_prerequest(Some(&events))
+    }
+
+    #[test]
+    fn test_literal_and_dynamic_values_become_vars() {
+        let result = convert(&[
+            "// set up the request",
+            "pm.environment.set(\"region\", \"eu-west-1\");",
+            "pm.collectionVariables.set('retries', 3);",
+            "pm.variables.set(\"requestedAt\", new Date().toISOString());",
+            "pm.globals.set(\"epoch\", Math.floor(Date.now() / 1000));",
+            "const uuid = require('uuid');",
+            "const id = uuid.v4();",
+            "pm.environment.set(\"requestId\", id);",
+            "postman.setEnvironmentVariable(\"nonce\", Math.floor(Math.random() * 100000));",
+            "pm.variables.set(\"trace\", pm.variables.replaceIn('{{$guid}}'))",
+            "console.log('ready')",
+        ]);
+        assert!(result.untranslated.is_none(), "{:?}", result.untranslated);
+        assert!(result.comment().is_none());
+        let expected: HashMap<String, String> = [
+            ("region", "eu-west-1"),
+            ("retries", "3"),
+            ("requestedAt", "{{$isoTimestamp}}"),
+            ("epoch", "{{$timestamp}}"),
+            ("requestId", "{{$uuid}}"),
+            ("nonce", "{{$randomInt}}"),
+            ("trace", "{{$uuid}}"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        assert_eq!(result.vars, expected);
+    }
+
+    #[test]
+    fn test_untranslatable_script_is_kept_as_comment() {
+        let result = convert(&[
+            "pm.environment.set(\"region\", \"eu\");",
+            "const signature = CryptoJS.HmacSHA256(",
+            "    pm.request.body.raw,",
+            "    pm.environment.get(\"secret\")",
+            ").toString();",
+            "pm.environment.set(\"signature\", signature);",
+        ]);
+        // What can be translated still is
+        assert_eq!(result.vars["region"], "eu");
+        assert!(!result.vars.contains_key("signature"));
+        let comment = result.comment().unwrap();
+        assert!(comment.starts_with("# Postman pre-request script"));
+        assert!(comment.contains("#   const signature = CryptoJS.HmacSHA256(\n"));
+        assert!(comment.contains("#       pm.request.body.raw,\n"));
+    }
+
+    #[test]
+    fn test_no_scripts() {
+        let result = convert_prerequest(None);
+        assert!(result.vars.is_empty() && result.untranslated.is_none());
+        let tests_only = [json!({"listen": "test", "script": {"exec": ["pm.test()"]}})];
+        assert!(convert_prerequest(Some(&tests_only)).untranslated.is_none());
+    }
+
+    #[test]
+    fn test_rename_dynamic_variables() {
+        assert_eq!(
+            rename_dynamic_variables("/orders/{{$guid}}?t={{$timestamp}}&r={{$randomUUID}}"),
+            "/orders/{{$uuid}}?t={{$timestamp}}&r={{$uuid}}"
+        );
+    }
+}
@@ -1,8 +1,24 @@
+//! Rivet as a library: the `rivet` command line is built on the same calls.
+//!
+//! - [`run::run_suites`] runs suites and returns a [`run::RunReport`]
+//! - [`gen::generate`] writes suites from an OpenAPI spec
+//! - [`import::import`] converts Postman collections, curl commands or
+//!   OpenAPI specs
+//!
+//! None of them print; a run's progress goes to the [`run::Reporter`] in
+//! its options, which is silent unless one is set.
+
 pub mod commands;
 pub mod config;
+pub mod gen;
 pub mod grpc;
+pub mod import;
+pub mod mock;
 pub mod performance;
+pub mod record;
 pub mod report;
+pub mod run;
 pub mod runner;
+pub mod style;
 pub mod ui;
 pub mod utils;
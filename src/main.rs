@@ -1,29 +1,27 @@
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Shell as CompShell};
-use owo_colors::OwoColorize;
+use rivet::commands::{self, *};
+use rivet::config::TlsConfig;
+use rivet::style::{self, Colorize};
+use rivet::utils;
 use std::path::PathBuf;
 
-mod commands;
-mod config;
-mod grpc;
-mod performance;
-mod report;
-mod runner;
-mod ui;
-mod utils;
-
-use commands::*;
-
 #[derive(Parser)]
 #[command(name = "rivet")]
 #[command(version = "0.1.0")]
 #[command(about = "API testing that lives in git")]
 #[command(long_about = None)]
 struct Cli {
+    /// Disable colored output (also set by NO_COLOR, and off when stdout
+    /// isn't a terminal)
+    #[arg(long = "no-color", global = true)]
+    no_color: bool,
     #[command(subcommand)]
     command: Commands,
 }
 
+// Parsed once per process, so the size of the largest variant doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Send a single HTTP request
@@ -35,9 +33,30 @@ enum Commands {
         /// Headers in format "Key: Value"
         #[arg(short = 'H', long = "header", action = clap::ArgAction::Append)]
         headers: Vec<String>,
+        /// Query parameter "key=value" appended to the URL (repeatable)
+        #[arg(short = 'q', long = "query", action = clap::ArgAction::Append)]
+        query: Vec<String>,
         /// Request body data
-        #[arg(short = 'd', long = "data")]
+        #[arg(short = 'd', long = "data", conflicts_with_all = ["form", "file"])]
         data: Option<String>,
+        /// JSON request body (or @file.json); sets JSON Content-Type/Accept headers
+        #[arg(long = "json", conflicts_with_all = ["data", "form", "file"])]
+        json: Option<String>,
+        /// URL-encoded form field "key=value" (repeatable)
+        #[arg(long = "form", action = clap::ArgAction::Append)]
+        form: Vec<String>,
+        /// Multipart file field "field=@path" (repeatable; makes the body multipart)
+        #[arg(long = "file", action = clap::ArgAction::Append)]
+        file: Vec<String>,
+        /// Bearer token for the Authorization header ("-" reads RIVET_TOKEN)
+        #[arg(long = "bearer", conflicts_with = "basic")]
+        bearer: Option<String>,
+        /// Basic auth credentials "user:pass"
+        #[arg(long = "basic")]
+        basic: Option<String>,
+        /// API key header "HEADER=VALUE"
+        #[arg(long = "api-key")]
+        api_key: Option<String>,
         /// Save request to file
         #[arg(long = "save")]
         save: Option<PathBuf>,
@@ -47,6 +66,66 @@ enum Commands {
         /// Request timeout
         #[arg(long = "timeout", default_value = "30s")]
         timeout: String,
+        /// Write the response body to a file instead of printing it ("-" for stdout)
+        #[arg(short = 'o', long = "output")]
+        output: Option<PathBuf>,
+        /// Overwrite the --output file if it already exists
+        #[arg(long = "force")]
+        force: bool,
+        /// Print the full request, redirect hops, connection info and timings
+        #[arg(short = 'v', long = "verbose")]
+        verbose: bool,
+        /// Maximum number of redirects to follow
+        #[arg(long = "max-redirects", default_value = "10")]
+        max_redirects: usize,
+        /// Don't follow redirects; show the redirect response itself
+        #[arg(long = "no-follow-redirects")]
+        no_follow_redirects: bool,
+        /// Retry connection errors and 5xx responses this many times
+        #[arg(long = "retry", default_value = "0")]
+        retry: usize,
+        /// Delay between retries (e.g. "500ms", "2s")
+        #[arg(long = "retry-delay", default_value = "1s")]
+        retry_delay: String,
+        /// Exit non-zero when the final status is >= 400 (body goes to stderr)
+        #[arg(long = "fail")]
+        fail: bool,
+        /// Print only the status line and response headers
+        #[arg(short = 'I', long = "headers-only")]
+        headers_only: bool,
+        /// Print only the response body, without request/response boxes
+        #[arg(long = "silent")]
+        silent: bool,
+        /// Print the body verbatim, without JSON pretty-printing
+        #[arg(long = "raw")]
+        raw: bool,
+        /// Accept-Encoding to ask for: gzip, br, identity, or none to keep a compressed body as sent
+        #[arg(long = "compression")]
+        compression: Option<String>,
+        /// Route requests through a proxy (http, https, socks5)
+        #[arg(long = "proxy", conflicts_with = "no_proxy")]
+        proxy: Option<String>,
+        /// Ignore proxy environment variables and configured proxies
+        #[arg(long = "no-proxy")]
+        no_proxy: bool,
+        /// Load variables from this file instead of ./.env
+        #[arg(long = "env-file")]
+        env_file: Option<PathBuf>,
+        /// Client certificate (PEM) for mutual TLS
+        #[arg(long = "cert", requires = "key", conflicts_with = "identity")]
+        cert: Option<PathBuf>,
+        /// Private key (PEM) for --cert
+        #[arg(long = "key", requires = "cert")]
+        key: Option<PathBuf>,
+        /// Client identity as a PKCS#12 bundle (.p12/.pfx)
+        #[arg(long = "identity")]
+        identity: Option<PathBuf>,
+        /// Password for --identity
+        #[arg(long = "identity-password", requires = "identity")]
+        identity_password: Option<String>,
+        /// Trust this CA certificate bundle (PEM) in addition to the system roots
+        #[arg(long = "cacert")]
+        cacert: Option<PathBuf>,
     },
     /// Run test suites
     Run {
@@ -58,30 +137,145 @@ enum Commands {
         /// Data file for data-driven tests
         #[arg(long = "data")]
         data: Option<PathBuf>,
-        /// Number of parallel workers
-        #[arg(long = "parallel", default_value = "1")]
-        parallel: usize,
-        /// Filter tests by name pattern
+        /// Number of suites to run at once (default: 1)
+        #[arg(long = "parallel-suites", alias = "parallel")]
+        parallel_suites: Option<usize>,
+        /// Requests in flight at once, within a suite and across all suites
+        #[arg(long = "parallel-requests")]
+        parallel_requests: Option<usize>,
+        /// Only run dataset rows matching an expression, e.g. 'country == "US"'
+        #[arg(long = "data-filter")]
+        data_filter: Option<String>,
+        /// Run a random subset of dataset rows: a count (50) or percentage (10%)
+        #[arg(long = "data-sample")]
+        data_sample: Option<String>,
+        /// Seed for --data-sample and --shuffle so a run can be repeated
+        #[arg(long = "seed")]
+        seed: Option<u64>,
+        /// Run suites, and tests within each suite, in random order (see --seed)
+        #[arg(long = "shuffle", conflicts_with = "order")]
+        shuffle: bool,
+        /// Suite and test order: declared (by file name, tests as written), file (by path) or name
+        #[arg(long = "order", default_value = "declared")]
+        order: String,
+        /// Only run tests whose "suite :: test" name matches this regex
         #[arg(long = "grep")]
         grep: Option<String>,
+        /// Run only tests that don't match --grep
+        #[arg(long = "grep-invert", requires = "grep")]
+        grep_invert: bool,
+        /// Mask the value of variable KEY in output and reports (repeatable)
+        #[arg(long = "secret", value_name = "KEY")]
+        secret: Vec<String>,
+        /// Print each step's request and response to stderr; -vv prints whole bodies
+        #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
+        verbose: u8,
+        /// Write every step's request and response to FILE as NDJSON
+        #[arg(long = "log-file", value_name = "FILE")]
+        log_file: Option<PathBuf>,
+        /// Keep at most N bytes of each response body (default: 1 MiB; overrides suites)
+        #[arg(long = "max-capture-bytes", value_name = "N")]
+        max_capture_bytes: Option<usize>,
+        /// Fail suites that repeat a step name instead of renaming the repeats
+        #[arg(long = "strict")]
+        strict: bool,
+        /// Write counts, exit reason and report paths as JSON to PATH (- for stdout)
+        #[arg(long = "summary-json", value_name = "PATH")]
+        summary_json: Option<PathBuf>,
         /// Stop on first failure
         #[arg(long = "bail")]
         bail: bool,
-        /// Report formats (comma-separated)
+        /// Cancel the run after N failed tests (--bail is --max-failures 1)
+        #[arg(long = "max-failures", value_name = "N")]
+        max_failures: Option<usize>,
+        /// Report formats (comma-separated: json, html, junit, markdown, csv)
         #[arg(long = "report")]
         report: Option<String>,
         /// HTML template to use (detailed, simple, chatty, compact)
         #[arg(long = "template")]
         template: Option<String>,
+        /// Directory to write reports into (default: ./reports)
+        #[arg(long = "report-dir")]
+        report_dir: Option<PathBuf>,
+        /// Fixed report base name; overwrites <name>.json, <name>.html, ... instead of timestamping
+        #[arg(long = "report-name")]
+        report_name: Option<String>,
+        /// Embed request/response bodies in reports (none, failures, all)
+        #[arg(long = "report-include-bodies", default_value = "failures")]
+        report_include_bodies: String,
+        /// Show trends from the last N reports in the report directory (HTML)
+        #[arg(long = "history", default_value = "0")]
+        history: usize,
         /// Auto-open HTML report in browser
         #[arg(long = "open", conflicts_with = "no_open")]
         open: bool,
         /// Disable auto-opening HTML report in browser
         #[arg(long = "no-open", conflicts_with = "open")]
         no_open: bool,
+        /// Console output: human, or json for one JSON event per line
+        #[arg(long = "output", default_value = "human")]
+        output: String,
         /// CI mode (no animations)
         #[arg(long = "ci")]
         ci: bool,
+        /// Route requests through a proxy (http, https, socks5)
+        #[arg(long = "proxy", conflicts_with = "no_proxy")]
+        proxy: Option<String>,
+        /// Ignore proxy environment variables and configured proxies
+        #[arg(long = "no-proxy")]
+        no_proxy: bool,
+        /// Load variables from this file instead of ./.env
+        #[arg(long = "env-file")]
+        env_file: Option<PathBuf>,
+        /// Retarget every suite at this base URL (overrides the `baseUrl` variable)
+        #[arg(long = "base-url")]
+        base_url: Option<String>,
+        /// Header "Name: value" for every HTTP step, over the suites' own (repeatable)
+        #[arg(short = 'H', long = "header", action = clap::ArgAction::Append)]
+        header: Vec<String>,
+        /// Send "X-Rivet-Test: <suite>/<test>" with every HTTP step
+        #[arg(long = "identify")]
+        identify: bool,
+        /// Skip TLS certificate verification (self-signed dev servers)
+        #[arg(long = "insecure")]
+        insecure: bool,
+        /// Client certificate (PEM) for mutual TLS
+        #[arg(long = "cert", requires = "key", conflicts_with = "identity")]
+        cert: Option<PathBuf>,
+        /// Private key (PEM) for --cert
+        #[arg(long = "key", requires = "cert")]
+        key: Option<PathBuf>,
+        /// Client identity as a PKCS#12 bundle (.p12/.pfx)
+        #[arg(long = "identity")]
+        identity: Option<PathBuf>,
+        /// Password for --identity
+        #[arg(long = "identity-password", requires = "identity")]
+        identity_password: Option<String>,
+        /// Trust this CA certificate bundle (PEM) in addition to the system roots
+        #[arg(long = "cacert")]
+        cacert: Option<PathBuf>,
+    },
+    /// Check test suites for mistakes without sending any request
+    Validate {
+        /// File or directory to check
+        target: PathBuf,
+        /// Load variables from this file instead of ./.env
+        #[arg(long = "env-file", value_name = "FILE")]
+        env_file: Option<PathBuf>,
+    },
+    /// List the suites and tests in a file or directory without running them
+    List {
+        /// File or directory to list
+        target: PathBuf,
+        /// List only tests matching this regex over "suite name :: test name"
+        #[arg(long = "grep")]
+        grep: Option<String>,
+        /// List only tests that don't match --grep
+        #[arg(long = "grep-invert", requires = "grep")]
+        grep_invert: bool,
+        /// Print the listing as JSON
+        #[arg(long = "json")]
+        json: bool,
     },
     /// Generate test files from OpenAPI spec
     Gen {
@@ -91,6 +285,70 @@ enum Commands {
         /// Output directory
         #[arg(long = "out", default_value = "tests/")]
         out: PathBuf,
+        /// Check responses against the spec's schemas, written to <out>/schemas/
+        #[arg(long = "with-schemas")]
+        with_schemas: bool,
+        /// Server to generate for: an index into the spec's servers, a URL,
+        /// or `all` for a baseUrl variable tests can be pointed anywhere with
+        #[arg(long = "server")]
+        server: Option<String>,
+        /// Data-driven tests over a CSV of the values of enum path and query
+        /// parameters, written to <out>/data/
+        #[arg(long = "data-driven")]
+        data_driven: bool,
+        /// Most rows per generated dataset (default 100)
+        #[arg(long = "data-limit", requires = "data_driven")]
+        data_limit: Option<usize>,
+        /// Write request URLs relative to baseUrl, e.g. /users, for suites
+        /// to join under their base_path
+        #[arg(long = "relative-urls")]
+        relative_urls: bool,
+    },
+    /// Serve a mock API from an OpenAPI spec or a suite
+    Mock {
+        /// OpenAPI specification whose operations to serve
+        #[arg(
+            long = "spec",
+            conflicts_with = "suite",
+            required_unless_present = "suite"
+        )]
+        spec: Option<PathBuf>,
+        /// Suite whose steps to serve, answering with what each step expects
+        #[arg(long = "suite")]
+        suite: Option<PathBuf>,
+        /// Port to listen on (0 picks a free one)
+        #[arg(long = "port", default_value = "8080")]
+        port: u16,
+        /// Address to listen on
+        #[arg(long = "host", default_value = "127.0.0.1")]
+        host: String,
+        /// Delay every response (e.g. 100ms, 1s)
+        #[arg(long = "latency")]
+        latency: Option<String>,
+        /// Answer this share of requests with a 500 (e.g. 5%)
+        #[arg(long = "chaos")]
+        chaos: Option<String>,
+        /// Seed for --chaos, to fail the same requests on every run
+        #[arg(long = "seed", requires = "chaos")]
+        seed: Option<u64>,
+        /// Log request headers and bodies too
+        #[arg(short = 'v', long = "verbose")]
+        verbose: bool,
+    },
+    /// Proxy traffic to an API and save each new request as a test
+    Record {
+        /// Address the proxy listens on
+        #[arg(long = "listen", default_value = "127.0.0.1:8899")]
+        listen: std::net::SocketAddr,
+        /// Base URL requests are forwarded to
+        #[arg(long = "target")]
+        target: String,
+        /// Output directory
+        #[arg(long = "out", default_value = "tests/")]
+        out: PathBuf,
+        /// Only record requests whose path matches this regex
+        #[arg(long = "filter-path")]
+        filter_path: Option<String>,
     },
     /// Generate coverage report
     Coverage {
@@ -100,40 +358,103 @@ enum Commands {
         /// Report files to analyze
         #[arg(long = "from")]
         from: Vec<PathBuf>,
-        /// Output file for coverage report
+        /// Output file for coverage report (.json or .html, plain text otherwise)
         #[arg(long = "out")]
         out: Option<PathBuf>,
+        /// Minimum coverage percentage; exit non-zero when below
+        #[arg(long = "min")]
+        min: Option<f64>,
+        /// Previously saved coverage JSON to compare against
+        #[arg(long = "baseline")]
+        baseline: Option<PathBuf>,
+        /// Exit non-zero if any operation covered in the baseline is now uncovered
+        #[arg(long = "fail-on-regression", requires = "baseline")]
+        fail_on_regression: bool,
+    },
+    /// Work with saved test reports
+    Report {
+        #[command(subcommand)]
+        action: ReportCommands,
+    },
+    /// View and edit settings in ~/.rivet/config.json
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
     },
     /// Import from other tools
     Import {
-        /// Tool to import from (postman, insomnia, bruno, curl)
+        /// Tool to import from (postman, insomnia, bruno, curl, openapi)
         tool: String,
-        /// File to import
-        file: PathBuf,
-        /// Output directory
+        /// File to import; `-` reads curl commands from stdin
+        #[arg(required_unless_present = "inline")]
+        file: Option<PathBuf>,
+        /// A single curl command to convert instead of a file
+        #[arg(long = "inline", value_name = "COMMAND", conflicts_with = "file")]
+        inline: Option<String>,
+        /// Output directory; `-` prints a curl import's suite to stdout
         #[arg(long = "out", default_value = "tests/")]
         out: PathBuf,
+        /// Write request URLs relative to baseUrl (postman, openapi)
+        #[arg(long = "relative-urls", conflicts_with = "inline")]
+        relative_urls: bool,
     },
     /// Make gRPC calls
     Grpc {
         /// gRPC server address (e.g., http://localhost:50051)
-        #[arg(long = "server")]
-        server: String,
+        #[arg(long = "server", required_unless_present_any = ["list", "describe", "from"])]
+        server: Option<String>,
         /// Proto files directory
-        #[arg(long = "proto")]
-        proto: PathBuf,
+        #[arg(long = "proto", required_unless_present = "from")]
+        proto: Option<PathBuf>,
         /// Service call (e.g., svc.Users/GetUser)
-        #[arg(long = "call")]
-        call: String,
+        #[arg(long = "call", required_unless_present_any = ["list", "describe", "from"])]
+        call: Option<String>,
         /// Request data (JSON)
         #[arg(long = "data")]
         data: Option<String>,
+        /// Request metadata in format "key: value" (repeatable; `-bin` keys take base64)
+        #[arg(short = 'H', long = "metadata", action = clap::ArgAction::Append)]
+        metadata: Vec<String>,
         /// JSONPath expectations
         #[arg(long = "expect-jsonpath")]
         expect_jsonpath: Vec<String>,
-        /// Request timeout
-        #[arg(long = "timeout", default_value = "30s")]
-        timeout: String,
+        /// Status the call should end with, by name or number (e.g., NOT_FOUND)
+        #[arg(long = "expect-status", value_name = "CODE")]
+        expect_status: Option<String>,
+        /// Request timeout [default: 30s]
+        #[arg(long = "timeout")]
+        timeout: Option<String>,
+        /// Replay a call saved with --save; other options override its values
+        #[arg(long = "from", value_name = "FILE", conflicts_with_all = ["list", "describe"])]
+        from: Option<PathBuf>,
+        /// Save the call as a suite that `rivet run` and `--from` can replay
+        #[arg(long = "save", value_name = "PATH", conflicts_with_all = ["list", "describe"])]
+        save: Option<PathBuf>,
+        /// Trust this CA certificate bundle (PEM) in addition to the system roots
+        #[arg(long = "cacert")]
+        cacert: Option<PathBuf>,
+        /// Skip TLS certificate verification
+        #[arg(long = "insecure")]
+        insecure: bool,
+        /// Server name to use for SNI and certificate verification
+        #[arg(long = "domain-override")]
+        domain_override: Option<String>,
+        /// Compile protos with the external protoc binary instead of in-process
+        #[arg(long = "use-protoc")]
+        use_protoc: bool,
+        /// List the services and methods in the protos (no server needed)
+        #[arg(long = "list", conflicts_with_all = ["call", "describe"])]
+        list: bool,
+        /// Show the request and response messages of a method (e.g., svc.Users/GetUser)
+        #[arg(
+            long = "describe",
+            value_name = "SERVICE/METHOD",
+            conflicts_with = "call"
+        )]
+        describe: Option<String>,
+        /// Output format: human, json
+        #[arg(long = "output", default_value = "human")]
+        output: String,
     },
     /// Run performance tests
     Perf {
@@ -148,21 +469,143 @@ enum Commands {
         /// Number of concurrent connections
         #[arg(long = "concurrent", default_value = "10")]
         concurrent: u32,
-        /// Warmup period before measuring
+        /// How long to send warmup traffic, at half the target rate, before measuring
         #[arg(long = "warmup", default_value = "5s")]
         warmup: String,
         /// Report interval during test
         #[arg(long = "report-interval", default_value = "5s")]
         report_interval: String,
-        /// Performance report output file
+        /// Performance report file, or a directory to write reports into
         #[arg(long = "output")]
         output: Option<PathBuf>,
-        /// Load pattern (constant, ramp-up, spike)
+        /// Report formats to save: json, html (comma-separated) [default: json]
+        #[arg(long = "report", value_name = "FORMATS")]
+        report: Option<String>,
+        /// Append a sample per report interval to this .csv or .ndjson file
+        #[arg(long = "timeseries", value_name = "PATH")]
+        timeseries: Option<PathBuf>,
+        /// Vary requests with rows from this CSV (or JSON) file instead of each suite's dataset
+        #[arg(long = "data", value_name = "FILE")]
+        data: Option<PathBuf>,
+        /// Only speak HTTP/1.1
+        #[arg(long = "http1-only", conflicts_with = "http2_only")]
+        http1_only: bool,
+        /// Only speak HTTP/2, without negotiating it first
+        #[arg(long = "http2-only")]
+        http2_only: bool,
+        /// Open a new connection for every request
+        #[arg(long = "no-keepalive")]
+        no_keepalive: bool,
+        /// How long idle connections are kept for reuse (e.g., "90s")
+        #[arg(long = "pool-idle-timeout", value_name = "DUR")]
+        pool_idle_timeout: Option<String>,
+        /// Idle connections kept per host
+        #[arg(
+            long = "pool-max-idle-per-host",
+            value_name = "N",
+            conflicts_with = "no_keepalive"
+        )]
+        pool_max_idle_per_host: Option<usize>,
+        /// Keep at most N bytes of each response body; 0 discards bodies
+        /// (default: 1 MiB)
+        #[arg(long = "max-capture-bytes", value_name = "N")]
+        max_capture_bytes: Option<usize>,
+        /// Show a live dashboard instead of periodic progress reports
+        /// (falls back to the reports when stdout isn't a terminal)
+        #[arg(long = "tui")]
+        tui: bool,
+        /// Serve live metrics for Prometheus at /metrics on this address
+        /// (e.g., "0.0.0.0:9095")
+        #[arg(long = "prometheus-listen", value_name = "ADDR")]
+        prometheus_listen: Option<String>,
+        /// Push the final results to this Prometheus Pushgateway
+        #[arg(long = "pushgateway", value_name = "URL")]
+        pushgateway: Option<String>,
+        /// Run ID exported metrics are labeled with (default: generated)
+        #[arg(long = "run-id", value_name = "ID")]
+        run_id: Option<String>,
+        /// Load pattern (constant, ramp-up, spike, step, ramp, stress)
         #[arg(long = "pattern", default_value = "constant")]
         pattern: String,
+        /// RPS added at each step of the step and stress patterns
+        #[arg(long = "step-rps", value_name = "N", default_value = "10")]
+        step_rps: u32,
+        /// How long each step of the step and stress patterns lasts
+        #[arg(long = "step-duration", value_name = "DUR", default_value = "30s")]
+        step_duration: String,
+        /// Ramp pattern: time to climb to the target RPS [default: a quarter of --duration]
+        #[arg(long = "ramp-up", value_name = "DUR")]
+        ramp_up: Option<String>,
+        /// Ramp pattern: time to hold the target RPS [default: the rest of --duration]
+        #[arg(long = "hold", value_name = "DUR")]
+        hold: Option<String>,
+        /// Ramp pattern: time to come back down to zero [default: a quarter of --duration]
+        #[arg(long = "ramp-down", value_name = "DUR")]
+        ramp_down: Option<String>,
+        /// Stress pattern: error rate (percent) at which a step breaks
+        #[arg(
+            long = "stress-max-error-rate",
+            value_name = "PCT",
+            default_value = "5"
+        )]
+        stress_max_error_rate: f64,
+        /// Stress pattern: P99 response time at which a step breaks
+        #[arg(long = "stress-max-p99", value_name = "DUR")]
+        stress_max_p99: Option<String>,
+        /// Only load test this suite (by name or file name); default is every suite
+        #[arg(long = "suite")]
+        suite: Option<String>,
+        /// Fail if the P95 response time exceeds this (e.g., 250ms)
+        #[arg(long = "fail-p95", value_name = "DUR")]
+        fail_p95: Option<String>,
+        /// Fail if the P99 response time exceeds this
+        #[arg(long = "fail-p99", value_name = "DUR")]
+        fail_p99: Option<String>,
+        /// Fail if the average response time exceeds this
+        #[arg(long = "fail-avg", value_name = "DUR")]
+        fail_avg: Option<String>,
+        /// Fail if throughput stays below this many requests per second
+        #[arg(long = "min-rps", value_name = "N")]
+        min_rps: Option<f64>,
+        /// Fail if the success rate (percent) is below this [default: 95]
+        #[arg(long = "min-success-rate", value_name = "PCT")]
+        min_success_rate: Option<f64>,
+        /// Count assertion failures against --min-success-rate, not just transport and HTTP errors
+        #[arg(long = "count-assertion-failures")]
+        count_assertion_failures: bool,
+        /// Previously saved JSON report to compare the run against
+        #[arg(long = "baseline", value_name = "FILE")]
+        baseline: Option<PathBuf>,
+        /// Fail if P50, P95 or P99 is this many percent slower than the baseline
+        #[arg(long = "fail-on-regression", value_name = "PCT", requires = "baseline")]
+        fail_on_regression: Option<f64>,
+        /// Perf config file with a `thresholds` block; flags take precedence
+        #[arg(long = "config")]
+        config: Option<PathBuf>,
         /// Environment to use
         #[arg(long = "env")]
         env: Option<String>,
+        /// Route requests through a proxy (http, https, socks5)
+        #[arg(long = "proxy", conflicts_with = "no_proxy")]
+        proxy: Option<String>,
+        /// Ignore proxy environment variables and configured proxies
+        #[arg(long = "no-proxy")]
+        no_proxy: bool,
+        /// Load variables from this file instead of ./.env
+        #[arg(long = "env-file")]
+        env_file: Option<PathBuf>,
+        /// Skip TLS certificate verification (self-signed dev servers)
+        #[arg(long = "insecure")]
+        insecure: bool,
+        /// Trust this CA certificate bundle (PEM) in addition to the system roots
+        #[arg(long = "cacert")]
+        cacert: Option<PathBuf>,
+        /// Retarget every suite at this base URL (overrides the `baseUrl` variable)
+        #[arg(long = "base-url")]
+        base_url: Option<String>,
+        /// Header "Name: value" for every HTTP step, over the suites' own (repeatable)
+        #[arg(short = 'H', long = "header", action = clap::ArgAction::Append)]
+        header: Vec<String>,
     },
     /// Generate shell completions (internal)
     #[command(hide = true)]
@@ -175,6 +618,18 @@ enum Commands {
     Man,
 }
 
+/// Exit with the code for how a run went: 0 passed, 1 test failures,
+/// 2 usage errors, 3 infrastructure errors, 130 interrupted.
+fn exit_for(outcome: anyhow::Result<exit::ExitReason>) {
+    let reason = outcome.unwrap_or_else(|e| {
+        eprintln!("Error: {:?}", e);
+        exit::ExitReason::for_error(&e)
+    });
+    if reason != exit::ExitReason::Passed {
+        std::process::exit(reason.code());
+    }
+}
+
 pub fn print_banner() {
     let banner = r#"
     ██████╗ ██╗██╗   ██╗███████╗████████╗
@@ -185,22 +640,86 @@ pub fn print_banner() {
     ╚═╝  ╚═╝╚═╝  ╚═══╝  ╚══════╝   ╚═╝
 "#;
 
-    if atty::is(atty::Stream::Stdout) {
+    if utils::is_interactive() {
         println!("{}", banner.cyan());
     } else {
         println!("rivet v0.1.0 — API testing that lives in git");
     }
 }
 
+#[derive(Subcommand)]
+enum ReportCommands {
+    /// Merge several JSON reports (e.g. from CI shards) into one combined report
+    Merge {
+        /// JSON report files to merge
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Report formats to generate (comma-separated: json, html, junit, markdown, csv)
+        #[arg(long = "format", default_value = "html")]
+        format: String,
+        /// Output directory
+        #[arg(long = "out", default_value = "./reports")]
+        out: PathBuf,
+        /// HTML template to use (detailed, simple, chatty, compact)
+        #[arg(long = "template")]
+        template: Option<String>,
+        /// Fixed report base name instead of timestamping
+        #[arg(long = "name")]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print a setting (e.g. reports.default_template), a section, or everything
+    Get {
+        /// Setting or section, as a dot path
+        key: Option<String>,
+    },
+    /// Change a setting; lists are comma-separated and an empty value unsets
+    Set {
+        /// Setting, as a dot path
+        key: String,
+        /// New value
+        value: String,
+        /// Write to ./.rivet/config.json, which overrides the home-dir file
+        #[arg(long = "project")]
+        project: bool,
+    },
+    /// Print every setting and its value
+    List,
+    /// Print where the config file is
+    Path {
+        /// Print the project config file instead
+        #[arg(long = "project")]
+        project: bool,
+    },
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    style::init(cli.no_color);
 
-    // Print banner for user-facing commands only
-    if !matches!(
-        cli.command,
-        Commands::Send { .. } | Commands::Completions { .. } | Commands::Man
-    ) {
+    // Print banner for user-facing commands only; JSON output must stay parseable
+    let json_output = matches!(
+        &cli.command,
+        Commands::Run { output, .. } | Commands::Grpc { output, .. }
+            if output.eq_ignore_ascii_case("json")
+    ) || matches!(cli.command, Commands::List { json: true, .. });
+    // Likewise a suite printed by `import --out -`
+    let suite_output =
+        matches!(&cli.command, Commands::Import { out, .. } if out.as_os_str() == "-");
+    if !json_output
+        && !suite_output
+        && !matches!(
+            cli.command,
+            Commands::Send { .. }
+                | Commands::Config { .. }
+                | Commands::Completions { .. }
+                | Commands::Man
+        )
+    {
         print_banner();
     }
 
@@ -209,60 +728,360 @@ async fn main() -> anyhow::Result<()> {
             method,
             url,
             headers,
+            query,
             data,
+            json,
+            form,
+            file,
+            bearer,
+            basic,
+            api_key,
             save,
             insecure,
             timeout,
+            output,
+            force,
+            verbose,
+            max_redirects,
+            no_follow_redirects,
+            retry,
+            retry_delay,
+            fail,
+            headers_only,
+            silent,
+            raw,
+            compression,
+            proxy,
+            no_proxy,
+            env_file,
+            cert,
+            key,
+            identity,
+            identity_password,
+            cacert,
         } => {
-            send::handle_send(method, url, headers, data, save, insecure, timeout).await?;
+            send::handle_send(send::SendOptions {
+                method,
+                url,
+                headers,
+                query,
+                data,
+                json,
+                form,
+                files: file,
+                bearer,
+                basic,
+                api_key,
+                save,
+                insecure,
+                timeout,
+                output,
+                force,
+                verbose,
+                max_redirects,
+                follow_redirects: !no_follow_redirects,
+                retry,
+                retry_delay,
+                fail,
+                headers_only,
+                silent,
+                raw,
+                compression,
+                proxy,
+                no_proxy,
+                env_file,
+                tls: TlsConfig {
+                    cert,
+                    key,
+                    identity,
+                    identity_password,
+                    cacert,
+                },
+            })
+            .await?;
         }
         Commands::Run {
             target,
             env,
             data,
-            parallel,
+            parallel_suites,
+            parallel_requests,
+            data_filter,
+            data_sample,
+            seed,
+            shuffle,
+            order,
             grep,
+            grep_invert,
+            secret,
+            verbose,
+            log_file,
+            max_capture_bytes,
+            strict,
+            summary_json,
+            output,
             bail,
+            max_failures,
             report,
             template,
+            report_dir,
+            report_name,
+            report_include_bodies,
+            history,
             open,
             no_open,
             ci,
+            proxy,
+            no_proxy,
+            env_file,
+            base_url,
+            header,
+            identify,
+            insecure,
+            cert,
+            key,
+            identity,
+            identity_password,
+            cacert,
         } => {
-            run::handle_run(run::RunOptions {
+            let outcome = run::handle_run(run::RunOptions {
                 target,
                 env,
                 _data: data,
-                parallel,
+                parallel_suites,
+                parallel_requests,
                 grep,
+                grep_invert,
                 bail,
+                max_failures,
                 report,
                 template,
+                report_dir,
+                report_name,
+                report_include_bodies,
+                history,
                 open,
                 no_open,
                 ci,
+                proxy,
+                no_proxy,
+                env_file,
+                tls: TlsConfig {
+                    cert,
+                    key,
+                    identity,
+                    identity_password,
+                    cacert,
+                },
+                insecure,
+                base_url,
+                headers: header,
+                identify,
+                data_filter,
+                data_sample,
+                seed,
+                shuffle,
+                order,
+                secrets: secret,
+                verbose,
+                log_file,
+                max_capture_bytes,
+                strict,
+                summary_json,
+                args: std::env::args().skip(1).collect(),
+                output,
             })
+            .await;
+            exit_for(outcome);
+        }
+        Commands::Validate { target, env_file } => {
+            validate::handle_validate(target, env_file.as_deref()).await?;
+        }
+        Commands::List {
+            target,
+            grep,
+            grep_invert,
+            json,
+        } => {
+            list::handle_list(
+                target,
+                list::ListOptions {
+                    grep,
+                    grep_invert,
+                    json,
+                },
+            )
+            .await?;
+        }
+        Commands::Gen {
+            spec,
+            out,
+            with_schemas,
+            server,
+            data_driven,
+            data_limit,
+            relative_urls,
+        } => {
+            gen::handle_gen(
+                &spec,
+                rivet::gen::GenOptions {
+                    out,
+                    with_schemas,
+                    server,
+                    data_driven,
+                    data_limit,
+                    relative_urls,
+                },
+            )
             .await?;
         }
-        Commands::Gen { spec, out } => {
-            gen::handle_gen(spec, out).await?;
+        Commands::Mock {
+            spec,
+            suite,
+            port,
+            host,
+            latency,
+            chaos,
+            seed,
+            verbose,
+        } => {
+            commands::mock::handle_mock(commands::mock::MockOptions {
+                spec,
+                suite,
+                host,
+                port,
+                latency,
+                chaos,
+                seed,
+                verbose,
+            })
+            .await?;
         }
-        Commands::Coverage { spec, from, out } => {
-            coverage::handle_coverage(spec, from, out).await?;
+        Commands::Record {
+            listen,
+            target,
+            out,
+            filter_path,
+        } => {
+            commands::record::handle_record(commands::record::RecordOptions {
+                listen,
+                target,
+                out,
+                filter_path,
+            })
+            .await?;
         }
-        Commands::Import { tool, file, out } => {
-            import::handle_import(tool, file, out).await?;
+        Commands::Coverage {
+            spec,
+            from,
+            out,
+            min,
+            baseline,
+            fail_on_regression,
+        } => {
+            coverage::handle_coverage(coverage::CoverageOptions {
+                spec,
+                from,
+                out,
+                min,
+                baseline,
+                fail_on_regression,
+            })
+            .await?;
         }
+        Commands::Report { action } => match action {
+            ReportCommands::Merge {
+                files,
+                format,
+                out,
+                template,
+                name,
+            } => {
+                commands::report::handle_merge(commands::report::MergeOptions {
+                    files,
+                    format,
+                    out,
+                    template,
+                    name,
+                })
+                .await?;
+            }
+        },
+        Commands::Config { action } => match action {
+            ConfigCommands::Get { key } => commands::config::handle_get(key.as_deref())?,
+            ConfigCommands::Set {
+                key,
+                value,
+                project,
+            } => commands::config::handle_set(&key, &value, project)?,
+            ConfigCommands::List => commands::config::handle_list()?,
+            ConfigCommands::Path { project } => commands::config::handle_path(project)?,
+        },
+        Commands::Import {
+            tool,
+            file,
+            inline,
+            out,
+            relative_urls,
+        } => match (inline, file) {
+            (Some(command), _) => import::handle_import_inline(tool, command, out).await?,
+            (None, Some(file)) => {
+                let options = rivet::import::ImportOptions { out, relative_urls };
+                import::handle_import(tool, file, options).await?
+            }
+            (None, None) => unreachable!("clap requires a file without --inline"),
+        },
         Commands::Grpc {
             server,
             proto,
             call,
             data,
+            metadata,
             expect_jsonpath,
+            expect_status,
             timeout,
+            from,
+            save,
+            cacert,
+            insecure,
+            domain_override,
+            use_protoc,
+            list,
+            describe,
+            output,
         } => {
-            commands::grpc::handle_grpc(server, proto, call, data, expect_jsonpath, timeout)
+            // clap requires --proto whenever --from is absent, which it is for list and describe
+            if list {
+                commands::grpc::handle_grpc_list(proto.unwrap_or_default(), use_protoc, &output)?;
+            } else if let Some(service_method) = describe {
+                commands::grpc::handle_grpc_describe(
+                    proto.unwrap_or_default(),
+                    &service_method,
+                    use_protoc,
+                    &output,
+                )?;
+            } else {
+                commands::grpc::handle_grpc(commands::grpc::GrpcOptions {
+                    server,
+                    proto,
+                    call,
+                    data,
+                    metadata,
+                    expect_jsonpath,
+                    expect_status,
+                    timeout,
+                    from,
+                    save,
+                    use_protoc,
+                    tls: rivet::grpc::GrpcTlsOptions {
+                        cacert,
+                        insecure,
+                        domain_override,
+                    },
+                    output,
+                })
                 .await?;
+            }
         }
         Commands::Perf {
             target,
@@ -272,10 +1091,47 @@ async fn main() -> anyhow::Result<()> {
             warmup,
             report_interval,
             output,
+            report,
+            timeseries,
+            data,
+            http1_only,
+            http2_only,
+            no_keepalive,
+            pool_idle_timeout,
+            pool_max_idle_per_host,
+            max_capture_bytes,
+            tui,
+            prometheus_listen,
+            pushgateway,
+            run_id,
             pattern,
+            step_rps,
+            step_duration,
+            ramp_up,
+            hold,
+            ramp_down,
+            stress_max_error_rate,
+            stress_max_p99,
+            suite,
+            fail_p95,
+            fail_p99,
+            fail_avg,
+            min_rps,
+            min_success_rate,
+            count_assertion_failures,
+            baseline,
+            fail_on_regression,
+            config,
             env,
+            proxy,
+            no_proxy,
+            env_file,
+            insecure,
+            cacert,
+            base_url,
+            header,
         } => {
-            commands::perf::handle_perf(commands::perf::PerfOptions {
+            let outcome = commands::perf::handle_perf(commands::perf::PerfOptions {
                 target,
                 duration,
                 rps,
@@ -283,10 +1139,48 @@ async fn main() -> anyhow::Result<()> {
                 warmup,
                 report_interval,
                 output,
+                report,
+                timeseries,
+                data,
+                http1_only,
+                http2_only,
+                no_keepalive,
+                pool_idle_timeout,
+                pool_max_idle_per_host,
+                max_capture_bytes,
+                tui,
+                prometheus_listen,
+                pushgateway,
+                run_id,
                 pattern,
+                step_rps,
+                step_duration,
+                ramp_up,
+                hold,
+                ramp_down,
+                stress_max_error_rate,
+                stress_max_p99,
+                suite,
+                fail_p95,
+                fail_p99,
+                fail_avg,
+                min_rps,
+                min_success_rate,
+                count_assertion_failures,
+                baseline,
+                fail_on_regression,
+                config,
                 env,
+                proxy,
+                no_proxy,
+                env_file,
+                insecure,
+                cacert,
+                base_url,
+                headers: header,
             })
-            .await?;
+            .await;
+            exit_for(outcome);
         }
         Commands::Completions { shell } => {
             // Generate completions to stdout for the requested shell
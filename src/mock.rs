@@ -0,0 +1,895 @@
+//! A mock HTTP API for `rivet mock`, served from the operations of an
+//! OpenAPI spec or the steps of a rivet suite.
+
+use crate::commands::coverage::{path_matches, server_base_paths};
+use crate::config::{RivetConfig, StatusExpectation, TestStep};
+use crate::gen::generate_example_body;
+use crate::runner::data::SplitMix64;
+use crate::runner::log::header_list;
+use crate::runner::variables::VariableContext;
+use crate::style::Colorize;
+use anyhow::{Context, Result};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Server};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+/// One method and path the mock answers, with the response it gives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockRoute {
+    pub method: String,
+    /// Path template; `{name}` segments match any value
+    pub path: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+/// What a request resolves to.
+#[derive(Debug, PartialEq)]
+pub enum RouteMatch<'a> {
+    Found(&'a MockRoute),
+    /// The path is known but not for this method; holds the methods it has
+    MethodNotAllowed(Vec<String>),
+    NotFound,
+}
+
+#[derive(Debug, Default)]
+pub struct MockApi {
+    routes: Vec<MockRoute>,
+    /// Prefixes requests may carry ahead of the route paths, such as `/v1`
+    base_paths: Vec<String>,
+}
+
+impl MockApi {
+    /// A route per operation, answering with its first 2xx response (200
+    /// for a `2XX` range or `default`) and that response's example, or one
+    /// built from its schema.
+    pub async fn from_spec(spec: &openapiv3::OpenAPI) -> Self {
+        let mut routes = Vec::new();
+        for (path, path_item) in &spec.paths.paths {
+            let openapiv3::ReferenceOr::Item(path_item) = path_item else {
+                continue;
+            };
+            for (method, operation) in path_item.iter() {
+                let (status, response) = success_response(spec, &operation.responses);
+                let (headers, body) = match response {
+                    Some(response) => response_content(spec, response).await,
+                    None => (Vec::new(), None),
+                };
+                routes.push(MockRoute {
+                    method: method.to_uppercase(),
+                    path: path.clone(),
+                    status,
+                    headers,
+                    body,
+                });
+            }
+        }
+        Self {
+            routes,
+            base_paths: server_base_paths(spec),
+        }
+    }
+
+    /// A route per HTTP step, answering with the status, headers and
+    /// JSONPath values the step expects. `{{var}}` path segments match any
+    /// value; when steps share a method and path, the first one wins.
+    pub fn from_suite(config: &RivetConfig) -> Result<Self> {
+        let context = VariableContext::new().with_config_vars(config.vars.as_ref())?;
+        let steps = [
+            &config.setup,
+            &config.before_each,
+            &Some(config.tests.clone()),
+            &config.after_each,
+            &config.teardown,
+        ];
+
+        let mut api = Self::default();
+        for step in steps.into_iter().flatten().flatten() {
+            if step.request.grpc.is_some() {
+                continue;
+            }
+            let (base_path, path) = suite_route_path(&step.request.url, &context);
+            if let Some(base_path) = base_path {
+                if !api.base_paths.contains(&base_path) {
+                    api.base_paths.push(base_path);
+                }
+            }
+            let method = step.request.method.to_uppercase();
+            if api.routes.iter().any(|route| {
+                route.method == method
+                    && path_matches(&route.path, &path)
+                    && path_matches(&path, &route.path)
+            }) {
+                continue;
+            }
+            api.routes.push(suite_route(step, method, path, &context));
+        }
+        Ok(api)
+    }
+
+    pub fn routes(&self) -> &[MockRoute] {
+        &self.routes
+    }
+
+    /// The route for `method` and `path`, trying the path as is and then
+    /// without each base path. Literal segments beat `{param}` ones, so
+    /// `/users/me` is preferred over `/users/{id}`. HEAD falls back to GET.
+    pub fn find(&self, method: &str, path: &str) -> RouteMatch<'_> {
+        let mut candidates = vec![path];
+        candidates.extend(
+            self.base_paths
+                .iter()
+                .filter_map(|base| path.strip_prefix(base.as_str()))
+                .filter(|rest| rest.is_empty() || rest.starts_with('/')),
+        );
+
+        let mut matching: Vec<&MockRoute> = self
+            .routes
+            .iter()
+            .filter(|route| {
+                candidates
+                    .iter()
+                    .any(|candidate| path_matches(&route.path, candidate))
+            })
+            .collect();
+        if matching.is_empty() {
+            return RouteMatch::NotFound;
+        }
+        // Stable, so equally specific routes keep their declared order
+        matching.sort_by_key(|route| std::cmp::Reverse(literal_segments(&route.path)));
+
+        let method = method.to_uppercase();
+        let found = matching
+            .iter()
+            .find(|route| route.method == method)
+            .or_else(|| {
+                (method == "HEAD")
+                    .then(|| matching.iter().find(|route| route.method == "GET"))
+                    .flatten()
+            });
+        match found {
+            Some(route) => RouteMatch::Found(route),
+            None => {
+                let mut allowed: Vec<String> =
+                    matching.iter().map(|route| route.method.clone()).collect();
+                allowed.sort();
+                allowed.dedup();
+                RouteMatch::MethodNotAllowed(allowed)
+            }
+        }
+    }
+}
+
+/// The status code and headers a mock sends back, and its body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// Failure injected by `--chaos` rather than a route's response
+    pub injected: bool,
+}
+
+impl MockResponse {
+    fn error(status: u16, message: String) -> Self {
+        Self {
+            status,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: json!({ "error": message }).to_string(),
+            injected: false,
+        }
+    }
+}
+
+/// Serves a [`MockApi`] over HTTP/1, logging every request it answers.
+pub struct MockServer {
+    api: MockApi,
+    latency: Duration,
+    /// Percentage of requests answered with an injected 500
+    chaos: f64,
+    verbose: bool,
+    rng: Mutex<SplitMix64>,
+}
+
+impl MockServer {
+    pub fn new(api: MockApi) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        Self {
+            api,
+            latency: Duration::ZERO,
+            chaos: 0.0,
+            verbose: false,
+            rng: Mutex::new(SplitMix64(seed)),
+        }
+    }
+
+    pub fn routes(&self) -> &[MockRoute] {
+        self.api.routes()
+    }
+
+    /// Delay every response by `latency`.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Answer `percent` of requests with a 500 instead of their route.
+    pub fn with_chaos(mut self, percent: f64) -> Self {
+        self.chaos = percent;
+        self
+    }
+
+    /// Seed the `--chaos` draws so a run fails the same requests again.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Mutex::new(SplitMix64(seed));
+        self
+    }
+
+    /// Log request headers and bodies, not just the request line.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn respond(&self, method: &str, path: &str) -> MockResponse {
+        if self.chaos > 0.0 && self.draw() < self.chaos {
+            return MockResponse {
+                injected: true,
+                ..MockResponse::error(500, "Injected failure (--chaos)".to_string())
+            };
+        }
+
+        match self.api.find(method, path) {
+            RouteMatch::Found(route) => MockResponse {
+                status: route.status,
+                headers: route.headers.clone(),
+                body: route.body.clone().unwrap_or_default(),
+                injected: false,
+            },
+            RouteMatch::MethodNotAllowed(allowed) => {
+                let mut response =
+                    MockResponse::error(405, format!("Method {} not allowed for {}", method, path));
+                response
+                    .headers
+                    .push(("allow".to_string(), allowed.join(", ")));
+                response
+            }
+            RouteMatch::NotFound => {
+                MockResponse::error(404, format!("No mock for {} {}", method, path))
+            }
+        }
+    }
+
+    /// A number in `[0, 100)`.
+    fn draw(&self) -> f64 {
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64 * 100.0
+    }
+
+    /// Start listening on `addr` in the background. Returns the address
+    /// bound, which tells the port when `addr` asked for port 0.
+    pub fn serve(self, addr: SocketAddr) -> Result<(SocketAddr, JoinHandle<()>)> {
+        let server = Arc::new(self);
+        let make_service = make_service_fn(move |_connection| {
+            let server = server.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_request(server.clone(), request)
+                }))
+            }
+        });
+
+        let listener = Server::try_bind(&addr)
+            .with_context(|| format!("Failed to listen on {}", addr))?
+            .serve(make_service);
+        let bound = listener.local_addr();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = listener.await {
+                eprintln!("{} Mock server stopped: {}", "✗".red(), e);
+            }
+        });
+        Ok((bound, handle))
+    }
+
+    fn log(&self, request: &LoggedExchange, response: &MockResponse, elapsed: Duration) {
+        let status = response.status.to_string();
+        let status = match response.status {
+            200..=399 => status.green().to_string(),
+            400..=499 => status.yellow().to_string(),
+            _ => status.red().to_string(),
+        };
+        let mut text = format!(
+            "{} {} {} {} {} {}{}\n",
+            chrono::Local::now().format("%H:%M:%S").to_string().dimmed(),
+            request.method.bold(),
+            request.target,
+            "→".dimmed(),
+            status,
+            format!("({}ms)", elapsed.as_millis()).dimmed(),
+            if response.injected {
+                format!(" {}", "chaos".red())
+            } else {
+                String::new()
+            }
+        );
+        if self.verbose {
+            for (name, value) in &request.headers {
+                text.push_str(&format!("  {} {}: {}\n", ">".dimmed(), name, value));
+            }
+            if !request.body.is_empty() {
+                text.push_str(&format!("  {} {}\n", ">".dimmed(), request.body));
+            }
+        }
+        print!("{}", text);
+    }
+}
+
+/// A served request as logged.
+struct LoggedExchange {
+    method: String,
+    /// Path and query string
+    target: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+async fn handle_request(
+    server: Arc<MockServer>,
+    request: hyper::Request<Body>,
+) -> Result<hyper::Response<Body>, Infallible> {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let target = request
+        .uri()
+        .path_and_query()
+        .map(|target| target.to_string())
+        .unwrap_or_else(|| path.clone());
+    let headers = header_list(request.headers());
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .unwrap_or_default();
+
+    if !server.latency.is_zero() {
+        tokio::time::sleep(server.latency).await;
+    }
+    let response = server.respond(&method, &path);
+    server.log(
+        &LoggedExchange {
+            method,
+            target,
+            headers,
+            body: String::from_utf8_lossy(&body).to_string(),
+        },
+        &response,
+        started.elapsed(),
+    );
+
+    let mut builder = hyper::Response::builder().status(response.status);
+    for (name, value) in &response.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    Ok(builder.body(Body::from(response.body)).unwrap_or_else(|_| {
+        let mut fallback = hyper::Response::new(Body::from("Invalid mock response"));
+        *fallback.status_mut() = hyper::StatusCode::INTERNAL_SERVER_ERROR;
+        fallback
+    }))
+}
+
+/// The status an operation succeeds with and the response documenting it.
+pub(crate) fn success_response<'a>(
+    spec: &'a openapiv3::OpenAPI,
+    responses: &'a openapiv3::Responses,
+) -> (u16, Option<&'a openapiv3::Response>) {
+    for (status_code, response) in &responses.responses {
+        let status = match status_code {
+            openapiv3::StatusCode::Code(code) if (200..300).contains(code) => *code,
+            openapiv3::StatusCode::Range(2) => 200,
+            _ => continue,
+        };
+        return (status, resolve_response(spec, response));
+    }
+    let default = responses
+        .default
+        .as_ref()
+        .and_then(|response| resolve_response(spec, response));
+    (200, default)
+}
+
+fn resolve_response<'a>(
+    spec: &'a openapiv3::OpenAPI,
+    response: &'a openapiv3::ReferenceOr<openapiv3::Response>,
+) -> Option<&'a openapiv3::Response> {
+    match response {
+        openapiv3::ReferenceOr::Item(response) => Some(response),
+        openapiv3::ReferenceOr::Reference { reference } => {
+            let name = reference.strip_prefix("#/components/responses/")?;
+            match spec.components.as_ref()?.responses.get(name)? {
+                openapiv3::ReferenceOr::Item(response) => Some(response),
+                openapiv3::ReferenceOr::Reference { .. } => None,
+            }
+        }
+    }
+}
+
+/// The content type header and body for a response, preferring JSON when
+/// it documents several content types.
+async fn response_content(
+    spec: &openapiv3::OpenAPI,
+    response: &openapiv3::Response,
+) -> (Vec<(String, String)>, Option<String>) {
+    let Some((content_type, media_type)) = response
+        .content
+        .get_key_value("application/json")
+        .or_else(|| response.content.first())
+    else {
+        return (Vec::new(), None);
+    };
+    let headers = vec![("content-type".to_string(), content_type.clone())];
+
+    let example = media_type.example.clone().or_else(|| {
+        media_type
+            .examples
+            .values()
+            .find_map(|example| match example {
+                openapiv3::ReferenceOr::Item(example) => example.value.clone(),
+                openapiv3::ReferenceOr::Reference { .. } => None,
+            })
+    });
+    if let Some(example) = example {
+        let body = match example {
+            Value::String(text) if !content_type.contains("json") => text,
+            example => serde_json::to_string_pretty(&example).unwrap_or_default(),
+        };
+        return (headers, Some(body));
+    }
+
+    // Resolve a top-level schema reference so `gen`'s schema examples apply
+    let mut media_type = media_type.clone();
+    if let Some(openapiv3::ReferenceOr::Reference { reference }) = &media_type.schema {
+        media_type.schema = reference
+            .strip_prefix("#/components/schemas/")
+            .and_then(|name| spec.components.as_ref()?.schemas.get(name))
+            .cloned();
+    }
+    let content = HashMap::from([(content_type.clone(), media_type)]);
+    (headers, generate_example_body(&content).await)
+}
+
+fn literal_segments(template: &str) -> usize {
+    template
+        .split('/')
+        .filter(|segment| !segment.is_empty() && !segment.starts_with('{'))
+        .count()
+}
+
+/// The route path of a step URL, and the base path its leading variable
+/// holds, if any. `http://host/users/{{id}}?x=1` gives `/users/{id}`, and
+/// `{{baseUrl}}/users` with `baseUrl: http://host/v1` gives `/v1` and
+/// `/users`.
+fn suite_route_path(url: &str, context: &VariableContext) -> (Option<String>, String) {
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+
+    let mut base_path = None;
+    let path = if let Some(rest) = leading_variable_end(url).map(|end| &url[end..]) {
+        let prefix = context.substitute_variables(&url[..url.len() - rest.len()]);
+        base_path = url::Url::parse(&prefix)
+            .ok()
+            .map(|base| base.path().trim_end_matches('/').to_string())
+            .filter(|path| !path.is_empty());
+        rest
+    } else if let Some(idx) = url.find("://") {
+        let rest = &url[idx + 3..];
+        rest.find('/').map(|slash| &rest[slash..]).unwrap_or("/")
+    } else {
+        url
+    };
+
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            if segment.contains("{{") || segment.contains("${") {
+                let name: String = segment
+                    .chars()
+                    .filter(|c| c.is_alphanumeric() || *c == '_')
+                    .collect();
+                format!("{{{}}}", name)
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect();
+    (base_path, format!("/{}", segments.join("/")))
+}
+
+/// Where a `{{var}}` or `${VAR}` that starts `url` ends.
+fn leading_variable_end(url: &str) -> Option<usize> {
+    if url.starts_with("{{") {
+        url.find("}}").map(|end| end + 2)
+    } else if url.starts_with("${") {
+        url.find('}').map(|end| end + 1)
+    } else {
+        None
+    }
+}
+
+fn suite_route(
+    step: &TestStep,
+    method: String,
+    path: String,
+    context: &VariableContext,
+) -> MockRoute {
+    let expect = step.expect.as_ref();
+    let status = match expect.and_then(|expect| expect.status.as_ref()) {
+        Some(StatusExpectation::Number(code)) => *code,
+        Some(StatusExpectation::String(code)) => {
+            context.substitute_variables(code).parse().unwrap_or(200)
+        }
+        None => 200,
+    };
+
+    let mut headers: Vec<(String, String)> = expect
+        .and_then(|expect| expect.headers.as_ref())
+        .map(|expected| {
+            expected
+                .iter()
+                .map(|(name, value)| (name.to_lowercase(), context.substitute_variables(value)))
+                .collect()
+        })
+        .unwrap_or_default();
+    headers.sort();
+
+    let body = expect
+        .and_then(|expect| expect.jsonpath.as_ref())
+        .map(|expected| {
+            let mut expected: Vec<_> = expected.iter().collect();
+            expected.sort_by_key(|(path, _)| path.as_str());
+            let mut body = Value::Null;
+            for (path, value) in expected {
+                if let Some(keys) = parse_json_path(path) {
+                    insert_at(&mut body, &keys, substitute_value(value, context));
+                }
+            }
+            serde_json::to_string_pretty(&body).unwrap_or_default()
+        });
+    if body.is_some() && !headers.iter().any(|(name, _)| name == "content-type") {
+        headers.push(("content-type".to_string(), "application/json".to_string()));
+    }
+
+    MockRoute {
+        method,
+        path,
+        status,
+        headers,
+        body,
+    }
+}
+
+/// An expected value as the runner compares it: strings have variables
+/// substituted and become numbers or booleans when they look like one.
+fn substitute_value(value: &Value, context: &VariableContext) -> Value {
+    match value {
+        Value::String(text) => {
+            let substituted = context.substitute_variables(text);
+            if let Ok(number) = substituted.parse::<i64>() {
+                Value::Number(number.into())
+            } else if let Ok(flag) = substituted.parse::<bool>() {
+                Value::Bool(flag)
+            } else {
+                Value::String(substituted)
+            }
+        }
+        other => other.clone(),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum JsonKey {
+    Field(String),
+    Index(usize),
+}
+
+/// The keys of a plain JSONPath such as `$.users[0].name` or `$['a b']`;
+/// `None` for wildcards, filters and recursive descent, which name no
+/// single place in a body.
+fn parse_json_path(path: &str) -> Option<Vec<JsonKey>> {
+    let mut rest = path.strip_prefix('$')?;
+    let mut keys = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            let name = &after[..end];
+            if name.is_empty() || name == "*" {
+                return None;
+            }
+            keys.push(JsonKey::Field(name.to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']')?;
+            let inner = &after[..end];
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|name| name.strip_suffix('\''))
+                .or_else(|| {
+                    inner
+                        .strip_prefix('"')
+                        .and_then(|name| name.strip_suffix('"'))
+                });
+            match quoted {
+                Some(name) => keys.push(JsonKey::Field(name.to_string())),
+                None => keys.push(JsonKey::Index(inner.parse().ok()?)),
+            }
+            rest = &after[end + 1..];
+        } else {
+            return None;
+        }
+    }
+    Some(keys)
+}
+
+/// Set `value` at `keys` under `root`, creating objects and arrays on the
+/// way. A key that conflicts with what an earlier one built is skipped.
+fn insert_at(root: &mut Value, keys: &[JsonKey], value: Value) {
+    let Some((key, rest)) = keys.split_first() else {
+        *root = value;
+        return;
+    };
+    match key {
+        JsonKey::Field(name) => {
+            if root.is_null() {
+                *root = Value::Object(Default::default());
+            }
+            if let Value::Object(fields) = root {
+                insert_at(
+                    fields.entry(name.clone()).or_insert(Value::Null),
+                    rest,
+                    value,
+                );
+            }
+        }
+        JsonKey::Index(index) => {
+            if root.is_null() {
+                *root = Value::Array(Vec::new());
+            }
+            if let Value::Array(items) = root {
+                if items.len() <= *index {
+                    items.resize(index + 1, Value::Null);
+                }
+                insert_at(&mut items[*index], rest, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(method: &str, path: &str, status: u16) -> MockRoute {
+        MockRoute {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    fn status_of(api: &MockApi, method: &str, path: &str) -> Option<u16> {
+        match api.find(method, path) {
+            RouteMatch::Found(route) => Some(route.status),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_find_prefers_literal_segments() {
+        let api = MockApi {
+            routes: vec![
+                route("GET", "/users/{id}", 200),
+                route("GET", "/users/me", 201),
+                route("DELETE", "/users/{id}", 204),
+            ],
+            base_paths: vec!["/v1".to_string()],
+        };
+
+        assert_eq!(status_of(&api, "GET", "/users/42"), Some(200));
+        assert_eq!(status_of(&api, "GET", "/users/me"), Some(201));
+        assert_eq!(status_of(&api, "GET", "/v1/users/42"), Some(200));
+        assert_eq!(status_of(&api, "HEAD", "/users/42"), Some(200));
+        assert_eq!(status_of(&api, "delete", "/users/me"), Some(204));
+        assert_eq!(
+            api.find("POST", "/users/42"),
+            RouteMatch::MethodNotAllowed(vec!["DELETE".to_string(), "GET".to_string()])
+        );
+        assert_eq!(api.find("GET", "/users"), RouteMatch::NotFound);
+        assert_eq!(api.find("GET", "/v1x/users/42"), RouteMatch::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_from_spec_uses_examples_and_schemas() {
+        let spec: openapiv3::OpenAPI = serde_yaml::from_str(
+            r##"
+openapi: 3.0.0
+info: { title: Pets, version: "1" }
+servers:
+  - url: https://api.example.com/v2
+paths:
+  /pets:
+    post:
+      responses:
+        "201":
+          description: Created
+          content:
+            application/json:
+              schema: { $ref: "#/components/schemas/Pet" }
+  /pets/{id}:
+    get:
+      responses:
+        "404": { description: Missing }
+        "200":
+          description: A pet
+          content:
+            application/json:
+              example: { id: 7, name: Rex }
+    delete:
+      responses:
+        "204": { description: Deleted }
+  /health:
+    get:
+      responses:
+        default:
+          description: Up
+          content:
+            text/plain:
+              example: ok
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name: { type: string }
+"##,
+        )
+        .unwrap();
+        let api = MockApi::from_spec(&spec).await;
+
+        let RouteMatch::Found(get) = api.find("GET", "/v2/pets/7") else {
+            panic!("GET /pets/{{id}} should match");
+        };
+        assert_eq!(get.status, 200);
+        let body: Value = serde_json::from_str(get.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body, json!({"id": 7, "name": "Rex"}));
+
+        let RouteMatch::Found(post) = api.find("POST", "/pets") else {
+            panic!("POST /pets should match");
+        };
+        assert_eq!(post.status, 201);
+        let body: Value = serde_json::from_str(post.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body, json!({"name": "example"}));
+
+        let RouteMatch::Found(delete) = api.find("DELETE", "/pets/7") else {
+            panic!("DELETE /pets/{{id}} should match");
+        };
+        assert_eq!((delete.status, delete.body.as_deref()), (204, None));
+
+        let RouteMatch::Found(health) = api.find("GET", "/health") else {
+            panic!("GET /health should match");
+        };
+        assert_eq!(health.body.as_deref(), Some("ok"));
+        assert_eq!(
+            health.headers,
+            vec![("content-type".to_string(), "text/plain".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_from_suite_answers_with_expectations() {
+        let config: RivetConfig = serde_yaml::from_str(
+            r#"
+name: Users
+vars:
+  baseUrl: http://localhost:3000/api
+  userName: Ada
+tests:
+  - name: Get user
+    request:
+      method: GET
+      url: "{{baseUrl}}/users/{{userId}}?fields=all"
+    expect:
+      status: 200
+      headers:
+        X-Request-Id: abc
+      jsonpath:
+        "$.name": "{{userName}}"
+        "$.roles[1]": admin
+        "$.address.city": Paris
+        "$..id": 1
+  - name: Get user again
+    request:
+      method: GET
+      url: "{{baseUrl}}/users/{{otherId}}"
+    expect:
+      status: 500
+  - name: Create user
+    request:
+      method: post
+      url: http://localhost:3000/api/users
+    expect:
+      status: "201"
+"#,
+        )
+        .unwrap();
+        let api = MockApi::from_suite(&config).unwrap();
+
+        assert_eq!(api.base_paths, vec!["/api".to_string()]);
+        assert_eq!(api.routes().len(), 2);
+        let get = &api.routes()[0];
+        assert_eq!(get.path, "/users/{userId}");
+        assert_eq!(
+            get.headers,
+            vec![
+                ("x-request-id".to_string(), "abc".to_string()),
+                ("content-type".to_string(), "application/json".to_string()),
+            ]
+        );
+        let body: Value = serde_json::from_str(get.body.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            body,
+            json!({"name": "Ada", "roles": [null, "admin"], "address": {"city": "Paris"}})
+        );
+
+        assert_eq!(status_of(&api, "GET", "/api/users/9"), Some(200));
+        assert_eq!(status_of(&api, "POST", "/api/users"), Some(201));
+    }
+
+    #[test]
+    fn test_parse_json_path() {
+        assert_eq!(
+            parse_json_path("$.a['b c'][2]"),
+            Some(vec![
+                JsonKey::Field("a".to_string()),
+                JsonKey::Field("b c".to_string()),
+                JsonKey::Index(2),
+            ])
+        );
+        assert_eq!(parse_json_path("$"), Some(Vec::new()));
+        assert_eq!(parse_json_path("$.items[*].id"), None);
+        assert_eq!(parse_json_path("$..id"), None);
+        assert_eq!(parse_json_path("name"), None);
+    }
+
+    #[test]
+    fn test_chaos_injects_failures() {
+        let api = MockApi {
+            routes: vec![route("GET", "/", 200)],
+            base_paths: Vec::new(),
+        };
+        let server = MockServer::new(api).with_chaos(100.0).with_seed(7);
+        let response = server.respond("GET", "/");
+        assert_eq!((response.status, response.injected), (500, true));
+
+        let server = server.with_chaos(30.0);
+        let failed = (0..1000)
+            .filter(|_| server.respond("GET", "/").injected)
+            .count();
+        assert!((200..400).contains(&failed), "{} of 1000 failed", failed);
+
+        let response = server.with_chaos(0.0).respond("PUT", "/");
+        assert_eq!(response.status, 405);
+        assert!(response
+            .headers
+            .contains(&("allow".to_string(), "GET".to_string())));
+    }
+}
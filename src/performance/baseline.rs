@@ -0,0 +1,213 @@
+use crate::performance::PerformanceResults;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The figures of a saved report that a run is compared on. Each is optional
+/// so reports from older versions, which lack some of them, still load.
+#[derive(Debug, Default, Deserialize)]
+pub struct BaselineFigures {
+    requests_per_second: Option<f64>,
+    /// Fraction, 0-1
+    success_rate: Option<f64>,
+    /// Milliseconds, as saved
+    p50_response_time: Option<u64>,
+    p95_response_time: Option<u64>,
+    p99_response_time: Option<u64>,
+}
+
+impl BaselineFigures {
+    /// Read the figures from a JSON report saved by `rivet perf --output`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse baseline {}", path.display()))
+    }
+}
+
+/// How a run compares with a baseline, as written to the JSON report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineComparison {
+    /// The baseline report, as given on the command line
+    pub baseline: String,
+    /// Latency increase (percent) that fails the run, if one was set
+    pub max_regression: Option<f64>,
+    /// One entry per figure the baseline has
+    pub metrics: Vec<MetricDelta>,
+}
+
+/// One figure of the run next to the baseline's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricDelta {
+    /// `rps`, `error_rate`, `p50`, `p95` or `p99`
+    pub name: String,
+    /// `rps`, `%` or `ms`
+    pub unit: String,
+    pub baseline: f64,
+    pub current: f64,
+    /// Percent change from the baseline, or percentage points for
+    /// `error_rate`; unset when the baseline is zero
+    pub change: Option<f64>,
+    /// Whether the run did worse than the baseline
+    pub worse: bool,
+    /// Whether a latency grew by more than the allowed regression
+    pub regressed: bool,
+}
+
+impl fmt::Display for MetricDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({} → {})",
+            self.name,
+            self.change_text(),
+            self.value_text(self.baseline),
+            self.value_text(self.current)
+        )
+    }
+}
+
+impl MetricDelta {
+    pub fn value_text(&self, value: f64) -> String {
+        match self.unit.as_str() {
+            "ms" => format!("{:.0}ms", value),
+            "%" => format!("{:.2}%", value),
+            unit => format!("{:.1} {}", value, unit),
+        }
+    }
+
+    pub fn change_text(&self) -> String {
+        match (self.change, self.unit.as_str()) {
+            (Some(change), "%") => format!("{:+.2} pts", change),
+            (Some(change), _) => format!("{:+.1}%", change),
+            (None, _) => "n/a".to_string(),
+        }
+    }
+
+    /// Whether the figure moved at all.
+    pub fn changed(&self) -> bool {
+        self.baseline != self.current
+    }
+}
+
+impl BaselineComparison {
+    /// Compare `results` with `figures`. Latency percentiles that grew by
+    /// more than `max_regression` percent are marked regressed.
+    pub fn new(
+        baseline: &Path,
+        figures: &BaselineFigures,
+        results: &PerformanceResults,
+        max_regression: Option<f64>,
+    ) -> Self {
+        let mut metrics = Vec::new();
+        let relative = |baseline: f64, current: f64| {
+            (baseline != 0.0).then(|| (current - baseline) / baseline * 100.0)
+        };
+
+        if let Some(rps) = figures.requests_per_second {
+            let current = results.requests_per_second;
+            metrics.push(MetricDelta {
+                name: "rps".to_string(),
+                unit: "rps".to_string(),
+                baseline: rps,
+                current,
+                change: relative(rps, current),
+                worse: current < rps,
+                regressed: false,
+            });
+        }
+
+        if let Some(success_rate) = figures.success_rate {
+            let baseline = (1.0 - success_rate) * 100.0;
+            let current = (1.0 - results.success_rate) * 100.0;
+            metrics.push(MetricDelta {
+                name: "error_rate".to_string(),
+                unit: "%".to_string(),
+                baseline,
+                current,
+                change: Some(current - baseline),
+                worse: current > baseline,
+                regressed: false,
+            });
+        }
+
+        let latencies = [
+            ("p50", figures.p50_response_time, results.p50_response_time),
+            ("p95", figures.p95_response_time, results.p95_response_time),
+            ("p99", figures.p99_response_time, results.p99_response_time),
+        ];
+        for (name, baseline, current) in latencies {
+            let Some(baseline) = baseline else {
+                continue;
+            };
+            let baseline = baseline as f64;
+            let current = current.as_millis() as f64;
+            let change = relative(baseline, current);
+            metrics.push(MetricDelta {
+                name: name.to_string(),
+                unit: "ms".to_string(),
+                baseline,
+                current,
+                change,
+                worse: current > baseline,
+                regressed: matches!(
+                    (change, max_regression),
+                    (Some(change), Some(max)) if change > max
+                ),
+            });
+        }
+
+        Self {
+            baseline: baseline.display().to_string(),
+            max_regression,
+            metrics,
+        }
+    }
+
+    pub fn regressions(&self) -> impl Iterator<Item = &MetricDelta> {
+        self.metrics.iter().filter(|metric| metric.regressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::PerformanceMetrics;
+    use std::time::Duration;
+
+    #[test]
+    fn test_compare_with_an_older_baseline() {
+        // Written before percentiles other than p95 were saved
+        let figures: BaselineFigures = serde_json::from_str(
+            r#"{"requests_per_second": 100.0, "success_rate": 1.0, "p95_response_time": 100}"#,
+        )
+        .unwrap();
+
+        let mut metrics = PerformanceMetrics::new();
+        for _ in 0..9 {
+            metrics.record_request(Duration::from_millis(140), 200, 10, 10, false);
+        }
+        metrics.record_request(Duration::from_millis(140), 500, 10, 10, true);
+        let results = metrics.calculate_results();
+
+        let comparison =
+            BaselineComparison::new(Path::new("main.json"), &figures, &results, Some(25.0));
+        let names: Vec<_> = comparison.metrics.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["rps", "error_rate", "p95"]);
+
+        let error_rate = &comparison.metrics[1];
+        assert!(error_rate.worse);
+        assert_eq!(error_rate.change_text(), "+10.00 pts");
+
+        let regressions: Vec<_> = comparison.regressions().collect();
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].to_string(), "p95 +40.0% (100ms → 140ms)");
+
+        let lenient =
+            BaselineComparison::new(Path::new("main.json"), &figures, &results, Some(50.0));
+        assert_eq!(lenient.regressions().count(), 0);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::utils::ClientOptions;
+use anyhow::{Context, Result};
+use reqwest::redirect::Policy;
+use reqwest::Client;
+use std::time::Duration;
+
+/// How the load test client uses connections. Defaults are reqwest's:
+/// HTTP/1.1 or HTTP/2 as negotiated, with idle connections kept for reuse.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionOptions {
+    /// Speak HTTP/1.1 only
+    pub http1_only: bool,
+    /// Speak HTTP/2 only, without negotiating (prior knowledge)
+    pub http2_only: bool,
+    /// Open a new connection for every request
+    pub no_keepalive: bool,
+    /// How long an idle connection is kept for reuse
+    pub pool_idle_timeout: Option<Duration>,
+    /// Idle connections kept per host
+    pub pool_max_idle_per_host: Option<usize>,
+}
+
+impl ConnectionOptions {
+    /// Build the client a load test sends its requests with.
+    pub fn build_client(
+        &self,
+        timeout: Duration,
+        options: &ClientOptions,
+        redirect: Policy,
+    ) -> Result<Client> {
+        if self.http1_only && self.http2_only {
+            anyhow::bail!("HTTP/1 only and HTTP/2 only can't both be set");
+        }
+
+        let mut builder =
+            options.configure(Client::builder().timeout(timeout).redirect(redirect))?;
+        if self.http1_only {
+            builder = builder.http1_only();
+        }
+        if self.http2_only {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(idle_timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(idle_timeout);
+        }
+        // Keeping no idle connections makes every request open its own
+        let max_idle = if self.no_keepalive {
+            Some(0)
+        } else {
+            self.pool_max_idle_per_host
+        };
+        if let Some(max_idle) = max_idle {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+
+        builder.build().context("Failed to create HTTP client")
+    }
+}
@@ -0,0 +1,229 @@
+use crate::performance::patterns::LoadController;
+use crate::performance::{PerformanceMetrics, PerformanceResults};
+use crate::style::Colorize;
+use crate::ui::screen;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// How often the dashboard is redrawn.
+const REFRESH: Duration = Duration::from_secs(1);
+
+/// RPS samples (one per refresh) the sparkline shows.
+const HISTORY: usize = 60;
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// An in-place view of a running load test, drawn on the alternate screen.
+/// Everything shown comes from the run's shared [`PerformanceMetrics`].
+pub struct Dashboard {
+    target_duration: Duration,
+    start_time: Instant,
+    /// Requests per second over each refresh, oldest first
+    rps_history: VecDeque<f64>,
+    last_total: u64,
+}
+
+impl Dashboard {
+    pub fn new(target_duration: Duration) -> Self {
+        Self {
+            target_duration,
+            start_time: Instant::now(),
+            rps_history: VecDeque::with_capacity(HISTORY),
+            last_total: 0,
+        }
+    }
+
+    /// Take over the terminal and redraw every second until the returned
+    /// guard is dropped, which restores the terminal.
+    pub fn spawn(
+        mut self,
+        metrics: Arc<Mutex<PerformanceMetrics>>,
+        load_controller: Arc<LoadController>,
+    ) -> io::Result<DashboardGuard> {
+        screen::enter()?;
+
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REFRESH);
+            loop {
+                interval.tick().await;
+                let results = metrics.lock().await.calculate_results();
+                let lines = self.frame(
+                    &results,
+                    &load_controller.current_phase_description(),
+                    load_controller.active_target_rps(),
+                );
+                if screen::draw(&lines).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(DashboardGuard { task })
+    }
+
+    /// The dashboard's lines for `results`, recording the rate since the
+    /// previous frame for the sparkline.
+    fn frame(
+        &mut self,
+        results: &PerformanceResults,
+        phase: &str,
+        target_rps: Option<f64>,
+    ) -> Vec<String> {
+        let rps =
+            results.total_requests.saturating_sub(self.last_total) as f64 / REFRESH.as_secs_f64();
+        self.last_total = results.total_requests;
+        if self.rps_history.len() == HISTORY {
+            self.rps_history.pop_front();
+        }
+        self.rps_history.push_back(rps);
+
+        let elapsed = self.start_time.elapsed().min(self.target_duration);
+        let progress = if self.target_duration.is_zero() {
+            0.0
+        } else {
+            elapsed.as_secs_f64() / self.target_duration.as_secs_f64()
+        };
+        let bar_width = 40;
+        let filled = (progress * bar_width as f64) as usize;
+
+        let mut lines = vec![
+            format!(
+                "{} rivet perf - {}",
+                "📊".bright_white(),
+                phase.bright_white()
+            ),
+            String::new(),
+            format!(
+                "  [{}{}] {:.0}%  {}s / {}s",
+                "█".repeat(filled).green(),
+                "░".repeat(bar_width - filled).dimmed(),
+                progress * 100.0,
+                elapsed.as_secs(),
+                self.target_duration.as_secs()
+            ),
+            String::new(),
+            format!(
+                "  RPS       {}  {:.1}{}",
+                sparkline(self.rps_history.make_contiguous()).cyan(),
+                rps,
+                target_rps
+                    .map(|target| format!(" (target {:.0})", target))
+                    .unwrap_or_default()
+            ),
+            format!(
+                "  Requests  {}  failed {} ({:.2}%)",
+                results.total_requests.to_string().bright_white(),
+                results.failed_requests,
+                (1.0 - results.success_rate) * 100.0
+            ),
+            format!(
+                "  Latency   p50 {}ms  p95 {}ms  p99 {}ms",
+                results
+                    .p50_response_time
+                    .as_millis()
+                    .to_string()
+                    .bright_white(),
+                results
+                    .p95_response_time
+                    .as_millis()
+                    .to_string()
+                    .bright_white(),
+                results
+                    .p99_response_time
+                    .as_millis()
+                    .to_string()
+                    .bright_white()
+            ),
+        ];
+
+        let mut codes: Vec<_> = results.status_code_distribution.iter().collect();
+        codes.sort_by_key(|(code, _)| **code);
+        let codes: Vec<String> = codes
+            .into_iter()
+            .map(|(code, count)| {
+                let text = format!("{}: {}", code, count);
+                match code {
+                    200..=299 => text.green().to_string(),
+                    300..=399 => text.yellow().to_string(),
+                    _ => text.red().to_string(),
+                }
+            })
+            .collect();
+        if !codes.is_empty() {
+            lines.push(format!("  Status    {}", codes.join("  ")));
+        }
+        if results.failed_requests > 0 {
+            lines.push(format!("  Errors    {}", results.error_breakdown));
+        }
+        lines.push(String::new());
+        lines.push(format!("  {}", "Ctrl-C stops the run".dimmed()));
+        lines
+    }
+}
+
+/// Stops the dashboard and restores the terminal when dropped.
+pub struct DashboardGuard {
+    task: JoinHandle<()>,
+}
+
+impl Drop for DashboardGuard {
+    fn drop(&mut self) {
+        self.task.abort();
+        screen::restore_terminal();
+    }
+}
+
+/// One bar per value, scaled to the largest.
+fn sparkline(values: &[f64]) -> String {
+    let max = values.iter().cloned().fold(0.0, f64::max);
+    values
+        .iter()
+        .map(|value| {
+            if max <= 0.0 {
+                SPARKS[0]
+            } else {
+                let level = (value / max * (SPARKS.len() - 1) as f64).round() as usize;
+                SPARKS[level.min(SPARKS.len() - 1)]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline_scales_to_the_peak() {
+        assert_eq!(sparkline(&[0.0, 50.0, 100.0]), "▁▅█");
+        assert_eq!(sparkline(&[0.0, 0.0]), "▁▁");
+    }
+
+    #[test]
+    fn test_frame_shows_live_figures() {
+        let mut metrics = PerformanceMetrics::new();
+        for _ in 0..9 {
+            metrics.record_request(Duration::from_millis(20), 200, 10, 10, false);
+        }
+        metrics.record_request(Duration::from_millis(20), 503, 10, 10, true);
+
+        let mut dashboard = Dashboard::new(Duration::from_secs(60));
+        let lines = dashboard.frame(&metrics.calculate_results(), "Constant load", Some(50.0));
+        let text = lines.join("\n");
+        assert!(text.contains("Constant load"), "{}", text);
+        assert!(text.contains("(target 50)"), "{}", text);
+        assert!(text.contains("p95 "), "{}", text);
+        assert!(text.contains("503: 1"), "{}", text);
+        assert!(text.contains("5xx"), "{}", text);
+
+        // The sparkline only counts requests made since the previous frame
+        dashboard.frame(&metrics.calculate_results(), "Constant load", None);
+        assert_eq!(
+            dashboard.rps_history.iter().cloned().collect::<Vec<_>>(),
+            [10.0, 0.0]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use hdrhistogram::Histogram;
+use std::time::Duration;
+
+/// Slowest response time the histogram resolves; slower ones count as this.
+const HIGHEST_TRACKABLE: Duration = Duration::from_secs(3600);
+
+/// Response times in a fixed-size histogram (microsecond resolution, three
+/// significant digits), so memory stays constant however long a run is and
+/// percentiles are read without sorting. Min, max and the total are kept
+/// exactly.
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    histogram: Histogram<u64>,
+    min: Duration,
+    max: Duration,
+    total: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            histogram: Histogram::new_with_bounds(1, HIGHEST_TRACKABLE.as_micros() as u64, 3)
+                .expect("valid histogram bounds"),
+            min: Duration::ZERO,
+            max: Duration::ZERO,
+            total: Duration::ZERO,
+        }
+    }
+
+    pub fn record(&mut self, response_time: Duration) {
+        if self.is_empty() || response_time < self.min {
+            self.min = response_time;
+        }
+        self.max = self.max.max(response_time);
+        self.total += response_time;
+        self.histogram
+            .saturating_record((response_time.as_micros() as u64).max(1));
+    }
+
+    pub fn clear(&mut self) {
+        self.histogram.reset();
+        self.min = Duration::ZERO;
+        self.max = Duration::ZERO;
+        self.total = Duration::ZERO;
+    }
+
+    pub fn len(&self) -> u64 {
+        self.histogram.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histogram.is_empty()
+    }
+
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.is_empty() {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total.as_nanos() / self.len() as u128) as u64)
+        }
+    }
+
+    /// The response time `percent` (0-100) of requests were at or under,
+    /// to within the histogram's precision.
+    pub fn percentile(&self, percent: f64) -> Duration {
+        if self.is_empty() {
+            return Duration::ZERO;
+        }
+        let micros = self.histogram.value_at_quantile(percent / 100.0);
+        // Bucket edges can round past the slowest response actually seen
+        Duration::from_micros(micros).min(self.max)
+    }
+
+    /// Sum of every response time recorded.
+    pub fn sum(&self) -> Duration {
+        self.total
+    }
+
+    /// How many responses took at most `bound`, to within the histogram's
+    /// precision.
+    pub fn count_up_to(&self, bound: Duration) -> u64 {
+        let micros = (bound.as_micros() as u64).max(1);
+        self.histogram.count_between(0, micros)
+    }
+
+    /// Add `other`'s samples to these.
+    pub fn add(&mut self, other: &LatencyHistogram) {
+        if other.is_empty() {
+            return;
+        }
+        self.min = if self.is_empty() {
+            other.min
+        } else {
+            self.min.min(other.min)
+        };
+        self.histogram
+            .add(&other.histogram)
+            .expect("histograms share bounds");
+        self.max = self.max.max(other.max);
+        self.total += other.total;
+    }
+
+    /// The samples recorded since `earlier`, a copy of this histogram taken
+    /// before. Min and max of the difference are only as exact as the
+    /// histogram's buckets.
+    pub fn since(&self, earlier: &LatencyHistogram) -> LatencyHistogram {
+        let mut histogram = self.histogram.clone();
+        histogram
+            .subtract(&earlier.histogram)
+            .expect("an earlier copy is a subset");
+        let (min, max) = if histogram.is_empty() {
+            (Duration::ZERO, Duration::ZERO)
+        } else {
+            (
+                Duration::from_micros(histogram.min()),
+                Duration::from_micros(histogram.max()).min(self.max),
+            )
+        };
+        LatencyHistogram {
+            histogram,
+            min,
+            max,
+            total: self.total.saturating_sub(earlier.total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counters the histogram holds; fixed by its bounds.
+    fn buckets(histogram: &LatencyHistogram) -> usize {
+        histogram.histogram.distinct_values()
+    }
+
+    #[test]
+    fn test_percentiles_and_exact_extremes() {
+        let mut histogram = LatencyHistogram::new();
+        for millis in 1..=100 {
+            histogram.record(Duration::from_millis(millis));
+        }
+
+        assert_eq!(histogram.len(), 100);
+        assert_eq!(histogram.min(), Duration::from_millis(1));
+        assert_eq!(histogram.max(), Duration::from_millis(100));
+        assert_eq!(histogram.mean(), Duration::from_micros(50_500));
+        for (percent, expected) in [(50.0, 50), (95.0, 95), (99.0, 99), (100.0, 100)] {
+            let actual = histogram.percentile(percent).as_secs_f64() * 1000.0;
+            assert!(
+                (actual - expected as f64).abs() <= expected as f64 * 0.001,
+                "p{} = {}ms",
+                percent,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn test_add_and_since() {
+        let mut first = LatencyHistogram::new();
+        first.record(Duration::from_millis(20));
+        let snapshot = first.clone();
+        first.record(Duration::from_millis(200));
+        first.record(Duration::from_millis(300));
+
+        let window = first.since(&snapshot);
+        assert_eq!(window.len(), 2);
+        assert_eq!(window.mean(), Duration::from_millis(250));
+        assert!(window.percentile(1.0) >= Duration::from_millis(199));
+
+        let mut merged = LatencyHistogram::new();
+        merged.add(&window);
+        merged.add(&snapshot);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged.min(), Duration::from_millis(20));
+        assert_eq!(merged.max(), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_memory_is_constant_with_a_million_samples() {
+        let mut histogram = LatencyHistogram::new();
+        let before = buckets(&histogram);
+        for i in 0..1_000_000u64 {
+            // 1ms to ~10s, plus one beyond the trackable range
+            histogram.record(Duration::from_micros(1_000 + (i * 7919) % 10_000_000));
+        }
+        histogram.record(Duration::from_secs(2 * 3600));
+
+        assert_eq!(histogram.len(), 1_000_001);
+        assert_eq!(buckets(&histogram), before);
+        assert_eq!(histogram.max(), Duration::from_secs(2 * 3600));
+        assert!(histogram.percentile(50.0) > Duration::from_secs(4));
+        assert!(histogram.percentile(50.0) < Duration::from_secs(6));
+    }
+}
@@ -1,6 +1,11 @@
+use crate::performance::baseline::BaselineComparison;
+use crate::performance::histogram::LatencyHistogram;
+use crate::performance::stress::StressOutcome;
+use crate::performance::thresholds::ThresholdResult;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 use std::time::{Duration, Instant};
@@ -8,13 +13,142 @@ use std::time::{Duration, Instant};
 #[derive(Debug, Clone)]
 pub struct PerformanceMetrics {
     pub start_time: Instant,
-    pub response_times: Vec<Duration>,
+    pub response_times: LatencyHistogram,
     pub error_count: u64,
     pub request_count: u64,
     pub status_codes: HashMap<u16, u64>,
     pub bytes_sent: u64,
     pub bytes_received: u64,
     pub connection_errors: u64,
+    /// Requests per suite, then per test within it
+    pub breakdown: BTreeMap<String, BTreeMap<String, RequestTally>>,
+    /// Requests sent during warmup, which are not measured
+    pub warmup_requests: u64,
+    /// Set once a stress run has been judged
+    pub stress: Option<StressOutcome>,
+    /// Dataset rows the suites could draw on, and the ones requests used,
+    /// by suite index and row index
+    pub data_rows_available: u64,
+    pub data_rows_used: HashSet<(usize, usize)>,
+    /// Set when the run was cut short, e.g. by Ctrl-C
+    pub interrupted: bool,
+    /// Responses per HTTP version, e.g. `HTTP/1.1`
+    pub http_versions: BTreeMap<String, u64>,
+    /// Failed requests by what went wrong
+    pub error_breakdown: ErrorBreakdown<u64>,
+    /// One snapshot per report interval, taken by the monitor
+    pub timeline: Vec<IntervalSnapshot>,
+    /// Response times since the last snapshot
+    interval_response_times: LatencyHistogram,
+}
+
+/// Failed requests split by what went wrong, so a degraded service can be
+/// told apart from one returning slightly different data under load.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorBreakdown<T> {
+    /// No response at all: refused, reset or timed out
+    pub transport: T,
+    /// Failed with a 5xx status
+    pub http_5xx: T,
+    /// Failed with a 4xx status
+    pub http_4xx: T,
+    /// Any other status, failing an assertion on the response
+    pub assertion: T,
+}
+
+impl ErrorBreakdown<u64> {
+    /// Count a failed response by its status.
+    fn record(&mut self, status_code: u16) {
+        match status_code {
+            500.. => self.http_5xx += 1,
+            400..=499 => self.http_4xx += 1,
+            _ => self.assertion += 1,
+        }
+    }
+
+    fn add(&mut self, other: &ErrorBreakdown<u64>) {
+        self.transport += other.transport;
+        self.http_5xx += other.http_5xx;
+        self.http_4xx += other.http_4xx;
+        self.assertion += other.assertion;
+    }
+
+    /// Each count as a fraction of `requests`.
+    fn rates(&self, requests: u64) -> ErrorBreakdown<f64> {
+        let rate = |count: u64| {
+            if requests == 0 {
+                0.0
+            } else {
+                count as f64 / requests as f64
+            }
+        };
+        ErrorBreakdown {
+            transport: rate(self.transport),
+            http_5xx: rate(self.http_5xx),
+            http_4xx: rate(self.http_4xx),
+            assertion: rate(self.assertion),
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorBreakdown<u64> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "transport {}, 5xx {}, 4xx {}, assertion {}",
+            self.transport, self.http_5xx, self.http_4xx, self.assertion
+        )
+    }
+}
+
+/// Throughput and latency over one report interval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntervalSnapshot {
+    /// When the interval ended
+    pub timestamp: DateTime<Utc>,
+    /// Time since measuring started, at the end of the interval
+    #[serde(with = "duration_serde")]
+    pub elapsed: Duration,
+    pub requests: u64,
+    pub errors: u64,
+    pub requests_per_second: f64,
+    /// Fraction of the interval's requests that failed
+    pub error_rate: f64,
+    #[serde(with = "duration_serde")]
+    pub p50_response_time: Duration,
+    #[serde(with = "duration_serde")]
+    pub p95_response_time: Duration,
+    #[serde(with = "duration_serde")]
+    pub p99_response_time: Duration,
+    /// Rate the load controller was pacing to, if it was throttling
+    pub target_rps: Option<f64>,
+    /// Requests and failures so far, including this interval's
+    pub total_requests: u64,
+    pub total_errors: u64,
+}
+
+/// Requests made for one test of a suite.
+#[derive(Debug, Clone, Default)]
+pub struct RequestTally {
+    pub requests: u64,
+    pub errors: u64,
+    pub total_response_time: Duration,
+}
+
+impl RequestTally {
+    fn add(&mut self, other: &RequestTally) {
+        self.requests += other.requests;
+        self.errors += other.errors;
+        self.total_response_time += other.total_response_time;
+    }
+
+    fn average_response_time(&self) -> Duration {
+        if self.requests == 0 {
+            Duration::ZERO
+        } else {
+            self.total_response_time / self.requests as u32
+        }
+    }
 }
 
 impl Default for PerformanceMetrics {
@@ -27,13 +161,36 @@ impl PerformanceMetrics {
     pub fn new() -> Self {
         Self {
             start_time: Instant::now(),
-            response_times: Vec::new(),
+            response_times: LatencyHistogram::new(),
             error_count: 0,
             request_count: 0,
             status_codes: HashMap::new(),
             bytes_sent: 0,
             bytes_received: 0,
             connection_errors: 0,
+            breakdown: BTreeMap::new(),
+            warmup_requests: 0,
+            stress: None,
+            data_rows_available: 0,
+            data_rows_used: HashSet::new(),
+            interrupted: false,
+            http_versions: BTreeMap::new(),
+            error_breakdown: ErrorBreakdown::default(),
+            timeline: Vec::new(),
+            interval_response_times: LatencyHistogram::new(),
+        }
+    }
+
+    pub fn record_data_row(&mut self, suite: usize, row: usize) {
+        self.data_rows_used.insert((suite, row));
+    }
+
+    pub fn record_http_version(&mut self, version: &str) {
+        match self.http_versions.get_mut(version) {
+            Some(count) => *count += 1,
+            None => {
+                self.http_versions.insert(version.to_string(), 1);
+            }
         }
     }
 
@@ -45,7 +202,8 @@ impl PerformanceMetrics {
         bytes_received: u64,
         is_error: bool,
     ) {
-        self.response_times.push(response_time);
+        self.response_times.record(response_time);
+        self.interval_response_times.record(response_time);
         self.request_count += 1;
         self.bytes_sent += bytes_sent;
         self.bytes_received += bytes_received;
@@ -54,12 +212,105 @@ impl PerformanceMetrics {
 
         if is_error {
             self.error_count += 1;
+            self.error_breakdown.record(status_code);
         }
     }
 
     pub fn record_connection_error(&mut self) {
         self.connection_errors += 1;
         self.error_count += 1;
+        self.error_breakdown.transport += 1;
+    }
+
+    /// Attribute a request (recorded with [`Self::record_request`] or
+    /// [`Self::record_connection_error`]) to a suite and test.
+    pub fn record_test(
+        &mut self,
+        suite: &str,
+        test: &str,
+        response_time: Duration,
+        is_error: bool,
+    ) {
+        let tally = self
+            .breakdown
+            .entry(suite.to_string())
+            .or_default()
+            .entry(test.to_string())
+            .or_default();
+        tally.requests += 1;
+        tally.total_response_time += response_time;
+        if is_error {
+            tally.errors += 1;
+        }
+    }
+
+    /// Close the current interval, adding its snapshot to the timeline.
+    /// `target_rps` is the rate requests were being paced to, if any.
+    pub fn record_snapshot(&mut self, target_rps: Option<f64>) -> &IntervalSnapshot {
+        let elapsed = self.start_time.elapsed();
+        let total_requests = self.request_count + self.connection_errors;
+        let (since, requests_before, errors_before) = self
+            .timeline
+            .last()
+            .map(|last| (last.elapsed, last.total_requests, last.total_errors))
+            .unwrap_or_default();
+
+        let requests = total_requests - requests_before;
+        let errors = self.error_count - errors_before;
+        let interval = elapsed.saturating_sub(since).as_secs_f64();
+        let times = std::mem::take(&mut self.interval_response_times);
+
+        self.timeline.push(IntervalSnapshot {
+            timestamp: Utc::now(),
+            elapsed,
+            requests,
+            errors,
+            requests_per_second: if interval > 0.0 {
+                requests as f64 / interval
+            } else {
+                0.0
+            },
+            error_rate: if requests > 0 {
+                errors as f64 / requests as f64
+            } else {
+                0.0
+            },
+            p50_response_time: times.percentile(50.0),
+            p95_response_time: times.percentile(95.0),
+            p99_response_time: times.percentile(99.0),
+            target_rps,
+            total_requests,
+            total_errors: self.error_count,
+        });
+        self.timeline.last().expect("snapshot was just recorded")
+    }
+
+    /// Per-suite totals, each with its tests, in name order.
+    fn suite_breakdown(&self) -> Vec<SuiteBreakdown> {
+        self.breakdown
+            .iter()
+            .map(|(suite, tests)| {
+                let mut total = RequestTally::default();
+                for tally in tests.values() {
+                    total.add(tally);
+                }
+                SuiteBreakdown {
+                    name: suite.clone(),
+                    total_requests: total.requests,
+                    failed_requests: total.errors,
+                    average_response_time: total.average_response_time(),
+                    tests: tests
+                        .iter()
+                        .map(|(test, tally)| TestBreakdown {
+                            name: test.clone(),
+                            total_requests: tally.requests,
+                            failed_requests: tally.errors,
+                            average_response_time: tally.average_response_time(),
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
     }
 
     pub fn calculate_results(&self) -> PerformanceResults {
@@ -84,69 +335,108 @@ impl PerformanceMetrics {
                 bytes_per_second_received: 0.0,
                 connection_errors: self.connection_errors,
                 total_duration,
+                suites: self.suite_breakdown(),
+                thresholds: Vec::new(),
+                warmup_requests: self.warmup_requests,
+                stress: self.stress.clone(),
+                data_rows_available: self.data_rows_available,
+                data_rows_used: self.data_rows_used.len() as u64,
+                comparison: None,
+                interrupted: self.interrupted,
+                http_versions: self.http_versions.clone(),
+                error_breakdown: self.error_breakdown,
+                error_rates: self.error_breakdown.rates(total_requests),
+                timeline: self.timeline.clone(),
             };
         }
 
-        let mut sorted_times = self.response_times.clone();
-        sorted_times.sort();
-
-        let successful_requests = self.request_count - self.error_count + self.connection_errors;
+        let successful_requests = total_requests - self.error_count;
         let success_rate = if total_requests > 0 {
             (total_requests - self.error_count) as f64 / total_requests as f64
         } else {
             0.0
         };
 
-        let avg_response_time = Duration::from_nanos(
-            (sorted_times.iter().map(|d| d.as_nanos()).sum::<u128>() / sorted_times.len() as u128)
-                as u64,
-        );
-
-        let p50_index = sorted_times.len() * 50 / 100;
-        let p95_index = sorted_times.len() * 95 / 100;
-        let p99_index = sorted_times.len() * 99 / 100;
-
         PerformanceResults {
             total_requests,
             successful_requests,
             failed_requests: self.error_count,
             success_rate,
             requests_per_second: total_requests as f64 / total_duration.as_secs_f64(),
-            average_response_time: avg_response_time,
-            min_response_time: sorted_times.first().copied().unwrap_or(Duration::ZERO),
-            max_response_time: sorted_times.last().copied().unwrap_or(Duration::ZERO),
-            p50_response_time: sorted_times
-                .get(p50_index)
-                .copied()
-                .unwrap_or(Duration::ZERO),
-            p95_response_time: sorted_times
-                .get(p95_index)
-                .copied()
-                .unwrap_or(Duration::ZERO),
-            p99_response_time: sorted_times
-                .get(p99_index)
-                .copied()
-                .unwrap_or(Duration::ZERO),
+            average_response_time: self.response_times.mean(),
+            min_response_time: self.response_times.min(),
+            max_response_time: self.response_times.max(),
+            p50_response_time: self.response_times.percentile(50.0),
+            p95_response_time: self.response_times.percentile(95.0),
+            p99_response_time: self.response_times.percentile(99.0),
             status_code_distribution: self.status_codes.clone(),
             bytes_per_second_sent: self.bytes_sent as f64 / total_duration.as_secs_f64(),
             bytes_per_second_received: self.bytes_received as f64 / total_duration.as_secs_f64(),
             connection_errors: self.connection_errors,
             total_duration,
+            suites: self.suite_breakdown(),
+            thresholds: Vec::new(),
+            warmup_requests: self.warmup_requests,
+            stress: self.stress.clone(),
+            data_rows_available: self.data_rows_available,
+            data_rows_used: self.data_rows_used.len() as u64,
+            comparison: None,
+            interrupted: self.interrupted,
+            http_versions: self.http_versions.clone(),
+            error_breakdown: self.error_breakdown,
+            error_rates: self.error_breakdown.rates(total_requests),
+            timeline: self.timeline.clone(),
         }
     }
 
-    #[allow(dead_code)]
+    /// Move everything recorded here into `aggregate`, leaving these
+    /// metrics empty for more requests.
+    pub fn drain_into(&mut self, aggregate: &mut PerformanceMetrics) {
+        aggregate.merge(self);
+        self.response_times.clear();
+        self.interval_response_times.clear();
+        self.error_count = 0;
+        self.request_count = 0;
+        self.status_codes.clear();
+        self.bytes_sent = 0;
+        self.bytes_received = 0;
+        self.connection_errors = 0;
+        self.breakdown.clear();
+        self.data_rows_used.clear();
+        self.http_versions.clear();
+        self.error_breakdown = ErrorBreakdown::default();
+    }
+
     pub fn merge(&mut self, other: &PerformanceMetrics) {
-        self.response_times.extend(other.response_times.iter());
+        self.response_times.add(&other.response_times);
+        self.interval_response_times
+            .add(&other.interval_response_times);
         self.error_count += other.error_count;
         self.request_count += other.request_count;
         self.bytes_sent += other.bytes_sent;
         self.bytes_received += other.bytes_received;
         self.connection_errors += other.connection_errors;
+        self.warmup_requests += other.warmup_requests;
+        if self.stress.is_none() {
+            self.stress = other.stress.clone();
+        }
+        self.interrupted |= other.interrupted;
+        self.error_breakdown.add(&other.error_breakdown);
+        for (version, count) in &other.http_versions {
+            *self.http_versions.entry(version.clone()).or_insert(0) += count;
+        }
+        self.data_rows_available = self.data_rows_available.max(other.data_rows_available);
+        self.data_rows_used.extend(&other.data_rows_used);
 
         for (status, count) in &other.status_codes {
             *self.status_codes.entry(*status).or_insert(0) += count;
         }
+        for (suite, tests) in &other.breakdown {
+            let suite = self.breakdown.entry(suite.clone()).or_default();
+            for (test, tally) in tests {
+                suite.entry(test.clone()).or_default().add(tally);
+            }
+        }
     }
 }
 
@@ -178,6 +468,69 @@ pub struct PerformanceResults {
 
     #[serde(with = "duration_serde")]
     pub total_duration: Duration,
+
+    /// Requests per suite and test
+    #[serde(default)]
+    pub suites: Vec<SuiteBreakdown>,
+
+    /// Pass/fail per threshold, filled in once the run is evaluated
+    #[serde(default)]
+    pub thresholds: Vec<ThresholdResult>,
+
+    /// Requests sent during warmup; not counted in any other figure
+    #[serde(default)]
+    pub warmup_requests: u64,
+
+    /// Breaking point found by the stress pattern
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stress: Option<StressOutcome>,
+
+    /// Dataset rows the run could draw on, and how many distinct ones it used
+    #[serde(default)]
+    pub data_rows_available: u64,
+    #[serde(default)]
+    pub data_rows_used: u64,
+
+    /// How the run compares with `--baseline`, filled in once evaluated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<BaselineComparison>,
+
+    /// The run was stopped early; every figure covers only the time it ran
+    #[serde(default)]
+    pub interrupted: bool,
+
+    /// Responses per negotiated HTTP version
+    #[serde(default)]
+    pub http_versions: BTreeMap<String, u64>,
+
+    /// Failed requests by what went wrong, and each as a fraction of all requests
+    #[serde(default)]
+    pub error_breakdown: ErrorBreakdown<u64>,
+    #[serde(default)]
+    pub error_rates: ErrorBreakdown<f64>,
+
+    /// Throughput and latency per report interval
+    #[serde(default)]
+    pub timeline: Vec<IntervalSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuiteBreakdown {
+    pub name: String,
+    pub total_requests: u64,
+    pub failed_requests: u64,
+    #[serde(with = "duration_serde")]
+    pub average_response_time: Duration,
+    pub tests: Vec<TestBreakdown>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestBreakdown {
+    pub name: String,
+    pub total_requests: u64,
+    pub failed_requests: u64,
+    #[serde(with = "duration_serde")]
+    pub average_response_time: Duration,
 }
 
 impl PerformanceResults {
@@ -1,8 +1,18 @@
+pub mod baseline;
+pub mod connection;
+pub mod dashboard;
+pub mod histogram;
 pub mod metrics;
 pub mod monitor;
 pub mod patterns;
+pub mod prometheus;
+pub mod report;
 pub mod runner;
+pub mod stress;
+pub mod thresholds;
+pub mod timeseries;
 
 pub use metrics::{PerformanceMetrics, PerformanceResults};
 pub use patterns::LoadPattern;
 pub use runner::PerformanceTestRunner;
+pub use thresholds::Thresholds;
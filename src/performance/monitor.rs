@@ -1,11 +1,15 @@
+use crate::performance::timeseries::TimeseriesWriter;
 use crate::performance::{LoadPattern, PerformanceMetrics};
-use owo_colors::OwoColorize;
+use crate::style::Colorize;
 use std::time::{Duration, Instant};
 
 pub struct PerformanceMonitor {
     start_time: Instant,
     last_report: Instant,
     report_interval: Duration,
+    /// Print a progress report each interval; off while a dashboard shows
+    /// the run instead
+    progress_reports: bool,
     #[allow(dead_code)] // Used for future monitoring enhancements
     load_pattern: LoadPattern,
 }
@@ -17,10 +21,16 @@ impl PerformanceMonitor {
             start_time: now,
             last_report: now,
             report_interval,
+            progress_reports: true,
             load_pattern,
         }
     }
 
+    pub fn with_progress_reports(mut self, progress_reports: bool) -> Self {
+        self.progress_reports = progress_reports;
+        self
+    }
+
     /// Check if it's time to generate a progress report
     pub fn should_report(&self) -> bool {
         self.last_report.elapsed() >= self.report_interval
@@ -111,9 +121,10 @@ impl PerformanceMonitor {
 
             if results.failed_requests > 0 {
                 println!(
-                    "  {} Errors: {}",
+                    "  {} Errors: {} ({})",
                     "⚠".yellow(),
-                    results.failed_requests.to_string().bright_white()
+                    results.failed_requests.to_string().bright_white(),
+                    results.error_breakdown
                 );
             }
         }
@@ -140,15 +151,73 @@ impl PerformanceMonitor {
             "  Total Duration: {:?}",
             results.total_duration.bright_white()
         );
+        if results.warmup_requests > 0 {
+            println!(
+                "  Warmup Requests: {} (not measured)",
+                results.warmup_requests.to_string().bright_white()
+            );
+        }
         println!(
             "  Total Requests: {}",
             results.total_requests.to_string().bright_white()
         );
+        match results.http_versions.len() {
+            0 => {}
+            1 => {
+                let version = results.http_versions.keys().next().unwrap();
+                println!("  Protocol: {}", version.bright_white());
+            }
+            _ => {
+                let responses: u64 = results.http_versions.values().sum();
+                let mix: Vec<String> = results
+                    .http_versions
+                    .iter()
+                    .map(|(version, count)| {
+                        format!(
+                            "{} {:.1}%",
+                            version,
+                            *count as f64 / responses as f64 * 100.0
+                        )
+                    })
+                    .collect();
+                println!("  Protocols: {}", mix.join(", ").bright_white());
+            }
+        }
+        if results.data_rows_available > 0 {
+            println!(
+                "  Data Rows Used: {} of {}",
+                results.data_rows_used.to_string().bright_white(),
+                results.data_rows_available
+            );
+        }
         println!(
             "  Successful: {}",
             results.successful_requests.to_string().green()
         );
         println!("  Failed: {}", results.failed_requests.to_string().red());
+        if results.failed_requests > 0 {
+            let rates = &results.error_rates;
+            println!(
+                "    Transport: {} ({:.2}%)",
+                results.error_breakdown.transport,
+                rates.transport * 100.0
+            );
+            println!(
+                "    HTTP 5xx: {} ({:.2}%)",
+                results.error_breakdown.http_5xx,
+                rates.http_5xx * 100.0
+            );
+            println!(
+                "    HTTP 4xx: {} ({:.2}%)",
+                results.error_breakdown.http_4xx,
+                rates.http_4xx * 100.0
+            );
+            println!(
+                "    Assertions: {} ({:.2}%)",
+                results.error_breakdown.assertion,
+                rates.assertion * 100.0
+            );
+        }
         println!(
             "  Success Rate: {:.2}%",
             (results.success_rate * 100.0).to_string().bright_white()
@@ -185,6 +254,25 @@ impl PerformanceMonitor {
                 .bright_white()
         );
 
+        if let Some(stress) = &results.stress {
+            println!();
+            println!("{} Stress Test:", "💥".bright_white());
+            println!(
+                "  Max Sustained RPS: {}",
+                format!("{:.0}", stress.max_rps).bright_white()
+            );
+            match (stress.breaking_rps, &stress.reason) {
+                (Some(rps), Some(reason)) => {
+                    println!(
+                        "  Breaking Point: {} ({})",
+                        format!("{:.0} RPS", rps).red(),
+                        reason
+                    )
+                }
+                _ => println!("  Breaking Point: {}", "not reached".green()),
+            }
+        }
+
         println!();
         println!("{} Response Time Percentiles:", "📊".bright_white());
         println!(
@@ -230,6 +318,29 @@ impl PerformanceMonitor {
             }
         }
 
+        if !results.suites.is_empty() {
+            println!();
+            println!("{} Per-Suite Breakdown:", "📦".bright_white());
+            for suite in &results.suites {
+                print_breakdown_line(
+                    "  ",
+                    &suite.name,
+                    suite.total_requests,
+                    suite.failed_requests,
+                    suite.average_response_time,
+                );
+                for test in &suite.tests {
+                    print_breakdown_line(
+                        "    - ",
+                        &test.name,
+                        test.total_requests,
+                        test.failed_requests,
+                        test.average_response_time,
+                    );
+                }
+            }
+        }
+
         if results.bytes_per_second_received > 0.0 {
             println!();
             println!("{} Network Traffic:", "🌐".bright_white());
@@ -301,12 +412,14 @@ impl PerformanceMonitor {
         }
     }
 
-    /// Start a background monitoring task that prints progress reports
+    /// Start a background monitoring task that prints progress reports,
+    /// snapshots each interval and appends it to `timeseries` if given
     pub async fn start_background_monitoring(
         mut self,
         metrics: std::sync::Arc<tokio::sync::Mutex<PerformanceMetrics>>,
         target_duration: Duration,
         load_controller: std::sync::Arc<crate::performance::patterns::LoadController>,
+        mut timeseries: Option<TimeseriesWriter>,
     ) {
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(self.report_interval);
@@ -314,9 +427,21 @@ impl PerformanceMonitor {
 
             loop {
                 interval.tick().await;
+                if load_controller.is_stopped() {
+                    break;
+                }
 
-                let metrics_guard = metrics.lock().await;
-                self.print_progress_report(&metrics_guard, target_duration, &load_controller);
+                let mut metrics_guard = metrics.lock().await;
+                let snapshot = metrics_guard.record_snapshot(load_controller.active_target_rps());
+                if let Some(writer) = &mut timeseries {
+                    if let Err(e) = writer.write(snapshot) {
+                        println!("⚠️  Failed to write time series, stopping it: {}", e);
+                        timeseries = None;
+                    }
+                }
+                if self.progress_reports {
+                    self.print_progress_report(&metrics_guard, target_duration, &load_controller);
+                }
 
                 // Stop monitoring if test duration exceeded
                 if self.start_time.elapsed() >= target_duration {
@@ -326,3 +451,25 @@ impl PerformanceMonitor {
         });
     }
 }
+
+fn print_breakdown_line(
+    prefix: &str,
+    name: &str,
+    requests: u64,
+    failed: u64,
+    average_response_time: Duration,
+) {
+    let failed = if failed > 0 {
+        failed.to_string().red().to_string()
+    } else {
+        failed.to_string()
+    };
+    println!(
+        "{}{}: {} requests, {} failed, avg {}ms",
+        prefix,
+        name.bright_white(),
+        requests,
+        failed,
+        average_response_time.as_millis()
+    );
+}
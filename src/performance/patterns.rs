@@ -1,3 +1,6 @@
+use crate::performance::stress::StressLimits;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
@@ -8,6 +11,33 @@ pub enum LoadPattern {
     RampUp,
     /// Spike pattern with bursts of high load
     Spike,
+    /// Start at `increment` RPS and add `increment` every `every`, up to the
+    /// target RPS when one is set
+    Step { increment: u32, every: Duration },
+    /// Climb from 0 to the target over `up`, hold it for `hold`, then come
+    /// back down to 0 over `down`
+    Ramp {
+        up: Duration,
+        hold: Duration,
+        down: Duration,
+    },
+    /// Step the rate up like [`LoadPattern::Step`] until a step breaks
+    /// `limits`
+    Stress {
+        increment: u32,
+        every: Duration,
+        limits: StressLimits,
+    },
+}
+
+impl LoadPattern {
+    /// Whether the pattern paces requests even without a target RPS.
+    fn sets_rate(&self) -> bool {
+        matches!(
+            self,
+            LoadPattern::Step { .. } | LoadPattern::Ramp { .. } | LoadPattern::Stress { .. }
+        )
+    }
 }
 
 pub struct LoadController {
@@ -16,8 +46,47 @@ pub struct LoadController {
     concurrent_users: u32,
     test_start: Instant,
     warmup_duration: Duration,
+    /// Shared by every worker, so `target_rps` limits the run as a whole
+    bucket: Mutex<TokenBucket>,
+    stopped: AtomicBool,
+}
+
+/// Request tokens accrued since the last refill.
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Refill at `rate` per second up to `now` and take a token, or say how
+    /// long to wait before trying again.
+    fn take(&mut self, now: Instant, rate: f64) -> Option<Duration> {
+        let capacity = (rate * BURST_WINDOW.as_secs_f64()).max(1.0);
+        let refill = rate * now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + refill).min(capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return None;
+        }
+        if rate > 0.0 {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / rate).min(RATE_RECHECK))
+        } else {
+            // Ramps start and end at zero
+            Some(RATE_RECHECK)
+        }
+    }
 }
 
+/// How much unused rate can be saved up, so timer lag doesn't lose requests
+/// without allowing large bursts.
+const BURST_WINDOW: Duration = Duration::from_millis(50);
+
+/// Longest a waiting worker sleeps before looking at the rate again, so
+/// pattern changes and [`LoadController::stop`] take effect promptly.
+const RATE_RECHECK: Duration = Duration::from_millis(100);
+
 impl LoadController {
     pub fn new(
         pattern: LoadPattern,
@@ -31,6 +100,29 @@ impl LoadController {
             concurrent_users,
             test_start: Instant::now(),
             warmup_duration,
+            bucket: Mutex::new(TokenBucket {
+                tokens: 0.0,
+                last_refill: Instant::now(),
+            }),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// Stop the run: waiting workers are released and should exit.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Rate of step `index` (from 0) of a step or stress pattern.
+    fn step_rps(&self, increment: u32, index: u64) -> f64 {
+        let rps = increment as f64 * (index + 1) as f64;
+        match self.target_rps {
+            Some(target) => rps.min(target as f64),
+            None => rps,
         }
     }
 
@@ -63,6 +155,22 @@ impl LoadController {
                     base_rps
                 }
             }
+            LoadPattern::Step { increment, every }
+            | LoadPattern::Stress {
+                increment, every, ..
+            } => self.step_rps(increment, step_index(elapsed, every)),
+            LoadPattern::Ramp { up, hold, down } => {
+                if elapsed < up {
+                    base_rps * elapsed.as_secs_f64() / up.as_secs_f64()
+                } else if elapsed < up + hold {
+                    base_rps
+                } else if elapsed < up + hold + down {
+                    let progress = (elapsed - up - hold).as_secs_f64() / down.as_secs_f64();
+                    base_rps * (1.0 - progress)
+                } else {
+                    0.0
+                }
+            }
         }
     }
 
@@ -92,16 +200,41 @@ impl LoadController {
                     self.concurrent_users
                 }
             }
+            LoadPattern::Step { .. } | LoadPattern::Ramp { .. } | LoadPattern::Stress { .. } => {
+                self.concurrent_users
+            }
+        }
+    }
+
+    /// The rate requests are being paced to right now, or `None` when they
+    /// are not throttled: without a target RPS only the step, ramp and
+    /// stress patterns pace requests.
+    pub fn active_target_rps(&self) -> Option<f64> {
+        if self.target_rps.is_none() && !self.pattern.sets_rate() {
+            return None;
         }
+        Some(self.current_target_rps())
     }
 
-    /// Calculate delay between requests to achieve target RPS
-    pub fn request_delay(&self) -> Option<Duration> {
-        self.target_rps.map(|_rps| {
-            let current_rps = self.current_target_rps();
-            let delay_millis = 1000.0 / current_rps;
-            Duration::from_millis(delay_millis as u64)
-        })
+    /// Wait for a request token. Tokens refill at [`Self::current_target_rps`],
+    /// so the load pattern shapes the rate (see [`Self::active_target_rps`]).
+    /// Also returns, without a token, once the run is stopped.
+    pub async fn acquire(&self) {
+        if self.active_target_rps().is_none() {
+            return;
+        }
+
+        while !self.is_stopped() {
+            let wait = self
+                .bucket
+                .lock()
+                .expect("token bucket lock poisoned")
+                .take(Instant::now(), self.current_target_rps());
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
     }
 
     /// Get a human-readable description of the current load phase
@@ -129,6 +262,74 @@ impl LoadController {
                     format!("Normal phase ({:.1}s to spike)", 30.0 - cycle_elapsed)
                 }
             }
+            LoadPattern::Step { every, .. } => format!(
+                "Step {} ({:.0} RPS)",
+                step_index(elapsed, every) + 1,
+                self.current_target_rps()
+            ),
+            LoadPattern::Stress { every, .. } => format!(
+                "Stress step {} ({:.0} RPS)",
+                step_index(elapsed, every) + 1,
+                self.current_target_rps()
+            ),
+            LoadPattern::Ramp { up, hold, down } => {
+                if elapsed < up {
+                    let progress = (elapsed.as_secs_f64() / up.as_secs_f64() * 100.0) as u32;
+                    format!("Ramping up ({}%)", progress)
+                } else if elapsed < up + hold {
+                    "Holding peak load".to_string()
+                } else if elapsed < up + hold + down {
+                    let progress =
+                        ((elapsed - up - hold).as_secs_f64() / down.as_secs_f64() * 100.0) as u32;
+                    format!("Ramping down ({}%)", progress)
+                } else {
+                    "Ramp complete".to_string()
+                }
+            }
+        }
+    }
+}
+
+/// Which step (from 0) of `every`-long steps `elapsed` falls in.
+fn step_index(elapsed: Duration, every: Duration) -> u64 {
+    (elapsed.as_secs_f64() / every.as_secs_f64()) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_refills_at_the_target_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket {
+            tokens: 0.0,
+            last_refill: start,
+        };
+
+        // At 100 RPS a token comes every 10ms: 20 in 200ms, give or take
+        // rounding
+        let wait = bucket.take(start, 100.0).unwrap();
+        assert!(wait.abs_diff(Duration::from_millis(10)) < Duration::from_micros(1));
+        let mut now = start;
+        let mut taken = 0;
+        while now < start + Duration::from_millis(205) {
+            now += Duration::from_millis(1);
+            if bucket.take(now, 100.0).is_none() {
+                taken += 1;
+            }
         }
+        assert_eq!(taken, 20);
+
+        // Idle time saves up no more than BURST_WINDOW's worth of requests
+        now += Duration::from_secs(10);
+        let burst = std::iter::from_fn(|| bucket.take(now, 100.0).is_none().then_some(()));
+        assert_eq!(burst.count(), 5);
+
+        // Nothing is issued while the rate is zero
+        assert_eq!(
+            bucket.take(now + Duration::from_secs(1), 0.0),
+            Some(RATE_RECHECK)
+        );
     }
 }
@@ -0,0 +1,292 @@
+use crate::performance::patterns::LoadController;
+use crate::performance::{PerformanceMetrics, PerformanceResults};
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Upper bounds of the response time histogram buckets, in seconds
+/// (Prometheus' defaults).
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Serve the run's live metrics at `/metrics` on `addr` in the Prometheus
+/// text format, until the returned task is aborted.
+pub async fn serve_metrics(
+    addr: SocketAddr,
+    run_id: String,
+    metrics: Arc<Mutex<PerformanceMetrics>>,
+    load_controller: Arc<LoadController>,
+) -> Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to listen for Prometheus on {}", addr))?;
+
+    Ok(tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let metrics = Arc::clone(&metrics);
+            let load_controller = Arc::clone(&load_controller);
+            let run_id = run_id.clone();
+            tokio::spawn(async move {
+                // Only the request line matters; scrapers send small requests
+                let mut buf = [0u8; 2048];
+                let Ok(n) = socket.read(&mut buf).await else {
+                    return;
+                };
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("");
+
+                let response = if path == "/metrics" || path.starts_with("/metrics?") {
+                    let body = render_live(
+                        &*metrics.lock().await,
+                        load_controller.active_target_rps(),
+                        &run_id,
+                    );
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        CONTENT_TYPE,
+                        body.len(),
+                        body
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                        .to_string()
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            });
+        }
+    }))
+}
+
+/// Push the final results to a Pushgateway, grouped under job `rivet_perf`
+/// and the run ID.
+pub async fn push_results(gateway: &str, run_id: &str, results: &PerformanceResults) -> Result<()> {
+    let mut url = reqwest::Url::parse(gateway)
+        .with_context(|| format!("Invalid Pushgateway URL: {}", gateway))?;
+    // Segments are percent-encoded, so any run ID makes a valid grouping key
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("Invalid Pushgateway URL: {}", gateway))?
+        .pop_if_empty()
+        .extend(["metrics", "job", "rivet_perf", "run_id", run_id]);
+    let response = reqwest::Client::new()
+        .put(url.clone())
+        .header("content-type", CONTENT_TYPE)
+        .body(render_results(results))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .with_context(|| format!("Failed to push metrics to {}", url))?;
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "Pushgateway {} answered {}: {}",
+            url,
+            response.status(),
+            response.text().await.unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// Counters, the response time histogram and the target rate, as scraped
+/// during a run.
+fn render_live(metrics: &PerformanceMetrics, target_rps: Option<f64>, run_id: &str) -> String {
+    let labels = format!("run_id=\"{}\"", escape(run_id));
+    let mut out = String::new();
+
+    // Status classes, from the distribution kept per status code
+    let mut classes = [0u64; 6];
+    for (status, count) in &metrics.status_codes {
+        if let Some(class) = classes.get_mut((*status / 100) as usize) {
+            *class += count;
+        }
+    }
+    out.push_str("# HELP rivet_perf_requests_total Requests sent, by response status class.\n");
+    out.push_str("# TYPE rivet_perf_requests_total counter\n");
+    for (class, count) in classes.iter().enumerate().skip(1) {
+        let _ = writeln!(
+            out,
+            "rivet_perf_requests_total{{{},class=\"{}xx\"}} {}",
+            labels, class, count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "rivet_perf_requests_total{{{},class=\"transport_error\"}} {}",
+        labels, metrics.connection_errors
+    );
+
+    let errors = &metrics.error_breakdown;
+    out.push_str("# HELP rivet_perf_failures_total Failed requests, by what went wrong.\n");
+    out.push_str("# TYPE rivet_perf_failures_total counter\n");
+    for (kind, count) in [
+        ("transport", errors.transport),
+        ("http_5xx", errors.http_5xx),
+        ("http_4xx", errors.http_4xx),
+        ("assertion", errors.assertion),
+    ] {
+        let _ = writeln!(
+            out,
+            "rivet_perf_failures_total{{{},kind=\"{}\"}} {}",
+            labels, kind, count
+        );
+    }
+
+    let response_times = &metrics.response_times;
+    out.push_str("# HELP rivet_perf_response_time_seconds Response times.\n");
+    out.push_str("# TYPE rivet_perf_response_time_seconds histogram\n");
+    for bound in BUCKETS {
+        let _ = writeln!(
+            out,
+            "rivet_perf_response_time_seconds_bucket{{{},le=\"{}\"}} {}",
+            labels,
+            bound,
+            response_times.count_up_to(Duration::from_secs_f64(bound))
+        );
+    }
+    let _ = writeln!(
+        out,
+        "rivet_perf_response_time_seconds_bucket{{{},le=\"+Inf\"}} {}",
+        labels,
+        response_times.len()
+    );
+    let _ = writeln!(
+        out,
+        "rivet_perf_response_time_seconds_sum{{{}}} {}",
+        labels,
+        response_times.sum().as_secs_f64()
+    );
+    let _ = writeln!(
+        out,
+        "rivet_perf_response_time_seconds_count{{{}}} {}",
+        labels,
+        response_times.len()
+    );
+
+    if let Some(target_rps) = target_rps {
+        out.push_str("# HELP rivet_perf_target_rps Rate the load pattern is pacing requests to.\n");
+        out.push_str("# TYPE rivet_perf_target_rps gauge\n");
+        let _ = writeln!(out, "rivet_perf_target_rps{{{}}} {}", labels, target_rps);
+    }
+    out
+}
+
+/// The final figures of a run, as gauges.
+fn render_results(results: &PerformanceResults) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: &[(&str, f64)]| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        for (labels, value) in samples {
+            if labels.is_empty() {
+                let _ = writeln!(out, "{} {}", name, value);
+            } else {
+                let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+            }
+        }
+    };
+
+    gauge(
+        "rivet_perf_requests",
+        "Requests sent during the run.",
+        &[("", results.total_requests as f64)],
+    );
+    let errors = &results.error_breakdown;
+    gauge(
+        "rivet_perf_failures",
+        "Failed requests, by what went wrong.",
+        &[
+            ("kind=\"transport\"", errors.transport as f64),
+            ("kind=\"http_5xx\"", errors.http_5xx as f64),
+            ("kind=\"http_4xx\"", errors.http_4xx as f64),
+            ("kind=\"assertion\"", errors.assertion as f64),
+        ],
+    );
+    gauge(
+        "rivet_perf_requests_per_second",
+        "Average throughput over the run.",
+        &[("", results.requests_per_second)],
+    );
+    gauge(
+        "rivet_perf_success_ratio",
+        "Fraction of requests that succeeded.",
+        &[("", results.success_rate)],
+    );
+    gauge(
+        "rivet_perf_response_time_seconds",
+        "Response time percentiles over the run.",
+        &[
+            ("quantile=\"0.5\"", results.p50_response_time.as_secs_f64()),
+            ("quantile=\"0.95\"", results.p95_response_time.as_secs_f64()),
+            ("quantile=\"0.99\"", results.p99_response_time.as_secs_f64()),
+        ],
+    );
+    gauge(
+        "rivet_perf_duration_seconds",
+        "How long the run measured for.",
+        &[("", results.total_duration.as_secs_f64())],
+    );
+    out
+}
+
+/// Escape a label value for the text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_live_metrics() {
+        let mut metrics = PerformanceMetrics::new();
+        metrics.record_request(Duration::from_millis(3), 200, 10, 10, false);
+        metrics.record_request(Duration::from_millis(40), 200, 10, 10, false);
+        metrics.record_request(Duration::from_millis(700), 503, 10, 10, true);
+        metrics.record_connection_error();
+
+        let text = render_live(&metrics, Some(25.0), "nightly \"7\"");
+        let labels = "run_id=\"nightly \\\"7\\\"\"";
+        for line in [
+            format!("rivet_perf_requests_total{{{},class=\"2xx\"}} 2", labels),
+            format!("rivet_perf_requests_total{{{},class=\"5xx\"}} 1", labels),
+            format!(
+                "rivet_perf_requests_total{{{},class=\"transport_error\"}} 1",
+                labels
+            ),
+            format!(
+                "rivet_perf_failures_total{{{},kind=\"http_5xx\"}} 1",
+                labels
+            ),
+            format!(
+                "rivet_perf_response_time_seconds_bucket{{{},le=\"0.005\"}} 1",
+                labels
+            ),
+            format!(
+                "rivet_perf_response_time_seconds_bucket{{{},le=\"0.5\"}} 2",
+                labels
+            ),
+            format!(
+                "rivet_perf_response_time_seconds_bucket{{{},le=\"+Inf\"}} 3",
+                labels
+            ),
+            format!("rivet_perf_response_time_seconds_count{{{}}} 3", labels),
+            format!("rivet_perf_target_rps{{{}}} 25", labels),
+        ] {
+            assert!(text.contains(&line), "missing {}\n{}", line, text);
+        }
+        assert!(!render_live(&metrics, None, "x").contains("rivet_perf_target_rps"));
+    }
+}
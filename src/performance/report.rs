@@ -0,0 +1,97 @@
+use crate::performance::PerformanceResults;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+impl PerformanceResults {
+    /// Write the results as a self-contained HTML page: summary cards, a
+    /// chart of RPS and P95 over the run, status codes and per-test figures.
+    pub fn save_html_report(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.render_html()?)?;
+        Ok(())
+    }
+
+    fn render_html(&self) -> Result<String> {
+        use tera::{Context, Tera};
+
+        let mut tera = Tera::default();
+        tera.add_raw_template(
+            "perf_report.html",
+            include_str!("../../templates/perf_report.html"),
+        )
+        .map_err(|e| anyhow!("Failed to add template: {}", e))?;
+
+        let mut context = Context::new();
+        context.insert("results", self);
+        context.insert(
+            "timestamp",
+            &Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        );
+        context.insert(
+            "requests_per_second",
+            &format!("{:.1}", self.requests_per_second),
+        );
+        context.insert("success_rate", &format!("{:.1}", self.success_rate * 100.0));
+        context.insert(
+            "total_duration_secs",
+            &format!("{:.1}", self.total_duration.as_secs_f64()),
+        );
+
+        let mut status_codes: Vec<_> = self.status_code_distribution.iter().collect();
+        status_codes.sort();
+        let status_codes: Vec<_> = status_codes
+            .into_iter()
+            .map(|(code, count)| {
+                let class = match code {
+                    200..=299 => "text-success",
+                    400.. | 0 => "text-error",
+                    _ => "text-warning",
+                };
+                json!({ "code": code, "count": count, "class": class })
+            })
+            .collect();
+        context.insert("status_codes", &status_codes);
+
+        // Numbers only, so the JSON is safe to embed in the page's script
+        let chart: Vec<_> = self
+            .timeline
+            .iter()
+            .map(|snapshot| {
+                json!({
+                    "t": snapshot.elapsed.as_secs_f64(),
+                    "rps": snapshot.requests_per_second,
+                    "p95": snapshot.p95_response_time.as_millis() as u64,
+                })
+            })
+            .collect();
+        context.insert("chart_data", &serde_json::to_string(&chart)?);
+
+        Ok(tera.render("perf_report.html", &context)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::performance::PerformanceMetrics;
+    use std::time::Duration;
+
+    #[test]
+    fn test_render_html_report() {
+        let mut metrics = PerformanceMetrics::new();
+        metrics.record_test("Users", "List <users>", Duration::from_millis(40), false);
+        metrics.record_request(Duration::from_millis(40), 200, 10, 10, false);
+        metrics.record_request(Duration::from_millis(90), 503, 10, 10, true);
+        metrics.record_snapshot(None);
+        let html = metrics.calculate_results().render_html().unwrap();
+
+        assert!(html.contains("Performance Report"));
+        assert!(html.contains("List &lt;users&gt;"));
+        assert!(html.contains(">503<"));
+        assert!(html.contains("\"p95\":90"));
+        // Self-contained: no external scripts or stylesheets
+        assert!(!html.contains("<script src"));
+        assert!(!html.contains("<link"));
+    }
+}
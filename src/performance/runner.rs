@@ -1,17 +1,42 @@
 use crate::config::RivetConfig;
+use crate::performance::connection::ConnectionOptions;
+use crate::performance::dashboard::Dashboard;
 use crate::performance::monitor::PerformanceMonitor;
 use crate::performance::patterns::LoadController;
+use crate::performance::prometheus;
+use crate::performance::stress::StressWatch;
+use crate::performance::timeseries::TimeseriesWriter;
 use crate::performance::{LoadPattern, PerformanceMetrics, PerformanceResults};
+use crate::runner::base_url::{retarget_suite, BASE_URL_VAR};
+use crate::runner::data::{load_data, DataRow, DataSelection};
 use crate::runner::executor::RequestExecutor;
-use crate::runner::parser::load_test_suite;
+use crate::runner::parser::{describe_suite_path, load_test_suite, SuiteFile};
+use crate::runner::test_runner::{suite_env, RIVET_ENV_VAR};
 use crate::runner::variables::VariableContext;
+use crate::utils::ClientOptions;
 use anyhow::{Context, Result};
 use futures::stream::{FuturesUnordered, StreamExt};
-use std::path::Path;
+use reqwest::redirect::Policy;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+/// Request timeout; longer than usual so requests aren't cut off under load.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long requests still in flight get to finish once a run ends.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Workers record into their own metrics and move them into the shared ones
+/// this often (or after [`FLUSH_EVERY`] requests), so they rarely contend
+/// for its lock.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+const FLUSH_EVERY: u64 = 100;
 
 pub struct PerformanceTestRunner {
     concurrent_users: u32,
@@ -21,6 +46,29 @@ pub struct PerformanceTestRunner {
     report_interval: Duration,
     load_pattern: LoadPattern,
     executor: RequestExecutor,
+    client_options: ClientOptions,
+    base_url: Option<String>,
+    /// `--header`s, set on every HTTP step over its own headers
+    headers: HashMap<String, String>,
+    suite: Option<String>,
+    timeseries: Option<PathBuf>,
+    data: Option<PathBuf>,
+    prometheus_listen: Option<SocketAddr>,
+    run_id: String,
+    tui: bool,
+    cancel: CancellationToken,
+}
+
+/// A suite taking part in the run, under the name its metrics are reported by.
+struct PerfSuite {
+    name: String,
+    config: RivetConfig,
+    /// Variables every request of the suite starts from
+    context: VariableContext,
+    /// Dataset rows spread over the suite's requests; empty without a dataset
+    rows: Vec<DataRow>,
+    /// The run's executor, resolving the suite's relative paths against its directory
+    executor: RequestExecutor,
 }
 
 impl PerformanceTestRunner {
@@ -31,10 +79,9 @@ impl PerformanceTestRunner {
         warmup_duration: Duration,
         report_interval: Duration,
         load_pattern: LoadPattern,
+        client_options: &ClientOptions,
     ) -> Result<Self> {
-        // Use a longer timeout for performance tests to avoid timeouts under load
-        let timeout = Duration::from_secs(60);
-        let executor = RequestExecutor::new(timeout)?;
+        let executor = RequestExecutor::new(REQUEST_TIMEOUT, client_options)?;
 
         Ok(Self {
             concurrent_users,
@@ -44,32 +91,137 @@ impl PerformanceTestRunner {
             report_interval,
             load_pattern,
             executor,
+            client_options: client_options.clone(),
+            base_url: None,
+            headers: HashMap::new(),
+            suite: None,
+            timeseries: None,
+            data: None,
+            prometheus_listen: None,
+            run_id: String::new(),
+            tui: false,
+            cancel: CancellationToken::new(),
         })
     }
 
+    /// Send requests with a client using these connection settings.
+    pub fn with_connection(mut self, connection: &ConnectionOptions) -> Result<Self> {
+        let client =
+            connection.build_client(REQUEST_TIMEOUT, &self.client_options, Policy::default())?;
+        let no_redirect_client =
+            connection.build_client(REQUEST_TIMEOUT, &self.client_options, Policy::none())?;
+        self.executor = RequestExecutor::with_client(
+            client,
+            no_redirect_client,
+            REQUEST_TIMEOUT,
+            &self.client_options,
+        );
+        Ok(self)
+    }
+
+    /// Keep at most this much of each response body; 0 saves the memory of
+    /// buffering bodies when only status and timing matter. Call after
+    /// [`with_connection`](Self::with_connection), which replaces the executor.
+    pub fn with_max_capture_bytes(mut self, max_capture_bytes: Option<usize>) -> Self {
+        if let Some(max_capture_bytes) = max_capture_bytes {
+            self.executor = self.executor.with_max_capture_bytes(max_capture_bytes);
+        }
+        self
+    }
+
+    /// Only load test the suite with this name (or file name).
+    pub fn with_suite(mut self, suite: Option<String>) -> Self {
+        self.suite = suite;
+        self
+    }
+
+    /// Append a sample per report interval to this CSV or NDJSON file.
+    pub fn with_timeseries(mut self, path: Option<PathBuf>) -> Self {
+        self.timeseries = path;
+        self
+    }
+
+    /// Vary requests with rows from this dataset instead of each suite's own
+    /// `dataset`.
+    pub fn with_data(mut self, path: Option<PathBuf>) -> Self {
+        self.data = path;
+        self
+    }
+
+    /// Serve live metrics for Prometheus to scrape at `/metrics` on this
+    /// address while the load runs.
+    pub fn with_prometheus_listen(mut self, addr: Option<SocketAddr>) -> Self {
+        self.prometheus_listen = addr;
+        self
+    }
+
+    /// Label exported metrics with this run ID.
+    pub fn with_run_id(mut self, run_id: String) -> Self {
+        self.run_id = run_id;
+        self
+    }
+
+    /// Show a live dashboard instead of periodic progress reports. Needs
+    /// stdout to be a terminal.
+    pub fn with_tui(mut self, tui: bool) -> Self {
+        self.tui = tui;
+        self
+    }
+
+    /// Cancelling this token stops the run early; results cover the
+    /// requests made until then and are marked interrupted.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Retarget the suite at `base_url` (see [`retarget_suite`]).
+    pub fn with_base_url(mut self, base_url: Option<String>) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Send `headers` with every HTTP step, over the steps' and suites' own.
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
     pub async fn run_performance_test(
         &self,
         target: &Path,
         env: Option<&str>,
     ) -> Result<PerformanceResults> {
         // Load test suites
-        let test_suites = load_test_suite(target)
+        let test_suites = load_test_suite(target, false)
             .await
             .context("Failed to load test suite for performance testing")?;
 
         if test_suites.is_empty() {
             anyhow::bail!("No test suites found in target path");
         }
-
-        // For performance testing, we'll focus on the first test suite
-        let (suite_name, config) = &test_suites[0];
-
-        if config.tests.is_empty() {
-            anyhow::bail!("Test suite '{}' contains no tests", suite_name);
+        for warning in test_suites.iter().flat_map(|suite| &suite.warnings) {
+            println!("⚠️  {}", warning);
         }
 
-        println!("🚀 Starting performance test on suite: {}", suite_name);
-        println!("   Tests to execute: {}", config.tests.len());
+        let mut suites = self.select_suites(test_suites, env)?;
+        self.load_rows(&mut suites).await?;
+        let suites = Arc::new(suites);
+        let timeseries = self
+            .timeseries
+            .as_deref()
+            .map(TimeseriesWriter::create)
+            .transpose()?;
+        let test_count: usize = suites.iter().map(|suite| suite.config.tests.len()).sum();
+
+        println!("🚀 Starting performance test on {} suite(s):", suites.len());
+        for suite in suites.iter() {
+            println!("   - {} ({} tests)", suite.name, suite.config.tests.len());
+        }
+        println!("   Tests to execute: {}", test_count);
+        let data_rows: usize = suites.iter().map(|suite| suite.rows.len()).sum();
+        if data_rows > 0 {
+            println!("   Data rows: {}", data_rows);
+        }
         println!("   Concurrent users: {}", self.concurrent_users);
         if let Some(rps) = self.target_rps {
             println!("   Target RPS: {}", rps);
@@ -77,8 +229,18 @@ impl PerformanceTestRunner {
         println!("   Test duration: {:?}", self.test_duration);
         println!("   Load pattern: {:?}", self.load_pattern);
 
-        // Setup shared metrics and load controller
+        // Warmup phase
+        let warmup_requests = self.warm_up(&suites).await?;
+
+        println!("\n🔥 Starting load generation...");
+
+        // Metrics and load pattern both start from here, after warmup
         let metrics = Arc::new(Mutex::new(PerformanceMetrics::new()));
+        {
+            let mut metrics = metrics.lock().await;
+            metrics.warmup_requests = warmup_requests;
+            metrics.data_rows_available = data_rows as u64;
+        }
         let load_controller = Arc::new(LoadController::new(
             self.load_pattern.clone(),
             self.target_rps,
@@ -86,39 +248,89 @@ impl PerformanceTestRunner {
             self.warmup_duration,
         ));
 
+        let exporter = match self.prometheus_listen {
+            Some(addr) => {
+                let exporter = prometheus::serve_metrics(
+                    addr,
+                    self.run_id.clone(),
+                    Arc::clone(&metrics),
+                    Arc::clone(&load_controller),
+                )
+                .await?;
+                println!("📈 Serving Prometheus metrics at http://{}/metrics", addr);
+                Some(exporter)
+            }
+            None => None,
+        };
+
         // Setup monitoring
-        let monitor = PerformanceMonitor::new(self.report_interval, self.load_pattern.clone());
+        let monitor = PerformanceMonitor::new(self.report_interval, self.load_pattern.clone())
+            .with_progress_reports(!self.tui);
         monitor
             .start_background_monitoring(
                 Arc::clone(&metrics),
                 self.test_duration,
                 Arc::clone(&load_controller),
+                timeseries,
             )
             .await;
 
-        // Warmup phase
-        if self.warmup_duration > Duration::ZERO {
-            println!("\n⏳ Warming up for {:?}...", self.warmup_duration);
-            sleep(self.warmup_duration).await;
-        }
-
-        println!("\n🔥 Starting load generation...");
-
-        // Start the performance test
-        let test_start = Instant::now();
+        let dashboard = if self.tui {
+            Some(
+                Dashboard::new(self.test_duration)
+                    .spawn(Arc::clone(&metrics), Arc::clone(&load_controller))
+                    .context("Failed to start the dashboard")?,
+            )
+        } else {
+            None
+        };
 
-        // Run the load generation
-        self.generate_load(
-            config,
-            env,
+        // Run the load generation; a stress run also ends at its breaking point
+        let load = self.generate_load(
+            suites,
             Arc::clone(&metrics),
             Arc::clone(&load_controller),
-            test_start,
-        )
-        .await?;
+            self.test_duration,
+        );
+        let mut stress_watch = match &self.load_pattern {
+            LoadPattern::Stress { every, limits, .. } => {
+                Some(StressWatch::new(*every, limits.clone()))
+            }
+            _ => None,
+        };
+        match &mut stress_watch {
+            Some(watch) => {
+                tokio::pin!(load);
+                tokio::select! {
+                    result = &mut load => result?,
+                    () = watch.run(&metrics, &load_controller) => load.await?,
+                }
+            }
+            None => load.await?,
+        }
 
-        // Wait for any remaining requests to complete (with timeout)
-        sleep(Duration::from_secs(2)).await;
+        if let Some(exporter) = exporter {
+            exporter.abort();
+        }
+        drop(dashboard);
+
+        let interrupted = self.cancel.is_cancelled();
+        match stress_watch
+            .as_ref()
+            .and_then(|watch| watch.outcome.breaking_rps)
+        {
+            Some(rps) => println!(
+                "\n💥 Breaking point reached at {:.0} RPS, stopping load generation...",
+                rps
+            ),
+            None if interrupted => println!("\n⚠️  Interrupted, stopping load generation..."),
+            None => println!("\n⏱️  Test duration reached, stopping load generation..."),
+        }
+        {
+            let mut metrics = metrics.lock().await;
+            metrics.stress = stress_watch.map(|watch| watch.outcome);
+            metrics.interrupted = interrupted;
+        }
 
         // Generate final results
         let final_metrics = metrics.lock().await;
@@ -131,31 +343,201 @@ impl PerformanceTestRunner {
         Ok(results)
     }
 
-    async fn generate_load(
+    /// Send traffic at half the target rate for the warmup period, so
+    /// connections are open before measuring starts. Its metrics are
+    /// discarded; returns how many requests were sent.
+    async fn warm_up(&self, suites: &Arc<Vec<PerfSuite>>) -> Result<u64> {
+        if self.warmup_duration.is_zero() {
+            return Ok(0);
+        }
+        println!("\n⏳ Warming up for {:?}...", self.warmup_duration);
+
+        let warmup_rps = (self.target_rps.unwrap_or(self.concurrent_users * 10) / 2).max(1);
+        let metrics = Arc::new(Mutex::new(PerformanceMetrics::new()));
+        let load_controller = Arc::new(LoadController::new(
+            LoadPattern::Constant,
+            Some(warmup_rps),
+            self.concurrent_users,
+            Duration::ZERO,
+        ));
+        self.generate_load(
+            Arc::clone(suites),
+            Arc::clone(&metrics),
+            Arc::clone(&load_controller),
+            self.warmup_duration,
+        )
+        .await?;
+
+        let metrics = metrics.lock().await;
+        Ok(metrics.request_count + metrics.connection_errors)
+    }
+
+    /// The suites to load test: the one named by `--suite`, or every suite
+    /// with tests, retargeted at the base URL if one is set. Suites sharing
+    /// a name are reported under the name and their file, so their metrics
+    /// aren't merged.
+    fn select_suites(
         &self,
-        config: &RivetConfig,
+        test_suites: Vec<SuiteFile>,
         env: Option<&str>,
+    ) -> Result<Vec<PerfSuite>> {
+        let test_suites = match &self.suite {
+            Some(wanted) => {
+                let available: Vec<String> = test_suites
+                    .iter()
+                    .map(|suite| suite.config.name.clone())
+                    .collect();
+                let selected: Vec<_> = test_suites
+                    .into_iter()
+                    .filter(|suite| &suite.config.name == wanted || &suite.name == wanted)
+                    .collect();
+                if selected.is_empty() {
+                    anyhow::bail!(
+                        "No suite named '{}'. Available: {}",
+                        wanted,
+                        available.join(", ")
+                    );
+                }
+                selected
+            }
+            None => test_suites,
+        };
+
+        let mut name_counts: HashMap<&str, usize> = HashMap::new();
+        for suite in &test_suites {
+            *name_counts.entry(suite.config.name.as_str()).or_default() += 1;
+        }
+        let names: Vec<String> = test_suites
+            .iter()
+            .map(|suite| match name_counts[suite.config.name.as_str()] {
+                1 => suite.config.name.clone(),
+                _ => format!("{} ({})", suite.config.name, suite.name),
+            })
+            .collect();
+
+        let mut suites = Vec::new();
+        for (suite, name) in test_suites.into_iter().zip(names) {
+            let executor = self
+                .executor
+                .clone()
+                .with_base_dir(suite.dir())
+                .with_base_path(suite.config.base_path.as_deref());
+            let config = suite.config;
+            if config.tests.is_empty() {
+                if self.suite.is_some() {
+                    anyhow::bail!("Test suite '{}' contains no tests", suite.name);
+                }
+                continue;
+            }
+            let mut config = match &self.base_url {
+                Some(base_url) => retarget_suite(&config, base_url)?,
+                None => config,
+            };
+            config.override_headers(&self.headers);
+            let mut context = VariableContext::new().with_env_vars();
+            let (env, overridden) = suite_env(&config, env);
+            if let Some(declared) = overridden {
+                println!(
+                    "Note: --env {} overrides env: {} of suite '{}'",
+                    env.as_deref().unwrap_or_default(),
+                    declared,
+                    config.name
+                );
+            }
+            if let Some(env_name) = env {
+                context.set(RIVET_ENV_VAR.to_string(), env_name);
+            }
+            let mut context = context
+                .with_config_vars(config.vars.as_ref())
+                .with_context(|| format!("Invalid vars in suite '{}'", config.name))?;
+            if let Some(base_url) = &self.base_url {
+                context.set_variable(BASE_URL_VAR.to_string(), base_url.clone());
+            }
+            suites.push(PerfSuite {
+                name,
+                config,
+                context,
+                rows: Vec::new(),
+                executor,
+            });
+        }
+
+        if suites.is_empty() {
+            anyhow::bail!("No tests found in the loaded suites");
+        }
+        Ok(suites)
+    }
+
+    /// Load each suite's dataset rows: `--data` for every suite, otherwise
+    /// the suite's own `dataset` with its filter and sample applied.
+    async fn load_rows(&self, suites: &mut [PerfSuite]) -> Result<()> {
+        let shared = match &self.data {
+            Some(path) => Some(
+                load_data(path, None)
+                    .await
+                    .with_context(|| format!("Failed to load dataset: {}", path.display()))?,
+            ),
+            None => None,
+        };
+
+        for suite in suites {
+            suite.rows = match (&shared, &suite.config.dataset) {
+                (Some(rows), _) => rows.clone(),
+                (None, Some(dataset)) => {
+                    let file = suite.executor.suite_path(&dataset.file);
+                    let rows = load_data(&file, dataset.format.as_deref())
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Failed to load dataset: {}",
+                                describe_suite_path(&dataset.file, &file)
+                            )
+                        })?;
+                    let selection = DataSelection {
+                        filter: dataset.filter.clone(),
+                        sample: dataset.sample.as_ref().map(|s| s.to_string()),
+                        ..Default::default()
+                    };
+                    selection
+                        .apply(rows)
+                        .with_context(|| {
+                            format!("Failed to select rows from dataset: {}", dataset.file)
+                        })?
+                        .into_iter()
+                        .map(|(_, row)| row)
+                        .collect()
+                }
+                (None, None) => continue,
+            };
+            if suite.rows.is_empty() {
+                anyhow::bail!("Dataset for suite '{}' has no rows", suite.name);
+            }
+        }
+        Ok(())
+    }
+
+    async fn generate_load(
+        &self,
+        suites: Arc<Vec<PerfSuite>>,
         metrics: Arc<Mutex<PerformanceMetrics>>,
         load_controller: Arc<LoadController>,
-        test_start: Instant,
+        total_duration: Duration,
     ) -> Result<()> {
         let mut futures = FuturesUnordered::new();
-        let total_duration = self.test_duration;
+        let test_start = Instant::now();
 
         // Spawn worker tasks
         for worker_id in 0..self.concurrent_users {
-            let config = config.clone();
-            let env = env.map(|s| s.to_string());
-            let executor = self.executor.clone();
+            let suites = Arc::clone(&suites);
             let metrics = Arc::clone(&metrics);
             let load_controller = Arc::clone(&load_controller);
+            let workers = self.concurrent_users;
 
             futures.push(tokio::spawn(async move {
                 Self::worker_task(
                     worker_id,
-                    config,
-                    env.as_deref(),
-                    executor,
+                    workers,
+                    suites,
                     metrics,
                     load_controller,
                     test_start,
@@ -166,15 +548,13 @@ impl PerformanceTestRunner {
         }
 
         // Wait for all workers to complete or timeout
-        let timeout_future = sleep(self.test_duration);
+        let timeout_future = sleep(total_duration);
         tokio::pin!(timeout_future);
 
         loop {
             tokio::select! {
-                _ = &mut timeout_future => {
-                    println!("\n⏱️  Test duration reached, stopping load generation...");
-                    break;
-                }
+                _ = &mut timeout_future => break,
+                _ = self.cancel.cancelled() => break,
                 result = futures.next() => {
                     match result {
                         Some(Ok(Ok(()))) => {
@@ -195,52 +575,83 @@ impl PerformanceTestRunner {
             }
         }
 
+        // Release waiting workers and let requests in flight finish, so every
+        // worker flushes its metrics before results are calculated
+        load_controller.stop();
+        let _ = tokio::time::timeout(SHUTDOWN_GRACE, async {
+            while futures.next().await.is_some() {}
+        })
+        .await;
+
         Ok(())
     }
 
-    #[allow(clippy::too_many_arguments)]
     async fn worker_task(
         worker_id: u32,
-        config: RivetConfig,
-        env: Option<&str>,
-        executor: RequestExecutor,
+        workers: u32,
+        suites: Arc<Vec<PerfSuite>>,
         metrics: Arc<Mutex<PerformanceMetrics>>,
         load_controller: Arc<LoadController>,
         _test_start: Instant,
         total_duration: Duration,
     ) -> Result<()> {
-        // Create variable context for this worker
-        let mut context = VariableContext::new();
-
-        // Load environment variables if specified
-        if let Some(_env_name) = env {
-            if let Some(env_vars) = config.vars.as_ref() {
-                for (key, value) in env_vars {
-                    context.set_variable(key.clone(), value.clone());
-                }
-            }
-        }
-
-        let test_count = config.tests.len();
-        let mut current_test_index = 0;
+        // Each worker walks a suite's rows from its own offset, `workers`
+        // rows at a time, so together they spread over the whole dataset
+        let mut next_row: Vec<usize> = vec![worker_id as usize; suites.len()];
+
+        // Every suite's tests in one rotation, so each suite gets traffic in
+        // proportion to its test count; workers start at different offsets
+        let steps: Vec<(usize, usize)> = suites
+            .iter()
+            .enumerate()
+            .flat_map(|(suite_index, suite)| {
+                (0..suite.config.tests.len()).map(move |test_index| (suite_index, test_index))
+            })
+            .collect();
+        let mut current_step = worker_id as usize % steps.len();
 
         // Worker runs for the specified duration
         let worker_start = Instant::now();
+        let mut local_metrics = PerformanceMetrics::new();
+        let mut last_flush = Instant::now();
 
         while worker_start.elapsed() < total_duration {
+            // Apply rate limiting if configured
+            load_controller.acquire().await;
+            if load_controller.is_stopped() || worker_start.elapsed() >= total_duration {
+                break;
+            }
+
             // Get current test to execute (round-robin)
-            let test_step = &config.tests[current_test_index];
-            current_test_index = (current_test_index + 1) % test_count;
+            let (suite_index, test_index) = steps[current_step];
+            current_step = (current_step + 1) % steps.len();
+            let suite = &suites[suite_index];
+            let test_step = &suite.config.tests[test_index];
 
             let _request_start = Instant::now();
 
+            let row = (!suite.rows.is_empty()).then(|| {
+                let row = next_row[suite_index] % suite.rows.len();
+                next_row[suite_index] = row + workers as usize;
+                row
+            });
+            let row_context;
+            let context = match row {
+                Some(row) => {
+                    row_context = suite.context.clone().with_data_row(&suite.rows[row]);
+                    &row_context
+                }
+                None => &suite.context,
+            };
+
             // Execute the request
-            let test_result = executor
+            let test_result = suite
+                .executor
                 .execute_test(
-                    &format!("worker_{}_test_{}", worker_id, current_test_index),
+                    &format!("worker_{}_test_{}", worker_id, current_step),
                     &test_step.request,
                     test_step.expect.as_ref(),
-                    &context,
+                    context,
                 )
                 .await;
 
@@ -248,45 +659,37 @@ impl PerformanceTestRunner {
             let is_error = !test_result.passed;
             let status_code = test_result.response_status.unwrap_or(0);
 
-            // Estimate request/response size (simplified)
-            let bytes_sent = test_step
-                .request
-                .body
-                .as_ref()
-                .map(|b| b.len() as u64)
-                .unwrap_or(100); // Estimate header size
-            let bytes_received = test_result
-                .response_body
-                .as_ref()
-                .map(|b| b.len() as u64)
-                .unwrap_or(0);
+            let (bytes_sent, bytes_received) = (test_result.bytes_sent, test_result.bytes_received);
 
             // Record metrics
-            {
-                let mut metrics_guard = metrics.lock().await;
-                if is_error && status_code == 0 {
-                    // Connection error
-                    metrics_guard.record_connection_error();
-                } else {
-                    metrics_guard.record_request(
-                        response_time,
-                        status_code,
-                        bytes_sent,
-                        bytes_received,
-                        is_error,
-                    );
-                }
+            local_metrics.record_test(&suite.name, &test_step.name, response_time, is_error);
+            if let Some(row) = row {
+                local_metrics.record_data_row(suite_index, row);
             }
-
-            // Apply rate limiting if configured
-            if let Some(delay) = load_controller.request_delay() {
-                sleep(delay).await;
+            if let Some(version) = &test_result.http_version {
+                local_metrics.record_http_version(version);
+            }
+            if is_error && status_code == 0 {
+                // Connection error
+                local_metrics.record_connection_error();
+            } else {
+                local_metrics.record_request(
+                    response_time,
+                    status_code,
+                    bytes_sent,
+                    bytes_received,
+                    is_error,
+                );
+            }
+            if last_flush.elapsed() >= FLUSH_INTERVAL
+                || local_metrics.request_count + local_metrics.connection_errors >= FLUSH_EVERY
+            {
+                local_metrics.drain_into(&mut *metrics.lock().await);
+                last_flush = Instant::now();
             }
-
-            // Small delay between requests to prevent overwhelming
-            sleep(Duration::from_millis(1)).await;
         }
 
+        local_metrics.drain_into(&mut *metrics.lock().await);
         Ok(())
     }
 }
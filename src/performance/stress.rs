@@ -0,0 +1,159 @@
+use crate::performance::histogram::LatencyHistogram;
+use crate::performance::patterns::LoadController;
+use crate::performance::PerformanceMetrics;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// What breaks a stress run.
+#[derive(Debug, Clone)]
+pub struct StressLimits {
+    /// Percent of a step's requests that may fail
+    pub max_error_rate: f64,
+    /// Highest P99 response time a step may have
+    pub max_p99: Option<Duration>,
+}
+
+/// The breaking point a stress run found, as written to the JSON report.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StressOutcome {
+    /// Highest step rate that stayed within the limits
+    pub max_rps: f64,
+    /// Step rate that broke the limits; unset if the run ended first
+    pub breaking_rps: Option<f64>,
+    /// Which limit broke, e.g. `error rate 12.0% exceeds 5.0%`
+    pub reason: Option<String>,
+}
+
+impl StressLimits {
+    /// Why a step with this error rate (percent) and P99 breaks the limits.
+    fn broken_by(&self, error_rate: f64, p99: Duration) -> Option<String> {
+        if error_rate > self.max_error_rate {
+            return Some(format!(
+                "error rate {:.1}% exceeds {:.1}%",
+                error_rate, self.max_error_rate
+            ));
+        }
+        match self.max_p99 {
+            Some(max_p99) if p99 > max_p99 => Some(format!(
+                "p99 {}ms exceeds {}ms",
+                p99.as_millis(),
+                max_p99.as_millis()
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Judges a stress run one step at a time and stops it at the first step
+/// that breaks the limits.
+pub struct StressWatch {
+    every: Duration,
+    limits: StressLimits,
+    pub outcome: StressOutcome,
+}
+
+/// Where the metrics stood when a step began.
+struct StepStart {
+    response_times: LatencyHistogram,
+    requests: u64,
+    errors: u64,
+}
+
+impl StepStart {
+    fn new(metrics: &PerformanceMetrics) -> Self {
+        Self {
+            response_times: metrics.response_times.clone(),
+            requests: metrics.request_count + metrics.connection_errors,
+            errors: metrics.error_count,
+        }
+    }
+
+    /// Requests, error rate (percent) and P99 since the step began.
+    fn measure(&self, metrics: &PerformanceMetrics) -> (u64, f64, Duration) {
+        let requests = metrics.request_count + metrics.connection_errors - self.requests;
+        if requests == 0 {
+            return (0, 0.0, Duration::ZERO);
+        }
+        let errors = metrics.error_count - self.errors;
+
+        let p99 = metrics
+            .response_times
+            .since(&self.response_times)
+            .percentile(99.0);
+
+        (requests, errors as f64 / requests as f64 * 100.0, p99)
+    }
+}
+
+impl StressWatch {
+    pub fn new(every: Duration, limits: StressLimits) -> Self {
+        Self {
+            every,
+            limits,
+            outcome: StressOutcome::default(),
+        }
+    }
+
+    /// Follow the run until a step breaks the limits, which stops
+    /// `load_controller`. Steps are timed from now, so start this alongside
+    /// the load.
+    pub async fn run(
+        &mut self,
+        metrics: &Mutex<PerformanceMetrics>,
+        load_controller: &LoadController,
+    ) {
+        let mut steps = tokio::time::interval(self.every);
+        steps.tick().await; // The first tick fires immediately
+
+        loop {
+            let rps = load_controller.current_target_rps();
+            let start = StepStart::new(&*metrics.lock().await);
+            steps.tick().await;
+
+            let (requests, error_rate, p99) = start.measure(&*metrics.lock().await);
+            if requests == 0 {
+                continue;
+            }
+            if let Some(reason) = self.limits.broken_by(error_rate, p99) {
+                self.outcome.breaking_rps = Some(rps);
+                self.outcome.reason = Some(reason);
+                load_controller.stop();
+                return;
+            }
+            self.outcome.max_rps = rps;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_report_what_broke() {
+        let limits = StressLimits {
+            max_error_rate: 5.0,
+            max_p99: Some(Duration::from_millis(500)),
+        };
+
+        assert_eq!(limits.broken_by(5.0, Duration::from_millis(500)), None);
+        assert_eq!(
+            limits.broken_by(12.0, Duration::from_millis(900)).unwrap(),
+            "error rate 12.0% exceeds 5.0%"
+        );
+        assert_eq!(
+            limits.broken_by(0.0, Duration::from_millis(812)).unwrap(),
+            "p99 812ms exceeds 500ms"
+        );
+
+        let error_rate_only = StressLimits {
+            max_p99: None,
+            ..limits
+        };
+        assert_eq!(
+            error_rate_only.broken_by(1.0, Duration::from_secs(30)),
+            None
+        );
+    }
+}
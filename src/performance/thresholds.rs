@@ -0,0 +1,236 @@
+use crate::config::PerfThresholds;
+use crate::performance::PerformanceResults;
+use crate::utils::parse_timeout;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Success rate (percent) a run must reach when no threshold is given.
+pub const DEFAULT_MIN_SUCCESS_RATE: f64 = 95.0;
+
+/// Limits a performance run is gated on.
+#[derive(Debug, Clone, Default)]
+pub struct Thresholds {
+    pub max_p95: Option<Duration>,
+    pub max_p99: Option<Duration>,
+    pub max_avg: Option<Duration>,
+    pub min_rps: Option<f64>,
+    /// Percent, 0-100; [`DEFAULT_MIN_SUCCESS_RATE`] when unset
+    pub min_success_rate: Option<f64>,
+    /// Count assertion failures against the success rate, not just
+    /// transport and HTTP errors
+    pub count_assertion_failures: bool,
+}
+
+/// The outcome of one threshold, as written to the JSON report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThresholdResult {
+    /// `p95`, `p99`, `avg`, `rps` or `success_rate`
+    pub name: String,
+    pub limit: f64,
+    pub actual: f64,
+    /// `ms`, `rps` or `%`
+    pub unit: String,
+    pub passed: bool,
+}
+
+impl fmt::Display for ThresholdResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = |value: f64| match self.unit.as_str() {
+            "ms" => format!("{:.0}ms", value),
+            "%" => format!("{:.1}%", value),
+            unit => format!("{:.1} {}", value, unit),
+        };
+        // Latencies are upper bounds, throughput and success rate lower bounds
+        let (relation, limit_relation) = if self.unit == "ms" {
+            ("exceeds", "≤")
+        } else {
+            ("is below", "≥")
+        };
+        if self.passed {
+            write!(
+                f,
+                "{} {} ({} {})",
+                self.name,
+                unit(self.actual),
+                limit_relation,
+                unit(self.limit)
+            )
+        } else {
+            write!(
+                f,
+                "{} {} {} {}",
+                self.name,
+                unit(self.actual),
+                relation,
+                unit(self.limit)
+            )
+        }
+    }
+}
+
+impl Thresholds {
+    /// Thresholds from a perf config file, with `self` (the command line)
+    /// taking precedence for anything both set.
+    pub fn or_config(self, config: Option<&PerfThresholds>) -> Result<Self> {
+        let Some(config) = config else {
+            return Ok(self);
+        };
+        let duration = |value: &Option<String>, name: &str| {
+            value
+                .as_deref()
+                .map(|value| {
+                    parse_timeout(value)
+                        .with_context(|| format!("Invalid {} threshold: {}", name, value))
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            max_p95: self.max_p95.or(duration(&config.fail_p95, "fail_p95")?),
+            max_p99: self.max_p99.or(duration(&config.fail_p99, "fail_p99")?),
+            max_avg: self.max_avg.or(duration(&config.fail_avg, "fail_avg")?),
+            min_rps: self.min_rps.or(config.min_rps),
+            min_success_rate: self.min_success_rate.or(config.min_success_rate),
+            count_assertion_failures: self.count_assertion_failures
+                || config.count_assertion_failures.unwrap_or(false),
+        })
+    }
+
+    /// Check every threshold that is set against `results`.
+    pub fn evaluate(&self, results: &PerformanceResults) -> Vec<ThresholdResult> {
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let mut outcomes = Vec::new();
+
+        let latencies = [
+            ("p95", self.max_p95, results.p95_response_time),
+            ("p99", self.max_p99, results.p99_response_time),
+            ("avg", self.max_avg, results.average_response_time),
+        ];
+        for (name, limit, actual) in latencies {
+            if let Some(limit) = limit {
+                outcomes.push(ThresholdResult {
+                    name: name.to_string(),
+                    limit: millis(limit),
+                    actual: millis(actual),
+                    unit: "ms".to_string(),
+                    passed: actual <= limit,
+                });
+            }
+        }
+
+        if let Some(min_rps) = self.min_rps {
+            outcomes.push(ThresholdResult {
+                name: "rps".to_string(),
+                limit: min_rps,
+                actual: results.requests_per_second,
+                unit: "rps".to_string(),
+                passed: results.requests_per_second >= min_rps,
+            });
+        }
+
+        let success_rate = if self.count_assertion_failures || results.total_requests == 0 {
+            results.success_rate * 100.0
+        } else {
+            let failed = results.failed_requests - results.error_breakdown.assertion;
+            (results.total_requests - failed) as f64 / results.total_requests as f64 * 100.0
+        };
+        let min_success_rate = self.min_success_rate.unwrap_or(DEFAULT_MIN_SUCCESS_RATE);
+        outcomes.push(ThresholdResult {
+            name: "success_rate".to_string(),
+            limit: min_success_rate,
+            actual: success_rate,
+            unit: "%".to_string(),
+            passed: success_rate >= min_success_rate,
+        });
+
+        outcomes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::PerformanceMetrics;
+
+    fn results() -> PerformanceResults {
+        let mut metrics = PerformanceMetrics::new();
+        for millis in [100, 120, 140, 300] {
+            metrics.record_request(Duration::from_millis(millis), 200, 10, 10, false);
+        }
+        metrics.record_request(Duration::from_millis(150), 500, 10, 10, true);
+        metrics.calculate_results()
+    }
+
+    #[test]
+    fn test_evaluate_lists_every_threshold() {
+        let thresholds = Thresholds {
+            max_p95: Some(Duration::from_millis(250)),
+            max_avg: Some(Duration::from_millis(500)),
+            ..Default::default()
+        };
+        let outcomes = thresholds.evaluate(&results());
+
+        let summary: Vec<(&str, bool)> = outcomes
+            .iter()
+            .map(|outcome| (outcome.name.as_str(), outcome.passed))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("p95", false), ("avg", true), ("success_rate", false)]
+        );
+        assert_eq!(outcomes[0].to_string(), "p95 300ms exceeds 250ms");
+        assert_eq!(outcomes[2].to_string(), "success_rate 80.0% is below 95.0%");
+    }
+
+    #[test]
+    fn test_assertion_failures_only_count_when_asked() {
+        let mut metrics = PerformanceMetrics::new();
+        for _ in 0..8 {
+            metrics.record_request(Duration::from_millis(10), 200, 10, 10, false);
+        }
+        // Right status, wrong body
+        metrics.record_request(Duration::from_millis(10), 200, 10, 10, true);
+        metrics.record_connection_error();
+        let results = metrics.calculate_results();
+        assert_eq!(results.error_breakdown.assertion, 1);
+        assert_eq!(results.error_breakdown.transport, 1);
+
+        let lenient = Thresholds::default().evaluate(&results);
+        assert_eq!(lenient[0].actual, 90.0);
+        let strict = Thresholds {
+            count_assertion_failures: true,
+            ..Default::default()
+        }
+        .evaluate(&results);
+        assert_eq!(strict[0].actual, 80.0);
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() {
+        let config = PerfThresholds {
+            fail_p95: Some("200ms".to_string()),
+            fail_p99: Some("1s".to_string()),
+            min_rps: Some(50.0),
+            ..Default::default()
+        };
+        let thresholds = Thresholds {
+            max_p95: Some(Duration::from_millis(400)),
+            ..Default::default()
+        }
+        .or_config(Some(&config))
+        .unwrap();
+
+        assert_eq!(thresholds.max_p95, Some(Duration::from_millis(400)));
+        assert_eq!(thresholds.max_p99, Some(Duration::from_secs(1)));
+        assert_eq!(thresholds.min_rps, Some(50.0));
+
+        let config = PerfThresholds {
+            fail_avg: Some("fast".to_string()),
+            ..Default::default()
+        };
+        let error = Thresholds::default().or_config(Some(&config)).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid fail_avg threshold: fast");
+    }
+}
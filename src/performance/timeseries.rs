@@ -0,0 +1,69 @@
+use crate::performance::metrics::IntervalSnapshot;
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Appends one record per report interval to a CSV or NDJSON file, chosen
+/// by its extension. Every record is flushed as it is written, so a run
+/// that is killed still leaves the samples taken so far.
+pub struct TimeseriesWriter {
+    output: Output,
+}
+
+enum Output {
+    // csv::Writer buffers on its own
+    Csv(Box<csv::Writer<File>>),
+    Ndjson(BufWriter<File>),
+}
+
+impl TimeseriesWriter {
+    /// Open `path` for appending: `.csv` for CSV (with a header row when the
+    /// file is new), `.ndjson` or `.jsonl` for one JSON object per line.
+    pub fn create(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        if !matches!(extension.as_str(), "csv" | "ndjson" | "jsonl") {
+            anyhow::bail!(
+                "Unsupported time series file '{}': use a .csv, .ndjson or .jsonl extension",
+                path.display()
+            );
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open time series file {}", path.display()))?;
+        let is_new = file.metadata()?.len() == 0;
+
+        let output = if extension == "csv" {
+            Output::Csv(Box::new(
+                csv::WriterBuilder::new()
+                    .has_headers(is_new)
+                    .from_writer(file),
+            ))
+        } else {
+            Output::Ndjson(BufWriter::new(file))
+        };
+        Ok(Self { output })
+    }
+
+    pub fn write(&mut self, snapshot: &IntervalSnapshot) -> Result<()> {
+        match &mut self.output {
+            Output::Csv(writer) => {
+                writer.serialize(snapshot)?;
+                writer.flush()?;
+            }
+            Output::Ndjson(writer) => {
+                serde_json::to_writer(&mut *writer, snapshot)?;
+                writer.write_all(b"\n")?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,523 @@
+//! A recording reverse proxy for `rivet record`: requests are forwarded to
+//! a target and each new method and path is saved as a rivet test.
+
+use crate::config::{Expectation, Request, RivetConfig, StatusExpectation, TestStep};
+use crate::import::sanitize_filename;
+use crate::runner::log::header_list;
+use crate::style::Colorize;
+use anyhow::{anyhow, Context, Result};
+use hyper::body::Bytes;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Server};
+use regex::Regex;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::fs;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Headers that only concern one connection, never forwarded or recorded.
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "trailers",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Request headers recorded as a secret variable read from the environment
+/// instead of their value, e.g. `authorization: ${AUTHORIZATION}`.
+const REDACTED: &[&str] = &["authorization", "cookie"];
+
+/// One request that went through the proxy and the status it got.
+pub struct Exchange {
+    pub method: String,
+    /// Path and query string
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Bytes,
+    pub status: u16,
+}
+
+/// What became of a recorded exchange.
+#[derive(Debug, PartialEq)]
+pub enum Recorded {
+    /// Saved as a new test file
+    Saved(PathBuf),
+    /// Its method and path were already saved
+    Repeat,
+    /// Its path didn't match `--filter-path`
+    Filtered,
+}
+
+#[derive(Default)]
+struct RecordState {
+    seen: HashSet<(String, String)>,
+    files: Vec<PathBuf>,
+    repeats: usize,
+}
+
+/// Turns exchanges into test files under `out`, one per method and path.
+pub struct Recorder {
+    target: String,
+    out: PathBuf,
+    filter: Option<Regex>,
+    state: Mutex<RecordState>,
+}
+
+impl Recorder {
+    pub fn new(target: &str, out: &Path) -> Result<Self> {
+        let parsed =
+            url::Url::parse(target).with_context(|| format!("Invalid --target: {}", target))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(anyhow!("--target must be an http(s) URL: {}", target));
+        }
+        fs::create_dir_all(out).with_context(|| format!("Failed to create {}", out.display()))?;
+        Ok(Self {
+            target: target.trim_end_matches('/').to_string(),
+            out: out.to_path_buf(),
+            filter: None,
+            state: Mutex::new(RecordState::default()),
+        })
+    }
+
+    /// Only record requests whose path matches `filter`; the rest are still
+    /// forwarded.
+    pub fn with_filter(mut self, filter: Regex) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn record(&self, exchange: &Exchange) -> Result<Recorded> {
+        let path = exchange
+            .target
+            .split('?')
+            .next()
+            .unwrap_or(&exchange.target);
+        if self
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !filter.is_match(path))
+        {
+            return Ok(Recorded::Filtered);
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if !state
+            .seen
+            .insert((exchange.method.clone(), path.to_string()))
+        {
+            state.repeats += 1;
+            return Ok(Recorded::Repeat);
+        }
+
+        let file = self.unused_path(&exchange.method, path);
+        let yaml = serde_yaml::to_string(&self.suite(exchange, path))?;
+        fs::write(&file, yaml).with_context(|| format!("Failed to write {}", file.display()))?;
+        state.files.push(file.clone());
+        Ok(Recorded::Saved(file))
+    }
+
+    /// Write the main `rivet.yaml` and return its path and the number of
+    /// tests and repeats recorded.
+    pub fn finish(&self) -> Result<(PathBuf, usize, usize)> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let config = RivetConfig {
+            default_headers: None,
+            identify_requests: None,
+            base_path: None,
+            include: None,
+            templates: None,
+            auth: None,
+            max_capture_bytes: None,
+            follow_redirects: None,
+            name: format!("Recorded from {}", self.target),
+            description: Some(format!(
+                "{} request(s) recorded by rivet record",
+                state.files.len()
+            )),
+            env: None,
+            vars: Some(HashMap::from([(
+                "baseUrl".to_string(),
+                self.target.clone(),
+            )])),
+            setup: None,
+            tests: vec![], // Individual test files will be loaded
+            dataset: None,
+            teardown: None,
+            tls: None,
+            insecure: None,
+            before_each: None,
+            after_each: None,
+            secrets: None,
+            strict_vars: None,
+        };
+        let path = self.out.join("rivet.yaml");
+        fs::write(&path, serde_yaml::to_string(&config)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok((path, state.files.len(), state.repeats))
+    }
+
+    /// A suite with one test replaying the exchange against `{{baseUrl}}`.
+    fn suite(&self, exchange: &Exchange, path: &str) -> RivetConfig {
+        let mut vars = HashMap::from([("baseUrl".to_string(), self.target.clone())]);
+        let mut secrets = Vec::new();
+        let mut headers = HashMap::new();
+        for (name, value) in recordable_headers(&exchange.headers) {
+            if REDACTED.contains(&name.as_str()) {
+                vars.insert(name.clone(), format!("${{{}}}", name.to_uppercase()));
+                headers.insert(name.clone(), format!("{{{{{}}}}}", name));
+                secrets.push(name);
+            } else {
+                headers.insert(name, value);
+            }
+        }
+        secrets.sort();
+
+        let name = format!("{} {}", exchange.method, path);
+        RivetConfig {
+            default_headers: None,
+            identify_requests: None,
+            base_path: None,
+            include: None,
+            templates: None,
+            auth: None,
+            max_capture_bytes: None,
+            follow_redirects: None,
+            name: name.clone(),
+            description: None,
+            env: None,
+            vars: Some(vars),
+            secrets: (!secrets.is_empty()).then_some(secrets),
+            strict_vars: None,
+            setup: None,
+            before_each: None,
+            tests: vec![TestStep {
+                capture: None,
+                name,
+                description: None,
+                vars: None,
+                request: Request {
+                    compression: None,
+                    follow_redirects: None,
+                    method: exchange.method.clone(),
+                    url: format!("{{{{baseUrl}}}}{}", exchange.target),
+                    headers: (!headers.is_empty()).then_some(headers),
+                    params: None,
+                    body: (!exchange.body.is_empty())
+                        .then(|| String::from_utf8_lossy(&exchange.body).to_string()),
+                    grpc: None,
+                },
+                expect: Some(Expectation {
+                    poll: None,
+                    redirects_to: None,
+                    content_type: None,
+                    status: Some(StatusExpectation::Number(exchange.status)),
+                    schema: None,
+                    jsonpath: None,
+                    jsonpath_length: None,
+                    jsonpath_contains: None,
+                    jsonpath_every: None,
+                    headers: None,
+                }),
+            }],
+            after_each: None,
+            dataset: None,
+            teardown: None,
+            tls: None,
+            insecure: None,
+        }
+    }
+
+    /// `get_users_id.rivet.yaml`, numbered when another path sanitizes to
+    /// the same name.
+    fn unused_path(&self, method: &str, path: &str) -> PathBuf {
+        let stem = sanitize_filename(&format!(
+            "{}_{}",
+            method,
+            path.trim_matches('/').replace('/', "_")
+        ));
+        let mut file = self.out.join(format!("{}.rivet.yaml", stem));
+        let mut n = 2;
+        while file.exists() {
+            file = self.out.join(format!("{}_{}.rivet.yaml", stem, n));
+            n += 1;
+        }
+        file
+    }
+}
+
+/// `headers` without hop-by-hop ones, those the `connection` header names,
+/// and the ones the client sets itself.
+fn recordable_headers(headers: &[(String, String)]) -> Vec<(String, String)> {
+    let named: Vec<String> = headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("connection"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(|name| name.trim().to_lowercase())
+        .collect();
+    headers
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.clone()))
+        .filter(|(name, _)| {
+            !HOP_BY_HOP.contains(&name.as_str())
+                && !named.contains(name)
+                && name != "host"
+                && name != "content-length"
+        })
+        .collect()
+}
+
+/// Proxy requests on `addr` to the recorder's target until `shutdown`
+/// resolves. Returns the address bound, which tells the port when `addr`
+/// asked for port 0.
+pub fn serve(
+    recorder: Arc<Recorder>,
+    addr: SocketAddr,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(SocketAddr, JoinHandle<()>)> {
+    // Redirects are passed back to the client rather than followed
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let make_service = make_service_fn(move |_connection| {
+        let (recorder, client) = (recorder.clone(), client.clone());
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                proxy_request(recorder.clone(), client.clone(), request)
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&addr)
+        .with_context(|| format!("Failed to listen on {}", addr))?
+        .serve(make_service);
+    let bound = server.local_addr();
+    let handle = tokio::spawn(async move {
+        if let Err(e) = server.with_graceful_shutdown(shutdown).await {
+            eprintln!("{} Recording proxy stopped: {}", "✗".red(), e);
+        }
+    });
+    Ok((bound, handle))
+}
+
+async fn proxy_request(
+    recorder: Arc<Recorder>,
+    client: reqwest::Client,
+    request: hyper::Request<Body>,
+) -> Result<hyper::Response<Body>, Infallible> {
+    let started = Instant::now();
+    let method = request.method().clone();
+    let target = request
+        .uri()
+        .path_and_query()
+        .map(|target| target.to_string())
+        .unwrap_or_else(|| "/".to_string());
+    let headers = header_list(request.headers());
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .unwrap_or_default();
+
+    let mut upstream = client.request(method.clone(), format!("{}{}", recorder.target, target));
+    for (name, value) in recordable_headers(&headers) {
+        upstream = upstream.header(name, value);
+    }
+    let response = match upstream.body(body.clone()).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            log_exchange(
+                &method,
+                &target,
+                502,
+                started.elapsed(),
+                &"upstream failed".red(),
+            );
+            return Ok(error_response(
+                502,
+                &format!("Upstream request failed: {}", e),
+            ));
+        }
+    };
+
+    let status = response.status();
+    let response_headers = header_list(response.headers());
+    let response_body = match response.bytes().await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            log_exchange(
+                &method,
+                &target,
+                502,
+                started.elapsed(),
+                &"upstream failed".red(),
+            );
+            return Ok(error_response(
+                502,
+                &format!("Upstream response failed: {}", e),
+            ));
+        }
+    };
+
+    let exchange = Exchange {
+        method: method.to_string(),
+        target: target.clone(),
+        headers,
+        body,
+        status: status.as_u16(),
+    };
+    let note = match recorder.record(&exchange) {
+        Ok(Recorded::Saved(file)) => format!("saved {}", file.display()).green().to_string(),
+        Ok(Recorded::Repeat) => "seen".dimmed().to_string(),
+        Ok(Recorded::Filtered) => "filtered".dimmed().to_string(),
+        Err(e) => format!("not saved: {}", e).red().to_string(),
+    };
+    log_exchange(&method, &target, status.as_u16(), started.elapsed(), &note);
+
+    let mut builder = hyper::Response::builder().status(status);
+    for (name, value) in recordable_headers(&response_headers) {
+        builder = builder.header(name, value);
+    }
+    Ok(builder
+        .body(Body::from(response_body))
+        .unwrap_or_else(|e| error_response(502, &format!("Invalid upstream response: {}", e))))
+}
+
+fn error_response(status: u16, message: &str) -> hyper::Response<Body> {
+    let mut response = hyper::Response::new(Body::from(json!({ "error": message }).to_string()));
+    *response.status_mut() =
+        hyper::StatusCode::from_u16(status).unwrap_or(hyper::StatusCode::INTERNAL_SERVER_ERROR);
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn log_exchange(
+    method: &hyper::Method,
+    target: &str,
+    status: u16,
+    elapsed: Duration,
+    note: &dyn std::fmt::Display,
+) {
+    let status = status.to_string();
+    println!(
+        "{} {} {} {} {} {} {}",
+        chrono::Local::now().format("%H:%M:%S").to_string().dimmed(),
+        method.as_str().bold(),
+        target,
+        "→".dimmed(),
+        status,
+        format!("({}ms)", elapsed.as_millis()).dimmed(),
+        note
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn exchange(method: &str, target: &str, headers: &[(&str, &str)]) -> Exchange {
+        Exchange {
+            method: method.to_string(),
+            target: target.to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: Bytes::new(),
+            status: 200,
+        }
+    }
+
+    #[test]
+    fn test_recordable_headers_drop_hop_by_hop() {
+        let headers: Vec<(String, String)> = [
+            ("Host", "api.test"),
+            ("Connection", "keep-alive, X-Trace"),
+            ("X-Trace", "1"),
+            ("Transfer-Encoding", "chunked"),
+            ("Content-Length", "2"),
+            ("Accept", "application/json"),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        assert_eq!(
+            recordable_headers(&headers),
+            vec![("accept".to_string(), "application/json".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_record_dedupes_filters_and_redacts() {
+        let temp_dir = TempDir::new().unwrap();
+        let recorder = Recorder::new("https://api.test/", temp_dir.path())
+            .unwrap()
+            .with_filter(Regex::new("^/users").unwrap());
+
+        let saved = recorder
+            .record(&exchange(
+                "GET",
+                "/users/1?fields=all",
+                &[("Authorization", "Bearer s3cret"), ("Accept", "*/*")],
+            ))
+            .unwrap();
+        let Recorded::Saved(file) = saved else {
+            panic!("expected a saved test, got {:?}", saved);
+        };
+        assert_eq!(file, temp_dir.path().join("get_users_1.rivet.yaml"));
+        assert_eq!(
+            recorder.record(&exchange("GET", "/users/1", &[])).unwrap(),
+            Recorded::Repeat
+        );
+        assert_eq!(
+            recorder.record(&exchange("GET", "/health", &[])).unwrap(),
+            Recorded::Filtered
+        );
+
+        let content = fs::read_to_string(&file).unwrap();
+        assert!(!content.contains("s3cret"), "{}", content);
+        let suite: RivetConfig = serde_yaml::from_str(&content).unwrap();
+        let vars = suite.vars.unwrap();
+        assert_eq!(vars["baseUrl"], "https://api.test");
+        assert_eq!(vars["authorization"], "${AUTHORIZATION}");
+        assert_eq!(suite.secrets, Some(vec!["authorization".to_string()]));
+        let request = &suite.tests[0].request;
+        assert_eq!(request.url, "{{baseUrl}}/users/1?fields=all");
+        assert_eq!(
+            request.headers.as_ref().unwrap()["authorization"],
+            "{{authorization}}"
+        );
+
+        let (main, recorded, repeats) = recorder.finish().unwrap();
+        assert_eq!((recorded, repeats), (1, 1));
+        let main: RivetConfig = serde_yaml::from_str(&fs::read_to_string(main).unwrap()).unwrap();
+        assert_eq!(main.name, "Recorded from https://api.test");
+    }
+
+    #[test]
+    fn test_paths_with_the_same_file_name_are_numbered() {
+        let temp_dir = TempDir::new().unwrap();
+        let recorder = Recorder::new("http://api.test", temp_dir.path()).unwrap();
+        recorder.record(&exchange("GET", "/a.b", &[])).unwrap();
+        assert_eq!(
+            recorder.record(&exchange("GET", "/a_b", &[])).unwrap(),
+            Recorded::Saved(temp_dir.path().join("get_a_b_2.rivet.yaml"))
+        );
+    }
+}
@@ -1,7 +1,13 @@
+use crate::runner::executor::PollSummary;
+use crate::runner::failure::FailureKind;
 use crate::runner::test_runner::TestSuiteResult;
+use crate::runner::variables::REDACTED;
+use crate::utils::is_secret_header;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
@@ -9,10 +15,209 @@ use std::time::Duration;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TestReport {
     pub timestamp: DateTime<Utc>,
+    /// `{{run_id}}` of the run; merged reports keep it only when all sources share it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// What was run and where; merged reports keep it only when all sources share it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ReportMetadata>,
     pub summary: TestSummary,
     pub suites: Vec<TestSuiteReport>,
 }
 
+/// Context about a run, shown in the HTML header and kept in JSON reports
+/// and as JUnit properties.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ReportMetadata {
+    /// File or directory that was run
+    pub target: String,
+    /// `--env`, or the env every suite ran in; suites in different envs
+    /// record theirs in [`TestSuiteReport::env`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub rivet_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Command-line arguments, with password, proxy and credential header
+    /// values masked
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Flags whose values may hold credentials.
+const MASKED_FLAGS: [&str; 2] = ["--identity-password", "--proxy"];
+/// Flags taking a `Name: value` header, whose value is masked when the
+/// header holds credentials.
+const HEADER_FLAGS: [&str; 2] = ["-H", "--header"];
+
+impl ReportMetadata {
+    /// Describe a run of `target`. The commit comes from `GIT_COMMIT` or
+    /// `GITHUB_SHA`, else `git rev-parse HEAD`; the hostname from `HOSTNAME`
+    /// or `COMPUTERNAME`, else `hostname`.
+    pub fn collect(target: &Path, env: Option<&str>, args: &[String]) -> Self {
+        let from_env = |names: &[&str]| {
+            names
+                .iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|value| !value.trim().is_empty())
+        };
+        Self {
+            target: target.display().to_string(),
+            env: env.map(str::to_string),
+            commit: from_env(&["GIT_COMMIT", "GITHUB_SHA"])
+                .or_else(|| command_output("git", &["rev-parse", "HEAD"])),
+            rivet_version: env!("CARGO_PKG_VERSION").to_string(),
+            hostname: from_env(&["HOSTNAME", "COMPUTERNAME"])
+                .or_else(|| command_output("hostname", &[])),
+            args: mask_args(args),
+        }
+    }
+
+    /// The commit as `git log --oneline` shows it.
+    fn short_commit(&self) -> Option<&str> {
+        self.commit
+            .as_deref()
+            .map(|commit| commit.get(..7).unwrap_or(commit))
+    }
+
+    /// One line for report headers: target, env, commit, host and version.
+    /// The env reads "per suite" when `suites` ran in different ones.
+    fn summary_line(&self, suites: &[TestSuiteReport]) -> String {
+        let mut parts = vec![self.target.clone()];
+        let env = match &self.env {
+            Some(env) => Some(env.as_str()),
+            None if suites.iter().any(|suite| suite.env.is_some()) => Some("per suite"),
+            None => None,
+        };
+        parts.extend(env.map(|env| format!("env {}", env)));
+        parts.extend(
+            self.short_commit()
+                .map(|commit| format!("commit {}", commit)),
+        );
+        parts.extend(self.hostname.clone());
+        parts.push(format!("rivet {}", self.rivet_version));
+        parts.join(" • ")
+    }
+
+    /// `name`/`value` pairs, as JUnit `<property>` elements list them, for
+    /// a suite that ran in `suite_env`.
+    fn properties(&self, suite_env: Option<&str>) -> Vec<(&'static str, String)> {
+        let mut properties = vec![("target", self.target.clone())];
+        let env = suite_env.or(self.env.as_deref());
+        properties.extend(env.map(|env| ("env", env.to_string())));
+        properties.extend(self.commit.clone().map(|commit| ("commit", commit)));
+        properties.push(("rivet_version", self.rivet_version.clone()));
+        properties.extend(self.hostname.clone().map(|host| ("hostname", host)));
+        properties.push(("args", self.args.join(" ")));
+        properties
+    }
+}
+
+/// Trimmed stdout of `program`, when it runs and succeeds.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn mask_args(args: &[String]) -> Vec<String> {
+    let is_masked = |flag: &str| MASKED_FLAGS.contains(&flag) || HEADER_FLAGS.contains(&flag);
+    let mut masked = Vec::with_capacity(args.len());
+    let mut value_of: Option<&str> = None;
+    for arg in args {
+        if let Some(flag) = value_of.take() {
+            masked.push(mask_value(flag, arg));
+        } else if is_masked(arg) {
+            value_of = Some(arg);
+            masked.push(arg.clone());
+        } else if let Some((flag, value)) = arg.split_once('=').filter(|(flag, _)| is_masked(flag))
+        {
+            masked.push(format!("{}={}", flag, mask_value(flag, value)));
+        } else if let Some(header) = arg.strip_prefix("-H").filter(|h| !h.is_empty()) {
+            masked.push(format!("-H{}", mask_header(header)));
+        } else {
+            masked.push(arg.clone());
+        }
+    }
+    masked
+}
+
+fn mask_value(flag: &str, value: &str) -> String {
+    if HEADER_FLAGS.contains(&flag) {
+        mask_header(value)
+    } else {
+        REDACTED.to_string()
+    }
+}
+
+/// A `Name: value` header, with the value masked when it's a credential.
+fn mask_header(header: &str) -> String {
+    match header.split_once(':') {
+        Some((name, _)) if is_secret_header(name) => format!("{}: {}", name.trim(), REDACTED),
+        _ => header.to_string(),
+    }
+}
+
+/// Placeholders `--report-name` may use.
+const REPORT_NAME_PLACEHOLDERS: [&str; 5] = ["env", "date", "time", "commit", "run_id"];
+
+fn placeholder_regex() -> &'static Regex {
+    static REGEX: std::sync::OnceLock<Regex> = std::sync::OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\{(\w+)\}").unwrap())
+}
+
+/// Fail on placeholders in a report name that [`expand_report_name`] doesn't know.
+pub fn check_report_name(name: &str) -> Result<()> {
+    for caps in placeholder_regex().captures_iter(name) {
+        if !REPORT_NAME_PLACEHOLDERS.contains(&&caps[1]) {
+            anyhow::bail!(
+                "Unknown placeholder {{{}}} in report name; use {}",
+                &caps[1],
+                REPORT_NAME_PLACEHOLDERS
+                    .iter()
+                    .map(|placeholder| format!("{{{}}}", placeholder))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `name` with `{env}` (`default` when unset), `{date}` (`2025-01-31`),
+/// `{time}` (`120000`), `{commit}` (short) and `{run_id}` filled in from `report`.
+pub fn expand_report_name(name: &str, report: &TestReport) -> Result<String> {
+    check_report_name(name)?;
+    let metadata = report.metadata.as_ref();
+    let expanded = placeholder_regex().replace_all(name, |caps: &regex::Captures| {
+        let value = match &caps[1] {
+            "env" => metadata
+                .and_then(|metadata| metadata.env.clone())
+                .unwrap_or_else(|| "default".to_string()),
+            "date" => report.timestamp.format("%Y-%m-%d").to_string(),
+            "time" => report.timestamp.format("%H%M%S").to_string(),
+            "commit" => metadata
+                .and_then(ReportMetadata::short_commit)
+                .unwrap_or("unknown")
+                .to_string(),
+            _ => report
+                .run_id
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+        };
+        // Keep each value to one path segment
+        value.replace(['/', '\\'], "-")
+    });
+    Ok(expanded.into_owned())
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TestSummary {
     pub total: usize,
@@ -25,6 +230,9 @@ pub struct TestSummary {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TestSuiteReport {
     pub name: String,
+    /// The environment the suite ran in: `--env`, else its own `env`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
     pub duration: Duration,
     pub summary: TestSummary,
     pub tests: Vec<TestResult>,
@@ -36,7 +244,40 @@ pub struct TestResult {
     pub status: TestStatus,
     pub duration: Duration,
     pub error: Option<String>,
+    /// Why a failed test failed; see [`TestResult::failure_kind`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<FailureKind>,
     pub response_status: Option<u16>,
+    #[serde(default)]
+    pub request_method: Option<String>,
+    #[serde(default)]
+    pub request_url: Option<String>,
+    /// Size of the whole response body, which may be more than was captured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_size: Option<u64>,
+    /// Where redirects led, when that differs from `request_url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_headers: Option<HashMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_body: Option<String>,
+    /// Attempts and wait of the step's `expect.poll`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll: Option<PollSummary>,
+}
+
+impl TestResult {
+    /// Why the test failed, `Unknown` for failures in reports written
+    /// before kinds were recorded; `None` unless it failed.
+    pub fn failure_kind(&self) -> Option<FailureKind> {
+        match self.status {
+            TestStatus::Failed => Some(self.failure_kind.unwrap_or(FailureKind::Unknown)),
+            TestStatus::Passed | TestStatus::Skipped => None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,46 +287,309 @@ pub enum TestStatus {
     Skipped,
 }
 
+/// Which tests get request/response bodies embedded in the report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyInclusion {
+    None,
+    Failures,
+    All,
+}
+
+impl std::str::FromStr for BodyInclusion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(BodyInclusion::None),
+            "failures" | "failed" => Ok(BodyInclusion::Failures),
+            "all" => Ok(BodyInclusion::All),
+            _ => anyhow::bail!("Invalid body inclusion '{}'. Use: none, failures, all", s),
+        }
+    }
+}
+
+// Bodies larger than this are truncated in reports to keep file sizes reasonable
+const MAX_REPORT_BODY_BYTES: usize = 64 * 1024;
+
+fn truncate_body(body: &str) -> String {
+    if body.len() <= MAX_REPORT_BODY_BYTES {
+        return body.to_string();
+    }
+
+    let mut end = MAX_REPORT_BODY_BYTES;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}\n... [truncated: showing {} of {} bytes]",
+        &body[..end],
+        end,
+        body.len()
+    )
+}
+
+/// Per-run figures shown in the HTML report's trend section.
+#[derive(Debug, Serialize)]
+struct TrendPoint {
+    timestamp: String,
+    total: usize,
+    failed: usize,
+    success_rate: String,
+    duration_secs: String,
+}
+
 pub struct ReportGenerator;
 
+/// Formats `write_reports` knows how to write
+const REPORT_FORMATS: &[&str] = &["json", "html", "junit", "markdown", "md", "csv"];
+
 impl ReportGenerator {
+    /// Entries of a comma-separated `formats` list that no report is written for.
+    pub fn unknown_formats(formats: &str) -> Vec<String> {
+        formats
+            .split(',')
+            .map(|format| format.trim().to_lowercase())
+            .filter(|format| !REPORT_FORMATS.contains(&format.as_str()))
+            .collect()
+    }
+
+    /// Write `report` (see [`Self::build_report`]) in each of `formats`,
+    /// with trends over the last `history_limit` runs in `output_dir`.
     pub fn generate_reports(
-        results: &[TestSuiteResult],
+        report: &TestReport,
         formats: &str,
         output_dir: &Path,
         template: &str,
+        report_name: Option<&str>,
+        history_limit: usize,
+    ) -> Result<Vec<PathBuf>> {
+        // Read previous runs before writing, so a stable report name doesn't clobber the last one first
+        let history = if history_limit > 0 {
+            Self::load_history(output_dir, history_limit, report.timestamp)
+        } else {
+            Vec::new()
+        };
+        Self::write_reports(report, formats, output_dir, template, report_name, &history)
+    }
+
+    /// Write an already-built report in each of the requested formats.
+    /// Unknown formats are skipped; see [`Self::unknown_formats`].
+    pub fn write_reports(
+        report: &TestReport,
+        formats: &str,
+        output_dir: &Path,
+        template: &str,
+        report_name: Option<&str>,
+        history: &[TestReport],
     ) -> Result<Vec<PathBuf>> {
         // Ensure output directory exists
         fs::create_dir_all(output_dir)?;
 
-        let report = Self::build_report(results);
         let mut generated_files = Vec::new();
 
         for format in formats.split(',') {
             let format = format.trim().to_lowercase();
             match format.as_str() {
                 "json" => {
-                    let path = Self::generate_json_report(&report, output_dir)?;
+                    let path = Self::generate_json_report(report, output_dir, report_name)?;
                     generated_files.push(path);
                 }
                 "html" => {
-                    let path = Self::generate_html_report(&report, output_dir, template)?;
+                    let path = Self::generate_html_report(
+                        report,
+                        output_dir,
+                        template,
+                        report_name,
+                        history,
+                    )?;
                     generated_files.push(path);
                 }
                 "junit" => {
-                    let path = Self::generate_junit_report(&report, output_dir)?;
+                    let path = Self::generate_junit_report(report, output_dir, report_name)?;
+                    generated_files.push(path);
+                }
+                "markdown" | "md" => {
+                    let path = Self::generate_markdown_report(report, output_dir, report_name)?;
                     generated_files.push(path);
                 }
-                _ => {
-                    eprintln!("Warning: Unknown report format '{}'", format);
+                "csv" => {
+                    let paths = Self::generate_csv_report(report, output_dir, report_name)?;
+                    generated_files.extend(paths);
                 }
+                _ => {}
             }
         }
 
         Ok(generated_files)
     }
 
-    fn build_report(results: &[TestSuiteResult]) -> TestReport {
+    /// Combine reports from several runs (e.g. CI shards) into one.
+    ///
+    /// Suites are concatenated in input order; a suite name that appears in more
+    /// than one source is suffixed with its source name to keep them apart.
+    pub fn merge_reports(reports: Vec<(String, TestReport)>) -> Result<TestReport> {
+        if reports.is_empty() {
+            anyhow::bail!("No reports to merge");
+        }
+
+        let mut name_sources: HashMap<String, HashSet<String>> = HashMap::new();
+        for (source, report) in &reports {
+            for suite in &report.suites {
+                name_sources
+                    .entry(suite.name.clone())
+                    .or_default()
+                    .insert(source.clone());
+            }
+        }
+
+        let timestamp = reports
+            .iter()
+            .map(|(_, report)| report.timestamp)
+            .min()
+            .unwrap_or_else(Utc::now);
+        let run_ids: HashSet<_> = reports.iter().map(|(_, report)| &report.run_id).collect();
+        let run_id = match run_ids.into_iter().collect::<Vec<_>>()[..] {
+            [run_id] => run_id.clone(),
+            _ => None,
+        };
+        let metadata: HashSet<_> = reports.iter().map(|(_, report)| &report.metadata).collect();
+        let metadata = match metadata.into_iter().collect::<Vec<_>>()[..] {
+            [metadata] => metadata.clone(),
+            _ => None,
+        };
+
+        let mut suites = Vec::new();
+        for (source, report) in reports {
+            for mut suite in report.suites {
+                if name_sources.get(&suite.name).is_some_and(|s| s.len() > 1) {
+                    suite.name = format!("{} ({})", suite.name, source);
+                }
+                suites.push(suite);
+            }
+        }
+
+        let total: usize = suites.iter().map(|s| s.summary.total).sum();
+        let passed: usize = suites.iter().map(|s| s.summary.passed).sum();
+        let failed: usize = suites.iter().map(|s| s.summary.failed).sum();
+        let duration: Duration = suites.iter().map(|s| s.duration).sum();
+
+        Ok(TestReport {
+            timestamp,
+            run_id,
+            metadata,
+            summary: TestSummary {
+                total,
+                passed,
+                failed,
+                duration,
+                success_rate: if total > 0 {
+                    (passed as f64 / total as f64) * 100.0
+                } else {
+                    0.0
+                },
+            },
+            suites,
+        })
+    }
+
+    /// Load up to `limit` earlier JSON reports from `dir`, oldest first.
+    ///
+    /// Files that aren't run reports (coverage output, corrupt files) are skipped,
+    /// as is anything not strictly older than `before`.
+    pub fn load_history(dir: &Path, limit: usize, before: DateTime<Utc>) -> Vec<TestReport> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        let mut reports: Vec<TestReport> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .filter_map(|content| serde_json::from_str::<TestReport>(&content).ok())
+            .filter(|report| report.timestamp < before)
+            .collect();
+
+        reports.sort_by_key(|report| report.timestamp);
+        let skip = reports.len().saturating_sub(limit);
+        reports.split_off(skip)
+    }
+
+    /// Tests that passed in `previous` but fail in `current`, as `(suite, test)` pairs.
+    pub fn find_regressions(previous: &TestReport, current: &TestReport) -> Vec<(String, String)> {
+        let passed_before: HashSet<(&str, &str)> = previous
+            .suites
+            .iter()
+            .flat_map(|suite| {
+                suite
+                    .tests
+                    .iter()
+                    .filter(|test| matches!(test.status, TestStatus::Passed))
+                    .map(move |test| (suite.name.as_str(), test.name.as_str()))
+            })
+            .collect();
+
+        current
+            .suites
+            .iter()
+            .flat_map(|suite| {
+                suite
+                    .tests
+                    .iter()
+                    .filter(|test| matches!(test.status, TestStatus::Failed))
+                    .map(move |test| (suite.name.as_str(), test.name.as_str()))
+            })
+            .filter(|key| passed_before.contains(key))
+            .map(|(suite, test)| (suite.to_string(), test.to_string()))
+            .collect()
+    }
+
+    /// One trend point per historical run followed by the current run.
+    fn trend_points(history: &[TestReport], current: &TestReport) -> Vec<TrendPoint> {
+        history
+            .iter()
+            .chain(std::iter::once(current))
+            .map(|report| TrendPoint {
+                timestamp: report.timestamp.format("%Y-%m-%d %H:%M").to_string(),
+                total: report.summary.total,
+                failed: report.summary.failed,
+                success_rate: format!("{:.1}", report.summary.success_rate),
+                duration_secs: format!("{:.2}", report.summary.duration.as_secs_f64()),
+            })
+            .collect()
+    }
+
+    /// `<name>.<ext>` when a report name is given (see [`expand_report_name`]),
+    /// otherwise `<prefix>_<timestamp>_<run_id>.<ext>`.
+    fn report_path(
+        report: &TestReport,
+        output_dir: &Path,
+        prefix: &str,
+        ext: &str,
+        report_name: Option<&str>,
+    ) -> Result<PathBuf> {
+        let filename = match report_name {
+            Some(name) => format!("{}.{}", expand_report_name(name, report)?, ext),
+            None => {
+                let run_suffix = report
+                    .run_id
+                    .as_ref()
+                    .map(|run_id| format!("_{}", run_id))
+                    .unwrap_or_default();
+                format!(
+                    "{}_{}{}.{}",
+                    prefix,
+                    report.timestamp.format("%Y%m%d_%H%M%S"),
+                    run_suffix,
+                    ext
+                )
+            }
+        };
+        Ok(output_dir.join(filename))
+    }
+
+    /// Gather a run's results into a report, without metadata.
+    pub fn build_report(results: &[TestSuiteResult], include_bodies: BodyInclusion) -> TestReport {
         let mut suites = Vec::new();
         let mut total_tests = 0;
         let mut total_passed = 0;
@@ -96,16 +600,46 @@ impl ReportGenerator {
             let suite_tests: Vec<TestResult> = suite_result
                 .results
                 .iter()
-                .map(|test| TestResult {
-                    name: test.name.clone(),
-                    status: if test.passed {
-                        TestStatus::Passed
-                    } else {
-                        TestStatus::Failed
-                    },
-                    duration: test.duration,
-                    error: test.error.clone(),
-                    response_status: test.response_status,
+                .map(|test| {
+                    let with_bodies = match include_bodies {
+                        BodyInclusion::None => false,
+                        BodyInclusion::Failures => !test.passed && !test.skipped,
+                        BodyInclusion::All => true,
+                    };
+
+                    TestResult {
+                        name: test.name.clone(),
+                        status: if test.skipped {
+                            TestStatus::Skipped
+                        } else if test.passed {
+                            TestStatus::Passed
+                        } else {
+                            TestStatus::Failed
+                        },
+                        duration: test.duration,
+                        error: test.error.clone(),
+                        failure_kind: test.failure_kind,
+                        response_status: test.response_status,
+                        request_method: test.request_method.clone(),
+                        request_url: test.request_url.clone(),
+                        response_size: test.response_size,
+                        poll: test.poll,
+                        final_url: test
+                            .final_url
+                            .clone()
+                            .filter(|url| Some(url) != test.request_url.as_ref()),
+                        request_body: test
+                            .request_body
+                            .as_deref()
+                            .filter(|_| with_bodies)
+                            .map(truncate_body),
+                        response_headers: test.response_headers.clone().filter(|_| with_bodies),
+                        response_body: test
+                            .response_body
+                            .as_deref()
+                            .filter(|_| with_bodies)
+                            .map(truncate_body),
+                    }
                 })
                 .collect();
 
@@ -125,6 +659,7 @@ impl ReportGenerator {
 
             suites.push(TestSuiteReport {
                 name: suite_result.name.clone(),
+                env: suite_result.env.clone(),
                 duration: suite_result.duration,
                 summary: suite_summary,
                 tests: suite_tests,
@@ -138,6 +673,8 @@ impl ReportGenerator {
 
         TestReport {
             timestamp: Utc::now(),
+            run_id: results.first().map(|suite| suite.run_id.clone()),
+            metadata: None,
             summary: TestSummary {
                 total: total_tests,
                 passed: total_passed,
@@ -153,10 +690,12 @@ impl ReportGenerator {
         }
     }
 
-    fn generate_json_report(report: &TestReport, output_dir: &Path) -> Result<PathBuf> {
-        let timestamp = report.timestamp.format("%Y%m%d_%H%M%S");
-        let filename = format!("rivet_report_{}.json", timestamp);
-        let path = output_dir.join(&filename);
+    fn generate_json_report(
+        report: &TestReport,
+        output_dir: &Path,
+        report_name: Option<&str>,
+    ) -> Result<PathBuf> {
+        let path = Self::report_path(report, output_dir, "rivet_report", "json", report_name)?;
 
         let json = serde_json::to_string_pretty(report)?;
         fs::write(&path, json)?;
@@ -168,12 +707,12 @@ impl ReportGenerator {
         report: &TestReport,
         output_dir: &Path,
         template_name: &str,
+        report_name: Option<&str>,
+        history: &[TestReport],
     ) -> Result<PathBuf> {
         use tera::{Context, Tera};
 
-        let timestamp = report.timestamp.format("%Y%m%d_%H%M%S");
-        let filename = format!("rivet_report_{}.html", timestamp);
-        let path = output_dir.join(&filename);
+        let path = Self::report_path(report, output_dir, "rivet_report", "html", report_name)?;
 
         // Create a minimal Tera instance with our HTML template
         let mut tera = Tera::default();
@@ -198,22 +737,60 @@ impl ReportGenerator {
         );
 
         // Add duration as seconds for template use
+        context.insert(
+            "run_context",
+            &report
+                .metadata
+                .as_ref()
+                .map(|metadata| metadata.summary_line(&report.suites)),
+        );
         context.insert(
             "total_duration_secs",
             &format!("{:.2}", report.summary.duration.as_secs_f64()),
         );
 
+        // Trend across previous runs; regressions are relative to the most recent one
+        let regressions: HashSet<(String, String)> = history
+            .last()
+            .map(|previous| Self::find_regressions(previous, report))
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        let trend = if history.is_empty() {
+            Vec::new()
+        } else {
+            Self::trend_points(history, report)
+        };
+        context.insert("trend", &trend);
+        context.insert("regression_count", &regressions.len());
+
         // Pre-process suite and test data for template
         let mut enhanced_suites = Vec::new();
         for suite in &report.suites {
             let mut enhanced_tests = Vec::new();
             for test in &suite.tests {
+                // Sort headers so the rendered panel is stable between runs
+                let response_headers = test.response_headers.as_ref().map(|headers| {
+                    let mut sorted: Vec<_> = headers.iter().collect();
+                    sorted.sort();
+                    sorted
+                        .into_iter()
+                        .map(|(name, value)| format!("{}: {}", name, value))
+                        .collect::<Vec<_>>()
+                });
                 enhanced_tests.push(serde_json::json!({
                     "name": test.name,
                     "status": test.status,
                     "duration_ms": (test.duration.as_secs_f64() * 1000.0) as u64,
                     "error": test.error,
-                    "response_status": test.response_status
+                    "failure_kind": test.failure_kind().map(|kind| kind.label()),
+                    "response_status": test.response_status,
+                    "request_method": test.request_method,
+                    "request_url": test.request_url,
+                    "request_body": test.request_body.as_deref().map(pretty_body),
+                    "response_headers": response_headers,
+                    "response_body": test.response_body.as_deref().map(pretty_body),
+                    "regression": regressions.contains(&(suite.name.clone(), test.name.clone()))
                 }));
             }
             enhanced_suites.push(serde_json::json!({
@@ -235,10 +812,12 @@ impl ReportGenerator {
         Ok(path)
     }
 
-    fn generate_junit_report(report: &TestReport, output_dir: &Path) -> Result<PathBuf> {
-        let timestamp = report.timestamp.format("%Y%m%d_%H%M%S");
-        let filename = format!("rivet_junit_{}.xml", timestamp);
-        let path = output_dir.join(&filename);
+    fn generate_junit_report(
+        report: &TestReport,
+        output_dir: &Path,
+        report_name: Option<&str>,
+    ) -> Result<PathBuf> {
+        let path = Self::report_path(report, output_dir, "rivet_junit", "xml", report_name)?;
 
         let mut xml = String::new();
         xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
@@ -257,6 +836,17 @@ impl ReportGenerator {
                 suite.summary.failed,
                 suite.duration.as_secs_f64()
             ));
+            if let Some(metadata) = &report.metadata {
+                xml.push_str("    <properties>\n");
+                for (name, value) in metadata.properties(suite.env.as_deref()) {
+                    xml.push_str(&format!(
+                        "      <property name=\"{}\" value=\"{}\"/>\n",
+                        name,
+                        escape_xml_attribute(&value)
+                    ));
+                }
+                xml.push_str("    </properties>\n");
+            }
 
             for test in &suite.tests {
                 xml.push_str(&format!(
@@ -269,8 +859,9 @@ impl ReportGenerator {
                     TestStatus::Failed => {
                         xml.push_str(">\n");
                         xml.push_str(&format!(
-                            "      <failure message=\"{}\">{}</failure>\n",
+                            "      <failure message=\"{}\" type=\"{:?}\">{}</failure>\n",
                             test.error.as_deref().unwrap_or("Test failed"),
+                            test.failure_kind().unwrap_or(FailureKind::Unknown),
                             test.error.as_deref().unwrap_or("Test failed")
                         ));
                         xml.push_str("    </testcase>\n");
@@ -295,6 +886,219 @@ impl ReportGenerator {
 
         Ok(path)
     }
+
+    fn generate_markdown_report(
+        report: &TestReport,
+        output_dir: &Path,
+        report_name: Option<&str>,
+    ) -> Result<PathBuf> {
+        let path = Self::report_path(report, output_dir, "rivet_report", "md", report_name)?;
+
+        let markdown = Self::render_markdown(report);
+        fs::write(&path, &markdown)?;
+
+        // GitHub Actions renders anything appended to this file on the run summary page
+        if let Ok(summary_path) = std::env::var("GITHUB_STEP_SUMMARY") {
+            if !summary_path.is_empty() {
+                Self::append_step_summary(Path::new(&summary_path), &markdown)?;
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Writes one row per test plus a second file with per-suite aggregates.
+    fn generate_csv_report(
+        report: &TestReport,
+        output_dir: &Path,
+        report_name: Option<&str>,
+    ) -> Result<Vec<PathBuf>> {
+        let tests_path = Self::report_path(report, output_dir, "rivet_report", "csv", report_name)?;
+        let summary_name = report_name.map(|name| format!("{}_summary", name));
+        let summary_path = Self::report_path(
+            report,
+            output_dir,
+            "rivet_summary",
+            "csv",
+            summary_name.as_deref(),
+        )?;
+
+        fs::write(&tests_path, Self::render_tests_csv(report)?)?;
+        fs::write(&summary_path, Self::render_summary_csv(report)?)?;
+
+        Ok(vec![tests_path, summary_path])
+    }
+
+    fn render_tests_csv(report: &TestReport) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record([
+            "suite",
+            "test",
+            "status",
+            "duration_ms",
+            "response_status",
+            "error",
+        ])?;
+
+        for suite in &report.suites {
+            for test in &suite.tests {
+                let status = match test.status {
+                    TestStatus::Passed => "passed",
+                    TestStatus::Failed => "failed",
+                    TestStatus::Skipped => "skipped",
+                };
+                writer.write_record([
+                    suite.name.as_str(),
+                    test.name.as_str(),
+                    status,
+                    &test.duration.as_millis().to_string(),
+                    &test
+                        .response_status
+                        .map(|code| code.to_string())
+                        .unwrap_or_default(),
+                    test.error.as_deref().unwrap_or(""),
+                ])?;
+            }
+        }
+
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+
+    fn render_summary_csv(report: &TestReport) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record([
+            "suite",
+            "total",
+            "passed",
+            "failed",
+            "success_rate",
+            "duration_ms",
+        ])?;
+
+        for suite in &report.suites {
+            writer.write_record([
+                suite.name.clone(),
+                suite.summary.total.to_string(),
+                suite.summary.passed.to_string(),
+                suite.summary.failed.to_string(),
+                format!("{:.1}", suite.summary.success_rate),
+                suite.duration.as_millis().to_string(),
+            ])?;
+        }
+
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+
+    fn render_markdown(report: &TestReport) -> String {
+        let mut md = String::new();
+
+        let icon = if report.summary.failed == 0 {
+            "✅"
+        } else {
+            "❌"
+        };
+        md.push_str(&format!("## {} Rivet Test Report\n\n", icon));
+        let run_note = report
+            .run_id
+            .as_ref()
+            .map(|run_id| format!(" • run {}", run_id))
+            .unwrap_or_default();
+        md.push_str(&format!(
+            "_{}{}_\n\n",
+            report.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+            run_note
+        ));
+
+        md.push_str("| Total | Passed | Failed | Duration | Success Rate |\n");
+        md.push_str("|------:|-------:|-------:|---------:|-------------:|\n");
+        md.push_str(&format!(
+            "| {} | {} | {} | {:.2}s | {:.1}% |\n\n",
+            report.summary.total,
+            report.summary.passed,
+            report.summary.failed,
+            report.summary.duration.as_secs_f64(),
+            report.summary.success_rate
+        ));
+
+        for suite in &report.suites {
+            md.push_str(&format!("### {}\n\n", escape_markdown_cell(&suite.name)));
+            md.push_str("| Status | Test | Duration | HTTP |\n");
+            md.push_str("|:------:|------|---------:|-----:|\n");
+
+            for test in &suite.tests {
+                let status = match test.status {
+                    TestStatus::Passed => "✅",
+                    TestStatus::Failed => "❌",
+                    TestStatus::Skipped => "⏭️",
+                };
+                md.push_str(&format!(
+                    "| {} | {} | {}ms | {} |\n",
+                    status,
+                    escape_markdown_cell(&test.name),
+                    test.duration.as_millis(),
+                    test.response_status
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| "-".to_string())
+                ));
+            }
+            md.push('\n');
+
+            for test in &suite.tests {
+                if let (TestStatus::Failed, Some(error)) = (&test.status, &test.error) {
+                    let fence = code_fence(error);
+                    md.push_str(&format!(
+                        "<details>\n<summary>❌ {}</summary>\n\n{}\n{}\n{}\n\n</details>\n\n",
+                        escape_html(&test.name),
+                        fence,
+                        error,
+                        fence
+                    ));
+                }
+            }
+        }
+
+        md
+    }
+
+    fn append_step_summary(path: &Path, markdown: &str) -> Result<()> {
+        use std::io::Write;
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(file, "{}", markdown)?;
+
+        Ok(())
+    }
+}
+
+fn pretty_body(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| serde_json::to_string_pretty(&json).ok())
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// A backtick fence longer than any backtick run in `text`, so a body with
+/// its own ``` can't close the block early.
+fn code_fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn escape_markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+fn escape_xml_attribute(text: &str) -> String {
+    escape_html(text).replace('"', "&quot;")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
 }
 
 #[cfg(test)]
@@ -307,30 +1111,80 @@ mod tests {
     fn create_sample_test_suite_result() -> TestSuiteResult {
         TestSuiteResult {
             name: "API Test Suite".to_string(),
+            run_id: "3f9a0c12".to_string(),
+            env: None,
             results: vec![
                 ExecutorTestResult {
                     name: "Test GET users".to_string(),
                     passed: true,
+                    skipped: false,
                     duration: Duration::from_millis(150),
                     error: None,
+                    error_diff: None,
                     response_status: Some(200),
                     response_body: Some(r#"{"users": []}"#.to_string()),
+                    response_size: None,
+                    final_url: None,
+                    request_method: Some("GET".to_string()),
+                    request_url: Some("https://api.example.com/users".to_string()),
+                    request_body: None,
+                    response_headers: None,
+                    http_version: None,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    failure_kind: None,
+                    captures: HashMap::new(),
+                    poll: None,
+                    warnings: Vec::new(),
                 },
                 ExecutorTestResult {
                     name: "Test POST user".to_string(),
                     passed: false,
+                    skipped: false,
                     duration: Duration::from_millis(300),
                     error: Some("Status code mismatch: expected 201, got 400".to_string()),
+                    error_diff: None,
                     response_status: Some(400),
                     response_body: Some(r#"{"error": "Invalid data"}"#.to_string()),
+                    response_size: None,
+                    final_url: None,
+                    request_method: None,
+                    request_url: None,
+                    request_body: Some(r#"{"name": ""}"#.to_string()),
+                    response_headers: Some(HashMap::from([(
+                        "content-type".to_string(),
+                        "application/json".to_string(),
+                    )])),
+                    http_version: None,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    failure_kind: Some(FailureKind::HttpStatus),
+                    captures: HashMap::new(),
+                    poll: None,
+                    warnings: Vec::new(),
                 },
                 ExecutorTestResult {
                     name: "Test DELETE user".to_string(),
                     passed: true,
+                    skipped: false,
                     duration: Duration::from_millis(100),
                     error: None,
+                    error_diff: None,
                     response_status: Some(204),
                     response_body: None,
+                    response_size: None,
+                    final_url: None,
+                    request_method: None,
+                    request_url: None,
+                    request_body: None,
+                    response_headers: None,
+                    http_version: None,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    failure_kind: None,
+                    captures: HashMap::new(),
+                    poll: None,
+                    warnings: Vec::new(),
                 },
             ],
             duration: Duration::from_millis(550),
@@ -342,7 +1196,7 @@ mod tests {
     #[test]
     fn test_build_report() {
         let suite_results = vec![create_sample_test_suite_result()];
-        let report = ReportGenerator::build_report(&suite_results);
+        let report = ReportGenerator::build_report(&suite_results, BodyInclusion::Failures);
 
         // Check overall summary
         assert_eq!(report.summary.total, 3);
@@ -374,7 +1228,7 @@ mod tests {
     #[test]
     fn test_build_report_empty() {
         let suite_results = vec![];
-        let report = ReportGenerator::build_report(&suite_results);
+        let report = ReportGenerator::build_report(&suite_results, BodyInclusion::Failures);
 
         assert_eq!(report.summary.total, 0);
         assert_eq!(report.summary.passed, 0);
@@ -387,22 +1241,54 @@ mod tests {
     fn test_build_report_all_passed() {
         let suite_result = TestSuiteResult {
             name: "All Pass Suite".to_string(),
+            run_id: "3f9a0c12".to_string(),
+            env: None,
             results: vec![
                 ExecutorTestResult {
                     name: "Test 1".to_string(),
                     passed: true,
+                    skipped: false,
                     duration: Duration::from_millis(100),
                     error: None,
+                    error_diff: None,
                     response_status: Some(200),
                     response_body: None,
+                    response_size: None,
+                    final_url: None,
+                    request_method: None,
+                    request_url: None,
+                    request_body: None,
+                    response_headers: None,
+                    http_version: None,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    failure_kind: None,
+                    captures: HashMap::new(),
+                    poll: None,
+                    warnings: Vec::new(),
                 },
                 ExecutorTestResult {
                     name: "Test 2".to_string(),
                     passed: true,
+                    skipped: false,
                     duration: Duration::from_millis(200),
                     error: None,
+                    error_diff: None,
                     response_status: Some(200),
                     response_body: None,
+                    response_size: None,
+                    final_url: None,
+                    request_method: None,
+                    request_url: None,
+                    request_body: None,
+                    response_headers: None,
+                    http_version: None,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    failure_kind: None,
+                    captures: HashMap::new(),
+                    poll: None,
+                    warnings: Vec::new(),
                 },
             ],
             duration: Duration::from_millis(300),
@@ -410,7 +1296,7 @@ mod tests {
             failed: 0,
         };
 
-        let report = ReportGenerator::build_report(&[suite_result]);
+        let report = ReportGenerator::build_report(&[suite_result], BodyInclusion::Failures);
         assert_eq!(report.summary.success_rate, 100.0);
     }
 
@@ -418,22 +1304,54 @@ mod tests {
     fn test_build_report_all_failed() {
         let suite_result = TestSuiteResult {
             name: "All Fail Suite".to_string(),
+            run_id: "3f9a0c12".to_string(),
+            env: None,
             results: vec![
                 ExecutorTestResult {
                     name: "Test 1".to_string(),
                     passed: false,
+                    skipped: false,
                     duration: Duration::from_millis(100),
                     error: Some("Error 1".to_string()),
+                    error_diff: None,
                     response_status: Some(500),
                     response_body: None,
+                    response_size: None,
+                    final_url: None,
+                    request_method: None,
+                    request_url: None,
+                    request_body: None,
+                    response_headers: None,
+                    http_version: None,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    failure_kind: None,
+                    captures: HashMap::new(),
+                    poll: None,
+                    warnings: Vec::new(),
                 },
                 ExecutorTestResult {
                     name: "Test 2".to_string(),
                     passed: false,
+                    skipped: false,
                     duration: Duration::from_millis(200),
                     error: Some("Error 2".to_string()),
+                    error_diff: None,
                     response_status: Some(404),
                     response_body: None,
+                    response_size: None,
+                    final_url: None,
+                    request_method: None,
+                    request_url: None,
+                    request_body: None,
+                    response_headers: None,
+                    http_version: None,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    failure_kind: None,
+                    captures: HashMap::new(),
+                    poll: None,
+                    warnings: Vec::new(),
                 },
             ],
             duration: Duration::from_millis(300),
@@ -441,7 +1359,7 @@ mod tests {
             failed: 2,
         };
 
-        let report = ReportGenerator::build_report(&[suite_result]);
+        let report = ReportGenerator::build_report(&[suite_result], BodyInclusion::Failures);
         assert_eq!(report.summary.success_rate, 0.0);
     }
 
@@ -449,13 +1367,30 @@ mod tests {
     fn test_build_report_multiple_suites() {
         let suite1 = TestSuiteResult {
             name: "Suite 1".to_string(),
+            run_id: "3f9a0c12".to_string(),
+            env: None,
             results: vec![ExecutorTestResult {
                 name: "Test 1".to_string(),
                 passed: true,
+                skipped: false,
                 duration: Duration::from_millis(100),
                 error: None,
+                error_diff: None,
                 response_status: Some(200),
                 response_body: None,
+                response_size: None,
+                final_url: None,
+                request_method: None,
+                request_url: None,
+                request_body: None,
+                response_headers: None,
+                http_version: None,
+                bytes_sent: 0,
+                bytes_received: 0,
+                failure_kind: None,
+                captures: HashMap::new(),
+                poll: None,
+                warnings: Vec::new(),
             }],
             duration: Duration::from_millis(100),
             passed: 1,
@@ -464,22 +1399,54 @@ mod tests {
 
         let suite2 = TestSuiteResult {
             name: "Suite 2".to_string(),
+            run_id: "3f9a0c12".to_string(),
+            env: None,
             results: vec![
                 ExecutorTestResult {
                     name: "Test 2".to_string(),
                     passed: true,
+                    skipped: false,
                     duration: Duration::from_millis(150),
                     error: None,
+                    error_diff: None,
                     response_status: Some(200),
                     response_body: None,
+                    response_size: None,
+                    final_url: None,
+                    request_method: None,
+                    request_url: None,
+                    request_body: None,
+                    response_headers: None,
+                    http_version: None,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    failure_kind: None,
+                    captures: HashMap::new(),
+                    poll: None,
+                    warnings: Vec::new(),
                 },
                 ExecutorTestResult {
                     name: "Test 3".to_string(),
                     passed: false,
+                    skipped: false,
                     duration: Duration::from_millis(200),
                     error: Some("Failed".to_string()),
+                    error_diff: None,
                     response_status: Some(500),
                     response_body: None,
+                    response_size: None,
+                    final_url: None,
+                    request_method: None,
+                    request_url: None,
+                    request_body: None,
+                    response_headers: None,
+                    http_version: None,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    failure_kind: None,
+                    captures: HashMap::new(),
+                    poll: None,
+                    warnings: Vec::new(),
                 },
             ],
             duration: Duration::from_millis(350),
@@ -487,7 +1454,7 @@ mod tests {
             failed: 1,
         };
 
-        let report = ReportGenerator::build_report(&[suite1, suite2]);
+        let report = ReportGenerator::build_report(&[suite1, suite2], BodyInclusion::Failures);
 
         // Overall summary should aggregate both suites
         assert_eq!(report.summary.total, 3);
@@ -502,9 +1469,9 @@ mod tests {
     fn test_generate_json_report() {
         let temp_dir = TempDir::new().unwrap();
         let suite_results = vec![create_sample_test_suite_result()];
-        let report = ReportGenerator::build_report(&suite_results);
+        let report = ReportGenerator::build_report(&suite_results, BodyInclusion::Failures);
 
-        let path = ReportGenerator::generate_json_report(&report, temp_dir.path()).unwrap();
+        let path = ReportGenerator::generate_json_report(&report, temp_dir.path(), None).unwrap();
 
         assert!(path.exists());
         assert!(path
@@ -513,22 +1480,32 @@ mod tests {
             .to_str()
             .unwrap()
             .starts_with("rivet_report_"));
-        assert!(path.extension().unwrap() == "json");
+        assert!(path.to_str().unwrap().ends_with("_3f9a0c12.json"));
 
         // Verify the JSON content is valid
         let content = std::fs::read_to_string(&path).unwrap();
         let parsed_report: TestReport = serde_json::from_str(&content).unwrap();
+        assert_eq!(parsed_report.run_id.as_deref(), Some("3f9a0c12"));
         assert_eq!(parsed_report.summary.total, 3);
         assert_eq!(parsed_report.suites.len(), 1);
+        assert_eq!(
+            parsed_report.suites[0].tests[0].request_method.as_deref(),
+            Some("GET")
+        );
+        assert_eq!(
+            parsed_report.suites[0].tests[0].request_url.as_deref(),
+            Some("https://api.example.com/users")
+        );
+        assert!(parsed_report.suites[0].tests[1].request_url.is_none());
     }
 
     #[test]
     fn test_generate_junit_report() {
         let temp_dir = TempDir::new().unwrap();
         let suite_results = vec![create_sample_test_suite_result()];
-        let report = ReportGenerator::build_report(&suite_results);
+        let report = ReportGenerator::build_report(&suite_results, BodyInclusion::Failures);
 
-        let path = ReportGenerator::generate_junit_report(&report, temp_dir.path()).unwrap();
+        let path = ReportGenerator::generate_junit_report(&report, temp_dir.path(), None).unwrap();
 
         assert!(path.exists());
         assert!(path
@@ -548,6 +1525,7 @@ mod tests {
         assert!(content.contains("API Test Suite"));
         assert!(content.contains("Test GET users"));
         assert!(content.contains("<failure"));
+        assert!(content.contains("type=\"HttpStatus\""));
         assert!(content.contains("Status code mismatch"));
     }
 
@@ -555,14 +1533,20 @@ mod tests {
     fn test_generate_html_report_templates() {
         let temp_dir = TempDir::new().unwrap();
         let suite_results = vec![create_sample_test_suite_result()];
-        let report = ReportGenerator::build_report(&suite_results);
+        let report = ReportGenerator::build_report(&suite_results, BodyInclusion::Failures);
 
         // Test different template names
         let templates = vec!["simple", "detailed", "compact", "chatty", "unknown"];
 
         for template in templates {
-            let path =
-                ReportGenerator::generate_html_report(&report, temp_dir.path(), template).unwrap();
+            let path = ReportGenerator::generate_html_report(
+                &report,
+                temp_dir.path(),
+                template,
+                None,
+                &[],
+            )
+            .unwrap();
 
             assert!(path.exists());
             assert!(path
@@ -587,10 +1571,12 @@ mod tests {
         let suite_results = vec![create_sample_test_suite_result()];
 
         let generated_files = ReportGenerator::generate_reports(
-            &suite_results,
+            &ReportGenerator::build_report(&suite_results, BodyInclusion::Failures),
             "json,html,junit",
             temp_dir.path(),
             "compact",
+            None,
+            0,
         )
         .unwrap();
 
@@ -612,6 +1598,102 @@ mod tests {
         assert!(extensions.contains(&"xml".to_string()));
     }
 
+    #[test]
+    fn test_generate_reports_with_report_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let suite_results = vec![create_sample_test_suite_result()];
+
+        let generated_files = ReportGenerator::generate_reports(
+            &ReportGenerator::build_report(&suite_results, BodyInclusion::Failures),
+            "json,html,junit,markdown",
+            temp_dir.path(),
+            "compact",
+            Some("latest"),
+            0,
+        )
+        .unwrap();
+
+        let names: Vec<String> = generated_files
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["latest.json", "latest.html", "latest.xml", "latest.md"]
+        );
+
+        // A second run overwrites the same files instead of adding new ones
+        std::fs::write(temp_dir.path().join("latest.json"), "stale").unwrap();
+        ReportGenerator::generate_reports(
+            &ReportGenerator::build_report(&suite_results, BodyInclusion::Failures),
+            "json",
+            temp_dir.path(),
+            "compact",
+            Some("latest"),
+            0,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(temp_dir.path().join("latest.json")).unwrap();
+        assert!(content.contains("API Test Suite"));
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn test_generate_csv_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut suite = create_sample_test_suite_result();
+        suite.results[1].error = Some("expected 201, got 400\nbody: {\"a\", \"b\"}".to_string());
+        let report = ReportGenerator::build_report(&[suite], BodyInclusion::None);
+
+        let paths = ReportGenerator::generate_csv_report(&report, temp_dir.path(), None).unwrap();
+        assert_eq!(paths.len(), 2);
+        let summary_name = paths[1].file_name().unwrap().to_str().unwrap();
+        assert!(summary_name.starts_with("rivet_summary_"));
+        assert!(summary_name.ends_with(".csv"));
+
+        let content = std::fs::read_to_string(&paths[0]).unwrap();
+        let mut lines = content.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "suite,test,status,duration_ms,response_status,error"
+        );
+        assert_eq!(
+            lines.next().unwrap(),
+            "API Test Suite,Test GET users,passed,150,200,"
+        );
+        // Commas, quotes and newlines in the error are quoted per RFC 4180
+        assert!(content.contains(
+            "API Test Suite,Test POST user,failed,300,400,\"expected 201, got 400\nbody: {\"\"a\"\", \"\"b\"\"}\"\n"
+        ));
+
+        // Round-trips through a CSV reader
+        let mut reader = csv::Reader::from_path(&paths[0]).unwrap();
+        let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(&rows[1][5], "expected 201, got 400\nbody: {\"a\", \"b\"}");
+
+        let summary = std::fs::read_to_string(&paths[1]).unwrap();
+        assert_eq!(
+            summary,
+            "suite,total,passed,failed,success_rate,duration_ms\nAPI Test Suite,3,2,1,66.7,550\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_csv_report_with_report_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let report = ReportGenerator::build_report(
+            &[create_sample_test_suite_result()],
+            BodyInclusion::None,
+        );
+
+        let paths =
+            ReportGenerator::generate_csv_report(&report, temp_dir.path(), Some("latest")).unwrap();
+        assert_eq!(paths[0], temp_dir.path().join("latest.csv"));
+        assert_eq!(paths[1], temp_dir.path().join("latest_summary.csv"));
+    }
+
     #[test]
     fn test_generate_reports_unknown_format() {
         let temp_dir = TempDir::new().unwrap();
@@ -619,14 +1701,354 @@ mod tests {
 
         // This should not panic, just skip unknown formats
         let generated_files = ReportGenerator::generate_reports(
-            &suite_results,
+            &ReportGenerator::build_report(&suite_results, BodyInclusion::Failures),
             "json,unknown_format,html",
             temp_dir.path(),
             "compact",
+            None,
+            0,
         )
         .unwrap();
 
         assert_eq!(generated_files.len(), 2); // Only json and html should be generated
+        assert_eq!(
+            ReportGenerator::unknown_formats("json, Unknown_Format,html,md"),
+            vec!["unknown_format"]
+        );
+    }
+
+    #[test]
+    fn test_render_markdown_mixed_suite() {
+        let mut suite_result = create_sample_test_suite_result();
+        suite_result.results[0].name = "List | filter users".to_string();
+        let report = ReportGenerator::build_report(&[suite_result], BodyInclusion::Failures);
+
+        let md = ReportGenerator::render_markdown(&report);
+        let lines: Vec<&str> = md.lines().collect();
+
+        // Summary table
+        assert!(md.starts_with("## ❌ Rivet Test Report"));
+        assert!(lines.contains(&"| Total | Passed | Failed | Duration | Success Rate |"));
+        assert!(lines.contains(&"| 3 | 2 | 1 | 0.55s | 66.7% |"));
+
+        // Per-suite table with escaped pipes
+        assert!(lines.contains(&"### API Test Suite"));
+        assert!(lines.contains(&"| Status | Test | Duration | HTTP |"));
+        assert!(lines.contains(&"| ✅ | List \\| filter users | 150ms | 200 |"));
+        assert!(lines.contains(&"| ❌ | Test POST user | 300ms | 400 |"));
+        assert!(lines.contains(&"| ✅ | Test DELETE user | 100ms | 204 |"));
+
+        // Failures get a collapsed details block, passes do not
+        assert_eq!(md.matches("<details>").count(), 1);
+        assert!(md.contains("<summary>❌ Test POST user</summary>"));
+        assert!(md.contains("Status code mismatch: expected 201, got 400"));
+        assert!(!md.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_render_markdown_fences_errors_containing_backticks() {
+        let mut suite_result = create_sample_test_suite_result();
+        suite_result.results[1].error =
+            Some("Body mismatch:\n```\n{\"id\": 1}\n```\nthen ````".to_string());
+        let report = ReportGenerator::build_report(&[suite_result], BodyInclusion::Failures);
+
+        let md = ReportGenerator::render_markdown(&report);
+        assert!(md.contains("\n`````\nBody mismatch:\n```\n"));
+        assert!(md.contains("then ````\n`````\n\n</details>"));
+        assert_eq!(code_fence("no backticks"), "```");
+    }
+
+    #[test]
+    fn test_generate_markdown_report_and_step_summary() {
+        let temp_dir = TempDir::new().unwrap();
+        let report = ReportGenerator::build_report(
+            &[create_sample_test_suite_result()],
+            BodyInclusion::Failures,
+        );
+
+        let path =
+            ReportGenerator::generate_markdown_report(&report, temp_dir.path(), None).unwrap();
+        assert!(path.exists());
+        assert!(path.extension().unwrap() == "md");
+
+        let summary_path = temp_dir.path().join("step_summary.md");
+        let markdown = ReportGenerator::render_markdown(&report);
+        ReportGenerator::append_step_summary(&summary_path, &markdown).unwrap();
+        ReportGenerator::append_step_summary(&summary_path, &markdown).unwrap();
+
+        let summary = std::fs::read_to_string(&summary_path).unwrap();
+        assert_eq!(summary.matches("Rivet Test Report").count(), 2);
+    }
+
+    #[test]
+    fn test_build_report_body_inclusion() {
+        let suite_results = vec![create_sample_test_suite_result()];
+
+        let report = ReportGenerator::build_report(&suite_results, BodyInclusion::Failures);
+        let tests = &report.suites[0].tests;
+        assert!(tests[0].response_body.is_none());
+        assert_eq!(
+            tests[1].response_body.as_deref(),
+            Some(r#"{"error": "Invalid data"}"#)
+        );
+        assert_eq!(tests[1].request_body.as_deref(), Some(r#"{"name": ""}"#));
+        assert!(tests[1].response_headers.is_some());
+
+        let report = ReportGenerator::build_report(&suite_results, BodyInclusion::All);
+        assert!(report.suites[0].tests[0].response_body.is_some());
+
+        let report = ReportGenerator::build_report(&suite_results, BodyInclusion::None);
+        assert!(report.suites[0]
+            .tests
+            .iter()
+            .all(|t| t.response_body.is_none() && t.response_headers.is_none()));
+    }
+
+    #[test]
+    fn test_report_body_truncation() {
+        let mut suite_result = create_sample_test_suite_result();
+        suite_result.results[1].response_body = Some("x".repeat(100 * 1024));
+
+        let report = ReportGenerator::build_report(&[suite_result], BodyInclusion::Failures);
+        let body = report.suites[0].tests[1].response_body.as_ref().unwrap();
+        assert!(body.len() < 100 * 1024);
+        assert!(body.ends_with("[truncated: showing 65536 of 102400 bytes]"));
+    }
+
+    #[test]
+    fn test_body_inclusion_from_str() {
+        assert_eq!(
+            "failures".parse::<BodyInclusion>().unwrap(),
+            BodyInclusion::Failures
+        );
+        assert_eq!("ALL".parse::<BodyInclusion>().unwrap(), BodyInclusion::All);
+        assert_eq!(
+            "none".parse::<BodyInclusion>().unwrap(),
+            BodyInclusion::None
+        );
+        assert!("sometimes".parse::<BodyInclusion>().is_err());
+    }
+
+    #[test]
+    fn test_detailed_html_includes_request_response_panel() {
+        let temp_dir = TempDir::new().unwrap();
+        let report = ReportGenerator::build_report(
+            &[create_sample_test_suite_result()],
+            BodyInclusion::Failures,
+        );
+
+        let path =
+            ReportGenerator::generate_html_report(&report, temp_dir.path(), "detailed", None, &[])
+                .unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+
+        // Only the failed test gets a panel
+        assert_eq!(
+            content
+                .matches("<summary>Request / Response</summary>")
+                .count(),
+            1
+        );
+        assert!(content.contains("content-type: application&#x2F;json"));
+        // JSON bodies are pretty-printed (and HTML-escaped by Tera)
+        assert!(content.contains("&quot;error&quot;: &quot;Invalid data&quot;"));
+    }
+
+    #[test]
+    fn test_merge_reports_with_overlapping_suite_names() {
+        let shard = |suites: Vec<TestSuiteResult>, offset_secs: i64| {
+            let mut report = ReportGenerator::build_report(&suites, BodyInclusion::None);
+            report.timestamp = DateTime::from_timestamp(1_700_000_000 + offset_secs, 0).unwrap();
+            report
+        };
+
+        let mut users = create_sample_test_suite_result();
+        users.name = "users.rivet.yaml".to_string();
+        let mut orders = create_sample_test_suite_result();
+        orders.name = "orders.rivet.yaml".to_string();
+        let mut users_again = create_sample_test_suite_result();
+        users_again.name = "users.rivet.yaml".to_string();
+        let mut health = create_sample_test_suite_result();
+        health.name = "health.rivet.yaml".to_string();
+
+        let merged = ReportGenerator::merge_reports(vec![
+            ("shard1.json".to_string(), shard(vec![users], 30)),
+            ("shard2.json".to_string(), shard(vec![orders], 10)),
+            (
+                "shard3.json".to_string(),
+                shard(vec![users_again, health], 20),
+            ),
+        ])
+        .unwrap();
+
+        let names: Vec<&str> = merged.suites.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "users.rivet.yaml (shard1.json)",
+                "orders.rivet.yaml",
+                "users.rivet.yaml (shard3.json)",
+                "health.rivet.yaml",
+            ]
+        );
+
+        assert_eq!(merged.summary.total, 12);
+        assert_eq!(merged.summary.passed, 8);
+        assert_eq!(merged.summary.failed, 4);
+        assert_eq!(merged.summary.duration, Duration::from_millis(550 * 4));
+        assert!((merged.summary.success_rate - 66.67).abs() < 0.1);
+        assert_eq!(merged.timestamp.timestamp(), 1_700_000_010);
+        assert_eq!(merged.run_id.as_deref(), Some("3f9a0c12"));
+
+        // Shards of separate runs have no single run id
+        let mut other_run = create_sample_test_suite_result();
+        other_run.run_id = "77d01b2e".to_string();
+        let merged = ReportGenerator::merge_reports(vec![
+            (
+                "a.json".to_string(),
+                shard(vec![create_sample_test_suite_result()], 0),
+            ),
+            ("b.json".to_string(), shard(vec![other_run], 0)),
+        ])
+        .unwrap();
+        assert_eq!(merged.run_id, None);
+
+        assert!(ReportGenerator::merge_reports(vec![]).is_err());
+    }
+
+    fn report_at(suite: TestSuiteResult, secs: i64) -> TestReport {
+        let mut report = ReportGenerator::build_report(&[suite], BodyInclusion::None);
+        report.timestamp = DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap();
+        report
+    }
+
+    #[test]
+    fn test_load_history_keeps_most_recent_older_reports() {
+        let temp_dir = TempDir::new().unwrap();
+        for (i, secs) in [10, 30, 20, 40].iter().enumerate() {
+            let report = report_at(create_sample_test_suite_result(), *secs);
+            std::fs::write(
+                temp_dir.path().join(format!("run{}.json", i)),
+                serde_json::to_string(&report).unwrap(),
+            )
+            .unwrap();
+        }
+        // Not a run report; must be ignored
+        std::fs::write(temp_dir.path().join("coverage.json"), r#"{"total": 4}"#).unwrap();
+        std::fs::write(temp_dir.path().join("notes.txt"), "hello").unwrap();
+
+        let before = DateTime::from_timestamp(1_700_000_035, 0).unwrap();
+        let history = ReportGenerator::load_history(temp_dir.path(), 2, before);
+        let offsets: Vec<i64> = history
+            .iter()
+            .map(|r| r.timestamp.timestamp() - 1_700_000_000)
+            .collect();
+        assert_eq!(offsets, vec![20, 30]);
+
+        let missing = temp_dir.path().join("missing");
+        assert!(ReportGenerator::load_history(&missing, 5, before).is_empty());
+    }
+
+    #[test]
+    fn test_find_regressions() {
+        let previous = report_at(create_sample_test_suite_result(), 0);
+
+        let mut suite = create_sample_test_suite_result();
+        // DELETE passed last time and fails now; POST was already failing
+        suite.results[2].passed = false;
+        suite.results[2].error = Some("boom".to_string());
+        let current = report_at(suite, 10);
+
+        let regressions = ReportGenerator::find_regressions(&previous, &current);
+        assert_eq!(
+            regressions,
+            vec![("API Test Suite".to_string(), "Test DELETE user".to_string())]
+        );
+        assert!(ReportGenerator::find_regressions(&current, &previous).is_empty());
+    }
+
+    #[test]
+    fn test_html_report_with_history_shows_trend_and_regressions() {
+        let temp_dir = TempDir::new().unwrap();
+        let history = vec![
+            report_at(create_sample_test_suite_result(), 0),
+            report_at(create_sample_test_suite_result(), 10),
+        ];
+
+        let mut suite = create_sample_test_suite_result();
+        suite.results[0].passed = false;
+        suite.results[0].error = Some("Status code mismatch".to_string());
+        suite.passed = 1;
+        suite.failed = 2;
+        let report = report_at(suite, 20);
+
+        let path = ReportGenerator::generate_html_report(
+            &report,
+            temp_dir.path(),
+            "detailed",
+            None,
+            &history,
+        )
+        .unwrap();
+        let html = std::fs::read_to_string(path).unwrap();
+
+        assert!(html.contains("last 3 runs, 1 new regressions"));
+        assert!(html.contains("(this run)"));
+        assert!(html.contains("66.7%"));
+        assert!(html.contains("33.3%"));
+        assert_eq!(html.matches("<span class=\"regression-badge\">").count(), 1);
+
+        // Without history there's no trend section
+        let path =
+            ReportGenerator::generate_html_report(&report, temp_dir.path(), "detailed", None, &[])
+                .unwrap();
+        let html = std::fs::read_to_string(path).unwrap();
+        assert!(!html.contains("(this run)"));
+        assert!(!html.contains("<span class=\"regression-badge\">"));
+    }
+
+    #[test]
+    fn test_report_without_request_fields_deserializes() {
+        // Reports written before request details were recorded must still load
+        let json = r#"{
+            "name": "Old test",
+            "status": "Passed",
+            "duration": {"secs": 0, "nanos": 1000000},
+            "error": null,
+            "response_status": 200
+        }"#;
+
+        let test: TestResult = serde_json::from_str(json).unwrap();
+        assert!(test.request_method.is_none());
+        assert!(test.request_url.is_none());
+    }
+
+    #[test]
+    fn test_failure_kinds_in_reports() {
+        let report = ReportGenerator::build_report(
+            &[create_sample_test_suite_result()],
+            BodyInclusion::None,
+        );
+        let json = serde_json::to_value(&report).unwrap();
+        let tests = &json["suites"][0]["tests"];
+        assert!(tests[0].get("failure_kind").is_none());
+        assert_eq!(tests[1]["failure_kind"], "HttpStatus");
+
+        let temp_dir = TempDir::new().unwrap();
+        let path =
+            ReportGenerator::generate_html_report(&report, temp_dir.path(), "detailed", None, &[])
+                .unwrap();
+        let html = std::fs::read_to_string(path).unwrap();
+        assert!(html.contains("<span class=\"failure-kind-badge\">HTTP status</span>"));
+
+        // Failures in reports written before kinds were recorded
+        let old: TestResult = serde_json::from_str(
+            r#"{"name": "Old", "status": "Failed", "duration": {"secs": 0, "nanos": 0},
+                "error": "boom", "response_status": null}"#,
+        )
+        .unwrap();
+        assert_eq!(old.failure_kind(), Some(FailureKind::Unknown));
+        assert_eq!(report.suites[0].tests[0].failure_kind(), None);
     }
 
     #[test]
@@ -652,4 +2074,198 @@ mod tests {
         assert!(matches!(failed_back, TestStatus::Failed));
         assert!(matches!(skipped_back, TestStatus::Skipped));
     }
+
+    fn sample_metadata() -> ReportMetadata {
+        ReportMetadata {
+            target: "tests/api".to_string(),
+            env: Some("staging".to_string()),
+            commit: Some("4f2a9c1d8e7b6a5f4e3d2c1b0a9f8e7d6c5b4a39".to_string()),
+            rivet_version: "0.1.0".to_string(),
+            hostname: Some("ci-runner-1".to_string()),
+            args: vec!["run".to_string(), "tests/api".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_metadata_round_trips_through_json_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut report = ReportGenerator::build_report(
+            &[create_sample_test_suite_result()],
+            BodyInclusion::None,
+        );
+        report.metadata = Some(sample_metadata());
+
+        let path = ReportGenerator::generate_json_report(&report, temp_dir.path(), None).unwrap();
+        let loaded: TestReport =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(loaded.metadata, Some(sample_metadata()));
+
+        // Reports from before metadata existed still load
+        let mut old: serde_json::Value = serde_json::to_value(&report).unwrap();
+        old.as_object_mut().unwrap().remove("metadata");
+        let old: TestReport = serde_json::from_value(old).unwrap();
+        assert!(old.metadata.is_none());
+    }
+
+    #[test]
+    fn test_metadata_in_junit_and_html() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut report = ReportGenerator::build_report(
+            &[create_sample_test_suite_result()],
+            BodyInclusion::None,
+        );
+        let mut metadata = sample_metadata();
+        metadata.args.push("--grep=\"users\"".to_string());
+        report.metadata = Some(metadata);
+
+        let path = ReportGenerator::generate_junit_report(&report, temp_dir.path(), None).unwrap();
+        let xml = std::fs::read_to_string(path).unwrap();
+        assert!(xml.contains("<property name=\"env\" value=\"staging\"/>"));
+        assert!(xml.contains("<property name=\"hostname\" value=\"ci-runner-1\"/>"));
+        assert!(xml.contains(
+            "<property name=\"args\" value=\"run tests/api --grep=&quot;users&quot;\"/>"
+        ));
+
+        let path =
+            ReportGenerator::generate_html_report(&report, temp_dir.path(), "compact", None, &[])
+                .unwrap();
+        let html = std::fs::read_to_string(path).unwrap();
+        assert!(html.contains("env staging"));
+        assert!(html.contains("commit 4f2a9c1"));
+    }
+
+    #[test]
+    fn test_metadata_env_per_suite() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut staging = create_sample_test_suite_result();
+        staging.env = Some("staging".to_string());
+        let mut prod = create_sample_test_suite_result();
+        prod.name = "Billing".to_string();
+        prod.env = Some("prod".to_string());
+        let mut report = ReportGenerator::build_report(&[staging, prod], BodyInclusion::None);
+        let mut metadata = sample_metadata();
+        metadata.env = None;
+        report.metadata = Some(metadata);
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["suites"][0]["env"], "staging");
+        assert_eq!(json["suites"][1]["env"], "prod");
+
+        let path = ReportGenerator::generate_junit_report(&report, temp_dir.path(), None).unwrap();
+        let xml = std::fs::read_to_string(path).unwrap();
+        assert!(xml.contains("<property name=\"env\" value=\"staging\"/>"));
+        assert!(xml.contains("<property name=\"env\" value=\"prod\"/>"));
+
+        let path =
+            ReportGenerator::generate_html_report(&report, temp_dir.path(), "compact", None, &[])
+                .unwrap();
+        let html = std::fs::read_to_string(path).unwrap();
+        assert!(html.contains("env per suite"));
+    }
+
+    #[test]
+    fn test_mask_args_hides_credentials() {
+        let args: Vec<String> = [
+            "run",
+            "--identity-password",
+            "hunter2",
+            "--proxy=http://user:pw@proxy:8080",
+            "--env",
+            "prod",
+            "-H",
+            "Authorization: Bearer x",
+            "--header=X-Api-Key: abc123",
+            "-HCookie: session=1",
+            "-H",
+            "Accept: application/json",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        assert_eq!(
+            mask_args(&args),
+            vec![
+                "run",
+                "--identity-password",
+                REDACTED,
+                &format!("--proxy={}", REDACTED),
+                "--env",
+                "prod",
+                "-H",
+                &format!("Authorization: {}", REDACTED),
+                &format!("--header=X-Api-Key: {}", REDACTED),
+                &format!("-HCookie: {}", REDACTED),
+                "-H",
+                "Accept: application/json",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_header_credentials_are_masked_in_reports() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut report = ReportGenerator::build_report(
+            &[create_sample_test_suite_result()],
+            BodyInclusion::None,
+        );
+        let args: Vec<String> = ["run", "tests/api", "-H", "Authorization: Bearer x"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        report.metadata = Some(ReportMetadata::collect(Path::new("tests/api"), None, &args));
+
+        let path = ReportGenerator::generate_json_report(&report, temp_dir.path(), None).unwrap();
+        let json = std::fs::read_to_string(path).unwrap();
+        assert!(
+            json.contains(&format!("Authorization: {}", REDACTED)),
+            "{}",
+            json
+        );
+        assert!(!json.contains("Bearer x"));
+
+        let path = ReportGenerator::generate_junit_report(&report, temp_dir.path(), None).unwrap();
+        let xml = std::fs::read_to_string(path).unwrap();
+        assert!(!xml.contains("Bearer x"), "{}", xml);
+    }
+
+    #[test]
+    fn test_expand_report_name() {
+        let mut report = report_at(create_sample_test_suite_result(), 0);
+        report.run_id = Some("abc123".to_string());
+        assert_eq!(
+            expand_report_name("{env}-{date}-{commit}", &report).unwrap(),
+            "default-2023-11-14-unknown"
+        );
+
+        let mut metadata = sample_metadata();
+        metadata.env = Some("eu/staging".to_string());
+        report.metadata = Some(metadata);
+        assert_eq!(
+            expand_report_name("{env}_{commit}_{time}_{run_id}", &report).unwrap(),
+            "eu-staging_4f2a9c1_221320_abc123"
+        );
+        assert_eq!(expand_report_name("latest", &report).unwrap(), "latest");
+
+        let err = expand_report_name("{branch}", &report).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown placeholder {branch} in report name; use {env}, {date}, {time}, {commit}, {run_id}"
+        );
+    }
+
+    #[test]
+    fn test_report_name_placeholders_in_file_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut report = report_at(create_sample_test_suite_result(), 0);
+        report.metadata = Some(sample_metadata());
+
+        let paths =
+            ReportGenerator::generate_csv_report(&report, temp_dir.path(), Some("{env}-{date}"))
+                .unwrap();
+        assert_eq!(paths[0], temp_dir.path().join("staging-2023-11-14.csv"));
+        assert_eq!(
+            paths[1],
+            temp_dir.path().join("staging-2023-11-14_summary.csv")
+        );
+    }
 }
@@ -0,0 +1,326 @@
+//! Running suites from code: what `rivet run` does, without the command
+//! line. Progress goes to a [`Reporter`]; the outcome comes back as a
+//! [`RunReport`].
+
+use crate::commands::exit::ExitReason;
+use crate::report::{check_report_name, BodyInclusion, ReportGenerator, ReportMetadata};
+use crate::runner::data::DataSelection;
+use crate::runner::failure::FailureKind;
+use crate::runner::log::RequestLog;
+use crate::runner::order::TestOrder;
+use crate::runner::test_runner::TestSuiteResult;
+use crate::runner::TestRunner;
+use crate::utils::{load_env_file, validate_base_url, ClientOptions};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+pub use crate::runner::reporter::{ConsoleReporter, JsonReporter, Reporter, SilentReporter};
+
+/// What to run and how; the settings behind `rivet run`'s flags.
+#[derive(Clone)]
+pub struct RunOptions {
+    /// A suite file, or a directory searched for them
+    pub target: PathBuf,
+    /// Environment for every suite, over their own `env`
+    pub env: Option<String>,
+    pub parallel_suites: usize,
+    /// Requests in flight at once across all suites; the suite
+    /// parallelism, uncapped, when unset
+    pub parallel_requests: Option<usize>,
+    /// Per request
+    pub timeout: Duration,
+    /// Run only tests whose `suite/test` name matches this regex
+    pub grep: Option<String>,
+    /// Run the tests `grep` does *not* match instead
+    pub grep_invert: bool,
+    /// Cancel the run after this many failed tests
+    pub max_failures: Option<usize>,
+    pub client: ClientOptions,
+    /// Retarget every suite at this URL
+    pub base_url: Option<String>,
+    /// Sent with every HTTP step, over the steps' own
+    pub headers: HashMap<String, String>,
+    /// Send `X-Rivet-Test` with every HTTP step
+    pub identify: bool,
+    /// Dataset filter and sample, over the suites' own
+    pub data_selection: DataSelection,
+    pub order: TestOrder,
+    /// Variables to mask in every suite, on top of each suite's `secrets`
+    pub secrets: Vec<String>,
+    /// Print each step's exchange, whole bodies from 2
+    pub verbose: u8,
+    /// NDJSON file every step's exchange is written to
+    pub log_file: Option<PathBuf>,
+    /// Loaded into the process environment before any suite
+    pub env_file: Option<PathBuf>,
+    /// Response body bytes kept per step, over any suite's setting
+    pub max_capture_bytes: Option<usize>,
+    /// Fail suites that repeat a step name instead of renaming the repeats
+    pub strict: bool,
+    /// Reports to write once the run is over
+    pub reports: Option<ReportOptions>,
+    pub reporter: Arc<dyn Reporter>,
+    /// Cancel to stop the run early: tests not yet finished are skipped and
+    /// the report covers what completed
+    pub interrupt: CancellationToken,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            target: PathBuf::from("."),
+            env: None,
+            parallel_suites: 1,
+            parallel_requests: None,
+            timeout: Duration::from_secs(30),
+            grep: None,
+            grep_invert: false,
+            max_failures: None,
+            client: ClientOptions::default(),
+            base_url: None,
+            headers: HashMap::new(),
+            identify: false,
+            data_selection: DataSelection::default(),
+            order: TestOrder::default(),
+            secrets: Vec::new(),
+            verbose: 0,
+            log_file: None,
+            env_file: None,
+            max_capture_bytes: None,
+            strict: false,
+            reports: None,
+            reporter: Arc::new(SilentReporter),
+            interrupt: CancellationToken::new(),
+        }
+    }
+}
+
+/// Reports as `rivet run --report` writes them.
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    /// Comma-separated: `html`, `json`, `junit`, ...
+    pub formats: String,
+    pub dir: PathBuf,
+    /// HTML template: `compact` or `detailed`
+    pub template: String,
+    /// File name without extension; timestamped when unset
+    pub name: Option<String>,
+    /// Previous reports to show trends from
+    pub history: usize,
+    pub include_bodies: BodyInclusion,
+    /// The command line, recorded in report metadata with secrets masked
+    pub args: Vec<String>,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            formats: "json".to_string(),
+            dir: PathBuf::from("./reports"),
+            template: "compact".to_string(),
+            name: None,
+            history: 0,
+            include_bodies: BodyInclusion::Failures,
+            args: Vec::new(),
+        }
+    }
+}
+
+/// How a run went.
+pub struct RunReport {
+    pub suites: Vec<TestSuiteResult>,
+    /// `{{run_id}}` of the run, when any suite ran
+    pub run_id: Option<String>,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub duration: Duration,
+    /// Stopped early through [`RunOptions::interrupt`]
+    pub interrupted: bool,
+    /// Report files written
+    pub reports: Vec<PathBuf>,
+}
+
+impl RunReport {
+    /// How the run ended, as `rivet run` would exit.
+    pub fn exit_reason(&self) -> ExitReason {
+        if self.interrupted {
+            ExitReason::Interrupted
+        } else if self.nothing_reached() {
+            ExitReason::InfrastructureError
+        } else if self.failed > 0 {
+            ExitReason::TestFailures
+        } else {
+            ExitReason::Passed
+        }
+    }
+
+    /// Failed tests counted by kind, e.g. `2 HTTP status, 1 timeout`.
+    pub fn failure_breakdown(&self) -> String {
+        let mut counts: HashMap<FailureKind, usize> = HashMap::new();
+        for test in self.suites.iter().flat_map(|suite| &suite.results) {
+            if !test.passed && !test.skipped {
+                let kind = test.failure_kind.unwrap_or(FailureKind::Unknown);
+                *counts.entry(kind).or_default() += 1;
+            }
+        }
+        FailureKind::ALL
+            .iter()
+            .filter_map(|kind| {
+                counts
+                    .get(kind)
+                    .map(|count| format!("{} {}", count, kind.label()))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Whether tests ran but none of them got a response, e.g. because the
+    /// server was down.
+    fn nothing_reached(&self) -> bool {
+        let mut sent = self
+            .suites
+            .iter()
+            .flat_map(|suite| &suite.results)
+            .filter(|test| !test.skipped)
+            .peekable();
+        sent.peek().is_some() && sent.all(|test| !test.passed && test.response_status.is_none())
+    }
+}
+
+/// Run the suites at `options.target`, reporting progress as it goes.
+/// Failing tests are part of the report, not an error; errors are for
+/// runs that can't start, e.g. a suite that doesn't parse.
+///
+/// ```no_run
+/// use rivet::run::{run_suites, RunOptions};
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let report = run_suites(RunOptions {
+///     target: "tests/api".into(),
+///     env: Some("staging".to_string()),
+///     ..RunOptions::default()
+/// })
+/// .await?;
+/// println!("{} of {} tests passed", report.passed, report.total);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn run_suites(options: RunOptions) -> Result<RunReport> {
+    if options.max_failures == Some(0) {
+        anyhow::bail!("max_failures must be at least 1");
+    }
+    if let Some(base_url) = &options.base_url {
+        validate_base_url(base_url)?;
+    }
+    if let Some(name) = options.reports.as_ref().and_then(|r| r.name.as_deref()) {
+        check_report_name(name)?;
+    }
+    options.data_selection.validate()?;
+    if let Some(path) = &options.env_file {
+        load_env_file(Some(path))?;
+    }
+    let reporter = options.reporter.clone();
+    reporter.run_started(&options);
+
+    let request_log = RequestLog::new(options.verbose, options.log_file.as_deref())?;
+    let mut runner = TestRunner::new(
+        options.timeout,
+        options.parallel_suites,
+        false,
+        options.grep.clone(),
+        false,
+        &options.client,
+    )?
+    .with_reporter(reporter.clone())
+    .with_max_failures(options.max_failures)
+    .with_grep_invert(options.grep_invert)
+    .with_base_url(options.base_url.clone())
+    .with_headers(options.headers.clone())
+    .with_identify(options.identify)
+    .with_data_selection(options.data_selection.clone())
+    .with_order(options.order)
+    .with_secrets(options.secrets.clone())
+    .with_max_capture_bytes(options.max_capture_bytes)
+    .with_strict(options.strict)
+    .with_request_log(request_log.clone());
+    if let Some(parallel_requests) = options.parallel_requests {
+        runner = runner.with_parallel_requests(parallel_requests);
+    }
+
+    let interrupt = {
+        let runner = runner.clone();
+        let token = options.interrupt.clone();
+        tokio::spawn(async move {
+            token.cancelled().await;
+            runner.interrupt();
+        })
+    };
+    let suites = runner
+        .run_tests(&options.target, options.env.as_deref())
+        .await;
+    interrupt.abort();
+    let suites = suites?;
+    request_log.connections(runner.connection_stats());
+
+    let mut reports = Vec::new();
+    if let Some(report_options) = &options.reports {
+        let mut report = ReportGenerator::build_report(&suites, report_options.include_bodies);
+        // --env, or the env every suite ran in
+        let env = options.env.clone().or_else(|| {
+            let first = suites.first()?.env.as_ref()?;
+            suites
+                .iter()
+                .all(|suite| suite.env.as_ref() == Some(first))
+                .then(|| first.clone())
+        });
+        report.metadata = Some(ReportMetadata::collect(
+            &options.target,
+            env.as_deref(),
+            &report_options.args,
+        ));
+        for format in ReportGenerator::unknown_formats(&report_options.formats) {
+            reporter.warning(&format!("Unknown report format '{}'", format));
+        }
+        match ReportGenerator::generate_reports(
+            &report,
+            &report_options.formats,
+            &report_options.dir,
+            &report_options.template,
+            report_options.name.as_deref(),
+            report_options.history,
+        ) {
+            Ok(generated_files) => {
+                reporter.reports_written(&generated_files);
+                reports = generated_files;
+            }
+            Err(e) => reporter.warning(&format!("Failed to generate reports: {}", e)),
+        }
+    }
+
+    let passed = suites.iter().map(|suite| suite.passed).sum();
+    let failed = suites.iter().map(|suite| suite.failed).sum();
+    let skipped = suites
+        .iter()
+        .flat_map(|suite| &suite.results)
+        .filter(|test| test.skipped)
+        .count();
+    let report = RunReport {
+        run_id: suites.first().map(|suite| suite.run_id.clone()),
+        total: passed + failed,
+        passed,
+        failed,
+        skipped,
+        duration: suites.iter().map(|suite| suite.duration).sum(),
+        interrupted: runner.is_interrupted(),
+        reports,
+        suites,
+    };
+    reporter.run_finished(&report);
+    Ok(report)
+}
@@ -1,7 +1,787 @@
-// Placeholder for future assertion extensions
+use crate::runner::data::json_type;
+use crate::runner::failure::FailureKind;
+use crate::style::Colorize;
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::fmt;
 
-// Future extensions could include:
-// - Schema validation using jsonschema crate
-// - Custom assertion plugins
-// - Response time assertions
-// - File content assertions
+/// A failed JSONPath assertion.
+///
+/// `Display` gives the one-line message stored in reports; `diff` holds a
+/// unified diff of the pretty-printed values for console output.
+#[derive(Debug)]
+pub struct JsonPathMismatch {
+    pub message: String,
+    pub diff: Option<String>,
+}
+
+impl fmt::Display for JsonPathMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for JsonPathMismatch {}
+
+/// A response status other than the expected one.
+#[derive(Debug)]
+pub struct StatusMismatch {
+    pub expected: u16,
+    pub actual: u16,
+}
+
+impl fmt::Display for StatusMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expected status {} but got {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for StatusMismatch {}
+
+/// A body that JSONPath or schema assertions couldn't parse.
+#[derive(Debug)]
+pub struct InvalidJsonBody;
+
+impl fmt::Display for InvalidJsonBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Response body is not valid JSON")
+    }
+}
+
+impl std::error::Error for InvalidJsonBody {}
+
+/// Every assertion that failed for one test, in the order they were checked.
+///
+/// A single failure displays as its own message; several are listed as a
+/// numbered block so the whole set can be fixed in one pass.
+#[derive(Debug)]
+pub struct AssertionFailures {
+    failures: Vec<anyhow::Error>,
+}
+
+impl AssertionFailures {
+    pub fn new(failures: Vec<anyhow::Error>) -> Self {
+        Self { failures }
+    }
+
+    /// What the failures come down to: an unexpected status outranks the
+    /// rest, since it usually explains them, then a body that isn't JSON.
+    pub fn kind(&self) -> FailureKind {
+        if self.failures.iter().any(|e| e.is::<StatusMismatch>()) {
+            FailureKind::HttpStatus
+        } else if self.failures.iter().any(|e| e.is::<InvalidJsonBody>()) {
+            FailureKind::InvalidResponseBody
+        } else {
+            FailureKind::AssertionFailure
+        }
+    }
+
+    /// Diffs of any JSONPath mismatches, numbered to match the error list.
+    pub fn diff(&self) -> Option<String> {
+        let numbered = self.failures.len() > 1;
+        let diffs: Vec<String> = self
+            .failures
+            .iter()
+            .enumerate()
+            .filter_map(|(i, failure)| {
+                let diff = failure.downcast_ref::<JsonPathMismatch>()?.diff.as_ref()?;
+                Some(if numbered {
+                    format!("{}.\n{}", i + 1, diff)
+                } else {
+                    diff.clone()
+                })
+            })
+            .collect();
+
+        if diffs.is_empty() {
+            None
+        } else {
+            Some(diffs.concat())
+        }
+    }
+}
+
+impl fmt::Display for AssertionFailures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let [failure] = self.failures.as_slice() {
+            return write!(f, "{}", failure);
+        }
+
+        write!(f, "{} assertions failed:", self.failures.len())?;
+        for (i, failure) in self.failures.iter().enumerate() {
+            write!(f, "\n  {}. {}", i + 1, failure)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AssertionFailures {}
+
+/// Last segment of a JSONPath expression.
+#[derive(Debug, PartialEq)]
+pub enum PathKey<'a> {
+    Field(&'a str),
+    Index(usize),
+}
+
+/// Split `path` into its parent path and final segment.
+///
+/// The parent is `"$"` for top-level keys; `None` means the path is the root
+/// itself or can't be split.
+pub fn split_parent(path: &str) -> Option<(&str, PathKey<'_>)> {
+    if let Some(without_bracket) = path.strip_suffix(']') {
+        let open = without_bracket.rfind('[')?;
+        let index = without_bracket[open + 1..].parse().ok()?;
+        let parent = &path[..open];
+        let parent = if parent.is_empty() { "$" } else { parent };
+        return Some((parent, PathKey::Index(index)));
+    }
+
+    match path.rfind('.') {
+        Some(dot) => {
+            let parent = &path[..dot];
+            let parent = if parent.is_empty() { "$" } else { parent };
+            Some((parent, PathKey::Field(&path[dot + 1..])))
+        }
+        None if path != "$" && !path.is_empty() => Some(("$", PathKey::Field(path))),
+        None => None,
+    }
+}
+
+/// Build the error for a JSONPath assertion whose actual value differs from the expected one.
+///
+/// When the enclosing value is known the diff shows it in full, with the
+/// expected value substituted in, so the failing field is seen in context.
+pub fn jsonpath_mismatch(
+    path: &str,
+    expected: &Value,
+    actual: &Value,
+    parent: Option<&Value>,
+) -> JsonPathMismatch {
+    let message = format!(
+        "JSONPath assertion failed for '{}': expected {:?} but got {:?}",
+        path, expected, actual
+    );
+
+    let context = parent.and_then(|parent| {
+        let (_, key) = split_parent(path)?;
+        let mut expected_parent = parent.clone();
+        match key {
+            PathKey::Field(field) => {
+                *expected_parent.as_object_mut()?.get_mut(field)? = expected.clone();
+            }
+            PathKey::Index(index) => {
+                *expected_parent.as_array_mut()?.get_mut(index)? = expected.clone();
+            }
+        }
+        Some((expected_parent, parent.clone()))
+    });
+
+    let diff = match context {
+        Some((expected_parent, actual_parent)) => Some(json_diff(&expected_parent, &actual_parent)),
+        // A scalar mismatch with no surrounding object is already clear from the message
+        None if is_composite(expected) || is_composite(actual) => Some(json_diff(expected, actual)),
+        None => None,
+    };
+
+    JsonPathMismatch { message, diff }
+}
+
+fn is_composite(value: &Value) -> bool {
+    matches!(value, Value::Object(_) | Value::Array(_))
+}
+
+/// Past this many lines on either side, unchanged lines away from the
+/// changes are collapsed to [`DIFF_CONTEXT`] lines.
+const MAX_FULL_DIFF_LINES: usize = 200;
+const DIFF_CONTEXT: usize = 3;
+/// Size of the largest LCS table built for the changed lines; beyond it the
+/// changed lines are shown as head and tail excerpts of each side instead.
+const MAX_DIFF_CELLS: usize = 1_000_000;
+const DIFF_EXCERPT: usize = 20;
+
+/// Unified line diff of two pretty-printed JSON values.
+///
+/// Only the lines between the common start and end are diffed, so a large
+/// body with a small change stays cheap and its diff short.
+pub fn json_diff(expected: &Value, actual: &Value) -> String {
+    let expected = serde_json::to_string_pretty(expected).unwrap_or_default();
+    let actual = serde_json::to_string_pretty(actual).unwrap_or_default();
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let collapse = old.len().max(new.len()) > MAX_FULL_DIFF_LINES;
+
+    let mut diff = String::from("--- expected\n+++ actual\n");
+    let mut head = &old[..prefix];
+    if collapse && head.len() > DIFF_CONTEXT {
+        diff.push_str(&format!(
+            " ... {} unchanged lines\n",
+            head.len() - DIFF_CONTEXT
+        ));
+        head = &head[head.len() - DIFF_CONTEXT..];
+    }
+    for line in head {
+        diff.push_str(&format!(" {}\n", line));
+    }
+
+    let old_changed = &old[prefix..old.len() - suffix];
+    let new_changed = &new[prefix..new.len() - suffix];
+    if old_changed.len().saturating_mul(new_changed.len()) <= MAX_DIFF_CELLS {
+        diff_lines(old_changed, new_changed, &mut diff);
+    } else {
+        push_excerpt('-', old_changed, &mut diff);
+        push_excerpt('+', new_changed, &mut diff);
+    }
+
+    let mut tail = &old[old.len() - suffix..];
+    let hidden = if collapse && tail.len() > DIFF_CONTEXT {
+        tail = &tail[..DIFF_CONTEXT];
+        suffix - DIFF_CONTEXT
+    } else {
+        0
+    };
+    for line in tail {
+        diff.push_str(&format!(" {}\n", line));
+    }
+    if hidden > 0 {
+        diff.push_str(&format!(" ... {} unchanged lines\n", hidden));
+    }
+
+    diff
+}
+
+/// The first and last [`DIFF_EXCERPT`] of `lines`, marked with `sign`.
+fn push_excerpt(sign: char, lines: &[&str], diff: &mut String) {
+    if lines.len() <= 2 * DIFF_EXCERPT {
+        for line in lines {
+            diff.push_str(&format!("{}{}\n", sign, line));
+        }
+        return;
+    }
+    for line in &lines[..DIFF_EXCERPT] {
+        diff.push_str(&format!("{}{}\n", sign, line));
+    }
+    diff.push_str(&format!(
+        " ... {} more lines\n",
+        lines.len() - 2 * DIFF_EXCERPT
+    ));
+    for line in &lines[lines.len() - DIFF_EXCERPT..] {
+        diff.push_str(&format!("{}{}\n", sign, line));
+    }
+}
+
+/// Line diff of `old` against `new` through their longest common subsequence.
+fn diff_lines(old: &[&str], new: &[&str], diff: &mut String) {
+    // Longest common subsequence table, filled from the end
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push_str(&format!(" {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("-{}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+{}\n", new[j]));
+            j += 1;
+        }
+    }
+}
+
+/// Indent a diff for console output, coloring removed lines red and added lines green.
+pub fn format_diff(diff: &str, color: bool, indent: &str) -> String {
+    diff.lines()
+        .map(|line| {
+            let styled = if !color {
+                line.to_string()
+            } else if line.starts_with('-') {
+                line.red().to_string()
+            } else if line.starts_with('+') {
+                line.green().to_string()
+            } else {
+                line.dimmed().to_string()
+            };
+            format!("{}{}\n", indent, styled)
+        })
+        .collect()
+}
+
+/// Whether a `Content-Type` value matches `pattern`, e.g. `application/json`,
+/// `text/*` or `application/*+json`. Type and subtype compare without case;
+/// parameters are ignored unless the pattern lists them, as in
+/// `text/html; charset=utf-8`.
+/// Where `instance` breaks `schema`, as `<JSON pointer>: <problem>`; at
+/// most [`MAX_SCHEMA_ERRORS`] of them, plus a count of the rest.
+pub fn schema_errors(schema: &JSONSchema, instance: &Value) -> Vec<String> {
+    let Err(errors) = schema.validate(instance) else {
+        return Vec::new();
+    };
+    let errors: Vec<String> = errors
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            let path = if path.is_empty() { "/" } else { &path };
+            format!("{}: {}", path, error)
+        })
+        .collect();
+    let hidden = errors.len().saturating_sub(MAX_SCHEMA_ERRORS);
+    let mut shown: Vec<String> = errors.into_iter().take(MAX_SCHEMA_ERRORS).collect();
+    if hidden > 0 {
+        shown.push(format!("and {} more", hidden));
+    }
+    shown
+}
+
+/// Schema violations listed per response; the first few show what's wrong.
+pub const MAX_SCHEMA_ERRORS: usize = 5;
+
+pub fn media_type_matches(pattern: &str, actual: &str) -> bool {
+    let (pattern_type, pattern_params) = parse_media_type(pattern);
+    let (actual_type, actual_params) = parse_media_type(actual);
+
+    let Some((pattern_main, pattern_sub)) = pattern_type.split_once('/') else {
+        return false;
+    };
+    let Some((actual_main, actual_sub)) = actual_type.split_once('/') else {
+        return false;
+    };
+    let sub_matches = match pattern_sub.split_once('*') {
+        Some((prefix, suffix)) => {
+            actual_sub.len() >= prefix.len() + suffix.len()
+                && actual_sub.starts_with(prefix)
+                && actual_sub.ends_with(suffix)
+        }
+        None => pattern_sub == actual_sub,
+    };
+
+    (pattern_main == "*" || pattern_main == actual_main)
+        && sub_matches
+        && pattern_params
+            .iter()
+            .all(|param| actual_params.contains(param))
+}
+
+/// `application/json` or a `+json` type such as `application/problem+json`.
+pub fn is_json_media_type(content_type: &str) -> bool {
+    media_type_matches("application/json", content_type)
+        || media_type_matches("*/*+json", content_type)
+}
+
+/// The array length a `jsonpath_length` assertion allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthBound {
+    Exactly(usize),
+    AtLeast(usize),
+    AtMost(usize),
+}
+
+impl LengthBound {
+    /// Parse `10`, `>= 1` or `<= 50`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (bound, number): (fn(usize) -> Self, _) = if let Some(n) = text.strip_prefix(">=") {
+            (Self::AtLeast, n)
+        } else if let Some(n) = text.strip_prefix("<=") {
+            (Self::AtMost, n)
+        } else {
+            (Self::Exactly, text.strip_prefix("==").unwrap_or(text))
+        };
+        number
+            .trim()
+            .parse()
+            .map(bound)
+            .map_err(|_| "expected a number, `>= n` or `<= n`".to_string())
+    }
+
+    fn allows(self, length: usize) -> bool {
+        match self {
+            Self::Exactly(n) => length == n,
+            Self::AtLeast(n) => length >= n,
+            Self::AtMost(n) => length <= n,
+        }
+    }
+}
+
+impl fmt::Display for LengthBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exactly(n) => write!(f, "{}", n),
+            Self::AtLeast(n) => write!(f, "at least {}", n),
+            Self::AtMost(n) => write!(f, "at most {}", n),
+        }
+    }
+}
+
+/// The elements of the array at `path`, or why `actual` isn't one.
+fn elements<'a>(path: &str, actual: &'a Value) -> Result<&'a [Value], String> {
+    match actual {
+        Value::Array(items) => Ok(items),
+        other => Err(format!(
+            "Expected '{}' to be an array but it is {}",
+            path,
+            json_type(other)
+        )),
+    }
+}
+
+/// Check a `jsonpath_length` assertion against the value at `path`.
+pub fn check_length(path: &str, actual: &Value, bound: LengthBound) -> Result<(), String> {
+    let length = elements(path, actual)?.len();
+    if bound.allows(length) {
+        return Ok(());
+    }
+    Err(format!(
+        "Expected '{}' to have {} element(s) but it has {}",
+        path, bound, length
+    ))
+}
+
+/// Check a `jsonpath_contains` assertion: some element equals `expected`.
+pub fn check_contains(path: &str, actual: &Value, expected: &Value) -> Result<(), String> {
+    match elements(path, actual)? {
+        items if items.contains(expected) => Ok(()),
+        [] => Err(format!(
+            "Expected '{}' to contain {} but it is empty",
+            path, expected
+        )),
+        items => Err(format!(
+            "Expected '{}' to contain {} but none of its {} element(s) do",
+            path,
+            expected,
+            items.len()
+        )),
+    }
+}
+
+/// Check a `jsonpath_every` assertion: every element equals `expected`,
+/// reporting the first that doesn't.
+pub fn check_every(path: &str, actual: &Value, expected: &Value) -> Result<(), String> {
+    let items = elements(path, actual)?;
+    match items.iter().position(|item| item != expected) {
+        None => Ok(()),
+        Some(index) => Err(format!(
+            "Expected every element of '{}' to be {} but element {} is {}",
+            path, expected, index, items[index]
+        )),
+    }
+}
+
+/// Lowercased `type/subtype` and its `name=value` parameters, unquoted.
+fn parse_media_type(value: &str) -> (String, Vec<(String, String)>) {
+    let mut parts = value.split(';');
+    let media_type = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(name, value)| {
+            (
+                name.trim().to_ascii_lowercase(),
+                value.trim().trim_matches('"').to_ascii_lowercase(),
+            )
+        })
+        .collect();
+    (media_type, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_media_type_matches() {
+        let matches = [
+            ("application/json", "application/json"),
+            ("application/json", "Application/JSON; charset=utf-8"),
+            ("text/html; charset=utf-8", "text/html;charset=\"UTF-8\""),
+            ("text/*", "text/plain"),
+            ("*/*", "image/png"),
+            ("application/*+json", "application/problem+json"),
+            (
+                "application/*+json",
+                "application/vnd.api+json; charset=utf-8",
+            ),
+        ];
+        for (pattern, actual) in matches {
+            assert!(
+                media_type_matches(pattern, actual),
+                "{} ~ {}",
+                pattern,
+                actual
+            );
+        }
+
+        let mismatches = [
+            ("application/json", "text/html; charset=utf-8"),
+            ("application/json", "application/json-seq"),
+            ("text/html; charset=utf-8", "text/html; charset=iso-8859-1"),
+            ("text/html; charset=utf-8", "text/html"),
+            ("application/*+json", "application/json"),
+            ("application/*+json", "text/problem+json"),
+            ("application/json", ""),
+        ];
+        for (pattern, actual) in mismatches {
+            assert!(
+                !media_type_matches(pattern, actual),
+                "{} !~ {}",
+                pattern,
+                actual
+            );
+        }
+
+        assert!(is_json_media_type("application/problem+json"));
+        assert!(is_json_media_type("application/json; charset=utf-8"));
+        assert!(!is_json_media_type("text/html"));
+    }
+
+    #[test]
+    fn test_split_parent() {
+        assert_eq!(
+            split_parent("$.user.name"),
+            Some(("$.user", PathKey::Field("name")))
+        );
+        assert_eq!(split_parent("$.id"), Some(("$", PathKey::Field("id"))));
+        assert_eq!(split_parent("id"), Some(("$", PathKey::Field("id"))));
+        assert_eq!(
+            split_parent("$.items[2]"),
+            Some(("$.items", PathKey::Index(2)))
+        );
+        assert_eq!(split_parent("$[0]"), Some(("$", PathKey::Index(0))));
+        assert_eq!(
+            split_parent("$[0].userId"),
+            Some(("$[0]", PathKey::Field("userId")))
+        );
+        assert_eq!(split_parent("$"), None);
+    }
+
+    #[test]
+    fn test_object_mismatch_diff_includes_enclosing_object() {
+        let parent = json!({"id": 1, "name": "Bob", "role": "admin"});
+        let mismatch =
+            jsonpath_mismatch("$.user.name", &json!("Alice"), &json!("Bob"), Some(&parent));
+
+        assert_eq!(
+            mismatch.to_string(),
+            "JSONPath assertion failed for '$.user.name': expected String(\"Alice\") but got String(\"Bob\")"
+        );
+        assert_eq!(
+            mismatch.diff.unwrap(),
+            r#"--- expected
++++ actual
+ {
+   "id": 1,
+-  "name": "Alice",
++  "name": "Bob",
+   "role": "admin"
+ }
+"#
+        );
+    }
+
+    #[test]
+    fn test_nested_object_mismatch_diff() {
+        let expected = json!({"street": "Main St", "zip": "12345", "geo": {"lat": 1}});
+        let actual = json!({"street": "Main St", "zip": "54321", "geo": {"lat": 1}});
+        let mismatch = jsonpath_mismatch("$.address", &expected, &actual, None);
+
+        assert_eq!(
+            mismatch.diff.unwrap(),
+            r#"--- expected
++++ actual
+ {
+   "geo": {
+     "lat": 1
+   },
+   "street": "Main St",
+-  "zip": "12345"
++  "zip": "54321"
+ }
+"#
+        );
+    }
+
+    #[test]
+    fn test_array_mismatch_diff() {
+        let parent = json!({"tags": ["a", "c", "d"], "count": 3});
+        let mismatch = jsonpath_mismatch(
+            "$.data.tags",
+            &json!(["a", "b", "c"]),
+            &json!(["a", "c", "d"]),
+            Some(&parent),
+        );
+
+        assert_eq!(
+            mismatch.diff.unwrap(),
+            r#"--- expected
++++ actual
+ {
+   "count": 3,
+   "tags": [
+     "a",
+-    "b",
+-    "c"
++    "c",
++    "d"
+   ]
+ }
+"#
+        );
+    }
+
+    #[test]
+    fn test_large_body_diff_stays_short() {
+        let items: Vec<usize> = (0..50_000).collect();
+        let mut changed = items.clone();
+        changed[25_000] = 0;
+        let diff = json_diff(&json!({ "items": items }), &json!({ "items": changed }));
+        assert_eq!(
+            diff,
+            r#"--- expected
++++ actual
+ ... 24999 unchanged lines
+     24997,
+     24998,
+     24999,
+-    25000,
++    0,
+     25001,
+     25002,
+     25003,
+ ... 24998 unchanged lines
+"#
+        );
+
+        // Nothing in common: excerpts of both sides rather than an LCS table
+        let other: Vec<String> = items.iter().map(|item| format!("#{}", item)).collect();
+        let diff = json_diff(&json!(items), &json!(other));
+        assert_eq!(diff.lines().count(), 4 + 2 * (2 * DIFF_EXCERPT + 1));
+        assert!(diff.contains("\n-  0,\n"), "{}", diff);
+        assert!(diff.contains("\n+  \"#49999\"\n"), "{}", diff);
+        assert!(diff.contains(" ... 49960 more lines\n"), "{}", diff);
+    }
+
+    #[test]
+    fn test_scalar_mismatch_without_context_has_no_diff() {
+        let mismatch = jsonpath_mismatch("$", &json!(1), &json!(2), None);
+        assert!(mismatch.diff.is_none());
+    }
+
+    #[test]
+    fn test_assertion_failures_display() {
+        let single =
+            AssertionFailures::new(vec![anyhow::anyhow!("Expected status 200 but got 404")]);
+        assert_eq!(single.to_string(), "Expected status 200 but got 404");
+        assert!(single.diff().is_none());
+
+        let several = AssertionFailures::new(vec![
+            anyhow::anyhow!("Expected status 200 but got 404"),
+            jsonpath_mismatch("$.items", &json!([1]), &json!([2]), None).into(),
+            anyhow::anyhow!("Field 'id' not found in JSON"),
+        ]);
+        assert_eq!(
+            several.to_string(),
+            "3 assertions failed:\n  1. Expected status 200 but got 404\n  2. JSONPath assertion failed for '$.items': expected Array [Number(1)] but got Array [Number(2)]\n  3. Field 'id' not found in JSON"
+        );
+        assert_eq!(
+            several.diff().unwrap(),
+            "2.\n--- expected\n+++ actual\n [\n-  1\n+  2\n ]\n"
+        );
+    }
+
+    #[test]
+    fn test_length_bound_parse() {
+        assert_eq!(LengthBound::parse("10"), Ok(LengthBound::Exactly(10)));
+        assert_eq!(LengthBound::parse(">= 1"), Ok(LengthBound::AtLeast(1)));
+        assert_eq!(LengthBound::parse("<=50"), Ok(LengthBound::AtMost(50)));
+        assert_eq!(LengthBound::parse("== 0"), Ok(LengthBound::Exactly(0)));
+        for text in ["", "> 1", ">= -1", "ten"] {
+            assert!(LengthBound::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_array_checks_on_empty_arrays() {
+        let empty = json!([]);
+        assert!(check_length("$.items", &empty, LengthBound::Exactly(0)).is_ok());
+        assert!(check_length("$.items", &empty, LengthBound::AtMost(3)).is_ok());
+        assert_eq!(
+            check_length("$.items", &empty, LengthBound::AtLeast(1)).unwrap_err(),
+            "Expected '$.items' to have at least 1 element(s) but it has 0"
+        );
+        assert_eq!(
+            check_contains("$.items", &empty, &json!("a")).unwrap_err(),
+            "Expected '$.items' to contain \"a\" but it is empty"
+        );
+        // Nothing in an empty array breaks the rule
+        assert!(check_every("$.items", &empty, &json!("a")).is_ok());
+    }
+
+    #[test]
+    fn test_array_checks_on_other_values() {
+        for (actual, kind) in [
+            (json!({"length": 2}), "an object"),
+            (json!("ab"), "a string"),
+            (Value::Null, "null"),
+        ] {
+            let expected = format!("Expected '$.items' to be an array but it is {}", kind);
+            assert_eq!(
+                check_length("$.items", &actual, LengthBound::Exactly(2)).unwrap_err(),
+                expected
+            );
+            assert_eq!(
+                check_contains("$.items", &actual, &json!(2)).unwrap_err(),
+                expected
+            );
+            assert_eq!(
+                check_every("$.items", &actual, &json!(2)).unwrap_err(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_array_checks_report_elements() {
+        let items = json!([{"id": 1}, {"id": 2}, {"id": 2}]);
+        assert!(check_contains("$.items", &items, &json!({"id": 2})).is_ok());
+        assert_eq!(
+            check_every("$.items", &items, &json!({"id": 2})).unwrap_err(),
+            r#"Expected every element of '$.items' to be {"id":2} but element 0 is {"id":1}"#
+        );
+        assert_eq!(
+            check_length("$.items", &items, LengthBound::AtMost(2)).unwrap_err(),
+            "Expected '$.items' to have at most 2 element(s) but it has 3"
+        );
+    }
+
+    #[test]
+    fn test_format_diff_without_color() {
+        let diff = "--- expected\n+++ actual\n-1\n+2\n";
+        assert_eq!(
+            format_diff(diff, false, "    "),
+            "    --- expected\n    +++ actual\n    -1\n    +2\n"
+        );
+        assert!(format_diff(diff, true, "").contains("\u{1b}["));
+    }
+}
@@ -0,0 +1,297 @@
+//! A suite's `auth` block: the `Authorization` header it gives steps, and
+//! the OAuth 2.0 tokens fetched for it during a run.
+
+use crate::config::{AuthConfig, AuthScheme, OAuth2ClientCredentials, Request, RivetConfig};
+use crate::runner::executor::RequestExecutor;
+use crate::runner::variables::VariableContext;
+use crate::ui::layout::truncate;
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Variable holding a suite's `Authorization` value. It's a secret, so the
+/// value is masked in output and reports.
+pub const AUTHORIZATION_VAR: &str = "RIVET_AUTHORIZATION";
+
+/// A cached token is fetched again once it's this close to expiring.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// Hold the client secret, raw and form-encoded, while its token is fetched,
+/// so the request log masks it.
+const CLIENT_SECRET_VARS: [&str; 2] = ["RIVET_CLIENT_SECRET", "RIVET_CLIENT_SECRET_FORM"];
+
+/// OAuth 2.0 tokens fetched during a run, so suites with the same client
+/// share one. Clones share the cache.
+#[derive(Clone, Default)]
+pub struct TokenCache {
+    tokens: Arc<Mutex<HashMap<TokenKey, CachedToken>>>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct TokenKey {
+    token_url: String,
+    client_id: String,
+    scope: String,
+}
+
+struct CachedToken {
+    access_token: String,
+    /// From `expires_in`; tokens without it are kept for the whole run
+    expires_at: Option<Instant>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+impl TokenCache {
+    /// The `Authorization` value `auth` gives in `context`, fetching a token
+    /// with `executor` when there's no fresh one.
+    pub async fn authorization(
+        &self,
+        auth: &AuthConfig,
+        context: &VariableContext,
+        executor: &RequestExecutor,
+    ) -> Result<String> {
+        match auth.scheme()? {
+            AuthScheme::Bearer(token) => Ok(format!("Bearer {}", context.resolve(token)?)),
+            AuthScheme::Basic(basic) => {
+                let credentials = format!(
+                    "{}:{}",
+                    context.resolve(&basic.user)?,
+                    context.resolve(&basic.password)?
+                );
+                let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+                Ok(format!("Basic {}", encoded))
+            }
+            AuthScheme::OAuth2ClientCredentials(oauth2) => {
+                let token = self.token(oauth2, context, executor).await?;
+                Ok(format!("Bearer {}", token))
+            }
+        }
+    }
+
+    async fn token(
+        &self,
+        oauth2: &OAuth2ClientCredentials,
+        context: &VariableContext,
+        executor: &RequestExecutor,
+    ) -> Result<String> {
+        let scopes = oauth2
+            .scopes
+            .iter()
+            .map(|scope| context.resolve(scope))
+            .collect::<Result<Vec<_>>>()?;
+        let key = TokenKey {
+            token_url: context.resolve(&oauth2.token_url)?,
+            client_id: context.resolve(&oauth2.client_id)?,
+            scope: scopes.join(" "),
+        };
+
+        // Held while fetching, so suites starting together make one request
+        let mut tokens = self.tokens.lock().await;
+        let now = Instant::now();
+        if let Some(cached) = tokens.get(&key) {
+            if cached
+                .expires_at
+                .is_none_or(|expires_at| now + EXPIRY_MARGIN < expires_at)
+            {
+                return Ok(cached.access_token.clone());
+            }
+        }
+
+        let client_secret = context.resolve(&oauth2.client_secret)?;
+        let mut token_context = context.clone();
+        token_context.set(CLIENT_SECRET_VARS[0].to_string(), client_secret.clone());
+        token_context.set(
+            CLIENT_SECRET_VARS[1].to_string(),
+            url::form_urlencoded::byte_serialize(client_secret.as_bytes()).collect(),
+        );
+        let token_context = token_context.with_secrets(CLIENT_SECRET_VARS);
+
+        let mut form = url::form_urlencoded::Serializer::new(String::new());
+        form.append_pair("grant_type", "client_credentials")
+            .append_pair("client_id", &key.client_id)
+            .append_pair("client_secret", &client_secret);
+        if !key.scope.is_empty() {
+            form.append_pair("scope", &key.scope);
+        }
+        let request = Request {
+            compression: None,
+            method: "POST".to_string(),
+            url: key.token_url.clone(),
+            headers: Some(HashMap::from([
+                (
+                    "Content-Type".to_string(),
+                    "application/x-www-form-urlencoded".to_string(),
+                ),
+                ("Accept".to_string(), "application/json".to_string()),
+            ])),
+            params: None,
+            body: Some(form.finish()),
+            grpc: None,
+            follow_redirects: None,
+        };
+        let (status, body) = executor
+            .send("Auth", &request, &token_context, |body| {
+                serde_json::from_str::<TokenResponse>(body)
+                    .ok()
+                    .map(|response| response.access_token)
+            })
+            .await
+            .with_context(|| format!("Token request to {} failed", key.token_url))?;
+        if !(200..300).contains(&status) {
+            anyhow::bail!(
+                "Token request to {} returned {}: {}",
+                key.token_url,
+                status,
+                token_context.redact(&truncate(body.trim(), 200))
+            );
+        }
+        let response: TokenResponse = serde_json::from_str(&body).with_context(|| {
+            format!(
+                "Token response from {} has no access_token: {}",
+                key.token_url,
+                truncate(body.trim(), 200)
+            )
+        })?;
+
+        let expires_at = response
+            .expires_in
+            .map(|seconds| now + Duration::from_secs(seconds));
+        let access_token = response.access_token.clone();
+        tokens.insert(
+            key,
+            CachedToken {
+                access_token: response.access_token,
+                expires_at,
+            },
+        );
+        Ok(access_token)
+    }
+}
+
+/// `config` with `Authorization: {{RIVET_AUTHORIZATION}}` on every step that
+/// doesn't set its own, as a header or as gRPC metadata.
+pub fn authorize_steps(config: &RivetConfig) -> RivetConfig {
+    let mut config = config.clone();
+    let value = format!("{{{{{}}}}}", AUTHORIZATION_VAR);
+    for step in config.steps_mut() {
+        let (headers, name) = match &mut step.request.grpc {
+            Some(grpc) => (&mut grpc.metadata, "authorization"),
+            None => (&mut step.request.headers, "Authorization"),
+        };
+        let headers = headers.get_or_insert_with(HashMap::new);
+        if !headers
+            .keys()
+            .any(|key| key.eq_ignore_ascii_case("authorization"))
+        {
+            headers.insert(name.to_string(), value.clone());
+        }
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BasicAuth;
+    use crate::utils::ClientOptions;
+
+    fn executor() -> RequestExecutor {
+        RequestExecutor::new(Duration::from_secs(5), &ClientOptions::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bearer_and_basic_authorization() {
+        let mut context = VariableContext::new();
+        context.set("TOKEN".to_string(), "abc123".to_string());
+        let cache = TokenCache::default();
+
+        let bearer = AuthConfig {
+            bearer: Some("{{TOKEN}}".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            cache
+                .authorization(&bearer, &context, &executor())
+                .await
+                .unwrap(),
+            "Bearer abc123"
+        );
+
+        let basic = AuthConfig {
+            basic: Some(BasicAuth {
+                user: "aladdin".to_string(),
+                password: "opensesame".to_string(),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            cache
+                .authorization(&basic, &context, &executor())
+                .await
+                .unwrap(),
+            "Basic YWxhZGRpbjpvcGVuc2VzYW1l"
+        );
+
+        let both = AuthConfig {
+            bearer: bearer.bearer.clone(),
+            basic: basic.basic.clone(),
+            ..Default::default()
+        };
+        let err = cache
+            .authorization(&both, &context, &executor())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "auth must set exactly one of bearer, basic and oauth2_client_credentials"
+        );
+    }
+
+    #[test]
+    fn test_authorize_steps_keeps_explicit_headers() {
+        let mut config: RivetConfig = serde_yaml::from_str(
+            r#"
+name: Auth
+tests:
+  - name: Default
+    request:
+      method: GET
+      url: http://localhost/a
+  - name: Own header
+    request:
+      method: GET
+      url: http://localhost/b
+      headers:
+        authorization: Bearer other
+  - name: gRPC
+    request:
+      grpc:
+        server: http://localhost:50051
+        proto: protos
+        call: greeter.Greeter/SayHello
+"#,
+        )
+        .unwrap();
+        config = authorize_steps(&config);
+
+        let headers = |index: usize| config.tests[index].request.headers.clone().unwrap();
+        assert_eq!(headers(0)["Authorization"], "{{RIVET_AUTHORIZATION}}");
+        assert_eq!(headers(1).len(), 1);
+        assert_eq!(headers(1)["authorization"], "Bearer other");
+        let grpc = config.tests[2].request.grpc.as_ref().unwrap();
+        assert_eq!(
+            grpc.metadata.as_ref().unwrap()["authorization"],
+            "{{RIVET_AUTHORIZATION}}"
+        );
+        assert!(config.tests[2].request.headers.is_none());
+    }
+}
@@ -0,0 +1,268 @@
+use crate::config::{RivetConfig, TestStep};
+use crate::runner::variables::VariableContext;
+use anyhow::{Context, Result};
+use url::Url;
+
+/// Name of the variable generated and imported suites build their URLs on.
+pub const BASE_URL_VAR: &str = "baseUrl";
+
+/// Point a suite at `base_url`.
+///
+/// The `baseUrl` variable is overridden, and absolute request URLs that share
+/// an origin with the suite's declared `baseUrl` are rewritten onto the new
+/// base, so collections with hardcoded hosts are retargeted too.
+pub fn retarget_suite(config: &RivetConfig, base_url: &str) -> Result<RivetConfig> {
+    let declared = VariableContext::new()
+        .with_env_vars()
+        .with_config_vars(config.vars.as_ref())?
+        .vars
+        .get(BASE_URL_VAR)
+        .cloned();
+
+    let mut config = config.clone();
+    config
+        .vars
+        .get_or_insert_with(Default::default)
+        .insert(BASE_URL_VAR.to_string(), base_url.to_string());
+
+    if let Some(declared) = declared {
+        for steps in [&mut config.setup, &mut config.teardown]
+            .into_iter()
+            .flatten()
+        {
+            rewrite_steps(steps, &declared, base_url);
+        }
+        rewrite_steps(&mut config.tests, &declared, base_url);
+    }
+
+    Ok(config)
+}
+
+fn rewrite_steps(steps: &mut [TestStep], declared: &str, base_url: &str) {
+    for step in steps {
+        if let Some(url) = rewrite_url(&step.request.url, declared, base_url) {
+            step.request.url = url;
+        }
+    }
+}
+
+/// Rewrite `url` onto `base_url` when it is absolute and has the same origin as `declared`.
+pub fn rewrite_url(url: &str, declared: &str, base_url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let declared_url = Url::parse(declared).ok()?;
+    if parsed.origin() != declared_url.origin() {
+        return None;
+    }
+
+    let base_url = base_url.trim_end_matches('/');
+    let declared = declared.trim_end_matches('/');
+    if let Some(rest) = url.strip_prefix(declared) {
+        if rest.is_empty() || rest.starts_with(['/', '?', '#']) {
+            return Some(format!("{}{}", base_url, rest));
+        }
+    }
+
+    // Same host but outside the declared path: keep the path, swap the origin
+    let base = Url::parse(base_url).ok()?;
+    let origin = base.origin().ascii_serialization();
+    Some(format!(
+        "{}{}",
+        origin,
+        &parsed[url::Position::BeforePath..]
+    ))
+}
+
+/// Whether `url` starts with a scheme such as `https://`; anything else is
+/// relative to the suite's `baseUrl`.
+pub fn has_scheme(url: &str) -> bool {
+    url.split_once("://").is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Resolve a request URL: absolute ones as they are, relative ones under
+/// `base_url` and then `base_path`, as directories, so `users`, `/users` and
+/// `../v1/users` all resolve as they would in a browser on `base_path/`.
+pub fn join_url(base_url: &str, base_path: Option<&str>, url: &str) -> Result<Url> {
+    if has_scheme(url) {
+        return Url::parse(url).with_context(|| format!("Invalid URL: {}", url));
+    }
+    let mut base =
+        Url::parse(base_url).with_context(|| format!("Invalid {}: {}", BASE_URL_VAR, base_url))?;
+    if base.cannot_be_a_base() {
+        anyhow::bail!("Invalid {}: {}", BASE_URL_VAR, base_url);
+    }
+
+    let mut path = base.path().trim_end_matches('/').to_string();
+    if let Some(base_path) = base_path
+        .map(|p| p.trim_matches('/'))
+        .filter(|p| !p.is_empty())
+    {
+        path.push('/');
+        path.push_str(base_path);
+    }
+    // Only a query or fragment stays on the base path itself
+    let relative = url.trim_start_matches('/');
+    if relative.is_empty() || relative.starts_with(['?', '#']) {
+        if path.is_empty() {
+            path.push('/');
+        }
+    } else {
+        path.push('/');
+    }
+    base.set_path(&path);
+    base.set_query(None);
+    base.set_fragment(None);
+
+    base.join(relative)
+        .with_context(|| format!("Invalid URL: {}", url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn join(base_url: &str, base_path: Option<&str>, url: &str) -> String {
+        join_url(base_url, base_path, url).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_join_url_slash_permutations() {
+        for base_url in ["http://api.test", "http://api.test/"] {
+            for base_path in ["api/v2", "/api/v2", "/api/v2/", "api/v2/"] {
+                for url in ["users/7", "/users/7"] {
+                    assert_eq!(
+                        join(base_url, Some(base_path), url),
+                        "http://api.test/api/v2/users/7",
+                        "{} + {} + {}",
+                        base_url,
+                        base_path,
+                        url
+                    );
+                }
+            }
+        }
+        // A base URL with a path of its own keeps it
+        assert_eq!(
+            join("http://api.test/gateway/", Some("/v2"), "/users"),
+            "http://api.test/gateway/v2/users"
+        );
+        assert_eq!(
+            join("http://api.test/gateway", None, "users"),
+            "http://api.test/gateway/users"
+        );
+        assert_eq!(
+            join("http://api.test", Some("/"), "/users"),
+            "http://api.test/users"
+        );
+        assert_eq!(join("http://api.test", None, ""), "http://api.test/");
+        assert_eq!(
+            join("http://api.test", Some("/v2/"), ""),
+            "http://api.test/v2"
+        );
+    }
+
+    #[test]
+    fn test_join_url_keeps_queries_and_dot_segments() {
+        assert_eq!(
+            join("http://api.test", Some("/v2"), "/users?page=2&sort=name"),
+            "http://api.test/v2/users?page=2&sort=name"
+        );
+        assert_eq!(
+            join("http://api.test", Some("/v2"), "?page=2"),
+            "http://api.test/v2?page=2"
+        );
+        assert_eq!(
+            join("http://api.test?key=1", Some("/v2"), "users#top"),
+            "http://api.test/v2/users#top"
+        );
+        assert_eq!(
+            join("http://api.test", Some("/api/v2"), "../v1/users"),
+            "http://api.test/api/v1/users"
+        );
+    }
+
+    #[test]
+    fn test_join_url_leaves_absolute_urls() {
+        assert_eq!(
+            join("http://api.test", Some("/v2"), "https://other.test/users"),
+            "https://other.test/users"
+        );
+        assert_eq!(
+            join(
+                "http://api.test",
+                Some("/v2"),
+                "/redirect?to=http://other.test"
+            ),
+            "http://api.test/v2/redirect?to=http://other.test"
+        );
+        assert!(join_url("not a url", None, "/users").is_err());
+        assert!(has_scheme("grpc+tls://localhost:50051"));
+        assert!(!has_scheme("localhost:8080/users"));
+        assert!(!has_scheme("{{baseUrl}}/users"));
+    }
+
+    #[test]
+    fn test_rewrite_url_under_declared_base() {
+        assert_eq!(
+            rewrite_url(
+                "https://api.example.com/v1/users?limit=5",
+                "https://api.example.com/v1",
+                "http://localhost:8080/api/"
+            ),
+            Some("http://localhost:8080/api/users?limit=5".to_string())
+        );
+        assert_eq!(
+            rewrite_url(
+                "https://api.example.com/v1",
+                "https://api.example.com/v1/",
+                "http://localhost:8080"
+            ),
+            Some("http://localhost:8080".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rewrite_url_same_origin_other_path() {
+        assert_eq!(
+            rewrite_url(
+                "https://api.example.com/health",
+                "https://api.example.com/v1",
+                "http://localhost:8080/v2"
+            ),
+            Some("http://localhost:8080/health".to_string())
+        );
+        // A path that merely shares a prefix isn't under the declared base
+        assert_eq!(
+            rewrite_url(
+                "https://api.example.com/v10/users",
+                "https://api.example.com/v1",
+                "http://localhost:8080"
+            ),
+            Some("http://localhost:8080/v10/users".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rewrite_url_leaves_other_origins_and_templates() {
+        assert_eq!(
+            rewrite_url(
+                "https://auth.example.com/token",
+                "https://api.example.com",
+                "http://localhost:8080"
+            ),
+            None
+        );
+        assert_eq!(
+            rewrite_url(
+                "{{baseUrl}}/users",
+                "https://api.example.com",
+                "http://localhost:8080"
+            ),
+            None
+        );
+    }
+}
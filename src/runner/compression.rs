@@ -0,0 +1,155 @@
+//! Decoding compressed response bodies for steps' `compression:`.
+
+use brotli_decompressor::DecompressorWriter;
+use flate2::write::GzDecoder;
+use std::io::{self, Write};
+
+/// Passes a response body on to `W` as its chunks arrive, decoded by its
+/// `Content-Encoding`. Bodies in other encodings pass through as sent.
+pub struct BodyDecoder<W: Write> {
+    encoding: Encoding,
+    /// Set up by the first bytes, so an empty body, e.g. of a `HEAD`, has
+    /// nothing to decode; `None` only while switching
+    decoder: Option<Decoder<W>>,
+    /// Bytes written so far
+    received: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Identity,
+    Gzip,
+    Brotli,
+}
+
+enum Decoder<W: Write> {
+    Plain(W),
+    Gzip(GzDecoder<W>),
+    Brotli(Box<DecompressorWriter<W>>),
+}
+
+impl<W: Write> BodyDecoder<W> {
+    pub fn new(content_encoding: Option<&str>, sink: W) -> Self {
+        let encoding = content_encoding.map(|encoding| encoding.trim().to_ascii_lowercase());
+        let encoding = match encoding.as_deref() {
+            Some("gzip" | "x-gzip") => Encoding::Gzip,
+            Some("br") => Encoding::Brotli,
+            _ => Encoding::Identity,
+        };
+        Self {
+            encoding,
+            decoder: Some(Decoder::Plain(sink)),
+            received: 0,
+        }
+    }
+
+    /// Bytes of the body as sent, before decoding.
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// The decoded bytes so far, e.g. to move them on as the body streams in.
+    pub fn get_mut(&mut self) -> &mut W {
+        match self.decoder.as_mut().expect("decoder in place") {
+            Decoder::Plain(sink) => sink,
+            Decoder::Gzip(decoder) => decoder.get_mut(),
+            Decoder::Brotli(decoder) => decoder.get_mut(),
+        }
+    }
+
+    /// Decode the rest of the body and return the sink. A truncated or
+    /// corrupt body is an error.
+    pub fn finish(self) -> io::Result<W> {
+        match self.decoder.expect("decoder in place") {
+            Decoder::Plain(sink) => Ok(sink),
+            Decoder::Gzip(decoder) => decoder.finish(),
+            Decoder::Brotli(mut decoder) => {
+                decoder.close()?;
+                decoder.into_inner().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "incomplete brotli stream")
+                })
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for BodyDecoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.received == 0 && !buf.is_empty() {
+            if let Some(Decoder::Plain(sink)) = self.decoder.take() {
+                self.decoder = Some(match self.encoding {
+                    Encoding::Identity => Decoder::Plain(sink),
+                    Encoding::Gzip => Decoder::Gzip(GzDecoder::new(sink)),
+                    Encoding::Brotli => {
+                        Decoder::Brotli(Box::new(DecompressorWriter::new(sink, 4096)))
+                    }
+                });
+            }
+        }
+        let written = match self.decoder.as_mut().expect("decoder in place") {
+            Decoder::Plain(sink) => sink.write(buf),
+            Decoder::Gzip(decoder) => decoder.write(buf),
+            Decoder::Brotli(decoder) => decoder.write(buf),
+        }?;
+        self.received += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.decoder.as_mut().expect("decoder in place") {
+            Decoder::Plain(sink) => sink.flush(),
+            Decoder::Gzip(decoder) => decoder.flush(),
+            Decoder::Brotli(decoder) => decoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn decode(encoding: Option<&str>, chunks: &[&[u8]]) -> io::Result<Vec<u8>> {
+        let mut decoder = BodyDecoder::new(encoding, Vec::new());
+        for chunk in chunks {
+            decoder.write_all(chunk)?;
+        }
+        decoder.finish()
+    }
+
+    #[test]
+    fn test_gzip_decodes_across_chunks() {
+        let body = br#"{"items": [1, 2, 3]}"#.repeat(50);
+        let encoded = gzip(&body);
+        let (first, rest) = encoded.split_at(7);
+        assert_eq!(decode(Some("gzip"), &[first, rest]).unwrap(), body);
+        let mut decoder = BodyDecoder::new(Some("gzip"), Vec::new());
+        decoder.write_all(&encoded).unwrap();
+        assert_eq!(decoder.received(), encoded.len() as u64);
+        assert_eq!(decode(Some(" GZIP "), &[&encoded]).unwrap(), body);
+    }
+
+    #[test]
+    fn test_other_encodings_pass_through() {
+        assert_eq!(decode(None, &[b"plain"]).unwrap(), b"plain");
+        assert_eq!(decode(Some("identity"), &[b"plain"]).unwrap(), b"plain");
+        // e.g. the empty body of a HEAD
+        assert_eq!(decode(Some("gzip"), &[]).unwrap(), b"");
+        assert_eq!(decode(Some("br"), &[]).unwrap(), b"");
+        assert_eq!(decode(Some("zstd"), &[b"raw"]).unwrap(), b"raw");
+    }
+
+    #[test]
+    fn test_corrupt_or_truncated_bodies_fail() {
+        let encoded = gzip(b"hello world");
+        assert!(decode(Some("gzip"), &[&encoded[..encoded.len() - 4]]).is_err());
+        assert!(decode(Some("gzip"), &[b"not gzip at all"]).is_err());
+        assert!(decode(Some("br"), &[b"not brotli at all"]).is_err());
+    }
+}
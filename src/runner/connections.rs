@@ -0,0 +1,42 @@
+//! Counting the connections a run's requests went over, so `-v` can show
+//! whether keep-alive is working.
+
+use hyper::client::connect::HttpInfo;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+/// Requests sent and connections they used. Clones share the counts, as the
+/// executors they belong to share a connection pool.
+#[derive(Clone, Default)]
+pub struct ConnectionStats {
+    counts: Arc<Mutex<Counts>>,
+}
+
+#[derive(Default)]
+struct Counts {
+    requests: usize,
+    /// A connection is told apart by its two ends
+    connections: HashSet<(SocketAddr, SocketAddr)>,
+}
+
+impl ConnectionStats {
+    /// Count a response, and the connection it came over when it's new.
+    pub fn record(&self, response: &reqwest::Response) {
+        let connection = response
+            .extensions()
+            .get::<HttpInfo>()
+            .map(|info| (info.local_addr(), info.remote_addr()));
+        let mut counts = self.counts.lock().unwrap();
+        counts.requests += 1;
+        counts.connections.extend(connection);
+    }
+
+    pub fn requests(&self) -> usize {
+        self.counts.lock().unwrap().requests
+    }
+
+    pub fn connections_opened(&self) -> usize {
+        self.counts.lock().unwrap().connections.len()
+    }
+}
@@ -1,13 +1,398 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
 use std::collections::HashMap;
 use std::path::Path;
+use std::str::FromStr;
 use tokio::fs;
 
-pub async fn load_csv_data(file_path: &Path) -> Result<Vec<HashMap<String, String>>> {
+/// One dataset row: variable name to value.
+pub type DataRow = HashMap<String, String>;
+
+/// File formats a dataset can be loaded from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataFormat {
+    Csv,
+    /// A JSON array of objects
+    Json,
+    /// One JSON object per line
+    JsonLines,
+}
+
+impl FromStr for DataFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(DataFormat::Csv),
+            "json" => Ok(DataFormat::Json),
+            "jsonl" | "ndjson" => Ok(DataFormat::JsonLines),
+            _ => Err(anyhow!(
+                "Unknown dataset format '{}'. Use: csv, json, jsonl",
+                s
+            )),
+        }
+    }
+}
+
+impl DataFormat {
+    /// Pick the format from the file extension.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        extension.parse().map_err(|_| {
+            anyhow!(
+                "Can't tell the format of dataset {} from its extension; set `format: csv|json|jsonl`",
+                path.display()
+            )
+        })
+    }
+}
+
+/// Load a dataset, using `format` when given and the file extension otherwise.
+///
+/// Rows are numbered from 1 in every format, and errors name the row.
+pub async fn load_data(file_path: &Path, format: Option<&str>) -> Result<Vec<DataRow>> {
+    let format = match format {
+        Some(format) => format.parse()?,
+        None => DataFormat::from_path(file_path)?,
+    };
+
     let content = fs::read_to_string(file_path)
         .await
-        .with_context(|| format!("Failed to read CSV file: {}", file_path.display()))?;
+        .with_context(|| format!("Failed to read dataset file: {}", file_path.display()))?;
+
+    match format {
+        DataFormat::Csv => parse_csv(&content),
+        DataFormat::Json => parse_json(&content),
+        DataFormat::JsonLines => parse_json_lines(&content),
+    }
+}
+
+/// Which dataset rows to run: an optional filter, then an optional sample.
+#[derive(Debug, Clone, Default)]
+pub struct DataSelection {
+    pub filter: Option<String>,
+    pub sample: Option<String>,
+    /// Seed for sampling, so the same rows are picked on every run
+    pub seed: u64,
+}
+
+impl DataSelection {
+    /// Check the filter and sample size without loading any data.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(filter) = &self.filter {
+            filter.parse::<RowFilter>()?;
+        }
+        if let Some(sample) = &self.sample {
+            sample.parse::<SampleSize>()?;
+        }
+        Ok(())
+    }
+
+    /// Apply the selection to loaded rows, keeping each row's 1-based number.
+    pub fn apply(&self, rows: Vec<DataRow>) -> Result<Vec<(usize, DataRow)>> {
+        let mut selected = Vec::new();
+        match &self.filter {
+            Some(filter) => {
+                let filter: RowFilter = filter.parse()?;
+                for (i, row) in rows.into_iter().enumerate() {
+                    if filter.matches(&row, i + 1)? {
+                        selected.push((i + 1, row));
+                    }
+                }
+            }
+            None => selected.extend(rows.into_iter().enumerate().map(|(i, row)| (i + 1, row))),
+        }
+
+        if let Some(sample) = &self.sample {
+            let size = sample.parse::<SampleSize>()?.of(selected.len());
+            selected = sample_rows(selected, size, self.seed);
+        }
+
+        Ok(selected)
+    }
+
+    /// Whether rows may be dropped, i.e. whether a summary line is worth printing.
+    pub fn is_active(&self) -> bool {
+        self.filter.is_some() || self.sample.is_some()
+    }
+
+    /// `filter: ..., seed: ...` for the "using N of M rows" line.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(filter) = &self.filter {
+            parts.push(format!("filter: {}", filter));
+        }
+        if let Some(sample) = &self.sample {
+            parts.push(format!("sample: {}", sample));
+            parts.push(format!("seed: {}", self.seed));
+        }
+        parts.join(", ")
+    }
+}
+
+/// A row filter such as `country == "US" && age >= 18`.
+///
+/// Conditions compare a column to a quoted string or bare value with
+/// `==`, `!=`, `<`, `<=`, `>` or `>=` (numerically when both sides are
+/// numbers). `&&` binds tighter than `||`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowFilter {
+    /// Alternatives (`||`), each a list of conditions that must all hold (`&&`)
+    any: Vec<Vec<Condition>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    column: String,
+    op: CompareOp,
+    value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl FromStr for RowFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let any = split_outside_quotes(s, "||")
+            .iter()
+            .map(|alternative| {
+                split_outside_quotes(alternative, "&&")
+                    .iter()
+                    .map(|condition| parse_condition(condition))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Invalid dataset filter '{}'", s))?;
+        Ok(RowFilter { any })
+    }
+}
+
+impl RowFilter {
+    /// Whether `row` passes. Referencing a column the row doesn't have is an error.
+    pub fn matches(&self, row: &DataRow, row_number: usize) -> Result<bool> {
+        for all in &self.any {
+            let mut passed = true;
+            for condition in all {
+                let actual = row.get(&condition.column).ok_or_else(|| {
+                    anyhow!(
+                        "Dataset filter column '{}' not found in row {}",
+                        condition.column,
+                        row_number
+                    )
+                })?;
+                if !condition.op.compare(actual, &condition.value) {
+                    passed = false;
+                    break;
+                }
+            }
+            if passed {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl CompareOp {
+    fn compare(self, actual: &str, expected: &str) -> bool {
+        let ordering = match (actual.trim().parse::<f64>(), expected.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.partial_cmp(&b),
+            _ => Some(actual.cmp(expected)),
+        };
+        let Some(ordering) = ordering else {
+            return false;
+        };
+        match self {
+            CompareOp::Eq => ordering.is_eq(),
+            CompareOp::Ne => ordering.is_ne(),
+            CompareOp::Lt => ordering.is_lt(),
+            CompareOp::Le => ordering.is_le(),
+            CompareOp::Gt => ordering.is_gt(),
+            CompareOp::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+/// The first operator in `condition` outside quotes, longest first where
+/// they overlap, so `<=` isn't read as `<` and a quoted `==` stays part of
+/// the value.
+fn find_operator(condition: &str) -> Option<(usize, &'static str, CompareOp)> {
+    const OPS: [(&str, CompareOp); 6] = [
+        ("<=", CompareOp::Le),
+        (">=", CompareOp::Ge),
+        ("==", CompareOp::Eq),
+        ("!=", CompareOp::Ne),
+        ("<", CompareOp::Lt),
+        (">", CompareOp::Gt),
+    ];
+
+    let mut quote = None;
+    for (i, c) in condition.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None => {
+                let rest = &condition[i..];
+                if let Some((symbol, op)) = OPS.iter().find(|(symbol, _)| rest.starts_with(symbol))
+                {
+                    return Some((i, symbol, *op));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn parse_condition(condition: &str) -> Result<Condition> {
+    let Some((at, symbol, op)) = find_operator(condition) else {
+        bail!(
+            "'{}' has no comparison (use ==, !=, <, <=, >, >=)",
+            condition.trim()
+        );
+    };
+    let column = condition[..at].trim();
+    let value = condition[at + symbol.len()..].trim();
+    if column.is_empty() || value.is_empty() {
+        bail!(
+            "expected `column {} value`, got '{}'",
+            symbol,
+            condition.trim()
+        );
+    }
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value);
+    Ok(Condition {
+        column: column.to_string(),
+        op,
+        value: value.to_string(),
+    })
+}
+
+/// Split on `separator` where it isn't inside a quoted string.
+fn split_outside_quotes<'a>(s: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    let mut i = 0;
+    while i < s.len() {
+        let c = s[i..].chars().next().unwrap_or_default();
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if s[i..].starts_with(separator) => {
+                parts.push(&s[start..i]);
+                i += separator.len();
+                start = i;
+                continue;
+            }
+            None => {}
+        }
+        i += c.len_utf8();
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// How many rows to sample: a count (`50`) or a share of the rows (`10%`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleSize {
+    Count(usize),
+    Percent(f64),
+}
+
+impl FromStr for SampleSize {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let invalid = || {
+            anyhow!(
+                "Invalid dataset sample '{}'. Use a row count like 50 or a percentage like 10%",
+                s
+            )
+        };
+        match s.strip_suffix('%') {
+            Some(percent) => {
+                let percent: f64 = percent.trim().parse().map_err(|_| invalid())?;
+                if !(0.0..=100.0).contains(&percent) {
+                    return Err(invalid());
+                }
+                Ok(SampleSize::Percent(percent))
+            }
+            None => s.parse().map(SampleSize::Count).map_err(|_| invalid()),
+        }
+    }
+}
+
+impl SampleSize {
+    /// Number of rows to keep out of `total`; a non-zero percentage keeps at least one.
+    pub fn of(self, total: usize) -> usize {
+        match self {
+            SampleSize::Count(count) => count.min(total),
+            SampleSize::Percent(percent) => {
+                ((total as f64 * percent / 100.0).ceil() as usize).min(total)
+            }
+        }
+    }
+}
+
+/// Pick `size` rows with a seeded shuffle, returned in their original order.
+fn sample_rows(rows: Vec<(usize, DataRow)>, size: usize, seed: u64) -> Vec<(usize, DataRow)> {
+    if size >= rows.len() {
+        return rows;
+    }
+
+    let mut indices: Vec<usize> = (0..rows.len()).collect();
+    let mut rng = SplitMix64(seed);
+    // Partial Fisher-Yates: the first `size` slots end up uniformly chosen
+    for i in 0..size {
+        let j = i + (rng.next_u64() % (indices.len() - i) as u64) as usize;
+        indices.swap(i, j);
+    }
+    let mut chosen = indices[..size].to_vec();
+    chosen.sort_unstable();
+
+    let mut keep = vec![false; rows.len()];
+    for i in chosen {
+        keep[i] = true;
+    }
+    rows.into_iter()
+        .zip(keep)
+        .filter_map(|(row, keep)| keep.then_some(row))
+        .collect()
+}
+
+/// Small deterministic PRNG; sampling only needs reproducibility, not quality.
+pub struct SplitMix64(pub u64);
+
+impl SplitMix64 {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
 
+fn parse_csv(content: &str) -> Result<Vec<DataRow>> {
     let mut reader = csv::Reader::from_reader(content.as_bytes());
     let headers = reader
         .headers()
@@ -15,8 +400,8 @@ pub async fn load_csv_data(file_path: &Path) -> Result<Vec<HashMap<String, Strin
         .clone();
 
     let mut data = Vec::new();
-    for record in reader.records() {
-        let record = record.context("Failed to parse CSV record")?;
+    for (i, record) in reader.records().enumerate() {
+        let record = record.with_context(|| format!("Failed to parse CSV row {}", i + 1))?;
         let mut row = HashMap::new();
 
         for (i, value) in record.iter().enumerate() {
@@ -32,3 +417,272 @@ pub async fn load_csv_data(file_path: &Path) -> Result<Vec<HashMap<String, Strin
 
     Ok(data)
 }
+
+fn parse_json(content: &str) -> Result<Vec<DataRow>> {
+    let value: Value = serde_json::from_str(content).context("Failed to parse JSON dataset")?;
+    let Value::Array(items) = value else {
+        bail!("JSON dataset must be an array of objects");
+    };
+
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| json_row(item, i + 1))
+        .collect()
+}
+
+fn parse_json_lines(content: &str) -> Result<Vec<DataRow>> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            let item: Value = serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse JSON on row {}", i + 1))?;
+            json_row(item, i + 1)
+        })
+        .collect()
+}
+
+fn json_row(item: Value, row_number: usize) -> Result<DataRow> {
+    let Value::Object(fields) = item else {
+        bail!(
+            "Row {} must be a JSON object, got {}",
+            row_number,
+            json_type(&item)
+        );
+    };
+
+    let mut row = HashMap::new();
+    flatten_into(&mut row, "", fields);
+    Ok(row)
+}
+
+/// Flatten nested objects into dot-separated keys (`user.name`). Other
+/// values become strings; arrays are kept as JSON text.
+fn flatten_into(row: &mut DataRow, prefix: &str, fields: serde_json::Map<String, Value>) {
+    for (key, value) in fields {
+        let key = if prefix.is_empty() {
+            key
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            Value::Object(nested) => flatten_into(row, &key, nested),
+            Value::String(s) => {
+                row.insert(key, s);
+            }
+            Value::Null => {
+                row.insert(key, String::new());
+            }
+            other => {
+                row.insert(key, other.to_string());
+            }
+        }
+    }
+}
+
+pub(crate) fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn load(name: &str, content: &str, format: Option<&str>) -> Result<Vec<DataRow>> {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        load_data(&path, format).await
+    }
+
+    #[tokio::test]
+    async fn test_load_csv() {
+        let rows = load("users.csv", "id,name\n1,Alice\n2,Bob\n", None)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["name"], "Bob");
+    }
+
+    #[tokio::test]
+    async fn test_load_json_flattens_nested_rows() {
+        let rows = load(
+            "users.json",
+            r#"[
+                {"id": 1, "active": true, "user": {"name": "Alice", "address": {"city": "Oslo"}}, "tags": ["a", "b"], "note": null},
+                {"id": 2, "user": {"name": "Bob"}}
+            ]"#,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["id"], "1");
+        assert_eq!(rows[0]["active"], "true");
+        assert_eq!(rows[0]["user.name"], "Alice");
+        assert_eq!(rows[0]["user.address.city"], "Oslo");
+        assert_eq!(rows[0]["tags"], r#"["a","b"]"#);
+        assert_eq!(rows[0]["note"], "");
+        assert_eq!(rows[1]["user.name"], "Bob");
+    }
+
+    #[tokio::test]
+    async fn test_load_json_lines() {
+        let rows = load(
+            "users.jsonl",
+            "{\"id\": 1, \"name\": \"Alice\"}\n\n{\"id\": 2, \"name\": \"Bob\"}\n",
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["id"], "2");
+    }
+
+    #[tokio::test]
+    async fn test_format_override_for_ambiguous_extension() {
+        let rows = load("users.data", r#"[{"id": 7}]"#, Some("json"))
+            .await
+            .unwrap();
+        assert_eq!(rows[0]["id"], "7");
+
+        let err = load("users.data", "id\n7\n", None).await.unwrap_err();
+        assert!(err.to_string().contains("set `format: csv|json|jsonl`"));
+    }
+
+    fn rows(countries: &[&str]) -> Vec<DataRow> {
+        countries
+            .iter()
+            .enumerate()
+            .map(|(i, country)| {
+                HashMap::from([
+                    ("id".to_string(), (i + 1).to_string()),
+                    ("country".to_string(), country.to_string()),
+                ])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_expressions() {
+        let data = rows(&["US", "DE", "US", "FR"]);
+        let matching = |filter: &str| -> Vec<usize> {
+            let filter: RowFilter = filter.parse().unwrap();
+            data.iter()
+                .enumerate()
+                .filter(|(i, row)| filter.matches(row, i + 1).unwrap())
+                .map(|(i, _)| i + 1)
+                .collect()
+        };
+
+        assert_eq!(matching(r#"country == "US""#), vec![1, 3]);
+        assert_eq!(matching("country != US"), vec![2, 4]);
+        assert_eq!(matching(r#"country == "US" && id > 1"#), vec![3]);
+        assert_eq!(matching("country == 'FR' || id <= 1"), vec![1, 4]);
+        // Numbers compare numerically
+        assert_eq!(matching("id >= 2 && id < 4"), vec![2, 3]);
+        // Operators inside quotes are part of the value
+        assert!(matching(r#"country == "a && b""#).is_empty());
+        assert_eq!(matching(r#"country < "US==""#), vec![1, 2, 3, 4]);
+        assert_eq!(matching("country>=US"), vec![1, 3]);
+
+        let condition = parse_condition("id<=2").unwrap();
+        assert_eq!(
+            (condition.column.as_str(), condition.op),
+            ("id", CompareOp::Le)
+        );
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        let err = "country".parse::<RowFilter>().unwrap_err();
+        assert!(format!("{:#}", err).contains("has no comparison"));
+
+        let filter: RowFilter = "contry == US".parse().unwrap();
+        let err = filter.matches(&rows(&["US"])[0], 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Dataset filter column 'contry' not found in row 1"
+        );
+    }
+
+    #[test]
+    fn test_sample_size() {
+        assert_eq!("50".parse::<SampleSize>().unwrap().of(5000), 50);
+        assert_eq!("50".parse::<SampleSize>().unwrap().of(10), 10);
+        assert_eq!("10%".parse::<SampleSize>().unwrap().of(5000), 500);
+        assert_eq!("1%".parse::<SampleSize>().unwrap().of(5), 1);
+        assert!("ten".parse::<SampleSize>().is_err());
+        assert!("150%".parse::<SampleSize>().is_err());
+    }
+
+    #[test]
+    fn test_sampling_is_deterministic_per_seed() {
+        let data = rows(&["US"; 100]);
+        let pick = |seed| {
+            DataSelection {
+                filter: None,
+                sample: Some("10".to_string()),
+                seed,
+            }
+            .apply(data.clone())
+            .unwrap()
+            .into_iter()
+            .map(|(n, _)| n)
+            .collect::<Vec<_>>()
+        };
+
+        let first = pick(42);
+        assert_eq!(first.len(), 10);
+        assert_eq!(first, pick(42));
+        assert_ne!(first, pick(7));
+        // Original row numbers, in order
+        assert!(first.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_filter_then_sample() {
+        let selection = DataSelection {
+            filter: Some(r#"country == "US""#.to_string()),
+            sample: Some("50%".to_string()),
+            seed: 1,
+        };
+        let selected = selection
+            .apply(rows(&["US", "DE", "US", "US", "FR"]))
+            .unwrap();
+        assert_eq!(selected.len(), 2);
+        assert!(selected.iter().all(|(_, row)| row["country"] == "US"));
+        assert_eq!(
+            selection.describe(),
+            r#"filter: country == "US", sample: 50%, seed: 1"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_errors_name_the_row() {
+        let err = load("bad.json", r#"[{"id": 1}, 42]"#, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Row 2 must be a JSON object, got a number");
+
+        let err = load("bad.jsonl", "{\"id\": 1}\n{oops\n", None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Failed to parse JSON on row 2");
+
+        let err = load("bad.csv", "id,name\n1,Alice\n2,Bob,extra\n", None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Failed to parse CSV row 2");
+    }
+}
@@ -0,0 +1,127 @@
+use crate::runner::executor::PollSummary;
+use serde::Serialize;
+use std::time::Duration;
+
+/// How `rivet run` reports progress on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Colored console output (or plain text with `--ci`)
+    #[default]
+    Human,
+    /// One JSON event per line, see [`RunEvent`]
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "human" | "text" => Ok(OutputFormat::Human),
+            "json" => Ok(OutputFormat::Json),
+            _ => anyhow::bail!("Invalid output format '{}'. Use: human, json", s),
+        }
+    }
+}
+
+/// A line of `rivet run --output json`, written as soon as it happens.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RunEvent<'a> {
+    RunStarted {
+        target: &'a str,
+        env: Option<&'a str>,
+        /// Seed of a `--shuffle`d run
+        #[serde(skip_serializing_if = "Option::is_none")]
+        shuffle_seed: Option<u64>,
+    },
+    SuiteStarted {
+        suite: &'a str,
+    },
+    /// Nothing in the suite ran: no test matched `--grep`, or the run was cancelled
+    SuiteSkipped {
+        suite: &'a str,
+        reason: &'a str,
+    },
+    TestFinished {
+        name: &'a str,
+        status: TestStatus,
+        duration_ms: u64,
+        error: Option<&'a str>,
+        response_status: Option<u16>,
+        #[serde(skip_serializing_if = "<[String]>::is_empty")]
+        warnings: &'a [String],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        poll: Option<PollSummary>,
+    },
+    SuiteFinished {
+        suite: &'a str,
+        passed: usize,
+        failed: usize,
+        duration_ms: u64,
+    },
+    RunFinished {
+        total: usize,
+        passed: usize,
+        failed: usize,
+        skipped: usize,
+        duration_ms: u64,
+        reports: Vec<String>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+impl RunEvent<'_> {
+    /// Write the event as one line; stdout is line buffered, so it is
+    /// visible to whoever is reading the pipe straight away.
+    pub fn emit(&self) {
+        println!(
+            "{}",
+            serde_json::to_string(self).expect("run events always serialize")
+        );
+    }
+}
+
+pub fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_are_tagged() {
+        let event = RunEvent::TestFinished {
+            name: "Get user",
+            status: TestStatus::Failed,
+            duration_ms: 12,
+            error: Some("Expected status 200 but got 404"),
+            response_status: Some(404),
+            warnings: &[],
+            poll: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"test_finished","name":"Get user","status":"failed","duration_ms":12,"error":"Expected status 200 but got 404","response_status":404}"#
+        );
+    }
+
+    #[test]
+    fn test_output_format_parse() {
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!(
+            "Human".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Human
+        );
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+}
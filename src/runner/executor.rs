@@ -1,8 +1,31 @@
-use crate::config::{Expectation, Request, StatusExpectation};
+use crate::config::{
+    CaptureSource, Expectation, GrpcRequest, LengthExpectation, Poll, Request, StatusExpectation,
+};
+use crate::grpc::{self, GrpcClient, GrpcTlsOptions, StatusError};
+use crate::runner::assertions::{
+    self, is_json_media_type, media_type_matches, schema_errors, AssertionFailures,
+    InvalidJsonBody, LengthBound, StatusMismatch,
+};
+use crate::runner::base_url::{has_scheme, join_url, BASE_URL_VAR};
+use crate::runner::compression::BodyDecoder;
+use crate::runner::connections::ConnectionStats;
+use crate::runner::data::json_type;
+use crate::runner::failure::FailureKind;
+use crate::runner::log::{header_list, LoggedRequest, LoggedResponse, RequestLog};
+use crate::runner::parser::{describe_suite_path, resolve_suite_path};
 use crate::runner::variables::VariableContext;
+use crate::utils::{is_rivet_header, parse_timeout, ClientOptions, USER_AGENT};
 use anyhow::{Context, Result};
+use jsonschema::JSONSchema;
+use reqwest::redirect::Policy;
 use reqwest::{Client, Method, Response};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use url::Url;
 
@@ -10,29 +33,242 @@ use url::Url;
 pub struct TestResult {
     pub name: String,
     pub passed: bool,
+    /// Never ran, e.g. cancelled by `--bail`; counts as neither passed nor failed
+    pub skipped: bool,
     pub duration: Duration,
     pub error: Option<String>,
+    /// Console-only diff for failed JSONPath assertions; not written to reports.
+    pub error_diff: Option<String>,
     #[allow(dead_code)]
     pub response_status: Option<u16>,
     #[allow(dead_code)]
     pub response_body: Option<String>,
+    /// Size of the whole response body, decoded when the step set
+    /// `compression`; `response_body` may hold only part of it, or a
+    /// placeholder for binary content
+    pub response_size: Option<u64>,
+    /// URL the response came from, after any redirects were followed
+    pub final_url: Option<String>,
+    pub request_method: Option<String>,
+    pub request_url: Option<String>,
+    pub request_body: Option<String>,
+    pub response_headers: Option<HashMap<String, String>>,
+    /// Protocol the response came over, e.g. `HTTP/1.1`
+    pub http_version: Option<String>,
+    /// Request line, headers and body as sent; 0 when nothing was sent
+    pub bytes_sent: u64,
+    /// Status line, headers and body as received; 0 without a response
+    pub bytes_received: u64,
+    /// Why the test failed; `None` when it passed or was skipped
+    pub failure_kind: Option<FailureKind>,
+    /// Variables the step's `capture` set, for the steps after it
+    pub captures: HashMap<String, String>,
+    /// How `expect.poll` went, once the step's own response passed
+    pub poll: Option<PollSummary>,
+    /// Likely problems that don't fail the test, e.g. JSON assertions on a
+    /// response not declared as JSON
+    pub warnings: Vec<String>,
 }
 
+/// The [`FailureKind`] of an error from sending a request or reading its
+/// response. Errors that never reached the network, e.g. an unresolved
+/// variable, are setup failures.
+pub fn failure_kind(error: &anyhow::Error) -> FailureKind {
+    if let Some(failures) = error.downcast_ref::<AssertionFailures>() {
+        return failures.kind();
+    }
+    for cause in error.chain() {
+        if let Some(error) = cause.downcast_ref::<reqwest::Error>() {
+            return if error.is_timeout() {
+                FailureKind::Timeout
+            } else if error.is_builder() {
+                FailureKind::SetupFailure
+            } else {
+                FailureKind::ConnectionError
+            };
+        }
+        if cause.is::<tokio::time::error::Elapsed>() {
+            return FailureKind::Timeout;
+        }
+        if cause.is::<tonic::transport::Error>() {
+            return FailureKind::ConnectionError;
+        }
+    }
+    FailureKind::SetupFailure
+}
+
+/// The requests `expect.poll` made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollSummary {
+    pub attempts: u32,
+    /// Time from the first poll request to the last response
+    pub wait_ms: u64,
+}
+
+impl TestResult {
+    /// Mask secret values in every text field.
+    fn redacted(mut self, context: &VariableContext) -> Self {
+        if !context.has_secrets() {
+            return self;
+        }
+        let redact = |text: &mut Option<String>| {
+            if let Some(text) = text {
+                *text = context.redact(text);
+            }
+        };
+        self.name = context.redact(&self.name);
+        redact(&mut self.error);
+        redact(&mut self.error_diff);
+        redact(&mut self.response_body);
+        redact(&mut self.request_url);
+        redact(&mut self.final_url);
+        redact(&mut self.request_body);
+        for warning in &mut self.warnings {
+            *warning = context.redact(warning);
+        }
+        if let Some(headers) = &mut self.response_headers {
+            for value in headers.values_mut() {
+                *value = context.redact(value);
+            }
+        }
+        self
+    }
+}
+
+/// Sends steps' requests and checks their responses. Cheap to clone: clones
+/// share the HTTP clients, and so their connection pools.
 #[derive(Clone)]
 pub struct RequestExecutor {
     client: Client,
+    /// `client`'s settings, but redirects come back as responses
+    no_redirect_client: Client,
+    timeout: Duration,
+    /// TLS settings for `https://` gRPC servers
+    grpc_tls: GrpcTlsOptions,
+    log: RequestLog,
+    /// Response body bytes kept; the rest is read and counted but dropped
+    max_capture_bytes: usize,
+    connections: ConnectionStats,
+    /// Compiled `expect.schema`s, by the resolved reference
+    schemas: Arc<Mutex<HashMap<String, Arc<JSONSchema>>>>,
+    /// gRPC clients by proto directory and server, so steps calling the
+    /// same service compile its protos and connect once
+    grpc_clients: Arc<tokio::sync::Mutex<HashMap<(String, String), GrpcClient>>>,
+    /// Directory of the suite being run; relative schema files resolve against it
+    base_dir: PathBuf,
+    /// Suite name sent, with the step's, in `X-Rivet-Test`
+    identify: Option<String>,
+    /// The suite's `base_path`, under `baseUrl`, for relative request URLs
+    base_path: Option<String>,
 }
 
+/// Default for `max_capture_bytes`.
+pub const DEFAULT_MAX_CAPTURE_BYTES: usize = 1024 * 1024;
+
+/// Holds the secret [`RequestExecutor::send`] finds in a response body.
+const RESPONSE_SECRET_VAR: &str = "RIVET_RESPONSE_SECRET";
+
 impl RequestExecutor {
-    pub fn new(timeout: Duration) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(timeout)
-            .build()
-            .context("Failed to create HTTP client")?;
+    pub fn new(timeout: Duration, options: &ClientOptions) -> Result<Self> {
+        let build = |redirect: Policy| {
+            options
+                .configure(Client::builder().timeout(timeout).redirect(redirect))?
+                .build()
+                .context("Failed to create HTTP client")
+        };
+        Ok(Self::with_client(
+            build(Policy::default())?,
+            build(Policy::none())?,
+            timeout,
+            options,
+        ))
+    }
+
+    /// Send HTTP requests with `client`, built elsewhere with its own
+    /// connection settings, or with `no_redirect_client`, the same but not
+    /// following redirects, for steps that set `follow_redirects: false`.
+    /// `options` still configures gRPC TLS.
+    pub fn with_client(
+        client: Client,
+        no_redirect_client: Client,
+        timeout: Duration,
+        options: &ClientOptions,
+    ) -> Self {
+        let grpc_tls = GrpcTlsOptions {
+            cacert: options.tls.cacert.clone(),
+            insecure: options.insecure,
+            domain_override: None,
+        };
+
+        Self {
+            client,
+            no_redirect_client,
+            timeout,
+            grpc_tls,
+            log: RequestLog::default(),
+            max_capture_bytes: DEFAULT_MAX_CAPTURE_BYTES,
+            connections: ConnectionStats::default(),
+            schemas: Arc::default(),
+            grpc_clients: Arc::default(),
+            base_dir: PathBuf::new(),
+            identify: None,
+            base_path: None,
+        }
+    }
+
+    /// Count requests in `connections`, e.g. those of another executor, so
+    /// one count covers every client a run uses.
+    pub fn with_connection_stats(mut self, connections: ConnectionStats) -> Self {
+        self.connections = connections;
+        self
+    }
+
+    pub fn connection_stats(&self) -> &ConnectionStats {
+        &self.connections
+    }
 
-        Ok(Self { client })
+    /// Log every step's request and response to `log`.
+    pub fn with_log(mut self, log: RequestLog) -> Self {
+        self.log = log;
+        self
     }
 
+    /// Keep at most `max_capture_bytes` of each response body; 0 discards
+    /// bodies entirely.
+    pub fn with_max_capture_bytes(mut self, max_capture_bytes: usize) -> Self {
+        self.max_capture_bytes = max_capture_bytes;
+        self
+    }
+
+    /// Resolve relative file paths in steps against `dir`, the directory of
+    /// the suite file, rather than the working directory.
+    pub fn with_base_dir(mut self, dir: &Path) -> Self {
+        self.base_dir = dir.to_path_buf();
+        self
+    }
+
+    /// Send `X-Rivet-Test: <suite>/<step>` with every HTTP request, so the
+    /// server's logs show which test sent it.
+    pub fn with_identify(mut self, suite: Option<&str>) -> Self {
+        self.identify = suite.map(str::to_string);
+        self
+    }
+
+    /// Join relative request URLs to `baseUrl` under `base_path` (see
+    /// [`join_url`]).
+    pub fn with_base_path(mut self, base_path: Option<&str>) -> Self {
+        self.base_path = base_path.map(str::to_string);
+        self
+    }
+
+    /// A path declared in the suite, resolved against its directory.
+    pub fn suite_path(&self, declared: &str) -> PathBuf {
+        resolve_suite_path(&self.base_dir, declared)
+    }
+
+    /// Run one request and check it against `expectation`. Values of the
+    /// context's secret variables are masked in everything the result carries,
+    /// so console output and reports never see them.
     pub async fn execute_test(
         &self,
         name: &str,
@@ -40,32 +276,249 @@ impl RequestExecutor {
         expectation: Option<&Expectation>,
         context: &VariableContext,
     ) -> TestResult {
+        self.execute_step(name, request, expectation, None, context)
+            .await
+    }
+
+    /// [`execute_test`](Self::execute_test), and once the response passes,
+    /// set the variables in `capture` from its body and headers and run
+    /// `expect.poll`, which may use them.
+    pub async fn execute_step(
+        &self,
+        name: &str,
+        request: &Request,
+        expectation: Option<&Expectation>,
+        capture: Option<&HashMap<String, CaptureSource>>,
+        context: &VariableContext,
+    ) -> TestResult {
+        let mut result = self.run_test(name, request, expectation, context).await;
+        if let (true, Some(capture)) = (result.passed, capture) {
+            let headers = result.response_headers.clone().unwrap_or_default();
+            let body = result.response_body.as_deref().unwrap_or_default();
+            match captured_values(body, &headers, capture) {
+                Ok(captures) => result.captures = captures,
+                Err(e) => {
+                    result.passed = false;
+                    result.error = Some(format!("{:#}", e));
+                    result.failure_kind = Some(FailureKind::AssertionFailure);
+                }
+            }
+        }
+        if let Some(poll) = expectation.and_then(|expect| expect.poll.as_deref()) {
+            if result.passed {
+                let mut poll_context = context.clone();
+                for (name, value) in &result.captures {
+                    poll_context.set(name.clone(), value.clone());
+                }
+                result = self.poll(name, request, poll, result, &poll_context).await;
+            }
+        }
+        result.redacted(context)
+    }
+
+    /// Send `poll`'s request until its response meets `until` or the poll
+    /// times out. `result`, the step's own passed result, takes the poll's
+    /// outcome, attempts and wait.
+    async fn poll(
+        &self,
+        name: &str,
+        request: &Request,
+        poll: &Poll,
+        mut result: TestResult,
+        context: &VariableContext,
+    ) -> TestResult {
+        let prepared = poll_request(
+            request,
+            poll,
+            result.response_headers.as_ref(),
+            result.final_url.as_deref(),
+        )
+        .and_then(|poll_request| {
+            let duration = |value: Option<&String>, default: &str, what: &str| {
+                let value = context.resolve(value.map_or(default, String::as_str))?;
+                parse_timeout(&value).with_context(|| format!("Invalid poll {}: {}", what, value))
+            };
+            let interval = duration(poll.interval.as_ref(), Poll::DEFAULT_INTERVAL, "interval")?;
+            let timeout = duration(poll.timeout.as_ref(), Poll::DEFAULT_TIMEOUT, "timeout")?;
+            Ok((poll_request, interval, timeout))
+        });
+        let (poll_request, interval, timeout) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                result.passed = false;
+                result.error = Some(format!("{:#}", e));
+                result.failure_kind = Some(FailureKind::SetupFailure);
+                return result;
+            }
+        };
+
+        let start = Instant::now();
+        let mut attempts = 0;
+        let mut answered = false;
+        let last = loop {
+            attempts += 1;
+            let attempt = self
+                .run_test(name, &poll_request, Some(&poll.until), context)
+                .await;
+            result.bytes_sent += attempt.bytes_sent;
+            result.bytes_received += attempt.bytes_received;
+            answered |= attempt.response_status.is_some();
+            if attempt.passed || start.elapsed() + interval > timeout {
+                break attempt;
+            }
+            tokio::time::sleep(interval).await;
+        };
+        let wait = start.elapsed();
+        result.duration += wait;
+        result.poll = Some(PollSummary {
+            attempts,
+            wait_ms: wait.as_millis() as u64,
+        });
+        if last.passed {
+            return result;
+        }
+
+        let url = last.request_url.as_deref().unwrap_or(&poll_request.url);
+        let error = last.error.unwrap_or_default();
+        result.passed = false;
+        result.error_diff = last.error_diff;
+        if answered {
+            result.error = Some(format!(
+                "Poll of {} never reached its condition after {} attempt(s) over {:.1}s: {}",
+                url,
+                attempts,
+                wait.as_secs_f64(),
+                error
+            ));
+            result.failure_kind = Some(FailureKind::PollTimeout);
+        } else {
+            result.error = Some(format!(
+                "Poll target {} unreachable after {} attempt(s): {}",
+                url, attempts, error
+            ));
+            result.failure_kind = last.failure_kind;
+        }
+        result
+    }
+
+    async fn run_test(
+        &self,
+        name: &str,
+        request: &Request,
+        expectation: Option<&Expectation>,
+        context: &VariableContext,
+    ) -> TestResult {
+        if let Some(grpc) = &request.grpc {
+            return self.run_grpc_test(name, grpc, expectation, context).await;
+        }
+
         let start_time = Instant::now();
+        let request_method = Some(request.method.to_uppercase());
+        // Record the URL as sent; fall back to the substituted string if it doesn't parse
+        let request_url = Some(match self.resolve_url(request, context) {
+            Ok(url) => url.to_string(),
+            Err(_) => context.substitute_variables(&request.url),
+        });
+
+        let request_body = request
+            .body
+            .as_ref()
+            .map(|body| context.substitute_variables(body));
 
-        match self.execute_request(request, context).await {
-            Ok(response) => {
+        match self.execute_request(name, request, context).await {
+            Ok((response, bytes_sent)) => {
                 let duration = start_time.elapsed();
                 let status = response.status().as_u16();
+                let head_size = response_head_size(&response);
+                let final_url = Some(response.url().to_string());
+                let redirect_target = redirect_target(&response);
+                let http_version = Some(format!("{:?}", response.version()));
+                let header_list = header_list(response.headers());
+                let headers: HashMap<String, String> = header_list.iter().cloned().collect();
 
-                match response.text().await {
-                    Ok(body) => {
+                let decode = request.compression.is_some_and(|c| c.decodes());
+                match capture_body(response, self.max_capture_bytes, decode).await {
+                    Ok(captured) => {
+                        let bytes_received = head_size + captured.wire_size;
+                        let response_size = Some(captured.size);
+                        let body = captured.text;
+                        let warnings = expectation
+                            .and_then(|expect| content_type_warning(&headers, expect))
+                            .into_iter()
+                            .collect();
+                        self.log.response(
+                            name,
+                            &LoggedResponse {
+                                status,
+                                http_version: http_version.as_deref(),
+                                headers: &header_list,
+                                body: &body,
+                                duration,
+                            },
+                            context,
+                        );
                         if let Some(expect) = expectation {
-                            match self.validate_response(status, &body, expect, context) {
+                            match self.validate_response(
+                                status,
+                                &headers,
+                                &body,
+                                Some(&redirect_target),
+                                expect,
+                                context,
+                            ) {
                                 Ok(()) => TestResult {
                                     name: name.to_string(),
                                     passed: true,
+                                    skipped: false,
                                     duration,
                                     error: None,
+                                    error_diff: None,
                                     response_status: Some(status),
                                     response_body: Some(body.clone()),
+                                    response_size,
+                                    final_url,
+                                    request_method,
+                                    request_url,
+                                    request_body,
+                                    response_headers: Some(headers),
+                                    http_version,
+                                    bytes_sent,
+                                    bytes_received,
+                                    failure_kind: None,
+                                    captures: HashMap::new(),
+                                    poll: None,
+                                    warnings,
                                 },
                                 Err(e) => TestResult {
                                     name: name.to_string(),
                                     passed: false,
+                                    skipped: false,
                                     duration,
-                                    error: Some(e.to_string()),
+                                    error: Some(match captured.kept {
+                                        Some(kept) if kept < captured.size => format!(
+                                            "{} (only the first {} of {} body bytes were captured; raise max_capture_bytes)",
+                                            e, kept, captured.size
+                                        ),
+                                        _ => e.to_string(),
+                                    }),
+                                    error_diff: e
+                                        .downcast_ref::<AssertionFailures>()
+                                        .and_then(|failures| failures.diff()),
                                     response_status: Some(status),
                                     response_body: Some(body.clone()),
+                                    response_size,
+                                    final_url,
+                                    request_method,
+                                    request_url,
+                                    request_body,
+                                    response_headers: Some(headers),
+                                    http_version,
+                                    bytes_sent,
+                                    bytes_received,
+                                    failure_kind: Some(failure_kind(&e)),
+                                    captures: HashMap::new(),
+                                    poll: None,
+                                    warnings,
                                 },
                             }
                         } else {
@@ -73,56 +526,310 @@ impl RequestExecutor {
                             TestResult {
                                 name: name.to_string(),
                                 passed: status < 400,
+                                skipped: false,
                                 duration,
                                 error: if status >= 400 {
                                     Some(format!("HTTP {}", status))
                                 } else {
                                     None
                                 },
+                                error_diff: None,
                                 response_status: Some(status),
                                 response_body: Some(body),
+                                response_size,
+                                final_url,
+                                request_method,
+                                request_url,
+                                request_body,
+                                response_headers: Some(headers),
+                                http_version,
+                                bytes_sent,
+                                bytes_received,
+                                failure_kind: (status >= 400).then_some(FailureKind::HttpStatus),
+                                captures: HashMap::new(),
+                                poll: None,
+                                warnings,
                             }
                         }
                     }
-                    Err(e) => TestResult {
-                        name: name.to_string(),
-                        passed: false,
-                        duration: start_time.elapsed(),
-                        error: Some(format!("Failed to read response body: {}", e)),
-                        response_status: Some(status),
-                        response_body: None,
-                    },
+                    Err(e) => {
+                        let error = format!("Failed to read response body: {:#}", e);
+                        self.log.error(name, &error, context);
+                        TestResult {
+                            name: name.to_string(),
+                            passed: false,
+                            skipped: false,
+                            duration: start_time.elapsed(),
+                            error: Some(error),
+                            error_diff: None,
+                            response_status: Some(status),
+                            response_body: None,
+                            response_size: None,
+                            final_url,
+                            request_method,
+                            request_url,
+                            request_body,
+                            response_headers: Some(headers),
+                            http_version,
+                            bytes_sent,
+                            bytes_received: head_size,
+                            failure_kind: Some(match e.downcast_ref::<reqwest::Error>() {
+                                Some(e) if e.is_timeout() => FailureKind::Timeout,
+                                Some(_) => FailureKind::ConnectionError,
+                                None => FailureKind::InvalidResponseBody,
+                            }),
+                            captures: HashMap::new(),
+                            poll: None,
+                            warnings: Vec::new(),
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                self.log.error(name, &format!("{:#}", e), context);
+                TestResult {
+                    name: name.to_string(),
+                    passed: false,
+                    skipped: false,
+                    duration: start_time.elapsed(),
+                    error: Some(e.to_string()),
+                    error_diff: None,
+                    response_status: None,
+                    response_body: None,
+                    response_size: None,
+                    final_url: None,
+                    request_method,
+                    request_url,
+                    request_body,
+                    response_headers: None,
+                    http_version: None,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                    failure_kind: Some(failure_kind(&e)),
+                    captures: HashMap::new(),
+                    poll: None,
+                    warnings: Vec::new(),
                 }
             }
-            Err(e) => TestResult {
-                name: name.to_string(),
-                passed: false,
-                duration: start_time.elapsed(),
-                error: Some(e.to_string()),
-                response_status: None,
-                response_body: None,
-            },
         }
     }
 
-    async fn execute_request(
+    /// Run a gRPC step. The response is checked as JSON, and the gRPC status
+    /// code (0 for OK) stands in for the HTTP status; a failed call's body is
+    /// its `{"error": ...}` document.
+    async fn run_grpc_test(
         &self,
-        request: &Request,
+        name: &str,
+        grpc: &GrpcRequest,
+        expectation: Option<&Expectation>,
         context: &VariableContext,
-    ) -> Result<Response> {
-        // Substitute variables in URL
-        let url_str = context.substitute_variables(&request.url);
-        let mut url = Url::parse(&url_str).with_context(|| format!("Invalid URL: {}", url_str))?;
+    ) -> TestResult {
+        let start_time = Instant::now();
+        let request_url = Some(format!(
+            "{}/{}",
+            context
+                .substitute_variables(&grpc.server)
+                .trim_end_matches('/'),
+            context.substitute_variables(&grpc.call)
+        ));
+        let request_body = grpc
+            .data
+            .as_ref()
+            .and_then(|data| resolve_grpc_data(data, context).ok());
 
-        // Add query parameters
-        if let Some(params) = &request.params {
-            let mut query_pairs = url.query_pairs_mut();
-            for (key, value) in params {
-                let key = context.substitute_variables(key);
-                let value = context.substitute_variables(value);
-                query_pairs.append_pair(&key, &value);
+        let mut result = TestResult {
+            name: name.to_string(),
+            passed: false,
+            skipped: false,
+            duration: Duration::ZERO,
+            error: None,
+            error_diff: None,
+            response_status: None,
+            response_body: None,
+            response_size: None,
+            final_url: None,
+            request_method: Some("GRPC".to_string()),
+            request_url,
+            request_body,
+            response_headers: None,
+            http_version: None,
+            bytes_sent: 0,
+            bytes_received: 0,
+            failure_kind: None,
+            captures: HashMap::new(),
+            poll: None,
+            warnings: Vec::new(),
+        };
+
+        if self.log.is_enabled() {
+            let request = LoggedRequest {
+                method: "GRPC".to_string(),
+                url: result.request_url.clone().unwrap_or_default(),
+                headers: Vec::new(),
+                body: result.request_body.clone(),
+            };
+            self.log.request(name, &request, context);
+        }
+        let response = self.execute_grpc(grpc, context).await;
+        result.duration = start_time.elapsed();
+        let (status, body, status_error) = match response {
+            Ok(response) => (0, response.to_string(), None),
+            Err(e) => match e.downcast_ref::<StatusError>() {
+                Some(error) => (
+                    error.status.code() as u16,
+                    error.to_json().to_string(),
+                    Some(e),
+                ),
+                None => {
+                    self.log.error(name, &format!("{:#}", e), context);
+                    result.error = Some(e.to_string());
+                    result.failure_kind = Some(failure_kind(&e));
+                    return result;
+                }
+            },
+        };
+        self.log.response(
+            name,
+            &LoggedResponse {
+                status,
+                http_version: None,
+                headers: &[],
+                body: &body,
+                duration: result.duration,
+            },
+            context,
+        );
+        result.response_status = Some(status);
+        result.response_body = Some(body.clone());
+
+        // A failed call only passes when the step expects that status
+        let outcome = match (expectation, status_error) {
+            (Some(expect), None) => {
+                self.validate_response(status, &HashMap::new(), &body, None, expect, context)
+            }
+            (Some(expect), Some(_)) if expect.status.is_some() => {
+                self.validate_response(status, &HashMap::new(), &body, None, expect, context)
+            }
+            (_, Some(e)) => Err(e),
+            (None, None) => Ok(()),
+        };
+        match outcome {
+            Ok(()) => result.passed = true,
+            Err(e) => {
+                result.error_diff = e
+                    .downcast_ref::<AssertionFailures>()
+                    .and_then(|failures| failures.diff());
+                // A failed call is its status, as with HTTP
+                result.failure_kind = Some(match e.downcast_ref::<StatusError>() {
+                    Some(_) => FailureKind::HttpStatus,
+                    None => failure_kind(&e),
+                });
+                result.error = Some(e.to_string());
             }
         }
+        result
+    }
+
+    async fn execute_grpc(&self, grpc: &GrpcRequest, context: &VariableContext) -> Result<Value> {
+        let server = context.resolve(&grpc.server)?;
+        let proto = context.resolve(&grpc.proto)?;
+        let call = context.resolve(&grpc.call)?;
+        let data = grpc
+            .data
+            .as_ref()
+            .map(|data| resolve_grpc_data(data, context))
+            .transpose()?;
+        let metadata = grpc
+            .metadata
+            .iter()
+            .flatten()
+            .map(|(key, value)| {
+                Ok(format!(
+                    "{}: {}",
+                    context.resolve(key)?,
+                    context.resolve(value)?
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let metadata = grpc::parse_metadata(&metadata)?;
+        let timeout = match &grpc.timeout {
+            Some(timeout) => parse_timeout(&context.resolve(timeout)?)?,
+            None => self.timeout,
+        };
+
+        let mut client = self.grpc_client(proto, server).await?;
+        client
+            .call(&call, data.as_deref(), &metadata, timeout)
+            .await
+    }
+
+    /// The client for `server` with the protos under `proto`, connecting on
+    /// first use. A failed connection isn't kept, so later steps try again.
+    async fn grpc_client(&self, proto: String, server: String) -> Result<GrpcClient> {
+        let mut clients = self.grpc_clients.lock().await;
+        let key = (proto, server);
+        if let Some(client) = clients.get(&key) {
+            return Ok(client.clone());
+        }
+
+        let (proto, server) = &key;
+        // Suite TLS settings only make sense for TLS endpoints
+        let tls = if server.starts_with("https://") {
+            self.grpc_tls.clone()
+        } else {
+            GrpcTlsOptions::default()
+        };
+        let client = GrpcClient::new(Path::new(proto), server, false, &tls).await?;
+        clients.insert(key.clone(), client.clone());
+        Ok(client)
+    }
+
+    /// Send `request` outside any step, e.g. for a suite's auth token, and
+    /// return the response status and body. `body_secret` picks a secret out
+    /// of the response body, such as a token, to mask in the request log.
+    pub async fn send(
+        &self,
+        name: &str,
+        request: &Request,
+        context: &VariableContext,
+        body_secret: impl Fn(&str) -> Option<String>,
+    ) -> Result<(u16, String)> {
+        let start_time = Instant::now();
+        let (response, _) = self.execute_request(name, request, context).await?;
+        let status = response.status().as_u16();
+        let http_version = format!("{:?}", response.version());
+        let headers = header_list(response.headers());
+        let body = response
+            .text()
+            .await
+            .context("Failed to read response body")?;
+        let mut context = Cow::Borrowed(context);
+        if let Some(secret) = body_secret(&body) {
+            let mut masked = context.into_owned();
+            masked.set(RESPONSE_SECRET_VAR.to_string(), secret);
+            context = Cow::Owned(masked.with_secrets([RESPONSE_SECRET_VAR]));
+        }
+        self.log.response(
+            name,
+            &LoggedResponse {
+                status,
+                http_version: Some(&http_version),
+                headers: &headers,
+                body: &body,
+                duration: start_time.elapsed(),
+            },
+            &context,
+        );
+        Ok((status, body))
+    }
+
+    async fn execute_request(
+        &self,
+        name: &str,
+        request: &Request,
+        context: &VariableContext,
+    ) -> Result<(Response, u64)> {
+        let url = self.resolve_url(request, context)?;
 
         // Parse method
         let method = request
@@ -130,74 +837,268 @@ impl RequestExecutor {
             .parse::<Method>()
             .with_context(|| format!("Invalid HTTP method: {}", request.method))?;
 
-        // Build request
-        let mut req_builder = self.client.request(method, url);
+        let client = if request.follow_redirects.unwrap_or(true) {
+            &self.client
+        } else {
+            &self.no_redirect_client
+        };
+        let mut req_builder = client.request(method, url);
+
+        // rivet's own headers go last, unless the step sets them itself
+        let mut rivet_headers = vec![("User-Agent", USER_AGENT.to_string())];
+        if let Some(suite) = &self.identify {
+            rivet_headers.push(("X-Rivet-Test", test_id(suite, name)));
+        }
+        if let Some(compression) = request.compression {
+            rivet_headers.push(("Accept-Encoding", compression.accept_encoding().to_string()));
+        }
 
         // Add headers
         if let Some(headers) = &request.headers {
             for (key, value) in headers {
-                let key = context.substitute_variables(key);
-                let value = context.substitute_variables(value);
+                let key = context.resolve(key)?;
+                let value = context.resolve(value)?;
+                rivet_headers.retain(|(name, _)| !name.eq_ignore_ascii_case(&key));
+                if value.is_empty() && is_rivet_header(&key) {
+                    continue;
+                }
                 req_builder = req_builder.header(key, value);
             }
         }
+        for (key, value) in rivet_headers {
+            req_builder = req_builder.header(key, value);
+        }
 
         // Add body
         if let Some(body) = &request.body {
-            let body = context.substitute_variables(body);
+            let body = context.resolve(body)?;
             req_builder = req_builder.body(body);
         }
 
-        // Execute request
-        let response = req_builder
-            .send()
+        let built = req_builder
+            .build()
+            .context("Failed to build HTTP request")?;
+        if self.log.is_enabled() {
+            self.log
+                .request(name, &LoggedRequest::from(&built), context);
+        }
+
+        let bytes_sent = request_size(&built);
+        let response = client
+            .execute(built)
             .await
             .context("Failed to send HTTP request")?;
+        self.connections.record(&response);
+
+        Ok((response, bytes_sent))
+    }
+
+    fn resolve_url(&self, request: &Request, context: &VariableContext) -> Result<Url> {
+        // Substitute variables in URL; a relative one goes under baseUrl
+        let url_str = context.resolve(&request.url)?;
+        let mut url = if has_scheme(&url_str) {
+            Url::parse(&url_str).with_context(|| format!("Invalid URL: {}", url_str))?
+        } else {
+            let base_url = context.vars.get(BASE_URL_VAR).with_context(|| {
+                format!(
+                    "Relative URL `{}` needs a `{}` variable to resolve against",
+                    url_str, BASE_URL_VAR
+                )
+            })?;
+            let base_path = self
+                .base_path
+                .as_deref()
+                .map(|base_path| context.resolve(base_path))
+                .transpose()?;
+            join_url(&context.resolve(base_url)?, base_path.as_deref(), &url_str)?
+        };
 
-        Ok(response)
+        // Add query parameters
+        if let Some(params) = &request.params {
+            let mut query_pairs = url.query_pairs_mut();
+            for (key, value) in params {
+                let key = context.resolve(key)?;
+                let value = context.resolve(value)?;
+                query_pairs.append_pair(&key, &value);
+            }
+        }
+
+        Ok(url)
     }
 
+    /// Check every expectation and report all failures together rather than stopping at the first.
+    /// `redirect_target` is where an HTTP response redirected to; see [`redirect_target`].
     fn validate_response(
         &self,
         status: u16,
+        headers: &HashMap<String, String>,
         body: &str,
+        redirect_target: Option<&str>,
         expectation: &Expectation,
         context: &VariableContext,
     ) -> Result<()> {
+        let mut failures = Vec::new();
+
         // Validate status code
         if let Some(expected_status) = &expectation.status {
             let expected_code = match expected_status {
-                StatusExpectation::Number(code) => *code,
+                StatusExpectation::Number(code) => Ok(*code),
                 StatusExpectation::String(code_str) => {
                     let substituted = context.substitute_variables(code_str);
                     substituted
                         .parse::<u16>()
-                        .with_context(|| format!("Invalid status code: {}", substituted))?
+                        .with_context(|| format!("Invalid status code: {}", substituted))
                 }
             };
 
-            if status != expected_code {
-                anyhow::bail!("Expected status {} but got {}", expected_code, status);
+            match expected_code {
+                Ok(code) if status != code => failures.push(
+                    StatusMismatch {
+                        expected: code,
+                        actual: status,
+                    }
+                    .into(),
+                ),
+                Ok(_) => {}
+                Err(e) => failures.push(e),
             }
         }
 
-        // Validate headers (we'd need response headers for this - skipping for now)
-        // if let Some(expected_headers) = &expectation.headers {
-        //     // TODO: Validate headers
-        // }
+        // Validate headers (names are case-insensitive)
+        if let Some(expected_headers) = &expectation.headers {
+            let mut expected_headers: Vec<_> = expected_headers.iter().collect();
+            expected_headers.sort();
 
-        // Validate JSON path assertions
-        if let Some(jsonpath_assertions) = &expectation.jsonpath {
-            // Try to parse as JSON
-            let json_value: Value =
-                serde_json::from_str(body).context("Response body is not valid JSON")?;
+            for (name, expected_value) in expected_headers {
+                let expected_value = context.substitute_variables(expected_value);
 
-            for (path, expected_value) in jsonpath_assertions {
-                self.validate_jsonpath(&json_value, path, expected_value, context)?;
+                match header_value(headers, name) {
+                    None => failures.push(anyhow::anyhow!(
+                        "Expected header '{}' but it was missing",
+                        name
+                    )),
+                    Some(actual) if *actual != expected_value => failures.push(anyhow::anyhow!(
+                        "Expected header '{}' to be '{}' but got '{}'",
+                        name,
+                        expected_value,
+                        actual
+                    )),
+                    Some(_) => {}
+                }
             }
         }
 
-        Ok(())
+        if let Some(expected) = &expectation.content_type {
+            let expected = context.substitute_variables(expected);
+            match header_value(headers, "content-type") {
+                Some(actual) if media_type_matches(&expected, actual) => {}
+                Some(actual) => failures.push(anyhow::anyhow!(
+                    "Expected content type '{}' but got '{}'",
+                    expected,
+                    actual
+                )),
+                None => failures.push(anyhow::anyhow!(
+                    "Expected content type '{}' but the response has no Content-Type header",
+                    expected
+                )),
+            }
+        }
+
+        if let Some(expected) = &expectation.redirects_to {
+            let expected = context.substitute_variables(expected);
+            match redirect_target {
+                Some(actual) if same_url(actual, &expected) => {}
+                Some(actual) => failures.push(anyhow::anyhow!(
+                    "Expected a redirect to '{}' but ended at '{}'",
+                    expected,
+                    actual
+                )),
+                None => failures.push(anyhow::anyhow!(
+                    "Expected a redirect to '{}' but the step isn't an HTTP request",
+                    expected
+                )),
+            }
+        }
+
+        // Validate JSON path and schema assertions; a non-JSON body is one
+        // failure, not one per path
+        if expectation.checks_json() {
+            match serde_json::from_str::<Value>(body) {
+                Ok(json_value) => {
+                    let mut assertions: Vec<_> = expectation.jsonpath.iter().flatten().collect();
+                    assertions.sort_by(|a, b| a.0.cmp(b.0));
+
+                    for (path, expected_value) in assertions {
+                        if let Err(e) =
+                            self.validate_jsonpath(&json_value, path, expected_value, context)
+                        {
+                            failures.push(e);
+                        }
+                    }
+                    failures.extend(array_failures(&json_value, expectation, context));
+
+                    if let Some(schema) = &expectation.schema {
+                        match self.load_schema(schema, context) {
+                            Ok(schema) => {
+                                let errors = schema_errors(&schema, &json_value);
+                                if !errors.is_empty() {
+                                    failures.push(anyhow::anyhow!(
+                                        "Response does not match schema: {}",
+                                        errors.join("; ")
+                                    ));
+                                }
+                            }
+                            Err(e) => failures.push(e),
+                        }
+                    }
+                }
+                Err(_) => failures.push(InvalidJsonBody.into()),
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(AssertionFailures::new(failures).into())
+        }
+    }
+
+    /// The JSON Schema `expect.schema` names: inline JSON starting with `{`,
+    /// or a JSON or YAML file.
+    fn load_schema(&self, schema: &str, context: &VariableContext) -> Result<Arc<JSONSchema>> {
+        let schema = context.resolve(schema)?;
+        let inline = schema.trim_start().starts_with('{');
+        let path = (!inline).then(|| self.suite_path(&schema));
+        let key = match &path {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => schema.clone(),
+        };
+        if let Some(compiled) = self.schemas.lock().unwrap().get(&key) {
+            return Ok(Arc::clone(compiled));
+        }
+
+        let (document, label): (Value, _) = match &path {
+            None => {
+                let document = serde_json::from_str(&schema).context("Invalid inline schema")?;
+                (document, "inline schema".to_string())
+            }
+            Some(path) => {
+                let described = describe_suite_path(&schema, path);
+                let text = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read schema {}", described))?;
+                let document = serde_yaml::from_str(&text)
+                    .with_context(|| format!("Invalid schema {}", described))?;
+                (document, format!("schema {}", schema))
+            }
+        };
+        let compiled = JSONSchema::compile(&document)
+            .map_err(|e| anyhow::anyhow!("Invalid {}: {}", label, e))?;
+        let compiled = Arc::new(compiled);
+        self.schemas
+            .lock()
+            .unwrap()
+            .insert(key, Arc::clone(&compiled));
+        Ok(compiled)
     }
 
     fn validate_jsonpath(
@@ -209,71 +1110,46 @@ impl RequestExecutor {
     ) -> Result<()> {
         // Simple JSONPath implementation for basic cases
         let actual_value = Self::extract_jsonpath_value(json, path)?;
+        let expected_value = expected_json_value(expected, context);
 
-        // Handle variable substitution in expected values
-        let expected_value = match expected {
-            Value::String(s) => {
-                let substituted = context.substitute_variables(s);
-                // Try to parse as number or boolean if it looks like one
-                if let Ok(num) = substituted.parse::<i64>() {
-                    Value::Number(num.into())
-                } else if let Ok(b) = substituted.parse::<bool>() {
-                    Value::Bool(b)
+        if actual_value != expected_value {
+            // The enclosing object gives the diff some context
+            let parent = assertions::split_parent(path).and_then(|(parent_path, _)| {
+                if parent_path == "$" {
+                    Some(json.clone())
                 } else {
-                    Value::String(substituted)
+                    Self::extract_jsonpath_value(json, parent_path).ok()
                 }
-            }
-            other => other.clone(),
-        };
-
-        if actual_value != expected_value {
-            anyhow::bail!(
-                "JSONPath assertion failed for '{}': expected {:?} but got {:?}",
+            });
+            return Err(assertions::jsonpath_mismatch(
                 path,
-                expected_value,
-                actual_value
-            );
+                &expected_value,
+                &actual_value,
+                parent.as_ref(),
+            )
+            .into());
         }
 
         Ok(())
     }
 
-    fn extract_jsonpath_value(json: &Value, path: &str) -> Result<Value> {
-        // Simple JSONPath implementation supporting basic syntax
-        let mut current = json;
-
-        // Handle root array access like "$[0].userId"
-        if path.starts_with("$[") {
-            let remaining = path.strip_prefix("$").unwrap();
-            if let Some(bracket_end) = remaining.find(']') {
-                let index_str = &remaining[1..bracket_end];
-                let index: usize = index_str
-                    .parse()
-                    .with_context(|| format!("Invalid array index: {}", index_str))?;
-
-                current = current
-                    .get(index)
-                    .ok_or_else(|| anyhow::anyhow!("Array index {} not found", index))?;
-
-                let remaining_path = &remaining[bracket_end + 1..];
-                if remaining_path.starts_with('.') {
-                    return Self::extract_jsonpath_value(
-                        current,
-                        remaining_path.strip_prefix('.').unwrap(),
-                    );
-                } else {
-                    return Ok(current.clone());
-                }
-            }
-        }
-
-        let parts = path.strip_prefix("$.").unwrap_or(path).split('.');
+    /// The value at `path` in `json`, as `jsonpath` expectations see it.
+    /// `[*]` maps the rest of the path over an array's elements, e.g.
+    /// `$.items[*].id` is the list of the items' ids.
+    pub fn extract_jsonpath_value(json: &Value, path: &str) -> Result<Value> {
+        // A root array, as in "$[0].userId", or the root itself
+        let path = match path.strip_prefix('$') {
+            Some(rest) if rest.is_empty() || rest.starts_with('[') => rest,
+            _ => path.strip_prefix("$.").unwrap_or(path),
+        };
+        let parts: Vec<&str> = path.split('.').filter(|part| !part.is_empty()).collect();
+        Self::extract_parts(json, &parts)
+    }
 
-        for part in parts {
-            if part.is_empty() {
-                continue;
-            }
+    fn extract_parts(json: &Value, parts: &[&str]) -> Result<Value> {
+        let mut current = json;
 
+        for (i, part) in parts.iter().enumerate() {
             // Handle array indexing like "items[0]"
             if part.contains('[') && part.ends_with(']') {
                 let (field, index_str) = part.split_once('[').unwrap();
@@ -285,6 +1161,22 @@ impl RequestExecutor {
                         .ok_or_else(|| anyhow::anyhow!("Field '{}' not found in JSON", field))?;
                 }
 
+                if index_str == "*" {
+                    let items = current.as_array().ok_or_else(|| {
+                        anyhow::anyhow!("'{}' is {}, not an array", part, json_type(current))
+                    })?;
+                    return items
+                        .iter()
+                        .enumerate()
+                        .map(|(n, item)| {
+                            Self::extract_parts(item, &parts[i + 1..]).map_err(|e| {
+                                anyhow::anyhow!("{} in element {} of '{}'", e, n, part)
+                            })
+                        })
+                        .collect::<Result<_>>()
+                        .map(Value::Array);
+                }
+
                 let index: usize = index_str
                     .parse()
                     .with_context(|| format!("Invalid array index: {}", index_str))?;
@@ -302,3 +1194,825 @@ impl RequestExecutor {
         Ok(current.clone())
     }
 }
+
+/// The JSON text of a gRPC step's `data`, with variables substituted in every
+/// string. A string at the top level is taken as JSON text itself.
+/// The request `poll` repeats: its URL, or the `Location` among the step's
+/// `response_headers`, with the step's headers under its own. Redirect and
+/// compression settings carry over.
+fn poll_request(
+    request: &Request,
+    poll: &Poll,
+    response_headers: Option<&HashMap<String, String>>,
+    final_url: Option<&str>,
+) -> Result<Request> {
+    let url = match &poll.url {
+        Some(url) => url.clone(),
+        None => {
+            let location = response_headers
+                .and_then(|headers| header_value(headers, "location"))
+                .context("Poll has no url and the response has no Location header")?;
+            // A relative Location is relative to the URL the response came from
+            match final_url.map(Url::parse) {
+                Some(Ok(base)) => base
+                    .join(location)
+                    .with_context(|| format!("Invalid Location header: {}", location))?
+                    .to_string(),
+                _ => location.to_string(),
+            }
+        }
+    };
+    let mut headers = request.headers.clone().unwrap_or_default();
+    for (key, value) in poll.headers.iter().flatten() {
+        headers.retain(|name, _| !name.eq_ignore_ascii_case(key));
+        headers.insert(key.clone(), value.clone());
+    }
+    Ok(Request {
+        method: poll.method.clone().unwrap_or_else(|| "GET".to_string()),
+        url,
+        headers: Some(headers),
+        params: None,
+        body: None,
+        grpc: None,
+        ..request.clone()
+    })
+}
+
+/// The values `capture` takes from a passed response. Strings in the body
+/// are taken as they are, anything else as JSON text.
+fn captured_values(
+    body: &str,
+    headers: &HashMap<String, String>,
+    capture: &HashMap<String, CaptureSource>,
+) -> Result<HashMap<String, String>> {
+    let mut names: Vec<&String> = capture.keys().collect();
+    names.sort();
+    let json: Option<Value> = serde_json::from_str(body).ok();
+    let mut captures = HashMap::new();
+    for name in names {
+        let value = match &capture[name] {
+            CaptureSource::JsonPath(path) => {
+                let json = json.as_ref().with_context(|| {
+                    format!(
+                        "Failed to capture `{}` from {}: the response body is not JSON",
+                        name, path
+                    )
+                })?;
+                match RequestExecutor::extract_jsonpath_value(json, path)
+                    .with_context(|| format!("Failed to capture `{}` from {}", name, path))?
+                {
+                    Value::String(text) => text,
+                    value => value.to_string(),
+                }
+            }
+            CaptureSource::Header { header, pattern } => {
+                let value = header_value(headers, header).with_context(|| {
+                    format!(
+                        "Failed to capture `{}`: the response has no `{}` header",
+                        name, header
+                    )
+                })?;
+                match pattern {
+                    Some(pattern) => {
+                        let regex = regex::Regex::new(pattern).with_context(|| {
+                            format!("Failed to capture `{}`: invalid pattern", name)
+                        })?;
+                        let groups = regex.captures(value).with_context(|| {
+                            format!(
+                                "Failed to capture `{}`: `{}` header `{}` does not match `{}`",
+                                name, header, value, pattern
+                            )
+                        })?;
+                        groups
+                            .get(1)
+                            .or_else(|| groups.get(0))
+                            .map_or_else(String::new, |group| group.as_str().to_string())
+                    }
+                    None => value.to_string(),
+                }
+            }
+        };
+        captures.insert(name.clone(), value);
+    }
+    Ok(captures)
+}
+
+/// Where `response` sends the client: the `Location` of a redirect that
+/// wasn't followed, resolved against the request URL, or else the URL the
+/// response came from.
+fn redirect_target(response: &Response) -> String {
+    if response.status().is_redirection() {
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| response.url().join(location).ok());
+        if let Some(location) = location {
+            return location.to_string();
+        }
+    }
+    response.url().to_string()
+}
+
+/// The `X-Rivet-Test` value for step `name` of `suite`: both names
+/// percent-encoded, so a `/` in either stays unambiguous.
+fn test_id(suite: &str, name: &str) -> String {
+    let encode = |text: &str| {
+        url::form_urlencoded::byte_serialize(text.as_bytes())
+            .collect::<String>()
+            .replace('+', "%20")
+    };
+    format!("{}/{}", encode(suite), encode(name))
+}
+
+/// An expected JSON value with variables substituted; strings that look
+/// like numbers or booleans become them.
+fn expected_json_value(expected: &Value, context: &VariableContext) -> Value {
+    match expected {
+        Value::String(s) => {
+            let substituted = context.substitute_variables(s);
+            if let Ok(num) = substituted.parse::<i64>() {
+                Value::Number(num.into())
+            } else if let Ok(b) = substituted.parse::<bool>() {
+                Value::Bool(b)
+            } else {
+                Value::String(substituted)
+            }
+        }
+        other => other.clone(),
+    }
+}
+
+/// Failures of the `jsonpath_length`, `jsonpath_contains` and
+/// `jsonpath_every` assertions, each kind in path order.
+fn array_failures(
+    json: &Value,
+    expectation: &Expectation,
+    context: &VariableContext,
+) -> Vec<anyhow::Error> {
+    let mut failures = Vec::new();
+    let mut lengths: Vec<_> = expectation.jsonpath_length.iter().flatten().collect();
+    lengths.sort_by(|a, b| a.0.cmp(b.0));
+    for (path, expected) in lengths {
+        let bound = match expected {
+            LengthExpectation::Number(n) => Ok(LengthBound::Exactly(*n)),
+            LengthExpectation::String(text) => {
+                let text = context.substitute_variables(text);
+                LengthBound::parse(&text).map_err(|reason| {
+                    format!("Invalid length `{}` for '{}': {}", text, path, reason)
+                })
+            }
+        };
+        let result = bound.and_then(|bound| {
+            let actual = RequestExecutor::extract_jsonpath_value(json, path)
+                .map_err(|e| format!("Expected '{}' to be an array: {}", path, e))?;
+            assertions::check_length(path, &actual, bound)
+        });
+        if let Err(message) = result {
+            failures.push(anyhow::anyhow!(message));
+        }
+    }
+
+    type Check = fn(&str, &Value, &Value) -> std::result::Result<(), String>;
+    let checks: [(_, Check); 2] = [
+        (&expectation.jsonpath_contains, assertions::check_contains),
+        (&expectation.jsonpath_every, assertions::check_every),
+    ];
+    for (expected, check) in checks {
+        let mut expected: Vec<_> = expected.iter().flatten().collect();
+        expected.sort_by(|a, b| a.0.cmp(b.0));
+        for (path, value) in expected {
+            let result = RequestExecutor::extract_jsonpath_value(json, path)
+                .map_err(|e| format!("Expected '{}' to be an array: {}", path, e))
+                .and_then(|actual| check(path, &actual, &expected_json_value(value, context)));
+            if let Err(message) = result {
+                failures.push(anyhow::anyhow!(message));
+            }
+        }
+    }
+    failures
+}
+
+/// A case-insensitive header lookup.
+fn header_value<'a>(headers: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(actual_name, _)| actual_name.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Warn when JSON assertions run against a response that isn't declared as
+/// JSON, the usual cause of "Response body is not valid JSON". Steps that
+/// check `content_type` themselves get no warning.
+fn content_type_warning(
+    headers: &HashMap<String, String>,
+    expectation: &Expectation,
+) -> Option<String> {
+    if expectation.content_type.is_some() || !expectation.checks_json() {
+        return None;
+    }
+    match header_value(headers, "content-type") {
+        Some(actual) if is_json_media_type(actual) => None,
+        Some(actual) => Some(format!(
+            "Response Content-Type is '{}', not JSON; add `content_type: application/json` to fail on it",
+            actual
+        )),
+        None => Some(
+            "Response has no Content-Type header, so it may not be JSON; add `content_type: application/json` to fail on it"
+                .to_string(),
+        ),
+    }
+}
+
+/// Compare URLs as parsed, so `https://a.test` equals `https://a.test/`.
+fn same_url(actual: &str, expected: &str) -> bool {
+    match (Url::parse(actual), Url::parse(expected)) {
+        (Ok(actual), Ok(expected)) => actual == expected,
+        _ => actual == expected,
+    }
+}
+
+/// A response body as kept for assertions, logs and reports.
+struct CapturedBody {
+    /// The body text, or `<binary, N bytes>`
+    text: String,
+    /// Bytes of the whole body, decoded
+    size: u64,
+    /// Bytes of the body as received
+    wire_size: u64,
+    /// Bytes of text kept; `None` for binary bodies
+    kept: Option<u64>,
+}
+
+/// Counts the bytes written to it, keeping the first `max_bytes`.
+struct Capture {
+    kept: Vec<u8>,
+    max_bytes: usize,
+    size: u64,
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.size += buf.len() as u64;
+        let room = self.max_bytes.saturating_sub(self.kept.len());
+        self.kept.extend_from_slice(&buf[..buf.len().min(room)]);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Read the whole body, keeping at most `max_bytes` of it, decoded by its
+/// `Content-Encoding` when `decode` is set. Binary bodies, by content type or
+/// because they aren't UTF-8, become a placeholder.
+async fn capture_body(
+    mut response: Response,
+    max_bytes: usize,
+    decode: bool,
+) -> Result<CapturedBody> {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let binary_type = header(reqwest::header::CONTENT_TYPE)
+        .as_deref()
+        .is_some_and(is_binary_content_type);
+    let encoding = header(reqwest::header::CONTENT_ENCODING).filter(|_| decode);
+
+    let capture = Capture {
+        kept: Vec::new(),
+        max_bytes: if binary_type { 0 } else { max_bytes },
+        size: 0,
+    };
+    let mut body = BodyDecoder::new(encoding.as_deref(), capture);
+    let invalid = || format!("Invalid {} body", encoding.as_deref().unwrap_or_default());
+    while let Some(chunk) = response.chunk().await? {
+        body.write_all(&chunk).with_context(invalid)?;
+    }
+    let wire_size = body.received();
+    let Capture { kept, size, .. } = body.finish().with_context(invalid)?;
+
+    let binary = || CapturedBody {
+        text: format!("<binary, {} bytes>", size),
+        size,
+        wire_size,
+        kept: None,
+    };
+    if binary_type {
+        return Ok(binary());
+    }
+    let truncated = (kept.len() as u64) < size;
+    let text = match String::from_utf8(kept) {
+        Ok(text) => text,
+        // The cap may split a character; drop its first bytes
+        Err(e) if truncated && e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes).unwrap_or_default()
+        }
+        Err(_) => return Ok(binary()),
+    };
+    Ok(CapturedBody {
+        kept: Some(text.len() as u64),
+        text,
+        size,
+        wire_size,
+    })
+}
+
+/// Content types that are never worth decoding as text.
+fn is_binary_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    ["image/", "audio/", "video/", "font/"]
+        .iter()
+        .any(|prefix| mime.starts_with(prefix))
+        || matches!(
+            mime.as_str(),
+            "application/octet-stream"
+                | "application/pdf"
+                | "application/zip"
+                | "application/gzip"
+                | "application/x-protobuf"
+                | "application/protobuf"
+                | "application/grpc"
+        )
+}
+
+/// Bytes `request` takes as HTTP/1.1: request line, headers including the
+/// `host`, `accept` and `content-length` the client adds, blank line and
+/// body.
+pub fn request_size(request: &reqwest::Request) -> u64 {
+    let url = request.url();
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map_or(0, |bytes| bytes.len());
+
+    let mut headers = header_list(request.headers());
+    if !request.headers().contains_key(reqwest::header::HOST) {
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        headers.push(("host".to_string(), host));
+    }
+    if !request.headers().contains_key(reqwest::header::ACCEPT) {
+        headers.push(("accept".to_string(), "*/*".to_string()));
+    }
+    if body > 0
+        && !request
+            .headers()
+            .contains_key(reqwest::header::CONTENT_LENGTH)
+    {
+        headers.push(("content-length".to_string(), body.to_string()));
+    }
+
+    let line = format!("{} {} HTTP/1.1\r\n", request.method(), target).len();
+    (line + head_size(&headers) + body) as u64
+}
+
+/// Bytes of `response`'s status line, headers and blank line, as HTTP/1.1.
+pub fn response_head_size(response: &Response) -> u64 {
+    let status = response.status();
+    let line = format!(
+        "HTTP/1.1 {} {}\r\n",
+        status.as_str(),
+        status.canonical_reason().unwrap_or_default()
+    )
+    .len();
+    (line + head_size(&header_list(response.headers()))) as u64
+}
+
+/// `name: value\r\n` per header, then the blank line ending the head.
+fn head_size(headers: &[(String, String)]) -> usize {
+    headers
+        .iter()
+        .map(|(name, value)| name.len() + value.len() + 4)
+        .sum::<usize>()
+        + 2
+}
+
+/// `data` of a gRPC step as request JSON, with variables filled in.
+pub(crate) fn resolve_grpc_data(data: &Value, context: &VariableContext) -> Result<String> {
+    fn resolve(value: &Value, context: &VariableContext) -> Result<Value> {
+        Ok(match value {
+            Value::String(text) => Value::String(context.resolve(text)?),
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| resolve(item, context))
+                    .collect::<Result<_>>()?,
+            ),
+            Value::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| Ok((context.resolve(key)?, resolve(value, context)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+
+    match data {
+        Value::String(text) => context.resolve(text),
+        other => Ok(resolve(other, context)?.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expectation(yaml: &str) -> Expectation {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn validate(status: u16, body: &str, expect: &Expectation) -> Result<()> {
+        let executor =
+            RequestExecutor::new(Duration::from_secs(1), &ClientOptions::default()).unwrap();
+        let headers = HashMap::from([("Content-Type".to_string(), "text/plain".to_string())]);
+        executor.validate_response(
+            status,
+            &headers,
+            body,
+            None,
+            expect,
+            &VariableContext::new(),
+        )
+    }
+
+    #[test]
+    fn test_validate_response_collects_every_failure() {
+        let expect = expectation(
+            r#"
+status: 200
+headers:
+  content-type: application/json
+  x-request-id: abc
+jsonpath:
+  "$.name": "Alice"
+  "$.id": 1
+  "$.missing": true
+"#,
+        );
+
+        let error = validate(404, r#"{"id": 2, "name": "Bob"}"#, &expect).unwrap_err();
+        let failures = error.downcast_ref::<AssertionFailures>().unwrap();
+        assert_eq!(
+            failures.to_string(),
+            "6 assertions failed:
+  1. Expected status 200 but got 404
+  2. Expected header 'content-type' to be 'application/json' but got 'text/plain'
+  3. Expected header 'x-request-id' but it was missing
+  4. JSONPath assertion failed for '$.id': expected Number(1) but got Number(2)
+  5. Field 'missing' not found in JSON
+  6. JSONPath assertion failed for '$.name': expected String(\"Alice\") but got String(\"Bob\")"
+        );
+        let diff = failures.diff().unwrap();
+        assert!(diff.starts_with("4.\n"));
+        assert!(diff.contains("\n6.\n"));
+        // The unexpected status is what gets reported
+        assert_eq!(failure_kind(&error), FailureKind::HttpStatus);
+    }
+
+    #[test]
+    fn test_validate_response_non_json_body_is_one_failure() {
+        let expect = expectation(
+            r#"
+status: 200
+jsonpath:
+  "$.a": 1
+  "$.b": 2
+  "$.c": 3
+"#,
+        );
+
+        let error = validate(200, "<html>oops</html>", &expect).unwrap_err();
+        assert_eq!(error.to_string(), "Response body is not valid JSON");
+        assert_eq!(failure_kind(&error), FailureKind::InvalidResponseBody);
+    }
+
+    #[test]
+    fn test_failure_kinds() {
+        let error =
+            validate(200, "ok", &expectation("content_type: application/json")).unwrap_err();
+        assert_eq!(failure_kind(&error), FailureKind::AssertionFailure);
+
+        // Nothing that reached the network, e.g. an unresolved strict variable
+        let error = anyhow::anyhow!("Unresolved variable(s): userId");
+        assert_eq!(failure_kind(&error), FailureKind::SetupFailure);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let elapsed = runtime
+            .block_on(async {
+                tokio::time::timeout(Duration::ZERO, std::future::pending::<()>()).await
+            })
+            .unwrap_err();
+        let error = anyhow::Error::new(elapsed).context("gRPC call timed out");
+        assert_eq!(failure_kind(&error), FailureKind::Timeout);
+    }
+
+    #[test]
+    fn test_validate_response_passes() {
+        let expect = expectation(
+            r#"
+status: 200
+headers:
+  Content-Type: text/plain
+jsonpath:
+  "$.id": 1
+"#,
+        );
+        assert!(validate(200, r#"{"id": 1}"#, &expect).is_ok());
+    }
+
+    #[test]
+    fn test_array_assertions() {
+        let body = r#"{"items": [{"id": 41, "status": "active"}, {"id": 42, "status": "active"}], "none": [], "total": 2}"#;
+        let expect = expectation(
+            r#"
+jsonpath_length:
+  "$.items": 2
+  "$.none": "<= 0"
+jsonpath_contains:
+  "$.items[*].id": 42
+jsonpath_every:
+  "$.items[*].status": active
+  "$.none": active
+"#,
+        );
+        assert!(validate(200, body, &expect).is_ok());
+
+        let expect = expectation(
+            r#"
+jsonpath_length:
+  "$.items": ">= 3"
+  "$.total": 2
+jsonpath_contains:
+  "$.items[*].id": "43"
+  "$.none": 1
+jsonpath_every:
+  "$.items[*].id": 41
+"#,
+        );
+        let error = validate(200, body, &expect).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "5 assertions failed:
+  1. Expected '$.items' to have at least 3 element(s) but it has 2
+  2. Expected '$.total' to be an array but it is a number
+  3. Expected '$.items[*].id' to contain 43 but none of its 2 element(s) do
+  4. Expected '$.none' to contain 1 but it is empty
+  5. Expected every element of '$.items[*].id' to be 41 but element 1 is 42"
+        );
+
+        let expect = expectation("jsonpath_every:\n  \"$.items[*].name\": x");
+        let error = validate(200, body, &expect).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Expected '$.items[*].name' to be an array: Field 'name' not found in JSON in element 0 of 'items[*]'"
+        );
+        let expect = expectation("jsonpath_length:\n  \"$.items\": \"about 2\"");
+        let error = validate(200, body, &expect).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid length `about 2` for '$.items': expected a number, `>= n` or `<= n`"
+        );
+    }
+
+    #[test]
+    fn test_extract_jsonpath_wildcards() {
+        let json = serde_json::json!([{"tags": ["a", "b"]}, {"tags": []}]);
+        assert_eq!(
+            RequestExecutor::extract_jsonpath_value(&json, "$[*].tags").unwrap(),
+            serde_json::json!([["a", "b"], []])
+        );
+        assert_eq!(
+            RequestExecutor::extract_jsonpath_value(&json, "$[0].tags[*]").unwrap(),
+            serde_json::json!(["a", "b"])
+        );
+        assert_eq!(
+            RequestExecutor::extract_jsonpath_value(&json, "$").unwrap(),
+            json
+        );
+        let error = RequestExecutor::extract_jsonpath_value(&json, "$[0].tags[0][*]").unwrap_err();
+        assert!(
+            error.to_string().contains("Invalid array index"),
+            "{}",
+            error
+        );
+        let error = RequestExecutor::extract_jsonpath_value(&json[0], "tags[0].x[*]").unwrap_err();
+        assert_eq!(error.to_string(), "Field 'x' not found in JSON");
+        let json = serde_json::json!({"user": {"name": "Ada"}});
+        let error = RequestExecutor::extract_jsonpath_value(&json, "$.user[*]").unwrap_err();
+        assert_eq!(error.to_string(), "'user[*]' is an object, not an array");
+    }
+
+    #[test]
+    fn test_validate_response_content_type() {
+        assert!(validate(200, "ok", &expectation("content_type: TEXT/*")).is_ok());
+
+        let error = validate(
+            200,
+            "<html>",
+            &expectation("content_type: application/json"),
+        )
+        .unwrap_err()
+        .to_string();
+        assert_eq!(
+            error,
+            "Expected content type 'application/json' but got 'text/plain'"
+        );
+    }
+
+    #[test]
+    fn test_content_type_warning_for_json_assertions() {
+        let headers = |content_type: &str| {
+            HashMap::from([("content-type".to_string(), content_type.to_string())])
+        };
+        let jsonpath = expectation("jsonpath:\n  \"$.id\": 1");
+
+        let warning = content_type_warning(&headers("text/html; charset=utf-8"), &jsonpath);
+        assert_eq!(
+            warning.as_deref(),
+            Some("Response Content-Type is 'text/html; charset=utf-8', not JSON; add `content_type: application/json` to fail on it")
+        );
+        assert!(content_type_warning(&HashMap::new(), &jsonpath).is_some());
+        assert!(content_type_warning(&headers("application/problem+json"), &jsonpath).is_none());
+        // Checked explicitly, or nothing that needs JSON
+        let explicit = expectation("content_type: text/html\njsonpath:\n  \"$.id\": 1");
+        assert!(content_type_warning(&headers("text/html"), &explicit).is_none());
+        assert!(content_type_warning(&headers("text/html"), &expectation("status: 200")).is_none());
+    }
+
+    #[test]
+    fn test_resolve_grpc_data_substitutes_strings() {
+        let mut context = VariableContext::new();
+        context.set("id".to_string(), "42".to_string());
+
+        let data = serde_json::json!({"userId": "{{id}}", "tags": ["t-{{id}}"], "limit": 5});
+        let resolved: Value =
+            serde_json::from_str(&resolve_grpc_data(&data, &context).unwrap()).unwrap();
+        assert_eq!(
+            resolved,
+            serde_json::json!({"userId": "42", "tags": ["t-42"], "limit": 5})
+        );
+
+        // A string is JSON text, so variables can fill in non-string fields
+        let data = Value::String(r#"{"limit": {{id}}}"#.to_string());
+        assert_eq!(
+            resolve_grpc_data(&data, &context).unwrap(),
+            r#"{"limit": 42}"#
+        );
+    }
+
+    #[test]
+    fn test_binary_content_types() {
+        for content_type in [
+            "image/png",
+            "Application/PDF",
+            "font/woff2",
+            "application/octet-stream; x=1",
+        ] {
+            assert!(is_binary_content_type(content_type), "{}", content_type);
+        }
+        for content_type in [
+            "application/json",
+            "text/html; charset=utf-8",
+            "application/problem+json",
+        ] {
+            assert!(!is_binary_content_type(content_type), "{}", content_type);
+        }
+    }
+
+    #[test]
+    fn test_test_id_encodes_names() {
+        assert_eq!(test_id("users", "list"), "users/list");
+        assert_eq!(
+            test_id("Users API", "GET /users [row 2]"),
+            "Users%20API/GET%20%2Fusers%20%5Brow%202%5D"
+        );
+        assert_eq!(test_id("a+b", "é"), "a%2Bb/%C3%A9");
+    }
+
+    #[test]
+    fn test_captured_values() {
+        let body = r#"{"job": {"id": 7, "name": "export", "tags": ["a"]}}"#;
+        let path = |path: &str| CaptureSource::JsonPath(path.to_string());
+        let capture = HashMap::from([
+            ("id".to_string(), path("$.job.id")),
+            ("name".to_string(), path("$.job.name")),
+            ("tags".to_string(), path("$.job.tags")),
+        ]);
+        let no_headers = HashMap::new();
+        let captures = captured_values(body, &no_headers, &capture).unwrap();
+        assert_eq!(captures["id"], "7");
+        assert_eq!(captures["name"], "export");
+        assert_eq!(captures["tags"], r#"["a"]"#);
+
+        let missing = HashMap::from([("owner".to_string(), path("$.job.owner"))]);
+        let error = format!(
+            "{:#}",
+            captured_values(body, &no_headers, &missing).unwrap_err()
+        );
+        assert!(
+            error.starts_with("Failed to capture `owner` from $.job.owner: Field 'owner'"),
+            "{}",
+            error
+        );
+        let error = captured_values("<html>", &no_headers, &capture)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Failed to capture `id` from $.job.id: the response body is not JSON"
+        );
+    }
+
+    #[test]
+    fn test_captured_header_values() {
+        let headers = HashMap::from([
+            ("location".to_string(), "/items/42?v=2".to_string()),
+            ("x-request-id".to_string(), "req-9".to_string()),
+        ]);
+        let header = |header: &str, pattern: Option<&str>| CaptureSource::Header {
+            header: header.to_string(),
+            pattern: pattern.map(str::to_string),
+        };
+        let capture = HashMap::from([
+            ("url".to_string(), header("Location", None)),
+            ("id".to_string(), header("Location", Some(r"/items/(\d+)"))),
+            ("path".to_string(), header("Location", Some(r"^[^?]+"))),
+            ("request".to_string(), header("X-Request-Id", None)),
+        ]);
+        // Headers are captured whatever the body is
+        let captures = captured_values("", &headers, &capture).unwrap();
+        assert_eq!(captures["url"], "/items/42?v=2");
+        assert_eq!(captures["id"], "42");
+        assert_eq!(captures["path"], "/items/42");
+        assert_eq!(captures["request"], "req-9");
+
+        let missing = HashMap::from([("etag".to_string(), header("ETag", None))]);
+        let error = captured_values("", &headers, &missing).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to capture `etag`: the response has no `ETag` header"
+        );
+        let unmatched =
+            HashMap::from([("id".to_string(), header("location", Some(r"/users/(\d+)")))]);
+        let error = captured_values("", &headers, &unmatched).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r"Failed to capture `id`: `location` header `/items/42?v=2` does not match `/users/(\d+)`"
+        );
+    }
+
+    #[test]
+    fn test_poll_request() {
+        let request: Request = serde_yaml::from_str(
+            "method: POST\nurl: http://api.test/jobs\nbody: '{}'\ncompression: gzip\nheaders:\n  Authorization: Bearer t\n  Accept: text/plain\n",
+        )
+        .unwrap();
+        let poll: Poll =
+            serde_yaml::from_str("headers:\n  accept: application/json\nuntil:\n  status: 200\n")
+                .unwrap();
+        let headers = HashMap::from([("location".to_string(), "/jobs/7".to_string())]);
+
+        let polled = poll_request(
+            &request,
+            &poll,
+            Some(&headers),
+            Some("http://api.test/jobs"),
+        )
+        .unwrap();
+        assert_eq!(polled.method, "GET");
+        assert_eq!(polled.url, "http://api.test/jobs/7");
+        assert!(polled.body.is_none());
+        assert_eq!(polled.compression, request.compression);
+        let sent = polled.headers.unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent["Authorization"], "Bearer t");
+        assert_eq!(sent["accept"], "application/json");
+
+        let error = poll_request(&request, &poll, None, None).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Poll has no url and the response has no Location header"
+        );
+    }
+}
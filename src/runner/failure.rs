@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// Why a test failed, so reports can group failures by cause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FailureKind {
+    /// The request couldn't be sent, or the response couldn't be read
+    ConnectionError,
+    /// No response within `--timeout`
+    Timeout,
+    /// An unexpected status, or 4xx/5xx for a step that expects nothing
+    HttpStatus,
+    /// A header, content type, redirect, JSONPath or schema assertion failed
+    AssertionFailure,
+    /// Assertions needed a JSON body and the response had none
+    InvalidResponseBody,
+    /// The test couldn't be prepared, e.g. a variable or auth failed
+    SetupFailure,
+    /// `expect.poll` got responses, but none met its `until` in time
+    PollTimeout,
+    /// Anything else, and failures in reports written before kinds existed
+    Unknown,
+}
+
+impl FailureKind {
+    /// Every kind, in the order summaries list them.
+    pub const ALL: [FailureKind; 8] = [
+        FailureKind::ConnectionError,
+        FailureKind::Timeout,
+        FailureKind::HttpStatus,
+        FailureKind::AssertionFailure,
+        FailureKind::InvalidResponseBody,
+        FailureKind::SetupFailure,
+        FailureKind::PollTimeout,
+        FailureKind::Unknown,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::ConnectionError => "connection error",
+            FailureKind::Timeout => "timeout",
+            FailureKind::HttpStatus => "HTTP status",
+            FailureKind::AssertionFailure => "assertion failure",
+            FailureKind::InvalidResponseBody => "invalid response body",
+            FailureKind::SetupFailure => "setup failure",
+            FailureKind::PollTimeout => "poll timeout",
+            FailureKind::Unknown => "unknown",
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! `include:` and `use:` in suites. Included files lend their `vars`,
+//! `setup` steps and step `templates` to the suite including them, and
+//! steps say `use: <template>` to start from a template. Both are expanded
+//! in the YAML before it's read as a [`RivetConfig`](crate::config::RivetConfig).
+
+use crate::runner::parser::{describe_suite_path, resolve_suite_path};
+use anyhow::{Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// How many levels of files a suite may include through included files.
+pub const MAX_INCLUDE_DEPTH: usize = 8;
+
+const STEP_SECTIONS: [&str; 5] = ["setup", "before_each", "tests", "after_each", "teardown"];
+
+/// Whether `document` includes files or uses templates, and so needs
+/// [`compose`] before it can be read.
+pub fn is_composed(document: &Value) -> bool {
+    document.get("include").is_some()
+        || document.get("templates").is_some()
+        || STEP_SECTIONS.iter().any(|section| {
+            document
+                .get(section)
+                .and_then(Value::as_sequence)
+                .is_some_and(|steps| steps.iter().any(|step| step.get("use").is_some()))
+        })
+}
+
+/// Expand the includes and template uses of the suite `document` read from
+/// `path`. Included vars are defaults under the suite's own, with later
+/// includes winning over earlier ones; included setup steps run first, in
+/// include order. A file included twice contributes once.
+pub fn compose(mut document: Value, path: &Path) -> Result<Value> {
+    let Some(suite) = document.as_mapping_mut() else {
+        return Ok(document);
+    };
+
+    let mut included = Included::default();
+    let root = path
+        .canonicalize()
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let mut chain = vec![root.clone()];
+    let mut seen = HashSet::from([root]);
+    included.collect(suite, path, &mut chain, &mut seen)?;
+
+    included.vars.extend(take_mapping(suite, "vars")?);
+    if !included.vars.is_empty() {
+        suite.insert("vars".into(), Value::Mapping(included.vars));
+    }
+    included.setup.extend(take_sequence(suite, "setup")?);
+    if !included.setup.is_empty() {
+        suite.insert("setup".into(), Value::Sequence(included.setup));
+    }
+    included.templates.extend(take_mapping(suite, "templates")?);
+
+    for section in STEP_SECTIONS {
+        let Some(steps) = suite.get_mut(section).and_then(Value::as_sequence_mut) else {
+            continue;
+        };
+        for step in steps {
+            use_template(step, &included.templates)?;
+        }
+    }
+    if !included.templates.is_empty() {
+        suite.insert("templates".into(), Value::Mapping(included.templates));
+    }
+    Ok(document)
+}
+
+/// What a suite's included files lend it.
+#[derive(Default)]
+struct Included {
+    vars: Mapping,
+    setup: Vec<Value>,
+    templates: Mapping,
+}
+
+impl Included {
+    /// Take on the includes of `file`, depth first, so a file's own vars
+    /// and templates win over those of the files it includes. `chain` is the
+    /// files being included, outermost first.
+    fn collect(
+        &mut self,
+        suite: &Mapping,
+        file: &Path,
+        chain: &mut Vec<PathBuf>,
+        seen: &mut HashSet<PathBuf>,
+    ) -> Result<()> {
+        let Some(include) = suite.get("include") else {
+            return Ok(());
+        };
+        let declared: Vec<&str> = include
+            .as_sequence()
+            .and_then(|paths| paths.iter().map(Value::as_str).collect())
+            .with_context(|| {
+                format!(
+                    "`include` in {} must be a list of file paths",
+                    file.display()
+                )
+            })?;
+        let dir = file.parent().unwrap_or(Path::new("."));
+
+        for declared in declared {
+            let path = resolve_suite_path(dir, declared);
+            let canonical = path.canonicalize().with_context(|| {
+                format!(
+                    "Failed to read included file {} in {}",
+                    describe_suite_path(declared, &path),
+                    file.display()
+                )
+            })?;
+            if let Some(start) = chain.iter().position(|seen| *seen == canonical) {
+                let cycle: Vec<String> = chain[start..]
+                    .iter()
+                    .chain([&canonical])
+                    .map(|path| path.display().to_string())
+                    .collect();
+                anyhow::bail!("Circular include: {}", cycle.join(" -> "));
+            }
+            if chain.len() > MAX_INCLUDE_DEPTH {
+                anyhow::bail!(
+                    "Includes nested more than {} deep at {} in {}",
+                    MAX_INCLUDE_DEPTH,
+                    declared,
+                    file.display()
+                );
+            }
+            if !seen.insert(canonical.clone()) {
+                continue;
+            }
+
+            let content = std::fs::read_to_string(&canonical)
+                .with_context(|| format!("Failed to read included file: {}", path.display()))?;
+            let document: Value = serde_yaml::from_str(&content)
+                .with_context(|| format!("Failed to parse YAML in file: {}", path.display()))?;
+            let mut included = match document {
+                Value::Mapping(mapping) => mapping,
+                Value::Null => Mapping::new(),
+                _ => anyhow::bail!("Included file {} is not a mapping", path.display()),
+            };
+            let included_dir = canonical.parent().unwrap_or(Path::new("/")).to_path_buf();
+
+            chain.push(canonical);
+            self.collect(&included, &path, chain, seen)?;
+            chain.pop();
+
+            self.vars.extend(take_mapping(&mut included, "vars")?);
+            let mut setup = take_sequence(&mut included, "setup")?;
+            for step in &mut setup {
+                rebase_paths(step, &included_dir);
+            }
+            self.setup.extend(setup);
+            let mut templates = take_mapping(&mut included, "templates")?;
+            for (_, template) in templates.iter_mut() {
+                rebase_paths(template, &included_dir);
+            }
+            self.templates.extend(templates);
+        }
+        Ok(())
+    }
+}
+
+/// Make the relative file paths in an included `step` absolute against
+/// `dir`, the directory of the file declaring it, since the step runs from
+/// the including suite's directory. `expect.schema` is the only step field
+/// naming a file; inline schemas and paths starting with a variable are
+/// left as they are.
+fn rebase_paths(step: &mut Value, dir: &Path) {
+    let Some(schema) = step
+        .get_mut("expect")
+        .and_then(|expect| expect.get_mut("schema"))
+    else {
+        return;
+    };
+    let Some(declared) = schema.as_str() else {
+        return;
+    };
+    if declared.trim_start().starts_with('{') || Path::new(declared).is_absolute() {
+        return;
+    }
+    *schema = Value::String(dir.join(declared).to_string_lossy().into_owned());
+}
+
+/// Replace a `use: <template>` step with the template, overlaid with the
+/// step's own settings: mappings such as `vars` and `request` are merged,
+/// and the step's values win.
+fn use_template(step: &mut Value, templates: &Mapping) -> Result<()> {
+    let Some(own) = step.as_mapping_mut() else {
+        return Ok(());
+    };
+    let Some(name) = own.remove("use") else {
+        return Ok(());
+    };
+    let name = name
+        .as_str()
+        .context("`use` must be the name of a template")?
+        .to_string();
+    let template = templates.get(name.as_str()).with_context(|| {
+        let mut available: Vec<&str> = templates.keys().filter_map(Value::as_str).collect();
+        available.sort_unstable();
+        if available.is_empty() {
+            format!("Unknown template `{}`; no templates are defined", name)
+        } else {
+            format!(
+                "Unknown template `{}`; available: {}",
+                name,
+                available.join(", ")
+            )
+        }
+    })?;
+    if template.get("use").is_some() {
+        anyhow::bail!("Template `{}` can't `use` another template", name);
+    }
+
+    let mut expanded = template.clone();
+    merge(&mut expanded, Value::Mapping(std::mem::take(own)));
+    if let Value::Mapping(expanded) = &mut expanded {
+        if !expanded.contains_key("name") {
+            expanded.insert("name".into(), name.into());
+        }
+    }
+    *step = expanded;
+    Ok(())
+}
+
+/// Overlay `over` on `base`, merging mappings key by key.
+fn merge(base: &mut Value, over: Value) {
+    match (base, over) {
+        (Value::Mapping(base), Value::Mapping(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
+
+fn take_mapping(document: &mut Mapping, key: &str) -> Result<Mapping> {
+    match document.remove(key) {
+        None | Some(Value::Null) => Ok(Mapping::new()),
+        Some(Value::Mapping(mapping)) => Ok(mapping),
+        Some(_) => anyhow::bail!("`{}` must be a mapping", key),
+    }
+}
+
+fn take_sequence(document: &mut Mapping, key: &str) -> Result<Vec<Value>> {
+    match document.remove(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Sequence(steps)) => Ok(steps),
+        Some(_) => anyhow::bail!("`{}` must be a list of steps", key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(text: &str) -> Value {
+        serde_yaml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_merge_overlays_nested_mappings() {
+        let mut base = yaml("request: {method: POST, url: /login}\nvars: {user: admin, pass: x}");
+        merge(
+            &mut base,
+            yaml("vars: {user: bob}\nrequest: {url: /signin}"),
+        );
+        assert_eq!(
+            base,
+            yaml("request: {method: POST, url: /signin}\nvars: {user: bob, pass: x}")
+        );
+    }
+
+    #[test]
+    fn test_use_template_names_the_step_after_the_template() {
+        let templates = yaml("login: {request: {method: POST, url: /login}}");
+        let mut step = yaml("use: login\nvars: {user: bob}");
+        use_template(&mut step, templates.as_mapping().unwrap()).unwrap();
+        assert_eq!(
+            step,
+            yaml("request: {method: POST, url: /login}\nvars: {user: bob}\nname: login")
+        );
+
+        let mut step = yaml("use: logout");
+        let error = use_template(&mut step, templates.as_mapping().unwrap()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown template `logout`; available: login"
+        );
+    }
+
+    #[test]
+    fn test_is_composed() {
+        assert!(!is_composed(&yaml("name: plain\ntests: []")));
+        assert!(is_composed(&yaml("name: s\ninclude: [common.yaml]")));
+        assert!(is_composed(&yaml("name: s\ntests: [{use: login}]")));
+    }
+}
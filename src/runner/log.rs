@@ -0,0 +1,309 @@
+//! Per-step request and response logging for `rivet run -v` and `--log-file`.
+
+use crate::runner::connections::ConnectionStats;
+use crate::runner::variables::VariableContext;
+use crate::style::Colorize;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Body bytes `-v` prints; `-vv` prints whole bodies.
+const BODY_PREVIEW: usize = 1024;
+
+/// A request as it went out, after variable substitution.
+pub struct LoggedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl From<&reqwest::Request> for LoggedRequest {
+    fn from(request: &reqwest::Request) -> Self {
+        Self {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers: header_list(request.headers()),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|bytes| String::from_utf8_lossy(bytes).to_string()),
+        }
+    }
+}
+
+pub struct LoggedResponse<'a> {
+    pub status: u16,
+    /// Protocol the response came over; unset for gRPC
+    pub http_version: Option<&'a str>,
+    pub headers: &'a [(String, String)],
+    pub body: &'a str,
+    pub duration: Duration,
+}
+
+/// Where each step's exchange goes: stderr at `-v` and up, and an NDJSON
+/// file when one is set. Values of the context's secrets are masked before
+/// anything is written.
+#[derive(Clone, Default)]
+pub struct RequestLog {
+    verbosity: u8,
+    file: Option<Arc<Mutex<File>>>,
+}
+
+impl RequestLog {
+    /// Log at `verbosity` (the number of `-v`s), and to `file` when set,
+    /// replacing what it held.
+    pub fn new(verbosity: u8, file: Option<&Path>) -> Result<Self> {
+        let file = file
+            .map(|path| {
+                File::create(path)
+                    .with_context(|| format!("Failed to create log file {}", path.display()))
+            })
+            .transpose()?;
+        Ok(Self {
+            verbosity,
+            file: file.map(|file| Arc::new(Mutex::new(file))),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.verbosity > 0 || self.file.is_some()
+    }
+
+    pub fn request(&self, step: &str, request: &LoggedRequest, context: &VariableContext) {
+        let url = context.redact(&request.url);
+        let headers = redact_headers(&request.headers, context);
+        let body = request.body.as_deref().map(|body| context.redact(body));
+
+        if self.verbosity > 0 {
+            let mut text = format!(
+                "{} {}\n{} {} {}\n",
+                "*".dimmed(),
+                step,
+                ">".dimmed(),
+                request.method,
+                url
+            );
+            for (name, value) in &headers {
+                text.push_str(&format!("{} {}: {}\n", ">".dimmed(), name, value));
+            }
+            text.push_str(&format!("{}\n", ">".dimmed()));
+            if let Some(body) = &body {
+                text.push_str(&self.body_text(body));
+            }
+            eprint!("{}", text);
+        }
+        self.write(json!({
+            "event": "request",
+            "step": step,
+            "method": request.method,
+            "url": url,
+            "headers": header_object(&headers),
+            "body": body,
+        }));
+    }
+
+    pub fn response(&self, step: &str, response: &LoggedResponse, context: &VariableContext) {
+        let headers = redact_headers(response.headers, context);
+        let body = context.redact(response.body);
+
+        if self.verbosity > 0 {
+            let status = match response.http_version {
+                Some(version) => format!(
+                    "{} {} {}",
+                    version,
+                    response.status,
+                    reqwest::StatusCode::from_u16(response.status)
+                        .ok()
+                        .and_then(|status| status.canonical_reason())
+                        .unwrap_or_default()
+                ),
+                None => format!("status {}", response.status),
+            };
+            let mut text = format!(
+                "{} {}\n{} {} ({}ms)\n",
+                "*".dimmed(),
+                step,
+                "<".dimmed(),
+                status.trim_end(),
+                response.duration.as_millis()
+            );
+            for (name, value) in &headers {
+                text.push_str(&format!("{} {}: {}\n", "<".dimmed(), name, value));
+            }
+            text.push_str(&format!("{}\n", "<".dimmed()));
+            text.push_str(&self.body_text(&body));
+            eprint!("{}", text);
+        }
+        self.write(json!({
+            "event": "response",
+            "step": step,
+            "status": response.status,
+            "http_version": response.http_version,
+            "headers": header_object(&headers),
+            "body": body,
+            "duration_ms": response.duration.as_millis() as u64,
+        }));
+    }
+
+    /// A step that got no response.
+    pub fn error(&self, step: &str, error: &str, context: &VariableContext) {
+        let error = context.redact(error);
+        if self.verbosity > 0 {
+            eprintln!("{} {}\n{} {}", "*".dimmed(), step, "!".red(), error.red());
+        }
+        self.write(json!({
+            "event": "error",
+            "step": step,
+            "error": error,
+        }));
+    }
+
+    /// How many connections the run's requests needed, to show keep-alive at work.
+    pub fn connections(&self, stats: &ConnectionStats) {
+        let (opened, requests) = (stats.connections_opened(), stats.requests());
+        if self.verbosity > 0 {
+            eprintln!(
+                "{} {} connection(s) opened for {} request(s)",
+                "*".dimmed(),
+                opened,
+                requests
+            );
+        }
+        self.write(json!({
+            "event": "connections",
+            "opened": opened,
+            "requests": requests,
+        }));
+    }
+
+    /// The body as printed: cut to [`BODY_PREVIEW`] bytes below `-vv`.
+    fn body_text(&self, body: &str) -> String {
+        if body.is_empty() {
+            return String::new();
+        }
+        if self.verbosity >= 2 || body.len() <= BODY_PREVIEW {
+            return format!("{}\n", body);
+        }
+        let mut end = BODY_PREVIEW;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!(
+            "{}\n{}\n",
+            &body[..end],
+            format!(
+                "… {} more bytes (-vv prints whole bodies)",
+                body.len() - end
+            )
+            .dimmed()
+        )
+    }
+
+    fn write(&self, mut event: Value) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        event["timestamp"] = Value::from(Utc::now().to_rfc3339());
+        let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+        // A log that can't be written must not fail the run
+        let _ = writeln!(file, "{}", event);
+    }
+}
+
+pub fn header_list(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.as_str().to_string(),
+                String::from_utf8_lossy(value.as_bytes()).to_string(),
+            )
+        })
+        .collect()
+}
+
+fn redact_headers(
+    headers: &[(String, String)],
+    context: &VariableContext,
+) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| (name.clone(), context.redact(value)))
+        .collect()
+}
+
+/// Headers as a JSON object; repeated ones are joined with `, `.
+fn header_object(headers: &[(String, String)]) -> Value {
+    let mut object = Map::new();
+    for (name, value) in headers {
+        match object.get_mut(name) {
+            Some(Value::String(existing)) => {
+                existing.push_str(", ");
+                existing.push_str(value);
+            }
+            _ => {
+                object.insert(name.clone(), Value::from(value.as_str()));
+            }
+        }
+    }
+    Value::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_preview_is_cut_below_vv() {
+        let body = "é".repeat(BODY_PREVIEW);
+        let log = RequestLog::new(1, None).unwrap();
+        let text = log.body_text(&body);
+        assert!(text.starts_with(&"é".repeat(BODY_PREVIEW / 2)));
+        assert!(text.contains(&format!("… {} more bytes", BODY_PREVIEW)));
+
+        let log = RequestLog::new(2, None).unwrap();
+        assert_eq!(log.body_text(&body), format!("{}\n", body));
+    }
+
+    #[test]
+    fn test_log_file_masks_secrets() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("run.ndjson");
+        let log = RequestLog::new(0, Some(&path)).unwrap();
+        let mut context = VariableContext::new().with_secrets(["token"]);
+        context.set_variable("token".to_string(), "s3cret".to_string());
+
+        log.request(
+            "Login",
+            &LoggedRequest {
+                method: "POST".to_string(),
+                url: "http://api.test/login?key=s3cret".to_string(),
+                headers: vec![
+                    ("authorization".to_string(), "Bearer s3cret".to_string()),
+                    ("accept".to_string(), "a".to_string()),
+                    ("accept".to_string(), "b".to_string()),
+                ],
+                body: Some(r#"{"token":"s3cret"}"#.to_string()),
+            },
+            &context,
+        );
+        log.error("Login", "connection refused", &context);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("s3cret"), "{}", content);
+        let events: Vec<Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events[0]["event"], "request");
+        assert_eq!(events[0]["headers"]["accept"], "a, b");
+        assert!(events[0]["timestamp"].is_string());
+        assert_eq!(events[1]["event"], "error");
+    }
+}
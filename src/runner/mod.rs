@@ -1,8 +1,19 @@
 pub mod assertions;
+pub mod auth;
+pub mod base_url;
+pub mod compression;
+pub mod connections;
 pub mod data;
+pub mod events;
 pub mod executor;
+pub mod failure;
+pub mod include;
+pub mod log;
+pub mod order;
 pub mod parser;
+pub mod reporter;
 pub mod test_runner;
+pub mod validate;
 pub mod variables;
 
 pub use test_runner::TestRunner;
@@ -0,0 +1,153 @@
+use crate::runner::data::SplitMix64;
+use crate::runner::parser::SuiteFile;
+
+/// The order `rivet run` runs suites, and tests within each suite, in.
+/// Setup, `before_each`, `after_each` and teardown steps keep their places.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TestOrder {
+    /// Suites by file name, tests as written in the suite
+    #[default]
+    Declared,
+    /// Suites by their path under the target, tests as written
+    File,
+    /// Suites by `name`, and tests by name within each suite
+    Name,
+    /// A random order of suites and of tests within each suite, repeated by
+    /// running with the same seed
+    Shuffle { seed: u64 },
+}
+
+impl std::str::FromStr for TestOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "declared" => Ok(TestOrder::Declared),
+            "file" => Ok(TestOrder::File),
+            "name" => Ok(TestOrder::Name),
+            _ => anyhow::bail!("Invalid order '{}'. Use: declared, file, name", s),
+        }
+    }
+}
+
+impl TestOrder {
+    /// Reorder `suites`, loaded in declared order, and their tests.
+    pub fn apply(&self, suites: &mut [SuiteFile]) {
+        match *self {
+            TestOrder::Declared => {}
+            TestOrder::File => suites.sort_by(|a, b| a.path.cmp(&b.path)),
+            TestOrder::Name => {
+                suites.sort_by(|a, b| a.config.name.cmp(&b.config.name));
+                for suite in suites {
+                    suite.config.tests.sort_by(|a, b| a.name.cmp(&b.name));
+                }
+            }
+            TestOrder::Shuffle { seed } => {
+                let mut rng = SplitMix64(seed);
+                shuffle(suites, &mut rng);
+                for suite in suites {
+                    shuffle(&mut suite.config.tests, &mut rng);
+                }
+            }
+        }
+    }
+}
+
+/// Fisher-Yates shuffle of `items`.
+fn shuffle<T>(items: &mut [T], rng: &mut SplitMix64) {
+    for i in (1..items.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RivetConfig;
+    use std::path::PathBuf;
+
+    fn suites() -> Vec<SuiteFile> {
+        ["b/users", "a/orders", "c/carts", "a/zones"]
+            .iter()
+            .map(|path| {
+                let name = path.rsplit('/').next().unwrap();
+                let yaml = format!(
+                    "name: {}\ntests:\n{}",
+                    name,
+                    (1..=8)
+                        .map(|i| format!(
+                            "  - name: step {}\n    request:\n      method: GET\n      url: /{}\n",
+                            i, i
+                        ))
+                        .collect::<String>()
+                );
+                let config: RivetConfig = serde_yaml::from_str(&yaml).unwrap();
+                SuiteFile {
+                    name: format!("{}.rivet.yaml", name),
+                    path: PathBuf::from(format!("{}.rivet.yaml", path)),
+                    config,
+                    warnings: Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    fn ordered(order: TestOrder) -> Vec<(String, Vec<String>)> {
+        let mut suites = suites();
+        order.apply(&mut suites);
+        suites
+            .into_iter()
+            .map(|suite| {
+                let tests = suite.config.tests.into_iter().map(|t| t.name).collect();
+                (suite.config.name, tests)
+            })
+            .collect()
+    }
+
+    fn suite_names(order: TestOrder) -> Vec<String> {
+        ordered(order).into_iter().map(|(name, _)| name).collect()
+    }
+
+    #[test]
+    fn test_explicit_orders() {
+        assert_eq!(
+            suite_names(TestOrder::Declared),
+            ["users", "orders", "carts", "zones"]
+        );
+        assert_eq!(
+            suite_names(TestOrder::File),
+            ["orders", "zones", "users", "carts"]
+        );
+        assert_eq!(
+            suite_names(TestOrder::Name),
+            ["carts", "orders", "users", "zones"]
+        );
+        assert!("random".parse::<TestOrder>().is_err());
+        assert_eq!("NAME".parse::<TestOrder>().unwrap(), TestOrder::Name);
+    }
+
+    #[test]
+    fn test_shuffle_is_reproducible_per_seed() {
+        let first = ordered(TestOrder::Shuffle { seed: 7 });
+        assert_eq!(ordered(TestOrder::Shuffle { seed: 7 }), first);
+
+        // Every suite and test is still there
+        let mut names = suite_names(TestOrder::Shuffle { seed: 7 });
+        names.sort();
+        assert_eq!(names, ["carts", "orders", "users", "zones"]);
+        for (_, tests) in &first {
+            let mut tests = tests.clone();
+            tests.sort();
+            assert_eq!(tests.len(), 8);
+            tests.dedup();
+            assert_eq!(tests.len(), 8);
+        }
+
+        // Different seeds almost always give different orders
+        let differing = (0..20)
+            .filter(|seed| ordered(TestOrder::Shuffle { seed: *seed }) != first)
+            .count();
+        assert!(differing >= 18, "{} of 20 seeds differ", differing);
+    }
+}
@@ -1,41 +1,185 @@
 use crate::config::RivetConfig;
+use crate::runner::include;
 use anyhow::{Context, Result};
-use std::path::Path;
+use futures::stream::{self, StreamExt};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 use walkdir::WalkDir;
 
-pub async fn load_test_suite(path: &Path) -> Result<Vec<(String, RivetConfig)>> {
+/// A loaded suite, with the file it came from.
+#[derive(Debug, Clone)]
+pub struct SuiteFile {
+    /// The file name, or the path relative to the loaded directory
+    pub name: String,
+    pub path: PathBuf,
+    pub config: RivetConfig,
+    /// Problems that didn't stop the suite loading, e.g. a renamed step
+    pub warnings: Vec<String>,
+}
+
+impl SuiteFile {
+    /// The directory relative paths in the suite (datasets, schemas) resolve against.
+    pub fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new(""))
+    }
+}
+
+/// `declared` relative to `dir`, or as is when absolute.
+pub fn resolve_suite_path(dir: &Path, declared: &str) -> PathBuf {
+    let path = Path::new(declared);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        dir.join(path)
+    }
+}
+
+/// `declared` for error messages, with the absolute path it resolved to
+/// when that differs, e.g. `data/users.csv (resolved to /work/api/data/users.csv)`.
+pub fn describe_suite_path(declared: &str, resolved: &Path) -> String {
+    let absolute = std::env::current_dir()
+        .map(|cwd| cwd.join(resolved))
+        .unwrap_or_else(|_| resolved.to_path_buf());
+    if absolute == Path::new(declared) {
+        declared.to_string()
+    } else {
+        format!("{} (resolved to {})", declared, absolute.display())
+    }
+}
+
+/// Load the suite at `path`, or every suite under it keyed by its path
+/// relative to `path`. `strict` rejects suites that repeat a step name
+/// rather than renaming the repeats (see [`load_single_file`]).
+pub async fn load_test_suite(path: &Path, strict: bool) -> Result<Vec<SuiteFile>> {
     if path.is_file() {
-        let config = load_single_file(path).await?;
+        let (config, warnings) = load_suite_file(path, strict).await?;
         let file_name = path
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("unknown")
             .to_string();
-        Ok(vec![(file_name, config)])
+        Ok(vec![SuiteFile {
+            name: file_name,
+            path: path.to_path_buf(),
+            config,
+            warnings,
+        }])
     } else if path.is_dir() {
-        load_directory(path).await
+        load_directory(path, strict).await
     } else {
-        anyhow::bail!("Path does not exist: {}", path.display());
+        Err(path_not_found(path))
     }
 }
 
-async fn load_single_file(path: &Path) -> Result<RivetConfig> {
+/// An I/O error, so the run counts as unable to read its suites.
+fn path_not_found(path: &Path) -> anyhow::Error {
+    anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::NotFound))
+        .context(format!("Path does not exist: {}", path.display()))
+}
+
+/// Read and parse one suite file. Step names share one namespace across
+/// setup, tests and teardown; a repeated name is an error when `strict`,
+/// and otherwise gets a ` (2)`, ` (3)`, ... suffix. [`load_test_suite`]
+/// returns a warning for each rename in [`SuiteFile::warnings`].
+pub async fn load_single_file(path: &Path, strict: bool) -> Result<RivetConfig> {
+    Ok(load_suite_file(path, strict).await?.0)
+}
+
+/// [`load_single_file`], with the warnings about what loading changed.
+async fn load_suite_file(path: &Path, strict: bool) -> Result<(RivetConfig, Vec<String>)> {
     let content = fs::read_to_string(path)
         .await
         .with_context(|| format!("Failed to read file: {}", path.display()))?;
 
-    let config: RivetConfig = serde_yaml::from_str(&content)
+    let document: serde_yaml::Value = serde_yaml::from_str(&content)
         .with_context(|| format!("Failed to parse YAML in file: {}", path.display()))?;
+    // Plain suites are read from the text, so errors keep their location
+    let config = if include::is_composed(&document) {
+        serde_yaml::from_value(include::compose(document, path)?)
+    } else {
+        serde_yaml::from_str(&content)
+    };
+    let mut config: RivetConfig =
+        config.with_context(|| format!("Failed to parse YAML in file: {}", path.display()))?;
+    config.apply_step_defaults();
+    let warnings = dedupe_step_names(&mut config, path, strict)?;
+
+    Ok((config, warnings))
+}
+
+/// Rename repeated step names, or reject them when `strict`. Returns a
+/// warning per rename.
+fn dedupe_step_names(config: &mut RivetConfig, path: &Path, strict: bool) -> Result<Vec<String>> {
+    let sections = [
+        config.setup.as_mut(),
+        config.before_each.as_mut(),
+        Some(&mut config.tests),
+        config.after_each.as_mut(),
+        config.teardown.as_mut(),
+    ];
+    let mut steps: Vec<_> = sections.into_iter().flatten().flatten().collect();
+    let mut names: HashSet<String> = HashSet::new();
+    let mut repeated = Vec::new();
+    let mut warnings = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        if !names.insert(step.name.clone()) {
+            repeated.push(index);
+        }
+    }
 
-    Ok(config)
+    for index in repeated {
+        let name = &steps[index].name;
+        if strict {
+            anyhow::bail!(
+                "Duplicate step name '{}' in {}; step names must be unique within a suite",
+                name,
+                path.display()
+            );
+        }
+        let renamed = (2..)
+            .map(|n| format!("{} ({})", name, n))
+            .find(|candidate| !names.contains(candidate))
+            .expect("unbounded");
+        warnings.push(format!(
+            "Duplicate step name '{}' in {}; running it as '{}'",
+            name,
+            path.display(),
+            renamed
+        ));
+        names.insert(renamed.clone());
+        steps[index].name = renamed;
+    }
+    Ok(warnings)
 }
 
-async fn load_directory(path: &Path) -> Result<Vec<(String, RivetConfig)>> {
-    let mut configs = Vec::new();
+/// Directories never searched for suites.
+const SKIPPED_DIRS: &[&str] = &[".git", "node_modules", "reports"];
 
-    for entry in WalkDir::new(path)
+/// Gitignore-style patterns, in the directory being searched, for files and
+/// directories that aren't suites.
+pub const IGNORE_FILE: &str = ".rivetignore";
+
+/// Suite files read and parsed at once when loading a directory.
+const LOAD_CONCURRENCY: usize = 64;
+
+/// The suite files (`*.rivet.yaml` / `*.rivet.yml`) under `path`, sorted by
+/// file name, or `path` itself when it is a file. `.git`, `node_modules`
+/// and `reports` directories are skipped, as is anything `path`'s
+/// `.rivetignore` matches.
+pub fn find_suite_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if !path.is_dir() {
+        return Err(path_not_found(path));
+    }
+
+    let ignore = load_ignore_file(path)?;
+    let mut files: Vec<PathBuf> = WalkDir::new(path)
         .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !is_ignored(e, &ignore))
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
         .filter(|e| {
@@ -52,13 +196,10 @@ async fn load_directory(path: &Path) -> Result<Vec<(String, RivetConfig)>> {
                 .map(|name| name.contains(".rivet."))
                 .unwrap_or(false)
         })
-    {
-        let config = load_single_file(entry.path()).await?;
-        let file_name = entry.file_name().to_string_lossy().to_string();
-        configs.push((file_name, config));
-    }
+        .map(|e| e.into_path())
+        .collect();
 
-    if configs.is_empty() {
+    if files.is_empty() {
         anyhow::bail!(
             "No .rivet.yaml files found in directory: {}",
             path.display()
@@ -66,14 +207,118 @@ async fn load_directory(path: &Path) -> Result<Vec<(String, RivetConfig)>> {
     }
 
     // Sort by filename for consistent execution order
-    configs.sort_by(|a, b| a.0.cmp(&b.0));
+    files.sort_by(|a, b| a.file_name().cmp(&b.file_name()).then_with(|| a.cmp(b)));
 
-    Ok(configs)
+    Ok(files)
+}
+
+/// Whether the search for suites leaves out `entry`: a directory that never
+/// holds them, or anything `.rivetignore` matches.
+fn is_ignored(entry: &walkdir::DirEntry, ignore: &Gitignore) -> bool {
+    let is_dir = entry.file_type().is_dir();
+    let skipped = is_dir
+        && entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| SKIPPED_DIRS.contains(&name));
+    skipped || ignore.matched(entry.path(), is_dir).is_ignore()
+}
+
+/// The `.rivetignore` patterns of `dir`, if it has one.
+fn load_ignore_file(dir: &Path) -> Result<Gitignore> {
+    let file = dir.join(IGNORE_FILE);
+    if !file.is_file() {
+        return Ok(Gitignore::empty());
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&file) {
+        return Err(e).with_context(|| format!("Invalid pattern in {}", file.display()));
+    }
+    builder
+        .build()
+        .with_context(|| format!("Invalid pattern in {}", file.display()))
+}
+
+async fn load_directory(path: &Path, strict: bool) -> Result<Vec<SuiteFile>> {
+    // The first bad file in order is the one reported
+    load_each_suite(path, strict)
+        .await?
+        .into_iter()
+        .map(|suite| suite.map_err(|failure| failure.error))
+        .collect()
+}
+
+/// A suite file that didn't load, e.g. because it doesn't parse.
+#[derive(Debug)]
+pub struct LoadFailure {
+    /// Named as the [`SuiteFile`] would have been
+    pub name: String,
+    pub path: PathBuf,
+    pub error: anyhow::Error,
+}
+
+/// Load the suites at `path` as [`load_test_suite`] does, but past any that
+/// fail: each file comes back loaded or with its error, in the order found.
+/// Only a `path` with no suites to load is an error.
+pub async fn load_each_suite(
+    path: &Path,
+    strict: bool,
+) -> Result<Vec<Result<SuiteFile, LoadFailure>>> {
+    let files = find_suite_files(path)?;
+    let root = if path.is_file() {
+        path.parent().unwrap_or(Path::new(""))
+    } else {
+        path
+    };
+    // Parsing is CPU-bound, so each file gets a task and can use another core
+    let mut loaded: Vec<_> = stream::iter(files.into_iter().enumerate())
+        .map(|(index, file)| {
+            let name = relative_name(&file, root);
+            let task = {
+                let file = file.clone();
+                tokio::spawn(async move { load_suite_file(&file, strict).await })
+            };
+            async move {
+                let loaded = task.await.unwrap_or_else(|e| Err(e.into()));
+                let suite = match loaded {
+                    Ok((config, warnings)) => Ok(SuiteFile {
+                        name,
+                        path: file,
+                        config,
+                        warnings,
+                    }),
+                    Err(error) => Err(LoadFailure {
+                        name,
+                        path: file,
+                        error,
+                    }),
+                };
+                (index, suite)
+            }
+        })
+        .buffer_unordered(LOAD_CONCURRENCY)
+        .collect()
+        .await;
+
+    // Back in the order the files were found
+    loaded.sort_unstable_by_key(|(index, _)| *index);
+    Ok(loaded.into_iter().map(|(_, suite)| suite).collect())
+}
+
+/// `file` relative to `root` with `/` separators, e.g. `users/smoke.rivet.yaml`.
+fn relative_name(file: &Path, root: &Path) -> String {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::TestStep;
     use std::collections::HashMap;
     use tempfile::{NamedTempFile, TempDir};
     use tokio::fs::write;
@@ -83,30 +328,55 @@ mod tests {
         vars.insert("baseUrl".to_string(), "https://api.example.com".to_string());
 
         RivetConfig {
+            default_headers: None,
+            identify_requests: None,
+            base_path: None,
+            include: None,
+            templates: None,
+            auth: None,
+            max_capture_bytes: None,
+            follow_redirects: None,
             name: "Test Suite".to_string(),
             description: Some("Sample test suite".to_string()),
             env: Some("test".to_string()),
             vars: Some(vars),
             setup: None,
             tests: vec![crate::config::TestStep {
+                capture: None,
                 name: "Test GET request".to_string(),
                 description: Some("Test a simple GET request".to_string()),
+                vars: None,
                 request: crate::config::Request {
+                    compression: None,
+                    follow_redirects: None,
                     method: "GET".to_string(),
                     url: "{{baseUrl}}/users".to_string(),
                     headers: None,
                     params: None,
                     body: None,
+                    grpc: None,
                 },
                 expect: Some(crate::config::Expectation {
+                    poll: None,
+                    redirects_to: None,
+                    content_type: None,
                     status: Some(crate::config::StatusExpectation::Number(200)),
                     schema: None,
                     jsonpath: None,
+                    jsonpath_length: None,
+                    jsonpath_contains: None,
+                    jsonpath_every: None,
                     headers: None,
                 }),
             }],
             dataset: None,
             teardown: None,
+            tls: None,
+            insecure: None,
+            before_each: None,
+            after_each: None,
+            secrets: None,
+            strict_vars: None,
         }
     }
 
@@ -118,7 +388,7 @@ mod tests {
         let temp_file = NamedTempFile::new().unwrap();
         write(temp_file.path(), yaml_content).await.unwrap();
 
-        let loaded_config = load_single_file(temp_file.path()).await.unwrap();
+        let loaded_config = load_single_file(temp_file.path(), false).await.unwrap();
 
         assert_eq!(loaded_config.name, "Test Suite");
         assert_eq!(
@@ -136,7 +406,7 @@ mod tests {
             .await
             .unwrap();
 
-        let result = load_single_file(temp_file.path()).await;
+        let result = load_single_file(temp_file.path(), false).await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -146,7 +416,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_load_single_file_nonexistent() {
-        let result = load_single_file(Path::new("/nonexistent/file.yaml")).await;
+        let result = load_single_file(Path::new("/nonexistent/file.yaml"), false).await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -162,10 +432,10 @@ mod tests {
         let temp_file = NamedTempFile::new().unwrap();
         write(temp_file.path(), yaml_content).await.unwrap();
 
-        let result = load_test_suite(temp_file.path()).await.unwrap();
+        let result = load_test_suite(temp_file.path(), false).await.unwrap();
 
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0].1.name, "Test Suite");
+        assert_eq!(result[0].config.name, "Test Suite");
     }
 
     #[tokio::test]
@@ -189,14 +459,14 @@ mod tests {
         let non_rivet_file = temp_dir.path().join("config.yaml");
         write(&non_rivet_file, "some: config").await.unwrap();
 
-        let result = load_directory(temp_dir.path()).await.unwrap();
+        let result = load_directory(temp_dir.path(), false).await.unwrap();
 
         assert_eq!(result.len(), 2);
         // Results should be sorted by filename
-        assert_eq!(result[0].0, "test1.rivet.yaml");
-        assert_eq!(result[1].0, "test2.rivet.yml");
-        assert_eq!(result[0].1.name, "Test Suite");
-        assert_eq!(result[1].1.name, "Second Test Suite");
+        assert_eq!(result[0].name, "test1.rivet.yaml");
+        assert_eq!(result[1].name, "test2.rivet.yml");
+        assert_eq!(result[0].config.name, "Test Suite");
+        assert_eq!(result[1].config.name, "Second Test Suite");
     }
 
     #[tokio::test]
@@ -207,7 +477,7 @@ mod tests {
         let non_rivet_file = temp_dir.path().join("config.yaml");
         write(&non_rivet_file, "some: config").await.unwrap();
 
-        let result = load_directory(temp_dir.path()).await;
+        let result = load_directory(temp_dir.path(), false).await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -225,16 +495,295 @@ mod tests {
         let rivet_file = temp_dir.path().join("api.rivet.yaml");
         write(&rivet_file, yaml_content).await.unwrap();
 
-        let result = load_test_suite(temp_dir.path()).await.unwrap();
+        let result = load_test_suite(temp_dir.path(), false).await.unwrap();
 
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0].0, "api.rivet.yaml");
-        assert_eq!(result[0].1.name, "Test Suite");
+        assert_eq!(result[0].name, "api.rivet.yaml");
+        assert_eq!(result[0].config.name, "Test Suite");
+    }
+
+    #[tokio::test]
+    async fn test_load_directory_keys_suites_by_relative_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let yaml_content = serde_yaml::to_string(&create_sample_rivet_config()).unwrap();
+        for dir in ["users", "orders"] {
+            tokio::fs::create_dir(temp_dir.path().join(dir))
+                .await
+                .unwrap();
+            write(
+                temp_dir.path().join(dir).join("smoke.rivet.yaml"),
+                &yaml_content,
+            )
+            .await
+            .unwrap();
+        }
+
+        let result = load_test_suite(temp_dir.path(), false).await.unwrap();
+
+        let names: Vec<_> = result.iter().map(|suite| suite.name.as_str()).collect();
+        assert_eq!(names, ["orders/smoke.rivet.yaml", "users/smoke.rivet.yaml"]);
+    }
+
+    #[tokio::test]
+    async fn test_load_directory_skips_ignored_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let suite = "name: Suite\ntests: []\n";
+        for dir in [
+            "api",
+            ".git",
+            "node_modules/pkg",
+            "reports",
+            "generated/v1",
+            "api/drafts",
+        ] {
+            std::fs::create_dir_all(temp_dir.path().join(dir)).unwrap();
+        }
+        for file in [
+            "api/users.rivet.yaml",
+            "api/orders.wip.rivet.yaml",
+            "api/drafts/new.rivet.yaml",
+            ".git/stale.rivet.yaml",
+            "node_modules/pkg/example.rivet.yaml",
+            "reports/copy.rivet.yaml",
+            "generated/v1/all.rivet.yaml",
+        ] {
+            write(temp_dir.path().join(file), suite).await.unwrap();
+        }
+        write(
+            temp_dir.path().join(IGNORE_FILE),
+            "# not suites\ngenerated/\n*.wip.rivet.yaml\ndrafts\n",
+        )
+        .await
+        .unwrap();
+
+        let result = load_test_suite(temp_dir.path(), false).await.unwrap();
+        let names: Vec<_> = result.iter().map(|suite| suite.name.as_str()).collect();
+        assert_eq!(names, ["api/users.rivet.yaml"]);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_step_names_are_renamed_or_rejected() {
+        let temp_file = NamedTempFile::new().unwrap();
+        write(
+            temp_file.path(),
+            r#"name: Dupes
+setup:
+  - name: Login
+    request: { method: POST, url: "http://localhost/login" }
+tests:
+  - name: Login
+    request: { method: GET, url: "http://localhost/a" }
+  - name: Login (2)
+    request: { method: GET, url: "http://localhost/b" }
+teardown:
+  - name: Login
+    request: { method: GET, url: "http://localhost/c" }
+"#,
+        )
+        .await
+        .unwrap();
+
+        let config = load_single_file(temp_file.path(), false).await.unwrap();
+        let names: Vec<_> = config.tests.iter().map(|step| step.name.as_str()).collect();
+        assert_eq!(names, ["Login (3)", "Login (2)"]);
+        assert_eq!(config.teardown.unwrap()[0].name, "Login (4)");
+
+        let error = load_single_file(temp_file.path(), true)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Duplicate step name 'Login'"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_nested_includes_lend_vars_setup_and_templates() {
+        let temp_dir = TempDir::new().unwrap();
+        let common = temp_dir.path().join("common");
+        std::fs::create_dir(&common).unwrap();
+        write(
+            common.join("base.yaml"),
+            r#"vars:
+  baseUrl: http://localhost
+  username: admin
+setup:
+  - name: Health
+    request: { method: GET, url: "{{baseUrl}}/health" }
+"#,
+        )
+        .await
+        .unwrap();
+        write(
+            common.join("auth.yaml"),
+            r#"include: [base.yaml]
+vars:
+  username: service
+setup:
+  - name: Login
+    request: { method: POST, url: "{{baseUrl}}/login", body: "{{username}}" }
+templates:
+  get_user:
+    request: { method: GET, url: "{{baseUrl}}/users/{{id}}" }
+    vars: { id: "1" }
+    expect: { status: 200 }
+"#,
+        )
+        .await
+        .unwrap();
+        let suite = temp_dir.path().join("users.rivet.yaml");
+        write(
+            &suite,
+            r#"name: Users
+include: [common/auth.yaml]
+vars:
+  username: bob
+setup:
+  - name: Seed
+    request: { method: POST, url: "{{baseUrl}}/seed" }
+tests:
+  - use: get_user
+  - name: Missing user
+    use: get_user
+    vars: { id: "404" }
+    expect: { status: 404 }
+"#,
+        )
+        .await
+        .unwrap();
+
+        let config = load_single_file(&suite, false).await.unwrap();
+        let vars = config.vars.unwrap();
+        assert_eq!(vars["username"], "bob");
+        assert_eq!(vars["baseUrl"], "http://localhost");
+        let setup: Vec<_> = config.setup.unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(setup, ["Health", "Login", "Seed"]);
+
+        let (first, missing) = (&config.tests[0], &config.tests[1]);
+        assert_eq!(first.name, "get_user");
+        assert_eq!(first.vars.as_ref().unwrap()["id"], "1");
+        assert_eq!(missing.name, "Missing user");
+        assert_eq!(missing.vars.as_ref().unwrap()["id"], "404");
+        assert_eq!(missing.request.url, "{{baseUrl}}/users/{{id}}");
+        assert!(matches!(
+            missing.expect.as_ref().unwrap().status,
+            Some(crate::config::StatusExpectation::Number(404))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_included_schema_paths_resolve_against_the_included_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let shared = temp_dir.path().join("shared");
+        std::fs::create_dir_all(shared.join("schemas")).unwrap();
+        write(
+            shared.join("checks.yaml"),
+            r#"setup:
+  - name: Health
+    request: { method: GET, url: /health }
+    expect: { schema: schemas/health.json }
+templates:
+  get_user:
+    request: { method: GET, url: /users/1 }
+    expect: { schema: schemas/user.json }
+  inline:
+    request: { method: GET, url: /a }
+    expect: { schema: '{"type": "object"}' }
+"#,
+        )
+        .await
+        .unwrap();
+        let suite = temp_dir.path().join("users.rivet.yaml");
+        write(
+            &suite,
+            r#"name: Users
+include: [shared/checks.yaml]
+tests:
+  - use: get_user
+  - use: get_user
+    name: Own schema
+    expect: { schema: schemas/mine.json }
+  - use: inline
+"#,
+        )
+        .await
+        .unwrap();
+
+        let config = load_single_file(&suite, false).await.unwrap();
+        let schema = |step: &TestStep| step.expect.as_ref().unwrap().schema.clone().unwrap();
+        let schemas = shared.canonicalize().unwrap().join("schemas");
+        let setup = config.setup.as_ref().unwrap();
+        assert_eq!(
+            schema(&setup[0]),
+            schemas.join("health.json").to_string_lossy()
+        );
+        assert_eq!(
+            schema(&config.tests[0]),
+            schemas.join("user.json").to_string_lossy()
+        );
+        // The suite's own steps keep resolving against the suite
+        assert_eq!(schema(&config.tests[1]), "schemas/mine.json");
+        assert_eq!(schema(&config.tests[2]), r#"{"type": "object"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_circular_include_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        write(temp_dir.path().join("a.yaml"), "include: [b.yaml]\n")
+            .await
+            .unwrap();
+        write(temp_dir.path().join("b.yaml"), "include: [a.yaml]\n")
+            .await
+            .unwrap();
+        let suite = temp_dir.path().join("suite.rivet.yaml");
+        write(&suite, "name: Loop\ninclude: [a.yaml]\ntests: []\n")
+            .await
+            .unwrap();
+
+        let error = load_single_file(&suite, false).await.unwrap_err();
+        let message = format!("{:#}", error);
+        assert!(message.contains("Circular include: "), "{}", message);
+        assert!(
+            message.contains("a.yaml -> ") && message.ends_with("a.yaml"),
+            "{}",
+            message
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_include_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let suite = temp_dir.path().join("suite.rivet.yaml");
+        write(&suite, "name: S\ninclude: [nope.yaml]\ntests: []\n")
+            .await
+            .unwrap();
+
+        let message = format!("{:#}", load_single_file(&suite, false).await.unwrap_err());
+        assert!(
+            message.contains("Failed to read included file nope.yaml"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_resolve_suite_path() {
+        let dir = Path::new("suites/api");
+        assert_eq!(
+            resolve_suite_path(dir, "data/users.csv"),
+            Path::new("suites/api/data/users.csv")
+        );
+        assert_eq!(
+            resolve_suite_path(dir, "/srv/data/users.csv"),
+            Path::new("/srv/data/users.csv")
+        );
+        assert_eq!(
+            describe_suite_path("/srv/data/users.csv", Path::new("/srv/data/users.csv")),
+            "/srv/data/users.csv"
+        );
     }
 
     #[tokio::test]
     async fn test_load_test_suite_nonexistent_path() {
-        let result = load_test_suite(Path::new("/nonexistent/path")).await;
+        let result = load_test_suite(Path::new("/nonexistent/path"), false).await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -264,11 +813,11 @@ mod tests {
             write(&file_path, &yaml_content).await.unwrap();
         }
 
-        let result = load_directory(temp_dir.path()).await.unwrap();
+        let result = load_directory(temp_dir.path(), false).await.unwrap();
 
         // Should only include the 2 valid rivet files
         assert_eq!(result.len(), 2);
-        assert!(result.iter().any(|(name, _)| name == "test.rivet.yaml"));
-        assert!(result.iter().any(|(name, _)| name == "test.rivet.yml"));
+        assert!(result.iter().any(|suite| suite.name == "test.rivet.yaml"));
+        assert!(result.iter().any(|suite| suite.name == "test.rivet.yml"));
     }
 }
@@ -0,0 +1,416 @@
+//! Where a run's progress goes: the console, JSON events on stdout, or an
+//! embedder's own [`Reporter`].
+
+use crate::commands::exit::ExitReason;
+use crate::run::{RunOptions, RunReport};
+use crate::runner::assertions::format_diff;
+use crate::runner::events::{duration_ms, RunEvent, TestStatus};
+use crate::runner::executor::TestResult;
+use crate::runner::order::TestOrder;
+use crate::runner::test_runner::TestSuiteResult;
+use crate::style::Colorize;
+use std::path::PathBuf;
+
+/// Receives a run's progress as it happens. Every method does nothing by
+/// default, so an implementation handles just the events it needs. Suites
+/// running in parallel report from several tasks at once.
+pub trait Reporter: Send + Sync {
+    /// Before any suite is loaded.
+    fn run_started(&self, _options: &RunOptions) {}
+
+    fn suite_started(&self, _suite: &str) {}
+
+    /// Nothing in the suite ran: no test matched the filter, or the run was
+    /// cancelled first.
+    fn suite_skipped(&self, _suite: &str, _reason: &str) {}
+
+    /// Something worth knowing about a running suite, e.g. an `env` that
+    /// `--env` overrides.
+    fn suite_note(&self, _message: &str) {}
+
+    /// A suite step failing in a way that skips others, e.g. its setup.
+    fn suite_warning(&self, _message: &str) {}
+
+    /// A dataset filter or sample kept `selected` of `total` rows.
+    fn rows_selected(&self, _selected: usize, _total: usize, _selection: &str) {}
+
+    /// A test, setup or teardown step passed, failed or was skipped.
+    fn test_finished(&self, _result: &TestResult) {}
+
+    fn suite_finished(&self, _suite: &TestSuiteResult) {}
+
+    /// A problem that doesn't stop the run, e.g. a report that couldn't be
+    /// written.
+    fn warning(&self, _message: &str) {}
+
+    fn reports_written(&self, _paths: &[PathBuf]) {}
+
+    fn run_finished(&self, _report: &RunReport) {}
+}
+
+/// Reports nothing; the run's results are all in the [`RunReport`].
+pub struct SilentReporter;
+
+impl Reporter for SilentReporter {}
+
+/// What `rivet run` prints: colors and symbols, or plain text in CI mode.
+pub struct ConsoleReporter {
+    ci: bool,
+}
+
+impl ConsoleReporter {
+    pub fn new(ci: bool) -> Self {
+        Self { ci }
+    }
+}
+
+impl Reporter for ConsoleReporter {
+    fn run_started(&self, options: &RunOptions) {
+        println!("Running tests from: {}", options.target.display());
+        println!(
+            "Environment: {}",
+            options.env.as_deref().unwrap_or("per suite")
+        );
+        match options.parallel_requests {
+            Some(requests) => println!(
+                "Parallel suites: {}, requests: {}",
+                options.parallel_suites, requests
+            ),
+            None => println!("Parallel suites: {}", options.parallel_suites),
+        }
+        if let Some(path) = &options.env_file {
+            println!("Env file: {}", path.display());
+        }
+        if let Some(path) = &options.log_file {
+            println!("Log file: {}", path.display());
+        }
+        if let Some(pattern) = &options.grep {
+            if options.grep_invert {
+                println!("Filter pattern: {} (inverted)", pattern);
+            } else {
+                println!("Filter pattern: {}", pattern);
+            }
+        }
+        if let Some(base_url) = &options.base_url {
+            println!("Base URL: {}", base_url);
+        }
+        if let TestOrder::Shuffle { seed } = options.order {
+            println!(
+                "Shuffle seed: {} (repeat with --shuffle --seed {})",
+                seed, seed
+            );
+        }
+    }
+
+    fn suite_started(&self, suite: &str) {
+        if self.ci {
+            println!("RUN {}", suite);
+        } else {
+            println!("\n{} {}", "RUN".cyan().bold(), suite.bright_white());
+        }
+    }
+
+    fn suite_skipped(&self, suite: &str, reason: &str) {
+        if self.ci {
+            println!("SKIP {} ({})", suite, reason);
+        } else {
+            println!(
+                "\n{} {} {}",
+                "SKIP".yellow().bold(),
+                suite.bright_white(),
+                format!("({})", reason).dimmed()
+            );
+        }
+    }
+
+    fn suite_note(&self, message: &str) {
+        if self.ci {
+            println!("  NOTE {}", message);
+        } else {
+            println!("  {} {}", "note:".dimmed(), message);
+        }
+    }
+
+    fn suite_warning(&self, message: &str) {
+        if self.ci {
+            println!("  WARN {}", message);
+        } else {
+            println!("  {} {}", "⚠".yellow(), message);
+        }
+    }
+
+    fn rows_selected(&self, selected: usize, total: usize, selection: &str) {
+        println!("  using {} of {} rows ({})", selected, total, selection);
+    }
+
+    fn test_finished(&self, result: &TestResult) {
+        if result.skipped {
+            let reason = result.error.as_deref().unwrap_or("skipped");
+            if self.ci {
+                println!("  SKIP {} ({})", result.name, reason);
+            } else {
+                println!(
+                    "  {} {} {}",
+                    "-".yellow(),
+                    result.name.dimmed(),
+                    format!("({})", reason).dimmed()
+                );
+            }
+        } else if self.ci {
+            // CI mode: plain text, no colors or fancy symbols
+            if result.passed {
+                println!(
+                    "  PASS {} ({:?}{})",
+                    result.name,
+                    result.duration,
+                    polled(result)
+                );
+            } else {
+                println!("  FAIL {} ({:?})", result.name, result.duration);
+                if let Some(error) = &result.error {
+                    let mut lines = error.lines();
+                    println!("    Error: {}", lines.next().unwrap_or_default());
+                    for line in lines {
+                        println!("    {}", line);
+                    }
+                }
+                if let Some(diff) = &result.error_diff {
+                    print!("{}", format_diff(diff, false, "    "));
+                }
+            }
+            for warning in &result.warnings {
+                println!("    WARN {}", warning);
+            }
+        } else {
+            // Interactive mode: colors and symbols
+            if result.passed {
+                println!(
+                    "  {} {} ({:?}{})",
+                    "✔".green(),
+                    result.name,
+                    result.duration,
+                    polled(result)
+                );
+            } else {
+                println!("  {} {} ({:?})", "✖".red(), result.name, result.duration);
+
+                if let Some(error) = &result.error {
+                    // Aggregated failures are a numbered list; keep it indented under the test
+                    let mut lines = error.lines();
+                    println!(
+                        "    {}: {}",
+                        "Error".red().bold(),
+                        lines.next().unwrap_or_default()
+                    );
+                    for line in lines {
+                        println!("    {}", line);
+                    }
+                }
+                if let Some(diff) = &result.error_diff {
+                    print!("{}", format_diff(diff, true, "    "));
+                }
+            }
+            for warning in &result.warnings {
+                println!("    {} {}", "⚠".yellow(), warning);
+            }
+        }
+    }
+
+    fn suite_finished(&self, suite: &TestSuiteResult) {
+        let (passed, failed, duration) = (suite.passed, suite.failed, suite.duration);
+        if failed == 0 {
+            if self.ci {
+                println!("  PASS {} tests in {:?}", passed, duration);
+            } else {
+                println!(
+                    "  {} {} tests passed in {:?}",
+                    "✔".green().bold(),
+                    passed,
+                    duration
+                );
+            }
+        } else if self.ci {
+            println!(
+                "  FAIL {} passed, {} failed in {:?}",
+                passed, failed, duration
+            );
+        } else {
+            println!(
+                "  {} {} passed, {} failed in {:?}",
+                "✖".red().bold(),
+                passed,
+                failed,
+                duration
+            );
+        }
+    }
+
+    fn warning(&self, message: &str) {
+        eprintln!("{} {}", "⚠".yellow(), message);
+    }
+
+    fn reports_written(&self, paths: &[PathBuf]) {
+        println!();
+        println!("Reports generated:");
+        for path in paths {
+            println!("  📊 {}", path.display());
+        }
+    }
+
+    fn run_finished(&self, report: &RunReport) {
+        let skipped_note = if report.skipped > 0 {
+            format!(", {} skipped", report.skipped)
+        } else {
+            String::new()
+        };
+
+        println!();
+        if report.interrupted {
+            println!(
+                "{} Interrupted: {} passed, {} failed{} in {:?}",
+                "⚠".yellow().bold(),
+                report.passed,
+                report.failed,
+                skipped_note,
+                report.duration
+            );
+            return;
+        }
+        if report.failed == 0 {
+            if self.ci {
+                println!("PASS {} tests in {:?}", report.total, report.duration);
+            } else {
+                println!(
+                    "{} {} tests passed in {:?}",
+                    "✔".green().bold(),
+                    report.total,
+                    report.duration
+                );
+
+                if report.total > 0 {
+                    // Add some celebration for successful runs (only in interactive mode)
+                    println!("      .       .  *     .     *");
+                    println!("   *    .   *   .  *      .        *");
+                }
+            }
+            return;
+        }
+
+        if self.ci {
+            println!(
+                "FAIL {} passed, {} failed{} in {:?}",
+                report.passed, report.failed, skipped_note, report.duration
+            );
+        } else {
+            println!(
+                "{} {} passed, {} failed{} in {:?}",
+                "✖".red().bold(),
+                report.passed,
+                report.failed,
+                skipped_note,
+                report.duration
+            );
+        }
+        println!("  Failures: {}", report.failure_breakdown());
+        if report.exit_reason() == ExitReason::InfrastructureError {
+            println!(
+                "{} No request got a response; is the server reachable?",
+                "⚠".yellow()
+            );
+        }
+    }
+}
+
+/// `rivet run --output json`: one [`RunEvent`] per line on stdout. Notes
+/// and warnings go to stderr, so stdout stays parseable.
+pub struct JsonReporter;
+
+impl Reporter for JsonReporter {
+    fn run_started(&self, options: &RunOptions) {
+        let shuffle_seed = match options.order {
+            TestOrder::Shuffle { seed } => Some(seed),
+            _ => None,
+        };
+        RunEvent::RunStarted {
+            target: &options.target.to_string_lossy(),
+            env: options.env.as_deref(),
+            shuffle_seed,
+        }
+        .emit();
+    }
+
+    fn suite_started(&self, suite: &str) {
+        RunEvent::SuiteStarted { suite }.emit();
+    }
+
+    fn suite_skipped(&self, suite: &str, reason: &str) {
+        RunEvent::SuiteSkipped { suite, reason }.emit();
+    }
+
+    fn suite_note(&self, message: &str) {
+        eprintln!("note: {}", message);
+    }
+
+    fn suite_warning(&self, message: &str) {
+        // The failed steps have their own events
+        eprintln!("warning: {}", message);
+    }
+
+    fn test_finished(&self, result: &TestResult) {
+        RunEvent::TestFinished {
+            name: &result.name,
+            status: if result.skipped {
+                TestStatus::Skipped
+            } else if result.passed {
+                TestStatus::Passed
+            } else {
+                TestStatus::Failed
+            },
+            duration_ms: duration_ms(result.duration),
+            error: result.error.as_deref(),
+            response_status: result.response_status,
+            warnings: &result.warnings,
+            poll: result.poll,
+        }
+        .emit();
+    }
+
+    fn suite_finished(&self, suite: &TestSuiteResult) {
+        RunEvent::SuiteFinished {
+            suite: &suite.name,
+            passed: suite.passed,
+            failed: suite.failed,
+            duration_ms: duration_ms(suite.duration),
+        }
+        .emit();
+    }
+
+    fn warning(&self, message: &str) {
+        eprintln!("warning: {}", message);
+    }
+
+    fn run_finished(&self, report: &RunReport) {
+        RunEvent::RunFinished {
+            total: report.total,
+            passed: report.passed,
+            failed: report.failed,
+            skipped: report.skipped,
+            duration_ms: duration_ms(report.duration),
+            reports: report
+                .reports
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+        }
+        .emit();
+    }
+}
+
+/// `, polled N times` for a result that ran `expect.poll`.
+fn polled(result: &TestResult) -> String {
+    match result.poll {
+        Some(poll) if poll.attempts == 1 => ", polled once".to_string(),
+        Some(poll) => format!(", polled {} times", poll.attempts),
+        None => String::new(),
+    }
+}
@@ -1,26 +1,78 @@
-use crate::config::{RivetConfig, TestStep};
+use crate::config::{AuthConfig, AuthScheme, RivetConfig, TestStep, TlsConfig};
 use crate::runner::{
-    data::load_csv_data,
+    auth::{authorize_steps, TokenCache, AUTHORIZATION_VAR},
+    base_url::{retarget_suite, BASE_URL_VAR},
+    connections::ConnectionStats,
+    data::{load_data, DataRow, DataSelection, SplitMix64},
     executor::{RequestExecutor, TestResult},
-    parser::load_test_suite,
+    failure::FailureKind,
+    log::RequestLog,
+    order::TestOrder,
+    parser::{describe_suite_path, load_test_suite, SuiteFile},
+    reporter::{ConsoleReporter, Reporter},
     variables::VariableContext,
 };
+use crate::utils::ClientOptions;
 use anyhow::{Context, Result};
-use owo_colors::OwoColorize;
+use futures::stream::{FuturesUnordered, StreamExt};
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
 
+#[derive(Clone)]
 pub struct TestRunner {
     executor: RequestExecutor,
-    parallel_workers: usize,
-    bail_on_failure: bool,
-    filter_pattern: Option<String>,
-    ci_mode: bool,
+    timeout: Duration,
+    client_options: ClientOptions,
+    parallel_suites: usize,
+    parallel_requests: usize,
+    /// Caps in-flight requests across every suite running at once
+    request_slots: Option<Arc<Semaphore>>,
+    /// Failures after which everything still running is cancelled (`--bail` is 1)
+    max_failures: Option<usize>,
+    failures: Arc<AtomicUsize>,
+    cancel: CancellationToken,
+    /// Set when the run was cancelled by [`TestRunner::interrupt`]
+    interrupted: Arc<AtomicBool>,
+    filter: Option<TestFilter>,
+    base_url: Option<String>,
+    /// `--header`s, set on every HTTP step over its own headers
+    headers: HashMap<String, String>,
+    /// `--identify`; a suite's `identify_requests` wins
+    identify: bool,
+    data_selection: DataSelection,
+    secrets: Vec<String>,
+    /// Where progress goes; the console unless [`TestRunner::with_reporter`]
+    reporter: Arc<dyn Reporter>,
+    request_log: RequestLog,
+    /// `--max-capture-bytes`, which wins over a suite's `max_capture_bytes`
+    max_capture_bytes: Option<usize>,
+    /// Reject suites that repeat a step name (see [`crate::runner::parser::load_single_file`])
+    strict: bool,
+    /// Executors for suites with their own TLS settings, one per distinct
+    /// settings, so those suites share connections as the others do
+    suite_executors: Arc<Mutex<HashMap<ClientOptions, RequestExecutor>>>,
+    /// Tokens for suites' `auth` blocks, shared by the whole run
+    tokens: TokenCache,
+    /// The running suite's OAuth 2.0 `auth`, whose token is checked for
+    /// expiry before each step
+    oauth2: Option<Arc<AuthConfig>>,
+    order: TestOrder,
 }
 
 pub struct TestSuiteResult {
     #[allow(dead_code)]
     pub name: String,
+    /// `{{run_id}}` of the run this suite was part of
+    pub run_id: String,
+    /// The environment the suite ran in: `--env`, else its own `env`
+    pub env: Option<String>,
     #[allow(dead_code)]
     pub results: Vec<TestResult>,
     pub duration: Duration,
@@ -31,72 +83,199 @@ pub struct TestSuiteResult {
 impl TestRunner {
     pub fn new(
         timeout: Duration,
-        parallel_workers: usize,
+        parallel_suites: usize,
         bail_on_failure: bool,
         filter_pattern: Option<String>,
         ci_mode: bool,
+        client_options: &ClientOptions,
     ) -> Result<Self> {
-        let executor = RequestExecutor::new(timeout)?;
+        let executor = RequestExecutor::new(timeout, client_options)?;
+        let filter = filter_pattern
+            .map(|pattern| TestFilter::new(&pattern))
+            .transpose()?;
 
         Ok(Self {
             executor,
-            parallel_workers,
-            bail_on_failure,
-            filter_pattern,
-            ci_mode,
+            timeout,
+            client_options: client_options.clone(),
+            parallel_suites,
+            parallel_requests: 1,
+            request_slots: None,
+            max_failures: bail_on_failure.then_some(1),
+            failures: Arc::new(AtomicUsize::new(0)),
+            cancel: CancellationToken::new(),
+            interrupted: Arc::new(AtomicBool::new(false)),
+            filter,
+            base_url: None,
+            headers: HashMap::new(),
+            identify: false,
+            data_selection: DataSelection::default(),
+            secrets: Vec::new(),
+            reporter: Arc::new(ConsoleReporter::new(ci_mode)),
+            request_log: RequestLog::default(),
+            max_capture_bytes: None,
+            strict: false,
+            suite_executors: Arc::default(),
+            tokens: TokenCache::default(),
+            oauth2: None,
+            order: TestOrder::default(),
         })
     }
 
+    /// Dataset filter/sample that override each suite's own `dataset` settings.
+    pub fn with_data_selection(mut self, data_selection: DataSelection) -> Self {
+        self.data_selection = data_selection;
+        self
+    }
+
+    /// Steps (or dataset rows) run at once within a suite, which is also the
+    /// limit on requests in flight across all suites. Without it, each suite
+    /// runs its steps one at a time, so the suite parallelism bounds requests.
+    pub fn with_parallel_requests(mut self, parallel_requests: usize) -> Self {
+        self.parallel_requests = parallel_requests;
+        self.request_slots = Some(Arc::new(Semaphore::new(parallel_requests.max(1))));
+        self
+    }
+
+    /// Cancel the run after `max_failures` failed tests; overrides `bail_on_failure`.
+    pub fn with_max_failures(mut self, max_failures: Option<usize>) -> Self {
+        if max_failures.is_some() {
+            self.max_failures = max_failures;
+        }
+        self
+    }
+
+    /// Cancel the run from outside, e.g. on Ctrl-C: tests not yet finished
+    /// are skipped and the run returns what completed.
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+        self.cancel.cancel();
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+
+    /// Report progress to `reporter` instead of the console.
+    pub fn with_reporter(mut self, reporter: Arc<dyn Reporter>) -> Self {
+        self.reporter = reporter;
+        self
+    }
+
+    /// Run only the tests that do *not* match the `--grep` pattern.
+    pub fn with_grep_invert(mut self, invert: bool) -> Self {
+        if let Some(filter) = &mut self.filter {
+            filter.invert = invert;
+        }
+        self
+    }
+
+    /// Variables to mask in every suite, on top of each suite's `secrets`.
+    pub fn with_secrets(mut self, secrets: Vec<String>) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// Log every step's request and response (`-v`, `--log-file`).
+    pub fn with_request_log(mut self, request_log: RequestLog) -> Self {
+        self.executor = self.executor.with_log(request_log.clone());
+        self.request_log = request_log;
+        self
+    }
+
+    /// Keep at most this much of each response body, whatever suites set.
+    pub fn with_max_capture_bytes(mut self, max_capture_bytes: Option<usize>) -> Self {
+        self.max_capture_bytes = max_capture_bytes;
+        self
+    }
+
+    /// Requests sent so far and the connections they went over.
+    pub fn connection_stats(&self) -> &ConnectionStats {
+        self.executor.connection_stats()
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Run suites, and tests within them, in `order` rather than as declared.
+    pub fn with_order(mut self, order: TestOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Retarget every suite at `base_url` (see [`retarget_suite`]).
+    pub fn with_base_url(mut self, base_url: Option<String>) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Send `headers` with every HTTP step, over the steps' and suites' own.
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Send `X-Rivet-Test` with every HTTP step of suites that don't set
+    /// `identify_requests` themselves.
+    pub fn with_identify(mut self, identify: bool) -> Self {
+        self.identify = identify;
+        self
+    }
+
     pub async fn run_tests(
         &self,
         target: &Path,
         env: Option<&str>,
     ) -> Result<Vec<TestSuiteResult>> {
-        let test_suites = load_test_suite(target).await?;
+        let mut test_suites = load_test_suite(target, self.strict).await?;
+        for warning in test_suites.iter().flat_map(|suite| &suite.warnings) {
+            self.reporter.warning(warning);
+        }
+        self.order.apply(&mut test_suites);
+        let mut ids = IdGenerator::new();
+        let run = RunIds {
+            run_id: ids.next_id(),
+            ids: Mutex::new(ids),
+        };
 
-        if test_suites.len() <= 1 || self.parallel_workers <= 1 {
+        if test_suites.len() <= 1 || self.parallel_suites <= 1 {
             // Sequential execution for single suite or when parallel is disabled
-            self.run_suites_sequential(test_suites, env).await
+            self.run_suites_sequential(test_suites, env, &run).await
         } else {
             // Parallel execution for multiple suites
-            self.run_suites_parallel(test_suites, env).await
+            self.run_suites_parallel(test_suites, env, &run).await
         }
     }
 
     async fn run_suites_sequential(
         &self,
-        test_suites: Vec<(String, crate::config::RivetConfig)>,
+        test_suites: Vec<SuiteFile>,
         env: Option<&str>,
+        run: &RunIds,
     ) -> Result<Vec<TestSuiteResult>> {
         let mut all_results = Vec::new();
 
-        for (suite_name, config) in test_suites {
-            if self.ci_mode {
-                println!("RUN {}", suite_name);
-            } else {
-                println!("\n{} {}", "RUN".cyan().bold(), suite_name.bright_white());
+        for suite in test_suites {
+            if !self.has_matching_tests(&suite.config) {
+                self.reporter
+                    .suite_skipped(&suite.name, "no tests match --grep");
+                continue;
+            }
+            if self.cancel.is_cancelled() {
+                all_results.push(self.cancelled_suite(suite.name, &suite.config, env, run));
+                continue;
             }
 
-            let suite_start = Instant::now();
-            let results = self.run_single_suite(&config, env).await?;
-            let duration = suite_start.elapsed();
-
-            let passed = results.iter().filter(|r| r.passed).count();
-            let failed = results.len() - passed;
-
-            self.print_suite_summary(&suite_name, passed, failed, duration);
-
-            all_results.push(TestSuiteResult {
-                name: suite_name,
-                results,
-                duration,
-                passed,
-                failed,
-            });
+            self.reporter.suite_started(&suite.name);
 
-            if self.bail_on_failure && failed > 0 {
-                break;
-            }
+            let suite_start = Instant::now();
+            let results = self.run_single_suite(&suite, env, run).await?;
+            let suite_env = suite_env(&suite.config, env).0;
+            let suite_result =
+                self.finish_suite(suite.name, suite_env, results, suite_start.elapsed(), run);
+            all_results.push(suite_result);
         }
 
         Ok(all_results)
@@ -104,272 +283,667 @@ impl TestRunner {
 
     async fn run_suites_parallel(
         &self,
-        test_suites: Vec<(String, crate::config::RivetConfig)>,
+        test_suites: Vec<SuiteFile>,
         env: Option<&str>,
+        run: &RunIds,
     ) -> Result<Vec<TestSuiteResult>> {
-        use futures::stream::{FuturesUnordered, StreamExt};
-        use std::sync::Arc;
-
-        let env = Arc::new(env);
         let mut all_results = Vec::new();
 
         // Process suites in chunks to limit concurrency
-        for chunk in test_suites.chunks(self.parallel_workers) {
+        for chunk in test_suites.chunks(self.parallel_suites) {
             let mut futures = FuturesUnordered::new();
 
-            for (suite_name, config) in chunk {
-                let suite_name = suite_name.clone();
-                let config = config.clone();
-                let env = Arc::clone(&env);
-                let executor = self.executor.clone();
-                let ci_mode = self.ci_mode;
-                let bail_on_failure = self.bail_on_failure;
-                let filter_pattern = self.filter_pattern.clone();
+            for suite in chunk {
+                if !self.has_matching_tests(&suite.config) {
+                    self.reporter
+                        .suite_skipped(&suite.name, "no tests match --grep");
+                    continue;
+                }
+                if self.cancel.is_cancelled() {
+                    all_results.push(self.cancelled_suite(
+                        suite.name.clone(),
+                        &suite.config,
+                        env,
+                        run,
+                    ));
+                    continue;
+                }
 
                 // Announce start
-                if ci_mode {
-                    println!("RUN {}", suite_name);
-                } else {
-                    println!("\n{} {}", "RUN".cyan().bold(), suite_name.bright_white());
-                }
+                self.reporter.suite_started(&suite.name);
 
                 futures.push(async move {
                     let suite_start = Instant::now();
-
-                    // Create a temporary runner for this suite
-                    let temp_runner = TestRunner {
-                        executor,
-                        parallel_workers: 1, // Use sequential within each suite for parallel suite execution
-                        bail_on_failure,
-                        filter_pattern,
-                        ci_mode,
-                    };
-
-                    let results = temp_runner.run_single_suite(&config, env.as_deref()).await;
+                    let results = self.run_single_suite(suite, env, run).await;
                     let duration = suite_start.elapsed();
 
-                    (suite_name, results, duration)
+                    (suite, results, duration)
                 });
             }
 
             // Collect results from this chunk
-            while let Some((suite_name, results, duration)) = futures.next().await {
-                match results {
-                    Ok(results) => {
-                        let passed = results.iter().filter(|r| r.passed).count();
-                        let failed = results.len() - passed;
-
-                        self.print_suite_summary(&suite_name, passed, failed, duration);
-
-                        all_results.push(TestSuiteResult {
-                            name: suite_name,
-                            results,
-                            duration,
-                            passed,
-                            failed,
-                        });
-
-                        if self.bail_on_failure && failed > 0 {
-                            return Ok(all_results);
-                        }
-                    }
-                    Err(e) => {
-                        return Err(e);
-                    }
-                }
+            while let Some((suite, results, duration)) = futures.next().await {
+                let results = results?;
+                let suite_env = suite_env(&suite.config, env).0;
+                all_results.push(self.finish_suite(
+                    suite.name.clone(),
+                    suite_env,
+                    results,
+                    duration,
+                    run,
+                ));
             }
         }
 
         Ok(all_results)
     }
 
-    fn print_suite_summary(
+    fn finish_suite(
         &self,
-        _suite_name: &str,
-        passed: usize,
-        failed: usize,
+        name: String,
+        env: Option<String>,
+        results: Vec<TestResult>,
         duration: Duration,
-    ) {
-        if failed == 0 {
-            if self.ci_mode {
-                println!("  PASS {} tests in {:?}", passed, duration);
-            } else {
-                println!(
-                    "  {} {} tests passed in {:?}",
-                    "✔".green().bold(),
-                    passed,
-                    duration
-                );
+        run: &RunIds,
+    ) -> TestSuiteResult {
+        let passed = results.iter().filter(|r| r.passed).count();
+        let failed = results.iter().filter(|r| !r.passed && !r.skipped).count();
+
+        let suite = TestSuiteResult {
+            name,
+            run_id: run.run_id.clone(),
+            env,
+            results,
+            duration,
+            passed,
+            failed,
+        };
+        self.reporter.suite_finished(&suite);
+        suite
+    }
+
+    /// A suite that never started because the run was already cancelled.
+    fn cancelled_suite(
+        &self,
+        name: String,
+        config: &RivetConfig,
+        env: Option<&str>,
+        run: &RunIds,
+    ) -> TestSuiteResult {
+        self.reporter.suite_skipped(&name, &self.cancel_reason());
+        let results = config
+            .tests
+            .iter()
+            .filter(|step| self.should_run_test(&config.name, &step.name))
+            .map(|step| self.cancelled_result(&step.name))
+            .collect();
+
+        TestSuiteResult {
+            name,
+            run_id: run.run_id.clone(),
+            env: suite_env(config, env).0,
+            results,
+            duration: Duration::ZERO,
+            passed: 0,
+            failed: 0,
+        }
+    }
+
+    fn cancel_reason(&self) -> String {
+        if self.is_interrupted() {
+            return "interrupted".to_string();
+        }
+        match self.max_failures {
+            Some(1) | None => "cancelled due to --bail".to_string(),
+            Some(max) => format!("cancelled after {} failures (--max-failures)", max),
+        }
+    }
+
+    fn cancelled_result(&self, name: &str) -> TestResult {
+        TestResult {
+            name: name.to_string(),
+            passed: false,
+            skipped: true,
+            duration: Duration::ZERO,
+            error: Some(self.cancel_reason()),
+            error_diff: None,
+            response_status: None,
+            response_body: None,
+            response_size: None,
+            final_url: None,
+            request_method: None,
+            request_url: None,
+            request_body: None,
+            response_headers: None,
+            http_version: None,
+            bytes_sent: 0,
+            bytes_received: 0,
+            failure_kind: None,
+            captures: HashMap::new(),
+            poll: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Count a failure, cancelling the run once `max_failures` is reached.
+    fn record_result(&self, result: &TestResult) {
+        if result.passed || result.skipped {
+            return;
+        }
+        if let Some(max) = self.max_failures {
+            if self.failures.fetch_add(1, Ordering::SeqCst) + 1 >= max {
+                self.cancel.cancel();
             }
-        } else if self.ci_mode {
-            println!(
-                "  FAIL {} passed, {} failed in {:?}",
-                passed, failed, duration
-            );
-        } else {
-            println!(
-                "  {} {} passed, {} failed in {:?}",
-                if failed > 0 {
-                    "✖".red().bold().to_string()
-                } else {
-                    "✔".green().bold().to_string()
-                },
-                passed,
-                failed,
-                duration
-            );
         }
     }
 
     async fn run_single_suite(
         &self,
-        config: &RivetConfig,
+        suite: &SuiteFile,
         env: Option<&str>,
+        run: &RunIds,
     ) -> Result<Vec<TestResult>> {
-        // Create variable context
-        let mut context = VariableContext::new()
-            .with_env_vars()
-            .with_config_vars(config.vars.as_ref());
+        let mut prepared = match &self.base_url {
+            Some(base_url) => Some(retarget_suite(&suite.config, base_url)?),
+            None => None,
+        };
+        if !self.headers.is_empty() {
+            prepared
+                .get_or_insert_with(|| suite.config.clone())
+                .override_headers(&self.headers);
+        }
+        let config = prepared.as_ref().unwrap_or(&suite.config);
 
+        // The environment is set first so `vars` can build on `{{RIVET_ENV}}`
+        let mut context = VariableContext::new().with_env_vars();
+        let (env, overridden) = suite_env(config, env);
+        if let Some(declared) = overridden {
+            self.reporter.suite_note(&format!(
+                "--env {} overrides env: {} of this suite",
+                env.as_deref().unwrap_or_default(),
+                declared
+            ));
+        }
         if let Some(env_name) = env {
-            context.set("RIVET_ENV".to_string(), env_name.to_string());
+            context.set(RIVET_ENV_VAR.to_string(), env_name);
         }
+        let mut context = context
+            .with_config_vars(config.vars.as_ref())
+            .with_context(|| format!("Invalid vars in suite '{}'", config.name))?
+            .with_secrets(
+                self.secrets
+                    .iter()
+                    .chain(config.secrets.iter().flatten())
+                    .cloned(),
+            )
+            .with_strict(config.strict_vars.unwrap_or(false));
+        context.set_default(RUN_ID_VAR, &run.run_id);
+        context.set_default(SUITE_ID_VAR, &run.next_suite_id());
 
-        let mut all_results = Vec::new();
+        // Suite-level TLS settings need their own client; CLI flags still take precedence
+        let mut executor = self.executor.clone();
+        let suite_insecure = config.insecure.unwrap_or(false);
+        if config.tls.is_some() || suite_insecure {
+            let suite_tls = config
+                .tls
+                .as_ref()
+                .map(|tls| resolve_tls_paths(tls, &context))
+                .unwrap_or_default();
+            let client_options = ClientOptions {
+                tls: self.client_options.tls.clone().or(suite_tls),
+                insecure: self.client_options.insecure || suite_insecure,
+                ..self.client_options.clone()
+            };
+            if suite_insecure && !self.client_options.insecure {
+                self.reporter.warning(&format!(
+                    "TLS certificate verification is disabled (insecure: true in suite '{}')",
+                    config.name
+                ));
+            }
+            executor = self.suite_executor(client_options)?;
+        }
+        if let Some(max_capture_bytes) = self.max_capture_bytes.or(config.max_capture_bytes) {
+            executor = executor.with_max_capture_bytes(max_capture_bytes);
+        }
 
-        // Run setup steps
-        if let Some(setup_steps) = &config.setup {
-            for step in setup_steps {
-                if self.should_run_test(&step.name) {
-                    let result = self
-                        .executor
-                        .execute_test(
-                            &format!("Setup: {}", step.name),
-                            &step.request,
-                            step.expect.as_ref(),
-                            &context,
-                        )
-                        .await;
-
-                    self.print_test_result(&result);
-                    all_results.push(result);
+        let identify = config.identify_requests.unwrap_or(self.identify);
+        let mut suite_runner = TestRunner {
+            executor: executor
+                .with_base_dir(suite.dir())
+                .with_base_path(config.base_path.as_deref())
+                .with_identify(identify.then_some(config.name.as_str())),
+            ..self.clone()
+        };
+        let Some(auth) = &config.auth else {
+            return suite_runner.run_suite_steps(config, &context).await;
+        };
+
+        // Resolved once per suite; a suite that can't authenticate runs nothing
+        match self
+            .tokens
+            .authorization(auth, &context, &suite_runner.executor)
+            .await
+        {
+            Ok(authorization) => {
+                if let Ok(AuthScheme::OAuth2ClientCredentials(_)) = auth.scheme() {
+                    suite_runner.oauth2 = Some(Arc::new(auth.clone()));
                 }
+                context.set(AUTHORIZATION_VAR.to_string(), authorization);
+                let context = context.with_secrets([AUTHORIZATION_VAR]);
+                suite_runner
+                    .run_suite_steps(&authorize_steps(config), &context)
+                    .await
+            }
+            Err(e) => {
+                let result = TestResult {
+                    error: Some(context.redact(&format!("{:#}", e))),
+                    skipped: false,
+                    failure_kind: Some(FailureKind::SetupFailure),
+                    ..self.cancelled_result("Auth")
+                };
+                suite_runner.record_result(&result);
+                suite_runner.reporter.test_finished(&result);
+                suite_runner
+                    .reporter
+                    .suite_warning("auth failed; skipping tests and teardown");
+                Ok(vec![result])
             }
         }
+    }
+
+    fn suite_executor(&self, client_options: ClientOptions) -> Result<RequestExecutor> {
+        let mut executors = self.suite_executors.lock().unwrap();
+        if let Some(executor) = executors.get(&client_options) {
+            return Ok(executor.clone());
+        }
+        let executor = RequestExecutor::new(self.timeout, &client_options)
+            .context("Invalid tls settings in suite")?
+            .with_log(self.request_log.clone())
+            .with_connection_stats(self.connection_stats().clone());
+        executors.insert(client_options, executor.clone());
+        Ok(executor)
+    }
+
+    async fn run_suite_steps(
+        &self,
+        config: &RivetConfig,
+        context: &VariableContext,
+    ) -> Result<Vec<TestResult>> {
+        let mut all_results = Vec::new();
+
+        // Setup is a prerequisite for the suite, so --grep doesn't apply to it
+        // and the first failure stops it. Nothing was set up to tear down then.
+        let setup_results = self
+            .run_fixture_steps("Setup", config.setup.as_deref(), context, true)
+            .await;
+        let setup_failed = setup_results.iter().any(|r| !r.passed);
+        let mut context = Cow::Borrowed(context);
+        for result in &setup_results {
+            keep_captures(&mut context, result);
+        }
+        all_results.extend(setup_results);
+        if setup_failed {
+            self.reporter
+                .suite_warning("setup failed; skipping tests and teardown");
+            return Ok(all_results);
+        }
+
+        // Teardown runs however the main tests ended, including bail and errors
+        let main_results = self.run_main_tests(config, &context).await;
+        for result in main_results.iter().flatten() {
+            keep_captures(&mut context, result);
+        }
+        let teardown_results = self
+            .run_fixture_steps("Teardown", config.teardown.as_deref(), &context, false)
+            .await;
+        let teardown_failures = teardown_results.iter().filter(|r| !r.passed).count();
+        if teardown_failures > 0 {
+            self.reporter
+                .suite_warning(&format!("teardown: {} step(s) failed", teardown_failures));
+        }
+
+        all_results.extend(main_results?);
+        all_results.extend(teardown_results);
+        Ok(all_results)
+    }
+
+    async fn run_main_tests(
+        &self,
+        config: &RivetConfig,
+        context: &VariableContext,
+    ) -> Result<Vec<TestResult>> {
+        let hooks = StepHooks::from_config(config);
+        let tests: Vec<TestStep> = config
+            .tests
+            .iter()
+            .filter(|step| self.should_run_test(&config.name, &step.name))
+            .cloned()
+            .collect();
 
-        // Run main tests
         if let Some(dataset) = &config.dataset {
             // Data-driven testing
-            let data_file = PathBuf::from(&dataset.file);
-            let data_rows = load_csv_data(&data_file)
+            let data_file = self.executor.suite_path(&dataset.file);
+            let data_rows = load_data(&data_file, dataset.format.as_deref())
                 .await
-                .with_context(|| format!("Failed to load dataset: {}", dataset.file))?;
+                .with_context(|| {
+                    format!(
+                        "Failed to load dataset: {}",
+                        describe_suite_path(&dataset.file, &data_file)
+                    )
+                })?;
+
+            let selection = DataSelection {
+                filter: self
+                    .data_selection
+                    .filter
+                    .clone()
+                    .or_else(|| dataset.filter.clone()),
+                sample: self
+                    .data_selection
+                    .sample
+                    .clone()
+                    .or_else(|| dataset.sample.as_ref().map(|s| s.to_string())),
+                seed: self.data_selection.seed,
+            };
+            let total_rows = data_rows.len();
+            let data_rows = selection
+                .apply(data_rows)
+                .with_context(|| format!("Failed to select rows from dataset: {}", dataset.file))?;
+            if selection.is_active() {
+                self.reporter
+                    .rows_selected(data_rows.len(), total_rows, &selection.describe());
+            }
+
+            // Up to `parallel` rows run at once; steps within a row stay in order
+            let parallel = dataset.parallel.unwrap_or(self.parallel_requests).max(1);
+            let mut pending = data_rows.iter();
+            let mut running = FuturesUnordered::new();
+            let mut row_results = Vec::new();
+
+            // Once the run is cancelled, remaining rows come back as skipped straight away
+            loop {
+                while running.len() < parallel {
+                    let Some((row_number, data_row)) = pending.next() else {
+                        break;
+                    };
+                    running.push(self.run_data_row(&tests, &hooks, context, *row_number, data_row));
+                }
+
+                let Some((row_number, results)) = running.next().await else {
+                    break;
+                };
+                for result in &results {
+                    self.reporter.test_finished(result);
+                }
+                row_results.push((row_number, results));
+            }
+
+            row_results.sort_by_key(|(row_number, _)| *row_number);
+            Ok(row_results
+                .into_iter()
+                .flat_map(|(_, results)| results)
+                .collect())
+        } else {
+            // Regular testing; a test capturing variables for the ones after
+            // it makes them run in order
+            let parallel = if tests.iter().any(|step| step.capture.is_some()) {
+                1
+            } else {
+                self.parallel_requests
+            };
+            Ok(self.run_test_steps(&tests, &hooks, context, parallel).await)
+        }
+    }
 
-            let parallel = dataset.parallel.unwrap_or(self.parallel_workers);
+    /// Run one step once a request slot is free, with its own `vars` over
+    /// `context`. `label` turns the step's resolved name into the reported
+    /// one. Cancellable steps are abandoned, even mid-request, when the run
+    /// is cancelled; cleanup steps always run to completion.
+    async fn execute_step(
+        &self,
+        label: impl Fn(&str) -> String,
+        step: &TestStep,
+        context: &VariableContext,
+        cancellable: bool,
+    ) -> TestResult {
+        let prepared = match step_context(step, context) {
+            Ok(step_context) => self.authorized(step_context).await,
+            Err(e) => Err(e),
+        };
+        let context = match prepared {
+            Ok(context) => context,
+            Err(e) => {
+                let result = TestResult {
+                    error: Some(context.redact(&format!("{:#}", e))),
+                    skipped: false,
+                    failure_kind: Some(FailureKind::SetupFailure),
+                    ..self.cancelled_result(&label(&step.name))
+                };
+                self.record_result(&result);
+                return result;
+            }
+        };
+        let name = &label(&context.substitute_variables(&step.name));
+        let context = context.as_ref();
+        let execute = async {
+            let _slot = match &self.request_slots {
+                Some(slots) => Some(
+                    slots
+                        .acquire()
+                        .await
+                        .expect("request semaphore is never closed"),
+                ),
+                None => None,
+            };
+            self.executor
+                .execute_step(
+                    name,
+                    &step.request,
+                    step.expect.as_ref(),
+                    step.capture.as_ref(),
+                    context,
+                )
+                .await
+        };
 
-            for data_row in data_rows {
-                let row_context = context.clone().with_data_row(&data_row);
-                let test_results = self
-                    .run_test_steps(&config.tests, &row_context, parallel)
-                    .await;
-                all_results.extend(test_results);
+        let result = if cancellable {
+            if self.cancel.is_cancelled() {
+                return self.cancelled_result(name);
+            }
+            tokio::select! {
+                result = execute => result,
+                _ = self.cancel.cancelled() => self.cancelled_result(name),
             }
         } else {
-            // Regular testing
-            let test_results = self
-                .run_test_steps(&config.tests, &context, self.parallel_workers)
+            execute.await
+        };
+        self.record_result(&result);
+        result
+    }
+
+    /// `context` with a new `Authorization` once the suite's OAuth 2.0 token
+    /// is close to expiring, so a long suite doesn't outlive its token.
+    async fn authorized<'a>(
+        &self,
+        context: Cow<'a, VariableContext>,
+    ) -> Result<Cow<'a, VariableContext>> {
+        let Some(auth) = &self.oauth2 else {
+            return Ok(context);
+        };
+        let authorization = self
+            .tokens
+            .authorization(auth, &context, &self.executor)
+            .await?;
+        if context.get(AUTHORIZATION_VAR) == Some(authorization.as_str()) {
+            return Ok(context);
+        }
+        let mut context = context.into_owned();
+        context.set(AUTHORIZATION_VAR.to_string(), authorization);
+        Ok(Cow::Owned(context))
+    }
+
+    /// Run setup or teardown steps, printing each result. Each step sees
+    /// the variables the ones before it captured.
+    async fn run_fixture_steps(
+        &self,
+        label: &str,
+        steps: Option<&[TestStep]>,
+        context: &VariableContext,
+        stop_on_failure: bool,
+    ) -> Vec<TestResult> {
+        let mut results = Vec::new();
+        let mut context = Cow::Borrowed(context);
+        for step in steps.unwrap_or_default() {
+            let result = self
+                .execute_step(|name| format!("{}: {}", label, name), step, &context, false)
                 .await;
-            all_results.extend(test_results);
-        }
-
-        // Run teardown steps
-        if let Some(teardown_steps) = &config.teardown {
-            for step in teardown_steps {
-                if self.should_run_test(&step.name) {
-                    let result = self
-                        .executor
-                        .execute_test(
-                            &format!("Teardown: {}", step.name),
-                            &step.request,
-                            step.expect.as_ref(),
-                            &context,
-                        )
-                        .await;
-
-                    self.print_test_result(&result);
-                    all_results.push(result);
-                }
+
+            self.reporter.test_finished(&result);
+            keep_captures(&mut context, &result);
+            let passed = result.passed;
+            results.push(result);
+
+            if stop_on_failure && !passed {
+                break;
             }
         }
+        results
+    }
 
-        Ok(all_results)
+    /// Run one main test wrapped in the suite's `before_each`/`after_each`
+    /// steps, each seeing the variables the ones before it captured. A
+    /// failed `before_each` skips the test, but `after_each` still runs. Tests run for a dataset row are
+    /// named after it.
+    async fn run_wrapped_test(
+        &self,
+        step: &TestStep,
+        hooks: &StepHooks<'_>,
+        context: &VariableContext,
+        row_number: Option<usize>,
+    ) -> Vec<TestResult> {
+        let label = |name: &str| match row_number {
+            // Rows keep their number in the file, matching dataset error messages
+            Some(row_number) => format!("{} [row {}]", name, row_number),
+            None => name.to_string(),
+        };
+        let name = match step_context(step, context) {
+            Ok(step_context) => label(&step_context.substitute_variables(&step.name)),
+            Err(_) => label(&step.name),
+        };
+        if self.cancel.is_cancelled() {
+            return vec![self.cancelled_result(&name)];
+        }
+
+        let mut results = Vec::new();
+        let mut context = Cow::Borrowed(context);
+
+        for hook in hooks.before_each {
+            let result = self
+                .execute_step(
+                    |hook_name| format!("Before each: {} ({})", hook_name, name),
+                    hook,
+                    &context,
+                    true,
+                )
+                .await;
+            keep_captures(&mut context, &result);
+            results.push(result);
+            if results.iter().any(|r| !r.passed) {
+                break;
+            }
+        }
+
+        if results.iter().all(|r| r.passed) {
+            let result = self.execute_step(label, step, &context, true).await;
+            keep_captures(&mut context, &result);
+            results.push(result);
+        }
+
+        for hook in hooks.after_each {
+            let result = self
+                .execute_step(
+                    |hook_name| format!("After each: {} ({})", hook_name, name),
+                    hook,
+                    &context,
+                    false,
+                )
+                .await;
+            keep_captures(&mut context, &result);
+            results.push(result);
+        }
+
+        results
+    }
+
+    /// Run every step for one dataset row, in order, without printing.
+    /// Variables a step captures are the row's own.
+    async fn run_data_row(
+        &self,
+        steps: &[TestStep],
+        hooks: &StepHooks<'_>,
+        context: &VariableContext,
+        row_number: usize,
+        data_row: &DataRow,
+    ) -> (usize, Vec<TestResult>) {
+        let mut row_context = context.clone().with_data_row(data_row);
+        row_context.set_default(ROW_INDEX_VAR, &row_number.to_string());
+        // --base-url wins over a baseUrl column in the dataset
+        if let Some(base_url) = &self.base_url {
+            row_context.set(BASE_URL_VAR.to_string(), base_url.clone());
+        }
+
+        let mut results = Vec::new();
+        for step in steps {
+            let step_results = self
+                .run_wrapped_test(step, hooks, &row_context, Some(row_number))
+                .await;
+            for (name, value) in step_results.iter().flat_map(|result| &result.captures) {
+                row_context.set(name.clone(), value.clone());
+            }
+            results.extend(step_results);
+        }
+
+        (row_number, results)
     }
 
     async fn run_test_steps(
         &self,
         steps: &[TestStep],
+        hooks: &StepHooks<'_>,
         context: &VariableContext,
         parallel: usize,
     ) -> Vec<TestResult> {
-        let filtered_steps: Vec<_> = steps
-            .iter()
-            .filter(|step| self.should_run_test(&step.name))
-            .collect();
-
         if parallel <= 1 {
             // Sequential execution
             let mut results = Vec::new();
-            for step in filtered_steps {
-                let result = self
-                    .executor
-                    .execute_test(&step.name, &step.request, step.expect.as_ref(), context)
-                    .await;
+            let mut context = Cow::Borrowed(context);
+            for step in steps {
+                let step_results = self.run_wrapped_test(step, hooks, &context, None).await;
 
-                self.print_test_result(&result);
-                let passed = result.passed;
-                results.push(result);
-
-                if self.bail_on_failure && !passed {
-                    break;
+                for result in &step_results {
+                    self.reporter.test_finished(result);
+                    keep_captures(&mut context, result);
                 }
+                results.extend(step_results);
             }
             results
         } else {
             // Parallel execution
-            use futures::stream::{FuturesUnordered, StreamExt};
-            use std::sync::Arc;
-
-            let executor = Arc::new(&self.executor);
-            let context = Arc::new(context);
             let mut results = Vec::new();
 
             // Process in chunks to limit concurrency
-            for chunk in filtered_steps.chunks(parallel) {
-                let mut futures = FuturesUnordered::new();
-
-                for step in chunk {
-                    let executor = Arc::clone(&executor);
-                    let context = Arc::clone(&context);
-                    let step_name = step.name.clone();
-                    let step_request = step.request.clone();
-                    let step_expect = step.expect.clone();
-
-                    futures.push(async move {
-                        executor
-                            .execute_test(&step_name, &step_request, step_expect.as_ref(), &context)
-                            .await
-                    });
-                }
+            for chunk in steps.chunks(parallel) {
+                let mut futures: FuturesUnordered<_> = chunk
+                    .iter()
+                    .map(|step| self.run_wrapped_test(step, hooks, context, None))
+                    .collect();
 
                 // Collect results from this chunk
-                while let Some(result) = futures.next().await {
-                    self.print_test_result(&result);
-                    let passed = result.passed;
-                    results.push(result);
-
-                    if self.bail_on_failure && !passed {
-                        return results;
+                while let Some(step_results) = futures.next().await {
+                    for result in &step_results {
+                        self.reporter.test_finished(result);
                     }
+                    results.extend(step_results);
                 }
             }
 
@@ -377,38 +951,238 @@ impl TestRunner {
         }
     }
 
-    fn should_run_test(&self, test_name: &str) -> bool {
-        if let Some(pattern) = &self.filter_pattern {
-            test_name.contains(pattern)
+    fn should_run_test(&self, suite_name: &str, test_name: &str) -> bool {
+        if let Some(filter) = &self.filter {
+            filter.matches(suite_name, test_name)
         } else {
             true
         }
     }
 
-    fn print_test_result(&self, result: &TestResult) {
-        if self.ci_mode {
-            // CI mode: plain text, no colors or fancy symbols
-            if result.passed {
-                println!("  PASS {} ({:?})", result.name, result.duration);
-            } else {
-                println!("  FAIL {} ({:?})", result.name, result.duration);
-                if let Some(error) = &result.error {
-                    println!("    Error: {}", error);
-                }
-            }
-        } else {
-            // Interactive mode: colors and symbols
-            if result.passed {
-                println!("  {} {} ({:?})", "✔".green(), result.name, result.duration);
-            } else {
-                println!("  {} {} ({:?})", "✖".red(), result.name, result.duration);
+    fn has_matching_tests(&self, config: &RivetConfig) -> bool {
+        config
+            .tests
+            .iter()
+            .any(|step| self.should_run_test(&config.name, &step.name))
+    }
+}
 
-                if let Some(error) = &result.error {
-                    println!("    {}: {}", "Error".red().bold(), error);
-                }
-            }
+// Implement Clone for VariableContext in the variables module instead
+
+/// Built-in variables; a suite var, dataset column or environment variable
+/// of the same name wins.
+pub const RUN_ID_VAR: &str = "run_id";
+pub const SUITE_ID_VAR: &str = "suite_id";
+pub const ROW_INDEX_VAR: &str = "row_index";
+
+/// The environment a suite runs in, as set by `--env` or the suite's `env`.
+pub const RIVET_ENV_VAR: &str = "RIVET_ENV";
+
+/// The environment `config` runs in: `--env` when given, otherwise the
+/// suite's own `env`, which may read `${RIVET_ENV:dev}`. The second value is
+/// the suite's declaration when `--env` overrides a different one.
+pub fn suite_env(config: &RivetConfig, cli_env: Option<&str>) -> (Option<String>, Option<String>) {
+    let mut context = VariableContext::new().with_env_vars();
+    if let Some(cli_env) = cli_env {
+        context.set(RIVET_ENV_VAR.to_string(), cli_env.to_string());
+    }
+    let declared = config
+        .env
+        .as_deref()
+        .map(|env| context.substitute_variables(env))
+        .filter(|env| !env.is_empty());
+
+    match cli_env {
+        Some(cli_env) => (
+            Some(cli_env.to_string()),
+            declared.filter(|declared| declared != cli_env),
+        ),
+        None => (declared, None),
+    }
+}
+
+/// Short random ids for `{{run_id}}` and `{{suite_id}}`.
+struct IdGenerator(SplitMix64);
+
+impl IdGenerator {
+    fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Self(SplitMix64(seed ^ (u64::from(std::process::id()) << 32)))
+    }
+
+    /// Eight lowercase hex digits, e.g. `3f9a0c12`.
+    fn next_id(&mut self) -> String {
+        format!("{:08x}", self.0.next_u64() as u32)
+    }
+}
+
+/// Ids shared by every suite of one `run_tests` call.
+struct RunIds {
+    run_id: String,
+    ids: Mutex<IdGenerator>,
+}
+
+impl RunIds {
+    fn next_suite_id(&self) -> String {
+        self.ids.lock().unwrap().next_id()
+    }
+}
+
+/// `--grep` pattern, matched against `suite name :: test name`.
+#[derive(Clone)]
+pub struct TestFilter {
+    pattern: Regex,
+    invert: bool,
+}
+
+impl TestFilter {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let pattern =
+            Regex::new(pattern).with_context(|| format!("Invalid --grep pattern: {}", pattern))?;
+        Ok(Self {
+            pattern,
+            invert: false,
+        })
+    }
+
+    /// Match the tests the pattern does *not* match instead.
+    pub fn inverted(self, invert: bool) -> Self {
+        Self { invert, ..self }
+    }
+
+    pub fn matches(&self, suite_name: &str, test_name: &str) -> bool {
+        let name = format!("{} :: {}", suite_name, test_name);
+        self.pattern.is_match(&name) != self.invert
+    }
+}
+
+/// Set the variables `result` captured in `context`, for the steps after it.
+fn keep_captures(context: &mut Cow<'_, VariableContext>, result: &TestResult) {
+    for (name, value) in &result.captures {
+        context.to_mut().set(name.clone(), value.clone());
+    }
+}
+
+/// `context` with `step`'s own `vars` layered over it.
+fn step_context<'a>(
+    step: &TestStep,
+    context: &'a VariableContext,
+) -> Result<Cow<'a, VariableContext>> {
+    match &step.vars {
+        Some(vars) => {
+            let context = context
+                .clone()
+                .with_config_vars(Some(vars))
+                .with_context(|| format!("Invalid vars in test `{}`", step.name))?;
+            Ok(Cow::Owned(context))
         }
+        None => Ok(Cow::Borrowed(context)),
     }
 }
 
-// Implement Clone for VariableContext in the variables module instead
+/// A suite's `before_each`/`after_each` steps.
+struct StepHooks<'a> {
+    before_each: &'a [TestStep],
+    after_each: &'a [TestStep],
+}
+
+impl<'a> StepHooks<'a> {
+    fn from_config(config: &'a RivetConfig) -> Self {
+        Self {
+            before_each: config.before_each.as_deref().unwrap_or_default(),
+            after_each: config.after_each.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+/// Substitute variables in the file paths of a suite's `tls` block.
+fn resolve_tls_paths(tls: &TlsConfig, context: &VariableContext) -> TlsConfig {
+    let resolve = |path: &Option<PathBuf>| {
+        path.as_ref()
+            .map(|p| PathBuf::from(context.substitute_variables(&p.to_string_lossy())))
+    };
+    TlsConfig {
+        cert: resolve(&tls.cert),
+        key: resolve(&tls.key),
+        identity: resolve(&tls.identity),
+        identity_password: tls
+            .identity_password
+            .as_ref()
+            .map(|password| context.substitute_variables(password)),
+        cacert: resolve(&tls.cacert),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(pattern: &str, invert: bool) -> TestFilter {
+        TestFilter {
+            invert,
+            ..TestFilter::new(pattern).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_suite_env_prefers_cli_and_notes_overrides() {
+        let suite = |env: &str| {
+            serde_yaml::from_str::<RivetConfig>(&format!("name: s\nenv: {}\ntests: []", env))
+                .unwrap()
+        };
+        let owned = |env: &str| Some(env.to_string());
+
+        assert_eq!(suite_env(&suite("staging"), None), (owned("staging"), None));
+        assert_eq!(
+            suite_env(&suite("staging"), Some("prod")),
+            (owned("prod"), owned("staging"))
+        );
+        // Following RIVET_ENV agrees with whatever --env says
+        assert_eq!(
+            suite_env(&suite("${RIVET_ENV:dev}"), Some("prod")),
+            (owned("prod"), None)
+        );
+    }
+
+    #[test]
+    fn test_grep_matches_suite_and_test_name() {
+        let users = filter("users", false);
+        assert!(users.matches("User API", "List users"));
+        assert!(!users.matches("User API", "Health"));
+
+        let suite = filter("^User API :: ", false);
+        assert!(suite.matches("User API", "Health"));
+        assert!(!suite.matches("Orders", "List users of order"));
+    }
+
+    #[test]
+    fn test_grep_anchors_and_alternation() {
+        let anchored = filter(":: Get( |$)", false);
+        assert!(anchored.matches("Users", "Get"));
+        assert!(anchored.matches("Users", "Get user"));
+        assert!(!anchored.matches("Users", "Getter"));
+
+        let either = filter("(Create|Update) user$", false);
+        assert!(either.matches("Users", "Create user"));
+        assert!(either.matches("Users", "Update user"));
+        assert!(!either.matches("Users", "Delete user"));
+        assert!(!either.matches("Users", "Create user twice"));
+    }
+
+    #[test]
+    fn test_grep_invert() {
+        let no_deletes = filter("(?i)delete", true);
+        assert!(no_deletes.matches("Users", "Create user"));
+        assert!(!no_deletes.matches("Users", "Delete user"));
+        assert!(!no_deletes.matches("Cleanup DELETE", "Anything"));
+    }
+
+    #[test]
+    fn test_invalid_grep_pattern_is_an_error() {
+        let err = TestFilter::new("users(").err().unwrap();
+        assert!(err.to_string().contains("Invalid --grep pattern: users("));
+    }
+}
@@ -0,0 +1,1091 @@
+//! Static checks of suite files for `rivet validate`: everything that can be
+//! caught without sending a request.
+
+use crate::config::{
+    CaptureSource, Dataset, Expectation, LengthExpectation, Poll, RivetConfig, StatusExpectation,
+    TestStep,
+};
+use crate::runner::assertions::LengthBound;
+use crate::runner::auth::AUTHORIZATION_VAR;
+use crate::runner::base_url::{has_scheme, join_url, BASE_URL_VAR};
+use crate::runner::data::{load_data, DataSelection};
+use crate::runner::include;
+use crate::runner::parser::{describe_suite_path, find_suite_files, resolve_suite_path};
+use crate::runner::test_runner::{RIVET_ENV_VAR, ROW_INDEX_VAR, RUN_ID_VAR, SUITE_ID_VAR};
+use crate::runner::variables::{referenced_variables, VariableContext};
+use crate::utils::parse_timeout;
+use anyhow::Result;
+use reqwest::Method;
+use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+const SUITE_KEYS: &[&str] = &[
+    "name",
+    "description",
+    "env",
+    "vars",
+    "secrets",
+    "strict_vars",
+    "setup",
+    "before_each",
+    "tests",
+    "after_each",
+    "dataset",
+    "teardown",
+    "tls",
+    "insecure",
+    "follow_redirects",
+    "max_capture_bytes",
+    "auth",
+    "default_headers",
+    "identify_requests",
+    "base_path",
+    "include",
+    "templates",
+];
+const STEP_KEYS: &[&str] = &[
+    "name",
+    "description",
+    "vars",
+    "request",
+    "expect",
+    "capture",
+    "use",
+];
+const REQUEST_KEYS: &[&str] = &[
+    "method",
+    "url",
+    "headers",
+    "params",
+    "body",
+    "grpc",
+    "follow_redirects",
+    "compression",
+];
+const GRPC_KEYS: &[&str] = &["server", "proto", "call", "data", "metadata", "timeout"];
+const EXPECT_KEYS: &[&str] = &[
+    "status",
+    "schema",
+    "jsonpath",
+    "jsonpath_length",
+    "jsonpath_contains",
+    "jsonpath_every",
+    "headers",
+    "redirects_to",
+    "content_type",
+    "poll",
+];
+const CAPTURE_KEYS: &[&str] = &["header", "pattern"];
+const POLL_KEYS: &[&str] = &["url", "method", "headers", "interval", "timeout", "until"];
+const DATASET_KEYS: &[&str] = &["file", "parallel", "format", "filter", "sample"];
+const TLS_KEYS: &[&str] = &["cert", "key", "identity", "identity_password", "cacert"];
+const AUTH_KEYS: &[&str] = &["bearer", "basic", "oauth2_client_credentials"];
+const BASIC_AUTH_KEYS: &[&str] = &["user", "password"];
+const OAUTH2_KEYS: &[&str] = &["token_url", "client_id", "client_secret", "scopes"];
+
+/// Sections of a suite that hold steps, in the order they run.
+const STEP_SECTIONS: &[&str] = &["setup", "before_each", "tests", "after_each", "teardown"];
+
+const STANDARD_METHODS: &[&str] = &[
+    "GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// One problem found in a suite file.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub file: PathBuf,
+    /// 1-based line the problem is on, when it could be located
+    pub line: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Finding {
+    /// `file:line`, or just the file when the line isn't known.
+    pub fn location(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}", self.file.display(), line),
+            None => self.file.display().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", self.location(), severity, self.message)
+    }
+}
+
+/// Findings for every suite under a target.
+#[derive(Debug, Default)]
+pub struct Validation {
+    /// Suite files checked
+    pub files: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl Validation {
+    pub fn errors(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+}
+
+/// Check every suite file under `target` (or `target` itself).
+pub async fn validate_target(target: &Path) -> Result<Validation> {
+    let mut validation = Validation::default();
+    for file in find_suite_files(target)? {
+        validation.files += 1;
+        validation.findings.extend(validate_file(&file).await);
+    }
+    Ok(validation)
+}
+
+/// Check one suite file.
+pub async fn validate_file(file: &Path) -> Vec<Finding> {
+    let content = match tokio::fs::read_to_string(file).await {
+        Ok(content) => content,
+        Err(e) => {
+            let mut checker = Checker::new(file, "");
+            checker.error(None, format!("Failed to read file: {}", e));
+            return checker.findings;
+        }
+    };
+    let mut checker = Checker::new(file, &content);
+
+    let document: Value = match serde_yaml::from_str(&content) {
+        Ok(document) => document,
+        Err(e) => {
+            checker.error(
+                e.location().map(|location| location.line()),
+                format!("Invalid YAML: {}", without_location(&e)),
+            );
+            return checker.findings;
+        }
+    };
+    checker.check_keys(&document);
+
+    let config = if include::is_composed(&document) {
+        let own_setup = document["setup"].as_sequence().map_or(0, Vec::len);
+        let composed = match include::compose(document, file) {
+            Ok(composed) => composed,
+            Err(e) => {
+                let line = checker.line_of_section("include");
+                checker.error(line, format!("{:#}", e));
+                return checker.findings;
+            }
+        };
+        let included_setup = composed["setup"].as_sequence().map_or(0, Vec::len) - own_setup;
+        checker.shift_steps("setup", included_setup);
+        serde_yaml::from_value::<RivetConfig>(composed)
+    } else {
+        serde_yaml::from_str::<RivetConfig>(&content)
+    };
+    match config {
+        Ok(mut config) => {
+            // Default headers are checked as part of each step's request
+            config.apply_step_defaults();
+            checker.check_suite(&config).await
+        }
+        Err(e) => checker.error(
+            e.location().map(|location| location.line()),
+            without_location(&e),
+        ),
+    }
+    checker.findings
+}
+
+/// serde_yaml's message without its trailing "at line L column C", which
+/// findings carry separately.
+fn without_location(error: &serde_yaml::Error) -> String {
+    let message = error.to_string();
+    match message.rfind(" at line ") {
+        Some(at) if error.location().is_some() => message[..at].to_string(),
+        _ => message,
+    }
+}
+
+struct Checker<'a> {
+    file: &'a Path,
+    lines: Vec<&'a str>,
+    /// Line (0-based) the next key is searched from; keys are located in
+    /// document order
+    cursor: usize,
+    /// Line of each step, keyed by section and index
+    step_lines: HashMap<(&'static str, usize), usize>,
+    findings: Vec<Finding>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(file: &'a Path, content: &'a str) -> Self {
+        Self {
+            file,
+            lines: content.lines().collect(),
+            cursor: 0,
+            step_lines: HashMap::new(),
+            findings: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, line: Option<usize>, message: impl Into<String>) {
+        self.push(line, Severity::Error, message.into());
+    }
+
+    fn warning(&mut self, line: Option<usize>, message: impl Into<String>) {
+        self.push(line, Severity::Warning, message.into());
+    }
+
+    fn push(&mut self, line: Option<usize>, severity: Severity, message: String) {
+        self.findings.push(Finding {
+            file: self.file.to_path_buf(),
+            line,
+            severity,
+            message,
+        });
+    }
+
+    /// The 1-based line of the next `key:` from the cursor, moving the
+    /// cursor past it.
+    fn locate(&mut self, key: &str) -> Option<usize> {
+        for (index, line) in self.lines.iter().enumerate().skip(self.cursor) {
+            let line = line.trim_start();
+            let line = line.strip_prefix('-').map(str::trim_start).unwrap_or(line);
+            let rest = [
+                key.to_string(),
+                format!("\"{}\"", key),
+                format!("'{}'", key),
+            ]
+            .iter()
+            .find_map(|candidate| line.strip_prefix(candidate.as_str()));
+            if rest.is_some_and(|rest| rest.trim_start().starts_with(':')) {
+                self.cursor = index + 1;
+                return Some(index + 1);
+            }
+        }
+        None
+    }
+
+    /// Flag keys no part of a suite has, walking the document in order so
+    /// each one can be located.
+    fn check_keys(&mut self, document: &Value) {
+        let Some(suite) = document.as_mapping() else {
+            return;
+        };
+        for (key, value) in suite {
+            let Some(key) = key.as_str() else { continue };
+            let line = self.locate(key);
+            if !SUITE_KEYS.contains(&key) {
+                self.unknown_key(line, key, "suite", SUITE_KEYS);
+                continue;
+            }
+            if let Some(section) = STEP_SECTIONS.iter().find(|section| **section == key) {
+                for (index, step) in value.as_sequence().into_iter().flatten().enumerate() {
+                    self.check_step_keys(section, index, step);
+                }
+            }
+            match key {
+                "dataset" => self.check_mapping_keys(value, "dataset", DATASET_KEYS),
+                "templates" => {
+                    for (name, template) in value.as_mapping().into_iter().flatten() {
+                        let at = format!("templates.{}", name.as_str().unwrap_or_default());
+                        self.locate(name.as_str().unwrap_or_default());
+                        self.check_mapping_keys(template, &at, STEP_KEYS);
+                    }
+                }
+                "tls" => self.check_mapping_keys(value, "tls", TLS_KEYS),
+                "auth" => {
+                    self.check_mapping_keys(value, "auth", AUTH_KEYS);
+                    self.check_mapping_keys(&value["basic"], "auth.basic", BASIC_AUTH_KEYS);
+                    self.check_mapping_keys(
+                        &value["oauth2_client_credentials"],
+                        "auth.oauth2_client_credentials",
+                        OAUTH2_KEYS,
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check_step_keys(&mut self, section: &'static str, index: usize, step: &Value) {
+        let Some(step) = step.as_mapping() else {
+            return;
+        };
+        let at = format!("{}[{}]", section, index);
+        for (position, (key, value)) in step.iter().enumerate() {
+            let Some(key) = key.as_str() else { continue };
+            let line = self.locate(key);
+            if position == 0 {
+                if let Some(line) = line {
+                    self.step_lines.insert((section, index), line);
+                }
+            }
+            if !STEP_KEYS.contains(&key) {
+                self.unknown_key(line, key, &at, STEP_KEYS);
+                continue;
+            }
+            match key {
+                "request" => {
+                    let at = format!("{}.request", at);
+                    for (key, value) in value.as_mapping().into_iter().flatten() {
+                        let Some(key) = key.as_str() else { continue };
+                        let line = self.locate(key);
+                        if !REQUEST_KEYS.contains(&key) {
+                            self.unknown_key(line, key, &at, REQUEST_KEYS);
+                        } else if key == "grpc" {
+                            self.check_mapping_keys(value, &format!("{}.grpc", at), GRPC_KEYS);
+                        }
+                    }
+                }
+                "expect" => self.check_expect_keys(value, &format!("{}.expect", at)),
+                "capture" => {
+                    for (name, source) in value.as_mapping().into_iter().flatten() {
+                        let Some(name) = name.as_str() else { continue };
+                        self.locate(name);
+                        let at = format!("{}.capture.{}", at, name);
+                        self.check_mapping_keys(source, &at, CAPTURE_KEYS);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Keys of an `expect`, or of a poll's `until`.
+    fn check_expect_keys(&mut self, value: &Value, at: &str) {
+        for (key, value) in value.as_mapping().into_iter().flatten() {
+            let Some(key) = key.as_str() else { continue };
+            let line = self.locate(key);
+            if !EXPECT_KEYS.contains(&key) {
+                self.unknown_key(line, key, at, EXPECT_KEYS);
+            } else if key == "poll" {
+                let at = format!("{}.poll", at);
+                for (key, value) in value.as_mapping().into_iter().flatten() {
+                    let Some(key) = key.as_str() else { continue };
+                    let line = self.locate(key);
+                    if !POLL_KEYS.contains(&key) {
+                        self.unknown_key(line, key, &at, POLL_KEYS);
+                    } else if key == "until" {
+                        self.check_expect_keys(value, &format!("{}.until", at));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_mapping_keys(&mut self, value: &Value, at: &str, known: &[&str]) {
+        for (key, _) in value.as_mapping().into_iter().flatten() {
+            let Some(key) = key.as_str() else { continue };
+            let line = self.locate(key);
+            if !known.contains(&key) {
+                self.unknown_key(line, key, at, known);
+            }
+        }
+    }
+
+    fn unknown_key(&mut self, line: Option<usize>, key: &str, at: &str, known: &[&str]) {
+        let suggestion = known
+            .iter()
+            .filter(|candidate| strsim::levenshtein(key, candidate) <= 2)
+            .min_by_key(|candidate| strsim::levenshtein(key, candidate))
+            .map(|candidate| format!("; did you mean `{}`?", candidate))
+            .unwrap_or_default();
+        self.error(
+            line,
+            format!("Unknown field `{}` in {}{}", key, at, suggestion),
+        );
+    }
+
+    async fn check_suite(&mut self, config: &RivetConfig) {
+        if config.tests.is_empty() {
+            self.warning(self.line_of_section("tests"), "Suite has no tests");
+        }
+
+        let context = match VariableContext::new()
+            .with_env_vars()
+            .with_config_vars(config.vars.as_ref())
+        {
+            Ok(context) => context,
+            Err(e) => {
+                self.error(self.line_of_section("vars"), e.to_string());
+                VariableContext::new().with_env_vars()
+            }
+        };
+
+        let mut defined: HashSet<String> = context.vars.keys().cloned().collect();
+        defined.insert(RIVET_ENV_VAR.to_string());
+        defined.insert(RUN_ID_VAR.to_string());
+        defined.insert(SUITE_ID_VAR.to_string());
+        if let Some(dataset) = &config.dataset {
+            defined.extend(self.check_dataset(dataset).await);
+            defined.insert(ROW_INDEX_VAR.to_string());
+        }
+        if let Some(auth) = &config.auth {
+            if let Err(e) = auth.scheme() {
+                self.error(self.line_of_section("auth"), e.to_string());
+            }
+            defined.insert(AUTHORIZATION_VAR.to_string());
+        }
+        let strict = config.strict_vars.unwrap_or(false);
+
+        let mut names: HashMap<&str, usize> = HashMap::new();
+        for (section, steps) in sections(config) {
+            for (index, step) in steps.iter().enumerate() {
+                let line = self.step_lines.get(&(section, index)).copied();
+                match names.get(step.name.as_str()) {
+                    Some(first) => self.warning(
+                        line,
+                        format!(
+                            "Duplicate test name `{}` (first used on line {})",
+                            step.name, first
+                        ),
+                    ),
+                    None => {
+                        names.insert(&step.name, line.unwrap_or_default());
+                    }
+                }
+                self.check_step(step, line, &context, &defined, strict);
+                // Captured variables are set for the steps that run after
+                defined.extend(step.capture.iter().flatten().map(|(name, _)| name.clone()));
+            }
+        }
+    }
+
+    /// The line of a top-level key, without moving the cursor.
+    /// Move the located steps of `section` down by `count`, for the steps
+    /// an include put before them.
+    fn shift_steps(&mut self, section: &'static str, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let shifted: Vec<_> = self
+            .step_lines
+            .iter()
+            .filter(|((at, _), _)| *at == section)
+            .map(|(&(at, index), &line)| ((at, index + count), line))
+            .collect();
+        self.step_lines.retain(|(at, _), _| *at != section);
+        self.step_lines.extend(shifted);
+    }
+
+    fn line_of_section(&self, key: &str) -> Option<usize> {
+        let prefix = format!("{}:", key);
+        self.lines
+            .iter()
+            .position(|line| line.starts_with(&prefix))
+            .map(|index| index + 1)
+    }
+
+    /// Check the dataset file loads and its filter and sample parse.
+    /// Returns its columns.
+    async fn check_dataset(&mut self, dataset: &Dataset) -> HashSet<String> {
+        let line = self.line_of_section("dataset");
+        let selection = DataSelection {
+            filter: dataset.filter.clone(),
+            sample: dataset.sample.as_ref().map(|sample| sample.to_string()),
+            seed: 0,
+        };
+        if let Err(e) = selection.validate() {
+            self.error(line, format!("Invalid dataset selection: {}", e));
+        }
+
+        let path = resolve_suite_path(self.file.parent().unwrap_or(Path::new("")), &dataset.file);
+        if !path.exists() {
+            self.error(
+                line,
+                format!(
+                    "Dataset file does not exist: {}",
+                    describe_suite_path(&dataset.file, &path)
+                ),
+            );
+            return HashSet::new();
+        }
+        match load_data(&path, dataset.format.as_deref()).await {
+            Ok(rows) => {
+                if rows.is_empty() {
+                    self.warning(line, format!("Dataset `{}` has no rows", dataset.file));
+                }
+                rows.into_iter().flat_map(|row| row.into_keys()).collect()
+            }
+            Err(e) => {
+                self.error(line, format!("{:#}", e));
+                HashSet::new()
+            }
+        }
+    }
+
+    fn check_step(
+        &mut self,
+        step: &TestStep,
+        line: Option<usize>,
+        context: &VariableContext,
+        defined: &HashSet<String>,
+        strict: bool,
+    ) {
+        let request = &step.request;
+        let mut texts: Vec<String> = Vec::new();
+
+        let mut defined = defined.clone();
+        if let Some(vars) = &step.vars {
+            defined.extend(vars.keys().cloned());
+            texts.extend(vars.values().cloned());
+        }
+        let mut unknown_in_name: Vec<String> = referenced_variables(&step.name)
+            .into_iter()
+            .filter(|name| !defined.contains(name))
+            .collect();
+        unknown_in_name.sort();
+        unknown_in_name.dedup();
+        for name in unknown_in_name {
+            self.warning(
+                line,
+                format!(
+                    "Test `{}`: name references `{}`, which is not a dataset column or variable",
+                    step.name, name
+                ),
+            );
+        }
+
+        if let Some(grpc) = &request.grpc {
+            match grpc.call.split_once('/') {
+                Some((service, method)) if !service.is_empty() && !method.is_empty() => {}
+                _ => self.error(
+                    line,
+                    format!(
+                        "Test `{}`: gRPC call `{}` must be `Service/Method`",
+                        step.name, grpc.call
+                    ),
+                ),
+            }
+            self.check_url(step, line, &grpc.server, context, false);
+            texts.extend([grpc.server.clone(), grpc.proto.clone(), grpc.call.clone()]);
+            texts.extend(grpc.data.iter().map(|data| data.to_string()));
+            texts.extend(grpc.metadata.iter().flatten().map(|(_, v)| v.clone()));
+        } else {
+            self.check_method(step, line);
+            self.check_url(step, line, &request.url, context, true);
+            texts.extend([request.method.clone(), request.url.clone()]);
+            if is_relative(&request.url, context) {
+                texts.push(format!("{{{{{}}}}}", BASE_URL_VAR));
+            }
+            for (key, value) in request
+                .headers
+                .iter()
+                .flatten()
+                .chain(request.params.iter().flatten())
+            {
+                texts.extend([key.clone(), value.clone()]);
+            }
+            texts.extend(request.body.clone());
+        }
+
+        for (name, source) in step.capture.iter().flatten() {
+            match source {
+                CaptureSource::JsonPath(path) => {
+                    if let Err(reason) = check_jsonpath(path) {
+                        self.error(
+                            line,
+                            format!(
+                                "Test `{}`: invalid JSONPath `{}` to capture `{}`: {}",
+                                step.name, path, name, reason
+                            ),
+                        );
+                    }
+                }
+                CaptureSource::Header {
+                    pattern: Some(pattern),
+                    ..
+                } => {
+                    if let Err(e) = regex::Regex::new(pattern) {
+                        // The regex error draws the pattern; its last line says what's wrong
+                        let e = e.to_string();
+                        self.error(
+                            line,
+                            format!(
+                                "Test `{}`: invalid pattern `{}` to capture `{}`: {}",
+                                step.name,
+                                pattern,
+                                name,
+                                e.lines()
+                                    .last()
+                                    .unwrap_or_default()
+                                    .trim_start_matches("error: ")
+                            ),
+                        );
+                    }
+                }
+                CaptureSource::Header { pattern: None, .. } => {}
+            }
+        }
+        if let Some(expect) = &step.expect {
+            self.check_expectation(step, line, expect, &mut texts);
+            if let Some(poll) = &expect.poll {
+                // The poll may use what the step captures
+                defined.extend(step.capture.iter().flatten().map(|(name, _)| name.clone()));
+                self.check_poll(step, line, poll, context, &mut texts);
+            }
+        }
+
+        let mut reported = HashSet::new();
+        for name in texts.iter().flat_map(|text| referenced_variables(text)) {
+            if defined.contains(&name) || !reported.insert(name.clone()) {
+                continue;
+            }
+            let hint = if name == BASE_URL_VAR {
+                " (set it in `vars` or pass --base-url)"
+            } else {
+                ""
+            };
+            let message = format!(
+                "Test `{}`: variable `{}` is not defined in vars, the dataset or the environment{}",
+                step.name, name, hint
+            );
+            if strict {
+                self.error(line, message);
+            } else {
+                self.warning(line, message);
+            }
+        }
+    }
+
+    /// Check an `expect`, or a poll's `until`, collecting its variable uses
+    /// in `texts`.
+    fn check_expectation(
+        &mut self,
+        step: &TestStep,
+        line: Option<usize>,
+        expect: &Expectation,
+        texts: &mut Vec<String>,
+    ) {
+        match &expect.status {
+            Some(StatusExpectation::Number(status)) => self.check_status(step, line, *status),
+            Some(StatusExpectation::String(status)) => {
+                if referenced_variables(status).is_empty() {
+                    match status.parse() {
+                        Ok(status) => self.check_status(step, line, status),
+                        Err(_) => self.error(
+                            line,
+                            format!(
+                                "Test `{}`: expected status `{}` is not a number",
+                                step.name, status
+                            ),
+                        ),
+                    }
+                }
+                texts.push(status.clone());
+            }
+            None => {}
+        }
+        let paths = expect
+            .jsonpath
+            .iter()
+            .chain(&expect.jsonpath_contains)
+            .chain(&expect.jsonpath_every)
+            .flatten();
+        for (path, value) in paths {
+            if let Err(reason) = check_jsonpath(path) {
+                self.error(
+                    line,
+                    format!(
+                        "Test `{}`: invalid JSONPath `{}`: {}",
+                        step.name, path, reason
+                    ),
+                );
+            }
+            texts.push(value.to_string());
+        }
+        for (path, length) in expect.jsonpath_length.iter().flatten() {
+            if let Err(reason) = check_jsonpath(path) {
+                self.error(
+                    line,
+                    format!(
+                        "Test `{}`: invalid JSONPath `{}`: {}",
+                        step.name, path, reason
+                    ),
+                );
+            }
+            let LengthExpectation::String(length) = length else {
+                continue;
+            };
+            if referenced_variables(length).is_empty() {
+                if let Err(reason) = LengthBound::parse(length) {
+                    self.error(
+                        line,
+                        format!(
+                            "Test `{}`: invalid length `{}` for `{}`: {}",
+                            step.name, length, path, reason
+                        ),
+                    );
+                }
+            }
+            texts.push(length.clone());
+        }
+        texts.extend(expect.headers.iter().flatten().map(|(_, v)| v.clone()));
+    }
+
+    fn check_poll(
+        &mut self,
+        step: &TestStep,
+        line: Option<usize>,
+        poll: &Poll,
+        context: &VariableContext,
+        texts: &mut Vec<String>,
+    ) {
+        if let Some(url) = &poll.url {
+            self.check_url(step, line, url, context, true);
+            texts.push(url.clone());
+        }
+        if let Some(method) = &poll.method {
+            if method.parse::<Method>().is_err() {
+                self.error(
+                    line,
+                    format!("Test `{}`: invalid poll method `{}`", step.name, method),
+                );
+            }
+        }
+        for (what, value) in [("interval", &poll.interval), ("timeout", &poll.timeout)] {
+            let Some(value) = value else { continue };
+            if referenced_variables(value).is_empty() && parse_timeout(value).is_err() {
+                self.error(
+                    line,
+                    format!("Test `{}`: invalid poll {} `{}`", step.name, what, value),
+                );
+            }
+            texts.push(value.clone());
+        }
+        for (key, value) in poll.headers.iter().flatten() {
+            texts.extend([key.clone(), value.clone()]);
+        }
+        if poll.until.poll.is_some() {
+            self.error(
+                line,
+                format!("Test `{}`: a poll's `until` cannot poll again", step.name),
+            );
+        }
+        self.check_expectation(step, line, &poll.until, texts);
+    }
+
+    fn check_method(&mut self, step: &TestStep, line: Option<usize>) {
+        let method = &step.request.method;
+        if method.is_empty() {
+            self.error(line, format!("Test `{}`: request has no method", step.name));
+        } else if method.parse::<Method>().is_err() {
+            self.error(
+                line,
+                format!("Test `{}`: invalid HTTP method `{}`", step.name, method),
+            );
+        } else if !STANDARD_METHODS.contains(&method.as_str()) {
+            let upper = method.to_uppercase();
+            let message = if STANDARD_METHODS.contains(&upper.as_str()) {
+                format!(
+                    "Test `{}`: method `{}` is sent as written; use `{}`",
+                    step.name, method, upper
+                )
+            } else {
+                format!(
+                    "Test `{}`: non-standard HTTP method `{}`",
+                    step.name, method
+                )
+            };
+            self.warning(line, message);
+        }
+    }
+
+    /// Check `url` parses once known variables are substituted; unknown
+    /// ones get a placeholder. With `relative`, a URL without a scheme is
+    /// checked as a path under `baseUrl`.
+    fn check_url(
+        &mut self,
+        step: &TestStep,
+        line: Option<usize>,
+        url: &str,
+        context: &VariableContext,
+        relative: bool,
+    ) {
+        if url.is_empty() {
+            self.error(line, format!("Test `{}`: request has no url", step.name));
+            return;
+        }
+        let substituted = context.substitute_variables(url);
+        // An unknown variable leading the URL stands for its scheme and host
+        let substituted = match substituted.strip_prefix("{{") {
+            Some(rest) => match rest.split_once("}}") {
+                Some((_, rest)) => format!("http://placeholder{}", rest),
+                None => substituted,
+            },
+            None => substituted,
+        };
+        let placeholder = placeholder_regex().replace_all(&substituted, "placeholder");
+        let parsed = if relative && !has_scheme(&placeholder) {
+            join_url("http://placeholder", None, &placeholder)
+        } else {
+            Url::parse(&placeholder).map_err(Into::into)
+        };
+        if let Err(e) = parsed {
+            self.error(
+                line,
+                format!("Test `{}`: URL `{}` is invalid: {}", step.name, url, e),
+            );
+        }
+    }
+
+    fn check_status(&mut self, step: &TestStep, line: Option<usize>, status: u16) {
+        if !(100..=599).contains(&status) {
+            self.error(
+                line,
+                format!(
+                    "Test `{}`: expected status {} is not an HTTP status code",
+                    step.name, status
+                ),
+            );
+        }
+    }
+}
+
+/// Whether `url` is joined to `baseUrl` when sent, rather than starting
+/// with a scheme or a variable standing for one.
+fn is_relative(url: &str, context: &VariableContext) -> bool {
+    let substituted = context.substitute_variables(url);
+    !substituted.starts_with("{{") && !has_scheme(&substituted)
+}
+
+fn placeholder_regex() -> &'static regex::Regex {
+    static REGEX: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    REGEX.get_or_init(|| regex::Regex::new(r"\{\{[\w.]+\}\}").unwrap())
+}
+
+fn sections(config: &RivetConfig) -> Vec<(&'static str, &[TestStep])> {
+    vec![
+        ("setup", config.setup.as_deref().unwrap_or_default()),
+        (
+            "before_each",
+            config.before_each.as_deref().unwrap_or_default(),
+        ),
+        ("tests", config.tests.as_slice()),
+        (
+            "after_each",
+            config.after_each.as_deref().unwrap_or_default(),
+        ),
+        ("teardown", config.teardown.as_deref().unwrap_or_default()),
+    ]
+}
+
+/// Check `path` against the JSONPath subset assertions support: dotted
+/// fields with optional numeric or `*` indexes, e.g. `$.items[0].id` or
+/// `$.items[*].id`.
+fn check_jsonpath(path: &str) -> std::result::Result<(), String> {
+    let rest = match path.strip_prefix('$') {
+        Some("") => return Ok(()),
+        Some(rest) if rest.starts_with('[') => rest,
+        Some(rest) => rest
+            .strip_prefix('.')
+            .ok_or_else(|| "expected `.` or `[` after `$`".to_string())?,
+        None => path,
+    };
+    if rest.is_empty() {
+        return Err("nothing after `$.`".to_string());
+    }
+    for segment in rest.split('.') {
+        if segment.is_empty() {
+            return Err("empty segment (`..` is not supported)".to_string());
+        }
+        let (field, index) = match segment.split_once('[') {
+            Some((field, index)) => (field, Some(index)),
+            None => (segment, None),
+        };
+        if field.contains(']') {
+            return Err(format!("unexpected `]` in `{}`", segment));
+        }
+        if let Some(index) = index {
+            let index = index
+                .strip_suffix(']')
+                .ok_or_else(|| format!("unclosed `[` in `{}`", segment))?;
+            if index != "*" && index.parse::<usize>().is_err() {
+                return Err(format!("array index `{}` is not a number", index));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Expectation, Request};
+
+    #[test]
+    fn test_check_jsonpath() {
+        for path in [
+            "$",
+            "$.id",
+            "$.items[0].id",
+            "$[2].name",
+            "user.name",
+            "$.items[*].id",
+            "$[*]",
+        ] {
+            assert_eq!(check_jsonpath(path), Ok(()), "{}", path);
+        }
+        for path in ["$..id", "$.items[x]", "$.items[0", "$id", "$.a]"] {
+            assert!(check_jsonpath(path).is_err(), "{}", path);
+        }
+    }
+
+    /// Every field a suite serializes is one `validate` knows about.
+    #[test]
+    fn test_known_keys_match_config() {
+        let until = Expectation {
+            poll: None,
+            redirects_to: None,
+            content_type: None,
+            status: None,
+            schema: None,
+            jsonpath: None,
+            jsonpath_length: None,
+            jsonpath_contains: None,
+            jsonpath_every: None,
+            headers: None,
+        };
+        let step = TestStep {
+            capture: Some(HashMap::from([(
+                "id".to_string(),
+                crate::config::CaptureSource::Header {
+                    header: String::new(),
+                    pattern: Some(String::new()),
+                },
+            )])),
+            name: "Step".to_string(),
+            description: Some(String::new()),
+            vars: Some(HashMap::new()),
+            request: Request {
+                follow_redirects: None,
+                compression: Some(crate::config::Compression::Gzip),
+                method: "GET".to_string(),
+                url: "http://localhost".to_string(),
+                headers: Some(HashMap::new()),
+                params: Some(HashMap::new()),
+                body: Some(String::new()),
+                grpc: Some(crate::config::GrpcRequest {
+                    server: String::new(),
+                    proto: String::new(),
+                    call: String::new(),
+                    data: None,
+                    metadata: None,
+                    timeout: None,
+                }),
+            },
+            expect: Some(Expectation {
+                poll: Some(Box::new(crate::config::Poll {
+                    url: Some(String::new()),
+                    method: Some(String::new()),
+                    headers: Some(HashMap::new()),
+                    interval: Some(String::new()),
+                    timeout: Some(String::new()),
+                    until,
+                })),
+                redirects_to: None,
+                content_type: None,
+                status: None,
+                schema: None,
+                jsonpath: None,
+                jsonpath_length: Some(HashMap::new()),
+                jsonpath_contains: Some(HashMap::new()),
+                jsonpath_every: Some(HashMap::new()),
+                headers: None,
+            }),
+        };
+        let config = RivetConfig {
+            default_headers: Some(HashMap::new()),
+            identify_requests: Some(true),
+            base_path: Some(String::new()),
+            include: Some(Vec::new()),
+            templates: Some(HashMap::new()),
+            auth: Some(crate::config::AuthConfig {
+                bearer: Some(String::new()),
+                basic: Some(crate::config::BasicAuth {
+                    user: String::new(),
+                    password: String::new(),
+                }),
+                oauth2_client_credentials: Some(crate::config::OAuth2ClientCredentials {
+                    token_url: String::new(),
+                    client_id: String::new(),
+                    client_secret: String::new(),
+                    scopes: vec![String::new()],
+                }),
+            }),
+            max_capture_bytes: None,
+            follow_redirects: None,
+            name: "Suite".to_string(),
+            description: None,
+            env: None,
+            vars: None,
+            secrets: None,
+            strict_vars: None,
+            setup: None,
+            before_each: None,
+            tests: vec![step],
+            after_each: None,
+            dataset: Some(Dataset {
+                file: String::new(),
+                parallel: None,
+                format: None,
+                filter: None,
+                sample: None,
+            }),
+            teardown: None,
+            tls: Some(Default::default()),
+            insecure: None,
+        };
+        let document = serde_yaml::to_value(&config).unwrap();
+        let keys = |value: &Value| -> Vec<String> {
+            value
+                .as_mapping()
+                .unwrap()
+                .keys()
+                .map(|key| key.as_str().unwrap().to_string())
+                .collect()
+        };
+        let step = &document["tests"][0];
+        for (value, known) in [
+            (&document, SUITE_KEYS),
+            (step, STEP_KEYS),
+            (&step["request"], REQUEST_KEYS),
+            (&step["request"]["grpc"], GRPC_KEYS),
+            (&step["expect"], EXPECT_KEYS),
+            (&step["expect"]["poll"], POLL_KEYS),
+            (&step["capture"]["id"], CAPTURE_KEYS),
+            (&document["dataset"], DATASET_KEYS),
+            (&document["tls"], TLS_KEYS),
+            (&document["auth"], AUTH_KEYS),
+            (&document["auth"]["basic"], BASIC_AUTH_KEYS),
+            (&document["auth"]["oauth2_client_credentials"], OAUTH2_KEYS),
+        ] {
+            for key in keys(value) {
+                assert!(known.contains(&key.as_str()), "{} is not known", key);
+            }
+        }
+    }
+}
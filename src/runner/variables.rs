@@ -1,10 +1,39 @@
+use anyhow::{bail, Result};
 use regex::Regex;
 use std::collections::HashMap;
 use std::env;
+use std::sync::OnceLock;
+
+/// How deep `vars` may reference each other before resolution gives up.
+const MAX_VAR_DEPTH: usize = 10;
 
 #[derive(Clone)]
 pub struct VariableContext {
     pub vars: HashMap<String, String>,
+    /// Names of variables whose values must never be printed or reported
+    secrets: Vec<String>,
+    /// Fail requests that still contain `{{name}}` after substitution
+    strict: bool,
+}
+
+/// Replacement shown wherever a secret value would appear.
+pub const REDACTED: &str = "*****";
+
+/// Built-in variables with a fresh value at every use, unless the suite
+/// defines a variable of the same name.
+pub const DYNAMIC_VARIABLES: &[&str] = &["$uuid", "$timestamp", "$isoTimestamp", "$randomInt"];
+
+/// A new value of the dynamic variable `name`: a random UUID, Unix seconds,
+/// an ISO 8601 UTC time with milliseconds, or an integer from 0 to 1000.
+pub fn dynamic_value(name: &str) -> Option<String> {
+    let now = chrono::Utc::now();
+    match name {
+        "$uuid" => Some(uuid::Uuid::new_v4().to_string()),
+        "$timestamp" => Some(now.timestamp().to_string()),
+        "$isoTimestamp" => Some(now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+        "$randomInt" => Some((uuid::Uuid::new_v4().as_u128() % 1001).to_string()),
+        _ => None,
+    }
 }
 
 impl Default for VariableContext {
@@ -17,6 +46,8 @@ impl VariableContext {
     pub fn new() -> Self {
         Self {
             vars: HashMap::new(),
+            secrets: Vec::new(),
+            strict: false,
         }
     }
 
@@ -28,14 +59,68 @@ impl VariableContext {
         self
     }
 
-    pub fn with_config_vars(mut self, config_vars: Option<&HashMap<String, String>>) -> Self {
-        if let Some(vars) = config_vars {
-            for (key, value) in vars {
-                let expanded_value = self.substitute_variables(value);
-                self.vars.insert(key.clone(), expanded_value);
+    /// Add a suite's `vars`. Values may reference each other in any order
+    /// and through several levels; cycles are an error.
+    pub fn with_config_vars(
+        mut self,
+        config_vars: Option<&HashMap<String, String>>,
+    ) -> Result<Self> {
+        let Some(config_vars) = config_vars else {
+            return Ok(self);
+        };
+
+        // Sorted so the same cycle is always reported the same way
+        let mut names: Vec<_> = config_vars.keys().collect();
+        names.sort();
+
+        let mut resolved = HashMap::new();
+        for name in names {
+            self.resolve_config_var(name, config_vars, &mut resolved, &mut Vec::new())?;
+        }
+        self.vars.extend(resolved);
+        Ok(self)
+    }
+
+    fn resolve_config_var(
+        &self,
+        name: &str,
+        config_vars: &HashMap<String, String>,
+        resolved: &mut HashMap<String, String>,
+        stack: &mut Vec<String>,
+    ) -> Result<String> {
+        if let Some(value) = resolved.get(name) {
+            return Ok(value.clone());
+        }
+        if let Some(start) = stack.iter().position(|entry| entry == name) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(name.to_string());
+            bail!("Variable cycle: {}", cycle.join(" -> "));
+        }
+        if stack.len() >= MAX_VAR_DEPTH {
+            bail!(
+                "Variables nested more than {} levels deep: {} -> {}",
+                MAX_VAR_DEPTH,
+                stack.join(" -> "),
+                name
+            );
+        }
+
+        let raw = &config_vars[name];
+        stack.push(name.to_string());
+        let mut overlay = HashMap::new();
+        // A variable referring to itself sees the value it had before `vars`,
+        // e.g. `token: ${token:dev}` still reads the environment
+        for reference in config_references(raw) {
+            if reference != name && config_vars.contains_key(&reference) {
+                let value = self.resolve_config_var(&reference, config_vars, resolved, stack)?;
+                overlay.insert(reference, value);
             }
         }
-        self
+        stack.pop();
+
+        let value = self.substitute_with(raw, &overlay);
+        resolved.insert(name.to_string(), value.clone());
+        Ok(value)
     }
 
     pub fn with_data_row(mut self, data: &HashMap<String, String>) -> Self {
@@ -45,13 +130,89 @@ impl VariableContext {
         self
     }
 
+    /// Mark variables as secret. Values are looked up when redacting, so a
+    /// secret filled in later (e.g. from a dataset row) is masked too.
+    pub fn with_secrets<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for name in names {
+            let name = name.into();
+            if !self.secrets.contains(&name) {
+                self.secrets.push(name);
+            }
+        }
+        self
+    }
+
+    pub fn has_secrets(&self) -> bool {
+        !self.secrets.is_empty()
+    }
+
+    /// Replace every secret value in `text` with [`REDACTED`].
+    pub fn redact(&self, text: &str) -> String {
+        let mut values: Vec<&str> = self
+            .secrets
+            .iter()
+            .filter_map(|name| self.vars.get(name))
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+            .collect();
+        // Longest first, so a secret containing another is masked whole
+        values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+
+        let mut result = text.to_string();
+        for value in values {
+            result = result.replace(value, REDACTED);
+        }
+        result
+    }
+
     pub fn set_variable(&mut self, key: String, value: String) {
         self.vars.insert(key, value);
     }
 
+    /// Fail unresolved `{{name}}` references in [`resolve`](Self::resolve)
+    /// instead of sending them literally.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Substitute variables in request text, failing on anything left
+    /// unresolved when the suite sets `strict_vars`.
+    pub fn resolve(&self, text: &str) -> Result<String> {
+        let result = self.substitute_variables(text);
+        if self.strict {
+            let mut unresolved: Vec<String> = Vec::new();
+            for caps in var_regex().captures_iter(&result) {
+                if !unresolved.iter().any(|name| name == &caps[1]) {
+                    unresolved.push(caps[1].to_string());
+                }
+            }
+            if !unresolved.is_empty() {
+                bail!("Unresolved variable(s): {}", unresolved.join(", "));
+            }
+        }
+        Ok(result)
+    }
+
     pub fn substitute_variables(&self, text: &str) -> String {
-        let var_regex = Regex::new(r"\{\{(\w+)\}\}").unwrap();
-        let env_regex = Regex::new(r"\$\{([^:}]+)(?::([^}]*))?\}").unwrap();
+        self.substitute_with(text, &HashMap::new())
+    }
+
+    /// Substitute using `overlay` ahead of the context's own variables.
+    fn substitute_with(&self, text: &str, overlay: &HashMap<String, String>) -> String {
+        let lookup = |name: &str| {
+            overlay
+                .get(name)
+                .or_else(|| self.vars.get(name))
+                .cloned()
+                .or_else(|| dynamic_value(name))
+        };
+        let var_regex = var_regex();
+        let env_regex = env_regex();
 
         let mut result = text.to_string();
 
@@ -59,7 +220,7 @@ impl VariableContext {
         result = var_regex
             .replace_all(&result, |caps: &regex::Captures| {
                 let var_name = &caps[1];
-                self.vars.get(var_name).cloned().unwrap_or_else(|| {
+                lookup(var_name).unwrap_or_else(|| {
                     format!("{{{{{}}}}}", var_name) // Return original if not found
                 })
             })
@@ -71,12 +232,10 @@ impl VariableContext {
                 let var_name = &caps[1];
                 let default_value = caps.get(2).map(|m| m.as_str()).unwrap_or("");
 
-                env::var(var_name).unwrap_or_else(|_| {
-                    self.vars
-                        .get(var_name)
-                        .cloned()
-                        .unwrap_or_else(|| default_value.to_string())
-                })
+                env::var(var_name)
+                    .ok()
+                    .or_else(|| lookup(var_name))
+                    .unwrap_or_else(|| default_value.to_string())
             })
             .to_string();
 
@@ -86,6 +245,62 @@ impl VariableContext {
     pub fn set(&mut self, key: String, value: String) {
         self.vars.insert(key, value);
     }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.vars.get(key).map(String::as_str)
+    }
+
+    /// Set a built-in variable unless one with that name is already defined.
+    pub fn set_default(&mut self, key: &str, value: &str) {
+        if !self.vars.contains_key(key) {
+            self.vars.insert(key.to_string(), value.to_string());
+        }
+    }
+}
+
+// Compiled once: substitution runs on every request, and load tests issue
+// thousands of them
+fn var_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\{\{(\$?[\w.]+)\}\}").unwrap())
+}
+
+fn env_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\$\{([^:}]+)(?::([^}]*))?\}").unwrap())
+}
+
+/// Variables `text` needs from outside: every `{{name}}` but the dynamic
+/// ones, and `${NAME}` references that have no default.
+pub fn referenced_variables(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = var_regex()
+        .captures_iter(text)
+        .map(|caps| caps[1].to_string())
+        .filter(|name| !DYNAMIC_VARIABLES.contains(&name.as_str()))
+        .collect();
+    names.extend(
+        env_regex()
+            .captures_iter(text)
+            .filter(|caps| caps.get(2).is_none())
+            .map(|caps| caps[1].to_string()),
+    );
+    names
+}
+
+/// Variables `value` refers to that may come from other `vars`. `${NAME}`
+/// references are skipped when the environment already provides `NAME`.
+fn config_references(value: &str) -> Vec<String> {
+    let mut references: Vec<String> = var_regex()
+        .captures_iter(value)
+        .map(|caps| caps[1].to_string())
+        .collect();
+    references.extend(
+        env_regex()
+            .captures_iter(value)
+            .map(|caps| caps[1].to_string())
+            .filter(|name| env::var(name).is_err()),
+    );
+    references
 }
 
 #[cfg(test)]
@@ -102,6 +317,153 @@ mod tests {
         assert_eq!(result, "https://api.example.com/users/123");
     }
 
+    #[test]
+    fn test_dotted_variable_substitution() {
+        // Nested JSON dataset fields are flattened to dot keys
+        let mut ctx = VariableContext::new();
+        ctx.set("user.name".to_string(), "Alice".to_string());
+
+        let result = ctx.substitute_variables(r#"{"name": "{{user.name}}"}"#);
+        assert_eq!(result, r#"{"name": "Alice"}"#);
+    }
+
+    fn config_vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_config_vars_resolve_in_any_order_and_depth() {
+        let vars = config_vars(&[
+            ("usersUrl", "{{apiUrl}}/users"),
+            ("apiUrl", "{{baseUrl}}/v2"),
+            (
+                "baseUrl",
+                "${RIVET_TEST_UNSET_HOST:https://api.example.com}",
+            ),
+        ]);
+        let ctx = VariableContext::new()
+            .with_config_vars(Some(&vars))
+            .unwrap();
+
+        assert_eq!(ctx.vars["usersUrl"], "https://api.example.com/v2/users");
+        assert_eq!(ctx.vars["apiUrl"], "https://api.example.com/v2");
+    }
+
+    #[test]
+    fn test_config_var_self_reference_reads_previous_value() {
+        let vars = config_vars(&[("region", "${region:eu}"), ("host", "{{host}}.internal")]);
+        let mut ctx = VariableContext::new();
+        ctx.set("host".to_string(), "db".to_string());
+        let ctx = ctx.with_config_vars(Some(&vars)).unwrap();
+
+        assert_eq!(ctx.vars["region"], "eu");
+        assert_eq!(ctx.vars["host"], "db.internal");
+    }
+
+    #[test]
+    fn test_config_var_cycle_is_named() {
+        let vars = config_vars(&[("a", "{{b}}"), ("b", "x-{{c}}"), ("c", "${a}")]);
+        let err = VariableContext::new()
+            .with_config_vars(Some(&vars))
+            .err()
+            .unwrap();
+
+        assert_eq!(err.to_string(), "Variable cycle: a -> b -> c -> a");
+    }
+
+    #[test]
+    fn test_config_var_depth_limit() {
+        let vars: HashMap<_, _> = (0..=MAX_VAR_DEPTH)
+            .map(|i| (format!("v{:02}", i), format!("{{{{v{:02}}}}}", i + 1)))
+            .collect();
+        let err = VariableContext::new()
+            .with_config_vars(Some(&vars))
+            .err()
+            .unwrap();
+
+        assert!(err.to_string().contains("more than 10 levels"), "{}", err);
+    }
+
+    #[test]
+    fn test_strict_resolve_rejects_unresolved() {
+        let mut ctx = VariableContext::new();
+        ctx.set("id".to_string(), "7".to_string());
+        assert_eq!(
+            ctx.resolve("/items/{{id}}/{{missing}}").unwrap(),
+            "/items/7/{{missing}}"
+        );
+
+        let ctx = ctx.with_strict(true);
+        assert_eq!(ctx.resolve("/items/{{id}}").unwrap(), "/items/7");
+        assert_eq!(
+            ctx.resolve("/items/{{missing}}?q={{other}}")
+                .unwrap_err()
+                .to_string(),
+            "Unresolved variable(s): missing, other"
+        );
+    }
+
+    #[test]
+    fn test_redact_secret_values() {
+        let mut ctx = VariableContext::new().with_secrets(["apiToken", "password", "missing"]);
+        ctx.set("apiToken".to_string(), "tok-123".to_string());
+        ctx.set("password".to_string(), "tok-123-extra".to_string());
+        ctx.set("user".to_string(), "alice".to_string());
+
+        assert_eq!(
+            ctx.redact("user=alice token=tok-123 pw=tok-123-extra"),
+            "user=alice token=***** pw=*****"
+        );
+    }
+
+    #[test]
+    fn test_redact_ignores_empty_values() {
+        let mut ctx = VariableContext::new().with_secrets(["apiToken"]);
+        ctx.set("apiToken".to_string(), String::new());
+
+        assert_eq!(ctx.redact("nothing to hide"), "nothing to hide");
+    }
+
+    #[test]
+    fn test_set_default_keeps_user_value() {
+        let mut ctx = VariableContext::new()
+            .with_config_vars(Some(&config_vars(&[("run_id", "mine")])))
+            .unwrap();
+        ctx.set_default("run_id", "generated");
+        ctx.set_default("suite_id", "generated");
+
+        assert_eq!(
+            ctx.substitute_variables("{{run_id}}/{{suite_id}}"),
+            "mine/generated"
+        );
+    }
+
+    #[test]
+    fn test_dynamic_variables() {
+        let mut ctx = VariableContext::new();
+        let text = "{{$uuid}} {{$uuid}} {{$timestamp}} {{$isoTimestamp}} {{$randomInt}}";
+        let result = ctx.substitute_variables(text);
+        let parts: Vec<_> = result.split(' ').collect();
+        assert_eq!(parts[0].len(), 36);
+        assert_ne!(parts[0], parts[1], "each use gets a new value");
+        assert!(parts[2].parse::<i64>().unwrap() > 1_600_000_000);
+        assert!(
+            parts[3].ends_with('Z') && parts[3].contains('T'),
+            "{}",
+            parts[3]
+        );
+        assert!(parts[4].parse::<u32>().unwrap() <= 1000);
+        assert!(referenced_variables(text).is_empty());
+
+        // Unknown names stay, and suite variables win
+        assert_eq!(ctx.substitute_variables("{{$nope}}"), "{{$nope}}");
+        ctx.set("$uuid".to_string(), "fixed".to_string());
+        assert_eq!(ctx.substitute_variables("{{$uuid}}"), "fixed");
+    }
+
     #[test]
     fn test_env_variable_substitution() {
         env::set_var("TEST_VAR", "test_value");
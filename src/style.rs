@@ -0,0 +1,140 @@
+//! Terminal colors. Every module colors output through [`Colorize`] rather
+//! than owo-colors directly, so one switch turns color off for `--no-color`,
+//! `NO_COLOR` and output that isn't a terminal.
+
+use crate::utils::is_interactive;
+use owo_colors::Style;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// On until [`init`] decides for the process.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Decide whether output is colored: not with `--no-color` or a non-empty
+/// `NO_COLOR`, otherwise only when stdout is a terminal or
+/// `CLICOLOR_FORCE` is set.
+pub fn init(no_color: bool) {
+    let enabled = color_wanted(
+        no_color,
+        std::env::var("NO_COLOR").ok().as_deref(),
+        std::env::var("CLICOLOR_FORCE").ok().as_deref(),
+        is_interactive(),
+    );
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn color_wanted(
+    no_color: bool,
+    no_color_env: Option<&str>,
+    force_env: Option<&str>,
+    interactive: bool,
+) -> bool {
+    if no_color || no_color_env.is_some_and(|value| !value.is_empty()) {
+        return false;
+    }
+    interactive || force_env.is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// A value printed in a style when colors are on, and as is otherwise.
+pub struct Painted<T> {
+    value: T,
+    style: Style,
+}
+
+impl<T: fmt::Display> fmt::Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if enabled() {
+            fmt::Display::fmt(&self.style.style(&self.value), f)
+        } else {
+            fmt::Display::fmt(&self.value, f)
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if enabled() {
+            fmt::Debug::fmt(&self.style.style(&self.value), f)
+        } else {
+            fmt::Debug::fmt(&self.value, f)
+        }
+    }
+}
+
+/// The owo-colors styles rivet uses, honoring [`enabled`].
+pub trait Colorize {
+    fn paint(&self, style: Style) -> Painted<&Self> {
+        Painted { value: self, style }
+    }
+
+    fn bold(&self) -> Painted<&Self> {
+        self.paint(Style::new().bold())
+    }
+
+    fn dimmed(&self) -> Painted<&Self> {
+        self.paint(Style::new().dimmed())
+    }
+
+    fn red(&self) -> Painted<&Self> {
+        self.paint(Style::new().red())
+    }
+
+    fn green(&self) -> Painted<&Self> {
+        self.paint(Style::new().green())
+    }
+
+    fn yellow(&self) -> Painted<&Self> {
+        self.paint(Style::new().yellow())
+    }
+
+    fn cyan(&self) -> Painted<&Self> {
+        self.paint(Style::new().cyan())
+    }
+
+    fn bright_white(&self) -> Painted<&Self> {
+        self.paint(Style::new().bright_white())
+    }
+
+    fn bright_blue(&self) -> Painted<&Self> {
+        self.paint(Style::new().bright_blue())
+    }
+
+    fn bright_green(&self) -> Painted<&Self> {
+        self.paint(Style::new().bright_green())
+    }
+
+    fn bright_yellow(&self) -> Painted<&Self> {
+        self.paint(Style::new().bright_yellow())
+    }
+}
+
+impl<T: ?Sized> Colorize for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_wanted() {
+        assert!(color_wanted(false, None, None, true));
+        assert!(!color_wanted(true, None, None, true));
+        assert!(!color_wanted(false, Some("1"), None, true));
+        // An empty NO_COLOR doesn't count
+        assert!(color_wanted(false, Some(""), None, true));
+        assert!(!color_wanted(false, None, None, false));
+        assert!(color_wanted(false, None, Some("1"), false));
+        assert!(!color_wanted(false, None, Some("0"), false));
+        assert!(!color_wanted(false, Some("1"), Some("1"), true));
+    }
+
+    #[test]
+    fn test_painted_keeps_formatting() {
+        // Colors are on unless `init` turned them off
+        assert_eq!(format!("{:>4}", 7.green()), "\u{1b}[32m   7\u{1b}[0m");
+        assert_eq!(format!("{:?}", "x".bold()), "\u{1b}[1m\"x\"\u{1b}[0m");
+    }
+}
@@ -0,0 +1,131 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Narrowest and widest a box gets, border included.
+const MIN_WIDTH: usize = 52;
+const MAX_WIDTH: usize = 102;
+
+/// One line of a box: a styled label shown as is, then text that is cut to
+/// fit the box.
+pub struct BoxLine {
+    label: String,
+    label_width: usize,
+    text: String,
+}
+
+impl BoxLine {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            label: String::new(),
+            label_width: 0,
+            text: text.into(),
+        }
+    }
+
+    /// `styled` is `plain` with colors; only `plain` is measured.
+    pub fn labeled(plain: &str, styled: impl ToString, text: impl Into<String>) -> Self {
+        Self {
+            label: styled.to_string(),
+            label_width: display_width(plain),
+            text: text.into(),
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.label_width + display_width(&self.text)
+    }
+}
+
+/// Columns `text` takes up in a terminal.
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// `text` cut to at most `width` columns, ending in `...` when cut. Cuts
+/// fall between grapheme clusters, so no character is split.
+pub fn truncate(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let budget = width.saturating_sub(3);
+    let mut used = 0;
+    let mut cut = String::new();
+    for grapheme in text.graphemes(true) {
+        let grapheme_width = display_width(grapheme);
+        if used + grapheme_width > budget {
+            break;
+        }
+        used += grapheme_width;
+        cut.push_str(grapheme);
+    }
+    cut.push_str(&"..."[..width.min(3)]);
+    cut
+}
+
+/// The rows of a rounded box titled `title`, sized to its lines within
+/// limits. Every row has the same display width.
+pub fn render_box(title: &str, lines: &[BoxLine]) -> Vec<String> {
+    let title = format!(" {} ", title);
+    let width = lines
+        .iter()
+        .map(|line| line.width() + 6)
+        .max()
+        .unwrap_or(0)
+        .max(display_width(&title) + 6)
+        .clamp(MIN_WIDTH, MAX_WIDTH);
+    // Columns between "│ " and " │"
+    let inner = width - 4;
+
+    let rule = width - 4 - display_width(&title);
+    let mut rows = vec![format!(
+        "╭─{}{}{}─╮",
+        "─".repeat(rule / 2),
+        title,
+        "─".repeat(rule - rule / 2)
+    )];
+    for line in lines {
+        let text = truncate(&line.text, inner.saturating_sub(line.label_width));
+        let used = line.label_width + display_width(&text);
+        rows.push(format!(
+            "│ {}{}{} │",
+            line.label,
+            text,
+            " ".repeat(inner.saturating_sub(used))
+        ));
+    }
+    rows.push(format!("╰{}╯", "─".repeat(width - 2)));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_keeps_graphemes_whole() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("abcdefghij", 8), "abcde...");
+        // Two columns per CJK character; a half-fitting one is dropped
+        assert_eq!(truncate("東京都港区", 8), "東京...");
+        // A base letter and its combining accent stay together
+        assert_eq!(truncate(&"e\u{301}".repeat(6), 5), "e\u{301}e\u{301}...");
+        assert_eq!(truncate("👍👍👍👍", 7), "👍👍...");
+    }
+
+    #[test]
+    fn test_render_box_rows_line_up() {
+        let rows = render_box(
+            "Test",
+            &[
+                BoxLine::new("x-emoji: 🚀🎉"),
+                BoxLine::labeled("key=", "\u{1b}[36mkey\u{1b}[0m=", "値"),
+                BoxLine::new("y".repeat(300)),
+            ],
+        );
+        let widths: Vec<usize> = rows
+            .iter()
+            .map(|row| display_width(&row.replace("\u{1b}[36m", "").replace("\u{1b}[0m", "")))
+            .collect();
+        assert!(widths.iter().all(|w| *w == MAX_WIDTH), "{:?}", widths);
+    }
+}
@@ -1,2 +1,4 @@
+pub mod layout;
 pub mod request_box;
 pub mod response_box;
+pub mod screen;
@@ -1,84 +1,91 @@
-use owo_colors::OwoColorize;
+use crate::style::Colorize;
+use crate::ui::layout::{render_box, BoxLine};
 
-pub fn print_request_box(method: &str, url: &str, headers: &[String]) {
-    // Calculate the optimal box width based on content
-    let method_url_line = format!("{} {}", method, url);
-    let mut max_width = method_url_line.len() + 4; // Add padding
-
-    // Check header lengths
-    for header in headers {
-        let display_header = if header.to_lowercase().contains("authorization") {
-            let parts: Vec<&str> = header.splitn(2, ':').collect();
-            if parts.len() == 2 {
-                format!("{}: ****", parts[0].trim())
-            } else {
-                header.clone()
-            }
-        } else {
-            header.clone()
-        };
-        max_width = max_width.max(display_header.len() + 4);
+/// Print the request summary box. `fields` are form fields shown below the headers.
+pub fn print_request_box(method: &str, url: &str, headers: &[String], fields: &[(String, String)]) {
+    for row in render_request_box(method, url, headers, fields) {
+        println!("{}", row);
     }
+    println!();
+}
 
-    // Ensure minimum width and reasonable maximum
-    max_width = max_width.clamp(50, 100);
-
-    // Create the top border
-    let title = " Request ";
-    let title_padding = (max_width - title.len() - 2) / 2;
-    let remaining_padding = max_width - title.len() - 2 - title_padding;
-
-    println!(
-        "╭─{}{}{}─╮",
-        "─".repeat(title_padding),
-        title,
-        "─".repeat(remaining_padding)
-    );
-
+fn render_request_box(
+    method: &str,
+    url: &str,
+    headers: &[String],
+    fields: &[(String, String)],
+) -> Vec<String> {
     // Method and URL line
-    let method_colored = method.bright_green();
-    let content_line = format!("{} {}", method_colored, url);
-    let content_plain = format!("{} {}", method, url);
-    let padding = if max_width > content_plain.len() + 2 {
-        max_width - content_plain.len() - 2
-    } else {
-        0
-    };
-    println!("│ {}{} │", content_line, " ".repeat(padding));
+    let mut lines = vec![BoxLine::labeled(
+        &format!("{} ", method),
+        format!("{} ", method.bright_green()),
+        url,
+    )];
 
-    // Header lines
     for header in headers {
-        let display_header = if header.to_lowercase().contains("authorization") {
-            let parts: Vec<&str> = header.splitn(2, ':').collect();
-            if parts.len() == 2 {
-                format!("{}: {}", parts[0].trim(), "****".dimmed())
-            } else {
-                header.clone()
+        let line = if header.to_lowercase().contains("authorization") {
+            match header.split_once(':') {
+                Some((name, _)) => BoxLine::labeled(
+                    &format!("{}: ****", name.trim()),
+                    format!("{}: {}", name.trim(), "****".dimmed()),
+                    "",
+                ),
+                None => BoxLine::new(header.as_str()),
             }
         } else {
-            header.clone()
+            BoxLine::new(header.as_str())
         };
+        lines.push(line);
+    }
 
-        let plain_header = if header.to_lowercase().contains("authorization") {
-            let parts: Vec<&str> = header.splitn(2, ':').collect();
-            if parts.len() == 2 {
-                format!("{}: ****", parts[0].trim())
-            } else {
-                header.clone()
-            }
+    for (key, value) in fields {
+        let lower = key.to_lowercase();
+        let line = if lower.contains("password") || lower.contains("secret") {
+            BoxLine::labeled(
+                &format!("{}=****", key),
+                format!("{}={}", key.cyan(), "****".dimmed()),
+                "",
+            )
         } else {
-            header.clone()
+            BoxLine::labeled(
+                &format!("{}=", key),
+                format!("{}=", key.cyan()),
+                value.as_str(),
+            )
         };
+        lines.push(line);
+    }
 
-        let padding = if max_width > plain_header.len() + 2 {
-            max_width - plain_header.len() - 2
-        } else {
-            0
-        };
-        println!("│ {}{} │", display_header, " ".repeat(padding));
+    render_box("Request", &lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::layout::display_width;
+
+    fn plain(row: &str) -> String {
+        regex::Regex::new("\u{1b}\\[[0-9;]*m")
+            .unwrap()
+            .replace_all(row, "")
+            .to_string()
     }
 
-    // Bottom border
-    println!("╰{}╯", "─".repeat(max_width));
-    println!();
+    #[test]
+    fn test_request_box_aligns_wide_characters() {
+        let headers = vec![
+            "X-Emoji: 🦀 rust".to_string(),
+            "X-Combining: re\u{301}sume\u{301}".to_string(),
+            "Authorization: Bearer 秘密".to_string(),
+            format!("X-Long: {}", "👍".repeat(70)),
+        ];
+        let fields = vec![("名前".to_string(), "山田太郎".to_string())];
+        let rows = render_request_box("GET", "https://例え.jp/検索?q=日本語", &headers, &fields);
+
+        let width = display_width(&plain(&rows[0]));
+        for row in &rows {
+            assert_eq!(display_width(&plain(row)), width, "{}", row);
+        }
+        assert!(!rows.iter().any(|row| row.contains("秘密")));
+    }
 }
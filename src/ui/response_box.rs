@@ -1,40 +1,29 @@
-use owo_colors::OwoColorize;
+use crate::style::Colorize;
+use crate::ui::layout::{render_box, BoxLine};
 use reqwest::header::HeaderMap;
 use std::time::Duration;
 
-pub fn print_response_box(status: &str, duration: Duration, body_size: usize, headers: &HeaderMap) {
-    // Calculate optimal width based on content
-    let status_line = format!(
-        "{} • {}ms • {}",
-        status,
-        duration.as_millis(),
-        format_size(body_size)
-    );
-    let mut max_width = status_line.len() + 4; // Add padding
-
-    // Check header lengths
-    for (name, value) in headers {
-        if let Ok(value_str) = value.to_str() {
-            let header_line = format!("{}: {}", name.as_str(), value_str);
-            max_width = max_width.max(header_line.len() + 4);
-        }
+/// `body_size` is the body as shown; `wire_size`, the body as received, is
+/// shown too when it differs, e.g. for a decoded gzip body.
+pub fn print_response_box(
+    status: &str,
+    duration: Duration,
+    body_size: usize,
+    wire_size: usize,
+    headers: &HeaderMap,
+) {
+    for row in render_response_box(status, duration, body_size, wire_size, headers) {
+        println!("{}", row);
     }
+}
 
-    // Ensure minimum width and reasonable maximum
-    max_width = max_width.clamp(50, 100);
-
-    // Create the top border with title
-    let title = " Response ";
-    let title_padding = (max_width - title.len() - 2) / 2;
-    let remaining_padding = max_width - title.len() - 2 - title_padding;
-
-    println!(
-        "╭─{}{}{}─╮",
-        "─".repeat(title_padding),
-        title,
-        "─".repeat(remaining_padding)
-    );
-
+fn render_response_box(
+    status: &str,
+    duration: Duration,
+    body_size: usize,
+    wire_size: usize,
+    headers: &HeaderMap,
+) -> Vec<String> {
     // Status line with colors
     let status_colored = if status.starts_with("2") {
         status.green().to_string()
@@ -45,53 +34,29 @@ pub fn print_response_box(status: &str, duration: Duration, body_size: usize, he
     };
 
     let duration_ms_str = format!("{}ms", duration.as_millis());
-    let size_str_plain = format_size(body_size);
-    let duration_ms = duration_ms_str.dimmed();
-    let size_str = size_str_plain.dimmed();
-    let status_display = format!("{} • {} • {}", status_colored, duration_ms, size_str);
-    let status_plain = format!("{} • {} • {}", status, duration.as_millis(), size_str_plain);
-    let padding = if max_width > status_plain.len() + 2 {
-        max_width - status_plain.len() - 2
-    } else {
-        0
-    };
-
-    println!("│ {}{} │", status_display, " ".repeat(padding));
+    let mut size_str_plain = format_size(body_size);
+    if wire_size != body_size {
+        size_str_plain.push_str(&format!(" ({} on the wire)", format_size(wire_size)));
+    }
+    let status_display = format!(
+        "{} • {} • {}",
+        status_colored,
+        duration_ms_str.dimmed(),
+        size_str_plain.dimmed()
+    );
+    let status_plain = format!("{} • {} • {}", status, duration_ms_str, size_str_plain);
+    let mut lines = vec![BoxLine::labeled(&status_plain, status_display, "")];
 
-    // Header lines
+    // Header lines; values aren't always ASCII, and long ones are cut to fit
     for (name, value) in headers {
-        if let Ok(value_str) = value.to_str() {
-            let header_name_str = name.as_str();
-            let header_name = header_name_str.dimmed();
-            let header_line = format!("{}: {}", header_name, value_str);
-            let plain_line = format!("{}: {}", name.as_str(), value_str);
-
-            // Truncate if too long
-            if plain_line.len() > max_width - 4 {
-                let name_str = name.as_str();
-                let truncated_value =
-                    &value_str[..value_str.len().min(max_width - name_str.len() - 8)];
-                let truncated_display = format!("{}: {}...", header_name, truncated_value);
-                let truncated_plain_len = name_str.len() + truncated_value.len() + 5; // ":" + "..."
-                let padding = if max_width > truncated_plain_len + 2 {
-                    max_width - truncated_plain_len - 2
-                } else {
-                    0
-                };
-                println!("│ {}{} │", truncated_display, " ".repeat(padding));
-            } else {
-                let padding = if max_width > plain_line.len() + 2 {
-                    max_width - plain_line.len() - 2
-                } else {
-                    0
-                };
-                println!("│ {}{} │", header_line, " ".repeat(padding));
-            }
-        }
+        lines.push(BoxLine::labeled(
+            &format!("{}: ", name),
+            format!("{}: ", name.as_str().dimmed()),
+            String::from_utf8_lossy(value.as_bytes()),
+        ));
     }
 
-    // Bottom border
-    println!("╰{}╯", "─".repeat(max_width));
+    render_box("Response", &lines)
 }
 
 fn format_size(bytes: usize) -> String {
@@ -103,3 +68,52 @@ fn format_size(bytes: usize) -> String {
         format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::layout::display_width;
+    use reqwest::header::HeaderValue;
+
+    fn plain(row: &str) -> String {
+        regex::Regex::new("\u{1b}\\[[0-9;]*m")
+            .unwrap()
+            .replace_all(row, "")
+            .to_string()
+    }
+
+    #[test]
+    fn test_response_box_aligns_wide_characters() {
+        let mut headers = HeaderMap::new();
+        for (name, value) in [
+            ("x-emoji", "ship it 🚀🎉"),
+            ("x-cjk", "東京都港区六本木"),
+            ("x-combining", "cafe\u{301} na\u{303}o"),
+            // Long enough to be cut, with a multi-byte character at the cut
+            ("x-long", &"界".repeat(80)),
+        ] {
+            headers.insert(name, HeaderValue::from_bytes(value.as_bytes()).unwrap());
+        }
+
+        let rows = render_response_box("200 OK", Duration::from_millis(42), 2048, 2048, &headers);
+        assert_eq!(rows.len(), headers.len() + 3);
+        let width = display_width(&plain(&rows[0]));
+        for row in &rows {
+            assert_eq!(display_width(&plain(row)), width, "{}", row);
+        }
+        assert!(rows.iter().any(|row| row.contains("...")));
+    }
+
+    #[test]
+    fn test_response_box_shows_wire_size_of_decoded_body() {
+        let headers = HeaderMap::new();
+        let rows = render_response_box("200 OK", Duration::from_millis(5), 2048, 512, &headers);
+        assert!(
+            plain(&rows[1]).contains("2.0 KB (512 B on the wire)"),
+            "{}",
+            rows[1]
+        );
+        let rows = render_response_box("200 OK", Duration::from_millis(5), 512, 512, &headers);
+        assert!(!plain(&rows[1]).contains("on the wire"));
+    }
+}
@@ -0,0 +1,45 @@
+//! The alternate screen full-screen views such as the perf dashboard draw
+//! on, tracked so a forced exit can hand the terminal back.
+
+use crossterm::{cursor, execute, queue, style, terminal};
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// Whether a view has the terminal. Drawing holds the lock, so nothing is
+/// drawn once it's restored.
+static ACTIVE: Mutex<bool> = Mutex::new(false);
+
+/// Switch to the alternate screen and hide the cursor.
+pub fn enter() -> io::Result<()> {
+    let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+    *active = true;
+    Ok(())
+}
+
+/// Hand the terminal back if a view has it.
+pub fn restore_terminal() {
+    let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    if *active {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        *active = false;
+    }
+}
+
+/// Replace the screen with `lines`, unless the terminal has been restored.
+pub fn draw(lines: &[String]) -> io::Result<()> {
+    let active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    if !*active {
+        return Ok(());
+    }
+    let mut stdout = io::stdout();
+    queue!(
+        stdout,
+        cursor::MoveTo(0, 0),
+        terminal::Clear(terminal::ClearType::All)
+    )?;
+    for (row, line) in lines.iter().enumerate() {
+        queue!(stdout, cursor::MoveTo(0, row as u16), style::Print(line))?;
+    }
+    stdout.flush()
+}
@@ -0,0 +1,314 @@
+use std::path::Path;
+use std::process::Output;
+use tempfile::TempDir;
+
+/// Run `rivet config ...` with `home` as HOME from the directory `cwd`.
+fn config(home: &Path, cwd: &Path, args: &[&str]) -> Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .arg("config")
+        .args(args)
+        .current_dir(cwd)
+        .env("HOME", home)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run rivet")
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_config_set_and_get() {
+    let home = TempDir::new().unwrap();
+    let cwd = TempDir::new().unwrap();
+
+    assert_eq!(
+        stdout(&config(
+            home.path(),
+            cwd.path(),
+            &["get", "reports.default_template"]
+        )),
+        "compact\n"
+    );
+
+    stdout(&config(
+        home.path(),
+        cwd.path(),
+        &["set", "reports.default_template", "detailed"],
+    ));
+    stdout(&config(
+        home.path(),
+        cwd.path(),
+        &["set", "defaults.report", "html, junit"],
+    ));
+    stdout(&config(
+        home.path(),
+        cwd.path(),
+        &["set", "reports.auto_open_browser", "false"],
+    ));
+
+    let saved: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(home.path().join(".rivet/config.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(saved["reports"]["default_template"], "detailed");
+    assert_eq!(
+        saved["defaults"]["report"],
+        serde_json::json!(["html", "junit"])
+    );
+    assert_eq!(saved["reports"]["auto_open_browser"], false);
+
+    let list = stdout(&config(home.path(), cwd.path(), &["list"]));
+    assert!(list.contains("defaults.report = html,junit"), "{}", list);
+    assert!(
+        list.contains("reports.auto_open_browser = false"),
+        "{}",
+        list
+    );
+
+    // An empty value unsets optional settings
+    stdout(&config(
+        home.path(),
+        cwd.path(),
+        &["set", "proxy", "http://proxy:3128"],
+    ));
+    assert_eq!(
+        stdout(&config(home.path(), cwd.path(), &["get", "proxy"])),
+        "http://proxy:3128\n"
+    );
+    stdout(&config(home.path(), cwd.path(), &["set", "proxy", ""]));
+    assert_eq!(
+        stdout(&config(home.path(), cwd.path(), &["get", "proxy"])),
+        "\n"
+    );
+}
+
+#[test]
+fn test_config_rejects_bad_keys_and_values() {
+    let home = TempDir::new().unwrap();
+    let cwd = TempDir::new().unwrap();
+
+    for (args, message) in [
+        (
+            &["set", "reports.default_templte", "compact"][..],
+            "did you mean `reports.default_template`?",
+        ),
+        (&["get", "report.auto_open_browser"][..], "did you mean"),
+        (
+            &["set", "reports.auto_open_browser", "maybe"][..],
+            "expects true or false",
+        ),
+        (
+            &["set", "defaults.report", "html,pdf"][..],
+            "`pdf` is not a valid value",
+        ),
+    ] {
+        let output = config(home.path(), cwd.path(), args);
+        assert!(!output.status.success(), "{:?} should fail", args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(message), "{:?}: {}", args, stderr);
+    }
+}
+
+#[test]
+fn test_project_config_overrides_home() {
+    let home = TempDir::new().unwrap();
+    let cwd = TempDir::new().unwrap();
+
+    stdout(&config(
+        home.path(),
+        cwd.path(),
+        &["set", "reports.default_template", "chatty"],
+    ));
+    stdout(&config(
+        home.path(),
+        cwd.path(),
+        &["set", "--project", "reports.default_template", "simple"],
+    ));
+
+    // The project file only holds what it overrides
+    let project: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(cwd.path().join(".rivet/config.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        project,
+        serde_json::json!({"reports": {"default_template": "simple"}})
+    );
+
+    let reports = stdout(&config(home.path(), cwd.path(), &["get", "reports"]));
+    assert!(
+        reports.contains("reports.default_template = simple"),
+        "{}",
+        reports
+    );
+    assert!(
+        reports.contains("reports.auto_open_browser = true"),
+        "{}",
+        reports
+    );
+
+    // Elsewhere only the home config applies
+    let elsewhere = TempDir::new().unwrap();
+    assert_eq!(
+        stdout(&config(
+            home.path(),
+            elsewhere.path(),
+            &["get", "reports.default_template"]
+        )),
+        "chatty\n"
+    );
+
+    let path = stdout(&config(home.path(), cwd.path(), &["path", "--project"]));
+    assert!(path.trim().ends_with(".rivet/config.json"), "{}", path);
+    let path = stdout(&config(home.path(), cwd.path(), &["path"]));
+    assert!(Path::new(path.trim()).starts_with(home.path()), "{}", path);
+}
+
+#[test]
+fn test_project_config_with_bad_setting_is_ignored_with_warning() {
+    let home = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    std::fs::write(
+        repo.path().join("rivet.toml"),
+        "[defaults]\nparallel = \"four\"\n",
+    )
+    .unwrap();
+
+    let output = config(home.path(), repo.path(), &["get", "defaults.parallel"]);
+    assert_eq!(stdout(&output), "\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Ignoring"), "{}", stderr);
+    assert!(stderr.contains("rivet.toml"), "{}", stderr);
+    assert!(stderr.contains("invalid type"), "{}", stderr);
+
+    // The old reports.default_formats key isn't a setting
+    let output = config(
+        home.path(),
+        repo.path(),
+        &["set", "reports.default_formats", "json"],
+    );
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_project_toml_found_from_subdirectory() {
+    let home = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    let sub = repo.path().join("tests/api");
+    std::fs::create_dir_all(&sub).unwrap();
+    std::fs::write(
+        repo.path().join("rivet.toml"),
+        "[defaults]\nparallel = 4\n\n[reports]\ndefault_template = \"simple\"\n",
+    )
+    .unwrap();
+
+    stdout(&config(
+        home.path(),
+        &sub,
+        &["set", "reports.auto_open_browser", "false"],
+    ));
+    let list = stdout(&config(home.path(), &sub, &["list"]));
+    let toml_path = repo.path().join("rivet.toml");
+    for line in [
+        format!(
+            "reports.default_template = simple (project: {})",
+            toml_path.display()
+        ),
+        format!("defaults.parallel = 4 (project: {})", toml_path.display()),
+        format!(
+            "reports.auto_open_browser = false (user: {})",
+            home.path().join(".rivet/config.json").display()
+        ),
+        "defaults.env =  (default)".to_string(),
+    ] {
+        assert!(list.contains(&line), "missing `{}` in:\n{}", line, list);
+    }
+
+    // --project edits the file that was found, keeping it TOML
+    stdout(&config(
+        home.path(),
+        &sub,
+        &["set", "--project", "defaults.env", "staging"],
+    ));
+    stdout(&config(
+        home.path(),
+        &sub,
+        &["set", "--project", "defaults.parallel", ""],
+    ));
+    let toml = std::fs::read_to_string(&toml_path).unwrap();
+    assert!(toml.contains("env = \"staging\""), "{}", toml);
+    assert!(!toml.contains("parallel"), "{}", toml);
+}
+
+#[test]
+fn test_run_flags_default_from_config() {
+    let home = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    std::fs::write(
+        repo.path().join("ping.rivet.yaml"),
+        "name: Ping\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: \"{{baseUrl}}/ping\"\n",
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+            .args(["run", "ping.rivet.yaml", "--no-open"])
+            .args(args)
+            .current_dir(repo.path())
+            .env("HOME", home.path())
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // Built-in default, then user config, then project config, then the flag
+    assert!(run(&[]).contains("Parallel suites: 1"));
+    stdout(&config(
+        home.path(),
+        repo.path(),
+        &["set", "defaults.parallel", "5"],
+    ));
+    assert!(run(&[]).contains("Parallel suites: 5"));
+    stdout(&config(
+        home.path(),
+        repo.path(),
+        &["set", "--project", "defaults.parallel", "3"],
+    ));
+    stdout(&config(
+        home.path(),
+        repo.path(),
+        &[
+            "set",
+            "--project",
+            "defaults.base_url",
+            "http://127.0.0.1:9",
+        ],
+    ));
+    stdout(&config(
+        home.path(),
+        repo.path(),
+        &["set", "--project", "defaults.report", "json"],
+    ));
+    let output = run(&[]);
+    assert!(output.contains("Parallel suites: 3"), "{}", output);
+    assert!(
+        output.contains("Base URL: http://127.0.0.1:9"),
+        "{}",
+        output
+    );
+    assert!(run(&["--parallel-suites", "2"]).contains("Parallel suites: 2"));
+
+    let reports: Vec<_> = std::fs::read_dir(repo.path().join("reports"))
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    assert!(!reports.is_empty());
+}
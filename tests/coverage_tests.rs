@@ -0,0 +1,235 @@
+use anyhow::Result;
+use rivet::commands::coverage::{handle_coverage, path_matches, CoverageOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const SPEC: &str = r#"
+openapi: 3.0.0
+info:
+  title: Coverage API
+  version: 1.0.0
+servers:
+  - url: https://api.test.com/v1
+paths:
+  /users:
+    get:
+      operationId: listUsers
+      tags: [users]
+      responses:
+        '200':
+          description: OK
+    post:
+      operationId: createUser
+      tags: [users]
+      responses:
+        '201':
+          description: Created
+  /users/{id}:
+    get:
+      operationId: getUser
+      tags: [users]
+      responses:
+        '200':
+          description: OK
+  /health:
+    get:
+      operationId: health
+      responses:
+        '200':
+          description: OK
+"#;
+
+const RUN_REPORT: &str = r#"{
+  "timestamp": "2024-01-01T00:00:00Z",
+  "summary": {"total": 2, "passed": 2, "failed": 0, "duration": {"secs": 0, "nanos": 0}, "success_rate": 100.0},
+  "suites": [{
+    "name": "users.rivet.yaml",
+    "duration": {"secs": 0, "nanos": 0},
+    "summary": {"total": 2, "passed": 2, "failed": 0, "duration": {"secs": 0, "nanos": 0}, "success_rate": 100.0},
+    "tests": [
+      {"name": "List users", "status": "Passed", "duration": {"secs": 0, "nanos": 0}, "error": null, "response_status": 200,
+       "request_method": "GET", "request_url": "https://api.test.com/v1/users?limit=10"},
+      {"name": "Get user", "status": "Passed", "duration": {"secs": 0, "nanos": 0}, "error": null, "response_status": 200,
+       "request_method": "GET", "request_url": "https://api.test.com/v1/users/42"}
+    ]
+  }]
+}"#;
+
+fn write_fixtures(dir: &Path) -> Result<(std::path::PathBuf, std::path::PathBuf)> {
+    let spec_path = dir.join("api.yaml");
+    let report_path = dir.join("report.json");
+    fs::write(&spec_path, SPEC)?;
+    fs::write(&report_path, RUN_REPORT)?;
+    Ok((spec_path, report_path))
+}
+
+#[test]
+fn test_path_template_matching() {
+    assert!(path_matches("/users/{id}", "/users/42"));
+    assert!(path_matches("/users", "/users/"));
+    assert!(!path_matches("/users/{id}", "/users"));
+    assert!(!path_matches("/users/{id}", "/posts/42"));
+    assert!(!path_matches("/users/{id}/posts", "/users/42/comments"));
+}
+
+#[tokio::test]
+async fn test_coverage_json_output() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (spec, report) = write_fixtures(temp_dir.path())?;
+    let out = temp_dir.path().join("coverage.json");
+
+    handle_coverage(CoverageOptions {
+        spec,
+        from: vec![report],
+        out: Some(out.clone()),
+        min: None,
+        baseline: None,
+        fail_on_regression: false,
+    })
+    .await?;
+
+    let coverage: serde_json::Value = serde_json::from_str(&fs::read_to_string(&out)?)?;
+    assert_eq!(coverage["total"], 4);
+    assert_eq!(coverage["covered"], 2);
+    assert_eq!(coverage["percentage"], 50.0);
+
+    let operations = coverage["operations"].as_array().unwrap();
+    let get_user = operations
+        .iter()
+        .find(|op| op["operation_id"] == "getUser")
+        .unwrap();
+    assert_eq!(get_user["covered"], true);
+    assert_eq!(get_user["covered_by"][0], "Get user");
+
+    let create_user = operations
+        .iter()
+        .find(|op| op["operation_id"] == "createUser")
+        .unwrap();
+    assert_eq!(create_user["covered"], false);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_coverage_min_threshold() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (spec, report) = write_fixtures(temp_dir.path())?;
+
+    let result = handle_coverage(CoverageOptions {
+        spec: spec.clone(),
+        from: vec![report.clone()],
+        out: None,
+        min: Some(80.0),
+        baseline: None,
+        fail_on_regression: false,
+    })
+    .await;
+    assert!(result.is_err());
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("below the required"));
+
+    handle_coverage(CoverageOptions {
+        spec,
+        from: vec![report],
+        out: None,
+        min: Some(50.0),
+        baseline: None,
+        fail_on_regression: false,
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_coverage_html_output() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (spec, report) = write_fixtures(temp_dir.path())?;
+    let out = temp_dir.path().join("coverage.html");
+
+    handle_coverage(CoverageOptions {
+        spec,
+        from: vec![report],
+        out: Some(out.clone()),
+        min: None,
+        baseline: None,
+        fail_on_regression: false,
+    })
+    .await?;
+
+    let html = fs::read_to_string(&out)?;
+    assert!(html.contains("<!DOCTYPE html>"));
+    assert!(html.contains("Rivet Coverage Report"));
+    assert!(html.contains("50.0%"));
+    // Operations grouped by tag, untagged ones under "default"
+    assert!(html.contains(">users<"));
+    assert!(html.contains(">default<"));
+    // Tera HTML-escapes slashes
+    assert!(html.contains("&#x2F;users&#x2F;{id}"));
+    assert!(html.contains("Get user"));
+    assert!(html.contains("Uncovered"));
+    // POST is the only method with no covered operation
+    assert!(html.contains("Methods never exercised"));
+    assert!(html.contains("<span class=\"method\">POST</span>"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_coverage_baseline_diff() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (spec, report) = write_fixtures(temp_dir.path())?;
+
+    // Baseline: health and createUser were covered, getUser was not
+    let baseline = temp_dir.path().join("baseline.json");
+    fs::write(
+        &baseline,
+        r#"{
+  "spec": "api.yaml", "total": 4, "covered": 3, "percentage": 75.0,
+  "operations": [
+    {"method": "GET", "path": "/users", "operation_id": "listUsers", "tags": ["users"], "covered": true, "covered_by": ["List users"]},
+    {"method": "POST", "path": "/users", "operation_id": "createUser", "tags": ["users"], "covered": true, "covered_by": ["Create user"]},
+    {"method": "GET", "path": "/users/{id}", "operation_id": "getUser", "tags": ["users"], "covered": false, "covered_by": []},
+    {"method": "GET", "path": "/health", "operation_id": "health", "tags": [], "covered": true, "covered_by": ["Health"]}
+  ]
+}"#,
+    )?;
+
+    let out = temp_dir.path().join("coverage.json");
+    let result = handle_coverage(CoverageOptions {
+        spec: spec.clone(),
+        from: vec![report.clone()],
+        out: Some(out.clone()),
+        min: None,
+        baseline: Some(baseline.clone()),
+        fail_on_regression: true,
+    })
+    .await;
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("regressed"));
+
+    // The machine-readable output is written before the regression check fails
+    let coverage: serde_json::Value = serde_json::from_str(&fs::read_to_string(&out)?)?;
+    let diff = &coverage["diff"];
+    assert_eq!(diff["baseline_percentage"], 75.0);
+    assert_eq!(diff["percentage_change"], -25.0);
+    assert_eq!(diff["newly_covered"].as_array().unwrap().len(), 1);
+    assert_eq!(diff["newly_covered"][0]["path"], "/users/{id}");
+    assert_eq!(diff["newly_uncovered"].as_array().unwrap().len(), 2);
+
+    // Without --fail-on-regression the diff is informational only
+    handle_coverage(CoverageOptions {
+        spec,
+        from: vec![report],
+        out: None,
+        min: None,
+        baseline: Some(baseline),
+        fail_on_regression: false,
+    })
+    .await?;
+
+    Ok(())
+}
@@ -1,6 +1,12 @@
 use anyhow::Result;
-use rivet::commands::gen::handle_gen;
+use rivet::gen::{generate, GenOptions};
+use rivet::mock::{MockApi, MockServer};
+use rivet::runner::executor::TestResult;
+use rivet::runner::parser::load_test_suite;
+use rivet::runner::TestRunner;
+use rivet::utils::ClientOptions;
 use std::fs;
+use std::time::Duration;
 use tempfile::TempDir;
 
 #[tokio::test]
@@ -72,10 +78,23 @@ paths:
     fs::write(&spec_file, openapi_spec)?;
 
     // Test the generation
-    handle_gen(spec_file, output_dir.clone()).await?;
+    let summary = generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(summary.title, "Test API");
+    assert_eq!(summary.version, "1.0.0");
+    assert_eq!(summary.endpoints, 2);
+    assert_eq!(summary.suites.len(), 3);
+    assert!(summary.warnings.is_empty());
 
     // Verify main config file was created
     let main_config = output_dir.join("rivet.yaml");
+    assert_eq!(summary.config, main_config);
     assert!(main_config.exists(), "Main config file should be created");
 
     let config_content = fs::read_to_string(&main_config)?;
@@ -159,7 +178,14 @@ paths:
     let spec_file = temp_dir.path().join("response_codes.yaml");
     fs::write(&spec_file, openapi_spec)?;
 
-    handle_gen(spec_file, output_dir.clone()).await?;
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
 
     // Check 201 response
     let create_file = output_dir.join("createresource.yaml");
@@ -203,7 +229,14 @@ paths:
     let spec_file = temp_dir.path().join("no_servers.yaml");
     fs::write(&spec_file, openapi_spec)?;
 
-    handle_gen(spec_file, output_dir.clone()).await?;
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
 
     // Should default to example.com
     let main_config = output_dir.join("rivet.yaml");
@@ -265,7 +298,14 @@ paths:
     let spec_file = temp_dir.path().join("complex.yaml");
     fs::write(&spec_file, openapi_spec)?;
 
-    handle_gen(spec_file, output_dir.clone()).await?;
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
 
     let test_file = output_dir.join("createcomplex.yaml");
     let test_content = fs::read_to_string(&test_file)?;
@@ -280,12 +320,42 @@ paths:
     Ok(())
 }
 
+#[tokio::test]
+async fn test_relative_urls_carry_base_url_in_each_test() -> Result<()> {
+    let spec = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/openapi/bundled.yaml");
+    let temp_dir = TempDir::new()?;
+    let summary = generate(
+        &spec,
+        &GenOptions {
+            out: temp_dir.path().to_path_buf(),
+            relative_urls: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    assert_eq!(summary.suites.len(), 3);
+    let suite: rivet::config::RivetConfig =
+        serde_yaml::from_str(&fs::read_to_string(temp_dir.path().join("getuser.yaml"))?)?;
+    assert_eq!(suite.tests[0].request.url, "/users/{id}");
+    assert_eq!(suite.vars.unwrap()["baseUrl"], "https://api.example.com");
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_nonexistent_openapi_file() {
     let temp_dir = TempDir::new().unwrap();
     let nonexistent = temp_dir.path().join("nonexistent.yaml");
 
-    let result = handle_gen(nonexistent, temp_dir.path().join("output")).await;
+    let result = generate(
+        &nonexistent,
+        &GenOptions {
+            out: temp_dir.path().join("output"),
+            ..Default::default()
+        },
+    )
+    .await;
 
     assert!(result.is_err());
     assert!(result.unwrap_err().to_string().contains("does not exist"));
@@ -297,7 +367,14 @@ async fn test_invalid_openapi_spec() {
     let invalid_file = temp_dir.path().join("invalid.yaml");
     fs::write(&invalid_file, "invalid: yaml: content: [").unwrap();
 
-    let result = handle_gen(invalid_file, temp_dir.path().join("output")).await;
+    let result = generate(
+        &invalid_file,
+        &GenOptions {
+            out: temp_dir.path().join("output"),
+            ..Default::default()
+        },
+    )
+    .await;
 
     assert!(result.is_err());
     assert!(result
@@ -341,7 +418,14 @@ async fn test_json_openapi_spec() -> Result<()> {
     let spec_file = temp_dir.path().join("json_spec.json");
     fs::write(&spec_file, openapi_spec)?;
 
-    handle_gen(spec_file, output_dir.clone()).await?;
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
 
     // Verify it parsed the JSON correctly
     let main_config = output_dir.join("rivet.yaml");
@@ -351,3 +435,474 @@ async fn test_json_openapi_spec() -> Result<()> {
 
     Ok(())
 }
+
+/// A spec whose `/users/current` example is `example`, served from `server`.
+fn contract_spec(server: &str, example: &str) -> String {
+    format!(
+        r#"
+openapi: 3.0.0
+info:
+  title: Contract API
+  version: 1.0.0
+servers:
+  - url: {server}
+paths:
+  /users/current:
+    get:
+      operationId: getCurrentUser
+      responses:
+        '200':
+          description: The signed-in user
+          content:
+            application/json:
+              schema:
+                $ref: '#/components/schemas/User'
+              example: {example}
+components:
+  schemas:
+    User:
+      type: object
+      required: [id, name]
+      properties:
+        id:
+          type: integer
+        name:
+          type: string
+        nickname:
+          type: string
+          nullable: true
+        tags:
+          type: array
+          items:
+            $ref: '#/components/schemas/Tag'
+    Tag:
+      type: string
+"#
+    )
+}
+
+/// Generate a suite with schemas from the contract spec, and run it against
+/// a mock answering with `example`.
+async fn run_generated_contract(example: &str) -> Result<Vec<TestResult>> {
+    let temp_dir = TempDir::new()?;
+    let mock_spec: openapiv3::OpenAPI =
+        serde_yaml::from_str(&contract_spec("http://localhost", example))?;
+    let (addr, _server) =
+        MockServer::new(MockApi::from_spec(&mock_spec).await).serve(([127, 0, 0, 1], 0).into())?;
+
+    let spec_file = temp_dir.path().join("contract.yaml");
+    let good_example = r#"{"id": 1, "name": "Ada", "nickname": null, "tags": ["admin"]}"#;
+    fs::write(
+        &spec_file,
+        contract_spec(&format!("http://{}", addr), good_example),
+    )?;
+    let output_dir = temp_dir.path().join("output");
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            with_schemas: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let suite = fs::read_to_string(output_dir.join("getcurrentuser.yaml"))?;
+    let schema_path = output_dir.join("schemas").join("getcurrentuser.json");
+    assert!(
+        suite.contains("schema: schemas/getcurrentuser.json"),
+        "{}",
+        suite
+    );
+    let schema: serde_json::Value = serde_json::from_str(&fs::read_to_string(&schema_path)?)?;
+    assert_eq!(schema["properties"]["id"]["type"], "integer");
+    assert_eq!(
+        schema["properties"]["nickname"]["type"],
+        serde_json::json!(["string", "null"])
+    );
+    assert_eq!(schema["properties"]["tags"]["items"]["type"], "string");
+
+    let runner = TestRunner::new(
+        Duration::from_secs(5),
+        1,
+        false,
+        None,
+        true,
+        &ClientOptions::default(),
+    )?;
+    let suites = runner
+        .run_tests(&output_dir.join("getcurrentuser.yaml"), None)
+        .await?;
+    Ok(suites.into_iter().flat_map(|suite| suite.results).collect())
+}
+
+#[tokio::test]
+async fn test_generated_schemas_check_responses() -> Result<()> {
+    let results =
+        run_generated_contract(r#"{"id": 1, "name": "Ada", "nickname": null, "tags": []}"#).await?;
+    assert_eq!(results.len(), 1);
+    assert!(results[0].passed, "{:?}", results[0].error);
+
+    let results = run_generated_contract(r#"{"id": "1", "name": "Ada", "tags": [7]}"#).await?;
+    assert!(!results[0].passed);
+    let error = results[0].error.as_deref().unwrap();
+    assert!(
+        error.contains("Response does not match schema"),
+        "{}",
+        error
+    );
+    assert!(
+        error.contains(r#"/id: "1" is not of type "integer""#),
+        "{}",
+        error
+    );
+    assert!(
+        error.contains(r#"/tags/0: 7 is not of type "string""#),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schemas_are_only_written_on_request() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let spec_file = temp_dir.path().join("contract.yaml");
+    fs::write(
+        &spec_file,
+        contract_spec("https://api.test.com", r#"{"id": 1, "name": "Ada"}"#),
+    )?;
+    let output_dir = temp_dir.path().join("output");
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    assert!(!output_dir.join("schemas").exists());
+    let suite = fs::read_to_string(output_dir.join("getcurrentuser.yaml"))?;
+    assert!(suite.contains("schema: null"), "{}", suite);
+    Ok(())
+}
+
+const MULTI_SERVER_SPEC: &str = r#"
+openapi: 3.0.0
+info:
+  title: Regional API
+  version: 1.0.0
+servers:
+  - url: https://{region}.api.example.com/{basePath}
+    description: Production
+    variables:
+      region:
+        enum: [eu, us]
+        default: ""
+      basePath:
+        default: v2
+  - url: https://staging.example.com/v2
+    description: Staging
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      responses:
+        '200':
+          description: Pets
+"#;
+
+/// Generate from the multi-server spec with `--server`, returning the main
+/// config and the test file.
+async fn generate_for_server(server: Option<&str>) -> Result<(String, String)> {
+    let temp_dir = TempDir::new()?;
+    let spec_file = temp_dir.path().join("regional.yaml");
+    fs::write(&spec_file, MULTI_SERVER_SPEC)?;
+    let output_dir = temp_dir.path().join("output");
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            server: server.map(str::to_string),
+            ..Default::default()
+        },
+    )
+    .await?;
+    Ok((
+        fs::read_to_string(output_dir.join("rivet.yaml"))?,
+        fs::read_to_string(output_dir.join("listpets.yaml"))?,
+    ))
+}
+
+#[tokio::test]
+async fn test_server_variables_are_expanded() -> Result<()> {
+    let (config, test) = generate_for_server(None).await?;
+    // The empty default falls back to the first enum value
+    assert!(
+        test.contains("url: https://eu.api.example.com/v2/pets"),
+        "{}",
+        test
+    );
+    let config: serde_yaml::Value = serde_yaml::from_str(&config)?;
+    assert_eq!(config["vars"]["region"], "eu");
+    assert_eq!(config["vars"]["basePath"], "v2");
+    assert_eq!(
+        config["vars"]["baseUrl"],
+        "https://{{region}}.api.example.com/{{basePath}}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_flag_picks_a_server() -> Result<()> {
+    let (config, test) = generate_for_server(Some("1")).await?;
+    assert!(test.contains("url: https://staging.example.com/v2/pets"));
+    assert!(config.contains("baseUrl: https://staging.example.com/v2"));
+
+    let (_, test) = generate_for_server(Some("https://staging.example.com/v2")).await?;
+    assert!(test.contains("url: https://staging.example.com/v2/pets"));
+
+    // Any other URL is used as is
+    let (_, test) = generate_for_server(Some("http://localhost:8080")).await?;
+    assert!(test.contains("url: http://localhost:8080/pets"));
+
+    let err = generate_for_server(Some("5")).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "--server 5 is out of range; the spec declares 0: https://{region}.api.example.com/{basePath}, 1: https://staging.example.com/v2"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_all_uses_a_base_url_variable() -> Result<()> {
+    let (config, test) = generate_for_server(Some("all")).await?;
+    for file in [&config, &test] {
+        assert!(file.starts_with(
+            "# Servers in the spec; set baseUrl or pass --base-url to use another:\n\
+             #   https://{region}.api.example.com/{basePath} (Production)\n\
+             #   https://staging.example.com/v2 (Staging)\n"
+        ));
+    }
+    let test: serde_yaml::Value = serde_yaml::from_str(&test)?;
+    assert_eq!(test["tests"][0]["request"]["url"], "{{baseUrl}}/pets");
+    assert_eq!(
+        test["vars"]["baseUrl"],
+        "https://{{region}}.api.example.com/{{basePath}}"
+    );
+    assert_eq!(test["vars"]["region"], "eu");
+    Ok(())
+}
+
+const ENUM_SPEC: &str = r#"
+openapi: 3.0.0
+info:
+  title: Pet Store
+  version: 1.0.0
+servers:
+  - url: https://pets.example.com
+paths:
+  /pets/{status}:
+    get:
+      operationId: findPets
+      parameters:
+        - name: status
+          in: path
+          required: true
+          schema:
+            type: string
+            enum: [available, pending, sold]
+        - name: size
+          in: query
+          schema:
+            $ref: '#/components/schemas/Size'
+        - name: limit
+          in: query
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Pets
+  /pets/{id}/photo:
+    get:
+      operationId: getPhoto
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Photo
+components:
+  schemas:
+    Size:
+      type: integer
+      enum: [1, 2]
+"#;
+
+async fn generate_data_driven(data_limit: Option<usize>) -> Result<TempDir> {
+    let temp_dir = TempDir::new()?;
+    let spec_file = temp_dir.path().join("pets.yaml");
+    fs::write(&spec_file, ENUM_SPEC)?;
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: temp_dir.path().join("output"),
+            data_driven: true,
+            data_limit,
+            ..Default::default()
+        },
+    )
+    .await?;
+    Ok(temp_dir)
+}
+
+#[tokio::test]
+async fn test_data_driven_generates_enum_datasets() -> Result<()> {
+    let temp_dir = generate_data_driven(None).await?;
+    let output_dir = temp_dir.path().join("output");
+
+    let csv = fs::read_to_string(output_dir.join("data/findpets.csv"))?;
+    assert_eq!(
+        csv,
+        "status,size\navailable,1\navailable,2\npending,1\npending,2\nsold,1\nsold,2\n"
+    );
+
+    let suite_file = output_dir.join("findpets.yaml");
+    let suites = load_test_suite(&suite_file, false).await?;
+    let config = &suites[0].config;
+    let dataset = config.dataset.as_ref().unwrap();
+    assert_eq!(dataset.file, "data/findpets.csv");
+    assert!(suite_file.parent().unwrap().join(&dataset.file).is_file());
+    let request = &config.tests[0].request;
+    assert_eq!(request.url, "https://pets.example.com/pets/{{status}}");
+    let params = request.params.as_ref().unwrap();
+    assert_eq!(params["size"], "{{size}}");
+    assert_eq!(params["limit"], "{{queryValue}}");
+
+    // Operations without enum parameters stay as they were
+    let photo: serde_yaml::Value =
+        serde_yaml::from_str(&fs::read_to_string(output_dir.join("getphoto.yaml"))?)?;
+    assert!(photo["dataset"].is_null());
+    assert_eq!(
+        photo["tests"][0]["request"]["url"],
+        "https://pets.example.com/pets/{id}/photo"
+    );
+    assert!(!output_dir.join("data/getphoto.csv").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_data_limit_caps_dataset_rows() -> Result<()> {
+    let temp_dir = generate_data_driven(Some(4)).await?;
+    let csv = fs::read_to_string(temp_dir.path().join("output/data/findpets.csv"))?;
+    assert_eq!(csv.lines().count(), 5);
+    assert!(csv.ends_with("pending,2\n"), "{}", csv);
+    Ok(())
+}
+
+/// Every file generated into `dir`, by its path relative to `dir`.
+fn generated_files(dir: &std::path::Path) -> Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let name = entry.path().strip_prefix(dir)?.display().to_string();
+            files.push((name, fs::read_to_string(entry.path())?));
+        }
+    }
+    Ok(files)
+}
+
+#[tokio::test]
+async fn test_split_spec_generates_like_its_bundled_equivalent() -> Result<()> {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/openapi");
+    let temp_dir = TempDir::new()?;
+    for (spec, out) in [("split/openapi.yaml", "split"), ("bundled.yaml", "bundled")] {
+        generate(
+            &fixtures.join(spec),
+            &GenOptions {
+                out: temp_dir.path().join(out),
+                with_schemas: true,
+                data_driven: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+    }
+
+    let split = generated_files(&temp_dir.path().join("split"))?;
+    let bundled = generated_files(&temp_dir.path().join("bundled"))?;
+    assert_eq!(split, bundled);
+    let names: Vec<&str> = split.iter().map(|(name, _)| name.as_str()).collect();
+    assert!(names.contains(&"getuser.yaml"), "{:?}", names);
+    assert!(names.contains(&"data/listusers.csv"), "{:?}", names);
+
+    // The schema from the third file, with its recursive `manager`
+    let schema: serde_json::Value = serde_json::from_str(&fs::read_to_string(
+        temp_dir.path().join("split/schemas/getuser.json"),
+    )?)?;
+    assert_eq!(schema["required"], serde_json::json!(["id", "name"]));
+    assert_eq!(
+        schema["properties"]["address"]["properties"]["city"]["type"],
+        "string"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_circular_file_refs_are_reported() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let spec_file = temp_dir.path().join("api.yaml");
+    fs::write(
+        &spec_file,
+        r#"openapi: 3.0.3
+info:
+  title: Loop
+  version: 1.0.0
+paths:
+  /a:
+    $ref: a.yaml
+"#,
+    )?;
+    fs::write(temp_dir.path().join("a.yaml"), "$ref: b.yaml\n")?;
+    fs::write(temp_dir.path().join("b.yaml"), "$ref: ./a.yaml\n")?;
+
+    let error = generate(
+        &spec_file,
+        &GenOptions {
+            out: temp_dir.path().join("output"),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap_err();
+    let message = format!("{:#}", error);
+    assert!(message.contains("Circular $ref"), "{}", message);
+    assert!(message.contains("a.yaml -> "), "{}", message);
+    Ok(())
+}
+
+#[test]
+fn test_import_openapi_generates_tests() -> Result<()> {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/openapi");
+    let temp_dir = TempDir::new()?;
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .arg("import")
+        .arg("openapi")
+        .arg(fixtures.join("split/openapi.yaml"))
+        .arg("--out")
+        .arg(temp_dir.path())
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let suite = fs::read_to_string(temp_dir.path().join("createuser.yaml"))?;
+    assert!(suite.contains("https://api.example.com/users"), "{}", suite);
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use rivet::commands::import::handle_import;
+use rivet::import::{import, ImportInput, ImportOptions, ImportTool};
 use std::fs;
 use tempfile::TempDir;
 
@@ -145,6 +146,149 @@ async fn test_postman_collection_with_variables() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_postman_relative_urls() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let output_dir = temp_dir.path().join("output");
+    let collection = r#"{
+        "info": { "name": "Relative", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json" },
+        "variable": [{ "key": "baseUrl", "value": "https://api.example.com/v2" }],
+        "item": [
+            { "name": "List", "request": { "method": "GET", "url": "{{baseUrl}}/items?page=2" } },
+            { "name": "Other host", "request": { "method": "GET", "url": "https://status.example.com/health" } },
+            { "name": "Lookalike", "request": { "method": "GET", "url": "{{baseUrlV1}}/items" } }
+        ]
+    }"#;
+    let collection_file = temp_dir.path().join("relative.json");
+    fs::write(&collection_file, collection)?;
+
+    let options = ImportOptions {
+        out: output_dir.clone(),
+        relative_urls: true,
+    };
+    handle_import("postman".to_string(), collection_file, options.clone()).await?;
+
+    let url = |file: &str| -> Result<String> {
+        let suite: rivet::config::RivetConfig =
+            serde_yaml::from_str(&fs::read_to_string(output_dir.join(file))?)?;
+        Ok(suite.tests[0].request.url.clone())
+    };
+    assert_eq!(url("list.yaml")?, "/items?page=2");
+    assert_eq!(url("other_host.yaml")?, "https://status.example.com/health");
+    assert_eq!(url("lookalike.yaml")?, "{{baseUrlV1}}/items");
+
+    let result = handle_import(
+        "curl".to_string(),
+        temp_dir.path().join("commands.sh"),
+        options,
+    )
+    .await;
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("only supported by the postman and openapi importers"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_postman_pre_request_scripts() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let output_dir = temp_dir.path().join("output");
+
+    let collection = r#"{
+        "info": {
+            "name": "Scripts",
+            "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+        },
+        "event": [
+            {
+                "listen": "prerequest",
+                "script": { "exec": ["pm.collectionVariables.set('tenant', 'acme');"] }
+            }
+        ],
+        "item": [
+            {
+                "name": "Create Order",
+                "event": [
+                    {
+                        "listen": "prerequest",
+                        "script": {
+                            "exec": [
+                                "pm.environment.set(\"createdAt\", new Date().toISOString());",
+                                "pm.environment.set(\"orderId\", uuid.v4());"
+                            ]
+                        }
+                    },
+                    { "listen": "test", "script": { "exec": ["pm.test('ok', () => {});"] } }
+                ],
+                "request": {
+                    "method": "POST",
+                    "url": "https://api.example.com/orders/{{orderId}}",
+                    "header": [{ "key": "X-Request-Id", "value": "{{$guid}}" }]
+                }
+            },
+            {
+                "name": "Signed Request",
+                "event": [
+                    {
+                        "listen": "prerequest",
+                        "script": {
+                            "exec": [
+                                "const sig = CryptoJS.HmacSHA256(pm.request.url.toString(), 'k');",
+                                "pm.environment.set('signature', sig.toString());"
+                            ]
+                        }
+                    }
+                ],
+                "request": { "method": "GET", "url": "https://api.example.com/signed" }
+            }
+        ]
+    }"#;
+
+    let collection_file = temp_dir.path().join("scripts.json");
+    fs::write(&collection_file, collection)?;
+    let summary = import(
+        ImportTool::Postman,
+        ImportInput::File(collection_file),
+        &ImportOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(summary.name, "Scripts");
+    assert_eq!(summary.tests, 2);
+    assert_eq!(summary.suites.len(), 2);
+    assert_eq!(summary.config, Some(output_dir.join("rivet.yaml")));
+    assert_eq!(
+        summary.needs_review,
+        vec![output_dir.join("signed_request.yaml")]
+    );
+
+    let create = fs::read_to_string(output_dir.join("create_order.yaml"))?;
+    let suite: rivet::config::RivetConfig = serde_yaml::from_str(&create)?;
+    let vars = suite.vars.unwrap();
+    assert_eq!(vars["createdAt"], "{{$isoTimestamp}}");
+    assert_eq!(vars["orderId"], "{{$uuid}}");
+    let headers = suite.tests[0].request.headers.as_ref().unwrap();
+    assert_eq!(headers["X-Request-Id"], "{{$uuid}}");
+    assert!(!create.starts_with('#'), "{}", create);
+
+    let signed = fs::read_to_string(output_dir.join("signed_request.yaml"))?;
+    assert!(
+        signed.starts_with("# Postman pre-request script, not fully converted; review by hand:\n#   const sig = CryptoJS"),
+        "{}",
+        signed
+    );
+    // Still a valid suite
+    let suite: rivet::config::RivetConfig = serde_yaml::from_str(&signed)?;
+    assert_eq!(suite.name, "Signed Request");
+
+    let main_config = fs::read_to_string(output_dir.join("rivet.yaml"))?;
+    assert!(main_config.contains("tenant: acme"), "{}", main_config);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_postman_different_request_body_modes() -> Result<()> {
     let temp_dir = TempDir::new()?;
@@ -208,6 +352,167 @@ async fn test_postman_different_request_body_modes() -> Result<()> {
     Ok(())
 }
 
+/// A devtools "Copy as cURL" command, continued over several lines.
+const COPIED_CURL: &str = r#"curl 'https://api.example.com/users' \
+  -H 'accept: application/json' \
+  -H 'content-type: application/json' \
+  --data-raw '{"name":"Ada","tags":["a b"]}' \
+  --compressed
+"#;
+
+#[tokio::test]
+async fn test_curl_import_from_stdin_to_stdout() -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut child = tokio::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .args(["import", "curl", "-", "--out", "-"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(COPIED_CURL.as_bytes()).await?;
+    drop(stdin);
+    let output = child.wait_with_output().await?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // Only the suite goes to stdout, so it can be redirected to a file
+    let suite: rivet::config::RivetConfig = serde_yaml::from_str(&stdout)?;
+    assert_eq!(suite.name, "POST /users");
+    let request = &suite.tests[0].request;
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.url, "https://api.example.com/users");
+    assert_eq!(
+        request.body.as_deref(),
+        Some(r#"{"name":"Ada","tags":["a b"]}"#)
+    );
+    assert_eq!(
+        request.headers.as_ref().unwrap()["content-type"],
+        "application/json"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_curl_import_saves_credentials_as_placeholders() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let commands = temp_dir.path().join("auth.sh");
+    fs::write(
+        &commands,
+        "curl -u ada:s3cr3t https://a.test/me\n\
+         curl https://a.test/orders -H 'Authorization: Bearer abc.def' -b 'session=xyz'\n",
+    )?;
+
+    let output = tokio::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .args(["import", "curl"])
+        .arg(&commands)
+        .args(["--out", "-"])
+        .output()
+        .await?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for secret in ["s3cr3t", "YWRhOnMzY3IzdA", "abc.def", "xyz"] {
+        assert!(!stdout.contains(secret), "{} in:\n{}", secret, stdout);
+    }
+    let suite: rivet::config::RivetConfig = serde_yaml::from_str(&stdout)?;
+    let headers = |i: usize| suite.tests[i].request.headers.clone().unwrap();
+    assert_eq!(headers(0)["Authorization"], "Basic ${RIVET_BASIC_AUTH}");
+    assert_eq!(headers(1)["Authorization"], "Bearer ${RIVET_TOKEN}");
+    assert_eq!(headers(1)["Cookie"], "${RIVET_COOKIE}");
+
+    // One warning for the whole file
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stderr.matches("saved as placeholders").count(),
+        1,
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("set RIVET_BASIC_AUTH, RIVET_TOKEN, RIVET_COOKIE"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_curl_import_returns_placeholder_env_vars() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let summary = import(
+        ImportTool::Curl,
+        ImportInput::Text("curl -H 'X-API-Key: k3y' https://a.test/me".to_string()),
+        &ImportOptions {
+            out: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(summary.env_vars, ["RIVET_API_KEY"]);
+
+    let summary = import(
+        ImportTool::Curl,
+        ImportInput::Text("curl https://a.test/health".to_string()),
+        &ImportOptions {
+            out: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert!(summary.env_vars.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_curl_import_inline_and_from_file() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let out = temp_dir.path().join("tests");
+
+    let output = tokio::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .args([
+            "import",
+            "curl",
+            "--inline",
+            "curl -X DELETE https://a.test/users/7",
+        ])
+        .arg("--out")
+        .arg(&out)
+        .output()
+        .await?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let suite = fs::read_to_string(out.join("delete_users_7.rivet.yaml"))?;
+    assert!(suite.contains("method: DELETE"), "{}", suite);
+
+    // A file holds one command per line (or continued line); each becomes a test
+    let commands = temp_dir.path().join("smoke.sh");
+    fs::write(
+        &commands,
+        format!(
+            "# smoke checks\n{}\ncurl https://api.example.com/health\n",
+            COPIED_CURL
+        ),
+    )?;
+    handle_import("curl".to_string(), commands, out.clone()).await?;
+    let suite: rivet::config::RivetConfig =
+        serde_yaml::from_str(&fs::read_to_string(out.join("smoke.rivet.yaml"))?)?;
+    assert_eq!(suite.name, "smoke");
+    let names: Vec<_> = suite.tests.iter().map(|test| test.name.as_str()).collect();
+    assert_eq!(names, ["POST /users", "GET /health"]);
+
+    // Only curl commands can come from stdin or go to stdout
+    let result = handle_import("postman".to_string(), "-".into(), out).await;
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("only supported by the curl importer"));
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_unsupported_import_tool() {
     let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,145 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use tempfile::TempDir;
+
+fn list(args: &[&str]) -> Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .arg("list")
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run rivet")
+}
+
+fn write_suites(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir.join("users"))?;
+    fs::write(
+        dir.join("users/smoke.rivet.yaml"),
+        r#"name: Users
+description: User endpoints
+env: staging
+dataset:
+  file: users.csv
+tests:
+  - name: Get user
+    request: { method: GET, url: "https://api.example.com/users/1" }
+  - name: List users
+    request: { method: GET, url: "https://api.example.com/users" }
+"#,
+    )?;
+    fs::write(
+        dir.join("health.rivet.yaml"),
+        r#"name: Health
+setup:
+  - name: Warm up
+    request: { method: GET, url: "https://api.example.com/" }
+tests:
+  - name: Ping
+    request: { method: GET, url: "https://api.example.com/ping" }
+"#,
+    )?;
+    fs::write(dir.join("broken.rivet.yaml"), "name: Broken\n")?;
+    Ok(())
+}
+
+#[test]
+fn test_list_json_shape() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    write_suites(temp_dir.path())?;
+
+    let output = list(&[temp_dir.path().to_str().unwrap(), "--json"]);
+    assert!(output.status.success(), "{:?}", output);
+    let listing: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+
+    let suites = listing["suites"].as_array().unwrap();
+    assert_eq!(suites.len(), 2);
+    assert_eq!(suites[0]["file"], "health.rivet.yaml");
+    assert_eq!(suites[0]["name"], "Health");
+    // Setup steps aren't tests
+    assert_eq!(suites[0]["tests"], serde_json::json!(["Ping"]));
+    assert!(suites[0].get("env").is_none());
+
+    let users = &suites[1];
+    assert_eq!(users["file"], "users/smoke.rivet.yaml");
+    assert_eq!(users["description"], "User endpoints");
+    assert_eq!(users["env"], "staging");
+    assert_eq!(users["dataset"], "users.csv");
+    assert_eq!(
+        users["tests"],
+        serde_json::json!(["Get user", "List users"])
+    );
+    assert!(users["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("smoke.rivet.yaml"));
+
+    let failed = listing["failed"].as_array().unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["file"], "broken.rivet.yaml");
+    let error = failed[0]["error"].as_str().unwrap();
+    assert!(error.contains("missing field `tests`"), "{}", error);
+    Ok(())
+}
+
+#[test]
+fn test_list_tree_shows_failed_files() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    write_suites(temp_dir.path())?;
+
+    let output = list(&[temp_dir.path().to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+    let text = String::from_utf8_lossy(&output.stdout);
+    for line in [
+        "  health.rivet.yaml Health (1 test)",
+        "  users/",
+        "    smoke.rivet.yaml Users (2 tests, env staging, dataset users.csv)",
+        "      User endpoints",
+        "      - List users",
+        "Failed to load:\n  broken.rivet.yaml\n",
+        "2 suite(s), 3 test(s); 1 file(s) failed to load",
+    ] {
+        assert!(text.contains(line), "missing `{}` in:\n{}", line, text);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_list_fails_only_when_no_file_loads() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    fs::write(temp_dir.path().join("a.rivet.yaml"), "name: A\n")?;
+    fs::write(temp_dir.path().join("b.rivet.yaml"), "tests: [\n")?;
+
+    let output = list(&[temp_dir.path().to_str().unwrap(), "--json"]);
+    assert!(!output.status.success());
+    let listing: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(listing["failed"].as_array().unwrap().len(), 2);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("None of the 2 suite file(s)"), "{}", stderr);
+    Ok(())
+}
+
+#[test]
+fn test_list_grep_selects_tests() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    write_suites(temp_dir.path())?;
+    let dir = temp_dir.path().to_str().unwrap();
+
+    let output = list(&[dir, "--json", "--grep", "Users :: List"]);
+    let listing: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let suites = listing["suites"].as_array().unwrap();
+    assert_eq!(suites.len(), 1);
+    assert_eq!(suites[0]["tests"], serde_json::json!(["List users"]));
+
+    let output = list(&[dir, "--json", "--grep", "Users :: List", "--grep-invert"]);
+    let listing: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let tests: Vec<_> = listing["suites"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|suite| suite["tests"].as_array().unwrap().clone())
+        .collect();
+    assert_eq!(tests, ["Ping", "Get user"]);
+    Ok(())
+}
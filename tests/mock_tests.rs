@@ -0,0 +1,273 @@
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use tempfile::TempDir;
+
+const SPEC: &str = r##"
+openapi: 3.0.0
+info:
+  title: Pets
+  version: 1.0.0
+servers:
+  - url: SERVER/v1
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      parameters:
+        - name: limit
+          in: query
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Pets
+          content:
+            application/json:
+              example: [{ id: 1, name: Rex }]
+    post:
+      operationId: createPet
+      requestBody:
+        content:
+          application/json:
+            schema: { $ref: "#/components/schemas/Pet" }
+      responses:
+        '201':
+          description: Created
+          content:
+            application/json:
+              schema: { $ref: "#/components/schemas/Pet" }
+  /pets/{id}:
+    get:
+      operationId: getPet
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: A pet
+    delete:
+      operationId: deletePet
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: integer
+      responses:
+        '204':
+          description: Deleted
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name: { type: string }
+"##;
+
+/// A `rivet mock` process, killed when dropped.
+struct Mock {
+    child: Child,
+    url: String,
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Start `rivet mock` on a free port and wait until it listens.
+fn start_mock(args: &[&str]) -> Mock {
+    let child = Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .arg("mock")
+        .args(args)
+        .args(["--port", "0"])
+        .env("NO_COLOR", "1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run rivet mock");
+
+    // Owned by the guard first, so a failed start still kills the process
+    let mut mock = Mock {
+        child,
+        url: String::new(),
+    };
+    let mut stdout = BufReader::new(mock.child.stdout.take().unwrap());
+    let mut printed = String::new();
+    loop {
+        let mut line = String::new();
+        if stdout.read_line(&mut line).unwrap() == 0 {
+            panic!("rivet mock exited before listening:\n{}", printed);
+        }
+        printed.push_str(&line);
+        if let Some(rest) = line.split("Listening on ").nth(1) {
+            mock.url = rest.split_whitespace().next().unwrap().to_string();
+            // Keep draining so logging never blocks on a full pipe
+            std::thread::spawn(move || for _ in stdout.lines() {});
+            return mock;
+        }
+    }
+}
+
+fn rivet(args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run rivet");
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (output.status.success(), text)
+}
+
+fn path_str(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+#[test]
+fn test_generated_suites_pass_against_mocked_spec() {
+    let temp_dir = TempDir::new().unwrap();
+    let mock_spec = temp_dir.path().join("mock.yaml");
+    fs::write(
+        &mock_spec,
+        SPEC.replace("SERVER", "https://api.example.com"),
+    )
+    .unwrap();
+    let mock = start_mock(&["--spec", path_str(&mock_spec)]);
+
+    // Generate against the same spec, pointed at the mock
+    let gen_spec = temp_dir.path().join("api.yaml");
+    fs::write(&gen_spec, SPEC.replace("SERVER", &mock.url)).unwrap();
+    let out = temp_dir.path().join("generated");
+    let (ok, text) = rivet(&[
+        "gen",
+        "--spec",
+        path_str(&gen_spec),
+        "--out",
+        path_str(&out),
+    ]);
+    assert!(ok, "{}", text);
+
+    let mut suites: Vec<_> = fs::read_dir(&out)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.file_name().is_some_and(|name| name != "rivet.yaml"))
+        .collect();
+    suites.sort();
+    assert_eq!(suites.len(), 4, "{:?}", suites);
+    for suite in &suites {
+        let (ok, text) = rivet(&["run", path_str(suite)]);
+        assert!(ok, "{} failed against the mock:\n{}", suite.display(), text);
+    }
+}
+
+#[tokio::test]
+async fn test_mock_answers_unknown_paths_and_methods() {
+    let temp_dir = TempDir::new().unwrap();
+    let spec = temp_dir.path().join("api.yaml");
+    fs::write(&spec, SPEC.replace("SERVER", "https://api.example.com")).unwrap();
+    let mock = start_mock(&["--spec", path_str(&spec), "--latency", "50ms"]);
+    let client = reqwest::Client::new();
+
+    let started = std::time::Instant::now();
+    let response = client
+        .get(format!("{}/v1/pets?limit=5", mock.url))
+        .send()
+        .await
+        .unwrap();
+    assert!(started.elapsed() >= std::time::Duration::from_millis(50));
+    assert_eq!(response.status(), 200);
+    let pets: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(pets[0]["name"], "Rex");
+
+    let response = client
+        .post(format!("{}/pets", mock.url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    let pet: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(pet["name"], "example");
+
+    let response = client
+        .put(format!("{}/pets/3", mock.url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 405);
+    assert_eq!(response.headers()["allow"], "DELETE, GET");
+
+    let response = client
+        .get(format!("{}/owners", mock.url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[test]
+fn test_suite_passes_against_its_own_mock() {
+    let temp_dir = TempDir::new().unwrap();
+    let suite = temp_dir.path().join("users.rivet.yaml");
+    fs::write(
+        &suite,
+        r#"
+name: Users
+vars:
+  baseUrl: http://localhost:3000/api
+  userId: "42"
+tests:
+  - name: Get user
+    request:
+      method: GET
+      url: "{{baseUrl}}/users/{{userId}}"
+    expect:
+      status: 200
+      headers:
+        X-Api-Version: "2"
+      jsonpath:
+        "$.id": "{{userId}}"
+        "$.tags[0]": admin
+  - name: Create user
+    request:
+      method: POST
+      url: "{{baseUrl}}/users"
+      body: '{"name": "Ada"}'
+    expect:
+      status: 201
+"#,
+    )
+    .unwrap();
+    let mock = start_mock(&["--suite", path_str(&suite)]);
+
+    let (ok, text) = rivet(&[
+        "run",
+        path_str(&suite),
+        "--base-url",
+        &format!("{}/api", mock.url),
+    ]);
+    assert!(ok, "{}", text);
+    assert!(text.contains("2 tests passed"), "{}", text);
+}
+
+#[tokio::test]
+async fn test_chaos_fails_every_request_at_full_rate() {
+    let temp_dir = TempDir::new().unwrap();
+    let spec = temp_dir.path().join("api.yaml");
+    fs::write(&spec, SPEC.replace("SERVER", "https://api.example.com")).unwrap();
+    let mock = start_mock(&["--spec", path_str(&spec), "--chaos", "100%"]);
+
+    for _ in 0..5 {
+        let response = reqwest::get(format!("{}/pets", mock.url)).await.unwrap();
+        assert_eq!(response.status(), 500);
+    }
+}
@@ -1,6 +1,9 @@
 use anyhow::Result;
+use rivet::performance::connection::ConnectionOptions;
 use rivet::performance::{LoadPattern, PerformanceTestRunner};
+use rivet::utils::ClientOptions;
 use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use tempfile::TempDir;
 
@@ -14,6 +17,7 @@ async fn test_performance_test_runner_creation() -> Result<()> {
         Duration::from_secs(1), // warmup duration
         Duration::from_secs(2), // report interval
         LoadPattern::Constant,  // load pattern
+        &ClientOptions::default(),
     )?;
 
     // Just verify we can create the runner successfully - if we get here, it worked
@@ -66,10 +70,6 @@ async fn test_load_patterns() -> Result<()> {
     let rps = constant_controller.current_target_rps();
     assert_eq!(rps, 100.0);
 
-    let delay = constant_controller.request_delay();
-    assert!(delay.is_some());
-    assert_eq!(delay.unwrap(), Duration::from_millis(10)); // 1000ms / 100 RPS = 10ms
-
     // Test ramp-up pattern - this would need more complex testing with actual time progression
     let ramp_controller =
         LoadController::new(LoadPattern::RampUp, Some(100), 10, Duration::from_secs(10));
@@ -111,6 +111,7 @@ tests:
         Duration::from_secs(1), // warmup duration
         Duration::from_secs(2), // report interval
         LoadPattern::Constant,  // load pattern
+        &ClientOptions::default(),
     )?;
 
     // Run the performance test
@@ -136,6 +137,7 @@ async fn test_performance_test_error_handling() -> Result<()> {
         Duration::from_secs(0),
         Duration::from_secs(1),
         LoadPattern::Constant,
+        &ClientOptions::default(),
     )?;
 
     let non_existent_path = std::path::Path::new("/non/existent/path");
@@ -176,6 +178,7 @@ tests:
             Duration::from_secs(1),
             Duration::from_secs(2),
             pattern,
+            &ClientOptions::default(),
         )?;
 
         let results = runner.run_performance_test(&config_file, None).await?;
@@ -216,3 +219,988 @@ async fn test_performance_report_saving() -> Result<()> {
 
     Ok(())
 }
+
+/// Answer every request with `200 {}` on a random local port, counting
+/// requests per path; paths under `/slow` are answered after 50ms. Returns
+/// the base URL and the counts.
+async fn serve_counting() -> Result<(
+    String,
+    std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, usize>>>,
+)> {
+    serve_failing_after(usize::MAX).await
+}
+
+/// Like [`serve_counting`], but answer `500` once `limit` requests have
+/// been served.
+async fn serve_failing_after(
+    limit: usize,
+) -> Result<(
+    String,
+    std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, usize>>>,
+)> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}", listener.local_addr()?);
+    let counts = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+
+    let server_counts = std::sync::Arc::clone(&counts);
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let counts = std::sync::Arc::clone(&server_counts);
+            tokio::spawn(async move {
+                // Keep-alive: answer each request on the connection in turn
+                let mut pending = Vec::new();
+                let mut buf = [0u8; 1024];
+                loop {
+                    let end = loop {
+                        if let Some(end) = pending.windows(4).position(|w| w == b"\r\n\r\n") {
+                            break end + 4;
+                        }
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => pending.extend_from_slice(&buf[..n]),
+                        }
+                    };
+                    let request: Vec<u8> = pending.drain(..end).collect();
+                    let request = String::from_utf8_lossy(&request);
+                    let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+                    if path.starts_with("/slow") {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                    }
+                    let served = {
+                        let mut counts = counts.lock().unwrap();
+                        *counts.entry(path).or_insert(0) += 1;
+                        counts.values().sum::<usize>()
+                    };
+                    let response: &[u8] = if served > limit {
+                        b"HTTP/1.1 500 Internal Server Error\r\ncontent-length: 2\r\n\r\n{}"
+                    } else {
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{}"
+                    };
+                    if socket.write_all(response).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    Ok((url, counts))
+}
+
+/// Every loaded suite receives traffic and is broken down in the results
+#[tokio::test]
+async fn test_performance_test_runs_all_suites() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, counts) = serve_counting().await?;
+    fs::write(
+        temp_dir.path().join("users.rivet.yaml"),
+        format!(
+            "name: Users\ntests:\n  - name: List users\n    request:\n      method: GET\n      url: {}/users\n",
+            url
+        ),
+    )?;
+    fs::write(
+        temp_dir.path().join("orders.rivet.yaml"),
+        format!(
+            "name: Orders\ntests:\n  - name: List orders\n    request:\n      method: GET\n      url: {}/orders\n  - name: Get order\n    request:\n      method: GET\n      url: {}/orders/1\n",
+            url, url
+        ),
+    )?;
+
+    let runner = PerformanceTestRunner::new(
+        2,
+        None,
+        Duration::from_secs(1),
+        Duration::ZERO,
+        Duration::from_secs(1),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?;
+    let results = runner.run_performance_test(temp_dir.path(), None).await?;
+
+    {
+        let counts = counts.lock().unwrap();
+        for path in ["/users", "/orders", "/orders/1"] {
+            assert!(counts.get(path).copied().unwrap_or(0) > 0, "{:?}", counts);
+        }
+    }
+
+    let names: Vec<&str> = results.suites.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["Orders", "Users"]);
+    let orders = &results.suites[0];
+    assert_eq!(orders.tests.len(), 2);
+    assert!(orders.total_requests > 0);
+    assert!(results.suites[1].total_requests > 0);
+
+    // --suite narrows the run to one suite
+    let runner = PerformanceTestRunner::new(
+        1,
+        None,
+        Duration::from_secs(1),
+        Duration::ZERO,
+        Duration::from_secs(1),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?
+    .with_suite(Some("Users".to_string()));
+    let results = runner.run_performance_test(temp_dir.path(), None).await?;
+    let names: Vec<&str> = results.suites.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["Users"]);
+
+    let error = PerformanceTestRunner::new(
+        1,
+        None,
+        Duration::from_secs(1),
+        Duration::ZERO,
+        Duration::from_secs(1),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?
+    .with_suite(Some("Missing".to_string()))
+    .run_performance_test(temp_dir.path(), None)
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "No suite named 'Missing'. Available: Orders, Users"
+    );
+    Ok(())
+}
+
+/// Suites that share a name are reported apart, under their files
+#[tokio::test]
+async fn test_performance_keeps_suites_with_the_same_name_apart() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _counts) = serve_counting().await?;
+    for version in ["v1", "v2"] {
+        fs::create_dir_all(temp_dir.path().join(version))?;
+        fs::write(
+            temp_dir.path().join(version).join("users.rivet.yaml"),
+            format!(
+                "name: Users\ntests:\n  - name: List users\n    request:\n      method: GET\n      url: {}/{}/users\n",
+                url, version
+            ),
+        )?;
+    }
+
+    let runner = PerformanceTestRunner::new(
+        2,
+        None,
+        Duration::from_secs(1),
+        Duration::ZERO,
+        Duration::from_secs(1),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?;
+    let results = runner.run_performance_test(temp_dir.path(), None).await?;
+
+    let names: Vec<&str> = results.suites.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["Users (v1/users.rivet.yaml)", "Users (v2/users.rivet.yaml)"]
+    );
+    assert!(results.suites.iter().all(|s| s.total_requests > 0));
+    Ok(())
+}
+
+/// Violated thresholds are all listed, fail the run and land in the report
+#[tokio::test]
+async fn test_performance_thresholds_fail_the_run() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _counts) = serve_counting().await?;
+    let suite = temp_dir.path().join("load.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Load\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: {}/ping\n",
+            url
+        ),
+    )?;
+    let config = temp_dir.path().join("perf.yaml");
+    fs::write(
+        &config,
+        "thresholds:\n  fail_avg: 0ms\n  min_success_rate: 90\n",
+    )?;
+    let report = temp_dir.path().join("perf.json");
+
+    let output = tokio::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .arg("perf")
+        .arg(&suite)
+        .args(["--duration", "1s", "--warmup", "0s", "--concurrent", "1"])
+        .args(["--min-rps", "1000000", "--fail-p99", "10s"])
+        .arg("--config")
+        .arg(&config)
+        .arg("--output")
+        .arg(&report)
+        .env_remove("RUST_BACKTRACE")
+        .output()
+        .await?;
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Performance test failed 2 threshold(s)"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("  - avg "), "{}", stderr);
+    assert!(stderr.contains("  - rps "), "{}", stderr);
+
+    let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&report)?)?;
+    let thresholds: Vec<(&str, bool)> = report["thresholds"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| (t["name"].as_str().unwrap(), t["passed"].as_bool().unwrap()))
+        .collect();
+    assert_eq!(
+        thresholds,
+        vec![
+            ("p99", true),
+            ("avg", false),
+            ("rps", false),
+            ("success_rate", true)
+        ]
+    );
+    assert_eq!(report["thresholds"][3]["limit"], 90.0);
+    Ok(())
+}
+
+/// The target RPS limits the run as a whole, measured at the server: ten
+/// workers pacing themselves separately would send ten times as much. The
+/// floor is loose, since a loaded machine may fall behind the target.
+#[tokio::test]
+async fn test_performance_rps_is_shared_across_workers() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, counts) = serve_counting().await?;
+    let suite = temp_dir.path().join("rate.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Rate\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: {}/ping\n",
+            url
+        ),
+    )?;
+
+    let duration = Duration::from_secs(2);
+    for target in [50u32, 500] {
+        counts.lock().unwrap().clear();
+        let runner = PerformanceTestRunner::new(
+            10,
+            Some(target),
+            duration,
+            Duration::ZERO,
+            Duration::from_secs(10),
+            LoadPattern::Constant,
+            &ClientOptions::default(),
+        )?;
+        runner.run_performance_test(&suite, None).await?;
+
+        let received = counts.lock().unwrap().get("/ping").copied().unwrap_or(0);
+        // The run's requests, plus a burst window's worth and one in flight
+        // per worker when it stops
+        let ceiling = target as f64 * (duration.as_secs_f64() + 0.05) + 10.0;
+        let floor = 0.5 * target as f64 * duration.as_secs_f64();
+        assert!(
+            (floor..=ceiling).contains(&(received as f64)),
+            "target {} RPS, received {} requests in {:?}",
+            target,
+            received,
+            duration
+        );
+    }
+    Ok(())
+}
+
+/// Per-worker metrics are all merged in: no request goes unrecorded
+#[tokio::test]
+async fn test_performance_many_workers_record_every_request() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, counts) = serve_counting().await?;
+    let suite = temp_dir.path().join("busy.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Busy\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: {}/ping\n",
+            url
+        ),
+    )?;
+
+    let runner = PerformanceTestRunner::new(
+        100,
+        None,
+        Duration::from_secs(2),
+        Duration::ZERO,
+        Duration::from_secs(10),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?;
+    let results = runner.run_performance_test(&suite, None).await?;
+
+    let received = counts.lock().unwrap().get("/ping").copied().unwrap_or(0) as u64;
+    assert!(results.total_requests > 100, "{:?}", results);
+    assert_eq!(results.total_requests, received);
+    Ok(())
+}
+
+/// Dataset rows vary each request, and suite vars resolve without --env
+#[tokio::test]
+async fn test_performance_data_rows_vary_requests() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, counts) = serve_counting().await?;
+    let suite = temp_dir.path().join("users.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Users\nvars:\n  baseUrl: {}\ntests:\n  - name: Get user\n    request:\n      method: GET\n      url: \"{{{{baseUrl}}}}/users/{{{{user_id}}}}\"\n",
+            url
+        ),
+    )?;
+    let data = temp_dir.path().join("users.csv");
+    fs::write(&data, "user_id\n1\n2\n3\n4\n5\n")?;
+
+    let runner = PerformanceTestRunner::new(
+        4,
+        Some(50),
+        Duration::from_secs(1),
+        Duration::ZERO,
+        Duration::from_secs(10),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?
+    .with_data(Some(data));
+    let results = runner.run_performance_test(&suite, None).await?;
+
+    let counts = counts.lock().unwrap();
+    for user_id in 1..=5 {
+        assert!(
+            counts.contains_key(&format!("/users/{}", user_id)),
+            "{:?}",
+            counts
+        );
+    }
+    assert_eq!(counts.len(), 5, "{:?}", counts);
+    assert_eq!(results.data_rows_available, 5);
+    assert_eq!(results.data_rows_used, 5);
+    assert_eq!(results.failed_requests, 0);
+    Ok(())
+}
+
+/// Cancelling a run stops it early with the results gathered so far
+#[tokio::test]
+async fn test_performance_cancel_keeps_partial_results() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _counts) = serve_counting().await?;
+    let suite = temp_dir.path().join("long.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Long\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: {}/ping\n",
+            url
+        ),
+    )?;
+
+    let runner = PerformanceTestRunner::new(
+        4,
+        Some(50),
+        Duration::from_secs(30),
+        Duration::ZERO,
+        Duration::from_secs(10),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?;
+    let cancel = runner.cancellation_token();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        cancel.cancel();
+    });
+    let results = runner.run_performance_test(&suite, None).await?;
+
+    assert!(results.interrupted);
+    assert!(results.total_requests > 0);
+    assert!(
+        results.total_duration < Duration::from_secs(5),
+        "{:?}",
+        results
+    );
+    let json = serde_json::to_value(&results)?;
+    assert_eq!(json["interrupted"], true);
+    Ok(())
+}
+
+/// Metrics served for Prometheus during a run only ever grow
+#[tokio::test]
+async fn test_performance_prometheus_endpoint() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _counts) = serve_counting().await?;
+    let suite = temp_dir.path().join("scraped.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Scraped\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: {}/ping\n",
+            url
+        ),
+    )?;
+
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+    let runner = PerformanceTestRunner::new(
+        2,
+        Some(50),
+        Duration::from_secs(3),
+        Duration::ZERO,
+        Duration::from_secs(10),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?
+    .with_prometheus_listen(Some(addr))
+    .with_run_id("scrape-test".to_string());
+    let run = tokio::spawn(async move { runner.run_performance_test(&suite, None).await });
+
+    let sample = |text: &str, series: &str| -> u64 {
+        text.lines()
+            .find_map(|line| line.strip_prefix(series))
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or_else(|| panic!("no {} in\n{}", series, text))
+    };
+    let client = reqwest::Client::new();
+    let mut scrapes = Vec::new();
+    for _ in 0..3 {
+        tokio::time::sleep(Duration::from_millis(700)).await;
+        let response = client
+            .get(format!("http://{}/metrics", addr))
+            .send()
+            .await?;
+        assert!(response.status().is_success());
+        let text = response.text().await?;
+        assert!(text.contains("# TYPE rivet_perf_response_time_seconds histogram"));
+        assert!(text.contains("rivet_perf_target_rps{run_id=\"scrape-test\"} 50"));
+        scrapes.push((
+            sample(
+                &text,
+                "rivet_perf_requests_total{run_id=\"scrape-test\",class=\"2xx\"}",
+            ),
+            sample(
+                &text,
+                "rivet_perf_response_time_seconds_count{run_id=\"scrape-test\"}",
+            ),
+        ));
+    }
+    let missing = client.get(format!("http://{}/other", addr)).send().await?;
+    assert_eq!(missing.status(), 404);
+
+    let results = run.await??;
+    assert!(
+        scrapes.windows(2).all(|pair| pair[0] <= pair[1]),
+        "{:?}",
+        scrapes
+    );
+    assert!(scrapes[2].0 > scrapes[0].0, "{:?}", scrapes);
+    assert!(scrapes[2].0 <= results.total_requests);
+
+    // The endpoint goes away with the run
+    assert!(client
+        .get(format!("http://{}/metrics", addr))
+        .send()
+        .await
+        .is_err());
+    Ok(())
+}
+
+/// Serve `200 {}` over HTTP/1.1 or HTTP/2 (prior knowledge) on a random local
+/// port. Returns the base URL and the number of connections accepted.
+async fn serve_counting_connections() -> Result<(String, std::sync::Arc<AtomicUsize>)> {
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Response, Server};
+
+    let connections = std::sync::Arc::new(AtomicUsize::new(0));
+    let accepted = std::sync::Arc::clone(&connections);
+    let make_service = make_service_fn(move |_| {
+        accepted.fetch_add(1, Ordering::SeqCst);
+        async {
+            Ok::<_, std::convert::Infallible>(service_fn(|_| async {
+                Ok::<_, std::convert::Infallible>(Response::new(Body::from("{}")))
+            }))
+        }
+    });
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    Ok((url, connections))
+}
+
+/// Connection flags change how requests reach the server, and the protocol
+/// used is reported
+#[tokio::test]
+async fn test_performance_connection_options() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let run = |connection: ConnectionOptions| {
+        let temp_dir = temp_dir.path().to_path_buf();
+        async move {
+            let (url, connections) = serve_counting_connections().await?;
+            let suite = temp_dir.join("conn.rivet.yaml");
+            fs::write(
+                &suite,
+                format!(
+                    "name: Conn\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: {}/ping\n",
+                    url
+                ),
+            )?;
+            let results = PerformanceTestRunner::new(
+                2,
+                Some(40),
+                Duration::from_secs(1),
+                Duration::ZERO,
+                Duration::from_secs(10),
+                LoadPattern::Constant,
+                &ClientOptions::default(),
+            )?
+            .with_connection(&connection)?
+            .run_performance_test(&suite, None)
+            .await?;
+            anyhow::Ok((results, connections.load(Ordering::SeqCst) as u64))
+        }
+    };
+
+    let (results, connections) = run(ConnectionOptions::default()).await?;
+    assert!(connections <= 4, "{} connections", connections);
+    assert_eq!(
+        results.http_versions.keys().collect::<Vec<_>>(),
+        ["HTTP/1.1"]
+    );
+
+    let (results, connections) = run(ConnectionOptions {
+        no_keepalive: true,
+        ..Default::default()
+    })
+    .await?;
+    assert!(results.total_requests > 10);
+    assert!(
+        connections >= results.total_requests,
+        "{} connections for {} requests",
+        connections,
+        results.total_requests
+    );
+
+    let (results, connections) = run(ConnectionOptions {
+        http2_only: true,
+        ..Default::default()
+    })
+    .await?;
+    assert!(connections <= 2, "{} connections", connections);
+    assert_eq!(
+        results.http_versions.keys().collect::<Vec<_>>(),
+        ["HTTP/2.0"]
+    );
+    assert_eq!(results.failed_requests, 0);
+    Ok(())
+}
+
+/// Failures are split into transport, HTTP status and assertion errors
+#[tokio::test]
+async fn test_performance_error_breakdown() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _counts) = serve_failing_after(20).await?;
+    let closed = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+    let suite = temp_dir.path().join("mixed.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            r#"name: Mixed
+tests:
+  - name: Body
+    request:
+      method: GET
+      url: {url}/body
+    expect:
+      jsonpath:
+        "$.ok": true
+  - name: Down
+    request:
+      method: GET
+      url: http://{closed}/down
+"#,
+        ),
+    )?;
+
+    let results = PerformanceTestRunner::new(
+        2,
+        Some(40),
+        Duration::from_secs(2),
+        Duration::ZERO,
+        Duration::from_secs(10),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?
+    .run_performance_test(&suite, None)
+    .await?;
+
+    let errors = results.error_breakdown;
+    assert!(errors.transport > 0, "{:?}", errors);
+    // The first 20 responses are 200s with the wrong body, the rest 500s
+    assert_eq!(errors.assertion, 20, "{:?}", errors);
+    assert!(errors.http_5xx > 0, "{:?}", errors);
+    assert_eq!(errors.http_4xx, 0);
+    assert_eq!(
+        errors.transport + errors.http_5xx + errors.assertion,
+        results.failed_requests
+    );
+    assert!(
+        (results.error_rates.transport - errors.transport as f64 / results.total_requests as f64)
+            .abs()
+            < 1e-9
+    );
+    Ok(())
+}
+
+/// Warmup sends real requests, which are counted apart from the measured ones
+#[tokio::test]
+async fn test_performance_warmup_sends_traffic() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, counts) = serve_counting().await?;
+    let suite = temp_dir.path().join("warm.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Warm\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: {}/ping\n",
+            url
+        ),
+    )?;
+
+    let runner = PerformanceTestRunner::new(
+        2,
+        Some(40),
+        Duration::from_secs(1),
+        Duration::from_secs(1),
+        Duration::from_secs(10),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?;
+    let results = runner.run_performance_test(&suite, None).await?;
+
+    // Warmup runs at half the target rate
+    assert!(
+        (10..=30).contains(&results.warmup_requests),
+        "{}",
+        results.warmup_requests
+    );
+    let received = counts.lock().unwrap().get("/ping").copied().unwrap_or(0) as u64;
+    assert!(
+        received >= results.warmup_requests + results.total_requests,
+        "server saw {}, results {:?}",
+        received,
+        results
+    );
+    // Measurement starts after warmup
+    assert!(results.total_duration < Duration::from_millis(3500));
+    Ok(())
+}
+
+/// Step and ramp patterns pace requests even without a target RPS
+#[tokio::test]
+async fn test_step_and_ramp_patterns() -> Result<()> {
+    use rivet::performance::patterns::LoadController;
+
+    let step = LoadPattern::Step {
+        increment: 50,
+        every: Duration::from_millis(200),
+    };
+    let controller = LoadController::new(step.clone(), None, 10, Duration::ZERO);
+    assert_eq!(controller.current_target_rps(), 50.0);
+    assert_eq!(controller.current_phase_description(), "Step 1 (50 RPS)");
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert_eq!(controller.current_target_rps(), 100.0);
+
+    // The target RPS caps the steps
+    let capped = LoadController::new(step, Some(30), 10, Duration::ZERO);
+    assert_eq!(capped.current_target_rps(), 30.0);
+
+    let ramp = LoadController::new(
+        LoadPattern::Ramp {
+            up: Duration::from_secs(10),
+            hold: Duration::from_secs(10),
+            down: Duration::from_secs(10),
+        },
+        Some(100),
+        10,
+        Duration::ZERO,
+    );
+    assert!(ramp.current_target_rps() < 10.0);
+    assert_eq!(ramp.current_phase_description(), "Ramping up (0%)");
+
+    let finished = LoadController::new(
+        LoadPattern::Ramp {
+            up: Duration::ZERO,
+            hold: Duration::ZERO,
+            down: Duration::ZERO,
+        },
+        Some(100),
+        10,
+        Duration::ZERO,
+    );
+    assert_eq!(finished.current_target_rps(), 0.0);
+    assert_eq!(finished.current_phase_description(), "Ramp complete");
+    Ok(())
+}
+
+/// Stress mode stops at the first step that breaks the limits and reports
+/// the last good one
+#[tokio::test]
+async fn test_stress_pattern_finds_breaking_point() -> Result<()> {
+    use rivet::performance::stress::StressLimits;
+
+    let temp_dir = TempDir::new()?;
+    // Steps of 20, 40 and 60 RPS over 500ms send about 10, 20 and 30
+    // requests, so failures start during the third step
+    let (url, _counts) = serve_failing_after(45).await?;
+    let suite = temp_dir.path().join("stress.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Stress\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: {}/ping\n    expect:\n      status: 200\n",
+            url
+        ),
+    )?;
+
+    let runner = PerformanceTestRunner::new(
+        4,
+        None,
+        Duration::from_secs(10),
+        Duration::ZERO,
+        Duration::from_secs(10),
+        LoadPattern::Stress {
+            increment: 20,
+            every: Duration::from_millis(500),
+            limits: StressLimits {
+                max_error_rate: 5.0,
+                max_p99: None,
+            },
+        },
+        &ClientOptions::default(),
+    )?;
+    let start = std::time::Instant::now();
+    let results = runner.run_performance_test(&suite, None).await?;
+
+    let stress = results.stress.expect("stress outcome");
+    assert_eq!(stress.max_rps, 40.0);
+    assert_eq!(stress.breaking_rps, Some(60.0));
+    assert!(
+        stress.reason.as_deref().unwrap().starts_with("error rate "),
+        "{:?}",
+        stress.reason
+    );
+    // The run ends at the breaking point, well before --duration
+    assert!(
+        start.elapsed() < Duration::from_secs(6),
+        "{:?}",
+        start.elapsed()
+    );
+    Ok(())
+}
+
+/// `--report html` writes a self-contained page with the run's timeline,
+/// beside the JSON report or into an output directory
+#[tokio::test]
+async fn test_performance_html_report() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _counts) = serve_counting().await?;
+    let suite = temp_dir.path().join("load.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Load\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: {}/ping\n",
+            url
+        ),
+    )?;
+
+    let perf = |report: &str, output: std::path::PathBuf| {
+        let suite = suite.clone();
+        let report = report.to_string();
+        async move {
+            tokio::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+                .arg("perf")
+                .arg(&suite)
+                .args(["--duration", "2s", "--warmup", "0s", "--concurrent", "1"])
+                .args(["--rps", "20", "--report-interval", "500ms"])
+                .args(["--report", &report])
+                .arg("--output")
+                .arg(&output)
+                .output()
+                .await
+        }
+    };
+
+    let output = perf("json,html", temp_dir.path().join("perf.json")).await?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(temp_dir.path().join("perf.json"))?)?;
+    assert!(report["timeline"].as_array().unwrap().len() >= 2);
+    let html = fs::read_to_string(temp_dir.path().join("perf.html"))?;
+    assert!(html.contains("Rivet Performance Report"));
+    assert!(html.contains("\"rps\":"), "{}", html);
+
+    let reports = temp_dir.path().join("reports");
+    let output = perf("html", reports.join("")).await?;
+    assert!(output.status.success());
+    let written: Vec<String> = fs::read_dir(&reports)?
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(written.len(), 1);
+    assert!(
+        written[0].starts_with("perf_report_") && written[0].ends_with(".html"),
+        "{:?}",
+        written
+    );
+    Ok(())
+}
+
+/// A run compared with a saved baseline embeds the comparison in its report
+/// and fails when a latency percentile regressed too far
+#[tokio::test]
+async fn test_performance_baseline_comparison() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _counts) = serve_counting().await?;
+    let suite = temp_dir.path().join("slow.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Slow\ntests:\n  - name: Slow\n    request:\n      method: GET\n      url: {}/slow\n",
+            url
+        ),
+    )?;
+
+    let perf = |baseline: std::path::PathBuf, max_regression: &str| {
+        let suite = suite.clone();
+        let max_regression = max_regression.to_string();
+        let output = temp_dir.path().join("perf.json");
+        async move {
+            tokio::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+                .arg("perf")
+                .arg(&suite)
+                .args(["--duration", "1s", "--warmup", "0s", "--concurrent", "2"])
+                .args(["--rps", "20", "--fail-on-regression", &max_regression])
+                .arg("--baseline")
+                .arg(&baseline)
+                .arg("--output")
+                .arg(&output)
+                .output()
+                .await
+        }
+    };
+
+    // An older report: no p50 or p99, and none of the newer sections
+    let slower = temp_dir.path().join("slower.json");
+    fs::write(
+        &slower,
+        r#"{"requests_per_second": 15.0, "success_rate": 1.0, "p95_response_time": 5000}"#,
+    )?;
+    let output = perf(slower, "10").await?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(temp_dir.path().join("perf.json"))?)?;
+    let metrics = report["comparison"]["metrics"].as_array().unwrap();
+    let names: Vec<_> = metrics
+        .iter()
+        .map(|m| m["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["rps", "error_rate", "p95"]);
+    assert_eq!(metrics[2]["worse"], false);
+
+    let faster = temp_dir.path().join("faster.json");
+    fs::write(
+        &faster,
+        r#"{"requests_per_second": 20.0, "success_rate": 1.0,
+            "p50_response_time": 10, "p95_response_time": 10, "p99_response_time": 10}"#,
+    )?;
+    let output = perf(faster, "10").await?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Performance regressed more than 10%"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("p95 +"), "{}", stderr);
+    // The report is still written, with the regression in it
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(temp_dir.path().join("perf.json"))?)?;
+    assert_eq!(report["comparison"]["metrics"][2]["regressed"], true);
+    Ok(())
+}
+
+/// A sample per report interval is appended to the time series file, as CSV
+/// or NDJSON depending on its extension
+#[tokio::test]
+async fn test_performance_timeseries_export() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _counts) = serve_counting().await?;
+    let suite = temp_dir.path().join("load.rivet.yaml");
+    fs::write(
+        &suite,
+        format!(
+            "name: Load\ntests:\n  - name: Ping\n    request:\n      method: GET\n      url: {}/ping\n",
+            url
+        ),
+    )?;
+
+    for file in ["samples.csv", "samples.ndjson"] {
+        let path = temp_dir.path().join(file);
+        // Three report intervals
+        let runner = PerformanceTestRunner::new(
+            2,
+            Some(40),
+            Duration::from_millis(1500),
+            Duration::ZERO,
+            Duration::from_millis(500),
+            LoadPattern::Constant,
+            &ClientOptions::default(),
+        )?
+        .with_timeseries(Some(path.clone()));
+        runner.run_performance_test(&suite, None).await?;
+
+        let content = fs::read_to_string(&path)?;
+        let lines: Vec<&str> = content.lines().collect();
+        if file.ends_with(".csv") {
+            assert!(
+                lines[0].starts_with("timestamp,elapsed,requests,errors,"),
+                "{}",
+                content
+            );
+            assert!(lines.len() >= 3, "{}", content);
+            assert!(lines[1].contains(",40.0,"), "{}", content);
+        } else {
+            assert!(lines.len() >= 2, "{}", content);
+            let sample: serde_json::Value = serde_json::from_str(lines[0])?;
+            assert_eq!(sample["target_rps"], 40.0);
+            assert!(sample["requests"].as_u64().unwrap() > 0);
+            assert!(sample["p99_response_time"].is_u64());
+        }
+    }
+
+    let error = PerformanceTestRunner::new(
+        1,
+        None,
+        Duration::from_secs(1),
+        Duration::ZERO,
+        Duration::from_secs(1),
+        LoadPattern::Constant,
+        &ClientOptions::default(),
+    )?
+    .with_timeseries(Some(temp_dir.path().join("samples.txt")))
+    .run_performance_test(&suite, None)
+    .await
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("use a .csv, .ndjson or .jsonl extension"));
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
-use rivet::commands::gen::handle_gen;
 use rivet::commands::import::handle_import;
+use rivet::gen::{generate, GenOptions};
 use std::fs;
 use tempfile::TempDir;
 
@@ -124,7 +124,14 @@ async fn test_real_openapi_redocly_template() -> Result<()> {
     fs::write(&spec_file, spec_content)?;
 
     // Test the generation
-    handle_gen(spec_file, output_dir.clone()).await?;
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
 
     // Verify main config
     let main_config = output_dir.join("rivet.yaml");
@@ -165,7 +172,14 @@ async fn test_real_swagger_petstore_openapi() -> Result<()> {
     fs::write(&spec_file, spec_content)?;
 
     // Test the generation
-    handle_gen(spec_file, output_dir.clone()).await?;
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
 
     // Verify main config
     let main_config = output_dir.join("rivet.yaml");
@@ -270,7 +284,14 @@ async fn test_mixed_format_handling() -> Result<()> {
     fs::write(&spec_file, json_spec)?;
 
     // Test generation with JSON format
-    handle_gen(spec_file, output_dir.clone()).await?;
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
 
     // Verify it worked
     let main_config = output_dir.join("rivet.yaml");
@@ -0,0 +1,151 @@
+use rivet::mock::{MockApi, MockServer};
+use rivet::record::{serve, Recorder};
+use std::fs;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+const SPEC: &str = r#"
+openapi: 3.0.0
+info: { title: Pets, version: "1" }
+paths:
+  /pets:
+    get:
+      responses:
+        "200":
+          description: Pets
+          content:
+            application/json:
+              example: [{ id: 1 }]
+    post:
+      responses:
+        "201": { description: Created }
+  /health:
+    get:
+      responses:
+        "200": { description: Up }
+"#;
+
+/// An in-process mock of [`SPEC`], returning its base URL.
+async fn upstream() -> String {
+    let spec: openapiv3::OpenAPI = serde_yaml::from_str(SPEC).unwrap();
+    let server = MockServer::new(MockApi::from_spec(&spec).await);
+    let (addr, _handle) = server.serve("127.0.0.1:0".parse().unwrap()).unwrap();
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_recorded_suites_replay_against_the_target() {
+    let target = upstream().await;
+    let temp_dir = TempDir::new().unwrap();
+    let out = temp_dir.path().join("recorded");
+    let recorder = Arc::new(
+        Recorder::new(&target, &out)
+            .unwrap()
+            .with_filter(regex::Regex::new("^/pets").unwrap()),
+    );
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let (addr, handle) = serve(recorder.clone(), "127.0.0.1:0".parse().unwrap(), async {
+        let _ = stopped.await;
+    })
+    .unwrap();
+
+    let client = reqwest::Client::new();
+    let proxy = format!("http://{}", addr);
+    for _ in 0..2 {
+        let response = client.get(format!("{}/pets", proxy)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.text().await.unwrap().trim_start().chars().next(),
+            Some('[')
+        );
+    }
+    let response = client
+        .post(format!("{}/pets", proxy))
+        .header("Authorization", "Bearer s3cret")
+        .body(r#"{"name":"Rex"}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    let response = client
+        .get(format!("{}/health", proxy))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    stop.send(()).unwrap();
+    handle.await.unwrap();
+    let (_, recorded, repeats) = recorder.finish().unwrap();
+    assert_eq!((recorded, repeats), (2, 1));
+
+    let mut files: Vec<String> = fs::read_dir(&out)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        ["get_pets.rivet.yaml", "post_pets.rivet.yaml", "rivet.yaml"]
+    );
+    let post = fs::read_to_string(out.join("post_pets.rivet.yaml")).unwrap();
+    assert!(!post.contains("s3cret"), "{}", post);
+
+    // The redacted header is read from the environment when replaying
+    let output = tokio::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .arg("run")
+        .arg(&out)
+        .env("NO_COLOR", "1")
+        .env("AUTHORIZATION", "Bearer replayed")
+        .output()
+        .await
+        .unwrap();
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", text);
+    assert!(text.contains("2 tests passed"), "{}", text);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_interrupt_writes_main_config() {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let temp_dir = TempDir::new().unwrap();
+    let out = temp_dir.path().join("recorded");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .args(["record", "--listen", "127.0.0.1:0", "--target"])
+        .arg("http://127.0.0.1:9")
+        .arg("--out")
+        .arg(&out)
+        .env("NO_COLOR", "1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run rivet record");
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut printed = String::new();
+    while !printed.contains("Proxy listening on") {
+        if stdout.read_line(&mut printed).unwrap() == 0 {
+            break;
+        }
+    }
+    let interrupted = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(interrupted.success());
+    let status = child.wait().unwrap();
+    let mut rest = String::new();
+    std::io::Read::read_to_string(&mut stdout, &mut rest).unwrap();
+    printed.push_str(&rest);
+
+    assert!(status.success(), "{}", printed);
+    assert!(printed.contains("0 test(s) recorded"), "{}", printed);
+    let main = fs::read_to_string(out.join("rivet.yaml")).unwrap();
+    assert!(
+        main.contains("Recorded from http://127.0.0.1:9"),
+        "{}",
+        main
+    );
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
-use rivet::commands::gen::handle_gen;
 use rivet::commands::import::handle_import;
+use rivet::gen::{generate, GenOptions};
 use std::fs;
 use tempfile::TempDir;
 
@@ -22,7 +22,14 @@ async fn test_github_massive_openapi_spec() -> Result<()> {
     fs::write(&spec_file, spec_content)?;
 
     // This should handle massive specs with 2000+ endpoints
-    handle_gen(spec_file, output_dir.clone()).await?;
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
 
     // Verify it generated a massive number of tests
     let main_config = output_dir.join("rivet.yaml");
@@ -148,7 +155,14 @@ async fn test_openai_large_integer_overflow_handling() -> Result<()> {
     fs::write(&spec_file, spec_content)?;
 
     // This should fail gracefully with a clear error message about integer overflow
-    let result = handle_gen(spec_file, output_dir.clone()).await;
+    let result = generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await;
 
     // Verify it fails with the expected integer overflow error
     assert!(
@@ -406,7 +420,14 @@ paths:
 
     // Time the generation
     let start = Instant::now();
-    handle_gen(spec_file, output_dir.clone()).await?;
+    generate(
+        &spec_file,
+        &GenOptions {
+            out: output_dir.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
     let duration = start.elapsed();
 
     // Should complete within reasonable time (less than 30 seconds for 1000 operations)
@@ -430,6 +451,55 @@ paths:
     Ok(())
 }
 
+#[tokio::test]
+async fn test_loading_a_directory_of_thousands_of_suites() -> Result<()> {
+    use rivet::runner::parser::load_test_suite;
+    use std::time::Instant;
+
+    let temp_dir = TempDir::new()?;
+    for i in 0..2000 {
+        let dir = temp_dir.path().join(format!("area{:02}", i % 40));
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join(format!("suite{:04}.rivet.yaml", i)),
+            format!(
+                r#"name: Suite {i}
+vars:
+  baseUrl: https://api.example.com
+tests:
+  - name: Get item {i}
+    request:
+      method: GET
+      url: "{{{{baseUrl}}}}/items/{i}"
+    expect:
+      status: 200
+"#
+            ),
+        )?;
+    }
+
+    let start = Instant::now();
+    let suites = load_test_suite(temp_dir.path(), false).await?;
+    let duration = start.elapsed();
+
+    assert_eq!(suites.len(), 2000);
+    // Sorted by file name, whichever parse finished first
+    for (i, suite) in suites.iter().enumerate() {
+        assert_eq!(suite.config.name, format!("Suite {}", i));
+        assert_eq!(
+            suite.name,
+            format!("area{:02}/suite{:04}.rivet.yaml", i % 40, i)
+        );
+    }
+    assert!(
+        duration.as_secs() < 30,
+        "Loading 2000 suites took too long: {:?}",
+        duration
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_malformed_collections_error_handling() -> Result<()> {
     let temp_dir = TempDir::new()?;
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::{self, Certificate, PrivateKey};
+use tokio_rustls::TlsAcceptor;
+
+/// Serve `200 ok` over HTTPS for `localhost`, using a certificate signed by
+/// a fresh private CA that no system trust store knows about.
+///
+/// Returns the base URL and the CA certificate in PEM form. Connections whose
+/// handshake fails (the client rejected the certificate) are skipped.
+async fn serve_with_private_ca() -> Result<(String, String)> {
+    let mut ca_params = rcgen::CertificateParams::new(vec![]);
+    ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    ca_params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, "Rivet Test CA");
+    let ca = rcgen::Certificate::from_params(ca_params)?;
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+    let config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![Certificate(cert.serialize_der_with_signer(&ca)?)],
+            PrivateKey(cert.serialize_private_key_der()),
+        )?;
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("https://localhost:{}", listener.local_addr()?.port());
+
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            let Ok(mut stream) = acceptor.accept(socket).await else {
+                continue;
+            };
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .await;
+            let _ = stream.shutdown().await;
+        }
+    });
+
+    Ok((url, ca.serialize_pem()?))
+}
+
+fn write_suite(dir: &Path, url: &str, extra: &str) -> Result<String> {
+    let path = dir.join("tls.rivet.yaml");
+    fs::write(
+        &path,
+        format!(
+            r#"name: TLS
+{}
+tests:
+  - name: Ping
+    request:
+      method: GET
+      url: {}/ping
+    expect:
+      status: 200
+"#,
+            extra, url
+        ),
+    )?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+async fn run_rivet(args: &[&str]) -> Result<std::process::Output> {
+    Ok(tokio::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .args(args)
+        .output()
+        .await?)
+}
+
+#[tokio::test]
+async fn test_run_rejects_self_signed_certificate_by_default() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _) = serve_with_private_ca().await?;
+    let suite = write_suite(temp_dir.path(), &url, "")?;
+
+    let output = run_rivet(&["run", &suite, "--ci"]).await?;
+
+    assert!(!output.status.success());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_run_insecure_accepts_self_signed_certificate() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _) = serve_with_private_ca().await?;
+    let suite = write_suite(temp_dir.path(), &url, "")?;
+
+    let output = run_rivet(&["run", &suite, "--ci", "--insecure"]).await?;
+
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("TLS certificate verification is disabled (--insecure)"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_suite_level_insecure() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, _) = serve_with_private_ca().await?;
+    let suite = write_suite(temp_dir.path(), &url, "insecure: true")?;
+
+    let output = run_rivet(&["run", &suite, "--ci"]).await?;
+
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("insecure: true in suite 'TLS'"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_run_trusts_cacert() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (url, ca_pem) = serve_with_private_ca().await?;
+    let suite = write_suite(temp_dir.path(), &url, "")?;
+    let ca = temp_dir.path().join("ca.pem");
+    fs::write(&ca, ca_pem)?;
+
+    let output = run_rivet(&["run", &suite, "--ci", "--cacert", ca.to_str().unwrap()]).await?;
+
+    assert!(output.status.success(), "{:?}", output);
+    // Verification stays on, so no warning
+    assert!(!String::from_utf8(output.stderr)?.contains("verification is disabled"));
+    Ok(())
+}
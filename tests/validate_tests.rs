@@ -0,0 +1,199 @@
+use std::process::Output;
+
+const FIXTURES: &str = "tests/fixtures/validate";
+
+fn validate(args: &[&str]) -> Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .arg("validate")
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run rivet")
+}
+
+/// Validate one fixture, returning whether it passed and everything printed.
+fn validate_fixture(name: &str) -> (bool, String) {
+    let output = validate(&[&format!("{}/{}.rivet.yaml", FIXTURES, name)]);
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (output.status.success(), text)
+}
+
+fn assert_reports(text: &str, expected: &[&str]) {
+    for line in expected {
+        assert!(text.contains(line), "missing `{}` in:\n{}", line, text);
+    }
+}
+
+#[test]
+fn test_validate_accepts_a_valid_suite() {
+    let (ok, text) = validate_fixture("valid");
+    assert!(ok, "{}", text);
+    assert!(text.contains("1 suite file(s) valid"), "{}", text);
+    assert!(!text.contains("warning"), "{}", text);
+}
+
+#[test]
+fn test_validate_reports_errors_with_locations() {
+    let cases: &[(&str, &[&str])] = &[
+        (
+            "invalid-yaml",
+            &["invalid-yaml.rivet.yaml:7: error: Invalid YAML"],
+        ),
+        (
+            "unknown-fields",
+            &[
+                "unknown-fields.rivet.yaml:5: error: Unknown field `methd` in tests[0].request; did you mean `method`?",
+                "unknown-fields.rivet.yaml:7: error: Unknown field `expct` in tests[0]; did you mean `expect`?",
+            ],
+        ),
+        (
+            "bad-method",
+            &["bad-method.rivet.yaml:3: error: Test `Spaced`: invalid HTTP method `GET ME`"],
+        ),
+        (
+            "bad-url",
+            &[
+                "bad-url.rivet.yaml:5: error: Test `Space in host`: URL `https://{{host}}/users` is invalid",
+                "bad-url.rivet.yaml:9: error: Test `Bad port`: URL `https://api.example.com:99999/users` is invalid",
+            ],
+        ),
+        (
+            "bad-status",
+            &[
+                "bad-status.rivet.yaml:3: error: Test `Out of range`: expected status 999",
+                "bad-status.rivet.yaml:9: error: Test `Pattern`: expected status `2xx` is not a number",
+            ],
+        ),
+        (
+            "bad-jsonpath",
+            &[
+                "error: Test `Recursive descent`: invalid JSONPath `$..id`",
+                "error: Test `Recursive descent`: invalid JSONPath `$.items[first]`",
+                "error: Test `Array checks`: invalid length `> 1` for `$.items`",
+                "error: Test `Array checks`: invalid JSONPath `$.items[].status`",
+            ],
+        ),
+        (
+            "missing-dataset",
+            &["missing-dataset.rivet.yaml:2: error: Dataset file does not exist: nowhere.csv (resolved to "],
+        ),
+        (
+            "dataset-columns",
+            &[
+                "dataset-columns.rivet.yaml:2: error: Invalid dataset selection",
+                "dataset-columns.rivet.yaml:6: warning: Test `Get user`: variable `team` is not defined",
+            ],
+        ),
+        (
+            "bad-auth",
+            &[
+                "bad-auth.rivet.yaml:7: error: Unknown field `realm` in auth.basic",
+                "bad-auth.rivet.yaml:2: error: auth must set exactly one of bearer, basic and oauth2_client_credentials",
+            ],
+        ),
+        (
+            "bad-poll",
+            &[
+                "bad-poll.rivet.yaml:5: error: Test `Start job`: invalid poll interval `soon`",
+                "bad-poll.rivet.yaml:5: error: Test `Start job`: a poll's `until` cannot poll again",
+                "bad-poll.rivet.yaml:5: error: Test `Start job`: invalid JSONPath `$..state`",
+                "bad-poll.rivet.yaml:31: error: Unknown field `intervall` in tests[1].expect.poll; did you mean `interval`?",
+            ],
+        ),
+        (
+            "bad-capture",
+            &[
+                "bad-capture.rivet.yaml:3: error: Test `Create item`: invalid pattern `/items/(\\d+` to capture `item_id`: unclosed group",
+                "bad-capture.rivet.yaml:3: error: Test `Create item`: invalid JSONPath `$..owner` to capture `owner`",
+            ],
+        ),
+        (
+            "strict-variables",
+            &["strict-variables.rivet.yaml:6: error: Test `Undefined`: variable `userId` is not defined"],
+        ),
+    ];
+
+    for (fixture, expected) in cases {
+        let (ok, text) = validate_fixture(fixture);
+        assert!(!ok, "{} should fail:\n{}", fixture, text);
+        assert_reports(&text, expected);
+    } // Captured variables are defined for the poll and the steps after
+    let (_, text) = validate_fixture("bad-poll");
+    assert!(!text.contains("`job_id`"), "{}", text);
+}
+
+#[test]
+fn test_validate_warnings_do_not_fail() {
+    let (ok, text) = validate_fixture("duplicate-names");
+    assert!(ok, "{}", text);
+    assert_reports(
+        &text,
+        &["duplicate-names.rivet.yaml:8: warning: Duplicate test name `Get user` (first used on line 3)"],
+    );
+
+    // Included setup steps run first, but lines are those of the suite itself
+    let (ok, text) = validate_fixture("includes");
+    assert!(ok, "{}", text);
+    assert_reports(
+        &text,
+        &[
+            "includes.rivet.yaml:10: warning: Duplicate test name `Seed` (first used on line 4)",
+            "1 warning(s)",
+        ],
+    );
+
+    let (ok, text) = validate_fixture("unknown-variables");
+    assert!(ok, "{}", text);
+    assert_reports(
+        &text,
+        &[
+            "warning: Test `Undefined`: variable `baseUrl` is not defined",
+            "pass --base-url",
+            "warning: Test `Undefined`: variable `userId` is not defined",
+            // Built-in, but only inside dataset rows
+            "warning: Test `Undefined`: variable `row_index` is not defined",
+            // A relative URL is joined to baseUrl
+            "warning: Test `Relative`: variable `baseUrl` is not defined",
+            "4 warning(s)",
+        ],
+    );
+    // Only `{{userId}}` and `{{row_index}}` are left once the environment defines baseUrl
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_rivet"))
+        .args([
+            "validate",
+            &format!("{}/unknown-variables.rivet.yaml", FIXTURES),
+        ])
+        .env("NO_COLOR", "1")
+        .env("baseUrl", "https://api.example.com")
+        .output()
+        .unwrap();
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.contains("2 warning(s)"), "{}", text);
+}
+
+#[test]
+fn test_validate_warns_about_unknown_columns_in_names() {
+    let (ok, text) = validate_fixture("name-templates");
+    assert!(ok, "{}", text);
+    assert_reports(
+        &text,
+        &[
+            "name-templates.rivet.yaml:5: warning: Test `Get {{email}} in {{region}}`: name references `region`, which is not a dataset column or variable",
+            "1 warning(s)",
+        ],
+    );
+    // Step vars are defined for the step's own request
+    assert!(!text.contains("`path`"), "{}", text);
+}
+
+#[test]
+fn test_validate_directory_checks_every_suite() {
+    let output = validate(&[FIXTURES]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("in 17 suite file(s)"), "{}", stderr);
+}